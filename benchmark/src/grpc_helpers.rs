@@ -195,7 +195,10 @@ fn get_account_state_async(
     client: &AdmissionControlClient,
     address: AccountAddress,
 ) -> Result<impl Future<Item = (AccountAddress, ResponseItem), Error = failure::Error>> {
-    let requested_item = RequestItem::GetAccountState { address };
+    let requested_item = RequestItem::GetAccountState {
+        address,
+        version: None,
+    };
     let requested_items = vec![requested_item];
     let req = UpdateToLatestLedgerRequest::new(0, requested_items);
     let proto_req = req.into();
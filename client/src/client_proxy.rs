@@ -602,6 +602,28 @@ impl ClientProxy {
         self.get_account_state_and_update(account)
     }
 
+    /// Get the account state as of a past version from validator.
+    pub fn get_account_state_by_version(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<(Option<AccountStateBlob>, Version)> {
+        ensure!(
+            space_delim_strings.len() == 3,
+            "Invalid number of arguments to get account state by version"
+        );
+        let account = self.get_account_address_from_parameter(space_delim_strings[1])?;
+        let version = space_delim_strings[2].parse::<u64>().map_err(|error| {
+            format_parse_data_error(
+                "version",
+                InputType::UnsignedInt,
+                space_delim_strings[2],
+                error,
+            )
+        })?;
+        self.client
+            .get_account_blob_by_version(account, Some(version))
+    }
+
     /// Get committed txn by account and sequence number.
     pub fn get_committed_txn_by_acc_seq(
         &mut self,
@@ -194,7 +194,17 @@ impl GRPCClient {
         &self,
         address: AccountAddress,
     ) -> Result<(Option<AccountStateBlob>, Version)> {
-        let req_item = RequestItem::GetAccountState { address };
+        self.get_account_blob_by_version(address, None)
+    }
+
+    /// Get the account state blob as of `version` from validator, or the latest one if `version`
+    /// is `None`. Fails if the validator has pruned the state at `version`.
+    pub(crate) fn get_account_blob_by_version(
+        &self,
+        address: AccountAddress,
+        version: Option<Version>,
+    ) -> Result<(Option<AccountStateBlob>, Version)> {
+        let req_item = RequestItem::GetAccountState { address, version };
 
         let mut response = self.get_with_proof_sync(vec![req_item])?;
         let account_state_with_proof = response
@@ -204,7 +214,7 @@ impl GRPCClient {
 
         Ok((
             account_state_with_proof.blob,
-            response.ledger_info_with_sigs.ledger_info().version(),
+            account_state_with_proof.version,
         ))
     }
 
@@ -77,7 +77,8 @@ impl Command for QueryCommandGetSeqNum {
     }
 }
 
-/// Command to query latest account state from validator.
+/// Command to query latest account state, or the account state as of a past version, from
+/// validator.
 pub struct QueryCommandGetLatestAccountState {}
 
 impl Command for QueryCommandGetLatestAccountState {
@@ -85,20 +86,28 @@ impl Command for QueryCommandGetLatestAccountState {
         vec!["account_state", "as"]
     }
     fn get_params_help(&self) -> &'static str {
-        "<account_ref_id>|<account_address>"
+        "<account_ref_id>|<account_address> [version]"
     }
     fn get_description(&self) -> &'static str {
-        "Get the latest state for an account"
+        "Get the latest state for an account, or its state as of a past version if provided"
     }
     fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
-        println!(">> Getting latest account state");
-        match client.get_latest_account_state(&params) {
+        let is_latest = params.len() < 3;
+        let result = if is_latest {
+            println!(">> Getting latest account state");
+            client.get_latest_account_state(&params)
+        } else {
+            println!(">> Getting account state by version");
+            client.get_account_state_by_version(&params)
+        };
+        match result {
             Ok((acc, version)) => match get_account_resource_or_default(&acc) {
                 Ok(_) => println!(
-                    "Latest account state is: \n \
+                    "{} account state is: \n \
                      Account: {:#?}\n \
                      State: {:#?}\n \
                      Blockchain Version: {}\n",
+                    if is_latest { "Latest" } else { "Historical" },
                     client
                         .get_account_address_from_parameter(params[1])
                         .expect("Unable to parse account parameter"),
@@ -107,7 +116,7 @@ impl Command for QueryCommandGetLatestAccountState {
                 ),
                 Err(e) => report_error("Error converting account blob to account resource", e),
             },
-            Err(e) => report_error("Error getting latest account state", e),
+            Err(e) => report_error("Error getting account state", e),
         }
     }
 }
@@ -3,7 +3,10 @@
 
 use failure::{prelude::*, Result};
 use futures::{compat::Future01CompatExt, future::Future, prelude::*};
-use futures_01::future::Future as Future01;
+use futures_01::{
+    future::{poll_fn, Future as Future01},
+    sink::Sink as Sink01,
+};
 use grpcio::{ChannelBuilder, EnvBuilder, ServerBuilder};
 use libra_logger::prelude::*;
 use libra_metrics::counters::SVC_COUNTERS;
@@ -53,6 +56,48 @@ pub fn provide_grpc_response<ResponseType: std::fmt::Debug>(
     SVC_COUNTERS.resp(&ctx, success);
 }
 
+/// Same as [`provide_grpc_response`], but for server streaming RPCs. On success, the items of
+/// `resp` are sent from a dedicated thread, so that iterators blocking until the next item is
+/// available don't hold up the GRPC threads. Sending stops once the iterator is exhausted or the
+/// client goes away.
+pub fn provide_grpc_streaming_response<ResponseType, I>(
+    resp: Result<I>,
+    ctx: ::grpcio::RpcContext<'_>,
+    sink: ::grpcio::ServerStreamingSink<ResponseType>,
+) where
+    ResponseType: std::fmt::Debug + Send + 'static,
+    I: Iterator<Item = ResponseType> + Send + 'static,
+{
+    let mut success = true;
+    match resp {
+        Ok(items) => {
+            thread::spawn(move || {
+                let mut sink = sink;
+                for item in items {
+                    sink = match sink.send((item, ::grpcio::WriteFlags::default())).wait() {
+                        Ok(sink) => sink,
+                        Err(e) => return default_reply_error_logger(e),
+                    };
+                }
+                if let Err(e) = poll_fn(|| sink.close()).wait() {
+                    default_reply_error_logger(e)
+                }
+            });
+        }
+        Err(e) => {
+            success = false;
+            let f = sink
+                .fail(create_grpc_invalid_arg_status(
+                    from_utf8(ctx.method()).expect("Unable to convert function name to string"),
+                    e,
+                ))
+                .map_err(default_reply_error_logger);
+            ctx.spawn(f)
+        }
+    }
+    SVC_COUNTERS.resp(&ctx, success);
+}
+
 pub fn spawn_service_thread(
     service: ::grpcio::Service,
     service_host_address: String,
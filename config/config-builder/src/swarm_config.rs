@@ -94,6 +94,9 @@ impl SwarmConfig {
             network_keypairs: NetworkKeyPairs::default(),
             network_peers: template_network.network_peers.clone(),
             seed_peers: template_network.seed_peers.clone(),
+            rate_limit: template_network.rate_limit.clone(),
+            compression: template_network.compression.clone(),
+            reputation: template_network.reputation.clone(),
         };
         let (mut private_keys, mut network_peers_config) =
             ConfigHelpers::gen_full_nodes(num_nodes, key_seed);
@@ -302,6 +305,7 @@ impl SwarmConfig {
             safety_rules_config.backend = SafetyRulesBackend::OnDiskStorage {
                 default: true,
                 path: PathBuf::from(format!("{}.node.safety_rules.toml", node_id.to_string())),
+                consensus_keys: None,
             }
         }
         // Save network keys.
@@ -335,19 +339,27 @@ impl SwarmConfig {
             network_keypairs: NetworkKeyPairs::default(),
             network_peers: template_network.network_peers.clone(),
             seed_peers: template_network.seed_peers.clone(),
+            rate_limit: template_network.rate_limit.clone(),
+            compression: template_network.compression.clone(),
+            reputation: template_network.reputation.clone(),
         };
         let consensus_config = ConsensusConfig {
             max_block_size: template.consensus.max_block_size,
             proposer_type: template.consensus.proposer_type,
             contiguous_rounds: template.consensus.contiguous_rounds,
+            vrf_expected_proposers: template.consensus.vrf_expected_proposers,
+            leader_reputation_window_size: template.consensus.leader_reputation_window_size,
+            leader_reputation_exclude_rounds: template.consensus.leader_reputation_exclude_rounds,
             max_pruned_blocks_in_mem: template.consensus.max_pruned_blocks_in_mem,
             pacemaker_initial_timeout_ms: template.consensus.pacemaker_initial_timeout_ms,
+            adaptive_pacemaker: template.consensus.adaptive_pacemaker.clone(),
             consensus_keypair_file: consensus_keys_file_name.into(),
             consensus_peers_file: consensus_peers_file_name.into(),
             // Dummy values - will be loaded from corresponding files.
             consensus_keypair: ConsensusKeyPair::default(),
             consensus_peers: template.consensus.consensus_peers.clone(),
             safety_rules: safety_rules_config,
+            observer: template.consensus.observer,
         };
         let mut config = NodeConfig {
             base: base_config,
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::{PersistableConfig, SafetyRulesBackend, SafetyRulesConfig},
+    config::{ConsensusKeysSecret, PersistableConfig, SafetyRulesBackend, SafetyRulesConfig},
     keys::ConsensusKeyPair,
     trusted_peers::ConsensusPeersConfig,
 };
@@ -17,8 +17,21 @@ pub struct ConsensusConfig {
     pub max_block_size: u64,
     pub proposer_type: ConsensusProposerType,
     pub contiguous_rounds: u32,
+    // Number of proposers the VrfProposer elects per round on average
+    pub vrf_expected_proposers: u64,
+    // Number of committed blocks the LeaderReputation weights the validators by
+    pub leader_reputation_window_size: usize,
+    // Number of rounds between a round and the last committed block its LeaderReputation window
+    // can include
+    pub leader_reputation_exclude_rounds: u64,
     pub max_pruned_blocks_in_mem: Option<u64>,
     pub pacemaker_initial_timeout_ms: Option<u64>,
+    // If set, the round timeouts are derived from the latency of the recent rounds instead of
+    // starting from pacemaker_initial_timeout_ms.
+    pub adaptive_pacemaker: Option<AdaptivePacemakerConfig>,
+    // Scheme the votes are signed with. All validators need a BLS12-381 key in the validator set
+    // for their signatures to be aggregated.
+    pub signature_scheme: ConsensusSignatureScheme,
     // consensus_keypair contains the node's consensus keypair.
     // it is filled later on from consensus_keypair_file.
     #[serde(skip)]
@@ -28,6 +41,10 @@ pub struct ConsensusConfig {
     pub consensus_peers: ConsensusPeersConfig,
     pub consensus_peers_file: PathBuf,
     pub safety_rules: SafetyRulesConfig,
+    // Validators publish the proposals they accept on their full node network, and full nodes
+    // follow the committed blocks as observers of their upstream network instead of polling state
+    // sync.
+    pub observer: bool,
 }
 
 impl Default for ConsensusConfig {
@@ -36,13 +53,44 @@ impl Default for ConsensusConfig {
             max_block_size: 100,
             proposer_type: ConsensusProposerType::MultipleOrderedProposers,
             contiguous_rounds: 2,
+            vrf_expected_proposers: 2,
+            leader_reputation_window_size: 10,
+            leader_reputation_exclude_rounds: 10,
             max_pruned_blocks_in_mem: None,
             pacemaker_initial_timeout_ms: None,
+            adaptive_pacemaker: None,
+            signature_scheme: ConsensusSignatureScheme::Ed25519,
             consensus_keypair: ConsensusKeyPair::default(),
             consensus_keypair_file: PathBuf::from("consensus_keypair.config.toml"),
             consensus_peers: ConsensusPeersConfig::default(),
             consensus_peers_file: PathBuf::from("consensus_peers.config.toml"),
             safety_rules: SafetyRulesConfig::default(),
+            observer: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct AdaptivePacemakerConfig {
+    // Number of recent rounds whose latency is tracked
+    pub window_size: usize,
+    // Percentile of the tracked latencies the timeout is derived from, between 0 and 1
+    pub percentile: f64,
+    // Ratio between the timeout of a round and the percentile of the latencies
+    pub multiplier: f64,
+    pub min_timeout_ms: u64,
+    pub max_timeout_ms: u64,
+}
+
+impl Default for AdaptivePacemakerConfig {
+    fn default() -> AdaptivePacemakerConfig {
+        AdaptivePacemakerConfig {
+            window_size: 100,
+            percentile: 0.9,
+            multiplier: 3.0,
+            min_timeout_ms: 200,
+            max_timeout_ms: 10_000,
         }
     }
 }
@@ -56,6 +104,19 @@ pub enum ConsensusProposerType {
     RotatingProposer,
     // Multiple ordered proposers per round (primary, secondary, etc.)
     MultipleOrderedProposers,
+    // Proposers elected privately by a VRF, revealed by the proofs attached to their proposals
+    VrfProposer,
+    // Single proposer per round, rarely electing validators absent from the recent commits
+    LeaderReputation,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsensusSignatureScheme {
+    // Votes carry Ed25519 signatures, which the certificates carry one by one
+    Ed25519,
+    // Votes also carry BLS12-381 signatures, which the certificates carry aggregated into one.
+    Bls12381,
 }
 
 impl ConsensusConfig {
@@ -71,19 +132,28 @@ impl ConsensusConfig {
             );
         }
         if let SafetyRulesBackend::OnDiskStorage {
-            default,
             path: sr_path,
-        } = &self.safety_rules.backend
+            consensus_keys,
+            ..
+        } = &mut self.safety_rules.backend
         {
-            // If the file is relative, it means it is in the same directory as this config,
-            // unfortunately this is meaningless to the process that would load the config.
-            if !sr_path.as_os_str().is_empty() && sr_path.is_relative() {
-                self.safety_rules.backend = SafetyRulesBackend::OnDiskStorage {
-                    default: *default,
-                    path: path.as_ref().with_file_name(sr_path),
-                };
+            resolve_relative_path(path.as_ref(), sr_path);
+            if let Some(consensus_keys) = consensus_keys {
+                resolve_relative_path(path.as_ref(), &mut consensus_keys.path);
+                if let ConsensusKeysSecret::KeyFile { path: key_path } = &mut consensus_keys.secret
+                {
+                    resolve_relative_path(path.as_ref(), key_path);
+                }
             }
         }
         Ok(())
     }
 }
+
+// If the file is relative, it means it is in the same directory as this config,
+// unfortunately this is meaningless to the process that would load the config.
+fn resolve_relative_path(config_path: &Path, file_path: &mut PathBuf) {
+    if !file_path.as_os_str().is_empty() && file_path.is_relative() {
+        *file_path = config_path.with_file_name(file_path.as_path());
+    }
+}
@@ -11,6 +11,7 @@ use libra_types::PeerId;
 use parity_multiaddr::Multiaddr;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     convert::TryFrom,
     fmt,
     path::{Path, PathBuf},
@@ -52,7 +53,8 @@ impl fmt::Display for RoleType {
 pub struct NetworkConfig {
     pub peer_id: String,
     // TODO: Add support for multiple listen/advertised addresses in config.
-    // The address that this node is listening on for new connections.
+    // The address that this node is listening on for new connections. Permissioned networks
+    // use QUIC instead of TCP when it is a QUIC address, e.g. /ip4/0.0.0.0/udp/6180/quic.
     pub listen_address: Multiaddr,
     // The address that this node advertises to other nodes for the discovery protocol.
     pub advertised_address: Multiaddr,
@@ -80,6 +82,12 @@ pub struct NetworkConfig {
     #[serde(skip)]
     pub seed_peers: SeedPeersConfig,
     pub seed_peers_file: PathBuf,
+    // Limits of the inbound DirectSend messages and rpcs of each peer.
+    pub rate_limit: RateLimitConfig,
+    // Compression of the DirectSend messages and rpcs sent to the peers supporting it.
+    pub compression: CompressionConfig,
+    // Reputation scores of the peers, and bans of the misbehaving ones.
+    pub reputation: ReputationConfig,
 }
 
 impl Default for NetworkConfig {
@@ -99,6 +107,9 @@ impl Default for NetworkConfig {
             network_peers: NetworkPeersConfig::default(),
             seed_peers_file: PathBuf::from("seed_peers.config.toml"),
             seed_peers: SeedPeersConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            compression: CompressionConfig::default(),
+            reputation: ReputationConfig::default(),
         }
     }
 }
@@ -140,3 +151,77 @@ impl NetworkConfig {
         Ok(())
     }
 }
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    // Limits of each peer by protocol id, e.g. "/libra/consensus/rpc/0.1.0". The protocols
+    // missing from the map aren't limited.
+    pub protocols: BTreeMap<String, ProtocolRateLimit>,
+    // Messages over the limits of a peer are delayed up to this long, dropped beyond.
+    pub max_delay_ms: u64,
+    // Peers whose messages keep being dropped for this long get disconnected.
+    pub disconnect_after_ms: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> RateLimitConfig {
+        RateLimitConfig {
+            protocols: BTreeMap::new(),
+            max_delay_ms: 1000,
+            disconnect_after_ms: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ProtocolRateLimit {
+    // 0 means no limit.
+    pub messages_per_sec: u64,
+    // 0 means no limit. Messages larger than this may be dropped, so it should exceed the
+    // largest message of the protocol.
+    pub bytes_per_sec: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    // Protocol ids whose messages are compressed, e.g. "/libra/consensus/direct-send/0.1.0".
+    // Peers only compress a protocol if both sides list it.
+    pub protocols: BTreeSet<String>,
+    // Messages smaller than this are sent uncompressed.
+    pub min_size_bytes: usize,
+    // The zstd compression level, from 1 (fastest) to 21 (smallest).
+    pub level: i32,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> CompressionConfig {
+        CompressionConfig {
+            protocols: BTreeSet::new(),
+            min_size_bytes: 1024,
+            level: 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ReputationConfig {
+    // Peers whose score drops to this or below are banned. Scores start at 0.
+    pub ban_threshold: i64,
+    // Banned peers can't connect, nor be dialed, for this long.
+    pub ban_duration_ms: u64,
+    // Scores are halved towards 0 every this long, so that past behavior is forgiven.
+    pub decay_half_life_ms: u64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> ReputationConfig {
+        ReputationConfig {
+            ban_threshold: -100,
+            ban_duration_ms: 10 * 60 * 1000,
+            decay_half_life_ms: 5 * 60 * 1000,
+        }
+    }
+}
@@ -8,12 +8,14 @@ use std::path::PathBuf;
 #[serde(default)]
 pub struct SafetyRulesConfig {
     pub backend: SafetyRulesBackend,
+    pub service: SafetyRulesService,
 }
 
 impl Default for SafetyRulesConfig {
     fn default() -> Self {
         Self {
             backend: SafetyRulesBackend::InMemoryStorage,
+            service: SafetyRulesService::Local,
         }
     }
 }
@@ -28,5 +30,51 @@ pub enum SafetyRulesBackend {
         default: bool,
         // Required path for on disk storage
         path: PathBuf,
+        // If set, the consensus private keys are kept there, encrypted, instead of being read
+        // by consensus from the consensus keypair file.
+        #[serde(default)]
+        consensus_keys: Option<ConsensusKeysConfig>,
     },
 }
+
+impl SafetyRulesBackend {
+    /// Whether the consensus private keys are kept in the backend, in which case they don't come
+    /// from the consensus keypair file.
+    pub fn holds_consensus_keys(&self) -> bool {
+        match self {
+            SafetyRulesBackend::OnDiskStorage { consensus_keys, .. } => consensus_keys.is_some(),
+            SafetyRulesBackend::InMemoryStorage => false,
+        }
+    }
+}
+
+/// Where SafetyRules keeps the consensus private keys and how they are encrypted at rest.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusKeysConfig {
+    /// File holding the consensus private keys, encrypted under `secret`.
+    pub path: PathBuf,
+    pub secret: ConsensusKeysSecret,
+}
+
+/// The operator-supplied secret the consensus private keys are encrypted under.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum ConsensusKeysSecret {
+    /// A passphrase read from the environment variable `env_var`.
+    Passphrase { env_var: String },
+    /// The contents of the file at `path`.
+    KeyFile { path: PathBuf },
+}
+
+/// Where SafetyRules, and with it the consensus private key, lives.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum SafetyRulesService {
+    /// In the consensus thread itself.
+    Local,
+    /// In a dedicated thread of the consensus process, reached over a local socket.
+    Thread,
+    /// In a separate process started with the `safety-rules` binary, listening on a Unix socket
+    /// at `socket_path`. Its backend is taken from the config of that process.
+    Process { socket_path: PathBuf },
+}
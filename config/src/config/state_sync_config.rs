@@ -17,6 +17,9 @@ pub struct StateSyncConfig {
     pub max_chunk_limit: u64,
     // valid maximum timeout limit for sanity check
     pub max_timeout_ms: u64,
+    // Full nodes following consensus as observers only poll their upstream peers once they haven't
+    // committed any block for this long
+    pub observer_timeout_ms: u64,
     // List of peers to use as upstream in state sync protocols.
     #[serde(flatten)]
     pub upstream_peers: UpstreamPeersConfig,
@@ -30,6 +33,7 @@ impl Default for StateSyncConfig {
             long_poll_timeout_ms: 30000,
             max_chunk_limit: 1000,
             max_timeout_ms: 120_000,
+            observer_timeout_ms: 10_000,
             upstream_peers: UpstreamPeersConfig::default(),
         }
     }
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default)]
@@ -11,6 +11,11 @@ pub struct StorageConfig {
     pub port: u16,
     pub dir: PathBuf,
     pub grpc_max_receive_len: Option<i32>,
+    pub engine: StorageEngine,
+    pub pruning: PruningConfig,
+    pub rocksdb: RocksdbConfig,
+    pub secondary_indexes: SecondaryIndexConfig,
+    pub replication: ReplicationConfig,
 }
 
 impl Default for StorageConfig {
@@ -20,6 +25,187 @@ impl Default for StorageConfig {
             port: 6184,
             dir: PathBuf::from("libradb/db"),
             grpc_max_receive_len: Some(100_000_000),
+            engine: StorageEngine::Rocksdb,
+            pruning: PruningConfig::default(),
+            rocksdb: RocksdbConfig::default(),
+            secondary_indexes: SecondaryIndexConfig::default(),
+            replication: ReplicationConfig::default(),
         }
     }
 }
+
+/// The key-value engine backing LibraDB and ConsensusDB.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageEngine {
+    /// Persists data on disk under `dir`, tuned by the `rocksdb` options.
+    Rocksdb,
+    /// Keeps all data in memory, so that nothing survives a restart. Meant for tests and
+    /// experiments.
+    InMemory,
+}
+
+/// Controls how much history LibraDB keeps. Each window is the number of versions, other than the
+/// latest one, that remain readable; `None` keeps that kind of data forever.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct PruningConfig {
+    /// Turns off all pruning regardless of the windows below, e.g. for archive nodes.
+    pub enabled: bool,
+    /// Historical versions of the account state tree to keep.
+    pub state_window: Option<u64>,
+    /// Historical transactions to keep, together with their infos, the account index and the
+    /// transaction accumulator.
+    pub ledger_window: Option<u64>,
+    /// Historical versions whose events are kept, together with the event indices and event
+    /// accumulators. Events outside of the ledger window are pruned regardless of this.
+    pub event_window: Option<u64>,
+}
+
+impl Default for PruningConfig {
+    fn default() -> PruningConfig {
+        PruningConfig {
+            enabled: true,
+            state_window: Some(1_000_000),
+            ledger_window: None,
+            event_window: None,
+        }
+    }
+}
+
+/// Opt-in indices of user transactions, maintained as transactions are committed and pruned
+/// together with them. Turning one on for an existing DB only indexes transactions committed from
+/// then on.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct SecondaryIndexConfig {
+    /// Index of transactions by the hash of the script they run.
+    pub transaction_by_script_hash: bool,
+    /// Index of transactions by the addresses passed to their scripts, e.g. payees.
+    pub transaction_by_receiver: bool,
+}
+
+/// Write-ahead replication of the storage service to hot-standby followers.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    /// Address of the storage service to follow. If set, the service starts as a follower: it
+    /// replicates every batch of transactions saved on the primary and refuses writes of its own
+    /// until promoted. Unset it after promoting a follower, or it follows its old primary again
+    /// once restarted.
+    pub primary_address: Option<String>,
+    pub primary_port: u16,
+    /// Number of the most recent batches a primary buffers, so that followers reconnecting from a
+    /// batch still in the buffer can catch up.
+    pub buffer_size: usize,
+    /// Time a follower waits before reconnecting to its primary after losing the connection.
+    pub retry_interval_ms: u64,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> ReplicationConfig {
+        ReplicationConfig {
+            primary_address: None,
+            primary_port: 6184,
+            buffer_size: 1000,
+            retry_interval_ms: 1000,
+        }
+    }
+}
+
+/// RocksDB tuning for LibraDB. The options in `column_family` apply to every column family unless
+/// overridden for a specific one in `column_family_overrides`, which is keyed by column family
+/// name, e.g. `jellyfish_merkle_node` or `event_by_key`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RocksdbConfig {
+    /// Upper bound of the total size of write-ahead logs, in bytes, before column families backed
+    /// by the oldest log get flushed.
+    pub max_total_wal_size: u64,
+    /// Collects RocksDB statistics and exports them through the metrics server. This costs a few
+    /// percent of throughput.
+    pub enable_statistics: bool,
+    pub column_family: ColumnFamilyConfig,
+    pub column_family_overrides: BTreeMap<String, ColumnFamilyConfig>,
+}
+
+impl Default for RocksdbConfig {
+    fn default() -> RocksdbConfig {
+        RocksdbConfig {
+            max_total_wal_size: 1 << 30,
+            enable_statistics: false,
+            column_family: ColumnFamilyConfig::default(),
+            column_family_overrides: BTreeMap::new(),
+        }
+    }
+}
+
+impl RocksdbConfig {
+    /// Returns the options of column family `cf_name`, i.e. its overrides on top of the global
+    /// options.
+    pub fn column_family_config(&self, cf_name: &str) -> ColumnFamilyConfig {
+        match self.column_family_overrides.get(cf_name) {
+            Some(overrides) => overrides.or(&self.column_family),
+            None => self.column_family.clone(),
+        }
+    }
+}
+
+/// Options of a column family. Options left unset in an override fall back to the global ones;
+/// options left unset globally fall back to RocksDB defaults.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ColumnFamilyConfig {
+    /// Size of the LRU block cache in bytes. The global value sizes one cache shared by all
+    /// column families without an override, while an override gives the column family a
+    /// dedicated cache of its own.
+    pub block_cache_size: Option<u64>,
+    /// Size of uncompressed data packed per block, in bytes.
+    pub block_size: Option<u64>,
+    /// Bits per key of the bloom filter, or 0 for no bloom filter.
+    pub bloom_filter_bits_per_key: Option<u32>,
+    pub compression: Option<RocksdbCompression>,
+    /// Size of a single memtable in bytes.
+    pub write_buffer_size: Option<u64>,
+    pub max_write_buffer_number: Option<u32>,
+    pub compaction_style: Option<RocksdbCompactionStyle>,
+}
+
+impl ColumnFamilyConfig {
+    /// Fills the options unset in `self` with the ones in `other`.
+    pub fn or(&self, other: &ColumnFamilyConfig) -> ColumnFamilyConfig {
+        ColumnFamilyConfig {
+            block_cache_size: self.block_cache_size.or(other.block_cache_size),
+            block_size: self.block_size.or(other.block_size),
+            bloom_filter_bits_per_key: self
+                .bloom_filter_bits_per_key
+                .or(other.bloom_filter_bits_per_key),
+            compression: self.compression.or(other.compression),
+            write_buffer_size: self.write_buffer_size.or(other.write_buffer_size),
+            max_write_buffer_number: self
+                .max_write_buffer_number
+                .or(other.max_write_buffer_number),
+            compaction_style: self.compaction_style.or(other.compaction_style),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RocksdbCompression {
+    None,
+    Snappy,
+    Zlib,
+    Bz2,
+    Lz4,
+    Lz4hc,
+    Zstd,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RocksdbCompactionStyle {
+    Level,
+    Universal,
+    Fifo,
+}
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use libra_crypto::{
+    bls12381::{BLS12381PublicKey, BLS12381Signature},
     ed25519::{compat, *},
-    traits::{ValidKey, ValidKeyStringExt},
+    traits::{Signature, ValidKey, ValidKeyStringExt},
     x25519::{self, X25519StaticPrivateKey, X25519StaticPublicKey},
 };
 use libra_types::{
@@ -11,6 +12,7 @@ use libra_types::{
     crypto_proxies::{ValidatorInfo, ValidatorVerifier},
     validator_public_keys::ValidatorPublicKeys,
     validator_set::ValidatorSet,
+    validator_signer::derive_bls_private_key,
     PeerId,
 };
 use mirai_annotations::*;
@@ -64,6 +66,16 @@ pub struct ConsensusPeerInfo {
     #[serde(deserialize_with = "deserialize_key")]
     #[serde(rename = "c")]
     pub consensus_pubkey: Ed25519PublicKey,
+    // The BLS12-381 key the signatures of the validator are aggregated with, if it has one
+    #[serde(serialize_with = "serialize_optional_key")]
+    #[serde(deserialize_with = "deserialize_optional_key")]
+    #[serde(default, rename = "cb", skip_serializing_if = "Option::is_none")]
+    pub consensus_bls_pubkey: Option<BLS12381PublicKey>,
+    // The proof of possession of the private key of consensus_bls_pubkey
+    #[serde(serialize_with = "serialize_optional_signature")]
+    #[serde(deserialize_with = "deserialize_optional_signature")]
+    #[serde(default, rename = "cbp", skip_serializing_if = "Option::is_none")]
+    pub consensus_bls_proof_of_possession: Option<BLS12381Signature>,
 }
 
 pub struct ConsensusPrivateKey {
@@ -84,6 +96,22 @@ pub struct UpstreamPeersConfig {
     // invariant self.upstream_peers.all(|peer_id_str| { PeerId::from_str(peer_id_str) })
 }
 
+impl ConsensusPeerInfo {
+    /// Returns the BLS12-381 key of the validator and the proof of possession of its private key,
+    /// if it has both.
+    pub fn consensus_bls_public_key(&self) -> Option<(&BLS12381PublicKey, &BLS12381Signature)> {
+        match (
+            &self.consensus_bls_pubkey,
+            &self.consensus_bls_proof_of_possession,
+        ) {
+            (Some(public_key), Some(proof_of_possession)) => {
+                Some((public_key, proof_of_possession))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl ConsensusPeersConfig {
     /// Return a sorted vector of ValidatorPublicKey's
     pub fn get_validator_set(&self, network_peers_config: &NetworkPeersConfig) -> ValidatorSet {
@@ -91,7 +119,7 @@ impl ConsensusPeersConfig {
             .peers
             .iter()
             .map(|(peer_id_str, peer_info)| {
-                ValidatorPublicKeys::new(
+                let keys = ValidatorPublicKeys::new(
                     AccountAddress::from_str(peer_id_str).expect("[config] invalid peer_id"),
                     peer_info.consensus_pubkey.clone(),
                     // TODO: Add support for dynamic voting weights in config
@@ -108,7 +136,13 @@ impl ConsensusPeersConfig {
                         .unwrap()
                         .network_identity_pubkey
                         .clone(),
-                )
+                );
+                match peer_info.consensus_bls_public_key() {
+                    Some((public_key, proof_of_possession)) => {
+                        keys.with_consensus_bls_public_key(public_key, proof_of_possession)
+                    }
+                    None => keys,
+                }
             })
             .collect();
         // self.peers is a HashMap, so iterating over it produces a differently ordered vector each
@@ -130,7 +164,14 @@ impl ConsensusPeersConfig {
                             )
                         }),
                         // TODO: Add support for dynamic voting weights in config
-                        ValidatorInfo::new(peer_info.consensus_pubkey.clone(), 1),
+                        match peer_info.consensus_bls_public_key() {
+                            Some((public_key, proof_of_possession)) => {
+                                ValidatorInfo::new(peer_info.consensus_pubkey.clone(), 1)
+                                    .with_bls_public_key(public_key.clone(), proof_of_possession)
+                                    .expect("[config] invalid BLS12-381 proof of possession")
+                            }
+                            None => ValidatorInfo::new(peer_info.consensus_pubkey.clone(), 1),
+                        },
                     )
                 })
                 .collect(),
@@ -166,10 +207,15 @@ impl ConfigHelpers {
             let (private2, public2) = compat::generate_keypair(&mut fast_rng);
             // Generate peer id from consensus public key.
             let peer_id = AccountAddress::from_public_key(&public2);
+            let bls_private_key = derive_bls_private_key(&private2);
             consensus_peers.insert(
                 peer_id.to_string(),
                 ConsensusPeerInfo {
                     consensus_pubkey: public2,
+                    consensus_bls_pubkey: Some((&bls_private_key).into()),
+                    consensus_bls_proof_of_possession: Some(
+                        bls_private_key.create_proof_of_possession(),
+                    ),
                 },
             );
             consensus_private_keys.insert(
@@ -279,6 +325,63 @@ where
         .map_err(<D::Error as serde::de::Error>::custom)
 }
 
+pub fn serialize_optional_key<S, K>(key: &Option<K>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize + ValidKeyStringExt,
+{
+    match key {
+        Some(key) => key
+            .to_encoded_string()
+            .map_err(<S::Error as serde::ser::Error>::custom)
+            .and_then(|str| serializer.serialize_some(&str)),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_optional_key<'de, D, K>(deserializer: D) -> Result<Option<K>, D::Error>
+where
+    D: Deserializer<'de>,
+    K: ValidKeyStringExt + DeserializeOwned + 'static,
+{
+    let encoded_key: Option<String> = Deserialize::deserialize(deserializer)?;
+    encoded_key
+        .map(|encoded_key| ValidKeyStringExt::from_encoded_string(&encoded_key))
+        .transpose()
+        .map_err(<D::Error as serde::de::Error>::custom)
+}
+
+pub fn serialize_optional_signature<S, Sig>(
+    signature: &Option<Sig>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    Sig: Signature,
+{
+    match signature {
+        Some(signature) => serializer.serialize_some(&::hex::encode(signature.to_bytes())),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize_optional_signature<'de, D, Sig>(deserializer: D) -> Result<Option<Sig>, D::Error>
+where
+    D: Deserializer<'de>,
+    Sig: Signature,
+{
+    let encoded_signature: Option<String> = Deserialize::deserialize(deserializer)?;
+    encoded_signature
+        .map(|encoded_signature| {
+            ::hex::decode(encoded_signature)
+                .map_err(<D::Error as serde::de::Error>::custom)
+                .and_then(|bytes| {
+                    Sig::try_from(&bytes[..]).map_err(<D::Error as serde::de::Error>::custom)
+                })
+        })
+        .transpose()
+}
+
 pub fn serialize_ordered_map<S, V, H>(
     value: &HashMap<String, V, H>,
     serializer: S,
@@ -9,3 +9,21 @@ fn generate_test_config() {
         ConfigHelpers::gen_validator_nodes(10, None);
     let (_keys, _network_peers_config) = ConfigHelpers::gen_full_nodes(10, None);
 }
+
+#[test]
+fn consensus_bls_keys_roundtrip() {
+    let (_keys, consensus_peers_config, network_peers_config) =
+        ConfigHelpers::gen_validator_nodes(4, None);
+    let serialized = toml::to_string(&consensus_peers_config).unwrap();
+    let deserialized: super::ConsensusPeersConfig = toml::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, consensus_peers_config);
+
+    let validator_verifier = deserialized.get_validator_verifier();
+    for address in validator_verifier.get_ordered_account_addresses() {
+        assert!(validator_verifier.get_bls_public_key(&address).is_some());
+    }
+    let validator_set = deserialized.get_validator_set(&network_peers_config);
+    for keys in validator_set.payload() {
+        assert!(keys.consensus_bls_public_key().is_some());
+    }
+}
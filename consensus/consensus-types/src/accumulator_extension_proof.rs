@@ -27,6 +27,18 @@ pub struct AccumulatorExtensionProof<H> {
     hasher: PhantomData<H>,
 }
 
+// Derived `Clone` would needlessly require `H: Clone`.
+impl<H> Clone for AccumulatorExtensionProof<H> {
+    fn clone(&self) -> Self {
+        Self {
+            frozen_subtree_roots: self.frozen_subtree_roots.clone(),
+            num_leaves: self.num_leaves,
+            leaves: self.leaves.clone(),
+            hasher: PhantomData,
+        }
+    }
+}
+
 impl<H: CryptoHasher> AccumulatorExtensionProof<H> {
     pub fn new(
         frozen_subtree_roots: Vec<HashValue>,
@@ -225,6 +225,19 @@ where
         }
     }
 
+    /// Creates a proposal from a signature of the hash of `block_data` made without a
+    /// `ValidatorSigner`, e.g. by a key store that doesn't give out its keys.
+    pub fn new_proposal_from_block_data_and_signature(
+        block_data: BlockData<T>,
+        signature: Signature,
+    ) -> Self {
+        Block {
+            id: block_data.hash(),
+            block_data,
+            signature: Some(signature),
+        }
+    }
+
     /// Verifies that the proposal and the QC are correctly signed.
     /// If this is the genesis block, we skip these checks.
     pub fn validate_signatures(&self, validator: &ValidatorVerifier) -> failure::Result<()> {
@@ -317,8 +330,14 @@ impl<T> From<&Block<T>> for BlockMetadata {
     fn from(block: &Block<T>) -> Self {
         Self::new(
             block.id(),
+            block.round(),
             block.timestamp_usecs(),
             block.quorum_cert().ledger_info().signatures().clone(),
+            block
+                .quorum_cert()
+                .ledger_info()
+                .aggregated_signature()
+                .map_or_else(Vec::new, |signature| signature.bitmap().to_vec()),
             // For nil block, we use 0x0 which is convention for nil address in move.
             block
                 .author()
@@ -19,6 +19,8 @@ pub struct ProposalMsg<T> {
     #[serde(bound(deserialize = "Block<T>: Deserialize<'de>"))]
     proposal: Block<T>,
     sync_info: SyncInfo,
+    /// The VRF proof electing the proposer, for proposer elections kept private until then.
+    proposer_proof: Option<Vec<u8>>,
 }
 
 /// A ProposalMsg is only accessible after verifying the signatures of a ProposalUncheckedSignatures
@@ -80,6 +82,20 @@ impl<T: Payload> ProposalMsg<T> {
         Self {
             proposal,
             sync_info,
+            proposer_proof: None,
+        }
+    }
+
+    /// Creates a new proposal carrying the VRF proof that its proposer was elected.
+    pub fn new_with_proposer_proof(
+        proposal: Block<T>,
+        sync_info: SyncInfo,
+        proposer_proof: Vec<u8>,
+    ) -> Self {
+        Self {
+            proposal,
+            sync_info,
+            proposer_proof: Some(proposer_proof),
         }
     }
 
@@ -142,6 +158,10 @@ impl<T: Payload> ProposalMsg<T> {
         &self.sync_info
     }
 
+    pub fn proposer_proof(&self) -> Option<&[u8]> {
+        self.proposer_proof.as_ref().map(Vec::as_slice)
+    }
+
     pub fn round(&self) -> Round {
         self.proposal.round()
     }
@@ -113,7 +113,7 @@ impl QuorumCert {
                 "Genesis QC has inconsistent commit block with certified block"
             );
             ensure!(
-                self.ledger_info().signatures().is_empty(),
+                !self.ledger_info().is_signed(),
                 "Genesis QC should not carry signatures"
             );
             return Ok(());
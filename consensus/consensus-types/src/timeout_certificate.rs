@@ -7,7 +7,10 @@ use crate::{
 };
 use failure::prelude::*;
 use libra_crypto::hash::CryptoHash;
-use libra_types::crypto_proxies::{Signature, ValidatorVerifier};
+use libra_types::{
+    aggregate_signature::AggregateSignature,
+    crypto_proxies::{Signature, ValidatorVerifier},
+};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt};
 
@@ -17,6 +20,8 @@ use std::{collections::HashMap, fmt};
 pub struct TimeoutCertificate {
     timeout: Timeout,
     signatures: HashMap<Author, Signature>,
+    /// The round signatures aggregated into one, in place of `signatures`.
+    aggregated_signature: Option<AggregateSignature>,
 }
 
 impl fmt::Display for TimeoutCertificate {
@@ -36,11 +41,34 @@ impl TimeoutCertificate {
         Self {
             timeout,
             signatures,
+            aggregated_signature: None,
+        }
+    }
+
+    /// Creates new TimeoutCertificate with the round signatures aggregated into one
+    pub fn new_with_aggregated_signature(
+        timeout: Timeout,
+        aggregated_signature: AggregateSignature,
+    ) -> Self {
+        Self {
+            timeout,
+            signatures: HashMap::new(),
+            aggregated_signature: Some(aggregated_signature),
         }
     }
 
     /// Verifies the signatures for the round
     pub fn verify(&self, validator: &ValidatorVerifier) -> failure::Result<()> {
+        if let Some(aggregated_signature) = &self.aggregated_signature {
+            ensure!(
+                self.signatures.is_empty(),
+                "TimeoutCertificate carries both individual and aggregated signatures"
+            );
+            validator
+                .verify_aggregate_signature(self.timeout.hash(), aggregated_signature)
+                .with_context(|e| format!("Fail to verify TimeoutCertificate: {:?}", e))?;
+            return Ok(());
+        }
         validator.check_voting_power(self.signatures().keys())?;
         let timeout_hash = self.timeout.hash();
         for (author, signature) in self.signatures() {
@@ -66,6 +94,11 @@ impl TimeoutCertificate {
         &self.signatures
     }
 
+    /// Returns the round signatures aggregated into one, if the certificate carries them so
+    pub fn aggregated_signature(&self) -> Option<&AggregateSignature> {
+        self.aggregated_signature.as_ref()
+    }
+
     pub fn add_signature(&mut self, author: Author, signature: Signature) {
         self.signatures.entry(author).or_insert(signature);
     }
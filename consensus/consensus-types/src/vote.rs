@@ -5,7 +5,7 @@ use crate::{common::Author, timeout::Timeout, vote_data::VoteData};
 use failure::{ensure, ResultExt};
 use libra_crypto::hash::CryptoHash;
 use libra_types::{
-    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+    crypto_proxies::{AggregatableSignature, Signature, ValidatorSigner, ValidatorVerifier},
     ledger_info::LedgerInfo,
 };
 use serde::{Deserialize, Serialize};
@@ -27,6 +27,10 @@ pub struct Vote {
     signature: Signature,
     /// The round signatures can be aggregated into a timeout certificate if present.
     timeout_signature: Option<Signature>,
+    /// Signature of the LedgerInfo which can be aggregated with others into one, if present.
+    aggregatable_signature: Option<AggregatableSignature>,
+    /// Round signature which can be aggregated with others into one, if present.
+    aggregatable_timeout_signature: Option<AggregatableSignature>,
 }
 
 impl Display for Vote {
@@ -55,15 +59,41 @@ impl Vote {
         let li_sig = validator_signer
             .sign_message(ledger_info_placeholder.hash())
             .expect("Failed to sign LedgerInfo");
+        Self::new_with_signature(vote_data, author, ledger_info_placeholder, li_sig.into())
+    }
+
+    /// Generates a new Vote from a signature of `ledger_info` made without a `ValidatorSigner`,
+    /// e.g. by a key store that doesn't give out its keys. The consensus data hash of
+    /// `ledger_info` must already be set to the hash of `vote_data`.
+    pub fn new_with_signature(
+        vote_data: VoteData,
+        author: Author,
+        ledger_info: LedgerInfo,
+        signature: Signature,
+    ) -> Self {
         Self {
             vote_data,
             author,
-            ledger_info: ledger_info_placeholder,
-            signature: li_sig.into(),
+            ledger_info,
+            signature,
             timeout_signature: None,
+            aggregatable_signature: None,
+            aggregatable_timeout_signature: None,
         }
     }
 
+    /// Adds a signature of the LedgerInfo that can be aggregated with the ones of the other
+    /// voters into the quorum certificate.
+    pub fn add_aggregatable_signature(&mut self, signature: AggregatableSignature) {
+        self.aggregatable_signature.get_or_insert(signature);
+    }
+
+    /// Adds a round signature that can be aggregated with the ones of the other voters into the
+    /// timeout certificate.
+    pub fn add_aggregatable_timeout_signature(&mut self, signature: AggregatableSignature) {
+        self.aggregatable_timeout_signature.get_or_insert(signature);
+    }
+
     /// Generates a round signature, which can then be used for aggregating a timeout certificate.
     /// Typically called for generating vote messages that are sent upon timeouts.
     pub fn add_timeout_signature(&mut self, signature: Signature) {
@@ -112,6 +142,16 @@ impl Vote {
         self.timeout_signature.as_ref()
     }
 
+    /// Returns the signature of the LedgerInfo that can be aggregated into a QuorumCert, if any.
+    pub fn aggregatable_signature(&self) -> Option<&AggregatableSignature> {
+        self.aggregatable_signature.as_ref()
+    }
+
+    /// Returns the round signature that can be aggregated into a TimeoutCertificate, if any.
+    pub fn aggregatable_timeout_signature(&self) -> Option<&AggregatableSignature> {
+        self.aggregatable_timeout_signature.as_ref()
+    }
+
     /// The vote message is considered a timeout vote message if it carries a signature on the
     /// round, which can then be used for aggregating it to the TimeoutCertificate.
     pub fn is_timeout(&self) -> bool {
@@ -119,7 +159,7 @@ impl Vote {
     }
 
     /// Verifies that the consensus data hash of LedgerInfo corresponds to the vote info,
-    /// and then verifies the signature.
+    /// and then verifies the signatures.
     pub fn verify(&self, validator: &ValidatorVerifier) -> failure::Result<()> {
         ensure!(
             self.ledger_info.consensus_data_hash() == self.vote_data.hash(),
@@ -133,6 +173,20 @@ impl Vote {
                 .verify(validator, self.author(), self.timeout().hash())
                 .with_context(|e| format!("Fail to verify Vote: {:?}", e))?;
         }
+        if let Some(signature) = &self.aggregatable_signature {
+            validator
+                .verify_bls_signature(self.author(), self.ledger_info.hash(), signature)
+                .with_context(|e| format!("Fail to verify Vote: {:?}", e))?;
+        }
+        if let Some(signature) = &self.aggregatable_timeout_signature {
+            ensure!(
+                self.timeout_signature.is_some(),
+                "Vote carries an aggregatable round signature without a round signature"
+            );
+            validator
+                .verify_bls_signature(self.author(), self.timeout().hash(), signature)
+                .with_context(|e| format!("Fail to verify Vote: {:?}", e))?;
+        }
         Ok(())
     }
 }
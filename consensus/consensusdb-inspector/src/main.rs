@@ -0,0 +1,62 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dumps the ConsensusDB of a node, or replays its recovery out of the ConsensusDB and the latest
+//! ledger info of its LibraDB. Both databases are opened in read-only mode.
+
+use consensus::consensusdb_inspector::{BlockTreeSummary, ConsensusDBInspector};
+use executable_helpers::helpers::setup_executable;
+use failure::prelude::*;
+use libra_config::config::NodeConfig;
+use libradb::LibraDB;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Dump the ConsensusDB of a node as JSON, and replay its recovery")]
+struct Args {
+    #[structopt(short = "f", long, parse(from_os_str))]
+    /// Path to NodeConfig of the node. The databases are opened read-only in its storage directory
+    config: Option<PathBuf>,
+    #[structopt(short = "d", long)]
+    /// Disable logging
+    no_logging: bool,
+    #[structopt(long)]
+    /// Rebuild the block tree out of the databases as the node would on startup, check its
+    /// invariants and print its summary instead of the contents of the ConsensusDB
+    replay: bool,
+}
+
+fn main() {
+    let args = Args::from_args();
+
+    let (config, _logger) =
+        setup_executable(args.config.as_ref().map(PathBuf::as_path), args.no_logging);
+
+    let inspector = ConsensusDBInspector::open(&config).expect("Unable to open the ConsensusDB");
+    let output = if args.replay {
+        let summary = replay(&inspector, &config).expect("Replay failed");
+        serde_json::to_string_pretty(&summary)
+    } else {
+        let contents = inspector
+            .contents()
+            .expect("Unable to read the ConsensusDB");
+        serde_json::to_string_pretty(&contents)
+    };
+    println!("{}", output.expect("Unable to serialize to JSON"));
+}
+
+/// Replays the recovery of the node from the latest ledger info of its LibraDB.
+fn replay(inspector: &ConsensusDBInspector, config: &NodeConfig) -> Result<BlockTreeSummary> {
+    let libra_db = LibraDB::open_readonly(config.get_storage_dir())?;
+    let startup_info = libra_db
+        .get_startup_info()?
+        .ok_or_else(|| format_err!("No startup info in LibraDB"))?;
+    let validators = startup_info
+        .ledger_info_with_validators
+        .ledger_info()
+        .next_validator_set()
+        .ok_or_else(|| format_err!("No validator set in the latest epoch change"))?
+        .into();
+    inspector.replay(startup_info.ledger_info.ledger_info(), validators)
+}
@@ -0,0 +1,221 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The consensus private keys held by the storage of SafetyRules, and their encryption at rest.
+//!
+//! Keys only ever sign inside the storage. On disk, they are kept in a single file encrypted with
+//! ChaCha20-Poly1305, under a key derived with PBKDF2 from a secret supplied by the operator.
+
+use crate::Error;
+use hmac::Hmac;
+use libra_crypto::{
+    bls12381::BLS12381Signature,
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::HashValue,
+    traits::{PrivateKey, SigningKey},
+    vrf::ecvrf::VRFPrivateKey,
+};
+use libra_types::validator_signer::derive_bls_private_key;
+use pbkdf2::pbkdf2;
+use serde::{Deserialize, Serialize};
+use sha3::Sha3_256;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::PathBuf,
+};
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const SALT_LENGTH: usize = 32;
+const TAG_LENGTH: usize = 16;
+/// Iterations of PBKDF2 for files created from now on, to slow down guessing weak passphrases.
+const KDF_ITERATIONS: u32 = 100_000;
+
+/// The consensus private keys held by a storage, indexed by public key. Only the current one
+/// signs; the others are kept for an upcoming key rotation.
+#[derive(Debug, Default)]
+pub struct ConsensusKeys {
+    current: Option<Ed25519PublicKey>,
+    keys: HashMap<Ed25519PublicKey, Ed25519PrivateKey>,
+}
+
+impl ConsensusKeys {
+    pub fn current_public_key(&self) -> Option<Ed25519PublicKey> {
+        self.current.clone()
+    }
+
+    /// Adds a copy of `private_key`, which becomes the current key if there's none yet.
+    pub fn add(&mut self, private_key: &Ed25519PrivateKey) -> Ed25519PublicKey {
+        let public_key = private_key.public_key();
+        self.keys
+            .insert(public_key.clone(), copy_private_key(private_key));
+        if self.current.is_none() {
+            self.current = Some(public_key.clone());
+        }
+        public_key
+    }
+
+    /// Makes the key matching `public_key` the current one. The previous current key is dropped,
+    /// so that a key rotated out can't sign anymore.
+    pub fn set_current(&mut self, public_key: &Ed25519PublicKey) -> Result<(), Error> {
+        if !self.keys.contains_key(public_key) {
+            return Err(Error::ConsensusKeyNotFound {
+                public_key: public_key.to_string(),
+            });
+        }
+        if let Some(previous) = self.current.replace(public_key.clone()) {
+            if &previous != public_key {
+                self.keys.remove(&previous);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn sign_message(&self, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        Ok(self.current_private_key()?.sign_message(message))
+    }
+
+    /// Signs `message` with the BLS12-381 key derived from the current key, into a signature that
+    /// can be aggregated with the ones of other validators.
+    pub fn bls_sign_message(&self, message: &HashValue) -> Result<BLS12381Signature, Error> {
+        Ok(derive_bls_private_key(self.current_private_key()?).sign_message(message))
+    }
+
+    /// Evaluates the VRF keyed by the current key over `input` and returns the proof of its output.
+    pub fn vrf_prove(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let private_key = self.current_private_key()?;
+        let vrf_private_key = VRFPrivateKey::try_from(&private_key.to_bytes()[..])
+            .expect("Consensus keys are valid VRF keys.");
+        Ok(vrf_private_key.prove(input).to_bytes().to_vec())
+    }
+
+    fn current_private_key(&self) -> Result<&Ed25519PrivateKey, Error> {
+        self.current
+            .as_ref()
+            .and_then(|public_key| self.keys.get(public_key))
+            .ok_or(Error::MissingConsensusKey)
+    }
+}
+
+/// Private keys are only `Clone` in tests, to keep copies of them from spreading. The storage
+/// needs its own copy of the keys added to it though.
+fn copy_private_key(private_key: &Ed25519PrivateKey) -> Ed25519PrivateKey {
+    Ed25519PrivateKey::try_from(&private_key.to_bytes()[..]).expect("Private keys are valid.")
+}
+
+/// The content of a file holding [`ConsensusKeys`].
+#[derive(Deserialize, Serialize)]
+struct SealedConsensusKeys {
+    kdf_iterations: u32,
+    salt: [u8; SALT_LENGTH],
+    nonce: [u8; NONCE_LENGTH],
+    tag: [u8; TAG_LENGTH],
+    /// The LCS encoding of the current public key and of all private keys, encrypted.
+    ciphertext: Vec<u8>,
+}
+
+/// A file holding [`ConsensusKeys`] encrypted under a secret.
+pub struct ConsensusKeysFile {
+    path: PathBuf,
+    kdf_iterations: u32,
+    salt: [u8; SALT_LENGTH],
+    /// The encryption key, derived from the secret and `salt`.
+    key: [u8; KEY_LENGTH],
+}
+
+impl ConsensusKeysFile {
+    /// Opens the file at `path` and decrypts the keys in it with `secret`. A file without keys is
+    /// created if there's none yet.
+    pub fn open(path: PathBuf, secret: &[u8]) -> Result<(Self, ConsensusKeys), Error> {
+        if !path.exists() {
+            let file = Self::new(path, secret, KDF_ITERATIONS, rand::random());
+            let keys = ConsensusKeys::default();
+            file.save(&keys)?;
+            return Ok((file, keys));
+        }
+
+        let sealed: SealedConsensusKeys =
+            lcs::from_bytes(&fs::read(&path).map_err(key_store_error)?)?;
+        let file = Self::new(path, secret, sealed.kdf_iterations, sealed.salt);
+        let mut plaintext = Vec::with_capacity(sealed.ciphertext.len());
+        chacha20_poly1305_aead::decrypt(
+            &file.key,
+            &sealed.nonce,
+            &[],
+            &sealed.ciphertext,
+            &sealed.tag,
+            &mut plaintext,
+        )
+        .map_err(|_| {
+            key_store_error("unable to decrypt the consensus keys, the secret may be wrong")
+        })?;
+        let (current, private_keys): (Option<Ed25519PublicKey>, Vec<Ed25519PrivateKey>) =
+            lcs::from_bytes(&plaintext)?;
+        let keys = ConsensusKeys {
+            current,
+            keys: private_keys
+                .into_iter()
+                .map(|private_key| (private_key.public_key(), private_key))
+                .collect(),
+        };
+        Ok((file, keys))
+    }
+
+    fn new(path: PathBuf, secret: &[u8], kdf_iterations: u32, salt: [u8; SALT_LENGTH]) -> Self {
+        let mut key = [0; KEY_LENGTH];
+        pbkdf2::<Hmac<Sha3_256>>(secret, &salt, kdf_iterations as usize, &mut key);
+        Self {
+            path,
+            kdf_iterations,
+            salt,
+            key,
+        }
+    }
+
+    /// Replaces the content of the file with `keys`, encrypted under a fresh nonce.
+    pub fn save(&self, keys: &ConsensusKeys) -> Result<(), Error> {
+        let private_keys: Vec<_> = keys.keys.values().collect();
+        let plaintext = lcs::to_bytes(&(&keys.current, private_keys))?;
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let mut ciphertext = Vec::with_capacity(plaintext.len());
+        let tag =
+            chacha20_poly1305_aead::encrypt(&self.key, &nonce, &[], &plaintext, &mut ciphertext)
+                .map_err(key_store_error)?;
+        let sealed = SealedConsensusKeys {
+            kdf_iterations: self.kdf_iterations,
+            salt: self.salt,
+            nonce,
+            tag,
+            ciphertext,
+        };
+
+        // Write a new file and move it in place, so that the keys can't be lost halfway through.
+        // The file is created readable by its owner only, before anything is written to it: a
+        // leftover of a previous attempt is removed first, as it may have other permissions.
+        let tmp_path = self.path.with_extension("tmp");
+        match fs::remove_file(&tmp_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(key_store_error(e)),
+            _ => (),
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp_path)
+            .map_err(key_store_error)?;
+        file.write_all(&lcs::to_bytes(&sealed)?)
+            .map_err(key_store_error)?;
+        fs::rename(&tmp_path, &self.path).map_err(key_store_error)
+    }
+}
+
+fn key_store_error(error: impl Display) -> Error {
+    Error::KeyStoreError {
+        error: error.to_string(),
+    }
+}
@@ -1,12 +1,25 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod consensus_keys;
 mod persistent_storage;
+mod process;
+mod remote_service;
 mod safety_rules;
+mod safety_rules_manager;
+mod serializer;
+mod t_safety_rules;
+mod thread;
 
 pub use crate::{
-    persistent_storage::{InMemoryStorage, OnDiskStorage},
+    persistent_storage::{InMemoryStorage, OnDiskStorage, PersistentStorage},
+    process::ProcessService,
+    remote_service::RemoteClient,
     safety_rules::{ConsensusState, Error, SafetyRules},
+    safety_rules_manager::{run_process_service, safety_rules_client, storage_from_config},
+    serializer::{SafetyRulesInput, SerializerClient, SerializerService, TSerializerClient},
+    t_safety_rules::TSafetyRules,
+    thread::spawn_thread,
 };
 
 #[cfg(test)]
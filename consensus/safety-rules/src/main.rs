@@ -0,0 +1,79 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use executable_helpers::helpers::setup_executable;
+use libra_logger::prelude::*;
+use libra_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use std::{convert::TryFrom, path::PathBuf};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Libra SafetyRules, serving the consensus of a validator in another process")]
+struct Args {
+    #[structopt(short = "f", long, parse(from_os_str))]
+    /// Path to NodeConfig of the validator. SafetyRules listens at the socket configured in its
+    /// `consensus.safety_rules.service`
+    config: Option<PathBuf>,
+    #[structopt(short = "d", long)]
+    /// Disable logging
+    no_logging: bool,
+    #[structopt(long)]
+    /// Add the private key of the consensus keypair file to the encrypted consensus keys of
+    /// `consensus.safety_rules.backend` and exit. The private key can then be removed from the
+    /// consensus keypair file
+    import_consensus_key: bool,
+}
+
+fn main() {
+    let args = Args::from_args();
+
+    let (mut config, _logger) =
+        setup_executable(args.config.as_ref().map(PathBuf::as_path), args.no_logging);
+
+    let peer_id = config
+        .get_validator_network_config()
+        .expect("SafetyRules only runs for validators")
+        .peer_id
+        .clone();
+    let author = AccountAddress::try_from(peer_id).expect("Failed to parse peer id of a validator");
+    let safety_rules_config = &config.consensus.safety_rules;
+    let holds_consensus_keys = safety_rules_config.backend.holds_consensus_keys();
+
+    if args.import_consensus_key {
+        assert!(
+            holds_consensus_keys,
+            "No encrypted consensus keys configured to import the key into"
+        );
+        let private_key = config
+            .consensus
+            .consensus_keypair
+            .take_consensus_private()
+            .expect("No consensus private key to import");
+        let public_key = safety_rules::storage_from_config(&safety_rules_config.backend)
+            .and_then(|mut storage| storage.add_consensus_key(&private_key))
+            .expect("Unable to import the consensus private key");
+        info!("Imported the consensus key with public key {}", public_key);
+        return;
+    }
+
+    // Without encrypted consensus keys, the key is read from the consensus keypair file.
+    let private_key = if holds_consensus_keys {
+        None
+    } else {
+        Some(
+            config
+                .consensus
+                .consensus_keypair
+                .take_consensus_private()
+                .expect("Failed to move a Consensus private key from a NodeConfig"),
+        )
+    };
+
+    info!("Starting SafetyRules for {}", author);
+    safety_rules::run_process_service::<Vec<SignedTransaction>>(
+        &config.consensus.safety_rules,
+        author,
+        private_key.as_ref(),
+    )
+    .expect("Unable to run SafetyRules");
+}
@@ -1,8 +1,19 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{
+    consensus_keys::{ConsensusKeys, ConsensusKeysFile},
+    Error,
+};
 use consensus_types::common::Round;
 use libra_config::config::PersistableConfig;
+use libra_crypto::{
+    bls12381::BLS12381Signature,
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::HashValue,
+};
+#[cfg(test)]
+use libra_crypto::{ed25519::compat, traits::Signature};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 #[cfg(test)]
@@ -11,8 +22,9 @@ use tempfile::NamedTempFile;
 /// SafetyRules needs an abstract storage interface to act as a common utility for storing
 /// persistent data to local disk, cloud, secrets managers, or even memory (for tests)
 /// Any set function is expected to sync to the remote system before returning.
-/// @TODO add access to private key from persistent store
-/// @TODO add retrieval of private key based upon public key to persistent store
+///
+/// The storage also holds the consensus private keys, indexed by public key. They never leave
+/// it: only signatures made with the current key come out.
 pub trait PersistentStorage: Send + Sync {
     fn epoch(&self) -> u64;
     fn set_epoch(&mut self, epoch: u64);
@@ -20,6 +32,23 @@ pub trait PersistentStorage: Send + Sync {
     fn set_last_voted_round(&mut self, last_voted_round: Round);
     fn preferred_round(&self) -> Round;
     fn set_preferred_round(&mut self, last_voted_round: Round);
+    /// Returns the public key of the consensus key currently signing, if any.
+    fn consensus_public_key(&self) -> Option<Ed25519PublicKey>;
+    /// Keeps a copy of `private_key`, e.g. ahead of a key rotation. It becomes the current key if
+    /// there's none yet.
+    fn add_consensus_key(
+        &mut self,
+        private_key: &Ed25519PrivateKey,
+    ) -> Result<Ed25519PublicKey, Error>;
+    /// Makes the consensus key matching `public_key` the current one, dropping the previous one.
+    fn set_consensus_public_key(&mut self, public_key: &Ed25519PublicKey) -> Result<(), Error>;
+    /// Signs `message` with the current consensus key.
+    fn sign_message(&self, message: &HashValue) -> Result<Ed25519Signature, Error>;
+    /// Signs `message` with the BLS12-381 key derived from the current consensus key.
+    fn bls_sign_message(&self, message: &HashValue) -> Result<BLS12381Signature, Error>;
+    /// Evaluates the VRF keyed by the current consensus key over `input`, and returns the proof
+    /// of the output.
+    fn vrf_prove(&self, input: &[u8]) -> Result<Vec<u8>, Error>;
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -27,6 +56,9 @@ pub struct InMemoryStorage {
     epoch: u64,
     last_voted_round: Round,
     preferred_round: Round,
+    // Never serialized: the keys only reach the disk encrypted, through `OnDiskStorage`.
+    #[serde(skip)]
+    consensus_keys: ConsensusKeys,
 }
 
 impl InMemoryStorage {
@@ -35,15 +67,12 @@ impl InMemoryStorage {
             epoch,
             last_voted_round,
             preferred_round,
+            consensus_keys: ConsensusKeys::default(),
         }
     }
 
     pub fn default() -> Self {
-        Self {
-            epoch: 1,
-            last_voted_round: 0,
-            preferred_round: 0,
-        }
+        Self::new(1, 0, 0)
     }
 
     pub fn default_storage() -> Box<dyn PersistentStorage> {
@@ -75,6 +104,33 @@ impl PersistentStorage for InMemoryStorage {
     fn set_last_voted_round(&mut self, last_voted_round: Round) {
         self.last_voted_round = last_voted_round;
     }
+
+    fn consensus_public_key(&self) -> Option<Ed25519PublicKey> {
+        self.consensus_keys.current_public_key()
+    }
+
+    fn add_consensus_key(
+        &mut self,
+        private_key: &Ed25519PrivateKey,
+    ) -> Result<Ed25519PublicKey, Error> {
+        Ok(self.consensus_keys.add(private_key))
+    }
+
+    fn set_consensus_public_key(&mut self, public_key: &Ed25519PublicKey) -> Result<(), Error> {
+        self.consensus_keys.set_current(public_key)
+    }
+
+    fn sign_message(&self, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        self.consensus_keys.sign_message(message)
+    }
+
+    fn bls_sign_message(&self, message: &HashValue) -> Result<BLS12381Signature, Error> {
+        self.consensus_keys.bls_sign_message(message)
+    }
+
+    fn vrf_prove(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        self.consensus_keys.vrf_prove(input)
+    }
 }
 
 #[test]
@@ -94,28 +150,57 @@ fn test_in_memory_storage() {
 pub struct OnDiskStorage {
     file_path: PathBuf,
     internal_data: InMemoryStorage,
+    // Where the consensus keys are kept, if anywhere. Otherwise they are only kept in memory.
+    consensus_keys_file: Option<ConsensusKeysFile>,
 }
 
 impl OnDiskStorage {
     pub fn new_storage(file_path: PathBuf) -> Box<dyn PersistentStorage> {
-        let internal_data = InMemoryStorage::load_config(file_path.clone());
-        Box::new(Self {
-            file_path,
-            internal_data,
-        })
+        Box::new(Self::open(file_path, false))
     }
 
     pub fn default_storage(file_path: PathBuf) -> Box<dyn PersistentStorage> {
-        if file_path.exists() {
-            return Self::new_storage(file_path);
-        }
+        Box::new(Self::open(file_path, true))
+    }
+
+    /// Opens the storage like `default_storage` or `new_storage`, depending on `default`, keeping
+    /// the consensus keys in `consensus_keys_path` encrypted under `secret`.
+    pub fn storage_with_consensus_keys(
+        file_path: PathBuf,
+        default: bool,
+        consensus_keys_path: PathBuf,
+        secret: &[u8],
+    ) -> Result<Box<dyn PersistentStorage>, Error> {
+        let mut storage = Self::open(file_path, default);
+        let (consensus_keys_file, consensus_keys) =
+            ConsensusKeysFile::open(consensus_keys_path, secret)?;
+        storage.internal_data.consensus_keys = consensus_keys;
+        storage.consensus_keys_file = Some(consensus_keys_file);
+        Ok(Box::new(storage))
+    }
 
-        let internal_data = InMemoryStorage::default();
-        internal_data.save_config(file_path.clone());
-        Box::new(Self {
+    fn open(file_path: PathBuf, default: bool) -> Self {
+        let internal_data = if default && !file_path.exists() {
+            let internal_data = InMemoryStorage::default();
+            internal_data.save_config(file_path.clone());
+            internal_data
+        } else {
+            InMemoryStorage::load_config(file_path.clone())
+        };
+        Self {
             file_path,
             internal_data,
-        })
+            consensus_keys_file: None,
+        }
+    }
+
+    fn save_consensus_keys(&self) -> Result<(), Error> {
+        match &self.consensus_keys_file {
+            Some(consensus_keys_file) => {
+                consensus_keys_file.save(&self.internal_data.consensus_keys)
+            }
+            None => Ok(()),
+        }
     }
 }
 
@@ -146,6 +231,36 @@ impl PersistentStorage for OnDiskStorage {
         self.internal_data.set_last_voted_round(last_voted_round);
         self.internal_data.save_config(self.file_path.clone());
     }
+
+    fn consensus_public_key(&self) -> Option<Ed25519PublicKey> {
+        self.internal_data.consensus_public_key()
+    }
+
+    fn add_consensus_key(
+        &mut self,
+        private_key: &Ed25519PrivateKey,
+    ) -> Result<Ed25519PublicKey, Error> {
+        let public_key = self.internal_data.add_consensus_key(private_key)?;
+        self.save_consensus_keys()?;
+        Ok(public_key)
+    }
+
+    fn set_consensus_public_key(&mut self, public_key: &Ed25519PublicKey) -> Result<(), Error> {
+        self.internal_data.set_consensus_public_key(public_key)?;
+        self.save_consensus_keys()
+    }
+
+    fn sign_message(&self, message: &HashValue) -> Result<Ed25519Signature, Error> {
+        self.internal_data.sign_message(message)
+    }
+
+    fn bls_sign_message(&self, message: &HashValue) -> Result<BLS12381Signature, Error> {
+        self.internal_data.bls_sign_message(message)
+    }
+
+    fn vrf_prove(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        self.internal_data.vrf_prove(input)
+    }
 }
 
 #[test]
@@ -167,3 +282,93 @@ fn test_on_disk_storage() {
     assert_eq!(storage.last_voted_round(), 8);
     assert_eq!(storage.preferred_round(), 1);
 }
+
+#[test]
+fn test_in_memory_consensus_keys() {
+    let (private_key, public_key) = compat::generate_keypair(None);
+    let (next_private_key, next_public_key) = compat::generate_genesis_keypair();
+    let message = HashValue::random();
+
+    let mut storage: Box<dyn PersistentStorage> = InMemoryStorage::default_storage();
+    assert_eq!(storage.consensus_public_key(), None);
+    assert_eq!(
+        storage.sign_message(&message),
+        Err(Error::MissingConsensusKey)
+    );
+
+    // The first key added becomes the current one.
+    assert_eq!(
+        storage.add_consensus_key(&private_key),
+        Ok(public_key.clone())
+    );
+    assert_eq!(
+        storage.add_consensus_key(&next_private_key),
+        Ok(next_public_key.clone())
+    );
+    assert_eq!(storage.consensus_public_key(), Some(public_key.clone()));
+    let signature = storage.sign_message(&message).unwrap();
+    assert!(signature.verify(&message, &public_key).is_ok());
+
+    // Rotating drops the previous key.
+    storage.set_consensus_public_key(&next_public_key).unwrap();
+    assert_eq!(
+        storage.consensus_public_key(),
+        Some(next_public_key.clone())
+    );
+    let signature = storage.sign_message(&message).unwrap();
+    assert!(signature.verify(&message, &next_public_key).is_ok());
+    assert_eq!(
+        storage.set_consensus_public_key(&public_key),
+        Err(Error::ConsensusKeyNotFound {
+            public_key: public_key.to_string()
+        })
+    );
+}
+
+#[test]
+fn test_on_disk_consensus_keys() {
+    let (private_key, public_key) = compat::generate_keypair(None);
+    let (next_private_key, next_public_key) = compat::generate_genesis_keypair();
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("safety_rules.toml");
+    let keys_path = dir.path().join("consensus_keys");
+    let open = |secret: &[u8]| {
+        OnDiskStorage::storage_with_consensus_keys(
+            file_path.clone(),
+            true,
+            keys_path.clone(),
+            secret,
+        )
+    };
+
+    let mut storage = open(b"passphrase").unwrap();
+    assert_eq!(storage.consensus_public_key(), None);
+    storage.add_consensus_key(&private_key).unwrap();
+    storage.add_consensus_key(&next_private_key).unwrap();
+    storage.set_epoch(9);
+
+    // Keys are only written encrypted.
+    let content = std::fs::read(&keys_path).unwrap();
+    for key in &[private_key.to_bytes(), next_private_key.to_bytes()] {
+        assert!(!content.windows(key.len()).any(|window| window == key));
+    }
+
+    let mut storage = open(b"passphrase").unwrap();
+    assert_eq!(storage.epoch(), 9);
+    assert_eq!(storage.consensus_public_key(), Some(public_key));
+    storage.set_consensus_public_key(&next_public_key).unwrap();
+
+    let storage = open(b"passphrase").unwrap();
+    assert_eq!(
+        storage.consensus_public_key(),
+        Some(next_public_key.clone())
+    );
+    let message = HashValue::random();
+    let signature = storage.sign_message(&message).unwrap();
+    assert!(signature.verify(&message, &next_public_key).is_ok());
+
+    match open(b"wrong passphrase") {
+        Err(Error::KeyStoreError { .. }) => (),
+        _ => panic!("Keys decrypted with the wrong secret"),
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{remote_service, serializer::SerializerService, SafetyRules};
+use consensus_types::common::Payload;
+use libra_logger::prelude::*;
+use std::{
+    fs, io,
+    os::unix::{fs::FileTypeExt, net::UnixListener},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// SafetyRules running in its own process, serving consensus over a Unix socket. Consensus
+/// connects anew on every epoch, possibly before it drops the connection of the previous one, so
+/// each connection is served in its own thread. They share SafetyRules, which handles one request
+/// at a time.
+pub struct ProcessService {
+    listener: UnixListener,
+}
+
+impl ProcessService {
+    /// Listens at `socket_path`, replacing the socket left behind by a previous run if any.
+    pub fn bind(socket_path: &Path) -> io::Result<Self> {
+        if let Ok(metadata) = fs::symlink_metadata(socket_path) {
+            if metadata.file_type().is_socket() {
+                fs::remove_file(socket_path)?;
+            }
+        }
+        Ok(Self {
+            listener: UnixListener::bind(socket_path)?,
+        })
+    }
+
+    /// Serves `safety_rules` forever.
+    pub fn serve<T: Payload>(&self, safety_rules: SafetyRules) -> io::Result<()> {
+        let service = Arc::new(Mutex::new(SerializerService::new(safety_rules)));
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let service = Arc::clone(&service);
+            thread::Builder::new()
+                .name("safety-rules-connection".to_string())
+                .spawn(move || {
+                    if let Err(e) = remote_service::serve::<T>(stream, &service) {
+                        warn!("Lost connection to consensus: {}", e);
+                    }
+                })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Carries the messages of [`SerializerClient`](crate::SerializerClient) over a Unix socket, to
+//! SafetyRules running in another thread or process. Each message is prefixed with its length as
+//! a little-endian `u32`, and can't be longer than [`MAX_MESSAGE_SIZE`].
+
+use crate::{
+    serializer::{SerializerService, TSerializerClient},
+    Error,
+};
+use consensus_types::common::Payload;
+use std::{
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    sync::Mutex,
+};
+
+/// Messages carry a few blocks at most, so longer ones come from a corrupt stream or a hostile
+/// process.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// The client end of a socket to a SafetyRules service.
+pub struct RemoteClient {
+    stream: Mutex<UnixStream>,
+}
+
+impl RemoteClient {
+    pub fn new(stream: UnixStream) -> Self {
+        Self {
+            stream: Mutex::new(stream),
+        }
+    }
+
+    /// Connects to the SafetyRules service listening at `socket_path`.
+    pub fn connect(socket_path: &Path) -> io::Result<Self> {
+        Ok(Self::new(UnixStream::connect(socket_path)?))
+    }
+}
+
+impl TSerializerClient for RemoteClient {
+    fn request(&self, input: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let mut stream = self.stream.lock().unwrap();
+        write_message(&mut *stream, &input)?;
+        Ok(read_message(&mut *stream)?)
+    }
+}
+
+/// Serves the requests coming in on `stream` with `service` until the client hangs up. The
+/// service may be shared with other connections: each request is handled under its lock.
+pub fn serve<T: Payload>(
+    mut stream: UnixStream,
+    service: &Mutex<SerializerService>,
+) -> io::Result<()> {
+    loop {
+        let request = match read_message(&mut stream) {
+            Ok(request) => request,
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let response = service.lock().unwrap().handle_message::<T>(&request);
+        write_message(&mut stream, &response)?;
+    }
+}
+
+fn read_message(stream: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Message of {} bytes is too long.", len),
+        ));
+    }
+    let mut message = vec![0; len];
+    stream.read_exact(&mut message)?;
+    Ok(message)
+}
+
+fn write_message(stream: &mut impl Write, message: &[u8]) -> io::Result<()> {
+    if message.len() > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Message too long.",
+        ));
+    }
+    stream.write_all(&(message.len() as u32).to_le_bytes())?;
+    stream.write_all(message)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_message() {
+        let mut buffer = vec![];
+        write_message(&mut buffer, b"hello").unwrap();
+        assert_eq!(read_message(&mut buffer.as_slice()).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_read_message_too_long() {
+        let len = (MAX_MESSAGE_SIZE as u32 + 1).to_le_bytes();
+        let error = read_message(&mut &len[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
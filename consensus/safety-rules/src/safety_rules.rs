@@ -1,11 +1,11 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::persistent_storage::PersistentStorage;
+use crate::{persistent_storage::PersistentStorage, t_safety_rules::TSafetyRules};
 use consensus_types::{
     block::Block,
     block_data::BlockData,
-    common::{Payload, Round},
+    common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
     timeout::Timeout,
     vote::Vote,
@@ -13,17 +13,17 @@ use consensus_types::{
     vote_proposal::VoteProposal,
 };
 use failure::Fail;
-use libra_crypto::hash::HashValue;
+use libra_crypto::hash::{CryptoHash, HashValue};
+use libra_logger::prelude::*;
 use libra_types::{
     block_info::BlockInfo,
-    crypto_proxies::{Signature, ValidatorSigner},
+    crypto_proxies::{AggregatableSignature, Signature, ValidatorVerifier},
     ledger_info::LedgerInfo,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
-use std::sync::Arc;
 
-#[derive(Debug, Fail, PartialEq)]
+#[derive(Debug, Deserialize, Fail, PartialEq, Serialize)]
 /// Different reasons for proposal rejection
 pub enum Error {
     #[fail(
@@ -49,6 +49,46 @@ pub enum Error {
         last_voted_round: Round,
         proposal_round: Round,
     },
+
+    /// A request to or a response from a SafetyRules service couldn't be (de)serialized.
+    #[fail(display = "Unable to serialize or deserialize a message: {}", error)]
+    SerializationError { error: String },
+
+    /// A SafetyRules service in another thread or process couldn't be reached.
+    #[fail(display = "Unable to reach SafetyRules: {}", error)]
+    InternalError { error: String },
+
+    /// The persistent storage holds no current consensus key to sign with.
+    #[fail(display = "No consensus key to sign with")]
+    MissingConsensusKey,
+
+    /// The persistent storage holds no consensus key matching the given public key.
+    #[fail(display = "No consensus key matching public key {}", public_key)]
+    ConsensusKeyNotFound { public_key: String },
+
+    /// A vote to sign again wasn't signed by this validator with its current consensus key.
+    #[fail(display = "Vote not signed with the current consensus key: {}", vote)]
+    ForeignVote { vote: String },
+
+    /// The consensus keys couldn't be read from or written to where they are kept.
+    #[fail(display = "Unable to access the consensus keys: {}", error)]
+    KeyStoreError { error: String },
+}
+
+impl From<lcs::Error> for Error {
+    fn from(error: lcs::Error) -> Self {
+        Error::SerializationError {
+            error: format!("{}", error),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::InternalError {
+            error: format!("{}", error),
+        }
+    }
 }
 
 /// Public representation of the internal state of SafetyRules for monitoring / debugging purposes.
@@ -105,7 +145,7 @@ impl ConsensusState {
 /// SafetyRules is responsible for the safety of the consensus:
 /// 1) voting rules
 /// 2) commit rules
-/// 3) ownership of the consensus private key
+/// 3) ownership of the consensus private key, through its persistent storage
 /// @TODO add a benchmark to evaluate SafetyRules
 /// @TODO consider a cache of verified QCs to cut down on verification costs
 /// @TODO bootstrap with a hash of a ledger info (waypoint) that includes a validator set
@@ -113,54 +153,22 @@ impl ConsensusState {
 /// set)
 pub struct SafetyRules {
     persistent_storage: Box<dyn PersistentStorage>,
-    validator_signer: Arc<ValidatorSigner>,
+    author: Author,
 }
 
 impl SafetyRules {
-    /// Constructs a new instance of SafetyRules with the given persistent storage and the
-    /// consensus private keys
+    /// Constructs a new instance of SafetyRules with the given persistent storage, signing on
+    /// behalf of `author` with the consensus keys held by the storage
     /// @TODO replace this with an API that takes in a SafetyRulesConfig
-    /// @TODO load private key from persistent store
-    pub fn new(
-        persistent_storage: Box<dyn PersistentStorage>,
-        validator_signer: Arc<ValidatorSigner>,
-    ) -> Self {
+    pub fn new(persistent_storage: Box<dyn PersistentStorage>, author: Author) -> Self {
         Self {
             persistent_storage,
-            validator_signer,
-        }
-    }
-
-    pub fn signer(&self) -> &ValidatorSigner {
-        &self.validator_signer
-    }
-
-    /// Learn about a new quorum certificate. In normal state, this updates the preferred round,
-    /// if the parent is greater than our current preferred round.
-    /// @TODO verify signatures of the QC, also the special genesis QC
-    /// @TODO improving signaling by stating reaction to passed in QC:
-    ///     QC has older preferred round,
-    ///     signatures are incorrect,
-    ///     epoch is unexpected
-    ///     updating to new preferred round
-    /// @TODO update epoch with validator set
-    /// @TODO if public key does not match private key in validator set, access persistent storage
-    /// to identify new key
-    pub fn update(&mut self, qc: &QuorumCert) {
-        if qc.parent_block().round() > self.persistent_storage.preferred_round() {
-            self.persistent_storage
-                .set_preferred_round(qc.parent_block().round());
+            author,
         }
     }
 
-    /// Notify the safety rules about the new epoch start.
-    pub fn start_new_epoch(&mut self, qc: &QuorumCert) {
-        if qc.commit_info().epoch() > self.persistent_storage.epoch() {
-            self.persistent_storage.set_epoch(qc.commit_info().epoch());
-            self.persistent_storage.set_last_voted_round(0);
-            self.persistent_storage.set_preferred_round(0);
-        }
-        self.update(qc);
+    pub fn author(&self) -> Author {
+        self.author
     }
 
     /// Produces a LedgerInfo that either commits a block based upon the 3-chain commit rule
@@ -185,24 +193,96 @@ impl SafetyRules {
         }
     }
 
+    fn update_preferred_round(&mut self, qc: &QuorumCert) {
+        if qc.parent_block().round() > self.persistent_storage.preferred_round() {
+            self.persistent_storage
+                .set_preferred_round(qc.parent_block().round());
+        }
+    }
+
+    /// Switches to the consensus key of this validator in the next validator set, if `qc` ends
+    /// the epoch. If the persistent storage doesn't hold that key, the current one keeps signing.
+    fn update_consensus_key(&mut self, qc: &QuorumCert) -> Result<(), Error> {
+        let next_validator_set = match qc.ledger_info().ledger_info().next_validator_set() {
+            Some(next_validator_set) => next_validator_set,
+            None => return Ok(()),
+        };
+        let next_public_key = match next_validator_set
+            .payload()
+            .iter()
+            .find(|validator| *validator.account_address() == self.author)
+        {
+            Some(validator) => validator.consensus_public_key(),
+            // Not a validator in the next epoch, nothing to sign with there.
+            None => return Ok(()),
+        };
+        if self.persistent_storage.consensus_public_key().as_ref() == Some(next_public_key) {
+            return Ok(());
+        }
+        match self
+            .persistent_storage
+            .set_consensus_public_key(next_public_key)
+        {
+            Err(Error::ConsensusKeyNotFound { public_key }) => {
+                error!(
+                    "Missing the consensus key {} of the next epoch, keeping the current one",
+                    public_key
+                );
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn sign(&self, message: HashValue) -> Result<Signature, Error> {
+        Ok(self.persistent_storage.sign_message(&message)?.into())
+    }
+}
+
+impl<T: Payload> TSafetyRules<T> for SafetyRules {
+    /// Learn about a new quorum certificate. In normal state, this updates the preferred round,
+    /// if the parent is greater than our current preferred round.
+    /// @TODO verify signatures of the QC, also the special genesis QC
+    /// @TODO improving signaling by stating reaction to passed in QC:
+    ///     QC has older preferred round,
+    ///     signatures are incorrect,
+    ///     epoch is unexpected
+    ///     updating to new preferred round
+    /// @TODO update epoch with validator set
+    ///
+    /// A QC ending the epoch also rotates the consensus key to the one of this validator in the
+    /// next validator set.
+    fn update(&mut self, qc: &QuorumCert) -> Result<(), Error> {
+        self.update_preferred_round(qc);
+        self.update_consensus_key(qc)
+    }
+
+    /// Notify the safety rules about the new epoch start.
+    fn start_new_epoch(&mut self, qc: &QuorumCert) -> Result<(), Error> {
+        if qc.commit_info().epoch() > self.persistent_storage.epoch() {
+            self.persistent_storage.set_epoch(qc.commit_info().epoch());
+            self.persistent_storage.set_last_voted_round(0);
+            self.persistent_storage.set_preferred_round(0);
+        }
+        self.update_preferred_round(qc);
+        self.update_consensus_key(qc)
+    }
+
     /// Provides the internal state of SafetyRules for monitoring / debugging purposes. This does
     /// not include sensitive data like private keys.
-    pub fn consensus_state(&self) -> ConsensusState {
-        ConsensusState {
+    fn consensus_state(&self) -> Result<ConsensusState, Error> {
+        Ok(ConsensusState {
             epoch: self.persistent_storage.epoch(),
             last_voted_round: self.persistent_storage.last_voted_round(),
             preferred_round: self.persistent_storage.preferred_round(),
-        }
+        })
     }
 
     /// Attempts to vote for a given proposal following the voting rules.
     /// @TODO verify signature on vote proposal
     /// @TODO verify QC correctness
     /// @TODO verify epoch on vote proposal
-    pub fn construct_and_sign_vote<T: Payload>(
-        &mut self,
-        vote_proposal: &VoteProposal<T>,
-    ) -> Result<Vote, Error> {
+    fn construct_and_sign_vote(&mut self, vote_proposal: &VoteProposal<T>) -> Result<Vote, Error> {
         let proposed_block = vote_proposal.block();
 
         if proposed_block.round() <= self.persistent_storage.last_voted_round() {
@@ -233,21 +313,26 @@ impl SafetyRules {
                 error: format!("{}", e),
             })?;
 
+        let vote_data = VoteData::new(
+            proposed_block.gen_block_info(
+                new_tree.root_hash(),
+                new_tree.version(),
+                vote_proposal.next_validator_set().cloned(),
+            ),
+            proposed_block.quorum_cert().certified_block().clone(),
+        );
+        let mut ledger_info = self.construct_ledger_info(proposed_block);
+        ledger_info.set_consensus_data_hash(vote_data.hash());
+        let signature = self.sign(ledger_info.hash())?;
+
         self.persistent_storage
             .set_last_voted_round(proposed_block.round());
 
-        Ok(Vote::new(
-            VoteData::new(
-                proposed_block.gen_block_info(
-                    new_tree.root_hash(),
-                    new_tree.version(),
-                    vote_proposal.next_validator_set().cloned(),
-                ),
-                proposed_block.quorum_cert().certified_block().clone(),
-            ),
-            self.validator_signer.author(),
-            self.construct_ledger_info(proposed_block),
-            &self.validator_signer,
+        Ok(Vote::new_with_signature(
+            vote_data,
+            self.author,
+            ledger_info,
+            signature,
         ))
     }
 
@@ -256,10 +341,10 @@ impl SafetyRules {
     /// @TODO only sign blocks that are later than last_voted_round and match the current epoch
     /// @TODO verify QC correctness
     /// @TODO verify QC matches preferred round
-    pub fn sign_proposal<T: Payload>(&self, block_data: BlockData<T>) -> Result<Block<T>, Error> {
-        Ok(Block::new_proposal_from_block_data(
-            block_data,
-            &self.validator_signer,
+    fn sign_proposal(&self, block_data: BlockData<T>) -> Result<Block<T>, Error> {
+        let signature = self.sign(block_data.hash())?;
+        Ok(Block::new_proposal_from_block_data_and_signature(
+            block_data, signature,
         ))
     }
 
@@ -267,7 +352,43 @@ impl SafetyRules {
     /// timeout message. This returns the signature for that timeout message.
     /// @TODO only sign a timeout if it matches last_voted_round or last_voted_round + 1
     /// @TODO update last_voted_round
-    pub fn sign_timeout(&self, timeout: &Timeout) -> Result<Signature, Error> {
-        Ok(timeout.sign(&self.validator_signer))
+    fn sign_timeout(&self, timeout: &Timeout) -> Result<Signature, Error> {
+        self.sign(timeout.hash())
+    }
+
+    /// Signing again only the votes SafetyRules signed itself, the voting rules it applied to them
+    /// cover the signature that can be aggregated as well.
+    fn sign_vote_aggregatable(&self, vote: &Vote) -> Result<AggregatableSignature, Error> {
+        let signed_by_current_key = vote.author() == self.author
+            && self
+                .persistent_storage
+                .consensus_public_key()
+                .map_or(false, |public_key| {
+                    vote.signature()
+                        .verify(
+                            &ValidatorVerifier::new_single(self.author, public_key),
+                            self.author,
+                            vote.ledger_info().hash(),
+                        )
+                        .is_ok()
+                });
+        if !signed_by_current_key {
+            return Err(Error::ForeignVote {
+                vote: vote.to_string(),
+            });
+        }
+        self.persistent_storage
+            .bls_sign_message(&vote.ledger_info().hash())
+    }
+
+    /// @TODO only sign a timeout if it matches last_voted_round or last_voted_round + 1
+    fn sign_timeout_aggregatable(&self, timeout: &Timeout) -> Result<AggregatableSignature, Error> {
+        self.persistent_storage.bls_sign_message(&timeout.hash())
+    }
+
+    /// As the holder of the private key, SafetyRules also evaluates the VRF privately electing
+    /// proposers, keyed by the consensus key.
+    fn vrf_prove(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        self.persistent_storage.vrf_prove(input)
     }
 }
@@ -0,0 +1,123 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    persistent_storage::{InMemoryStorage, OnDiskStorage, PersistentStorage},
+    process::ProcessService,
+    remote_service::RemoteClient,
+    serializer::SerializerClient,
+    thread::spawn_thread,
+    Error, SafetyRules, TSafetyRules,
+};
+use consensus_types::common::{Author, Payload};
+use libra_config::config::{
+    ConsensusKeysSecret, SafetyRulesBackend, SafetyRulesConfig, SafetyRulesService,
+};
+use libra_crypto::ed25519::Ed25519PrivateKey;
+use std::{env, fmt::Display, fs};
+
+/// Creates the persistent storage of SafetyRules according to `backend`.
+pub fn storage_from_config(
+    backend: &SafetyRulesBackend,
+) -> Result<Box<dyn PersistentStorage>, Error> {
+    Ok(match backend {
+        SafetyRulesBackend::InMemoryStorage => InMemoryStorage::default_storage(),
+        SafetyRulesBackend::OnDiskStorage {
+            default,
+            path,
+            consensus_keys: Some(consensus_keys),
+        } => OnDiskStorage::storage_with_consensus_keys(
+            path.clone(),
+            *default,
+            consensus_keys.path.clone(),
+            &read_secret(&consensus_keys.secret)?,
+        )?,
+        SafetyRulesBackend::OnDiskStorage {
+            default,
+            path,
+            consensus_keys: None,
+        } => {
+            if *default {
+                OnDiskStorage::default_storage(path.clone())
+            } else {
+                OnDiskStorage::new_storage(path.clone())
+            }
+        }
+    })
+}
+
+fn read_secret(secret: &ConsensusKeysSecret) -> Result<Vec<u8>, Error> {
+    match secret {
+        ConsensusKeysSecret::Passphrase { env_var } => env::var(env_var)
+            .map(String::into_bytes)
+            .map_err(|e| secret_error(env_var, e)),
+        ConsensusKeysSecret::KeyFile { path } => {
+            fs::read(path).map_err(|e| secret_error(&path.display(), e))
+        }
+    }
+}
+
+fn secret_error(source: &dyn Display, error: impl Display) -> Error {
+    Error::KeyStoreError {
+        error: format!("Unable to read the secret from {}: {}", source, error),
+    }
+}
+
+/// Creates SafetyRules signing on behalf of `author` with the storage of `backend`. If given,
+/// `consensus_key` is added to the keys held by the storage.
+fn new_safety_rules(
+    backend: &SafetyRulesBackend,
+    author: Author,
+    consensus_key: Option<&Ed25519PrivateKey>,
+) -> Result<SafetyRules, Error> {
+    let mut storage = storage_from_config(backend)?;
+    if let Some(consensus_key) = consensus_key {
+        storage.add_consensus_key(consensus_key)?;
+    }
+    Ok(SafetyRules::new(storage, author))
+}
+
+/// Returns SafetyRules for consensus, running where `config.service` says. In local and thread
+/// modes, SafetyRules is created with the storage of `config.backend`, to which `consensus_key`
+/// is added if given; in process mode, both are up to the process serving at the configured
+/// socket.
+pub fn safety_rules_client<T: Payload>(
+    config: &SafetyRulesConfig,
+    author: Author,
+    consensus_key: Option<&Ed25519PrivateKey>,
+) -> Result<Box<dyn TSafetyRules<T> + Send + Sync>, Error> {
+    Ok(match &config.service {
+        SafetyRulesService::Local => {
+            Box::new(new_safety_rules(&config.backend, author, consensus_key)?)
+        }
+        SafetyRulesService::Thread => {
+            let safety_rules = new_safety_rules(&config.backend, author, consensus_key)?;
+            let client = spawn_thread::<T>(safety_rules)?;
+            Box::new(SerializerClient::new(Box::new(client)))
+        }
+        SafetyRulesService::Process { socket_path } => {
+            let client = RemoteClient::connect(socket_path)?;
+            Box::new(SerializerClient::new(Box::new(client)))
+        }
+    })
+}
+
+/// Runs the process serving SafetyRules to consensus in process mode, at the socket configured in
+/// `config.service`. `consensus_key` is added to the keys held by the storage, if given.
+pub fn run_process_service<T: Payload>(
+    config: &SafetyRulesConfig,
+    author: Author,
+    consensus_key: Option<&Ed25519PrivateKey>,
+) -> Result<(), Error> {
+    let socket_path = match &config.service {
+        SafetyRulesService::Process { socket_path } => socket_path,
+        service => {
+            return Err(Error::InternalError {
+                error: format!("Not configured to run as a process: {:?}", service),
+            })
+        }
+    };
+    let safety_rules = new_safety_rules(&config.backend, author, consensus_key)?;
+    ProcessService::bind(socket_path)?.serve::<T>(safety_rules)?;
+    Ok(())
+}
@@ -1,27 +1,120 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{Error, InMemoryStorage, SafetyRules};
+use crate::{
+    spawn_thread, Error, InMemoryStorage, PersistentStorage, ProcessService, RemoteClient,
+    SafetyRules, SerializerClient, TSafetyRules, TSerializerClient,
+};
 use consensus_types::{
     accumulator_extension_proof::AccumulatorExtensionProof,
     block::block_test_utils::certificate_for_genesis, block::Block, common::Round,
     quorum_cert::QuorumCert, timeout::Timeout, vote::Vote, vote_data::VoteData,
     vote_proposal::VoteProposal,
 };
-use libra_crypto::hash::{CryptoHash, HashValue, TransactionAccumulatorHasher};
+use libra_crypto::{
+    ed25519::compat,
+    hash::{CryptoHash, HashValue, TransactionAccumulatorHasher},
+    traits::VerifyingKey,
+    vrf::ecvrf::{self, VRFPublicKey},
+};
 use libra_types::{
     block_info::BlockInfo,
-    crypto_proxies::ValidatorSigner,
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    validator_public_keys::ValidatorPublicKeys,
+    validator_set::ValidatorSet,
 };
-use std::sync::Arc;
 use std::{
     collections::BTreeMap,
+    convert::TryFrom,
+    thread,
     time::{SystemTime, UNIX_EPOCH},
 };
+use tempfile::TempDir;
 
 type Proof = AccumulatorExtensionProof<TransactionAccumulatorHasher>;
 
+/// Creates SafetyRules signing with the key of the given signer, in one of the modes it can run in.
+type SafetyRulesFactory = fn(&ValidatorSigner) -> Box<dyn TSafetyRules<Round>>;
+
+fn storage_with_key(validator_signer: &ValidatorSigner) -> Box<dyn PersistentStorage> {
+    let mut storage = InMemoryStorage::default_storage();
+    storage
+        .add_consensus_key(validator_signer.private_key())
+        .unwrap();
+    storage
+}
+
+fn new_safety_rules(validator_signer: &ValidatorSigner) -> SafetyRules {
+    SafetyRules::new(
+        storage_with_key(validator_signer),
+        validator_signer.author(),
+    )
+}
+
+fn local(validator_signer: &ValidatorSigner) -> Box<dyn TSafetyRules<Round>> {
+    Box::new(new_safety_rules(validator_signer))
+}
+
+fn in_thread(validator_signer: &ValidatorSigner) -> Box<dyn TSafetyRules<Round>> {
+    let safety_rules = new_safety_rules(validator_signer);
+    let client = spawn_thread::<Round>(safety_rules).unwrap();
+    Box::new(SerializerClient::new(Box::new(client)))
+}
+
+/// A [`RemoteClient`] holding the directory of the socket it's connected to, which is removed
+/// once the test drops the client.
+struct TempDirClient {
+    client: RemoteClient,
+    _dir: TempDir,
+}
+
+impl TSerializerClient for TempDirClient {
+    fn request(&self, input: Vec<u8>) -> Result<Vec<u8>, Error> {
+        self.client.request(input)
+    }
+}
+
+/// The service runs in a thread of the test process, but is reached through a socket path the
+/// same way as when it runs in its own process.
+fn in_process(validator_signer: &ValidatorSigner) -> Box<dyn TSafetyRules<Round>> {
+    let dir = tempfile::tempdir().unwrap();
+    let socket_path = dir.path().join("safety_rules.sock");
+    let service = ProcessService::bind(&socket_path).unwrap();
+    let safety_rules = new_safety_rules(validator_signer);
+    thread::spawn(move || service.serve::<Round>(safety_rules));
+    let client = RemoteClient::connect(&socket_path).unwrap();
+    Box::new(SerializerClient::new(Box::new(TempDirClient {
+        client,
+        _dir: dir,
+    })))
+}
+
+/// Runs each of the given tests, taking a [`SafetyRulesFactory`], against all modes.
+macro_rules! test_all_modes {
+    ($($test:ident),* $(,)?) => {
+        mod local_mode {
+            $(#[test] fn $test() { super::$test(super::local) })*
+        }
+        mod thread_mode {
+            $(#[test] fn $test() { super::$test(super::in_thread) })*
+        }
+        mod process_mode {
+            $(#[test] fn $test() { super::$test(super::in_process) })*
+        }
+    };
+}
+
+test_all_modes!(
+    test_initial_state,
+    test_preferred_block_rule,
+    test_voting_potential_commit_id,
+    test_voting,
+    test_bad_execution_output,
+    test_vrf_prove,
+    test_sign_aggregatable,
+);
+
 fn empty_proof() -> Proof {
     Proof::new(vec![], 0, vec![])
 }
@@ -131,28 +224,20 @@ fn make_proposal_with_parent(
     make_proposal_with_qc_and_proof(round, proof, qc, validator_signer)
 }
 
-#[test]
-fn test_initial_state() {
+fn test_initial_state(make_safety_rules: SafetyRulesFactory) {
     // Start from scratch, verify the state
     let block = Block::<Round>::make_genesis_block();
 
-    let safety_rules = SafetyRules::new(
-        InMemoryStorage::default_storage(),
-        Arc::new(ValidatorSigner::from_int(0)),
-    );
-    let state = safety_rules.consensus_state();
+    let safety_rules = make_safety_rules(&ValidatorSigner::from_int(0));
+    let state = safety_rules.consensus_state().unwrap();
     assert_eq!(state.last_voted_round(), block.round());
     assert_eq!(state.preferred_round(), block.round());
 }
 
-#[test]
-fn test_preferred_block_rule() {
+fn test_preferred_block_rule(make_safety_rules: SafetyRulesFactory) {
     // Preferred block is the highest 2-chain head.
     let validator_signer = ValidatorSigner::from_int(0);
-    let mut safety_rules = SafetyRules::new(
-        InMemoryStorage::default_storage(),
-        Arc::new(validator_signer.clone()),
-    );
+    let mut safety_rules = make_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //             _____    _____
@@ -173,57 +258,53 @@ fn test_preferred_block_rule() {
     let a3 = make_proposal_with_parent(round + 6, &a2, None, &validator_signer);
     let a4 = make_proposal_with_parent(round + 7, &a3, None, &validator_signer);
 
-    safety_rules.update(a1.block().quorum_cert());
+    safety_rules.update(a1.block().quorum_cert()).unwrap();
     assert_eq!(
-        safety_rules.consensus_state().preferred_round(),
+        safety_rules.consensus_state().unwrap().preferred_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(b1.block().quorum_cert());
+    safety_rules.update(b1.block().quorum_cert()).unwrap();
     assert_eq!(
-        safety_rules.consensus_state().preferred_round(),
+        safety_rules.consensus_state().unwrap().preferred_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(a2.block().quorum_cert());
+    safety_rules.update(a2.block().quorum_cert()).unwrap();
     assert_eq!(
-        safety_rules.consensus_state().preferred_round(),
+        safety_rules.consensus_state().unwrap().preferred_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(b2.block().quorum_cert());
+    safety_rules.update(b2.block().quorum_cert()).unwrap();
     assert_eq!(
-        safety_rules.consensus_state().preferred_round(),
+        safety_rules.consensus_state().unwrap().preferred_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(a3.block().quorum_cert());
+    safety_rules.update(a3.block().quorum_cert()).unwrap();
     assert_eq!(
-        safety_rules.consensus_state().preferred_round(),
+        safety_rules.consensus_state().unwrap().preferred_round(),
         b1.block().round()
     );
 
-    safety_rules.update(b3.block().quorum_cert());
+    safety_rules.update(b3.block().quorum_cert()).unwrap();
     assert_eq!(
-        safety_rules.consensus_state().preferred_round(),
+        safety_rules.consensus_state().unwrap().preferred_round(),
         b1.block().round()
     );
 
-    safety_rules.update(a4.block().quorum_cert());
+    safety_rules.update(a4.block().quorum_cert()).unwrap();
     assert_eq!(
-        safety_rules.consensus_state().preferred_round(),
+        safety_rules.consensus_state().unwrap().preferred_round(),
         a2.block().round()
     );
 }
 
-#[test]
 /// Test the potential ledger info that we're going to use in case of voting
-fn test_voting_potential_commit_id() {
+fn test_voting_potential_commit_id(make_safety_rules: SafetyRulesFactory) {
     let validator_signer = ValidatorSigner::from_int(0);
-    let mut safety_rules = SafetyRules::new(
-        InMemoryStorage::default_storage(),
-        Arc::new(validator_signer.clone()),
-    );
+    let mut safety_rules = make_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //            _____
@@ -245,12 +326,12 @@ fn test_voting_potential_commit_id() {
     let a5 = make_proposal_with_parent(round + 6, &a4, Some(&a3), &validator_signer);
 
     for b in &[&a1, &b1, &a2, &a3] {
-        safety_rules.update(b.block().quorum_cert());
+        safety_rules.update(b.block().quorum_cert()).unwrap();
         let vote = safety_rules.construct_and_sign_vote(b).unwrap();
         assert_eq!(vote.ledger_info().consensus_block_id(), HashValue::zero());
     }
 
-    safety_rules.update(a4.block().quorum_cert());
+    safety_rules.update(a4.block().quorum_cert()).unwrap();
     assert_eq!(
         safety_rules
             .construct_and_sign_vote(&a4)
@@ -260,7 +341,7 @@ fn test_voting_potential_commit_id() {
         a2.block().id(),
     );
 
-    safety_rules.update(a5.block().quorum_cert());
+    safety_rules.update(a5.block().quorum_cert()).unwrap();
     assert_eq!(
         safety_rules
             .construct_and_sign_vote(&a5)
@@ -271,13 +352,9 @@ fn test_voting_potential_commit_id() {
     );
 }
 
-#[test]
-fn test_voting() {
+fn test_voting(make_safety_rules: SafetyRulesFactory) {
     let validator_signer = ValidatorSigner::from_int(0);
-    let mut safety_rules = SafetyRules::new(
-        InMemoryStorage::default_storage(),
-        Arc::new(validator_signer.clone()),
-    );
+    let mut safety_rules = make_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //             _____    __________
@@ -310,19 +387,19 @@ fn test_voting() {
     let a4 = make_proposal_with_parent(round + 7, &a3, None, &validator_signer);
     let b4 = make_proposal_with_parent(round + 8, &b2, None, &validator_signer);
 
-    safety_rules.update(a1.block().quorum_cert());
+    safety_rules.update(a1.block().quorum_cert()).unwrap();
     let mut vote = safety_rules.construct_and_sign_vote(&a1).unwrap();
     assert_eq!(vote.ledger_info().consensus_block_id(), HashValue::zero());
 
-    safety_rules.update(b1.block().quorum_cert());
+    safety_rules.update(b1.block().quorum_cert()).unwrap();
     vote = safety_rules.construct_and_sign_vote(&b1).unwrap();
     assert_eq!(vote.ledger_info().consensus_block_id(), HashValue::zero());
 
-    safety_rules.update(a2.block().quorum_cert());
+    safety_rules.update(a2.block().quorum_cert()).unwrap();
     vote = safety_rules.construct_and_sign_vote(&a2).unwrap();
     assert_eq!(vote.ledger_info().consensus_block_id(), HashValue::zero());
 
-    safety_rules.update(b2.block().quorum_cert());
+    safety_rules.update(b2.block().quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.construct_and_sign_vote(&b2),
         Err(Error::OldProposal {
@@ -331,19 +408,19 @@ fn test_voting() {
         })
     );
 
-    safety_rules.update(a3.block().quorum_cert());
+    safety_rules.update(a3.block().quorum_cert()).unwrap();
     vote = safety_rules.construct_and_sign_vote(&a3).unwrap();
     assert_eq!(vote.ledger_info().consensus_block_id(), HashValue::zero());
 
-    safety_rules.update(b3.block().quorum_cert());
+    safety_rules.update(b3.block().quorum_cert()).unwrap();
     vote = safety_rules.construct_and_sign_vote(&b3).unwrap();
     assert_eq!(vote.ledger_info().consensus_block_id(), HashValue::zero());
 
-    safety_rules.update(a4.block().quorum_cert());
+    safety_rules.update(a4.block().quorum_cert()).unwrap();
     vote = safety_rules.construct_and_sign_vote(&a4).unwrap();
     assert_eq!(vote.ledger_info().consensus_block_id(), HashValue::zero());
 
-    safety_rules.update(a4.block().quorum_cert());
+    safety_rules.update(a4.block().quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.construct_and_sign_vote(&a4),
         Err(Error::OldProposal {
@@ -351,7 +428,7 @@ fn test_voting() {
             proposal_round: 7,
         })
     );
-    safety_rules.update(b4.block().quorum_cert());
+    safety_rules.update(b4.block().quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.construct_and_sign_vote(&b4),
         Err(Error::ProposalRoundLowerThenPreferredBlock { preferred_round: 4 })
@@ -361,10 +438,7 @@ fn test_voting() {
 #[test]
 fn test_commit_rule_consecutive_rounds() {
     let validator_signer = ValidatorSigner::from_int(0);
-    let safety_rules = SafetyRules::new(
-        InMemoryStorage::default_storage(),
-        Arc::new(validator_signer.clone()),
-    );
+    let safety_rules = new_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //             ___________
@@ -423,11 +497,9 @@ fn test_commit_rule_consecutive_rounds() {
     );
 }
 
-#[test]
-fn test_bad_execution_output() {
-    let validator_signer = Arc::new(ValidatorSigner::from_int(0));
-    let mut safety_rules =
-        SafetyRules::new(InMemoryStorage::default_storage(), validator_signer.clone());
+fn test_bad_execution_output(make_safety_rules: SafetyRulesFactory) {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let mut safety_rules = make_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //                 _____
@@ -473,3 +545,107 @@ fn test_bad_execution_output() {
     let a3_block = safety_rules.construct_and_sign_vote(&a3);
     assert!(a3_block.is_ok());
 }
+
+fn test_vrf_prove(make_safety_rules: SafetyRulesFactory) {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let safety_rules = make_safety_rules(&validator_signer);
+    let public_key = VRFPublicKey::try_from(&validator_signer.public_key().to_bytes()[..]).unwrap();
+
+    let proof = safety_rules.vrf_prove(b"round 1").unwrap();
+    let proof = ecvrf::Proof::try_from(&proof[..]).unwrap();
+    assert!(public_key.verify(&proof, b"round 1").is_ok());
+    assert!(public_key.verify(&proof, b"round 2").is_err());
+}
+
+fn test_sign_aggregatable(make_safety_rules: SafetyRulesFactory) {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let mut safety_rules = make_safety_rules(&validator_signer);
+    let bls_public_key = validator_signer.bls_public_key();
+
+    let genesis_qc = certificate_for_genesis();
+    let a1 = make_proposal_with_qc(1, genesis_qc, &validator_signer);
+    let vote = safety_rules.construct_and_sign_vote(&a1).unwrap();
+    let signature = safety_rules.sign_vote_aggregatable(&vote).unwrap();
+    assert!(bls_public_key
+        .verify_signature(&vote.ledger_info().hash(), &signature)
+        .is_ok());
+
+    // A vote SafetyRules didn't sign doesn't get a signature that can be aggregated.
+    let other_signer = ValidatorSigner::from_int(1);
+    let other_vote = Vote::new(
+        vote.vote_data().clone(),
+        validator_signer.author(),
+        vote.ledger_info().clone(),
+        &other_signer,
+    );
+    assert_eq!(
+        safety_rules.sign_vote_aggregatable(&other_vote),
+        Err(Error::ForeignVote {
+            vote: other_vote.to_string()
+        })
+    );
+
+    let timeout = Timeout::new(1, 1);
+    let signature = safety_rules.sign_timeout_aggregatable(&timeout).unwrap();
+    assert!(bls_public_key
+        .verify_signature(&timeout.hash(), &signature)
+        .is_ok());
+}
+
+#[test]
+fn test_consensus_key_rotation() {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let author = validator_signer.author();
+    let (next_private_key, next_public_key) = compat::generate_genesis_keypair();
+    let mut storage = storage_with_key(&validator_signer);
+    storage.add_consensus_key(&next_private_key).unwrap();
+    let mut safety_rules = SafetyRules::new(storage, author);
+
+    let timeout = Timeout::new(1, 1);
+    let verifier = ValidatorVerifier::new_single(author, validator_signer.public_key());
+    let next_verifier = ValidatorVerifier::new_single(author, next_public_key.clone());
+    let signature = TSafetyRules::<Round>::sign_timeout(&safety_rules, &timeout).unwrap();
+    assert!(signature.verify(&verifier, author, timeout.hash()).is_ok());
+
+    // A QC that doesn't end the epoch keeps the current key.
+    let genesis_qc = certificate_for_genesis();
+    TSafetyRules::<Round>::update(&mut safety_rules, &genesis_qc).unwrap();
+    let signature = TSafetyRules::<Round>::sign_timeout(&safety_rules, &timeout).unwrap();
+    assert!(signature.verify(&verifier, author, timeout.hash()).is_ok());
+
+    // Once a QC ends the epoch, the key of the next validator set signs.
+    let next_validator_set =
+        ValidatorSet::new(vec![ValidatorPublicKeys::new_with_random_network_keys(
+            author,
+            next_public_key,
+            1,
+        )]);
+    let commit_info = BlockInfo::new(
+        1,
+        0,
+        HashValue::zero(),
+        HashValue::zero(),
+        0,
+        0,
+        Some(next_validator_set),
+    );
+    let epoch_ending_qc = QuorumCert::new(
+        genesis_qc.vote_data().clone(),
+        LedgerInfoWithSignatures::new(
+            LedgerInfo::new(commit_info, genesis_qc.vote_data().hash()),
+            BTreeMap::new(),
+        ),
+    );
+    TSafetyRules::<Round>::update(&mut safety_rules, &epoch_ending_qc).unwrap();
+    let signature = TSafetyRules::<Round>::sign_timeout(&safety_rules, &timeout).unwrap();
+    assert!(signature
+        .verify(&next_verifier, author, timeout.hash())
+        .is_ok());
+    assert!(signature.verify(&verifier, author, timeout.hash()).is_err());
+
+    // Without the key of the next validator set, the current key keeps signing.
+    let mut safety_rules = new_safety_rules(&validator_signer);
+    TSafetyRules::<Round>::update(&mut safety_rules, &epoch_ending_qc).unwrap();
+    let signature = TSafetyRules::<Round>::sign_timeout(&safety_rules, &timeout).unwrap();
+    assert!(signature.verify(&verifier, author, timeout.hash()).is_ok());
+}
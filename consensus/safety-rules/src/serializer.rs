@@ -0,0 +1,160 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{ConsensusState, Error, SafetyRules, TSafetyRules};
+use consensus_types::{
+    block::Block, block_data::BlockData, common::Payload, quorum_cert::QuorumCert,
+    timeout::Timeout, vote::Vote, vote_proposal::VoteProposal,
+};
+use libra_types::crypto_proxies::{AggregatableSignature, Signature};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// A request to SafetyRules, one per method of [`TSafetyRules`]. Requests and responses are
+/// LCS-encoded; a response is the LCS encoding of the `Result` returned by the method.
+#[derive(Deserialize, Serialize)]
+pub enum SafetyRulesInput<T> {
+    ConsensusState,
+    Update(Box<QuorumCert>),
+    StartNewEpoch(Box<QuorumCert>),
+    #[serde(bound(deserialize = "VoteProposal<T>: Deserialize<'de>"))]
+    ConstructAndSignVote(Box<VoteProposal<T>>),
+    #[serde(bound(deserialize = "BlockData<T>: Deserialize<'de>"))]
+    SignProposal(Box<BlockData<T>>),
+    SignTimeout(Box<Timeout>),
+    SignVoteAggregatable(Box<Vote>),
+    SignTimeoutAggregatable(Box<Timeout>),
+    VrfProve(Vec<u8>),
+}
+
+/// Serves encoded requests with the SafetyRules it owns.
+pub struct SerializerService {
+    internal: SafetyRules,
+}
+
+impl SerializerService {
+    pub fn new(internal: SafetyRules) -> Self {
+        Self { internal }
+    }
+
+    /// Handles an encoded [`SafetyRulesInput`] and returns the encoded response. A request that
+    /// can't be decoded gets an error response.
+    pub fn handle_message<T: Payload>(&mut self, input_message: &[u8]) -> Vec<u8> {
+        self.process::<T>(input_message).unwrap_or_else(|e| {
+            // An error is encoded the same way whatever the type of the result.
+            lcs::to_bytes(&Err::<(), Error>(e)).expect("Errors are serializable.")
+        })
+    }
+
+    fn process<T: Payload>(&mut self, input_message: &[u8]) -> Result<Vec<u8>, Error> {
+        let input: SafetyRulesInput<T> = lcs::from_bytes(input_message)?;
+        let internal = &mut self.internal;
+        let output = match input {
+            SafetyRulesInput::ConsensusState => {
+                lcs::to_bytes(&TSafetyRules::<T>::consensus_state(internal))
+            }
+            SafetyRulesInput::Update(qc) => {
+                lcs::to_bytes(&TSafetyRules::<T>::update(internal, &qc))
+            }
+            SafetyRulesInput::StartNewEpoch(qc) => {
+                lcs::to_bytes(&TSafetyRules::<T>::start_new_epoch(internal, &qc))
+            }
+            SafetyRulesInput::ConstructAndSignVote(vote_proposal) => lcs::to_bytes(
+                &TSafetyRules::<T>::construct_and_sign_vote(internal, &vote_proposal),
+            ),
+            SafetyRulesInput::SignProposal(block_data) => {
+                lcs::to_bytes(&TSafetyRules::<T>::sign_proposal(internal, *block_data))
+            }
+            SafetyRulesInput::SignTimeout(timeout) => {
+                lcs::to_bytes(&TSafetyRules::<T>::sign_timeout(internal, &timeout))
+            }
+            SafetyRulesInput::SignVoteAggregatable(vote) => {
+                lcs::to_bytes(&TSafetyRules::<T>::sign_vote_aggregatable(internal, &vote))
+            }
+            SafetyRulesInput::SignTimeoutAggregatable(timeout) => lcs::to_bytes(
+                &TSafetyRules::<T>::sign_timeout_aggregatable(internal, &timeout),
+            ),
+            SafetyRulesInput::VrfProve(input) => {
+                lcs::to_bytes(&TSafetyRules::<T>::vrf_prove(internal, &input))
+            }
+        };
+        Ok(output?)
+    }
+}
+
+/// Transport carrying encoded requests to a [`SerializerService`] and its responses back.
+pub trait TSerializerClient: Send + Sync {
+    fn request(&self, input: Vec<u8>) -> Result<Vec<u8>, Error>;
+}
+
+/// Implements [`TSafetyRules`] by sending requests to a [`SerializerService`] over `service`.
+pub struct SerializerClient<T> {
+    service: Box<dyn TSerializerClient>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Payload> SerializerClient<T> {
+    pub fn new(service: Box<dyn TSerializerClient>) -> Self {
+        Self {
+            service,
+            marker: PhantomData,
+        }
+    }
+
+    fn request(&self, input: SafetyRulesInput<T>) -> Result<Vec<u8>, Error> {
+        self.service.request(lcs::to_bytes(&input)?)
+    }
+}
+
+impl<T: Payload> TSafetyRules<T> for SerializerClient<T> {
+    fn consensus_state(&self) -> Result<ConsensusState, Error> {
+        let response = self.request(SafetyRulesInput::ConsensusState)?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> Result<(), Error> {
+        let response = self.request(SafetyRulesInput::Update(Box::new(qc.clone())))?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn start_new_epoch(&mut self, qc: &QuorumCert) -> Result<(), Error> {
+        let response = self.request(SafetyRulesInput::StartNewEpoch(Box::new(qc.clone())))?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn construct_and_sign_vote(&mut self, vote_proposal: &VoteProposal<T>) -> Result<Vote, Error> {
+        let response = self.request(SafetyRulesInput::ConstructAndSignVote(Box::new(
+            vote_proposal.clone(),
+        )))?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn sign_proposal(&self, block_data: BlockData<T>) -> Result<Block<T>, Error> {
+        let response = self.request(SafetyRulesInput::SignProposal(Box::new(block_data)))?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn sign_timeout(&self, timeout: &Timeout) -> Result<Signature, Error> {
+        let response = self.request(SafetyRulesInput::SignTimeout(Box::new(timeout.clone())))?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn sign_vote_aggregatable(&self, vote: &Vote) -> Result<AggregatableSignature, Error> {
+        let response = self.request(SafetyRulesInput::SignVoteAggregatable(Box::new(
+            vote.clone(),
+        )))?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn sign_timeout_aggregatable(&self, timeout: &Timeout) -> Result<AggregatableSignature, Error> {
+        let response = self.request(SafetyRulesInput::SignTimeoutAggregatable(Box::new(
+            timeout.clone(),
+        )))?;
+        lcs::from_bytes(&response)?
+    }
+
+    fn vrf_prove(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let response = self.request(SafetyRulesInput::VrfProve(input.to_vec()))?;
+        lcs::from_bytes(&response)?
+    }
+}
@@ -0,0 +1,26 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    remote_service::{self, RemoteClient},
+    serializer::SerializerService,
+    SafetyRules,
+};
+use consensus_types::common::Payload;
+use libra_logger::prelude::*;
+use std::{io, os::unix::net::UnixStream, sync::Mutex, thread};
+
+/// Moves `safety_rules` to a dedicated thread, serving requests over a socket pair. Returns the
+/// client end of the socket; the thread exits once it's dropped.
+pub fn spawn_thread<T: Payload>(safety_rules: SafetyRules) -> io::Result<RemoteClient> {
+    let (client_end, server_end) = UnixStream::pair()?;
+    thread::Builder::new()
+        .name("safety-rules".to_string())
+        .spawn(move || {
+            let service = Mutex::new(SerializerService::new(safety_rules));
+            if let Err(e) = remote_service::serve::<T>(server_end, &service) {
+                error!("SafetyRules thread stopped serving: {}", e);
+            }
+        })?;
+    Ok(RemoteClient::new(client_end))
+}
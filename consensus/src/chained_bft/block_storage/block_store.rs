@@ -10,8 +10,12 @@ use crate::{
     state_replication::StateComputer,
 };
 use consensus_types::{
-    block::Block, common::Payload, executed_block::ExecutedBlock, quorum_cert::QuorumCert,
-    timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block,
+    common::{Payload, Round},
+    executed_block::ExecutedBlock,
+    quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate,
+    vote::Vote,
 };
 use executor::ProcessedVMOutput;
 use failure::ResultExt;
@@ -21,6 +25,7 @@ use libra_logger::prelude::*;
 use libra_types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier};
 #[cfg(any(test, feature = "fuzzing"))]
 use libra_types::validator_set::ValidatorSet;
+use serde::Serialize;
 use std::{
     collections::{vec_deque::VecDeque, HashMap},
     sync::{Arc, RwLock},
@@ -34,6 +39,27 @@ mod block_store_test;
 #[path = "sync_manager.rs"]
 pub mod sync_manager;
 
+/// The outline of a block tree rebuilt by [`BlockStore::replay`].
+#[derive(Debug, Serialize)]
+pub struct BlockTreeSummary {
+    /// Id of the root, i.e. of the last committed block
+    pub root_id: HashValue,
+    /// Round of the root
+    pub root_round: Round,
+    /// Number of blocks recovered, besides the root
+    pub num_blocks: usize,
+    /// Number of quorum certificates recovered, besides the one of the root
+    pub num_quorum_certs: usize,
+    /// Id of the block certified by the highest quorum certificate
+    pub highest_certified_block_id: HashValue,
+    /// Round of the block certified by the highest quorum certificate
+    pub highest_certified_round: Round,
+    /// Round of the block committed by the highest ledger info
+    pub highest_committed_round: Round,
+    /// Round of the highest timeout certificate, if any
+    pub highest_timeout_round: Option<Round>,
+}
+
 /// Responsible for maintaining all the blocks of payload and the dependencies of those blocks
 /// (parent and previous QC links).  It is expected to be accessed concurrently by multiple threads
 /// and is thread-safe.
@@ -75,6 +101,7 @@ impl<T: Payload> BlockStore<T> {
                 highest_tc,
                 Arc::clone(&state_computer),
                 max_pruned_blocks_in_mem,
+                true,
             )
             .await,
         ));
@@ -85,6 +112,43 @@ impl<T: Payload> BlockStore<T> {
         }
     }
 
+    /// Rebuilds the block tree out of `initial_data` the way `new` does on startup, executing the
+    /// blocks through `state_computer`, then checks the invariants of the tree. The executed
+    /// states aren't checked against the quorum certificates, so that the recovery can be
+    /// replayed offline through a mock `StateComputer`.
+    pub async fn replay(
+        initial_data: RecoveryData<T>,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+    ) -> failure::Result<BlockTreeSummary> {
+        let highest_tc = initial_data.highest_timeout_certificate();
+        let (root, blocks, quorum_certs) = initial_data.take();
+        let num_blocks = blocks.len();
+        let num_quorum_certs = quorum_certs.len();
+        let tree = Self::build_block_tree(
+            root,
+            blocks,
+            quorum_certs,
+            highest_tc,
+            state_computer,
+            0,
+            false,
+        )
+        .await;
+        tree.verify_invariants()?;
+        let root = tree.root();
+        let highest_certified_block = tree.highest_certified_block();
+        Ok(BlockTreeSummary {
+            root_id: root.id(),
+            root_round: root.round(),
+            num_blocks,
+            num_quorum_certs,
+            highest_certified_block_id: highest_certified_block.id(),
+            highest_certified_round: highest_certified_block.round(),
+            highest_committed_round: tree.highest_ledger_info().commit_info().round(),
+            highest_timeout_round: tree.highest_timeout_cert().map(|tc| tc.round()),
+        })
+    }
+
     async fn build_block_tree(
         root: (Block<T>, QuorumCert, QuorumCert),
         blocks: Vec<Block<T>>,
@@ -92,22 +156,25 @@ impl<T: Payload> BlockStore<T> {
         highest_timeout_cert: Option<TimeoutCertificate>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         max_pruned_blocks_in_mem: usize,
+        verify_executed_state: bool,
     ) -> BlockTree<T> {
         let (root_block, root_qc, root_li) = (root.0, root.1, root.2);
-        assert_eq!(
-            root_qc.certified_block().version(),
-            state_computer.committed_trees().version().unwrap_or(0),
-            "root qc version {} doesn't match committed trees {}",
-            root_qc.certified_block().version(),
-            state_computer.committed_trees().version().unwrap_or(0),
-        );
-        assert_eq!(
-            root_qc.certified_block().executed_state_id(),
-            state_computer.committed_trees().state_id(),
-            "root qc state id {} doesn't match committed trees {}",
-            root_qc.certified_block().executed_state_id(),
-            state_computer.committed_trees().state_id(),
-        );
+        if verify_executed_state {
+            assert_eq!(
+                root_qc.certified_block().version(),
+                state_computer.committed_trees().version().unwrap_or(0),
+                "root qc version {} doesn't match committed trees {}",
+                root_qc.certified_block().version(),
+                state_computer.committed_trees().version().unwrap_or(0),
+            );
+            assert_eq!(
+                root_qc.certified_block().executed_state_id(),
+                state_computer.committed_trees().state_id(),
+                "root qc state id {} doesn't match committed trees {}",
+                root_qc.certified_block().executed_state_id(),
+                state_computer.committed_trees().state_id(),
+            );
+        }
         let root_output = ProcessedVMOutput::new(
             vec![],
             state_computer.committed_trees(),
@@ -137,7 +204,7 @@ impl<T: Payload> BlockStore<T> {
                 .await
                 .expect("fail to rebuild scratchpad");
             // if this block is certified, ensure we agree with the certified state.
-            if let Some(qc) = quorum_certs.get(&block.id()) {
+            if let (true, Some(qc)) = (verify_executed_state, quorum_certs.get(&block.id())) {
                 assert_eq!(
                     qc.certified_block().executed_state_id(),
                     output.accu_root(),
@@ -209,6 +276,7 @@ impl<T: Payload> BlockStore<T> {
             prev_htc,
             Arc::clone(&self.state_computer),
             max_pruned_blocks_in_mem,
+            true,
         )
         .await;
         let to_remove = self.inner.read().unwrap().get_all_block_id();
@@ -3,8 +3,11 @@
 
 use crate::chained_bft::test_utils::build_simple_tree;
 use crate::chained_bft::{
-    block_storage::{block_store::sync_manager::NeedFetchResult, BlockReader, VoteReceptionResult},
-    test_utils::{build_empty_tree, TreeInserter},
+    block_storage::{
+        block_store::sync_manager::{verify_chain, NeedFetchResult},
+        BlockReader, BlockStore, VoteReceptionResult,
+    },
+    test_utils::{build_empty_tree, EmptyStateComputer, MockStorage, TestPayload, TreeInserter},
 };
 use consensus_types::{
     block::{
@@ -20,10 +23,10 @@ use consensus_types::{
 };
 use futures::executor::block_on;
 use libra_crypto::{HashValue, PrivateKey};
-use libra_types::crypto_proxies::random_validator_verifier;
+use libra_types::crypto_proxies::{random_validator_verifier, ValidatorVerifier};
 use libra_types::{account_address::AccountAddress, crypto_proxies::ValidatorSigner};
 use proptest::prelude::*;
-use std::{cmp::min, collections::HashSet};
+use std::{cmp::min, collections::HashSet, sync::Arc};
 
 #[test]
 fn test_highest_block_and_quorum_cert() {
@@ -428,3 +431,61 @@ fn test_empty_reconfiguration_suffix() {
     // If reconfiguration is committed, the child block can carry payload
     let _a6 = inserter.insert_block(&a3, 4, None);
 }
+
+#[test]
+fn test_replay() {
+    let signer = ValidatorSigner::random(None);
+    let validators = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let (initial_data, storage) = MockStorage::<TestPayload>::start_for_testing(validators);
+    let block_store = Arc::new(block_on(BlockStore::new(
+        storage.clone(),
+        initial_data,
+        Arc::new(EmptyStateComputer),
+        10,
+    )));
+    let mut inserter = TreeInserter::new_with_store(signer, block_store.clone());
+    let genesis = block_store.root();
+    let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2, None);
+    inserter.insert_block(&a2, 3, None);
+    inserter.insert_block(&a1, 4, None);
+
+    // The replayed tree matches the tree of the block store.
+    let summary = block_on(BlockStore::replay(
+        storage.try_start().unwrap(),
+        Arc::new(EmptyStateComputer),
+    ))
+    .unwrap();
+    assert_eq!(summary.root_id, genesis.id());
+    assert_eq!(summary.num_blocks, 4);
+    assert_eq!(summary.highest_certified_block_id, a2.id());
+    assert_eq!(summary.highest_certified_round, 2);
+    assert_eq!(
+        summary.highest_certified_block_id,
+        block_store.highest_certified_block().id()
+    );
+    assert_eq!(summary.highest_timeout_round, None);
+}
+
+#[test]
+fn test_verify_chain() {
+    let mut inserter = TreeInserter::default();
+    let block_store = inserter.block_store();
+    let genesis = block_store.root();
+    let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2, None);
+    let a3 = inserter.insert_block(&a2, 3, None);
+    let b2 = inserter.insert_block(&a1, 4, None);
+    let qc = inserter.create_qc_for_block(a3.as_ref(), None);
+    let chain: Vec<_> = vec![&a3, &a2, &a1]
+        .into_iter()
+        .map(|block| block.block().clone())
+        .collect();
+
+    assert!(verify_chain(&qc, &chain).is_ok());
+    assert!(verify_chain(&qc, &chain[..1]).is_ok());
+    // The chain starts from the certified block and follows the certificates of the blocks.
+    assert!(verify_chain::<TestPayload>(&qc, &[]).is_err());
+    assert!(verify_chain(&qc, &chain[1..]).is_err());
+    assert!(verify_chain(&qc, &[chain[0].clone(), b2.block().clone()]).is_err());
+}
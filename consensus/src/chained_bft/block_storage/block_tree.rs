@@ -390,6 +390,88 @@ where
     pub(super) fn get_all_block_id(&self) -> Vec<HashValue> {
         self.id_to_block.keys().cloned().collect()
     }
+
+    /// Checks the invariants of the tree: the parent and child links match, every block descends
+    /// from the root through increasing rounds, every quorum certificate certifies a block of the
+    /// tree, and the highest certificates are the ones of the highest rounds.
+    pub(super) fn verify_invariants(&self) -> failure::Result<()> {
+        let root_round = self.root().round();
+        for (id, linkable_block) in &self.id_to_block {
+            let block = linkable_block.executed_block();
+            for child_id in linkable_block.children() {
+                let child = self
+                    .get_block(child_id)
+                    .ok_or_else(|| format_err!("Child {} of block {} not found", child_id, id))?;
+                ensure!(
+                    child.parent_id() == *id,
+                    "Block {} is a child of {} but its parent is {}",
+                    child_id,
+                    id,
+                    child.parent_id()
+                );
+                ensure!(
+                    child.round() > block.round(),
+                    "Block {} has a round lower than its parent {}",
+                    child_id,
+                    id
+                );
+            }
+            if *id == self.root_id || self.pruned_block_ids.contains(id) {
+                continue;
+            }
+            ensure!(
+                block.round() > root_round,
+                "Block {} has a round lower than the root",
+                id
+            );
+            let parent = self.get_linkable_block(&block.parent_id()).ok_or_else(|| {
+                format_err!("Parent {} of block {} not found", block.parent_id(), id)
+            })?;
+            ensure!(
+                parent.children().contains(id),
+                "Block {} is missing from the children of its parent {}",
+                id,
+                block.parent_id()
+            );
+            ensure!(
+                self.path_from_root(*id).is_some(),
+                "Block {} doesn't descend from the root",
+                id
+            );
+        }
+        for (id, qc) in &self.id_to_quorum_cert {
+            let block = self
+                .get_block(id)
+                .ok_or_else(|| format_err!("Certified block {} not found", id))?;
+            ensure!(
+                qc.certified_block().id() == *id && qc.certified_block().round() == block.round(),
+                "Quorum certificate for block {} certifies {}",
+                id,
+                qc.certified_block()
+            );
+        }
+        let highest_certified_round = self
+            .id_to_quorum_cert
+            .values()
+            .map(|qc| qc.certified_block().round())
+            .max()
+            .unwrap_or(0);
+        ensure!(
+            self.highest_quorum_cert.certified_block().id() == self.highest_certified_block_id
+                && self.highest_certified_block().round() == highest_certified_round,
+            "Highest quorum certificate {} doesn't certify the highest certified block",
+            self.highest_quorum_cert
+        );
+        ensure!(
+            self.highest_ledger_info.commit_info().round() >= root_round
+                && self.highest_ledger_info.commit_info().round() <= highest_certified_round,
+            "Highest ledger info commits round {}, out of the root round {} and highest certified round {}",
+            self.highest_ledger_info.commit_info().round(),
+            root_round,
+            highest_certified_round
+        );
+        Ok(())
+    }
 }
 
 #[cfg(any(test, feature = "fuzzing"))]
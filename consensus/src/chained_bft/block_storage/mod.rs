@@ -11,7 +11,7 @@ mod block_store;
 mod block_tree;
 mod pending_votes;
 
-pub use block_store::{sync_manager::BlockRetriever, BlockStore};
+pub use block_store::{sync_manager::BlockRetriever, BlockStore, BlockTreeSummary};
 
 /// Result of the vote processing. The failure case (Verification error) is returned
 /// as the Error part of the result.
@@ -11,7 +11,8 @@ use consensus_types::{
 use libra_crypto::{hash::CryptoHash, HashValue};
 use libra_logger::prelude::*;
 use libra_types::{
-    crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier},
+    aggregate_signature::AggregateSignature,
+    crypto_proxies::{AggregatableSignature, LedgerInfoWithSignatures, ValidatorVerifier},
     validator_verifier::VerifyError,
 };
 use std::collections::BTreeMap;
@@ -37,12 +38,19 @@ pub struct PendingVotes {
     /// Thus, the structure of `li_digest_to_votes` is as follows:
     /// HashMap<ledger_info_digest, LedgerInfoWithSignatures>
     li_digest_to_votes: HashMap<HashValue, LedgerInfoWithSignatures>,
+    /// The signatures of the votes in `li_digest_to_votes` that can be aggregated into one.
+    /// The QuorumCertificate carries their aggregate in case every vote counted for it has one.
+    li_digest_to_aggregatable_signatures:
+        HashMap<HashValue, BTreeMap<Author, AggregatableSignature>>,
     /// Tracks all the signatures of the votes for the given round. In case we succeed to
     /// aggregate 2f+1 signatures for the same round a TimeoutCertificate is formed.
     /// Note that QuorumCert has higher priority than TimeoutCertificate (in case 2f+1 votes are
     /// gathered for the same ledger info we are going to generate QuorumCert and not the
     /// TimeoutCertificate).
     round_to_tc: HashMap<Round, TimeoutCertificate>,
+    /// The round signatures of the votes in `round_to_tc` that can be aggregated into one.
+    /// The TimeoutCertificate carries their aggregate in case every vote counted for it has one.
+    round_to_aggregatable_signatures: HashMap<Round, BTreeMap<Author, AggregatableSignature>>,
     /// Map of Author to last vote info. Any pending vote from Author is cleaned up
     /// whenever a new vote is added by same Author
     author_to_last_voted_info: HashMap<Author, LastVoteInfo>,
@@ -52,7 +60,9 @@ impl PendingVotes {
     pub fn new() -> Self {
         PendingVotes {
             li_digest_to_votes: HashMap::new(),
+            li_digest_to_aggregatable_signatures: HashMap::new(),
             round_to_tc: HashMap::new(),
+            round_to_aggregatable_signatures: HashMap::new(),
             author_to_last_voted_info: HashMap::new(),
         }
     }
@@ -96,12 +106,38 @@ impl PendingVotes {
         vote.signature()
             .clone()
             .add_to_li(vote.author(), li_with_sig);
+        let aggregatable_signatures = self
+            .li_digest_to_aggregatable_signatures
+            .entry(li_digest)
+            .or_insert_with(BTreeMap::new);
+        if let Some(signature) = vote.aggregatable_signature() {
+            aggregatable_signatures
+                .entry(vote.author())
+                .or_insert_with(|| signature.clone());
+        }
 
         match validator_verifier.check_voting_power(li_with_sig.signatures().keys()) {
-            Ok(_) => VoteReceptionResult::NewQuorumCertificate(Arc::new(QuorumCert::new(
-                vote.vote_data().clone(),
-                li_with_sig.clone(),
-            ))),
+            Ok(_) => {
+                // Every vote counted has a signature that can be aggregated: the QC carries
+                // their aggregate instead of the individual signatures.
+                let li_with_sig = match Self::aggregate_signatures(
+                    li_with_sig.signatures().len(),
+                    aggregatable_signatures,
+                    validator_verifier,
+                ) {
+                    Some(aggregated_signature) => {
+                        LedgerInfoWithSignatures::new_with_aggregated_signature(
+                            li_with_sig.ledger_info().clone(),
+                            aggregated_signature,
+                        )
+                    }
+                    None => li_with_sig.clone(),
+                };
+                VoteReceptionResult::NewQuorumCertificate(Arc::new(QuorumCert::new(
+                    vote.vote_data().clone(),
+                    li_with_sig,
+                )))
+            }
             Err(VerifyError::TooLittleVotingPower { voting_power, .. }) => {
                 VoteReceptionResult::VoteAdded(voting_power)
             }
@@ -118,20 +154,62 @@ impl PendingVotes {
     ) -> Option<VoteReceptionResult> {
         let timeout_signature = vote.timeout_signature().cloned()?;
         let timeout = vote.timeout();
+        let round = timeout.round();
         let tc = self
             .round_to_tc
-            .entry(timeout.round())
+            .entry(round)
             .or_insert_with(|| TimeoutCertificate::new(timeout, HashMap::new()));
         tc.add_signature(vote.author(), timeout_signature);
+        let aggregatable_signatures = self
+            .round_to_aggregatable_signatures
+            .entry(round)
+            .or_insert_with(BTreeMap::new);
+        if let Some(signature) = vote.aggregatable_timeout_signature() {
+            aggregatable_signatures
+                .entry(vote.author())
+                .or_insert_with(|| signature.clone());
+        }
         match validator_verifier.check_voting_power(tc.signatures().keys()) {
-            Ok(_) => Some(VoteReceptionResult::NewTimeoutCertificate(Arc::new(
-                tc.clone(),
-            ))),
+            Ok(_) => {
+                let tc = match Self::aggregate_signatures(
+                    tc.signatures().len(),
+                    aggregatable_signatures,
+                    validator_verifier,
+                ) {
+                    Some(aggregated_signature) => {
+                        TimeoutCertificate::new_with_aggregated_signature(
+                            vote.timeout(),
+                            aggregated_signature,
+                        )
+                    }
+                    None => tc.clone(),
+                };
+                Some(VoteReceptionResult::NewTimeoutCertificate(Arc::new(tc)))
+            }
             Err(VerifyError::TooLittleVotingPower { .. }) => None,
             _ => panic!("Unexpected verification error, vote = {}", vote),
         }
     }
 
+    /// Aggregates the signatures into one if there is one for each of the `num_votes` votes
+    /// counted, otherwise the certificate falls back to the individual signatures.
+    fn aggregate_signatures(
+        num_votes: usize,
+        signatures: &BTreeMap<Author, AggregatableSignature>,
+        validator_verifier: &ValidatorVerifier,
+    ) -> Option<AggregateSignature> {
+        if signatures.len() != num_votes {
+            return None;
+        }
+        match validator_verifier.aggregate_signatures(signatures) {
+            Ok(aggregated_signature) => Some(aggregated_signature),
+            Err(e) => {
+                warn!("Fail to aggregate the signatures of the votes: {:?}", e);
+                None
+            }
+        }
+    }
+
     /// If this is the first vote from Author, add it to map. If Author has
     /// already voted on same block then return DuplicateVote error. If Author has already voted
     /// on some other result, prune the last vote and insert new one in map.
@@ -175,6 +253,16 @@ impl PendingVotes {
                 self.li_digest_to_votes.remove(&last_voted_info.li_digest);
             }
         }
+        if let Some(signatures) = self
+            .li_digest_to_aggregatable_signatures
+            .get_mut(&last_voted_info.li_digest)
+        {
+            signatures.remove(&author);
+            if signatures.is_empty() {
+                self.li_digest_to_aggregatable_signatures
+                    .remove(&last_voted_info.li_digest);
+            }
+        }
 
         // Prune last pending vote from the pending timeout certificates.
         if round == last_voted_info.round {
@@ -195,6 +283,16 @@ impl PendingVotes {
                 self.round_to_tc.remove(&last_voted_info.round);
             }
         }
+        if let Some(signatures) = self
+            .round_to_aggregatable_signatures
+            .get_mut(&last_voted_info.round)
+        {
+            signatures.remove(&author);
+            if signatures.is_empty() {
+                self.round_to_aggregatable_signatures
+                    .remove(&last_voted_info.round);
+            }
+        }
 
         Ok(())
     }
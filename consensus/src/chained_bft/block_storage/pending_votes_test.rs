@@ -4,9 +4,9 @@
 use crate::chained_bft::block_storage::pending_votes::PendingVotes;
 use crate::chained_bft::block_storage::VoteReceptionResult;
 use consensus_types::{common::Round, vote::Vote, vote_data::VoteData};
-use libra_crypto::HashValue;
+use libra_crypto::{hash::CryptoHash, HashValue};
 use libra_types::block_info::BlockInfo;
-use libra_types::crypto_proxies::random_validator_verifier;
+use libra_types::crypto_proxies::{random_bls_validator_verifier, random_validator_verifier};
 use libra_types::ledger_info::LedgerInfo;
 
 fn random_ledger_info() -> LedgerInfo {
@@ -297,3 +297,111 @@ fn test_tc_aggregation_keep_last_only() {
         }
     };
 }
+
+#[test]
+/// Verify that the certificates carry an aggregate signature when every vote counted has a
+/// signature that can be aggregated
+fn test_aggregated_signatures() {
+    ::libra_logger::try_init_for_testing();
+
+    let (signers, validator) = random_bls_validator_verifier(4, Some(2), false);
+    let mut pending_votes = PendingVotes::new();
+
+    let li1 = random_ledger_info();
+    let vote_round_1 = random_vote_data(1);
+    let votes: Vec<_> = signers[..2]
+        .iter()
+        .map(|signer| {
+            let mut vote = Vote::new(vote_round_1.clone(), signer.author(), li1.clone(), signer);
+            vote.add_aggregatable_signature(signer.bls_sign_message(vote.ledger_info().hash()));
+            let timeout = vote.timeout();
+            vote.add_timeout_signature(timeout.sign(signer));
+            vote.add_aggregatable_timeout_signature(signer.bls_sign_message(timeout.hash()));
+            vote
+        })
+        .collect();
+
+    assert_eq!(
+        pending_votes.insert_vote(&votes[0], &validator),
+        VoteReceptionResult::VoteAdded(1)
+    );
+    match pending_votes.insert_vote(&votes[1], &validator) {
+        VoteReceptionResult::NewQuorumCertificate(qc) => {
+            assert!(qc.ledger_info().signatures().is_empty());
+            assert!(qc.ledger_info().aggregated_signature().is_some());
+            assert!(qc.verify(&validator).is_ok());
+        }
+        _ => {
+            panic!("No QC formed.");
+        }
+    };
+
+    // The round signatures are aggregated into the TC the same way
+    let li2 = random_ledger_info();
+    let vote2_round_1 = random_vote_data(1);
+    let mut vote2_round_1_author_2 = Vote::new(
+        vote2_round_1.clone(),
+        signers[2].author(),
+        li2.clone(),
+        &signers[2],
+    );
+    let timeout = vote2_round_1_author_2.timeout();
+    vote2_round_1_author_2.add_timeout_signature(timeout.sign(&signers[2]));
+    vote2_round_1_author_2
+        .add_aggregatable_timeout_signature(signers[2].bls_sign_message(timeout.hash()));
+    match pending_votes.insert_vote(&vote2_round_1_author_2, &validator) {
+        VoteReceptionResult::NewTimeoutCertificate(tc) => {
+            assert!(tc.signatures().is_empty());
+            assert!(tc.aggregated_signature().is_some());
+            assert!(tc.verify(&validator).is_ok());
+        }
+        _ => {
+            panic!("No TC formed.");
+        }
+    };
+}
+
+#[test]
+/// Verify that the QC falls back to the individual signatures when a vote counted has no signature
+/// that can be aggregated
+fn test_aggregated_signatures_fallback() {
+    ::libra_logger::try_init_for_testing();
+
+    let (signers, validator) = random_bls_validator_verifier(4, Some(2), false);
+    let mut pending_votes = PendingVotes::new();
+
+    let li1 = random_ledger_info();
+    let vote_round_1 = random_vote_data(1);
+    let mut vote_round_1_author_0 = Vote::new(
+        vote_round_1.clone(),
+        signers[0].author(),
+        li1.clone(),
+        &signers[0],
+    );
+    vote_round_1_author_0.add_aggregatable_signature(
+        signers[0].bls_sign_message(vote_round_1_author_0.ledger_info().hash()),
+    );
+    assert_eq!(
+        pending_votes.insert_vote(&vote_round_1_author_0, &validator),
+        VoteReceptionResult::VoteAdded(1)
+    );
+
+    let vote_round_1_author_1 = Vote::new(
+        vote_round_1.clone(),
+        signers[1].author(),
+        li1.clone(),
+        &signers[1],
+    );
+    match pending_votes.insert_vote(&vote_round_1_author_1, &validator) {
+        VoteReceptionResult::NewQuorumCertificate(qc) => {
+            assert!(qc.ledger_info().aggregated_signature().is_none());
+            assert!(validator
+                .check_voting_power(qc.ledger_info().signatures().keys())
+                .is_ok());
+            assert!(qc.verify(&validator).is_ok());
+        }
+        _ => {
+            panic!("No QC formed.");
+        }
+    };
+}
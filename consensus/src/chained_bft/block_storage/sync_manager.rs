@@ -19,6 +19,7 @@ use failure;
 use libra_logger::prelude::*;
 use libra_types::account_address::AccountAddress;
 use mirai_annotations::checked_precondition;
+use network::validator_network::{PeerBehavior, RpcError};
 use rand::{prelude::*, Rng};
 use std::{
     clone::Clone,
@@ -95,8 +96,9 @@ impl<T: Payload> BlockStore<T> {
 
     /// Insert the quorum certificate separately from the block, used to split the processing of
     /// updating the consensus state(with qc) and deciding whether to vote(with block)
-    /// The missing ancestors are going to be retrieved from the given peer. If a given peer
-    /// fails to provide the missing ancestors, the qc is not going to be added.
+    /// The missing ancestors are retrieved in chunks of up to RETRIEVAL_CHUNK_SIZE blocks, each
+    /// chunk from the signers of the quorum certificate of its highest block. If no peer manages
+    /// to provide a chunk, the qc is not going to be added.
     async fn fetch_quorum_cert(
         &self,
         qc: QuorumCert,
@@ -104,15 +106,31 @@ impl<T: Payload> BlockStore<T> {
     ) -> failure::Result<()> {
         let mut pending = vec![];
         let mut retrieve_qc = qc.clone();
-        loop {
-            if self.block_exists(retrieve_qc.certified_block().id()) {
-                break;
+        'retrieval: while !self.block_exists(retrieve_qc.certified_block().id()) {
+            // The rounds of the missing blocks are between the root and the certified block, so
+            // there are at most as many blocks to fetch as rounds.
+            let num_blocks = retrieve_qc
+                .certified_block()
+                .round()
+                .saturating_sub(self.root().round())
+                .min(RETRIEVAL_CHUNK_SIZE)
+                .max(1);
+            let blocks = retriever
+                .retrieve_chunk_for_qc(&retrieve_qc, num_blocks)
+                .await?;
+            for block in blocks {
+                if self.block_exists(block.id()) {
+                    break 'retrieval;
+                }
+                ensure!(
+                    block.round() > self.root().round(),
+                    "Retrieved block {} doesn't extend the root {}",
+                    block,
+                    self.root()
+                );
+                retrieve_qc = block.quorum_cert().clone();
+                pending.push(block);
             }
-            let mut blocks = retriever.retrieve_block_for_qc(&retrieve_qc, 1).await?;
-            // retrieve_block_for_qc guarantees that blocks has exactly 1 element
-            let block = blocks.remove(0);
-            retrieve_qc = block.quorum_cert().clone();
-            pending.push(block);
         }
         // insert the qc <- block pair
         while let Some(block) = pending.pop() {
@@ -182,6 +200,8 @@ pub struct BlockRetriever {
     network: NetworkSender,
     deadline: Instant,
     preferred_peer: Author,
+    // Number of chunks retrieved so far, to spread the chunks across the peers
+    num_chunks: usize,
 }
 
 impl BlockRetriever {
@@ -190,30 +210,60 @@ impl BlockRetriever {
             network,
             deadline,
             preferred_peer,
+            num_chunks: 0,
         }
     }
     /// Retrieve chain of n blocks for given QC
     ///
     /// Returns Result with Vec that has a guaranteed size of num_blocks
     /// This guarantee is based on BlockRetrievalResponse::verify that ensures that number of
-    /// blocks in response is equal to number of blocks requested.  This method will
-    /// continue until either the round deadline is reached or the quorum certificate members all
-    /// fail to return the missing chain.
-    ///
-    /// The first attempt of block retrieval will always be sent to preferred_peer to allow the
-    /// leader to drive quorum certificate creation The other peers from the quorum certificate
-    /// will be randomly tried next.  If all members of the quorum certificate are exhausted, an
-    /// error is returned
+    /// blocks in response is equal to number of blocks requested.
     async fn retrieve_block_for_qc<'a, T>(
         &'a mut self,
         qc: &'a QuorumCert,
         num_blocks: u64,
     ) -> failure::Result<Vec<Block<T>>>
+    where
+        T: Payload,
+    {
+        self.retrieve(qc, num_blocks, false).await
+    }
+
+    /// Retrieve a chunk of up to max_blocks blocks for given QC, which may be cut short if the
+    /// peer doesn't have all the requested ancestors anymore.
+    ///
+    /// Returns Result with a non-empty Vec of at most max_blocks blocks.
+    async fn retrieve_chunk_for_qc<'a, T>(
+        &'a mut self,
+        qc: &'a QuorumCert,
+        max_blocks: u64,
+    ) -> failure::Result<Vec<Block<T>>>
+    where
+        T: Payload,
+    {
+        self.retrieve(qc, max_blocks, true).await
+    }
+
+    /// This method will continue until either the round deadline is reached or the quorum
+    /// certificate members all fail to return a chain certified by the quorum certificate.
+    ///
+    /// The first attempt of the first retrieval will always be sent to preferred_peer to allow the
+    /// leader to drive quorum certificate creation. The first attempts of the following ones are
+    /// sent to the members of the quorum certificate in turn, to spread the load of catching up.
+    /// The other peers from the quorum certificate will be randomly tried next.  If all members of
+    /// the quorum certificate are exhausted, an error is returned
+    async fn retrieve<'a, T>(
+        &'a mut self,
+        qc: &'a QuorumCert,
+        num_blocks: u64,
+        allow_partial: bool,
+    ) -> failure::Result<Vec<Block<T>>>
     where
         T: Payload,
     {
         let block_id = qc.certified_block().id();
-        let mut peers: Vec<&AccountAddress> = qc.ledger_info().signatures().keys().collect();
+        let signers = qc.ledger_info().signers(self.network.validators());
+        let mut peers: Vec<&AccountAddress> = signers.iter().collect();
         let mut attempt = 0_u32;
         loop {
             if peers.is_empty() {
@@ -231,7 +281,8 @@ impl BlockRetriever {
                 format_err!("Failed to fetch block {} from {}, attempt {}: round deadline was reached, won't make more attempts", block_id, peer, attempt)
             })?;
             debug!(
-                "Fetching {} from {}, attempt {}",
+                "Fetching {} blocks from {} from {}, attempt {}",
+                num_blocks,
                 block_id,
                 peer.short_str(),
                 attempt
@@ -252,20 +303,42 @@ impl BlockRetriever {
                         peer.short_str(),
                         e
                     );
+                    // A request cut short by the round deadline says nothing about the peer.
+                    if let Some(RpcError::TimedOut) = e.downcast_ref::<RpcError>() {
+                        if timeout == attempt_timeout(attempt) {
+                            self.network
+                                .report_peer(peer, PeerBehavior::RpcTimeout)
+                                .await;
+                        }
+                    }
                     continue;
                 }
                 Ok(response) => response,
             };
-            if response.status() != BlockRetrievalStatus::Succeeded {
+            let status = response.status();
+            if status != BlockRetrievalStatus::Succeeded
+                && !(allow_partial && status == BlockRetrievalStatus::NotEnoughBlocks)
+            {
                 warn!(
                     "Failed to fetch block {} from {}: {:?}, trying another peer",
                     block_id,
                     peer.short_str(),
-                    response.status()
+                    status
+                );
+                continue;
+            }
+            let blocks = response.blocks().clone();
+            if let Err(e) = verify_chain(qc, &blocks) {
+                warn!(
+                    "Invalid blocks from {} for block {}: {}, trying another peer",
+                    peer.short_str(),
+                    block_id,
+                    e
                 );
                 continue;
             }
-            return Ok(response.blocks().clone());
+            self.num_chunks += 1;
+            return Ok(blocks);
         }
     }
 
@@ -273,6 +346,10 @@ impl BlockRetriever {
         assert!(!peers.is_empty(), "pick_peer on empty peer list");
 
         if attempt == 0 {
+            if self.num_chunks > 0 {
+                // the signers are sorted, so that the chunks go to each of them in turn
+                return *peers.remove(self.num_chunks % peers.len());
+            }
             // remove preferred_peer if its in list of peers
             // (strictly speaking it is not required to be there)
             for i in 0..peers.len() {
@@ -289,16 +366,58 @@ impl BlockRetriever {
     }
 }
 
+/// Checks that the blocks form a chain of quorum certificates from the block certified by `qc`
+/// down to the block certified by the quorum certificate of the last block.
+pub(super) fn verify_chain<T: Payload>(
+    qc: &QuorumCert,
+    blocks: &[Block<T>],
+) -> failure::Result<()> {
+    let first_block = blocks
+        .first()
+        .ok_or_else(|| format_err!("No block returned"))?;
+    let certified_block = qc.certified_block();
+    ensure!(
+        first_block.id() == certified_block.id()
+            && first_block.epoch() == certified_block.epoch()
+            && first_block.round() == certified_block.round(),
+        "First block {} isn't the certified block {}",
+        first_block,
+        certified_block
+    );
+    for pair in blocks.windows(2) {
+        let (block, parent) = (&pair[0], &pair[1]);
+        let certified_parent = block.quorum_cert().certified_block();
+        ensure!(
+            certified_parent.id() == parent.id()
+                && certified_parent.epoch() == parent.epoch()
+                && certified_parent.round() == parent.round(),
+            "Block {} certifies {} instead of its parent {}",
+            block,
+            certified_parent,
+            parent
+        );
+    }
+    Ok(())
+}
+
+/// Max number of blocks requested at once when fetching the ancestors of a quorum certificate.
+const RETRIEVAL_CHUNK_SIZE: u64 = 20;
+
 // Max timeout is 16s=RETRIEVAL_INITIAL_TIMEOUT*(2^RETRIEVAL_MAX_EXP)
 const RETRIEVAL_INITIAL_TIMEOUT: Duration = Duration::from_secs(1);
 const RETRIEVAL_MAX_EXP: u32 = 4;
 
 /// Returns exponentially increasing timeout with
 /// limit of RETRIEVAL_INITIAL_TIMEOUT*(2^RETRIEVAL_MAX_EXP)
-fn retrieval_timeout(deadline: &Instant, attempt: u32) -> Option<Duration> {
-    assert!(attempt > 0, "retrieval_timeout attempt can't be 0");
+fn attempt_timeout(attempt: u32) -> Duration {
+    assert!(attempt > 0, "retrieval attempt can't be 0");
     let exp = RETRIEVAL_MAX_EXP.min(attempt - 1); // [0..RETRIEVAL_MAX_EXP]
-    let request_timeout = RETRIEVAL_INITIAL_TIMEOUT * 2_u32.pow(exp);
+    RETRIEVAL_INITIAL_TIMEOUT * 2_u32.pow(exp)
+}
+
+/// Returns the timeout of the attempt, cut short by the round deadline if it's closer
+fn retrieval_timeout(deadline: &Instant, attempt: u32) -> Option<Duration> {
+    let request_timeout = attempt_timeout(attempt);
     let now = Instant::now();
     let deadline_timeout = if *deadline >= now {
         Some(deadline.duration_since(now))
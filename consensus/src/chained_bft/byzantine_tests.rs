@@ -0,0 +1,412 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scenarios running honest nodes along with a Byzantine one over the `NetworkPlayground`.
+//!
+//! The Byzantine node is either a validator whose outbound messages are rewritten, or a pair of
+//! twins: two honest nodes sharing one key but not their SafetyRules storage, which equivocate and
+//! double vote as soon as they are in different partitions. Each scenario splits the network in a
+//! different way at each new round, so that the Byzantine node, or each of the twins in turn,
+//! forms a quorum with a different pair of honest nodes, then heals it. All along, the commits of
+//! the honest nodes are checked to form a single chain, and once healed, all of them must commit
+//! new blocks.
+//!
+//! The network delivers the messages in an order drawn from a seed, so that a scenario can be
+//! replayed with the same seed, up to the timing of the nodes.
+
+use crate::chained_bft::{
+    chained_bft_smr_test::SMRNode,
+    network_tests::{MessageRewriter, NetworkPlayground, NodeId},
+    test_utils::{consensus_runtime, TestPayload},
+};
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
+    quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+    vote::Vote,
+    vote_data::VoteData,
+    vote_msg::VoteMsg,
+};
+use futures::executor::block_on;
+use libra_config::config::ConsensusProposerType::RotatingProposer;
+use libra_crypto::HashValue;
+use libra_types::{block_info::BlockInfo, crypto_proxies::ValidatorSigner};
+use network::proto::{ConsensusMsg, ConsensusMsg_oneof};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::{TryFrom, TryInto},
+};
+
+/// Number of rounds during which the network is split.
+const PARTITION_ROUNDS: Round = 20;
+/// Number of messages after which the honest nodes are considered stuck.
+const MAX_MESSAGES: usize = 5_000;
+
+/// The commits of the honest nodes, checked to form a single chain as they come.
+#[derive(Default)]
+struct CommitLog {
+    // The parent of each block seen in the messages, along with the round of the parent
+    parents: HashMap<HashValue, (HashValue, Round)>,
+    // The committed blocks and their ancestors, by round
+    chain: BTreeMap<Round, HashValue>,
+    // The highest round committed by each honest node
+    highest_rounds: HashMap<NodeId, Round>,
+}
+
+impl CommitLog {
+    /// Learns the parents of the blocks carried by a delivered message, which are the ancestors
+    /// of the blocks committed later on.
+    fn record_message(&mut self, msg: &ConsensusMsg) {
+        match &msg.message {
+            Some(ConsensusMsg_oneof::Proposal(proposal)) => {
+                let proposal: ProposalMsg<TestPayload> =
+                    ProposalUncheckedSignatures::<TestPayload>::try_from(proposal.clone())
+                        .unwrap()
+                        .into();
+                let block = proposal.proposal();
+                self.record_parent(block.id(), block.quorum_cert().certified_block());
+                self.record_sync_info(proposal.sync_info());
+            }
+            Some(ConsensusMsg_oneof::VoteMsg(vote)) => {
+                let vote_msg = VoteMsg::try_from(vote.clone()).unwrap();
+                let vote_data = vote_msg.vote().vote_data();
+                self.record_parent(vote_data.proposed().id(), vote_data.parent());
+                self.record_sync_info(vote_msg.sync_info());
+            }
+            Some(ConsensusMsg_oneof::SyncInfo(sync_info)) => {
+                self.record_sync_info(&SyncInfo::try_from(sync_info.clone()).unwrap());
+            }
+            _ => (),
+        }
+    }
+
+    fn record_sync_info(&mut self, sync_info: &SyncInfo) {
+        self.record_qc(sync_info.highest_quorum_cert());
+        self.record_qc(sync_info.highest_ledger_info());
+    }
+
+    fn record_qc(&mut self, qc: &QuorumCert) {
+        self.record_parent(qc.certified_block().id(), qc.parent_block());
+    }
+
+    fn record_parent(&mut self, id: HashValue, parent: &BlockInfo) {
+        self.parents.insert(id, (parent.id(), parent.round()));
+    }
+
+    /// Checks that the new commits of `node` extend the chain of the previous commits, or are
+    /// part of it.
+    fn record(&mut self, node: &mut SMRNode) {
+        while let Ok(Some(commit)) = node.commit_cb_receiver.try_next() {
+            let ledger_info = commit.ledger_info();
+            // Walk down the ancestors of the committed block until the chain committed so far.
+            let (mut id, mut round) = (ledger_info.consensus_block_id(), ledger_info.round());
+            let mut ancestors = BTreeMap::new();
+            while round > 0 && self.chain.get(&round) != Some(&id) {
+                ancestors.insert(round, id);
+                let parent = *self.parents.get(&id).unwrap_or_else(|| {
+                    panic!("Unknown ancestry of the block committed at round {}", round)
+                });
+                id = parent.0;
+                round = parent.1;
+            }
+            // Unless the committed block is part of the chain already, all the blocks committed
+            // above the common ancestor must be its ancestors as well.
+            if !ancestors.is_empty() {
+                for (committed_round, committed_id) in self.chain.range(round + 1..) {
+                    assert_eq!(
+                        ancestors.get(committed_round),
+                        Some(committed_id),
+                        "Conflicting commits: the block committed at round {} isn't an ancestor \
+                         of the one committed at round {}",
+                        committed_round,
+                        ledger_info.round()
+                    );
+                }
+            }
+            self.chain.extend(ancestors);
+            let highest_round = self.highest_rounds.entry(node.node_id).or_insert(0);
+            *highest_round = std::cmp::max(*highest_round, ledger_info.round());
+        }
+    }
+
+    fn highest_round(&self, node: &SMRNode) -> Round {
+        self.highest_rounds.get(&node.node_id).cloned().unwrap_or(0)
+    }
+}
+
+/// Returns the round of the proposal carried by `msg`, if any.
+fn proposal_round(msg: &ConsensusMsg) -> Option<Round> {
+    match &msg.message {
+        Some(ConsensusMsg_oneof::Proposal(proposal)) => {
+            let proposal: ProposalMsg<TestPayload> =
+                ProposalUncheckedSignatures::<TestPayload>::try_from(proposal.clone())
+                    .unwrap()
+                    .into();
+            Some(proposal.proposal().round())
+        }
+        _ => None,
+    }
+}
+
+/// Splits 4 validators, the first of which is Byzantine, for the given round: one of the honest
+/// validators in turn is isolated from the two others, which form a quorum with the Byzantine
+/// validator. With a twin, the two sides of the Byzantine validator alternate every 3 rounds, so
+/// that each of them forms a quorum with each pair of honest validators.
+fn rotating_partitions(node_ids: &[NodeId], round: Round) -> Vec<Vec<NodeId>> {
+    let isolated = 1 + (round % 3) as usize;
+    let mut quorum: Vec<_> = (1..4)
+        .filter(|i| *i != isolated)
+        .map(|i| node_ids[i])
+        .collect();
+    let mut minority = vec![node_ids[isolated]];
+    let mut byzantine = node_ids[0];
+    if let Some(twin) = node_ids.get(4) {
+        let mut twin = *twin;
+        if (round / 3) % 2 == 1 {
+            std::mem::swap(&mut byzantine, &mut twin);
+        }
+        minority.push(twin);
+    }
+    quorum.push(byzantine);
+    vec![quorum, minority]
+}
+
+/// Runs a scenario with 4 validators, the first `num_twins` of which have a twin, and the first of
+/// which is Byzantine: its messages are rewritten by `rewriter` if any. The network is split as
+/// `partitions` returns for each new round, among the nodes sorted by id.
+fn run_scenario(
+    seed: u8,
+    num_twins: usize,
+    rewriter: impl FnOnce(&ValidatorSigner) -> Option<MessageRewriter>,
+    partitions: impl Fn(&[NodeId], Round) -> Vec<Vec<NodeId>>,
+) {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new_with_seed(runtime.executor(), [seed; 32]);
+    let mut nodes = SMRNode::start_num_nodes_with_twins(
+        4,
+        num_twins,
+        3,
+        &mut playground,
+        RotatingProposer,
+        false,
+    );
+    if let Some(rewriter) = rewriter(&nodes[0].signer) {
+        playground.set_rewriter(nodes[0].signer.author(), rewriter);
+    }
+    let honest: Vec<_> = (num_twins.max(1)..4).collect();
+    let node_ids: Vec<_> = nodes.iter().map(|node| node.node_id).collect();
+
+    block_on(async move {
+        let mut log = CommitLog::default();
+        let mut round = 0;
+        playground.split_network(partitions(&node_ids, round));
+        let mut num_messages = 0;
+        while round < PARTITION_ROUNDS {
+            num_messages += 1;
+            assert!(
+                num_messages <= MAX_MESSAGES,
+                "Nodes didn't get beyond round {} in the split network",
+                round
+            );
+            for (_, msg) in playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await
+            {
+                log.record_message(&msg);
+                if let Some(proposal_round) = proposal_round(&msg) {
+                    if proposal_round > round {
+                        round = proposal_round;
+                        playground.split_network(partitions(&node_ids, round));
+                    }
+                }
+            }
+            for i in &honest {
+                log.record(&mut nodes[*i]);
+            }
+        }
+
+        playground.heal_network();
+        let round = honest
+            .iter()
+            .map(|i| log.highest_round(&nodes[*i]))
+            .max()
+            .unwrap();
+        for _ in 0..MAX_MESSAGES {
+            for (_, msg) in playground
+                .wait_for_messages(1, NetworkPlayground::take_all)
+                .await
+            {
+                log.record_message(&msg);
+            }
+            for i in &honest {
+                log.record(&mut nodes[*i]);
+            }
+            if honest.iter().all(|i| log.highest_round(&nodes[*i]) > round) {
+                return;
+            }
+        }
+        panic!("Honest nodes didn't commit beyond round {}", round);
+    });
+}
+
+fn proposal_msg(proposal: ProposalMsg<TestPayload>) -> ConsensusMsg {
+    ConsensusMsg {
+        message: Some(ConsensusMsg_oneof::Proposal(proposal.try_into().unwrap())),
+    }
+}
+
+fn vote_msg(vote_msg: VoteMsg) -> ConsensusMsg {
+    ConsensusMsg {
+        message: Some(ConsensusMsg_oneof::VoteMsg(vote_msg.try_into().unwrap())),
+    }
+}
+
+/// Sends a different proposal to each peer.
+fn equivocate(signer: &ValidatorSigner) -> Option<MessageRewriter> {
+    let signer = signer.clone();
+    let mut num_equivocations = 0;
+    Some(Box::new(move |_: Author, msg: ConsensusMsg| {
+        match msg.message {
+            Some(ConsensusMsg_oneof::Proposal(proposal)) => {
+                let proposal: ProposalMsg<TestPayload> =
+                    ProposalUncheckedSignatures::<TestPayload>::try_from(proposal)
+                        .unwrap()
+                        .into();
+                let block = proposal.proposal();
+                let mut payload = block.payload().cloned().unwrap_or_default();
+                payload.push(num_equivocations);
+                num_equivocations += 1;
+                let block = Block::new_proposal(
+                    payload,
+                    block.round(),
+                    block.timestamp_usecs(),
+                    block.quorum_cert().clone(),
+                    &signer,
+                );
+                vec![proposal_msg(ProposalMsg::new(
+                    block,
+                    proposal.sync_info().clone(),
+                ))]
+            }
+            message => vec![ConsensusMsg { message }],
+        }
+    }))
+}
+
+/// Sends a vote for an unknown block of the same round along with each vote.
+fn double_vote(signer: &ValidatorSigner) -> Option<MessageRewriter> {
+    let signer = signer.clone();
+    Some(Box::new(move |_: Author, msg: ConsensusMsg| {
+        match msg.message {
+            Some(ConsensusMsg_oneof::VoteMsg(vote)) => {
+                let original = VoteMsg::try_from(vote).unwrap();
+                let vote = original.vote();
+                let proposed = vote.vote_data().proposed();
+                let conflicting_block = BlockInfo::new(
+                    proposed.epoch(),
+                    proposed.round(),
+                    HashValue::random(),
+                    proposed.executed_state_id(),
+                    proposed.version(),
+                    proposed.timestamp_usecs(),
+                    None,
+                );
+                let conflicting_vote = Vote::new(
+                    VoteData::new(conflicting_block, vote.vote_data().parent().clone()),
+                    vote.author(),
+                    vote.ledger_info().clone(),
+                    &signer,
+                );
+                let conflicting = VoteMsg::new(conflicting_vote, original.sync_info().clone());
+                vec![vote_msg(conflicting), vote_msg(original)]
+            }
+            message => vec![ConsensusMsg { message }],
+        }
+    }))
+}
+
+/// Drops all the votes.
+fn withhold_votes(_signer: &ValidatorSigner) -> Option<MessageRewriter> {
+    Some(Box::new(|_: Author, msg: ConsensusMsg| match msg.message {
+        Some(ConsensusMsg_oneof::VoteMsg(_)) => vec![],
+        message => vec![ConsensusMsg { message }],
+    }))
+}
+
+/// Sends the first `SyncInfo` it sent again along with each message.
+fn replay_sync_info(_signer: &ValidatorSigner) -> Option<MessageRewriter> {
+    let mut first_sync_info = None;
+    Some(Box::new(move |_: Author, msg: ConsensusMsg| {
+        let sync_info = match &msg.message {
+            Some(ConsensusMsg_oneof::Proposal(proposal)) => {
+                let proposal: ProposalMsg<TestPayload> =
+                    ProposalUncheckedSignatures::<TestPayload>::try_from(proposal.clone())
+                        .unwrap()
+                        .into();
+                proposal.sync_info().clone()
+            }
+            Some(ConsensusMsg_oneof::VoteMsg(vote)) => {
+                VoteMsg::try_from(vote.clone()).unwrap().sync_info().clone()
+            }
+            Some(ConsensusMsg_oneof::SyncInfo(sync_info)) => {
+                SyncInfo::try_from(sync_info.clone()).unwrap()
+            }
+            _ => return vec![msg],
+        };
+        let stale_sync_info = first_sync_info.get_or_insert(sync_info).clone();
+        let replayed = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::SyncInfo(
+                stale_sync_info.try_into().unwrap(),
+            )),
+        };
+        vec![replayed, msg]
+    }))
+}
+
+#[test]
+/// Twins in different partitions propose and vote for conflicting blocks.
+fn twins() {
+    run_scenario(0, 1, |_| None, rotating_partitions);
+}
+
+#[test]
+fn equivocating_proposer() {
+    run_scenario(1, 0, equivocate, rotating_partitions);
+}
+
+#[test]
+fn double_voter() {
+    run_scenario(2, 0, double_vote, rotating_partitions);
+}
+
+#[test]
+fn vote_withholder() {
+    run_scenario(3, 0, withhold_votes, rotating_partitions);
+}
+
+#[test]
+fn sync_info_replayer() {
+    run_scenario(4, 0, replay_sync_info, rotating_partitions);
+}
+
+#[test]
+/// Twins which also send a different proposal to each peer.
+fn twins_with_equivocations() {
+    run_scenario(5, 1, equivocate, rotating_partitions);
+}
+
+#[test]
+#[should_panic(expected = "Conflicting commits")]
+/// Two of the validators have a twin, which is beyond the tolerated faults: with two keys voting
+/// in both partitions as if SafetyRules was bypassed, each partition holds a quorum and commits
+/// its own chain, which the harness must catch.
+fn twins_beyond_fault_tolerance() {
+    run_scenario(
+        6,
+        2,
+        |_| None,
+        |node_ids, _| vec![node_ids[..3].to_vec(), node_ids[3..].to_vec()],
+    );
+}
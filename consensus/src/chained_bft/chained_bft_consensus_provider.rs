@@ -15,13 +15,11 @@ use crate::{
 use consensus_types::common::Author;
 use executor::Executor;
 use failure::prelude::*;
-use libra_config::config::NodeConfig;
+use libra_config::config::{NodeConfig, SafetyRulesService};
+use libra_crypto::ed25519::Ed25519PrivateKey;
 use libra_logger::prelude::*;
 use libra_mempool::proto::mempool::MempoolClient;
-use libra_types::{
-    account_address::AccountAddress, crypto_proxies::ValidatorSigner,
-    transaction::SignedTransaction,
-};
+use libra_types::{account_address::AccountAddress, transaction::SignedTransaction};
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 use state_synchronizer::StateSyncClient;
 use std::{convert::TryFrom, sync::Arc};
@@ -31,9 +29,12 @@ use vm_runtime::MoveVM;
 ///  The state necessary to begin state machine replication including ValidatorSet, networking etc.
 pub struct InitialSetup {
     pub author: Author,
-    pub signer: ValidatorSigner,
+    // None when SafetyRules holds the consensus keys itself, or runs in another process.
+    pub consensus_key: Option<Ed25519PrivateKey>,
     pub network_sender: ConsensusNetworkSender,
     pub network_events: ConsensusNetworkEvents,
+    // The network of the full nodes following consensus as observers, if any.
+    pub observer_network: Option<(ConsensusNetworkSender, ConsensusNetworkEvents)>,
 }
 
 /// Supports the implementation of ConsensusProvider using LibraBFT.
@@ -48,6 +49,7 @@ impl ChainedBftProvider {
         node_config: &mut NodeConfig,
         network_sender: ConsensusNetworkSender,
         network_events: ConsensusNetworkEvents,
+        observer_network: Option<(ConsensusNetworkSender, ConsensusNetworkEvents)>,
         mempool_client: Arc<MempoolClient>,
         executor: Arc<Executor<MoveVM>>,
         synchronizer_client: Arc<StateSyncClient>,
@@ -57,7 +59,12 @@ impl ChainedBftProvider {
             .build()
             .expect("Failed to create Tokio runtime!");
 
-        let initial_setup = Self::initialize_setup(network_sender, network_events, node_config);
+        let initial_setup = Self::initialize_setup(
+            network_sender,
+            network_events,
+            observer_network,
+            node_config,
+        );
         debug!("[Consensus] My peer: {:?}", initial_setup.author);
         let config = ChainedBftSMRConfig::from_node_config(&node_config.consensus);
         let storage = Arc::new(StorageWriteProxy::new(node_config));
@@ -77,6 +84,7 @@ impl ChainedBftProvider {
     fn initialize_setup(
         network_sender: ConsensusNetworkSender,
         network_events: ConsensusNetworkEvents,
+        observer_network: Option<(ConsensusNetworkSender, ConsensusNetworkEvents)>,
         node_config: &mut NodeConfig,
     ) -> InitialSetup {
         let peer_id_str = node_config
@@ -86,19 +94,31 @@ impl ChainedBftProvider {
             .clone();
         let author =
             AccountAddress::try_from(peer_id_str).expect("Failed to parse peer id of a validator");
-        let private_key = node_config
-            .consensus
-            .consensus_keypair
-            .take_consensus_private()
-            .expect(
-            "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
-        );
-        let signer = ValidatorSigner::new(author, private_key);
+        let safety_rules_config = &node_config.consensus.safety_rules;
+        let in_process = match safety_rules_config.service {
+            SafetyRulesService::Process { .. } => true,
+            _ => false,
+        };
+        // Consensus only reads the consensus private key if SafetyRules can't get it otherwise.
+        let consensus_key = if in_process || safety_rules_config.backend.holds_consensus_keys() {
+            None
+        } else {
+            Some(
+                node_config
+                    .consensus
+                    .consensus_keypair
+                    .take_consensus_private()
+                    .expect(
+                        "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
+                    ),
+            )
+        };
         InitialSetup {
             author,
-            signer,
+            consensus_key,
             network_sender,
             network_events,
+            observer_network,
         }
     }
 }
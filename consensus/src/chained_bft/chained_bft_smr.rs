@@ -4,6 +4,7 @@
 use crate::chained_bft::chained_bft_consensus_provider::InitialSetup;
 use crate::chained_bft::epoch_manager::EpochManager;
 use crate::chained_bft::network::{NetworkReceivers, NetworkTask};
+use crate::chained_bft::observer_publisher::ObserverPublisher;
 use crate::{
     chained_bft::{
         block_storage::BlockStore,
@@ -18,7 +19,10 @@ use channel;
 use consensus_types::common::{Payload, Round};
 use failure::prelude::*;
 use futures::{select, stream::StreamExt};
-use libra_config::config::{ConsensusConfig, ConsensusProposerType, SafetyRulesConfig};
+use libra_config::config::{
+    AdaptivePacemakerConfig, ConsensusConfig, ConsensusProposerType, ConsensusSignatureScheme,
+    SafetyRulesConfig,
+};
 use libra_logger::prelude::*;
 use std::{
     sync::Arc,
@@ -32,12 +36,22 @@ pub struct ChainedBftSMRConfig {
     pub max_pruned_blocks_in_mem: usize,
     /// Initial timeout for pacemaker
     pub pacemaker_initial_timeout: Duration,
+    /// Derives the pacemaker timeouts from the recent round latencies if set
+    pub adaptive_pacemaker: Option<AdaptivePacemakerConfig>,
     /// Consensus proposer type
     pub proposer_type: ConsensusProposerType,
     /// Contiguous rounds for proposer
     pub contiguous_rounds: u32,
+    /// Number of proposers elected per round on average by the VRF proposer election
+    pub vrf_expected_proposers: u64,
+    /// Number of committed blocks the reputation of the validators is computed over
+    pub leader_reputation_window_size: usize,
+    /// Number of rounds the last committed block of a reputation window is older than its round
+    pub leader_reputation_exclude_rounds: Round,
     /// Max block size (number of transactions) that consensus pulls from mempool
     pub max_block_size: u64,
+    /// Scheme the votes are signed with
+    pub signature_scheme: ConsensusSignatureScheme,
     /// Path to SafetyRulesConfig
     pub safety_rules: SafetyRulesConfig,
 }
@@ -48,9 +62,14 @@ impl ChainedBftSMRConfig {
         ChainedBftSMRConfig {
             max_pruned_blocks_in_mem: cfg.max_pruned_blocks_in_mem.unwrap_or(10000) as usize,
             pacemaker_initial_timeout: Duration::from_millis(pacemaker_initial_timeout_ms),
+            adaptive_pacemaker: cfg.adaptive_pacemaker.clone(),
             proposer_type: cfg.proposer_type,
             contiguous_rounds: cfg.contiguous_rounds,
+            vrf_expected_proposers: cfg.vrf_expected_proposers,
+            leader_reputation_window_size: cfg.leader_reputation_window_size,
+            leader_reputation_exclude_rounds: cfg.leader_reputation_exclude_rounds,
             max_block_size: cfg.max_block_size,
+            signature_scheme: cfg.signature_scheme,
             safety_rules: cfg.safety_rules.clone(),
         }
     }
@@ -181,7 +200,6 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
         let (timeout_sender, timeout_receiver) =
             channel::new(1_024, &counters::PENDING_PACEMAKER_TIMEOUTS);
         let (self_sender, self_receiver) = channel::new(1_024, &counters::PENDING_SELF_MESSAGES);
-        let signer = Arc::new(initial_setup.signer);
         let epoch = initial_data.epoch();
         let validators = initial_data.validators();
         let epoch_mgr = EpochManager::new(
@@ -189,26 +207,33 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             self.config.take().expect("already started, config is None"),
             time_service,
             self_sender,
-            initial_setup.network_sender,
+            initial_setup.network_sender.clone(),
             timeout_sender,
             txn_manager,
             state_computer,
             self.storage.clone(),
-            signer.clone(),
+            initial_setup.author,
+            initial_setup.consensus_key,
         );
 
         // Step 2
-        let event_processor = epoch_mgr.start_epoch(signer, initial_data);
+        let event_processor = epoch_mgr.start_epoch(initial_data);
 
         // TODO: this is test only, we should remove this
         self.block_store = Some(event_processor.block_store());
 
-        let (network_task, network_receiver) = NetworkTask::new(
+        let (mut network_task, network_receiver) = NetworkTask::new(
             epoch,
+            initial_setup.network_sender,
             initial_setup.network_events,
             self_receiver,
             validators,
         );
+        if let Some((sender, events)) = initial_setup.observer_network {
+            let (publisher, publisher_task) = ObserverPublisher::new(sender, events);
+            network_task.set_observer_publisher(publisher);
+            executor.spawn(publisher_task.start());
+        }
 
         Self::start_event_processing(
             executor,
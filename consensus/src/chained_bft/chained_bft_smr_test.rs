@@ -6,7 +6,7 @@ use crate::{
         block_storage::BlockReader,
         chained_bft_consensus_provider::InitialSetup,
         chained_bft_smr::{ChainedBftSMR, ChainedBftSMRConfig},
-        network_tests::NetworkPlayground,
+        network_tests::{NetworkPlayground, NodeId},
         persistent_storage::RecoveryData,
         test_utils::{
             consensus_runtime, with_smr_id, MockStateComputer, MockStorage, MockTransactionManager,
@@ -23,7 +23,7 @@ use consensus_types::{
 use futures::{channel::mpsc, executor::block_on, prelude::*};
 use libra_config::config::{
     ConsensusProposerType::{self, FixedProposer, MultipleOrderedProposers, RotatingProposer},
-    {SafetyRulesBackend, SafetyRulesConfig},
+    ConsensusSignatureScheme, {SafetyRulesBackend, SafetyRulesConfig, SafetyRulesService},
 };
 use libra_crypto::hash::CryptoHash;
 use libra_types::{
@@ -36,23 +36,26 @@ use network::{
     proto::ConsensusMsg_oneof,
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
 };
-use safety_rules::OnDiskStorage;
-use std::{convert::TryFrom, path::PathBuf, sync::Arc, time::Duration};
-use tempfile::NamedTempFile;
+use safety_rules::{OnDiskStorage, ProcessService, SafetyRules};
+use std::{convert::TryFrom, sync::Arc, thread, time::Duration};
+use tempfile::{NamedTempFile, TempDir};
 use tokio::runtime;
 
 /// Auxiliary struct that is preparing SMR for the test
-struct SMRNode {
-    signer: ValidatorSigner,
+pub struct SMRNode {
+    pub signer: ValidatorSigner,
     validators: Arc<ValidatorVerifier>,
     proposer_type: ConsensusProposerType,
     smr_id: usize,
-    smr: ChainedBftSMR<TestPayload>,
-    commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    pub node_id: NodeId,
+    pub smr: ChainedBftSMR<TestPayload>,
+    pub commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
     mempool: Arc<MockTransactionManager>,
     mempool_notif_receiver: mpsc::Receiver<usize>,
     storage: Arc<MockStorage<TestPayload>>,
-    safety_rules_path: PathBuf,
+    safety_rules: SafetyRulesConfig,
+    // Holds the socket of SafetyRules in process mode
+    safety_rules_dir: Option<TempDir>,
 }
 
 impl SMRNode {
@@ -64,7 +67,8 @@ impl SMRNode {
         initial_data: RecoveryData<TestPayload>,
         proposer_type: ConsensusProposerType,
         executor_with_reconfig: Option<ValidatorSet>,
-        safety_rules_path: PathBuf,
+        safety_rules: SafetyRulesConfig,
+        safety_rules_dir: Option<TempDir>,
     ) -> Self {
         let validators = initial_data.validators();
         let author = signer.author();
@@ -74,31 +78,31 @@ impl SMRNode {
         let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
         let network_events = ConsensusNetworkEvents::new(consensus_rx);
 
-        playground.add_node(author, consensus_tx, network_reqs_rx);
+        let node_id = playground.add_node(author, consensus_tx, network_reqs_rx);
         let runtime = runtime::Builder::new()
             .after_start(with_smr_id(signer.author().short_str()))
             .build()
             .expect("Failed to create Tokio runtime!");
 
-        let mut safety_rules_config = SafetyRulesConfig::default();
-        safety_rules_config.backend = SafetyRulesBackend::OnDiskStorage {
-            default: false,
-            path: safety_rules_path.clone(),
-        };
-
         let config = ChainedBftSMRConfig {
             max_pruned_blocks_in_mem: 10000,
             pacemaker_initial_timeout: Duration::from_secs(3),
+            adaptive_pacemaker: None,
             proposer_type,
             contiguous_rounds: 2,
+            vrf_expected_proposers: 2,
+            leader_reputation_window_size: 10,
+            leader_reputation_exclude_rounds: 10,
             max_block_size: 50,
-            safety_rules: safety_rules_config,
+            signature_scheme: ConsensusSignatureScheme::Ed25519,
+            safety_rules: safety_rules.clone(),
         };
         let initial_setup = InitialSetup {
             author,
-            signer: signer.clone(),
+            consensus_key: Some(signer.private_key().clone()),
             network_sender,
             network_events,
+            observer_network: None,
         };
         let mut smr = ChainedBftSMR::new(
             initial_setup,
@@ -125,12 +129,14 @@ impl SMRNode {
             validators,
             proposer_type,
             smr_id,
+            node_id,
             smr,
             commit_cb_receiver,
             mempool,
             mempool_notif_receiver: commit_receiver,
             storage,
-            safety_rules_path,
+            safety_rules,
+            safety_rules_dir,
         }
     }
 
@@ -148,7 +154,8 @@ impl SMRNode {
             recover_data,
             self.proposer_type,
             None,
-            self.safety_rules_path,
+            self.safety_rules,
+            self.safety_rules_dir,
         )
     }
 
@@ -159,27 +166,111 @@ impl SMRNode {
         proposer_type: ConsensusProposerType,
         executor_with_reconfig: bool,
     ) -> Vec<Self> {
-        let (mut signers, validators) =
+        Self::start_num_nodes_with_twins(
+            num_nodes,
+            0,
+            quorum_voting_power,
+            playground,
+            proposer_type,
+            executor_with_reconfig,
+        )
+    }
+
+    /// Starts `num_nodes` validators followed by `num_twins` twins of the first validators. A twin
+    /// shares the keys of its validator, but has its own storage and node in the playground.
+    pub fn start_num_nodes_with_twins(
+        num_nodes: usize,
+        num_twins: usize,
+        quorum_voting_power: u64,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        executor_with_reconfig: bool,
+    ) -> Vec<Self> {
+        Self::start_nodes(
+            num_nodes,
+            num_twins,
+            quorum_voting_power,
+            playground,
+            proposer_type,
+            executor_with_reconfig,
+            false,
+        )
+    }
+
+    /// Starts `num_nodes` validators, each reaching its SafetyRules in its own process (served by
+    /// a thread of the test) over a socket.
+    fn start_num_nodes_with_safety_rules_process(
+        num_nodes: usize,
+        quorum_voting_power: u64,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        executor_with_reconfig: bool,
+    ) -> Vec<Self> {
+        Self::start_nodes(
+            num_nodes,
+            0,
+            quorum_voting_power,
+            playground,
+            proposer_type,
+            executor_with_reconfig,
+            true,
+        )
+    }
+
+    fn start_nodes(
+        num_nodes: usize,
+        num_twins: usize,
+        quorum_voting_power: u64,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        executor_with_reconfig: bool,
+        safety_rules_process: bool,
+    ) -> Vec<Self> {
+        assert!(num_twins <= num_nodes);
+        let (signers, validators) =
             random_validator_verifier(num_nodes, Some(quorum_voting_power), true);
         let validator_set = if executor_with_reconfig {
             Some((&validators).into())
         } else {
             None
         };
+        let signers = signers.iter().chain(signers.iter().take(num_twins));
         let mut nodes = vec![];
-        for smr_id in 0..num_nodes {
+        for (smr_id, signer) in signers.enumerate() {
             let (initial_data, storage) = MockStorage::start_for_testing(validators.clone());
             let safety_rules_path = NamedTempFile::new().unwrap().into_temp_path().to_path_buf();
-            OnDiskStorage::default_storage(safety_rules_path.clone());
+            let mut safety_rules_storage =
+                OnDiskStorage::default_storage(safety_rules_path.clone());
+            let mut safety_rules = SafetyRulesConfig::default();
+            let mut safety_rules_dir = None;
+            if safety_rules_process {
+                let dir = tempfile::tempdir().unwrap();
+                let socket_path = dir.path().join("safety_rules.sock");
+                let service = ProcessService::bind(&socket_path).unwrap();
+                safety_rules_storage
+                    .add_consensus_key(signer.private_key())
+                    .unwrap();
+                let process = SafetyRules::new(safety_rules_storage, signer.author());
+                thread::spawn(move || service.serve::<TestPayload>(process));
+                safety_rules.service = SafetyRulesService::Process { socket_path };
+                safety_rules_dir = Some(dir);
+            } else {
+                safety_rules.backend = SafetyRulesBackend::OnDiskStorage {
+                    default: false,
+                    path: safety_rules_path,
+                    consensus_keys: None,
+                };
+            }
             nodes.push(Self::start(
                 playground,
-                signers.remove(0),
+                signer.clone(),
                 smr_id,
                 storage,
                 initial_data,
                 proposer_type,
                 validator_set.clone(),
-                safety_rules_path,
+                safety_rules,
+                safety_rules_dir,
             ));
         }
         nodes
@@ -913,3 +1004,34 @@ fn reconfiguration_test() {
         }
     });
 }
+
+#[test]
+/// Test that consensus reaches SafetyRules in its own process across reconfigurations, connecting
+/// anew on every epoch.
+fn reconfiguration_with_safety_rules_process_test() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+
+    let _nodes = SMRNode::start_num_nodes_with_safety_rules_process(
+        4,
+        3,
+        &mut playground,
+        MultipleOrderedProposers,
+        true,
+    );
+    block_on(async move {
+        for _ in 0..3 {
+            for _ in 0..3 {
+                playground
+                    .wait_for_messages(2, NetworkPlayground::proposals_only)
+                    .await;
+                playground
+                    .wait_for_messages(2, NetworkPlayground::votes_only)
+                    .await;
+            }
+            playground
+                .wait_for_messages(2, NetworkPlayground::epoch_change_only)
+                .await;
+        }
+    });
+}
@@ -48,3 +48,18 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks::<i64>().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_in_memory() {
+    let mut config = StorageConfig::default();
+    config.engine = StorageEngine::InMemory;
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new_with_config(&tmp_dir, &config);
+
+    let blocks = vec![Block::<i64>::make_genesis_block()];
+    let qcs = vec![certificate_for_genesis()];
+    db.save_blocks_and_quorum_certificates(blocks, qcs).unwrap();
+    assert_eq!(db.get_blocks::<i64>().unwrap().len(), 1);
+    assert_eq!(db.get_quorum_certificates().unwrap().len(), 1);
+    assert!(!tmp_dir.path().join("consensusdb").exists());
+}
@@ -12,6 +12,7 @@ use crate::chained_bft::consensusdb::schema::{
 };
 use consensus_types::{block::Block, common::Payload, quorum_cert::QuorumCert};
 use failure::prelude::*;
+use libra_config::config::{StorageConfig, StorageEngine};
 use libra_crypto::HashValue;
 use libra_logger::prelude::*;
 use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
@@ -29,6 +30,27 @@ pub struct ConsensusDB {
 
 impl ConsensusDB {
     pub fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
+        Self::new_with_config(db_root_path, &StorageConfig::default())
+    }
+
+    /// Same as [`ConsensusDB::new`], but on the engine chosen by `config`. With the in-memory
+    /// engine, `db_root_path` is ignored and nothing survives a restart.
+    pub fn new_with_config<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        config: &StorageConfig,
+    ) -> Self {
+        if config.engine == StorageEngine::InMemory {
+            info!("Opened ConsensusDB in memory");
+            return Self {
+                db: DB::open_in_memory(&[
+                    /* UNUSED CF = */ DEFAULT_CF_NAME,
+                    BLOCK_CF_NAME,
+                    QC_CF_NAME,
+                    SINGLE_ENTRY_CF_NAME,
+                ]),
+            };
+        }
+
         let cf_opts_map: ColumnFamilyOptionsMap = [
             (
                 /* UNUSED CF = */ DEFAULT_CF_NAME,
@@ -57,6 +79,23 @@ impl ConsensusDB {
         Self { db }
     }
 
+    /// Opens the ConsensusDB under `db_root_path` in read-only mode, e.g. to inspect the one of a
+    /// running node. All writes are refused.
+    pub fn open_readonly<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        let path = db_root_path.as_ref().join("consensusdb");
+        let db = DB::open_readonly(
+            path.clone(),
+            &[
+                /* UNUSED CF = */ DEFAULT_CF_NAME,
+                BLOCK_CF_NAME,
+                QC_CF_NAME,
+                SINGLE_ENTRY_CF_NAME,
+            ],
+        )?;
+        info!("Opened ConsensusDB at {:?} in read-only mode", path);
+        Ok(Self { db })
+    }
+
     pub fn get_data<T: Payload>(
         &self,
     ) -> Result<(
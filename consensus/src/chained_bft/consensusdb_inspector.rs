@@ -0,0 +1,163 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline inspection of the ConsensusDB of a node, e.g. after it misbehaved in production.
+//!
+//! The ConsensusDB of the node is opened in read-only mode, so that the node may keep running. Its
+//! contents can be dumped as they are stored, and given the latest ledger info of the node, its
+//! recovery can be replayed: the block tree is rebuilt out of them as on startup, the blocks being
+//! executed by a mock `StateComputer`, and its invariants are checked.
+
+use crate::{
+    chained_bft::{
+        block_storage::BlockStore, consensusdb::ConsensusDB, persistent_storage::RecoveryData,
+    },
+    state_replication::StateComputer,
+};
+use consensus_types::{
+    block::Block, executed_block::ExecutedBlock, quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate, vote::Vote,
+};
+use executor::{ExecutedTrees, ProcessedVMOutput};
+use failure::prelude::*;
+use futures::{executor::block_on, future, Future, FutureExt};
+use libra_config::config::NodeConfig;
+use libra_types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeEventWithProof, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+    transaction::SignedTransaction,
+};
+use rmp_serde::from_slice;
+use serde::Serialize;
+use std::{pin::Pin, sync::Arc};
+
+pub use crate::chained_bft::block_storage::BlockTreeSummary;
+
+type Payload = Vec<SignedTransaction>;
+
+/// The contents of a ConsensusDB.
+#[derive(Serialize)]
+pub struct ConsensusDBContents {
+    /// The last vote of the node, if any
+    pub last_vote: Option<Vote>,
+    /// The highest timeout certificate known to the node, if any
+    pub highest_timeout_certificate: Option<TimeoutCertificate>,
+    /// The blocks, ordered by epoch and round
+    pub blocks: Vec<Block<Payload>>,
+    /// The quorum certificates, ordered by epoch and round of the certified block
+    pub quorum_certs: Vec<QuorumCert>,
+}
+
+/// Reads the ConsensusDB of a node.
+pub struct ConsensusDBInspector {
+    consensus_db: ConsensusDB,
+}
+
+impl ConsensusDBInspector {
+    /// Opens the ConsensusDB in the storage directory of `config` in read-only mode.
+    pub fn open(config: &NodeConfig) -> Result<Self> {
+        Ok(Self {
+            consensus_db: ConsensusDB::open_readonly(config.get_storage_dir())?,
+        })
+    }
+
+    /// Returns the contents of the ConsensusDB.
+    pub fn contents(&self) -> Result<ConsensusDBContents> {
+        let (last_vote, highest_timeout_certificate, mut blocks, mut quorum_certs) =
+            self.consensus_db.get_data::<Payload>()?;
+        let last_vote = match last_vote {
+            Some(vote_data) => Some(
+                from_slice(&vote_data[..])
+                    .map_err(|e| format_err!("Unable to deserialize last vote msg: {}", e))?,
+            ),
+            None => None,
+        };
+        let highest_timeout_certificate = match highest_timeout_certificate {
+            Some(tc_data) => Some(from_slice(&tc_data[..]).map_err(|e| {
+                format_err!("Unable to deserialize highest timeout certificate: {}", e)
+            })?),
+            None => None,
+        };
+        blocks.sort_by_key(|block| (block.epoch(), block.round()));
+        quorum_certs.sort_by_key(|qc| (qc.certified_block().epoch(), qc.certified_block().round()));
+        Ok(ConsensusDBContents {
+            last_vote,
+            highest_timeout_certificate,
+            blocks,
+            quorum_certs,
+        })
+    }
+
+    /// Replays the recovery of the node: rebuilds its block tree out of the ConsensusDB and
+    /// `ledger_info`, the latest one committed to the storage of the node, through
+    /// `RecoveryData::new` as on startup, and checks the invariants of the tree. The blocks are
+    /// executed by a mock `StateComputer`, so that the replay is deterministic, and the executed
+    /// states aren't checked.
+    ///
+    /// Like the node, the replay panics if the blocks can't be inserted in the tree.
+    pub fn replay(
+        &self,
+        ledger_info: &LedgerInfo,
+        validators: ValidatorVerifier,
+    ) -> Result<BlockTreeSummary> {
+        let contents = self.contents()?;
+        let initial_data = RecoveryData::new(
+            contents.last_vote,
+            contents.blocks,
+            contents.quorum_certs,
+            ledger_info,
+            contents.highest_timeout_certificate,
+            Arc::new(validators),
+        )?;
+        block_on(BlockStore::replay(
+            initial_data,
+            Arc::new(ReplayStateComputer),
+        ))
+    }
+}
+
+/// A StateComputer which executes every block into empty trees, and refuses to commit.
+struct ReplayStateComputer;
+
+impl StateComputer for ReplayStateComputer {
+    type Payload = Payload;
+
+    fn compute(
+        &self,
+        _block: &Block<Self::Payload>,
+        _parent_executed_trees: ExecutedTrees,
+    ) -> Pin<Box<dyn Future<Output = Result<ProcessedVMOutput>> + Send>> {
+        future::ok(ProcessedVMOutput::new(
+            vec![],
+            ExecutedTrees::new_empty(),
+            None,
+        ))
+        .boxed()
+    }
+
+    fn commit(
+        &self,
+        _blocks: Vec<&ExecutedBlock<Self::Payload>>,
+        _commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        future::err(format_err!("Replays don't commit")).boxed()
+    }
+
+    fn sync_to(
+        &self,
+        _commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        future::err(format_err!("Replays don't sync")).boxed()
+    }
+
+    fn committed_trees(&self) -> ExecutedTrees {
+        ExecutedTrees::new_empty()
+    }
+
+    fn get_epoch_proof(
+        &self,
+        _start_epoch: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<ValidatorChangeEventWithProof>> + Send>> {
+        future::err(format_err!("Replays don't serve epoch proofs")).boxed()
+    }
+}
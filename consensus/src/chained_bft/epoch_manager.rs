@@ -4,27 +4,32 @@
 use crate::chained_bft::block_storage::{BlockReader, BlockStore};
 use crate::chained_bft::chained_bft_smr::ChainedBftSMRConfig;
 use crate::chained_bft::event_processor::EventProcessor;
+use crate::chained_bft::liveness::leader_reputation::LeaderReputation;
 use crate::chained_bft::liveness::multi_proposer_election::MultiProposer;
-use crate::chained_bft::liveness::pacemaker::{ExponentialTimeInterval, Pacemaker};
+use crate::chained_bft::liveness::pacemaker::{
+    AdaptiveTimeInterval, ExponentialTimeInterval, Pacemaker, PacemakerTimeInterval,
+};
 use crate::chained_bft::liveness::proposal_generator::ProposalGenerator;
 use crate::chained_bft::liveness::proposer_election::ProposerElection;
 use crate::chained_bft::liveness::rotating_proposer_election::{choose_leader, RotatingProposer};
+use crate::chained_bft::liveness::vrf_proposer_election::VrfProposer;
 use crate::chained_bft::network::NetworkSender;
 use crate::chained_bft::persistent_storage::{PersistentStorage, RecoveryData};
 use crate::counters;
 use crate::state_replication::{StateComputer, TxnManager};
 use crate::util::time_service::{ClockTimeService, TimeService};
-use consensus_types::common::{Payload, Round};
+use consensus_types::common::{Author, Payload, Round};
 use consensus_types::epoch_retrieval::EpochRetrievalRequest;
 use futures::executor::block_on;
-use libra_config::config::{ConsensusProposerType, SafetyRulesBackend};
+use libra_config::config::ConsensusProposerType;
+use libra_crypto::ed25519::Ed25519PrivateKey;
 use libra_logger::prelude::*;
 use libra_types::account_address::AccountAddress;
-use libra_types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier};
+use libra_types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier};
 use network::proto::ConsensusMsg;
 use network::proto::ConsensusMsg_oneof;
 use network::validator_network::{ConsensusNetworkSender, Event};
-use safety_rules::{InMemoryStorage, OnDiskStorage, SafetyRules};
+use safety_rules::safety_rules_client;
 use std::cmp::Ordering;
 use std::convert::TryInto;
 use std::sync::Arc;
@@ -41,9 +46,10 @@ pub struct EpochManager<T> {
     txn_manager: Arc<dyn TxnManager<Payload = T>>,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     storage: Arc<dyn PersistentStorage<T>>,
-    // TODO: remove once we have separate key management structure, and we'll share a slim client
-    // across epoch
-    signer: Arc<ValidatorSigner>,
+    author: Author,
+    // The consensus key read from the node config, added to the storage of SafetyRules at the
+    // start of each epoch. None when SafetyRules holds the consensus keys itself.
+    consensus_key: Option<Ed25519PrivateKey>,
 }
 
 impl<T: Payload> EpochManager<T> {
@@ -57,7 +63,8 @@ impl<T: Payload> EpochManager<T> {
         txn_manager: Arc<dyn TxnManager<Payload = T>>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         storage: Arc<dyn PersistentStorage<T>>,
-        signer: Arc<ValidatorSigner>,
+        author: Author,
+        consensus_key: Option<Ed25519PrivateKey>,
     ) -> Self {
         Self {
             epoch,
@@ -69,7 +76,8 @@ impl<T: Payload> EpochManager<T> {
             txn_manager,
             state_computer,
             storage,
-            signer,
+            author,
+            consensus_key,
         }
     }
 
@@ -80,11 +88,19 @@ impl<T: Payload> EpochManager<T> {
     ) -> Pacemaker {
         // 1.5^6 ~= 11
         // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
-        let time_interval = Box::new(ExponentialTimeInterval::new(
-            self.config.pacemaker_initial_timeout,
-            1.5,
-            6,
-        ));
+        let time_interval: Box<dyn PacemakerTimeInterval> = match &self.config.adaptive_pacemaker {
+            Some(config) => Box::new(AdaptiveTimeInterval::new(
+                self.config.pacemaker_initial_timeout,
+                config,
+                1.5,
+                6,
+            )),
+            None => Box::new(ExponentialTimeInterval::new(
+                self.config.pacemaker_initial_timeout,
+                1.5,
+                6,
+            )),
+        };
         Pacemaker::new(time_interval, time_service, timeout_sender)
     }
 
@@ -111,6 +127,17 @@ impl<T: Payload> EpochManager<T> {
                     self.config.contiguous_rounds,
                 ))
             }
+            ConsensusProposerType::VrfProposer => Box::new(VrfProposer::new(
+                epoch,
+                validators,
+                self.config.vrf_expected_proposers,
+            )),
+            ConsensusProposerType::LeaderReputation => Box::new(LeaderReputation::new(
+                epoch,
+                proposers,
+                self.config.leader_reputation_window_size,
+                self.config.leader_reputation_exclude_rounds,
+            )),
         }
     }
 
@@ -170,14 +197,10 @@ impl<T: Payload> EpochManager<T> {
         self.state_computer.sync_to_or_bail(ledger_info.clone());
         let initial_data = self.storage.start();
         self.epoch = initial_data.epoch();
-        self.start_epoch(self.signer.clone(), initial_data)
+        self.start_epoch(initial_data)
     }
 
-    pub fn start_epoch(
-        &self,
-        signer: Arc<ValidatorSigner>,
-        initial_data: RecoveryData<T>,
-    ) -> EventProcessor<T> {
+    pub fn start_epoch(&self, initial_data: RecoveryData<T>) -> EventProcessor<T> {
         let validators = initial_data.validators();
         counters::EPOCH.set(self.epoch as i64);
         counters::CURRENT_EPOCH_VALIDATORS.set(validators.len() as i64);
@@ -189,18 +212,13 @@ impl<T: Payload> EpochManager<T> {
             validators,
         );
         let last_vote = initial_data.last_vote();
-        let author = signer.author();
-        let safety_rules_storage = match &self.config.safety_rules.backend {
-            SafetyRulesBackend::InMemoryStorage => InMemoryStorage::default_storage(),
-            SafetyRulesBackend::OnDiskStorage { default, path } => {
-                if *default {
-                    OnDiskStorage::default_storage(path.clone())
-                } else {
-                    OnDiskStorage::new_storage(path.clone())
-                }
-            }
-        };
-        let mut safety_rules = SafetyRules::new(safety_rules_storage, signer);
+        let author = self.author;
+        let mut safety_rules = safety_rules_client(
+            &self.config.safety_rules,
+            author,
+            self.consensus_key.as_ref(),
+        )
+        .expect("Unable to reach SafetyRules");
 
         let block_store = Arc::new(block_on(BlockStore::new(
             Arc::clone(&self.storage),
@@ -209,7 +227,9 @@ impl<T: Payload> EpochManager<T> {
             self.config.max_pruned_blocks_in_mem,
         )));
 
-        safety_rules.start_new_epoch(block_store.highest_quorum_cert().as_ref());
+        safety_rules
+            .start_new_epoch(block_store.highest_quorum_cert().as_ref())
+            .expect("Unable to start a new epoch in SafetyRules");
 
         // txn manager is required both by proposal generator (to pull the proposers)
         // and by event processor (to update their status).
@@ -244,6 +264,7 @@ impl<T: Payload> EpochManager<T> {
             self.storage.clone(),
             self.time_service.clone(),
             validators,
+            self.config.signature_scheme,
         )
     }
 }
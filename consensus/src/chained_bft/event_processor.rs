@@ -31,6 +31,7 @@ use consensus_types::{
     vote_proposal::VoteProposal,
 };
 use failure::ResultExt;
+use libra_config::config::ConsensusSignatureScheme;
 use libra_crypto::hash::TransactionAccumulatorHasher;
 use libra_logger::prelude::*;
 use libra_prost_ext::MessageExt;
@@ -47,7 +48,7 @@ use crate::chained_bft::network::IncomingBlockRetrievalRequest;
 use consensus_types::block_retrieval::{BlockRetrievalResponse, BlockRetrievalStatus};
 #[cfg(test)]
 use safety_rules::ConsensusState;
-use safety_rules::SafetyRules;
+use safety_rules::TSafetyRules;
 use std::convert::TryInto;
 use std::time::Instant;
 use std::{sync::Arc, time::Duration};
@@ -66,12 +67,12 @@ pub mod event_processor_fuzzing;
 /// etc.). It is exposing the async processing functions for each event type.
 /// The caller is responsible for running the event loops and driving the execution via some
 /// executors.
-pub struct EventProcessor<T> {
+pub struct EventProcessor<T: Payload> {
     block_store: Arc<BlockStore<T>>,
     pacemaker: Pacemaker,
     proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
     proposal_generator: ProposalGenerator<T>,
-    safety_rules: SafetyRules,
+    safety_rules: Box<dyn TSafetyRules<T> + Send + Sync>,
     txn_manager: Arc<dyn TxnManager<Payload = T>>,
     network: NetworkSender,
     storage: Arc<dyn PersistentStorage<T>>,
@@ -79,6 +80,8 @@ pub struct EventProcessor<T> {
     // Cache of the last sent vote message.
     last_vote_sent: Option<(Vote, Round)>,
     validators: Arc<ValidatorVerifier>,
+    // With Bls12381, the votes sent and received carry signatures that can be aggregated.
+    signature_scheme: ConsensusSignatureScheme,
 }
 
 impl<T: Payload> EventProcessor<T> {
@@ -88,12 +91,13 @@ impl<T: Payload> EventProcessor<T> {
         pacemaker: Pacemaker,
         proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
         proposal_generator: ProposalGenerator<T>,
-        safety_rules: SafetyRules,
+        safety_rules: Box<dyn TSafetyRules<T> + Send + Sync>,
         txn_manager: Arc<dyn TxnManager<Payload = T>>,
         network: NetworkSender,
         storage: Arc<dyn PersistentStorage<T>>,
         time_service: Arc<dyn TimeService>,
         validators: Arc<ValidatorVerifier>,
+        signature_scheme: ConsensusSignatureScheme,
     ) -> Self {
         counters::BLOCK_RETRIEVAL_COUNT.get();
         counters::STATE_SYNC_COUNT.get();
@@ -102,7 +106,7 @@ impl<T: Payload> EventProcessor<T> {
             (v, round)
         });
 
-        Self {
+        let mut event_processor = Self {
             block_store,
             pacemaker,
             proposer_election,
@@ -114,6 +118,22 @@ impl<T: Payload> EventProcessor<T> {
             time_service,
             last_vote_sent,
             validators,
+            signature_scheme,
+        };
+        event_processor.load_committed_history();
+        event_processor
+    }
+
+    /// Load the history of the committed blocks the proposer election depends on from storage:
+    /// the blocks committed before a restart or a state sync didn't go through process_commit.
+    fn load_committed_history(&mut self) {
+        let num_blocks = self.proposer_election.committed_history_size();
+        if num_blocks == 0 {
+            return;
+        }
+        match self.storage.get_committed_history(num_blocks) {
+            Ok(history) => self.proposer_election.process_committed_history(history),
+            Err(e) => error!("Failed to load the committed history from storage: {}", e),
         }
     }
 
@@ -152,7 +172,31 @@ impl<T: Payload> EventProcessor<T> {
         {
             return;
         }
-        let proposal_msg = match self.generate_proposal(new_round_event).await {
+        // Proposers elected privately find out whether they are by evaluating the VRF of the
+        // election in SafetyRules, over the highest quorum certificate they would extend.
+        let parent_id = self
+            .block_store
+            .highest_quorum_cert()
+            .certified_block()
+            .id();
+        let proposer_proof = match self
+            .proposer_election
+            .vrf_input(new_round_event.round, parent_id)
+        {
+            Some(vrf_input) => match self.safety_rules.vrf_prove(&vrf_input) {
+                Ok(proof) if self.proposer_election.is_elected(&proof) => Some(proof),
+                Ok(_) => return,
+                Err(e) => {
+                    error!("Error while proving the election of a proposer: {:?}", e);
+                    return;
+                }
+            },
+            None => None,
+        };
+        let proposal_msg = match self
+            .generate_proposal(new_round_event, proposer_proof)
+            .await
+        {
             Ok(x) => x,
             Err(e) => {
                 error!("Error while generating proposal: {:?}", e);
@@ -167,6 +211,7 @@ impl<T: Payload> EventProcessor<T> {
     async fn generate_proposal(
         &self,
         new_round_event: NewRoundEvent,
+        proposer_proof: Option<Vec<u8>>,
     ) -> failure::Result<ProposalMsg<T>> {
         // Proposal generator will ensure that at most one proposal is generated per round
         let proposal = self
@@ -179,7 +224,14 @@ impl<T: Payload> EventProcessor<T> {
         let signed_proposal = self.safety_rules.sign_proposal(proposal)?;
         debug!("Propose {}", signed_proposal);
         // return proposal
-        Ok(ProposalMsg::new(signed_proposal, self.gen_sync_info()))
+        Ok(match proposer_proof {
+            Some(proposer_proof) => ProposalMsg::new_with_proposer_proof(
+                signed_proposal,
+                self.gen_sync_info(),
+                proposer_proof,
+            ),
+            None => ProposalMsg::new(signed_proposal, self.gen_sync_info()),
+        })
     }
 
     /// Process a ProposalMsg, pre_process would bring all the dependencies and filter out invalid
@@ -204,11 +256,7 @@ impl<T: Payload> EventProcessor<T> {
         if proposal_msg.round() < current_round {
             return None;
         }
-        if self
-            .proposer_election
-            .is_valid_proposer(proposal_msg.proposer(), proposal_msg.round())
-            .is_none()
-        {
+        if !self.proposer_election.is_valid_proposal(&proposal_msg) {
             warn!(
                 "Proposer {} for block {} is not a valid proposer for this round",
                 proposal_msg.proposer(),
@@ -233,8 +281,7 @@ impl<T: Payload> EventProcessor<T> {
             return None;
         }
 
-        self.proposer_election
-            .process_proposal(proposal_msg.take_proposal())
+        self.proposer_election.process_proposal_msg(proposal_msg)
     }
 
     /// In case some peer's round or HQC is stale, send a SyncInfo message to that peer.
@@ -330,17 +377,24 @@ impl<T: Payload> EventProcessor<T> {
             sync_info.hqc_round(),
             deadline_repr,
         );
-        self.block_store
+        let root_id = self.block_store.root().id();
+        let sync_result = self
+            .block_store
             .sync_to(&sync_info, self.create_block_retriever(deadline, author))
-            .await
-            .map_err(|e| {
-                warn!(
-                    "Fail to sync up to HQC @ round {}: {}",
-                    sync_info.hqc_round(),
-                    e
-                );
+            .await;
+        // The root only moves outside of process_commit through state sync, even if fetching the
+        // blocks above it failed afterwards.
+        if self.block_store.root().id() != root_id {
+            self.load_committed_history();
+        }
+        sync_result.map_err(|e| {
+            warn!(
+                "Fail to sync up to HQC @ round {}: {}",
+                sync_info.hqc_round(),
                 e
-            })?;
+            );
+            e
+        })?;
         debug!("Caught up to HQC at round {}", sync_info.hqc_round());
 
         // Update the block store and potentially start a new round.
@@ -373,7 +427,13 @@ impl<T: Payload> EventProcessor<T> {
             // The timeout event is late: the node has already moved to another round.
             return;
         }
-        let last_voted_round = self.safety_rules.consensus_state().last_voted_round();
+        let last_voted_round = match self.safety_rules.consensus_state() {
+            Ok(consensus_state) => consensus_state.last_voted_round(),
+            Err(e) => {
+                error!("Unable to get the consensus state from SafetyRules: {}", e);
+                return;
+            }
+        };
         warn!(
             "Round {} timed out: {}, expected round proposer was {:?}, broadcasting the vote to all replicas",
             round,
@@ -407,6 +467,18 @@ impl<T: Payload> EventProcessor<T> {
                 }
             }
         }
+        if self.signature_scheme == ConsensusSignatureScheme::Bls12381
+            && timeout_vote.aggregatable_timeout_signature().is_none()
+        {
+            let timeout = timeout_vote.timeout();
+            match self.safety_rules.sign_timeout_aggregatable(&timeout) {
+                Ok(signature) => timeout_vote.add_aggregatable_timeout_signature(signature),
+                Err(e) => {
+                    error!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), timeout, e);
+                    return;
+                }
+            }
+        }
 
         let timeout_vote_msg = VoteMsg::new(timeout_vote, self.gen_sync_info());
         self.network.broadcast_vote(timeout_vote_msg).await
@@ -440,8 +512,8 @@ impl<T: Payload> EventProcessor<T> {
         qc: &QuorumCert,
         tc: Option<&TimeoutCertificate>,
     ) -> failure::Result<()> {
-        self.safety_rules.update(qc);
-        let consensus_state = self.safety_rules.consensus_state();
+        self.safety_rules.update(qc)?;
+        let consensus_state = self.safety_rules.consensus_state()?;
         counters::PREFERRED_BLOCK_ROUND.set(consensus_state.preferred_round() as i64);
 
         let mut highest_committed_proposal_round = None;
@@ -518,13 +590,19 @@ impl<T: Payload> EventProcessor<T> {
         // Safety invariant: The last voted round is updated to be the same as the proposed block's
         // round. At this point, the replica has decided to vote for the proposed block.
         debug_checked_verify_eq!(
-            self.safety_rules.consensus_state().last_voted_round(),
-            proposal_round
+            self.safety_rules
+                .consensus_state()
+                .map(|consensus_state| consensus_state.last_voted_round()),
+            Ok(proposal_round)
         );
         // Safety invariant: qc_parent <-- qc
         // the preferred block round must be at least as large as qc_parent's round.
         debug_checked_verify!(
-            self.safety_rules.consensus_state().preferred_round() >= certified_parent_block_round
+            self.safety_rules
+                .consensus_state()
+                .map(|consensus_state| consensus_state.preferred_round()
+                    >= certified_parent_block_round)
+                .unwrap_or(false)
         );
 
         let recipients = self
@@ -672,12 +750,19 @@ impl<T: Payload> EventProcessor<T> {
                 .clone(),
         );
 
-        let vote = self
+        let mut vote = self
             .safety_rules
             .construct_and_sign_vote(&vote_proposal)
             .with_context(|e| format!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e))?;
+        if self.signature_scheme == ConsensusSignatureScheme::Bls12381 {
+            let signature = self
+                .safety_rules
+                .sign_vote_aggregatable(&vote)
+                .with_context(|e| format!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e))?;
+            vote.add_aggregatable_signature(signature);
+        }
 
-        let consensus_state = self.safety_rules.consensus_state();
+        let consensus_state = self.safety_rules.consensus_state()?;
         counters::LAST_VOTE_ROUND.set(consensus_state.last_voted_round() as i64);
 
         self.storage
@@ -693,6 +778,20 @@ impl<T: Payload> EventProcessor<T> {
     /// 2. Add the vote to the store and check whether it finishes a QC.
     /// 3. Once the QC successfully formed, notify the Pacemaker.
     pub async fn process_vote(&mut self, vote_msg: VoteMsg) {
+        // With signatures aggregated into the certificates, a vote that can't be aggregated is
+        // useless.
+        if self.signature_scheme == ConsensusSignatureScheme::Bls12381 {
+            let vote = vote_msg.vote();
+            if vote.aggregatable_signature().is_none()
+                || (vote.is_timeout() && vote.aggregatable_timeout_signature().is_none())
+            {
+                security_log(SecurityEvent::InvalidConsensusVote)
+                    .error("MissingAggregatableSignature")
+                    .data(&vote_msg)
+                    .log();
+                return;
+            }
+        }
         // Check whether this validator is a valid recipient of the vote.
         if !vote_msg.vote().is_timeout() {
             // Unlike timeout votes regular votes are sent to the leaders of the next round only.
@@ -794,6 +893,8 @@ impl<T: Payload> EventProcessor<T> {
         // Multiple blocks might be committed at once: notify about all the transactions in the
         // path from the old root to the new root.
         for committed in blocks_to_commit {
+            self.proposer_election
+                .process_committed_block(committed.block());
             if let Some(time_to_commit) = duration_since_epoch()
                 .checked_sub(Duration::from_micros(committed.timestamp_usecs()))
             {
@@ -882,7 +983,7 @@ impl<T: Payload> EventProcessor<T> {
     /// Inspect the current consensus state.
     #[cfg(test)]
     pub fn consensus_state(&self) -> ConsensusState {
-        self.safety_rules.consensus_state()
+        self.safety_rules.consensus_state().unwrap()
     }
 
     pub fn block_store(&self) -> Arc<BlockStore<T>> {
@@ -19,6 +19,7 @@ use crate::{
 use consensus_types::proposal_msg::{ProposalMsg, ProposalUncheckedSignatures};
 use futures::{channel::mpsc, executor::block_on};
 use lazy_static::lazy_static;
+use libra_config::config::ConsensusSignatureScheme;
 use libra_prost_ext::MessageExt;
 use libra_types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier};
 use network::{proto::Proposal, validator_network::ConsensusNetworkSender};
@@ -91,8 +92,11 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
     let (initial_data, storage) = MockStorage::<TestPayload>::start_for_testing(validator);
 
     // TODO: remove
-    let safety_rules =
-        SafetyRules::new(InMemoryStorage::default_storage(), Arc::new(signer.clone()));
+    let mut safety_rules_storage = InMemoryStorage::default_storage();
+    safety_rules_storage
+        .add_consensus_key(signer.private_key())
+        .unwrap();
+    let safety_rules = Box::new(SafetyRules::new(safety_rules_storage, signer.author()));
 
     // TODO: mock channels
     let (network_reqs_tx, _network_reqs_rx) = channel::new_test(8);
@@ -141,6 +145,7 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
         storage.clone(),
         time_service,
         validators,
+        ConsensusSignatureScheme::Ed25519,
     )
 }
 
@@ -45,10 +45,12 @@ use futures::{
     channel::{mpsc, oneshot},
     executor::block_on,
 };
-use libra_crypto::HashValue;
+use libra_config::config::ConsensusSignatureScheme;
+use libra_crypto::{hash::CryptoHash, HashValue};
 use libra_types::block_info::BlockInfo;
 use libra_types::crypto_proxies::{
-    random_validator_verifier, LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier,
+    random_bls_validator_verifier, random_validator_verifier, LedgerInfoWithSignatures,
+    ValidatorSigner, ValidatorVerifier,
 };
 use network::{
     proto::{ConsensusMsg, ConsensusMsg_oneof},
@@ -70,6 +72,7 @@ pub struct NodeSetup {
     proposer_author: Author,
     validators: Arc<ValidatorVerifier>,
     safety_rules_file: PathBuf,
+    signature_scheme: ConsensusSignatureScheme,
 }
 
 impl NodeSetup {
@@ -91,7 +94,26 @@ impl NodeSetup {
         executor: TaskExecutor,
         num_nodes: usize,
     ) -> Vec<NodeSetup> {
-        let (signers, validators) = random_validator_verifier(num_nodes, None, false);
+        Self::create_nodes_with_signature_scheme(
+            playground,
+            executor,
+            num_nodes,
+            ConsensusSignatureScheme::Ed25519,
+        )
+    }
+
+    fn create_nodes_with_signature_scheme(
+        playground: &mut NetworkPlayground,
+        executor: TaskExecutor,
+        num_nodes: usize,
+        signature_scheme: ConsensusSignatureScheme,
+    ) -> Vec<NodeSetup> {
+        let (signers, validators) = match signature_scheme {
+            ConsensusSignatureScheme::Ed25519 => random_validator_verifier(num_nodes, None, false),
+            ConsensusSignatureScheme::Bls12381 => {
+                random_bls_validator_verifier(num_nodes, None, false)
+            }
+        };
         let proposer_author = signers[0].author();
         let mut nodes = vec![];
         for signer in signers.iter().take(num_nodes) {
@@ -109,6 +131,7 @@ impl NodeSetup {
                 storage,
                 initial_data,
                 safety_rules_file,
+                signature_scheme,
             ));
         }
         nodes
@@ -122,6 +145,7 @@ impl NodeSetup {
         storage: Arc<MockStorage<TestPayload>>,
         initial_data: RecoveryData<TestPayload>,
         safety_rules_file: PathBuf,
+        signature_scheme: ConsensusSignatureScheme,
     ) -> Self {
         let validators = initial_data.validators();
         let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
@@ -171,10 +195,11 @@ impl NodeSetup {
             1,
         );
 
-        let safety_rules = SafetyRules::new(
-            OnDiskStorage::new_storage(safety_rules_file.clone()),
-            Arc::new(signer.clone()),
-        );
+        let mut safety_rules_storage = OnDiskStorage::new_storage(safety_rules_file.clone());
+        safety_rules_storage
+            .add_consensus_key(signer.private_key())
+            .unwrap();
+        let safety_rules = Box::new(SafetyRules::new(safety_rules_storage, signer.author()));
 
         let pacemaker = Self::create_pacemaker(time_service.clone());
 
@@ -191,6 +216,7 @@ impl NodeSetup {
             storage.clone(),
             time_service,
             validators.clone(),
+            signature_scheme,
         );
         block_on(event_processor.start());
         Self {
@@ -202,6 +228,7 @@ impl NodeSetup {
             proposer_author,
             validators,
             safety_rules_file,
+            signature_scheme,
         }
     }
 
@@ -218,6 +245,7 @@ impl NodeSetup {
             self.storage,
             recover_data,
             self.safety_rules_file,
+            self.signature_scheme,
         )
     }
 }
@@ -364,7 +392,7 @@ fn process_successful_proposal_test() {
             proposal_id
         );
         assert_eq!(
-            node.event_processor.safety_rules.consensus_state(),
+            node.event_processor.consensus_state(),
             ConsensusState::new(1, 1, 0),
         );
     });
@@ -685,6 +713,71 @@ fn process_votes_basic_test() {
     runtime.shutdown_now();
 }
 
+#[test]
+/// With aggregated signatures, a vote without a signature that can be aggregated is ignored and
+/// the QC carries the aggregate of the signatures of the votes.
+fn process_votes_aggregated_test() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut node = NodeSetup::create_nodes_with_signature_scheme(
+        &mut playground,
+        runtime.executor(),
+        1,
+        ConsensusSignatureScheme::Bls12381,
+    )
+    .pop()
+    .unwrap();
+    let genesis = node.block_store.root();
+    let mut inserter = TreeInserter::new_with_store(node.signer.clone(), node.block_store.clone());
+    let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+    let executed_state = &a1.compute_result().executed_state;
+
+    let vote_data = VoteData::new(
+        BlockInfo::new(
+            a1.quorum_cert().certified_block().epoch(),
+            a1.round(),
+            a1.id(),
+            executed_state.state_id,
+            executed_state.version,
+            a1.timestamp_usecs(),
+            executed_state.validators.clone(),
+        ),
+        a1.quorum_cert().certified_block().clone(),
+    );
+    let mut vote = Vote::new(
+        vote_data,
+        node.signer.author(),
+        placeholder_ledger_info(),
+        &node.signer,
+    );
+
+    block_on(async move {
+        node.event_processor
+            .process_vote(VoteMsg::new(
+                vote.clone(),
+                test_utils::placeholder_sync_info(),
+            ))
+            .await;
+        assert_eq!(
+            node.block_store
+                .highest_quorum_cert()
+                .certified_block()
+                .id(),
+            genesis.id()
+        );
+
+        vote.add_aggregatable_signature(node.signer.bls_sign_message(vote.ledger_info().hash()));
+        node.event_processor
+            .process_vote(VoteMsg::new(vote, test_utils::placeholder_sync_info()))
+            .await;
+        let qc = node.block_store.highest_quorum_cert();
+        assert_eq!(qc.certified_block().id(), a1.id());
+        assert!(qc.ledger_info().signatures().is_empty());
+        assert!(qc.verify(&node.validators).is_ok());
+    });
+    runtime.shutdown_now();
+}
+
 #[test]
 fn process_block_retrieval() {
     let runtime = consensus_runtime();
@@ -860,3 +953,36 @@ fn nil_vote_on_timeout() {
         assert_eq!(vote.vote_data().parent().id(), node.block_store.root().id());
     });
 }
+
+#[test]
+/// With aggregated signatures, the timeout vote carries signatures that can be aggregated.
+fn aggregatable_nil_vote_on_timeout() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    // It needs 2 nodes to test network message.
+    let mut nodes = NodeSetup::create_nodes_with_signature_scheme(
+        &mut playground,
+        runtime.executor(),
+        2,
+        ConsensusSignatureScheme::Bls12381,
+    );
+    let node = &mut nodes[0];
+    block_on(async move {
+        node.event_processor.process_local_timeout(1).await;
+        let vote_msg = VoteMsg::try_from(
+            playground
+                .wait_for_messages(1, NetworkPlayground::timeout_votes_only)
+                .await[0]
+                .1
+                .clone(),
+        )
+        .unwrap();
+
+        let vote = vote_msg.vote();
+
+        assert!(vote.is_timeout());
+        assert!(vote.aggregatable_signature().is_some());
+        assert!(vote.aggregatable_timeout_signature().is_some());
+        assert!(vote.verify(&node.validators).is_ok());
+    });
+}
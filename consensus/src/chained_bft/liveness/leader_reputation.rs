@@ -0,0 +1,155 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::liveness::{
+    multi_proposer_election::next, proposer_election::ProposerElection,
+};
+use consensus_types::{
+    block::Block,
+    common::{Author, Payload, Round},
+};
+use libra_types::block_metadata::BlockMetadata;
+use std::collections::{HashSet, VecDeque};
+
+/// Weight of the validators which proposed or voted for any block of the window.
+pub const ACTIVE_WEIGHT: u64 = 100;
+/// Weight of the other validators, which are most likely down. They are still elected once in a
+/// while, so that they can prove they are back.
+pub const INACTIVE_WEIGHT: u64 = 1;
+
+/// LeaderReputation maps a round to a single author, weighting candidates by their recent
+/// history. A validator which neither proposed nor voted for any of the last `window_size`
+/// committed blocks, as recorded in their `BlockMetadata`, is most likely down: it is elected
+/// `ACTIVE_WEIGHT` times less often than the others, instead of getting its turn and letting the
+/// round time out. The leader is drawn from the weights with hash(epoch | round), as in the
+/// `MultiProposer`.
+///
+/// All honest replicas must agree on the history: the window of a round only includes blocks
+/// committed at least `exclude_rounds` rounds before it, which they all have committed by then.
+/// The history starts anew with each epoch. A validator which didn't commit all the blocks of the
+/// epoch itself (e.g. after a restart or a state sync) loads the `BlockMetadata` of the last ones
+/// from storage with `process_committed_history()`.
+pub struct LeaderReputation {
+    // Epoch is used as seed for electing different leaders per-epoch
+    epoch: u64,
+    // Ordering of proposers to choose from (all honest replicas must agree on this)
+    proposers: Vec<Author>,
+    // Number of committed blocks the history of a round is made of
+    window_size: usize,
+    // Number of rounds before a round from which its window starts
+    exclude_rounds: Round,
+    // The most recently committed blocks, oldest first. Only the ones that can be part of the
+    // windows of upcoming rounds are kept.
+    history: VecDeque<BlockMetadata>,
+}
+
+impl LeaderReputation {
+    pub fn new(
+        epoch: u64,
+        proposers: Vec<Author>,
+        window_size: usize,
+        exclude_rounds: Round,
+    ) -> Self {
+        assert!(!proposers.is_empty());
+        Self {
+            epoch,
+            proposers,
+            window_size,
+            exclude_rounds,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Returns the weight of each proposer for `round`, in the order of `proposers`.
+    pub fn get_weights(&self, round: Round) -> Vec<u64> {
+        let mut active = HashSet::new();
+        for metadata in self
+            .history
+            .iter()
+            .rev()
+            .filter(|metadata| metadata.round() + self.exclude_rounds <= round)
+            .take(self.window_size)
+        {
+            active.insert(metadata.proposer());
+            active.extend(metadata.previous_block_voters(&self.proposers));
+        }
+        self.proposers
+            .iter()
+            .map(|proposer| {
+                if active.contains(proposer) {
+                    ACTIVE_WEIGHT
+                } else {
+                    INACTIVE_WEIGHT
+                }
+            })
+            .collect()
+    }
+
+    /// The windows of the rounds to come only need the last `exclude_rounds` committed blocks and
+    /// the `window_size` ones before them.
+    fn history_size(&self) -> usize {
+        self.window_size + self.exclude_rounds as usize
+    }
+
+    fn get_proposer(&self, round: Round) -> Author {
+        let weights = self.get_weights(round);
+        // state = epoch | round
+        let mut state = self.epoch.to_le_bytes().to_vec();
+        state.extend_from_slice(&round.to_le_bytes());
+        // note: this modular reduction has a slight bias, see the MultiProposer.
+        let mut target = next(&mut state) % weights.iter().sum::<u64>();
+        for (proposer, weight) in self.proposers.iter().zip(weights) {
+            if target < weight {
+                return *proposer;
+            }
+            target -= weight;
+        }
+        unreachable!("The target is lower than the sum of the weights.")
+    }
+}
+
+impl<T: Payload> ProposerElection<T> for LeaderReputation {
+    fn is_valid_proposer(&self, author: Author, round: Round) -> Option<Author> {
+        if self.get_proposer(round) == author {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers(&self, round: Round) -> Vec<Author> {
+        vec![self.get_proposer(round)]
+    }
+
+    fn process_proposal(&mut self, proposal: Block<T>) -> Option<Block<T>> {
+        let round_author = self.get_proposer(proposal.round());
+        if Some(round_author) != proposal.author() {
+            None
+        } else {
+            Some(proposal)
+        }
+    }
+
+    fn take_backup_proposal(&mut self, _round: Round) -> Option<Block<T>> {
+        None
+    }
+
+    fn process_committed_block(&mut self, block: &Block<T>) {
+        // Blocks are committed in increasing rounds, so the oldest ones go first.
+        self.history.push_back(BlockMetadata::from(block));
+        while self.history.len() > self.history_size() {
+            self.history.pop_front();
+        }
+    }
+
+    fn committed_history_size(&self) -> usize {
+        self.history_size()
+    }
+
+    fn process_committed_history(&mut self, history: Vec<BlockMetadata>) {
+        self.history = history.into();
+        while self.history.len() > self.history_size() {
+            self.history.pop_front();
+        }
+    }
+}
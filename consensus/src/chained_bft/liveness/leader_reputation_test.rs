@@ -0,0 +1,199 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::liveness::{
+    leader_reputation::{LeaderReputation, ACTIVE_WEIGHT, INACTIVE_WEIGHT},
+    multi_proposer_election,
+    proposer_election::ProposerElection,
+};
+use consensus_types::{
+    block::block_test_utils::{certificate_for_genesis, gen_test_certificate},
+    block::Block,
+    quorum_cert::QuorumCert,
+};
+use libra_crypto::hash::CryptoHash;
+use libra_types::{
+    block_info::BlockInfo,
+    block_metadata::BlockMetadata,
+    crypto_proxies::{random_bls_validator_verifier, LedgerInfoWithSignatures, ValidatorSigner},
+};
+
+fn make_signers(num_validators: u8) -> Vec<ValidatorSigner> {
+    (0..num_validators)
+        .map(|i| ValidatorSigner::random([i; 32]))
+        .collect()
+}
+
+/// Replays the weighted draw of the leader of `round`.
+fn expected_proposer(
+    epoch: u64,
+    signers: &[ValidatorSigner],
+    weights: &[u64],
+    round: u64,
+) -> ValidatorSigner {
+    let mut state = epoch.to_le_bytes().to_vec();
+    state.extend_from_slice(&round.to_le_bytes());
+    let mut target = multi_proposer_election::next(&mut state) % weights.iter().sum::<u64>();
+    for (signer, weight) in signers.iter().zip(weights) {
+        if target < *weight {
+            return signer.clone();
+        }
+        target -= weight;
+    }
+    unreachable!()
+}
+
+#[test]
+fn test_leader_reputation_without_history() {
+    let signers = make_signers(4);
+    let proposers = signers.iter().map(ValidatorSigner::author).collect();
+    let mut pe = LeaderReputation::new(1, proposers, 10, 2);
+    assert_eq!(pe.get_weights(5), vec![INACTIVE_WEIGHT; 4]);
+
+    // Without history, all the validators are equally likely to be elected.
+    let leader = expected_proposer(1, &signers, &[INACTIVE_WEIGHT; 4], 5);
+    let pe: &mut dyn ProposerElection<u32> = &mut pe;
+    assert_eq!(pe.get_valid_proposers(5), vec![leader.author()]);
+    assert_eq!(
+        pe.is_valid_proposer(leader.author(), 5),
+        Some(leader.author())
+    );
+    let proposal = Block::new_proposal(1, 5, 1, certificate_for_genesis(), &leader);
+    assert_eq!(pe.process_proposal(proposal.clone()), Some(proposal));
+    assert_eq!(pe.take_backup_proposal(5), None);
+}
+
+#[test]
+fn test_leader_reputation_skips_inactive() {
+    let signers = make_signers(4);
+    let proposers = signers.iter().map(ValidatorSigner::author).collect();
+    let mut pe = LeaderReputation::new(1, proposers, 2, 2);
+
+    // Validator 0 proposes a block carrying the votes of validators 1 and 2, while validator 3
+    // stays silent.
+    let qc = gen_test_certificate(
+        vec![&signers[1], &signers[2]],
+        BlockInfo::empty(),
+        BlockInfo::empty(),
+        None,
+    );
+    let block = Block::new_proposal(1, 1, 1, qc, &signers[0]);
+    ProposerElection::<u32>::process_committed_block(&mut pe, &block);
+
+    // The block is only part of the history of the rounds at least 2 rounds later.
+    assert_eq!(pe.get_weights(2), vec![INACTIVE_WEIGHT; 4]);
+    let weights = vec![ACTIVE_WEIGHT, ACTIVE_WEIGHT, ACTIVE_WEIGHT, INACTIVE_WEIGHT];
+    for round in 3..20 {
+        assert_eq!(pe.get_weights(round), weights);
+        let leader = expected_proposer(1, &signers, &weights, round);
+        assert_eq!(
+            ProposerElection::<u32>::get_valid_proposers(&pe, round),
+            vec![leader.author()]
+        );
+    }
+
+    // Once the window moves past the block, validator 1 is the only one left active.
+    for round in 2..4 {
+        let block = Block::new_proposal(1, round, 1, certificate_for_genesis(), &signers[1]);
+        ProposerElection::<u32>::process_committed_block(&mut pe, &block);
+    }
+    assert_eq!(
+        pe.get_weights(5),
+        vec![
+            INACTIVE_WEIGHT,
+            ACTIVE_WEIGHT,
+            INACTIVE_WEIGHT,
+            INACTIVE_WEIGHT
+        ]
+    );
+}
+
+#[test]
+fn test_leader_reputation_committed_history() {
+    let signers = make_signers(4);
+    let proposers: Vec<_> = signers.iter().map(ValidatorSigner::author).collect();
+    let mut pe = LeaderReputation::new(1, proposers.clone(), 2, 2);
+    assert_eq!(ProposerElection::<u32>::committed_history_size(&pe), 4);
+
+    // Validator 3 proposes blocks carrying the votes of validator 2, then validator 1 proposes
+    // blocks carrying the votes of validator 0.
+    let blocks: Vec<_> = (1..7)
+        .map(|round| {
+            let (proposer, voter) = if round < 4 { (3, 2) } else { (1, 0) };
+            let qc = gen_test_certificate(
+                vec![&signers[voter]],
+                BlockInfo::empty(),
+                BlockInfo::empty(),
+                None,
+            );
+            Block::new_proposal(1, round, 1, qc, &signers[proposer])
+        })
+        .collect();
+    for block in &blocks {
+        ProposerElection::<u32>::process_committed_block(&mut pe, block);
+    }
+
+    // A validator restarting with the metadata of the committed blocks loaded from storage
+    // agrees on the weights, even if it's given more history than it needs.
+    let mut restarted_pe = LeaderReputation::new(1, proposers, 2, 2);
+    ProposerElection::<u32>::process_committed_history(
+        &mut restarted_pe,
+        blocks.iter().map(BlockMetadata::from).collect(),
+    );
+    for round in 4..10 {
+        assert_eq!(restarted_pe.get_weights(round), pe.get_weights(round));
+    }
+    assert_eq!(
+        pe.get_weights(7),
+        vec![
+            ACTIVE_WEIGHT,
+            ACTIVE_WEIGHT,
+            INACTIVE_WEIGHT,
+            INACTIVE_WEIGHT
+        ]
+    );
+}
+
+#[test]
+fn test_leader_reputation_aggregated_votes() {
+    let (signers, validator_verifier) = random_bls_validator_verifier(4, None, false);
+    let proposers = validator_verifier.get_ordered_account_addresses();
+    let mut pe = LeaderReputation::new(1, proposers.clone(), 2, 2);
+
+    // Validator 0 proposes a block whose QC aggregates the votes of validators 1 and 2 into one
+    // signature, while validator 3 stays silent.
+    let qc = gen_test_certificate(vec![], BlockInfo::empty(), BlockInfo::empty(), None);
+    let ledger_info = qc.ledger_info().ledger_info().clone();
+    let bls_signatures = signers[1..3]
+        .iter()
+        .map(|signer| (signer.author(), signer.bls_sign_message(ledger_info.hash())))
+        .collect();
+    let aggregated_signature = validator_verifier
+        .aggregate_signatures(&bls_signatures)
+        .unwrap();
+    let qc = QuorumCert::new(
+        qc.vote_data().clone(),
+        LedgerInfoWithSignatures::new_with_aggregated_signature(ledger_info, aggregated_signature),
+    );
+    let block = Block::new_proposal(1, 1, 1, qc, &signers[0]);
+    let metadata = BlockMetadata::from(&block);
+    assert!(metadata.previous_block_votes().is_empty());
+    ProposerElection::<u32>::process_committed_block(&mut pe, &block);
+
+    // The voters are recorded as active all the same, in the order of the proposers.
+    let weights: Vec<_> = proposers
+        .iter()
+        .map(|proposer| {
+            if *proposer == signers[3].author() {
+                INACTIVE_WEIGHT
+            } else {
+                ACTIVE_WEIGHT
+            }
+        })
+        .collect();
+    assert_eq!(pe.get_weights(3), weights);
+
+    let mut restarted_pe = LeaderReputation::new(1, proposers, 2, 2);
+    ProposerElection::<u32>::process_committed_history(&mut restarted_pe, vec![metadata]);
+    assert_eq!(restarted_pe.get_weights(3), weights);
+}
@@ -7,8 +7,10 @@ use crate::{
 };
 use channel;
 use consensus_types::common::Round;
+use libra_config::config::AdaptivePacemakerConfig;
 use libra_logger::prelude::*;
 use std::{
+    collections::VecDeque,
     fmt,
     sync::Arc,
     time::{Duration, Instant},
@@ -64,6 +66,9 @@ pub trait PacemakerTimeInterval: Send + Sync + 'static {
     /// to calculate the round duration of round 6 and the highest committed round is 3 (meaning
     /// the highest round to commit a block is round 5, then the round index is 0.
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration;
+
+    /// Called with the time it took for the current round to gather a QC, from its start.
+    fn record_qc_latency(&mut self, _latency: Duration) {}
 }
 
 /// Round durations increase exponentially
@@ -116,6 +121,109 @@ impl PacemakerTimeInterval for ExponentialTimeInterval {
     }
 }
 
+/// Round durations derived from the time it took for the recent rounds to gather a QC:
+/// the duration of the rounds right after a commit is a percentile of the recent latencies times
+/// a multiplier, bounded by a floor and a ceiling. It then increases exponentially with the
+/// rounds since the last commit, as in `ExponentialTimeInterval`, up to the ceiling.
+///
+/// Thus the timeouts follow the actual network conditions: they stay short on fast networks,
+/// where a leader failure is detected quickly, and grow on slow ones, where a fixed base would
+/// make rounds time out before the QC could form.
+pub struct AdaptiveTimeInterval {
+    // Number of recent latencies the base duration is derived from
+    window_size: usize,
+    // Percentile of the recent latencies the base duration is derived from, in (0, 1]
+    percentile: f64,
+    // Ratio between the base duration and the percentile of the latencies
+    multiplier: f64,
+    min_duration: Duration,
+    max_duration: Duration,
+    // By how much we increase interval every round since the last commit
+    exponent_base: f64,
+    // Maximum number of times we increase the interval
+    max_exponent: usize,
+    // The latencies of the most recent rounds, oldest first
+    latencies: VecDeque<Duration>,
+    // The duration of a round right after a commit
+    base_duration: Duration,
+}
+
+impl AdaptiveTimeInterval {
+    /// Starts from `initial_duration` until some latencies are recorded.
+    pub fn new(
+        initial_duration: Duration,
+        config: &AdaptivePacemakerConfig,
+        exponent_base: f64,
+        max_exponent: usize,
+    ) -> Self {
+        assert!(config.window_size > 0, "window_size should be positive");
+        assert!(
+            config.percentile > 0.0 && config.percentile <= 1.0,
+            "percentile should be in (0, 1]"
+        );
+        assert!(
+            config.min_timeout_ms <= config.max_timeout_ms,
+            "min_timeout_ms should not exceed max_timeout_ms"
+        );
+        assert!(
+            max_exponent < 32,
+            "max_exponent for PacemakerTimeInterval should be <32"
+        );
+        let min_duration = Duration::from_millis(config.min_timeout_ms);
+        let max_duration = Duration::from_millis(config.max_timeout_ms);
+        let base_duration = initial_duration.max(min_duration).min(max_duration);
+        counters::PACEMAKER_BASE_TIMEOUT_MS.set(base_duration.as_millis() as i64);
+        Self {
+            window_size: config.window_size,
+            percentile: config.percentile,
+            multiplier: config.multiplier,
+            min_duration,
+            max_duration,
+            exponent_base,
+            max_exponent,
+            latencies: VecDeque::new(),
+            base_duration,
+        }
+    }
+
+    /// Returns the duration of a round right after a commit.
+    #[cfg(test)]
+    pub fn base_duration(&self) -> Duration {
+        self.base_duration
+    }
+
+    fn update_base_duration(&mut self) {
+        let mut latencies: Vec<_> = self.latencies.iter().cloned().collect();
+        latencies.sort();
+        let rank = (self.percentile * latencies.len() as f64).ceil() as usize;
+        let latency = latencies[rank.max(1).min(latencies.len()) - 1];
+        let duration_ms = ((latency.as_millis() as f64) * self.multiplier).ceil() as u64;
+        self.base_duration = Duration::from_millis(duration_ms)
+            .max(self.min_duration)
+            .min(self.max_duration);
+        counters::PACEMAKER_BASE_TIMEOUT_MS.set(self.base_duration.as_millis() as i64);
+    }
+}
+
+impl PacemakerTimeInterval for AdaptiveTimeInterval {
+    fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
+        let pow = round_index_after_committed_qc.min(self.max_exponent) as u32;
+        let base_multiplier = self.exponent_base.powf(f64::from(pow));
+        let duration_ms = ((self.base_duration.as_millis() as f64) * base_multiplier).ceil();
+        // The ceiling bounds the product before any conversion can overflow.
+        let duration_ms = duration_ms.min(self.max_duration.as_millis() as f64) as u64;
+        Duration::from_millis(duration_ms)
+    }
+
+    fn record_qc_latency(&mut self, latency: Duration) {
+        self.latencies.push_back(latency);
+        while self.latencies.len() > self.window_size {
+            self.latencies.pop_front();
+        }
+        self.update_base_duration();
+    }
+}
+
 /// `Pacemaker` is a Pacemaker implementation that is responsible for generating the new round
 /// and local timeout events.
 ///
@@ -140,6 +248,8 @@ pub struct Pacemaker {
     highest_committed_round: Round,
     // Current round is max{highest_qc, highest_tc} + 1.
     current_round: Round,
+    // The time the current round started.
+    current_round_start: Instant,
     // The deadline for the next local timeout event. It is reset every time a new round start, or
     // a previous deadline expires.
     current_round_deadline: Instant,
@@ -166,6 +276,7 @@ impl Pacemaker {
             time_interval,
             highest_committed_round: 0,
             current_round: 0,
+            current_round_start: Instant::now(),
             current_round_deadline: Instant::now(),
             time_service,
             timeout_sender,
@@ -211,8 +322,15 @@ impl Pacemaker {
         }
         let new_round = std::cmp::max(qc_round, tc_round) + 1;
         if new_round > self.current_round {
+            let now = Instant::now();
+            // Only the QCs of the round we went through tell how long it takes to gather one.
+            if self.current_round > 0 && qc_round == self.current_round && qc_round >= tc_round {
+                self.time_interval
+                    .record_qc_latency(now.duration_since(self.current_round_start));
+            }
             // Start a new round.
             self.current_round = new_round;
+            self.current_round_start = now;
             let timeout = self.setup_timeout();
             // The new round reason is QCReady in case both QC and TC are equal
             let new_round_reason = if qc_round >= tc_round {
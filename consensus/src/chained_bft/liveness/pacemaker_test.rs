@@ -3,7 +3,8 @@
 
 use crate::{
     chained_bft::liveness::pacemaker::{
-        ExponentialTimeInterval, NewRoundEvent, NewRoundReason, Pacemaker, PacemakerTimeInterval,
+        AdaptiveTimeInterval, ExponentialTimeInterval, NewRoundEvent, NewRoundReason, Pacemaker,
+        PacemakerTimeInterval,
     },
     util::mock_time_service::SimulatedTimeService,
 };
@@ -11,7 +12,11 @@ use crate::{
 use channel;
 use consensus_types::common::Round;
 use futures::{executor::block_on, StreamExt};
-use std::{sync::Arc, time::Duration};
+use libra_config::config::AdaptivePacemakerConfig;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 #[test]
 fn test_pacemaker_time_interval() {
@@ -26,6 +31,83 @@ fn test_pacemaker_time_interval() {
     assert_eq!(6750, interval.get_round_duration(1000).as_millis());
 }
 
+#[test]
+fn test_adaptive_time_interval() {
+    let config = AdaptivePacemakerConfig {
+        window_size: 4,
+        percentile: 0.5,
+        multiplier: 2.0,
+        min_timeout_ms: 100,
+        max_timeout_ms: 5000,
+    };
+    let mut interval = AdaptiveTimeInterval::new(Duration::from_millis(1000), &config, 1.5, 2);
+    // Without latencies, the initial duration is used
+    assert_eq!(1000, interval.get_round_duration(0).as_millis());
+    assert_eq!(1500, interval.get_round_duration(1).as_millis());
+
+    // The median of 100, 200, 300 and 400 ms is 200 ms
+    for latency_ms in &[300, 100, 400, 200] {
+        interval.record_qc_latency(Duration::from_millis(*latency_ms));
+    }
+    assert_eq!(400, interval.base_duration().as_millis());
+    assert_eq!(400, interval.get_round_duration(0).as_millis());
+    assert_eq!(900, interval.get_round_duration(2).as_millis());
+    assert_eq!(900, interval.get_round_duration(1000).as_millis());
+
+    // Only the last 4 latencies count, and the duration doesn't go below the floor
+    for _ in 0..4 {
+        interval.record_qc_latency(Duration::from_millis(10));
+    }
+    assert_eq!(100, interval.get_round_duration(0).as_millis());
+
+    // Nor above the ceiling, even when backing off
+    for latency_ms in &[1000, 2000, 2000] {
+        interval.record_qc_latency(Duration::from_millis(*latency_ms));
+    }
+    assert_eq!(2000, interval.get_round_duration(0).as_millis());
+    assert_eq!(4500, interval.get_round_duration(2).as_millis());
+    for _ in 0..4 {
+        interval.record_qc_latency(Duration::from_millis(3000));
+    }
+    assert_eq!(5000, interval.get_round_duration(0).as_millis());
+    assert_eq!(5000, interval.get_round_duration(2).as_millis());
+}
+
+/// Records the QC latencies reported by the Pacemaker.
+struct RecordingTimeInterval(Arc<Mutex<Vec<Duration>>>);
+
+impl PacemakerTimeInterval for RecordingTimeInterval {
+    fn get_round_duration(&self, _round_index_after_committed_qc: usize) -> Duration {
+        Duration::from_millis(2)
+    }
+
+    fn record_qc_latency(&mut self, latency: Duration) {
+        self.0.lock().unwrap().push(latency);
+    }
+}
+
+#[test]
+fn test_qc_latency_recording() {
+    let latencies = Arc::new(Mutex::new(vec![]));
+    let time_interval = Box::new(RecordingTimeInterval(latencies.clone()));
+    let simulated_time = SimulatedTimeService::auto_advance_until(Duration::from_millis(4));
+    let (timeout_tx, _timeout_rx) = channel::new_test(1_024);
+    let mut pm = Pacemaker::new(time_interval, Arc::new(simulated_time), timeout_tx);
+
+    // The QC of round 0 doesn't end any round the Pacemaker went through
+    pm.process_certificates(Some(0), None, None);
+    assert_eq!(latencies.lock().unwrap().len(), 0);
+    // The QC of the current round does
+    pm.process_certificates(Some(1), None, None);
+    assert_eq!(latencies.lock().unwrap().len(), 1);
+    // Neither a TC nor the QC of a later round do
+    pm.process_certificates(None, Some(2), None);
+    pm.process_certificates(Some(5), None, None);
+    assert_eq!(latencies.lock().unwrap().len(), 1);
+    pm.process_certificates(Some(6), Some(6), None);
+    assert_eq!(latencies.lock().unwrap().len(), 2);
+}
+
 #[test]
 /// Verify that Pacemaker properly outputs local timeout events upon timeout
 fn test_basic_timeout() {
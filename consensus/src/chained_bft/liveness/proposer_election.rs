@@ -3,13 +3,16 @@
 
 use consensus_types::{
     block::Block,
-    common::{Author, Round},
+    common::{Author, Payload, Round},
+    proposal_msg::ProposalMsg,
 };
+use libra_crypto::HashValue;
+use libra_types::block_metadata::BlockMetadata;
 
 /// ProposerElection incorporates the logic of choosing a leader among multiple candidates.
 /// We are open to a possibility for having multiple proposers per round, the ultimate choice
 /// of a proposal is exposed by the election protocol via the stream of proposals.
-pub trait ProposerElection<T> {
+pub trait ProposerElection<T: Payload> {
     /// If a given author is a valid candidate for being a proposer, generate the info,
     /// otherwise return None.
     /// Note that this function is synchronous.
@@ -33,4 +36,42 @@ pub trait ProposerElection<T> {
     /// Note that once the backup proposal is taken and no other proposals are submitted, the
     /// following take requests are going to return None.
     fn take_backup_proposal(&mut self, round: Round) -> Option<Block<T>>;
+
+    /// Notify proposer election about a newly committed block, in the order they are committed.
+    /// Elections depending on the history of the chain can update it.
+    fn process_committed_block(&mut self, _block: &Block<T>) {}
+
+    /// Number of the last committed blocks of the epoch the election depends on, which are loaded
+    /// from storage when the validator starts the epoch or catches up through state sync.
+    fn committed_history_size(&self) -> usize {
+        0
+    }
+
+    /// Replace the history of the chain with the metadata of the last committed blocks of the
+    /// epoch, oldest first, as loaded from storage.
+    fn process_committed_history(&mut self, _history: Vec<BlockMetadata>) {}
+
+    /// Return the input of the VRF the proposers of `round` extending the block `parent_id` are
+    /// elected with, for elections kept private until each proposer reveals its VRF proof.
+    /// Elections anyone can compute from the round return None.
+    fn vrf_input(&self, _round: Round, _parent_id: HashValue) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Check whether a valid VRF `proof` over `vrf_input()` elects its author as a proposer.
+    fn is_elected(&self, _proof: &[u8]) -> bool {
+        false
+    }
+
+    /// Check whether the author of a proposal is a valid proposer for its round, given the proof
+    /// of election the proposal carries if any.
+    fn is_valid_proposal(&self, proposal_msg: &ProposalMsg<T>) -> bool {
+        self.is_valid_proposer(proposal_msg.proposer(), proposal_msg.round())
+            .is_some()
+    }
+
+    /// Same as `process_proposal()`, for elections which need the whole message.
+    fn process_proposal_msg(&mut self, proposal_msg: ProposalMsg<T>) -> Option<Block<T>> {
+        self.process_proposal(proposal_msg.take_proposal())
+    }
 }
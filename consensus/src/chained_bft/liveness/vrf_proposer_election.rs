@@ -0,0 +1,205 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::liveness::proposer_election::ProposerElection;
+use consensus_types::{
+    block::Block,
+    common::{Author, Payload, Round},
+    proposal_msg::ProposalMsg,
+};
+use libra_crypto::{
+    vrf::ecvrf::{Output, Proof, VRFPublicKey},
+    HashValue,
+};
+use libra_logger::prelude::*;
+use libra_types::crypto_proxies::ValidatorVerifier;
+use std::{collections::BTreeMap, convert::TryFrom};
+
+/// The VrfProposer elects the proposers of a round privately. Each validator evaluates a VRF keyed
+/// by its consensus key over (epoch, round, parent block), and is elected if the output is low
+/// enough for `expected_proposers_per_round` validators to be elected on average. Nobody knows
+/// who is elected until the proposers reveal it with the VRF proofs attached to their proposals,
+/// so that the proposers of a round can't be targeted ahead of it.
+///
+/// The parent block is the one certified by the quorum certificate a proposal extends. The
+/// certificate itself is left out of the input: a proposer could otherwise retry its luck with
+/// different sets of signatures over the same block.
+///
+/// As any validator may be elected, `get_valid_proposers()` returns all of them. A round without
+/// any proposer times out. In a round with several, the first proposal received is processed
+/// right away, and the one with the lowest output among the others is kept as a backup.
+pub struct VrfProposer<T> {
+    // Epoch is part of the VRF input for electing different leaders per-epoch
+    epoch: u64,
+    // The VRF public keys of the validators, which are their consensus public keys
+    public_keys: BTreeMap<Author, VRFPublicKey>,
+    // A proposer is elected if the first 8 bytes of its VRF output, read as an integer, are below
+    // the threshold
+    threshold: u128,
+    // The round of the last proposal processed right away, the others of that round are backups
+    primary_proposal_round: Round,
+    // Keeps the backup proposal with the lowest output of the highest round, see `MultiProposer`
+    backup_proposal_round: Round,
+    // The proposal is kept in a tuple (rank, block)
+    backup_proposal: Option<(u64, Block<T>)>,
+}
+
+impl<T> VrfProposer<T> {
+    pub fn new(
+        epoch: u64,
+        validators: &ValidatorVerifier,
+        expected_proposers_per_round: u64,
+    ) -> Self {
+        let public_keys: BTreeMap<_, _> = validators
+            .get_ordered_account_addresses()
+            .into_iter()
+            .filter_map(|author| {
+                let public_key = validators.get_public_key(&author)?;
+                match VRFPublicKey::try_from(&public_key.to_bytes()[..]) {
+                    Ok(vrf_public_key) => Some((author, vrf_public_key)),
+                    Err(e) => {
+                        error!(
+                            "Consensus public key of {} is not a VRF public key: {}",
+                            author, e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+        // Each validator is elected with probability expected_proposers_per_round / #validators.
+        let threshold = if public_keys.is_empty() {
+            0
+        } else {
+            std::cmp::min(
+                1 << 64,
+                (u128::from(expected_proposers_per_round) << 64) / public_keys.len() as u128,
+            )
+        };
+        Self {
+            epoch,
+            public_keys,
+            threshold,
+            primary_proposal_round: 0,
+            backup_proposal_round: 0,
+            backup_proposal: None,
+        }
+    }
+
+    fn input(&self, round: Round, parent_id: HashValue) -> Vec<u8> {
+        // input = epoch | round | parent_id
+        let mut input = self.epoch.to_le_bytes().to_vec();
+        input.extend_from_slice(&round.to_le_bytes());
+        input.extend_from_slice(parent_id.as_ref());
+        input
+    }
+
+    /// Returns the rank of the VRF output of `proof` if it elects its author, None otherwise. The
+    /// proof isn't verified.
+    fn elected_rank(&self, proof: &[u8]) -> Option<u64> {
+        let proof = Proof::try_from(proof).ok()?;
+        let mut rank = [0u8; 8];
+        rank.copy_from_slice(&Output::from(&proof).to_bytes()[..8]);
+        let rank = u64::from_be_bytes(rank);
+        if u128::from(rank) < self.threshold {
+            Some(rank)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Payload> ProposerElection<T> for VrfProposer<T> {
+    /// Any validator may be elected, only its proof tells.
+    fn is_valid_proposer(&self, author: Author, _round: Round) -> Option<Author> {
+        if self.public_keys.contains_key(&author) {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers(&self, _round: Round) -> Vec<Author> {
+        self.public_keys.keys().cloned().collect()
+    }
+
+    fn process_proposal(&mut self, proposal: Block<T>) -> Option<Block<T>> {
+        warn!(
+            "Proposal {} comes without a proof of election, ignore.",
+            proposal
+        );
+        None
+    }
+
+    fn take_backup_proposal(&mut self, round: Round) -> Option<Block<T>> {
+        if self.backup_proposal_round != round {
+            return None;
+        }
+        self.backup_proposal.take().map(|(_, block)| block)
+    }
+
+    fn vrf_input(&self, round: Round, parent_id: HashValue) -> Option<Vec<u8>> {
+        Some(self.input(round, parent_id))
+    }
+
+    fn is_elected(&self, proof: &[u8]) -> bool {
+        self.elected_rank(proof).is_some()
+    }
+
+    fn is_valid_proposal(&self, proposal_msg: &ProposalMsg<T>) -> bool {
+        let (proof, public_key) = match (
+            proposal_msg.proposer_proof(),
+            self.public_keys.get(&proposal_msg.proposer()),
+        ) {
+            (Some(proof), Some(public_key)) => (proof, public_key),
+            _ => return false,
+        };
+        let input = self.input(proposal_msg.round(), proposal_msg.proposal().parent_id());
+        let verified = Proof::try_from(proof)
+            .ok()
+            .map_or(false, |proof| public_key.verify(&proof, &input).is_ok());
+        verified && self.elected_rank(proof).is_some()
+    }
+
+    /// Expects a proposal checked by `is_valid_proposal()`.
+    fn process_proposal_msg(&mut self, proposal_msg: ProposalMsg<T>) -> Option<Block<T>> {
+        let rank = match proposal_msg
+            .proposer_proof()
+            .and_then(|proof| self.elected_rank(proof))
+        {
+            Some(rank) => rank,
+            None => {
+                warn!("Proposer of {} is not elected, ignore.", proposal_msg);
+                return None;
+            }
+        };
+        let round = proposal_msg.round();
+        let proposal = proposal_msg.take_proposal();
+        if round > self.primary_proposal_round {
+            debug!(
+                "First proposal {} of the round: going to process it right now.",
+                proposal
+            );
+            self.primary_proposal_round = round;
+            return Some(proposal);
+        }
+        debug!(
+            "Another proposal {}: will process it if the first one fails.",
+            proposal
+        );
+        if round > self.backup_proposal_round {
+            self.backup_proposal = Some((rank, proposal));
+            self.backup_proposal_round = round;
+        } else if round == self.backup_proposal_round {
+            // Already have some backup for the given round: choose the best (lowest) rank.
+            let current_rank = self
+                .backup_proposal
+                .as_ref()
+                .map_or(std::u64::MAX, |(r, _)| *r);
+            if rank < current_rank {
+                self.backup_proposal = Some((rank, proposal));
+            }
+        }
+        None
+    }
+}
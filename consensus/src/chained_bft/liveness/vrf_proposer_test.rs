@@ -0,0 +1,129 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::liveness::{
+    proposer_election::ProposerElection, vrf_proposer_election::VrfProposer,
+};
+use consensus_types::{
+    block::block_test_utils::certificate_for_genesis, block::Block, proposal_msg::ProposalMsg,
+    sync_info::SyncInfo,
+};
+use libra_crypto::vrf::ecvrf::{Output, Proof, VRFPrivateKey};
+use libra_types::crypto_proxies::{ValidatorInfo, ValidatorSigner, ValidatorVerifier};
+use std::{collections::BTreeMap, convert::TryFrom};
+
+fn make_signers_and_verifier(num_validators: u8) -> (Vec<ValidatorSigner>, ValidatorVerifier) {
+    let signers: Vec<_> = (0..num_validators)
+        .map(|i| ValidatorSigner::random([i; 32]))
+        .collect();
+    let validator_infos = signers
+        .iter()
+        .map(|signer| (signer.author(), ValidatorInfo::new(signer.public_key(), 1)))
+        .collect::<BTreeMap<_, _>>();
+    (signers, ValidatorVerifier::new(validator_infos))
+}
+
+fn prove(signer: &ValidatorSigner, input: &[u8]) -> Vec<u8> {
+    VRFPrivateKey::try_from(&signer.private_key().to_bytes()[..])
+        .unwrap()
+        .prove(input)
+        .to_bytes()
+        .to_vec()
+}
+
+fn rank(proof: &[u8]) -> [u8; 8] {
+    let mut rank = [0u8; 8];
+    rank.copy_from_slice(&Output::from(&Proof::try_from(proof).unwrap()).to_bytes()[..8]);
+    rank
+}
+
+fn make_proposal_msg(signer: &ValidatorSigner, proof: Option<Vec<u8>>) -> ProposalMsg<u32> {
+    let block = Block::new_proposal(1, 1, 1, certificate_for_genesis(), signer);
+    let sync_info = SyncInfo::new(certificate_for_genesis(), certificate_for_genesis(), None);
+    match proof {
+        Some(proof) => ProposalMsg::new_with_proposer_proof(block, sync_info, proof),
+        None => ProposalMsg::new(block, sync_info),
+    }
+}
+
+#[test]
+fn test_vrf_proposer_proofs() {
+    let (signers, verifier) = make_signers_and_verifier(4);
+    // All validators are elected on average, so any valid proof elects its author.
+    let pe: Box<dyn ProposerElection<u32>> = Box::new(VrfProposer::new(0, &verifier, 4));
+    let parent_id = certificate_for_genesis().certified_block().id();
+    let input = pe.vrf_input(1, parent_id).unwrap();
+    assert_ne!(input, pe.vrf_input(2, parent_id).unwrap());
+
+    let authors: Vec<_> = signers.iter().map(ValidatorSigner::author).collect();
+    assert_eq!(
+        pe.get_valid_proposers(1),
+        verifier.get_ordered_account_addresses()
+    );
+    for author in &authors {
+        assert_eq!(pe.is_valid_proposer(*author, 1), Some(*author));
+    }
+    assert_eq!(
+        pe.is_valid_proposer(ValidatorSigner::random([9; 32]).author(), 1),
+        None
+    );
+
+    let proof = prove(&signers[0], &input);
+    assert!(pe.is_elected(&proof));
+    assert!(pe.is_valid_proposal(&make_proposal_msg(&signers[0], Some(proof.clone()))));
+    // Proposals without a proof, with the proof of someone else or over another round are invalid.
+    assert!(!pe.is_valid_proposal(&make_proposal_msg(&signers[0], None)));
+    assert!(!pe.is_valid_proposal(&make_proposal_msg(&signers[1], Some(proof))));
+    let other_round_proof = prove(&signers[0], &pe.vrf_input(2, parent_id).unwrap());
+    assert!(!pe.is_valid_proposal(&make_proposal_msg(&signers[0], Some(other_round_proof))));
+    assert!(!pe.is_valid_proposal(&make_proposal_msg(&signers[0], Some(vec![0; 3]))));
+
+    // Nobody is elected without expected proposers.
+    let pe: Box<dyn ProposerElection<u32>> = Box::new(VrfProposer::new(0, &verifier, 0));
+    for signer in &signers {
+        let proof = prove(signer, &input);
+        assert!(!pe.is_elected(&proof));
+        assert!(!pe.is_valid_proposal(&make_proposal_msg(signer, Some(proof))));
+    }
+}
+
+#[test]
+fn test_vrf_proposer_backup() {
+    let (signers, verifier) = make_signers_and_verifier(3);
+    let mut pe: Box<dyn ProposerElection<u32>> = Box::new(VrfProposer::new(0, &verifier, 3));
+    let parent_id = certificate_for_genesis().certified_block().id();
+    let input = pe.vrf_input(1, parent_id).unwrap();
+    let proposal_msgs: Vec<_> = signers
+        .iter()
+        .map(|signer| make_proposal_msg(signer, Some(prove(signer, &input))))
+        .collect();
+
+    // The first proposal of the round is processed right away.
+    assert_eq!(
+        pe.process_proposal(proposal_msgs[0].proposal().clone()),
+        None
+    );
+    assert_eq!(
+        pe.process_proposal_msg(proposal_msgs[0].clone()),
+        Some(proposal_msgs[0].proposal().clone())
+    );
+    assert_eq!(pe.take_backup_proposal(1), None);
+
+    // The others are backups, the one with the lowest output is kept.
+    assert_eq!(pe.process_proposal_msg(proposal_msgs[1].clone()), None);
+    assert_eq!(pe.process_proposal_msg(proposal_msgs[2].clone()), None);
+    let best_backup = if rank(proposal_msgs[1].proposer_proof().unwrap())
+        < rank(proposal_msgs[2].proposer_proof().unwrap())
+    {
+        &proposal_msgs[1]
+    } else {
+        &proposal_msgs[2]
+    };
+    assert_eq!(pe.take_backup_proposal(2), None);
+    assert_eq!(
+        pe.take_backup_proposal(1),
+        Some(best_backup.proposal().clone())
+    );
+    // has been already popped out
+    assert_eq!(pe.take_backup_proposal(1), None);
+}
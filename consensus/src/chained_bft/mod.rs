@@ -2,19 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod consensusdb;
+pub mod consensusdb_inspector;
 
 mod block_storage;
 pub mod chained_bft_consensus_provider;
 mod chained_bft_smr;
 mod network;
+mod observer;
+mod observer_provider;
+mod observer_publisher;
 
 pub mod epoch_manager;
 pub mod persistent_storage;
 
+#[cfg(test)]
+mod byzantine_tests;
 #[cfg(test)]
 mod chained_bft_smr_test;
 #[cfg(test)]
 mod network_tests;
+#[cfg(test)]
+mod observer_test;
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod test_utils;
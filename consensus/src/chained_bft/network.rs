@@ -1,7 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::counters;
+use crate::{chained_bft::observer_publisher::ObserverPublisher, counters};
 use bytes::Bytes;
 use channel::{self, libra_channel, message_queues::QueueStyle};
 use consensus_types::block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse};
@@ -24,7 +24,9 @@ use network::{
         ConsensusMsg, ConsensusMsg_oneof, Proposal, RequestBlock, RequestEpoch,
         SyncInfo as SyncInfoProto, VoteMsg as VoteMsgProto,
     },
-    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event, RpcError},
+    validator_network::{
+        ConsensusNetworkEvents, ConsensusNetworkSender, Event, PeerBehavior, RpcError,
+    },
 };
 use std::cmp::Ordering;
 use std::{
@@ -90,8 +92,14 @@ impl NetworkSender {
         }
     }
 
+    /// Returns the validators of the current epoch
+    pub fn validators(&self) -> &ValidatorVerifier {
+        &self.validators
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is fulfilled with BlockRetrievalResponse.
+    /// Timeouts aren't reported: only the caller knows whether the peer was given enough time.
     pub async fn request_block<T: Payload>(
         &mut self,
         retrieval_request: BlockRetrievalRequest,
@@ -107,13 +115,27 @@ impl NetworkSender {
             .request_block(from, req_msg, timeout)
             .await?;
         counters::BLOCK_RETRIEVAL_DURATION_S.observe_duration(pre_retrieval_instant.elapsed());
-        let response = BlockRetrievalResponse::<T>::try_from(response_msg)?;
-        response.verify(
-            retrieval_request.block_id(),
-            retrieval_request.num_blocks(),
-            self.validators.as_ref(),
-        )?;
-        Ok(response)
+        let response = BlockRetrievalResponse::<T>::try_from(response_msg).and_then(|response| {
+            response.verify(
+                retrieval_request.block_id(),
+                retrieval_request.num_blocks(),
+                self.validators.as_ref(),
+            )?;
+            Ok(response)
+        });
+        let behavior = match response {
+            Ok(_) => PeerBehavior::Good,
+            Err(_) => PeerBehavior::MalformedMessage,
+        };
+        self.report_peer(from, behavior).await;
+        response
+    }
+
+    /// Reports the behavior of a peer to the network, which bans the peers misbehaving too often.
+    pub async fn report_peer(&mut self, peer: Author, behavior: PeerBehavior) {
+        if let Err(e) = self.network_sender.report_peer(peer, behavior).await {
+            warn!("Failed to report {:?} of peer {}: {:?}", behavior, peer, e);
+        }
     }
 
     /// Tries to send the given proposal (block and proposer metadata) to all the participants.
@@ -259,13 +281,18 @@ pub struct NetworkTask<T> {
     different_epoch_tx: libra_channel::Sender<AccountAddress, (u64, AccountAddress)>,
     epoch_retrieval_tx: libra_channel::Sender<AccountAddress, (u64, AccountAddress)>,
     all_events: Box<dyn Stream<Item = failure::Result<Event<ConsensusMsg>>> + Send + Unpin>,
+    // Reports the peers sending invalid messages.
+    network_sender: ConsensusNetworkSender,
     validators: Arc<ValidatorVerifier>,
+    // Forwards the valid proposals to the observers, if any.
+    observer_publisher: Option<ObserverPublisher>,
 }
 
 impl<T: Payload> NetworkTask<T> {
     /// Establishes the initial connections with the peers and returns the receivers.
     pub fn new(
         epoch: u64,
+        network_sender: ConsensusNetworkSender,
         network_events: ConsensusNetworkEvents,
         self_receiver: channel::Receiver<failure::Result<Event<ConsensusMsg>>>,
         validators: Arc<ValidatorVerifier>,
@@ -303,7 +330,9 @@ impl<T: Payload> NetworkTask<T> {
                 different_epoch_tx,
                 epoch_retrieval_tx,
                 all_events,
+                network_sender,
                 validators,
+                observer_publisher: None,
             },
             NetworkReceivers {
                 proposals: proposal_rx,
@@ -317,6 +346,11 @@ impl<T: Payload> NetworkTask<T> {
         )
     }
 
+    /// Publishes the valid proposals received from now on to the observers.
+    pub fn set_observer_publisher(&mut self, observer_publisher: ObserverPublisher) {
+        self.observer_publisher = Some(observer_publisher);
+    }
+
     pub async fn start(mut self) {
         use ConsensusMsg_oneof::*;
         while let Some(Ok(message)) = self.all_events.next().await {
@@ -382,21 +416,37 @@ impl<T: Payload> NetworkTask<T> {
         peer_id: AccountAddress,
         proposal: Proposal,
     ) -> failure::Result<()> {
-        let proposal = ProposalUncheckedSignatures::<T>::try_from(proposal)?;
+        let proposal_proto = self.observer_publisher.as_ref().map(|_| proposal.clone());
+        let proposal = ProposalUncheckedSignatures::<T>::try_from(proposal);
+        let proposal = self
+            .report_on_error(peer_id, proposal, PeerBehavior::MalformedMessage)
+            .await?;
         if proposal.epoch() != self.epoch {
             return self
                 .different_epoch_tx
                 .push(peer_id, (proposal.epoch(), peer_id));
         }
 
-        let proposal = proposal
-            .validate_signatures(self.validators.as_ref())?
-            .verify_well_formed()?;
-        ensure!(
-            proposal.proposal().author() == Some(peer_id),
-            "proposal received must be from the sending peer"
-        );
+        let proposal = proposal.validate_signatures(self.validators.as_ref());
+        let proposal = self
+            .report_on_error(peer_id, proposal, PeerBehavior::InvalidSignature)
+            .await?;
+        let proposal = proposal.verify_well_formed().and_then(|proposal| {
+            ensure!(
+                proposal.proposal().author() == Some(peer_id),
+                "proposal received must be from the sending peer"
+            );
+            Ok(proposal)
+        });
+        let proposal = self
+            .report_on_error(peer_id, proposal, PeerBehavior::MalformedMessage)
+            .await?;
         debug!("Received proposal {}", proposal);
+        if let (Some(publisher), Some(proposal_proto)) =
+            (self.observer_publisher.as_mut(), proposal_proto)
+        {
+            publisher.publish(peer_id, proposal_proto);
+        }
         self.proposal_tx.push(peer_id, proposal)
     }
 
@@ -405,12 +455,16 @@ impl<T: Payload> NetworkTask<T> {
         peer_id: AccountAddress,
         vote_msg: VoteMsgProto,
     ) -> failure::Result<()> {
-        let vote_msg = VoteMsg::try_from(vote_msg)?;
-
-        ensure!(
-            vote_msg.vote().author() == peer_id,
-            "vote received must be from the sending peer"
-        );
+        let vote_msg = VoteMsg::try_from(vote_msg).and_then(|vote_msg| {
+            ensure!(
+                vote_msg.vote().author() == peer_id,
+                "vote received must be from the sending peer"
+            );
+            Ok(vote_msg)
+        });
+        let vote_msg = self
+            .report_on_error(peer_id, vote_msg, PeerBehavior::MalformedMessage)
+            .await?;
 
         if vote_msg.epoch() != self.epoch {
             return self
@@ -419,16 +473,37 @@ impl<T: Payload> NetworkTask<T> {
         }
 
         debug!("Received {}", vote_msg);
-        vote_msg.verify(self.validators.as_ref()).map_err(|e| {
+        let verified = vote_msg.verify(self.validators.as_ref()).map_err(|e| {
             security_log(SecurityEvent::InvalidConsensusVote)
                 .error(&e)
                 .data(&vote_msg)
                 .log();
             e
-        })?;
+        });
+        self.report_on_error(peer_id, verified, PeerBehavior::InvalidSignature)
+            .await?;
         self.vote_tx.push(peer_id, vote_msg)
     }
 
+    /// Reports the `behavior` of the peer to the network if `result` is an error, which is
+    /// returned as is.
+    async fn report_on_error<R>(
+        &mut self,
+        peer_id: AccountAddress,
+        result: failure::Result<R>,
+        behavior: PeerBehavior,
+    ) -> failure::Result<R> {
+        if result.is_err() {
+            if let Err(e) = self.network_sender.report_peer(peer_id, behavior).await {
+                warn!(
+                    "Failed to report {:?} of peer {}: {:?}",
+                    behavior, peer_id, e
+                );
+            }
+        }
+        result
+    }
+
     async fn process_sync_info(
         &mut self,
         sync_info: SyncInfoProto,
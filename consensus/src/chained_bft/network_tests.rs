@@ -17,10 +17,11 @@ use libra_types::block_info::BlockInfo;
 use network::{
     interface::{NetworkNotification, NetworkRequest},
     proto::{ConsensusMsg, ConsensusMsg_oneof},
-    protocols::rpc::InboundRpcRequest,
+    protocols::{direct_send::Message as DirectSendMessage, rpc::InboundRpcRequest},
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
 };
 use prost::Message;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
     collections::{HashMap, HashSet},
     sync::{Arc, Mutex, RwLock},
@@ -28,26 +29,48 @@ use std::{
 };
 use tokio::runtime::TaskExecutor;
 
+/// The index of a node in the `NetworkPlayground`, in the order the nodes were added. Twins share
+/// an `Author` but each have their own `NodeId`.
+pub type NodeId = usize;
+
+/// Rewrites the direct-send messages a node sends, e.g. for simulating a Byzantine behavior. It
+/// is given the destination and the message, and returns the messages to send instead.
+pub type MessageRewriter = Box<dyn FnMut(Author, ConsensusMsg) -> Vec<ConsensusMsg> + Send>;
+
+type NodeConsensusTxs =
+    Arc<Mutex<HashMap<Author, Vec<(NodeId, channel::Sender<NetworkNotification>)>>>>;
+
 /// `NetworkPlayground` mocks the network implementation and provides convenience
 /// methods for testing. Test clients can use `wait_for_messages` or
 /// `deliver_messages` to inspect the direct-send messages sent between peers.
-/// They can also configure network messages to be dropped between specific peers.
+/// They can also configure network messages to be dropped between specific peers,
+/// split the network in partitions, and rewrite the messages sent by some peers.
+///
+/// Several nodes can share an `Author`: these twins receive all the messages sent to it, as long
+/// as they are in the same partition as the sender. A playground created with a seed delivers
+/// the queued messages in a random order drawn from it, instead of the order they were sent in.
 ///
 /// Currently, RPC messages are delivered immediately and are not controlled by
-/// `wait_for_messages` or `deliver_messages` for delivery. They are also not
-/// currently dropped according to the `NetworkPlayground`'s drop config.
+/// `wait_for_messages` or `deliver_messages` for delivery. They are dropped according to
+/// the `NetworkPlayground`'s drop config and partitions, but can't be rewritten.
 pub struct NetworkPlayground {
-    /// Maps each Author to a Sender of their inbound network notifications.
+    /// Maps each Author to the Senders of inbound network notifications of its nodes.
     /// These events will usually be handled by the event loop spawned in
     /// `ConsensusNetworkImpl`.
-    node_consensus_txs: Arc<Mutex<HashMap<Author, channel::Sender<NetworkNotification>>>>,
+    node_consensus_txs: NodeConsensusTxs,
     /// Nodes' outbound handlers forward their outbound non-rpc messages to this
     /// queue.
-    outbound_msgs_tx: mpsc::Sender<(Author, NetworkRequest)>,
+    outbound_msgs_tx: mpsc::Sender<(NodeId, Author, NetworkRequest)>,
     /// NetworkPlayground reads all nodes' outbound messages through this queue.
-    outbound_msgs_rx: mpsc::Receiver<(Author, NetworkRequest)>,
+    outbound_msgs_rx: mpsc::Receiver<(NodeId, Author, NetworkRequest)>,
     /// Allow test code to drop direct-send messages between peers.
     drop_config: Arc<RwLock<DropConfig>>,
+    /// Rewriters of the outbound messages of some peers.
+    rewriters: Arc<Mutex<HashMap<Author, MessageRewriter>>>,
+    /// If set, messages are taken out of `outbound_msgs_rx` into `pending_msgs` and delivered in
+    /// a random order.
+    rng: Option<StdRng>,
+    pending_msgs: Vec<(NodeId, Author, NetworkRequest)>,
     /// An executor for spawning node outbound network event handlers
     executor: TaskExecutor,
 }
@@ -60,11 +83,21 @@ impl NetworkPlayground {
             node_consensus_txs: Arc::new(Mutex::new(HashMap::new())),
             outbound_msgs_tx,
             outbound_msgs_rx,
-            drop_config: Arc::new(RwLock::new(DropConfig(HashMap::new()))),
+            drop_config: Arc::new(RwLock::new(DropConfig::default())),
+            rewriters: Arc::new(Mutex::new(HashMap::new())),
+            rng: None,
+            pending_msgs: vec![],
             executor,
         }
     }
 
+    /// Creates a playground which reorders the messages with a random generator seeded by `seed`.
+    pub fn new_with_seed(executor: TaskExecutor, seed: [u8; 32]) -> Self {
+        let mut playground = Self::new(executor);
+        playground.rng = Some(StdRng::from_seed(seed));
+        playground
+    }
+
     /// Create a new async task that handles outbound messages sent by a node.
     ///
     /// All non-rpc messages are forwarded to the NetworkPlayground's
@@ -72,13 +105,15 @@ impl NetworkPlayground {
     /// `deliver_messages` and `wait_for_messages` API's.
     ///
     /// Rpc messages are immediately sent to the destination for handling, so
-    /// they don't block.
+    /// they don't block. Reports of peer behavior are dropped.
     async fn start_node_outbound_handler(
         drop_config: Arc<RwLock<DropConfig>>,
+        rewriters: Arc<Mutex<HashMap<Author, MessageRewriter>>>,
+        src_node: NodeId,
         src: Author,
         mut network_reqs_rx: channel::Receiver<NetworkRequest>,
-        mut outbound_msgs_tx: mpsc::Sender<(Author, NetworkRequest)>,
-        node_consensus_txs: Arc<Mutex<HashMap<Author, channel::Sender<NetworkNotification>>>>,
+        mut outbound_msgs_tx: mpsc::Sender<(NodeId, Author, NetworkRequest)>,
+        node_consensus_txs: NodeConsensusTxs,
     ) {
         while let Some(net_req) = network_reqs_rx.next().await {
             let drop_rpc = drop_config
@@ -100,12 +135,24 @@ impl NetworkPlayground {
                     if drop_rpc {
                         continue;
                     }
-                    let mut node_consensus_tx = node_consensus_txs
+                    // Any twin of the destination in the same partition handles the request.
+                    let node_consensus_tx = node_consensus_txs
                         .lock()
                         .unwrap()
                         .get(&dst)
                         .unwrap()
-                        .clone();
+                        .iter()
+                        .find(|(dst_node, _)| {
+                            !drop_config
+                                .read()
+                                .unwrap()
+                                .is_partitioned(src_node, *dst_node)
+                        })
+                        .map(|(_, tx)| tx.clone());
+                    let mut node_consensus_tx = match node_consensus_tx {
+                        Some(tx) => tx,
+                        None => continue,
+                    };
 
                     let inbound_req = InboundRpcRequest {
                         protocol: outbound_req.protocol,
@@ -118,16 +165,38 @@ impl NetworkPlayground {
                         .await
                         .unwrap();
                 }
-                // Other NetworkRequest get buffered for `deliver_messages` to
-                // synchronously drain.
+                // The playground doesn't ban misbehaving peers, so their reports are dropped.
+                NetworkRequest::ReportPeer(..) => {}
+                // Other NetworkRequest get rewritten if needed, and buffered for
+                // `deliver_messages` to synchronously drain.
+                NetworkRequest::SendMessage(dst, msg) => {
+                    let msgs = match rewriters.lock().unwrap().get_mut(&src) {
+                        Some(rewriter) => {
+                            let consensus_msg = ConsensusMsg::decode(msg.mdata.as_ref()).unwrap();
+                            rewriter(dst, consensus_msg)
+                                .into_iter()
+                                .map(|consensus_msg| DirectSendMessage {
+                                    protocol: msg.protocol.clone(),
+                                    mdata: consensus_msg.to_bytes().unwrap(),
+                                })
+                                .collect()
+                        }
+                        None => vec![msg],
+                    };
+                    for msg in msgs {
+                        let net_req = NetworkRequest::SendMessage(dst, msg);
+                        let _ = outbound_msgs_tx.send((src_node, src, net_req)).await;
+                    }
+                }
                 net_req => {
-                    let _ = outbound_msgs_tx.send((src, net_req)).await;
+                    let _ = outbound_msgs_tx.send((src_node, src, net_req)).await;
                 }
             }
         }
     }
 
-    /// Add a new node to the NetworkPlayground.
+    /// Add a new node to the NetworkPlayground, which can be a twin of a node added before with
+    /// the same author.
     pub fn add_node(
         &mut self,
         author: Author,
@@ -138,65 +207,96 @@ impl NetworkPlayground {
         // `Sender` side of this queue is usually wrapped in a
         // `ConsensusNetworkSender` adapter.
         network_reqs_rx: channel::Receiver<NetworkRequest>,
-    ) {
+    ) -> NodeId {
+        let node = self.drop_config.write().unwrap().add_node(author);
         self.node_consensus_txs
             .lock()
             .unwrap()
-            .insert(author, consensus_tx);
-        self.drop_config.write().unwrap().add_node(author);
+            .entry(author)
+            .or_insert_with(Vec::new)
+            .push((node, consensus_tx));
 
         let fut = NetworkPlayground::start_node_outbound_handler(
             Arc::clone(&self.drop_config),
+            Arc::clone(&self.rewriters),
+            node,
             author,
             network_reqs_rx,
             self.outbound_msgs_tx.clone(),
             self.node_consensus_txs.clone(),
         );
         self.executor.spawn(fut);
+        node
     }
 
-    /// Deliver a `NetworkRequest` from peer `src` to the destination peer.
-    /// Returns a copy of the delivered message and the sending peer id.
+    /// Deliver a `NetworkRequest` from node `src_node` of peer `src` to the nodes of the
+    /// destination peer in its partition. Returns a copy of the delivered message and the sending
+    /// peer id, or None if no node could receive it.
     async fn deliver_message(
         &mut self,
+        src_node: NodeId,
         src: Author,
         msg: NetworkRequest,
-    ) -> (Author, ConsensusMsg) {
+    ) -> Option<(Author, ConsensusMsg)> {
         // extract destination peer
         let dst = match &msg {
             NetworkRequest::SendMessage(dst, _) => *dst,
             msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
         };
 
-        // get his sender
-        let mut node_consensus_tx = self
+        // get the senders of the nodes it can reach
+        let node_consensus_txs: Vec<_> = self
             .node_consensus_txs
             .lock()
             .unwrap()
             .get(&dst)
             .unwrap()
-            .clone();
+            .iter()
+            .filter(|(dst_node, _)| !self.is_partitioned(src_node, *dst_node))
+            .map(|(_, tx)| tx.clone())
+            .collect();
+        if node_consensus_txs.is_empty() {
+            return None;
+        }
 
-        // convert NetworkRequest to corresponding NetworkNotification
-        let msg_notif = match msg {
-            NetworkRequest::SendMessage(_dst, msg) => NetworkNotification::RecvMessage(src, msg),
+        let msg = match msg {
+            NetworkRequest::SendMessage(_dst, msg) => msg,
             msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
         };
 
         // copy message data
-        let msg_copy = match &msg_notif {
-            NetworkNotification::RecvMessage(src, msg) => {
-                let msg = ConsensusMsg::decode(msg.mdata.as_ref()).unwrap();
-                (*src, msg)
+        let msg_copy = (src, ConsensusMsg::decode(msg.mdata.as_ref()).unwrap());
+
+        // convert NetworkRequest to corresponding NetworkNotification for each node
+        for mut node_consensus_tx in node_consensus_txs {
+            node_consensus_tx
+                .send(NetworkNotification::RecvMessage(src, msg.clone()))
+                .await
+                .unwrap();
+        }
+        Some(msg_copy)
+    }
+
+    /// Takes the next message to deliver: the oldest one, or a random one out of the queued
+    /// messages if the playground has a seed.
+    async fn next_message(&mut self) -> (NodeId, Author, NetworkRequest) {
+        let rng = match &mut self.rng {
+            Some(rng) => rng,
+            None => {
+                return self.outbound_msgs_rx.next().await
+                    .expect("[network playground] waiting for messages, but message queue has shutdown unexpectedly");
             }
-            msg_notif => panic!(
-                "[network playground] Unexpected NetworkNotification: {:?}",
-                msg_notif
-            ),
         };
-
-        node_consensus_tx.send(msg_notif).await.unwrap();
-        msg_copy
+        if self.pending_msgs.is_empty() {
+            let msg = self.outbound_msgs_rx.next().await
+                .expect("[network playground] waiting for messages, but message queue has shutdown unexpectedly");
+            self.pending_msgs.push(msg);
+        }
+        while let Ok(Some(msg)) = self.outbound_msgs_rx.try_next() {
+            self.pending_msgs.push(msg);
+        }
+        let index = rng.gen_range(0, self.pending_msgs.len());
+        self.pending_msgs.remove(index)
     }
 
     /// Wait for exactly `num_messages` to be enqueued and delivered. Return a
@@ -214,14 +314,14 @@ impl NetworkPlayground {
         let mut msg_copies = vec![];
         while msg_copies.len() < num_messages {
             // Take the next queued message
-            let (src, net_req) = self.outbound_msgs_rx.next().await
-                .expect("[network playground] waiting for messages, but message queue has shutdown unexpectedly");
+            let (src_node, src, net_req) = self.next_message().await;
 
             // Deliver and copy message it if it's not dropped
             if !self.is_message_dropped(&src, &net_req) {
-                let msg_copy = self.deliver_message(src, net_req).await;
-                if msg_inspector(&msg_copy) {
-                    msg_copies.push(msg_copy);
+                if let Some(msg_copy) = self.deliver_message(src_node, src, net_req).await {
+                    if msg_inspector(&msg_copy) {
+                        msg_copies.push(msg_copy);
+                    }
                 }
             }
         }
@@ -302,41 +402,105 @@ impl NetworkPlayground {
             .unwrap()
             .stop_drop_message_for(src, dst)
     }
+
+    fn is_partitioned(&self, src_node: NodeId, dst_node: NodeId) -> bool {
+        self.drop_config
+            .read()
+            .unwrap()
+            .is_partitioned(src_node, dst_node)
+    }
+
+    /// Splits the network: the nodes of different partitions can't reach each other anymore.
+    /// Nodes left out of all the partitions are isolated.
+    pub fn split_network(&mut self, partitions: Vec<Vec<NodeId>>) {
+        self.drop_config.write().unwrap().split_network(partitions)
+    }
+
+    /// Lets all the nodes reach each other again.
+    pub fn heal_network(&mut self) {
+        self.drop_config.write().unwrap().heal_network()
+    }
+
+    /// Rewrites all the direct-send messages sent by `src` from now on with `rewriter`.
+    pub fn set_rewriter(&mut self, src: Author, rewriter: MessageRewriter) {
+        self.rewriters.lock().unwrap().insert(src, rewriter);
+    }
 }
 
-struct DropConfig(HashMap<Author, HashSet<Author>>);
+#[derive(Default)]
+struct DropConfig {
+    dropped: HashMap<Author, HashSet<Author>>,
+    num_nodes: usize,
+    // The partition of each node if the network is split
+    partitions: Option<HashMap<NodeId, usize>>,
+}
 
 impl DropConfig {
     pub fn is_message_dropped(&self, src: &Author, net_req: &NetworkRequest) -> bool {
         match net_req {
-            NetworkRequest::SendMessage(dst, _) => self.0.get(src).unwrap().contains(&dst),
-            NetworkRequest::SendRpc(dst, _) => self.0.get(src).unwrap().contains(&dst),
+            NetworkRequest::SendMessage(dst, _) => self.dropped.get(src).unwrap().contains(&dst),
+            NetworkRequest::SendRpc(dst, _) => self.dropped.get(src).unwrap().contains(&dst),
             _ => true,
         }
     }
 
     pub fn drop_message_for(&mut self, src: &Author, dst: Author) -> bool {
-        self.0.get_mut(src).unwrap().insert(dst)
+        self.dropped.get_mut(src).unwrap().insert(dst)
     }
 
     pub fn stop_drop_message_for(&mut self, src: &Author, dst: &Author) -> bool {
-        self.0.get_mut(src).unwrap().remove(dst)
+        self.dropped.get_mut(src).unwrap().remove(dst)
+    }
+
+    pub fn is_partitioned(&self, src_node: NodeId, dst_node: NodeId) -> bool {
+        match &self.partitions {
+            Some(partitions) => {
+                let src_partition = partitions.get(&src_node);
+                src_partition.is_none() || src_partition != partitions.get(&dst_node)
+            }
+            None => false,
+        }
+    }
+
+    pub fn split_network(&mut self, partitions: Vec<Vec<NodeId>>) {
+        let mut node_partitions = HashMap::new();
+        for (partition, nodes) in partitions.into_iter().enumerate() {
+            for node in nodes {
+                assert!(node < self.num_nodes, "Unknown node {}", node);
+                assert!(
+                    node_partitions.insert(node, partition).is_none(),
+                    "Node {} is in several partitions",
+                    node
+                );
+            }
+        }
+        self.partitions = Some(node_partitions);
+    }
+
+    pub fn heal_network(&mut self) {
+        self.partitions = None;
     }
 
-    fn add_node(&mut self, src: Author) {
-        self.0.insert(src, HashSet::new());
+    fn add_node(&mut self, src: Author) -> NodeId {
+        self.dropped.entry(src).or_insert_with(HashSet::new);
+        self.num_nodes += 1;
+        self.num_nodes - 1
     }
 }
 
+use crate::chained_bft::block_storage::{BlockReader, BlockRetriever};
 use crate::chained_bft::network::NetworkTask;
-use crate::chained_bft::test_utils::TestPayload;
+use crate::chained_bft::test_utils::{build_empty_tree, TestPayload, TreeInserter};
+use consensus_types::block::block_test_utils::gen_test_certificate;
 use consensus_types::block_retrieval::{
     BlockRetrievalRequest, BlockRetrievalResponse, BlockRetrievalStatus,
 };
 use libra_crypto::HashValue;
 #[cfg(test)]
 use libra_types::crypto_proxies::random_validator_verifier;
+use network::validator_network::RpcError;
 use std::convert::{TryFrom, TryInto};
+use std::time::Instant;
 
 #[test]
 fn test_network_api() {
@@ -356,9 +520,19 @@ fn test_network_api() {
 
         playground.add_node(*peer, consensus_tx, network_reqs_rx);
         let (self_sender, self_receiver) = channel::new_test(8);
-        let node = NetworkSender::new(*peer, network_sender, self_sender, Arc::clone(&validators));
-        let (task, receiver) =
-            NetworkTask::new(1, network_events, self_receiver, Arc::clone(&validators));
+        let node = NetworkSender::new(
+            *peer,
+            network_sender.clone(),
+            self_sender,
+            Arc::clone(&validators),
+        );
+        let (task, receiver) = NetworkTask::new(
+            1,
+            network_sender,
+            network_events,
+            self_receiver,
+            Arc::clone(&validators),
+        );
         receivers.push(receiver);
         runtime.executor().spawn(task.start());
         nodes.push(node);
@@ -424,8 +598,13 @@ fn test_rpc() {
             self_sender,
             Arc::clone(&validators),
         );
-        let (task, receiver) =
-            NetworkTask::new(1, network_events, self_receiver, Arc::clone(&validators));
+        let (task, receiver) = NetworkTask::new(
+            1,
+            network_sender.clone(),
+            network_events,
+            self_receiver,
+            Arc::clone(&validators),
+        );
         senders.push(network_sender);
         receivers.push(receiver);
         runtime.executor().spawn(task.start());
@@ -484,3 +663,149 @@ fn test_rpc() {
         assert_eq!(response.status(), BlockRetrievalStatus::IdNotFound);
     });
 }
+
+#[test]
+fn test_block_retrieval() {
+    let runtime = consensus_runtime();
+    let num_nodes = 4;
+    let mut receivers: Vec<NetworkReceivers<TestPayload>> = Vec::new();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = Vec::new();
+    let (signers, validator_verifier) = random_validator_verifier(num_nodes, None, false);
+    let validators = Arc::new(validator_verifier);
+    let peers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    for peer in peers.iter() {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
+        let network_events = ConsensusNetworkEvents::new(consensus_rx);
+
+        playground.add_node(*peer, consensus_tx, network_reqs_rx);
+        let (self_sender, self_receiver) = channel::new_test(8);
+        let node = NetworkSender::new(
+            *peer,
+            network_sender.clone(),
+            self_sender,
+            Arc::clone(&validators),
+        );
+        let (task, receiver) = NetworkTask::new(
+            1,
+            network_sender,
+            network_events,
+            self_receiver,
+            Arc::clone(&validators),
+        );
+        receivers.push(receiver);
+        runtime.executor().spawn(task.start());
+        nodes.push(node);
+    }
+
+    // A chain of 45 blocks certified by the other nodes, which node 0 has to retrieve in chunks.
+    let qc_signers: Vec<_> = signers[1..].iter().collect();
+    let mut inserter = TreeInserter::new(signers[1].clone());
+    let genesis = inserter.block_store().root();
+    let mut chain = vec![inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1)];
+    for round in 2..=45 {
+        let parent = Arc::clone(chain.last().unwrap());
+        let parent_qc = gen_test_certificate(
+            qc_signers.clone(),
+            parent.block_info(),
+            parent.quorum_cert().certified_block().clone(),
+            None,
+        );
+        chain.push(inserter.insert_block_with_qc(parent_qc, &parent, round));
+    }
+    let tip = chain.last().unwrap();
+    let qc = gen_test_certificate(
+        qc_signers,
+        tip.block_info(),
+        tip.quorum_cert().certified_block().clone(),
+        None,
+    );
+    let blocks: Arc<HashMap<_, _>> = Arc::new(
+        chain
+            .iter()
+            .map(|block| (block.id(), block.block().clone()))
+            .collect(),
+    );
+    // The chunks are of 20, 20 and 5 blocks, starting from these blocks.
+    let chunk_ids = vec![chain[44].id(), chain[24].id(), chain[4].id()];
+
+    // The signers are tried in the order of their ids: the first one times out, the second one
+    // returns an invalid chunk, and only the third one serves the blocks.
+    let mut sorted_signers = peers[1..].to_vec();
+    sorted_signers.sort();
+    let (timeout_peer, invalid_peer, good_peer) =
+        (sorted_signers[0], sorted_signers[1], sorted_signers[2]);
+    let requests = Arc::new(Mutex::new(vec![]));
+    for (peer, receiver) in peers.iter().zip(receivers.drain(..)).skip(1) {
+        let peer = *peer;
+        let mut block_retrieval = receiver.block_retrieval;
+        let blocks = Arc::clone(&blocks);
+        let requests = Arc::clone(&requests);
+        runtime.executor().spawn(async move {
+            while let Some(request) = block_retrieval.next().await {
+                requests
+                    .lock()
+                    .unwrap()
+                    .push((peer, request.req.block_id()));
+                let response = if peer == timeout_peer {
+                    // Emulates the rpc layer giving up on the peer.
+                    Err(RpcError::TimedOut)
+                } else {
+                    // The invalid chunk is off by one block.
+                    let mut id = request.req.block_id();
+                    if peer == invalid_peer {
+                        id = blocks[&id].parent_id();
+                    }
+                    let mut chunk = vec![];
+                    while let Some(block) = blocks.get(&id) {
+                        if chunk.len() as u64 == request.req.num_blocks() {
+                            break;
+                        }
+                        id = block.parent_id();
+                        chunk.push(block.clone());
+                    }
+                    let response = BlockRetrievalResponse::<TestPayload>::new(
+                        BlockRetrievalStatus::Succeeded,
+                        chunk,
+                    );
+                    Ok(ConsensusMsg {
+                        message: Some(ConsensusMsg_oneof::RespondBlock(
+                            response.try_into().unwrap(),
+                        )),
+                    }
+                    .to_bytes()
+                    .unwrap())
+                };
+                request.response_sender.send(response).unwrap();
+            }
+        });
+    }
+
+    let block_store = build_empty_tree();
+    let retriever = BlockRetriever::new(
+        nodes[0].clone(),
+        Instant::now() + Duration::from_secs(60),
+        timeout_peer,
+    );
+    let sync_info = SyncInfo::new(qc.clone(), certificate_for_genesis(), None);
+    block_on(block_store.sync_to(&sync_info, retriever)).unwrap();
+    assert!(chain
+        .iter()
+        .all(|block| block_store.block_exists(block.id())));
+    assert_eq!(block_store.highest_quorum_cert().as_ref(), &qc);
+
+    let requests = requests.lock().unwrap();
+    for (chunk_id, first_peer) in chunk_ids.iter().zip(sorted_signers.iter()) {
+        let chunk_requests: Vec<_> = requests
+            .iter()
+            .filter(|(_, block_id)| block_id == chunk_id)
+            .map(|(peer, _)| *peer)
+            .collect();
+        // The first attempt of each chunk goes to the next signer in turn, and the retrieval
+        // moves on to other signers until the good one serves the chunk.
+        assert_eq!(chunk_requests.first(), Some(first_peer));
+        assert_eq!(chunk_requests.last(), Some(&good_peer));
+    }
+}
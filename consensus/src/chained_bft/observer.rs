@@ -0,0 +1,298 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::state_replication::StateComputer;
+use consensus_types::{
+    block::Block,
+    common::{Payload, Round},
+    executed_block::ExecutedBlock,
+    proposal_msg::ProposalUncheckedSignatures,
+    quorum_cert::QuorumCert,
+};
+use executor::ExecutedTrees;
+use failure::prelude::*;
+use futures::StreamExt;
+use libra_crypto::HashValue;
+use libra_logger::prelude::*;
+use libra_types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+    validator_set::ValidatorSet,
+};
+use network::{
+    proto::ConsensusMsg_oneof,
+    validator_network::{ConsensusNetworkEvents, Event},
+};
+use std::{cmp::Ordering, collections::HashMap, convert::TryFrom, sync::Arc};
+
+/// Maximum number of blocks kept while waiting for their parents.
+const MAX_PENDING_BLOCKS: usize = 100;
+
+/// The Observer follows the blocks committed by the validators without taking part in consensus.
+/// It receives the proposals the validators accept, which the `ObserverPublisher` of its upstream
+/// peer forwards to it. It executes the proposed blocks speculatively as they come, and commits
+/// them once it receives a quorum certificate committing them: either the certificate of a
+/// proposal, or the highest commit certificate of its `SyncInfo`. All the signatures are checked
+/// against the validators of the current epoch, which change with the reconfigurations it commits.
+///
+/// If the observer misses some of the blocks to commit, e.g. because it was disconnected for a
+/// while, it hands over to state sync to reach the committed ledger info, and resumes from there.
+/// State sync also polls the upstream peers on its own when the observer stops committing blocks:
+/// the observer then resumes from the first certificate committing a block beyond the synced
+/// ledger. If state sync committed reconfigurations meanwhile, the observer first moves to the
+/// epoch of the last one, as it finds out when it receives a proposal of a later epoch or sees
+/// the committed ledger beyond its root.
+pub struct Observer<T> {
+    epoch: u64,
+    validators: Arc<ValidatorVerifier>,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    // The last committed block, and the trees resulting from its execution
+    root_id: HashValue,
+    root_round: Round,
+    root_trees: ExecutedTrees,
+    // The blocks executed on top of the root
+    blocks: HashMap<HashValue, ExecutedBlock<T>>,
+    // The blocks waiting for their parent to be executed, by parent id
+    pending_blocks: HashMap<HashValue, Vec<Block<T>>>,
+}
+
+impl<T: Payload> Observer<T> {
+    /// Starts following the validators of `epoch` from the committed `ledger_info`.
+    pub fn new(
+        epoch: u64,
+        validators: Arc<ValidatorVerifier>,
+        ledger_info: &LedgerInfo,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+    ) -> Self {
+        let (root_id, root_round) = Self::root_of(ledger_info);
+        let root_trees = state_computer.committed_trees();
+        Self {
+            epoch,
+            validators,
+            state_computer,
+            root_id,
+            root_round,
+            root_trees,
+            blocks: HashMap::new(),
+            pending_blocks: HashMap::new(),
+        }
+    }
+
+    /// Returns the id and round of the root after committing `ledger_info`: the committed block,
+    /// or the genesis block of the next epoch if it ends the epoch.
+    fn root_of(ledger_info: &LedgerInfo) -> (HashValue, Round) {
+        if ledger_info.next_validator_set().is_some() {
+            (
+                Block::<T>::make_genesis_block_from_ledger_info(ledger_info).id(),
+                0,
+            )
+        } else {
+            (ledger_info.consensus_block_id(), ledger_info.round())
+        }
+    }
+
+    #[cfg(test)]
+    pub fn root_id(&self) -> HashValue {
+        self.root_id
+    }
+
+    /// Processes the proposals forwarded by the upstream peers until the network shuts down.
+    pub async fn start(mut self, mut network_events: ConsensusNetworkEvents) {
+        while let Some(event) = network_events.next().await {
+            let (peer_id, msg) = match event {
+                Ok(Event::Message((peer_id, msg))) => (peer_id, msg),
+                Ok(_) => continue,
+                Err(e) => {
+                    warn!("Observer failed to receive a message: {:?}", e);
+                    continue;
+                }
+            };
+            let proposal = match msg.message {
+                Some(ConsensusMsg_oneof::Proposal(proposal)) => proposal,
+                _ => {
+                    warn!("Observer got an unexpected message from {}", peer_id);
+                    continue;
+                }
+            };
+            let r = match ProposalUncheckedSignatures::<T>::try_from(proposal) {
+                Ok(proposal) => self.process_proposal(proposal).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = r {
+                warn!(
+                    "Observer failed to process a proposal from {}: {}",
+                    peer_id, e
+                );
+            }
+        }
+    }
+
+    /// Commits what the certificates of the proposal commit, then executes the proposed block.
+    pub async fn process_proposal(
+        &mut self,
+        proposal: ProposalUncheckedSignatures<T>,
+    ) -> Result<()> {
+        if proposal.epoch() > self.epoch
+            || self.state_computer.committed_trees().version() > self.root_trees.version()
+        {
+            self.sync_epoch().await?;
+        }
+        ensure!(
+            proposal.epoch() == self.epoch,
+            "Proposal of epoch {} while observing epoch {}",
+            proposal.epoch(),
+            self.epoch
+        );
+        let proposal = proposal
+            .validate_signatures(&self.validators)?
+            .verify_well_formed()?;
+        let highest_ledger_info = proposal.sync_info().highest_ledger_info();
+        highest_ledger_info.verify(&self.validators)?;
+        self.process_commit(highest_ledger_info).await?;
+        self.process_commit(proposal.proposal().quorum_cert())
+            .await?;
+        if proposal.epoch() == self.epoch {
+            self.execute(proposal.take_proposal()).await?;
+        }
+        Ok(())
+    }
+
+    /// Executes `block` if its parent was, and the blocks waiting for it in turn. Otherwise keeps
+    /// it until its parent is executed.
+    async fn execute(&mut self, block: Block<T>) -> Result<()> {
+        let mut blocks = vec![block];
+        while let Some(block) = blocks.pop() {
+            if block.round() <= self.root_round || self.blocks.contains_key(&block.id()) {
+                continue;
+            }
+            let parent_trees = if block.parent_id() == self.root_id {
+                self.root_trees.clone()
+            } else if let Some(parent) = self.blocks.get(&block.parent_id()) {
+                parent.executed_trees().clone()
+            } else {
+                if self.pending_blocks.values().map(Vec::len).sum::<usize>() < MAX_PENDING_BLOCKS {
+                    self.pending_blocks
+                        .entry(block.parent_id())
+                        .or_insert_with(Vec::new)
+                        .push(block);
+                }
+                continue;
+            };
+            let output = self.state_computer.compute(&block, parent_trees).await?;
+            let id = block.id();
+            self.blocks.insert(id, ExecutedBlock::new(block, output));
+            if let Some(children) = self.pending_blocks.remove(&id) {
+                blocks.extend(children);
+            }
+        }
+        Ok(())
+    }
+
+    /// Commits the block committed by `qc` if it's beyond the root: directly if all the blocks up
+    /// to it were executed, through state sync otherwise.
+    async fn process_commit(&mut self, qc: &QuorumCert) -> Result<()> {
+        let commit_info = qc.commit_info();
+        if commit_info.epoch() != self.epoch || commit_info.round() <= self.root_round {
+            return Ok(());
+        }
+        let ledger_info = qc.ledger_info().clone();
+        let committed_trees = self.state_computer.committed_trees();
+        let root_trees = if committed_trees.version() != self.root_trees.version() {
+            // State sync committed beyond the root while polling on its own: skip the blocks it
+            // already committed.
+            match Some(commit_info.version()).cmp(&committed_trees.version()) {
+                Ordering::Less => return Ok(()),
+                Ordering::Equal => committed_trees,
+                Ordering::Greater => {
+                    self.state_computer.sync_to(ledger_info.clone()).await?;
+                    self.state_computer.committed_trees()
+                }
+            }
+        } else {
+            self.commit_from_root(ledger_info.clone()).await?
+        };
+
+        self.root_trees = root_trees;
+        if let Some(validator_set) = ledger_info.ledger_info().next_validator_set() {
+            self.start_next_epoch(ledger_info.ledger_info(), validator_set);
+        } else {
+            let (root_id, root_round) = Self::root_of(ledger_info.ledger_info());
+            self.root_id = root_id;
+            self.root_round = root_round;
+            self.blocks.retain(|_, block| block.round() > root_round);
+            self.pending_blocks
+                .retain(|_, blocks| blocks.iter().any(|block| block.round() > root_round));
+        }
+        Ok(())
+    }
+
+    /// Moves to the epoch of `validator_set`, which `ledger_info` ends the current one with,
+    /// starting from its genesis block.
+    fn start_next_epoch(&mut self, ledger_info: &LedgerInfo, validator_set: &ValidatorSet) {
+        let (root_id, root_round) = Self::root_of(ledger_info);
+        self.root_id = root_id;
+        self.root_round = root_round;
+        self.epoch = ledger_info.epoch() + 1;
+        self.validators = Arc::new(validator_set.into());
+        self.blocks.clear();
+        self.pending_blocks.clear();
+        info!("Observer moves to epoch {}", self.epoch);
+    }
+
+    /// Moves to the epoch following the last reconfiguration committed, if state sync committed
+    /// any beyond the root.
+    async fn sync_epoch(&mut self) -> Result<()> {
+        let proof = self.state_computer.get_epoch_proof(self.epoch).await?;
+        if proof.ledger_info_with_sigs.is_empty() {
+            return Ok(());
+        }
+        let ledger_info = proof.verify(self.epoch, &self.validators)?;
+        let ledger_info = ledger_info.ledger_info();
+        let validator_set = ledger_info
+            .next_validator_set()
+            .ok_or_else(|| format_err!("{} doesn't end its epoch", ledger_info))?;
+        // If state sync committed beyond the reconfiguration too, the root trees are left behind
+        // the committed ones, so that the certificates of the blocks it committed are skipped.
+        let committed_trees = self.state_computer.committed_trees();
+        if committed_trees.version() == Some(ledger_info.version()) {
+            self.root_trees = committed_trees;
+        }
+        self.start_next_epoch(ledger_info, validator_set);
+        Ok(())
+    }
+
+    /// Commits the block of `ledger_info` if all the blocks from the root up to it were
+    /// executed, syncs to it otherwise, and returns the resulting committed trees.
+    async fn commit_from_root(
+        &self,
+        ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<ExecutedTrees> {
+        let mut path = vec![];
+        let mut id = ledger_info.ledger_info().consensus_block_id();
+        while id != self.root_id {
+            match self.blocks.get(&id) {
+                Some(block) => {
+                    path.push(block);
+                    id = block.parent_id();
+                }
+                None => break,
+            }
+        }
+        if id == self.root_id {
+            path.reverse();
+            let root_trees = path
+                .last()
+                .map(|block| block.executed_trees().clone())
+                .unwrap_or_else(|| self.root_trees.clone());
+            self.state_computer.commit(path, ledger_info).await?;
+            Ok(root_trees)
+        } else {
+            info!(
+                "Observer is missing blocks to commit {}, hand over to state sync",
+                ledger_info.ledger_info()
+            );
+            self.state_computer.sync_to(ledger_info).await?;
+            Ok(self.state_computer.committed_trees())
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::observer::Observer,
+    consensus_provider::{create_storage_read_client, ConsensusProvider},
+    state_computer::ExecutionProxy,
+    state_replication::StateComputer,
+};
+use executor::Executor;
+use failure::prelude::*;
+use libra_config::config::NodeConfig;
+use libra_logger::prelude::*;
+use libra_types::transaction::SignedTransaction;
+use network::validator_network::ConsensusNetworkEvents;
+use state_synchronizer::StateSyncClient;
+use std::sync::Arc;
+use storage_client::StorageRead;
+use tokio::runtime::{self, Runtime};
+use vm_runtime::MoveVM;
+
+/// Supports the implementation of ConsensusProvider for full nodes following consensus as
+/// observers.
+pub struct ObserverProvider {
+    runtime: Option<Runtime>,
+    network_events: Option<ConsensusNetworkEvents>,
+    read_client: Arc<dyn StorageRead>,
+    state_computer: Arc<dyn StateComputer<Payload = Vec<SignedTransaction>>>,
+}
+
+impl ObserverProvider {
+    pub fn new(
+        node_config: &NodeConfig,
+        network_events: ConsensusNetworkEvents,
+        executor: Arc<Executor<MoveVM>>,
+        synchronizer_client: Arc<StateSyncClient>,
+    ) -> Self {
+        let runtime = runtime::Builder::new()
+            .name_prefix("observer-")
+            .build()
+            .expect("Failed to create Tokio runtime!");
+        Self {
+            runtime: Some(runtime),
+            network_events: Some(network_events),
+            read_client: create_storage_read_client(node_config),
+            state_computer: Arc::new(ExecutionProxy::new(executor, synchronizer_client)),
+        }
+    }
+}
+
+impl ConsensusProvider for ObserverProvider {
+    fn start(&mut self) -> Result<()> {
+        debug!("Starting observer provider.");
+        let network_events = self
+            .network_events
+            .take()
+            .expect("already started, network events are None");
+        let startup_info = self
+            .read_client
+            .get_startup_info()?
+            .ok_or_else(|| format_err!("startup info is None"))?;
+        let epoch_info = startup_info.ledger_info_with_validators.ledger_info();
+        let validators = Arc::new(
+            epoch_info
+                .next_validator_set()
+                .ok_or_else(|| format_err!("should have ValidatorSet when start new epoch"))?
+                .into(),
+        );
+        let observer = Observer::new(
+            epoch_info.epoch() + 1,
+            validators,
+            startup_info.ledger_info.ledger_info(),
+            Arc::clone(&self.state_computer),
+        );
+        self.runtime
+            .as_ref()
+            .ok_or_else(|| format_err!("Observer start: No valid runtime found!"))?
+            .executor()
+            .spawn(observer.start(network_events));
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(rt) = self.runtime.take() {
+            rt.shutdown_now();
+            debug!("Observer provider stopped.");
+        }
+    }
+}
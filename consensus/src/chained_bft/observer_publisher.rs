@@ -0,0 +1,111 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use channel::{libra_channel, message_queues::QueueStyle};
+use futures::{stream::select, StreamExt};
+use libra_logger::prelude::*;
+use libra_types::account_address::AccountAddress;
+use network::{
+    proto::{ConsensusMsg, ConsensusMsg_oneof, Proposal},
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event},
+};
+use std::collections::HashSet;
+
+/// Number of proposals of each proposer waiting to be published.
+const MAX_PENDING_PROPOSALS: usize = 10;
+
+/// Publishes the proposals accepted by a validator to the observers connected to it, i.e. to the
+/// full nodes running an `Observer` on the full node network. The proposals are forwarded as they
+/// were received: they carry the signatures of the validators, so the observers don't need to
+/// trust the publisher.
+pub struct ObserverPublisher {
+    proposal_tx: libra_channel::Sender<AccountAddress, Proposal>,
+}
+
+impl ObserverPublisher {
+    /// Returns the publisher, along with the task sending the published proposals over the given
+    /// network.
+    pub fn new(
+        network_sender: ConsensusNetworkSender,
+        network_events: ConsensusNetworkEvents,
+    ) -> (Self, ObserverPublisherTask) {
+        let (proposal_tx, proposal_rx) =
+            libra_channel::new(QueueStyle::LIFO, MAX_PENDING_PROPOSALS, None);
+        (
+            Self { proposal_tx },
+            ObserverPublisherTask {
+                network_sender,
+                network_events,
+                proposal_rx,
+                observers: HashSet::new(),
+            },
+        )
+    }
+
+    /// Publishes a proposal of `author` which was checked to be valid. Never blocks: the oldest
+    /// proposals are dropped if the observers can't keep up, and the most recent ones are sent
+    /// first.
+    pub fn publish(&mut self, author: AccountAddress, proposal: Proposal) {
+        if let Err(e) = self.proposal_tx.push(author, proposal) {
+            warn!("Failed to publish a proposal to the observers: {}", e);
+        }
+    }
+}
+
+enum PublisherInput {
+    Proposal(Proposal),
+    Network(Event<ConsensusMsg>),
+    NetworkError,
+}
+
+/// Sends the published proposals to the connected observers.
+pub struct ObserverPublisherTask {
+    network_sender: ConsensusNetworkSender,
+    network_events: ConsensusNetworkEvents,
+    proposal_rx: libra_channel::Receiver<AccountAddress, Proposal>,
+    observers: HashSet<AccountAddress>,
+}
+
+impl ObserverPublisherTask {
+    pub async fn start(mut self) {
+        let proposals = self.proposal_rx.map(PublisherInput::Proposal);
+        let events = self.network_events.map(|event| match event {
+            Ok(event) => PublisherInput::Network(event),
+            Err(_) => PublisherInput::NetworkError,
+        });
+        let mut inputs = select(proposals, events);
+        while let Some(input) = inputs.next().await {
+            match input {
+                PublisherInput::Proposal(proposal) => {
+                    if self.observers.is_empty() {
+                        continue;
+                    }
+                    let msg = ConsensusMsg {
+                        message: Some(ConsensusMsg_oneof::Proposal(proposal)),
+                    };
+                    if let Err(e) = self
+                        .network_sender
+                        .send_to_many(self.observers.iter().cloned(), msg)
+                        .await
+                    {
+                        warn!("Failed to send a proposal to the observers: {:?}", e);
+                    }
+                }
+                PublisherInput::Network(Event::NewPeer(peer_id)) => {
+                    debug!("Observer {} connected", peer_id);
+                    self.observers.insert(peer_id);
+                }
+                PublisherInput::Network(Event::LostPeer(peer_id)) => {
+                    debug!("Observer {} disconnected", peer_id);
+                    self.observers.remove(&peer_id);
+                }
+                PublisherInput::Network(_) => {
+                    // Observers only listen.
+                }
+                PublisherInput::NetworkError => {
+                    warn!("Failed to receive an event from the observers network");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{observer::Observer, test_utils::TestPayload},
+    state_replication::StateComputer,
+};
+use consensus_types::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    executed_block::ExecutedBlock,
+    proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
+    quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+    vote_data::VoteData,
+};
+use executor::{ExecutedTrees, ProcessedVMOutput};
+use failure::prelude::*;
+use futures::{executor::block_on, future, Future, FutureExt};
+use libra_crypto::{hash::CryptoHash, HashValue};
+use libra_types::{
+    block_info::BlockInfo,
+    crypto_proxies::{
+        LedgerInfoWithSignatures, ValidatorChangeEventWithProof, ValidatorInfo, ValidatorSigner,
+        ValidatorVerifier,
+    },
+    ledger_info::LedgerInfo,
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+/// Records the blocks committed by the observer, and the blocks it synced to. Serves the epoch
+/// changes committed by state sync on its own.
+#[derive(Default)]
+struct RecordingStateComputer {
+    commits: Mutex<Vec<Vec<HashValue>>>,
+    syncs: Mutex<Vec<HashValue>>,
+    epoch_changes: Mutex<Vec<LedgerInfoWithSignatures>>,
+}
+
+impl StateComputer for RecordingStateComputer {
+    type Payload = TestPayload;
+
+    fn compute(
+        &self,
+        _block: &Block<Self::Payload>,
+        _parent_executed_trees: ExecutedTrees,
+    ) -> Pin<Box<dyn Future<Output = Result<ProcessedVMOutput>> + Send>> {
+        future::ok(ProcessedVMOutput::new(
+            vec![],
+            ExecutedTrees::new_empty(),
+            None,
+        ))
+        .boxed()
+    }
+
+    fn commit(
+        &self,
+        blocks: Vec<&ExecutedBlock<Self::Payload>>,
+        commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        assert_eq!(
+            blocks.last().map(|block| block.id()),
+            Some(commit.ledger_info().consensus_block_id())
+        );
+        self.commits
+            .lock()
+            .unwrap()
+            .push(blocks.iter().map(|block| block.id()).collect());
+        future::ok(()).boxed()
+    }
+
+    fn sync_to(
+        &self,
+        target: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.syncs
+            .lock()
+            .unwrap()
+            .push(target.ledger_info().consensus_block_id());
+        future::ok(()).boxed()
+    }
+
+    fn committed_trees(&self) -> ExecutedTrees {
+        ExecutedTrees::new_empty()
+    }
+
+    fn get_epoch_proof(
+        &self,
+        start_epoch: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<ValidatorChangeEventWithProof>> + Send>> {
+        let ledger_infos = self
+            .epoch_changes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|ledger_info| ledger_info.ledger_info().epoch() >= start_epoch)
+            .cloned()
+            .collect();
+        future::ok(ValidatorChangeEventWithProof::new(ledger_infos)).boxed()
+    }
+}
+
+fn make_signers_and_verifier(num_validators: u8) -> (Vec<ValidatorSigner>, ValidatorVerifier) {
+    let signers: Vec<_> = (0..num_validators)
+        .map(|i| ValidatorSigner::random([i; 32]))
+        .collect();
+    let verifier = make_verifier(&signers);
+    (signers, verifier)
+}
+
+fn make_verifier(signers: &[ValidatorSigner]) -> ValidatorVerifier {
+    let validator_infos = signers
+        .iter()
+        .map(|signer| (signer.author(), ValidatorInfo::new(signer.public_key(), 1)))
+        .collect::<BTreeMap<_, _>>();
+    ValidatorVerifier::new(validator_infos)
+}
+
+fn block_info(block: &Block<TestPayload>) -> BlockInfo {
+    BlockInfo::new(
+        block.epoch(),
+        block.round(),
+        block.id(),
+        HashValue::zero(),
+        0,
+        block.timestamp_usecs(),
+        None,
+    )
+}
+
+/// Returns a certificate for `block` signed by `signers`, committing `committed` if any.
+fn certificate(
+    signers: &[ValidatorSigner],
+    block: &Block<TestPayload>,
+    committed: Option<&Block<TestPayload>>,
+) -> QuorumCert {
+    let parent_info = BlockInfo::new(
+        block.epoch(),
+        block.quorum_cert().certified_block().round(),
+        block.parent_id(),
+        HashValue::zero(),
+        0,
+        block.quorum_cert().certified_block().timestamp_usecs(),
+        None,
+    );
+    let vote_data = VoteData::new(block_info(block), parent_info);
+    let commit_info = committed.map_or_else(BlockInfo::empty, block_info);
+    let ledger_info = LedgerInfo::new(commit_info, vote_data.hash());
+    let signatures = signers
+        .iter()
+        .map(|signer| {
+            (
+                signer.author(),
+                signer.sign_message(ledger_info.hash()).unwrap(),
+            )
+        })
+        .collect();
+    QuorumCert::new(
+        vote_data,
+        LedgerInfoWithSignatures::new(ledger_info, signatures),
+    )
+}
+
+/// Builds a chain of `length` blocks on top of genesis, one per round, each certified by the next
+/// one. As of round 4, each certificate commits the block 2 rounds before the certified one.
+fn build_chain(signers: &[ValidatorSigner], length: usize) -> Vec<Block<TestPayload>> {
+    build_chain_from(signers, certificate_for_genesis(), length)
+}
+
+/// Same as `build_chain`, on top of the genesis block certified by `genesis_qc`.
+fn build_chain_from(
+    signers: &[ValidatorSigner],
+    genesis_qc: QuorumCert,
+    length: usize,
+) -> Vec<Block<TestPayload>> {
+    let mut blocks: Vec<Block<TestPayload>> = vec![];
+    for round in 1..=length as u64 {
+        let qc = match blocks.len() {
+            0 => genesis_qc.clone(),
+            n if n < 3 => certificate(&signers[..3], &blocks[n - 1], None),
+            n => certificate(&signers[..3], &blocks[n - 1], Some(&blocks[n - 3])),
+        };
+        blocks.push(Block::new_proposal(vec![], round, round, qc, &signers[0]));
+    }
+    blocks
+}
+
+fn proposal(block: &Block<TestPayload>) -> ProposalUncheckedSignatures<TestPayload> {
+    let sync_info = SyncInfo::new(block.quorum_cert().clone(), certificate_for_genesis(), None);
+    let proposal = ProposalMsg::new(block.clone(), sync_info);
+    ProposalUncheckedSignatures::try_from(network::proto::Proposal::try_from(proposal).unwrap())
+        .unwrap()
+}
+
+fn new_observer(
+    validators: ValidatorVerifier,
+) -> (Observer<TestPayload>, Arc<RecordingStateComputer>) {
+    let state_computer = Arc::new(RecordingStateComputer::default());
+    let observer = Observer::new(
+        1,
+        Arc::new(validators),
+        &LedgerInfo::genesis(),
+        state_computer.clone(),
+    );
+    (observer, state_computer)
+}
+
+#[test]
+fn test_observer_commits_executed_blocks() {
+    let (signers, validators) = make_signers_and_verifier(4);
+    let (mut observer, state_computer) = new_observer(validators);
+    let blocks = build_chain(&signers, 5);
+
+    for block in &blocks[..3] {
+        block_on(observer.process_proposal(proposal(block))).unwrap();
+    }
+    assert!(state_computer.commits.lock().unwrap().is_empty());
+
+    // The certificate of the 4th block commits the first one.
+    block_on(observer.process_proposal(proposal(&blocks[3]))).unwrap();
+    assert_eq!(
+        *state_computer.commits.lock().unwrap(),
+        vec![vec![blocks[0].id()]]
+    );
+    assert_eq!(observer.root_id(), blocks[0].id());
+
+    block_on(observer.process_proposal(proposal(&blocks[4]))).unwrap();
+    assert_eq!(
+        *state_computer.commits.lock().unwrap(),
+        vec![vec![blocks[0].id()], vec![blocks[1].id()]]
+    );
+    assert!(state_computer.syncs.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_observer_executes_blocks_out_of_order() {
+    let (signers, validators) = make_signers_and_verifier(4);
+    let (mut observer, state_computer) = new_observer(validators);
+    let blocks = build_chain(&signers, 4);
+
+    // The children wait for their parent to be executed.
+    for i in &[2, 1, 0, 3] {
+        block_on(observer.process_proposal(proposal(&blocks[*i]))).unwrap();
+    }
+    assert_eq!(
+        *state_computer.commits.lock().unwrap(),
+        vec![vec![blocks[0].id()]]
+    );
+    assert!(state_computer.syncs.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_observer_syncs_to_missing_blocks() {
+    let (signers, validators) = make_signers_and_verifier(4);
+    let (mut observer, state_computer) = new_observer(validators);
+    let blocks = build_chain(&signers, 4);
+
+    // Without the blocks to commit, the observer hands over to state sync.
+    block_on(observer.process_proposal(proposal(&blocks[3]))).unwrap();
+    assert!(state_computer.commits.lock().unwrap().is_empty());
+    assert_eq!(*state_computer.syncs.lock().unwrap(), vec![blocks[0].id()]);
+    assert_eq!(observer.root_id(), blocks[0].id());
+}
+
+#[test]
+fn test_observer_rejects_certificates_without_quorum() {
+    let (signers, validators) = make_signers_and_verifier(4);
+    let (mut observer, state_computer) = new_observer(validators);
+    let blocks = build_chain(&signers, 3);
+    let qc = certificate(&signers[..2], &blocks[2], Some(&blocks[0]));
+    let block = Block::new_proposal(vec![], 4, 4, qc, &signers[0]);
+
+    for block in &blocks {
+        block_on(observer.process_proposal(proposal(block))).unwrap();
+    }
+    assert!(block_on(observer.process_proposal(proposal(&block))).is_err());
+    assert!(state_computer.commits.lock().unwrap().is_empty());
+    assert!(state_computer.syncs.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_observer_syncs_across_epochs() {
+    let (signers, validators) = make_signers_and_verifier(4);
+    let (mut observer, state_computer) = new_observer(validators);
+    let blocks = build_chain(&signers, 3);
+    let next_signers: Vec<_> = (4..8).map(|i| ValidatorSigner::random([i; 32])).collect();
+
+    // While the observer was away, state sync committed the reconfiguration of the 3rd block,
+    // which hands over to the next validators.
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(
+            1,
+            blocks[2].round(),
+            blocks[2].id(),
+            HashValue::zero(),
+            0,
+            blocks[2].timestamp_usecs(),
+            Some((&make_verifier(&next_signers)).into()),
+        ),
+        HashValue::zero(),
+    );
+    let signatures = signers[..3]
+        .iter()
+        .map(|signer| {
+            (
+                signer.author(),
+                signer.sign_message(ledger_info.hash()).unwrap(),
+            )
+        })
+        .collect();
+    let genesis = Block::<TestPayload>::make_genesis_block_from_ledger_info(&ledger_info);
+    let genesis_qc =
+        QuorumCert::certificate_for_genesis_from_ledger_info(&ledger_info, genesis.id());
+    let next_blocks = build_chain_from(&next_signers, genesis_qc, 4);
+
+    // The proposals of the next epoch are rejected until the reconfiguration is committed.
+    assert!(block_on(observer.process_proposal(proposal(&next_blocks[0]))).is_err());
+    state_computer
+        .epoch_changes
+        .lock()
+        .unwrap()
+        .push(LedgerInfoWithSignatures::new(ledger_info, signatures));
+
+    // The observer moves to the next epoch, and follows its validators from its genesis block.
+    for block in &next_blocks {
+        block_on(observer.process_proposal(proposal(block))).unwrap();
+    }
+    assert_eq!(
+        *state_computer.commits.lock().unwrap(),
+        vec![vec![next_blocks[0].id()]]
+    );
+    assert_eq!(observer.root_id(), next_blocks[0].id());
+    assert!(state_computer.syncs.lock().unwrap().is_empty());
+}
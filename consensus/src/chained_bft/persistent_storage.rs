@@ -12,12 +12,17 @@ use failure::{Result, ResultExt};
 use libra_config::config::NodeConfig;
 use libra_crypto::HashValue;
 use libra_logger::prelude::*;
+use libra_types::block_metadata::BlockMetadata;
 use libra_types::crypto_proxies::ValidatorVerifier;
 use libra_types::ledger_info::LedgerInfo;
+use libra_types::transaction::Transaction;
 use rmp_serde::{from_slice, to_vec_named};
 use std::{collections::HashSet, sync::Arc};
 use storage_client::StorageRead;
 
+/// Number of transactions read at once when looking for the metadata of the committed blocks.
+const HISTORY_BATCH_SIZE: u64 = 1000;
+
 /// Persistent storage is essential for maintaining safety when a node crashes.  Specifically,
 /// upon a restart, a correct node will not equivocate.  Even if all nodes crash, safety is
 /// guaranteed.  This trait also also supports liveness aspects (i.e. highest timeout certificate)
@@ -37,6 +42,10 @@ pub trait PersistentStorage<T>: Send + Sync {
     /// Construct necessary data to start consensus.
     fn start(&self) -> RecoveryData<T>;
 
+    /// Return the metadata of the last `num_blocks` blocks committed in the current epoch, oldest
+    /// first, as persisted via StateComputer.
+    fn get_committed_history(&self, num_blocks: usize) -> Result<Vec<BlockMetadata>>;
+
     /// Persist the highest timeout certificate for improved liveness - proof for other replicas
     /// to jump to this round
     fn save_highest_timeout_cert(&self, highest_timeout_cert: TimeoutCertificate) -> Result<()>;
@@ -236,7 +245,10 @@ pub struct StorageWriteProxy {
 impl StorageWriteProxy {
     pub fn new(config: &NodeConfig) -> Self {
         let read_client = create_storage_read_client(config);
-        let db = Arc::new(ConsensusDB::new(config.get_storage_dir()));
+        let db = Arc::new(ConsensusDB::new_with_config(
+            config.get_storage_dir(),
+            &config.storage,
+        ));
         StorageWriteProxy { db, read_client }
     }
 }
@@ -335,6 +347,46 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
         initial_data
     }
 
+    fn get_committed_history(&self, num_blocks: usize) -> Result<Vec<BlockMetadata>> {
+        let startup_info = self
+            .read_client
+            .get_startup_info()?
+            .ok_or_else(|| format_err!("startup info is None"))?;
+        let ledger_version = startup_info.ledger_info.ledger_info().version();
+        // The blocks of the current epoch are committed after the ledger info which started it.
+        let epoch_start_version = startup_info
+            .ledger_info_with_validators
+            .ledger_info()
+            .version()
+            + 1;
+        // Each block starts with its BlockMetadata transaction: walk the ledger backwards until
+        // enough of them are found.
+        let mut history = vec![];
+        let mut end_version = ledger_version + 1;
+        while history.len() < num_blocks && end_version > epoch_start_version {
+            let start_version = end_version
+                .saturating_sub(HISTORY_BATCH_SIZE)
+                .max(epoch_start_version);
+            let transactions = self
+                .read_client
+                .get_transactions(
+                    start_version,
+                    end_version - start_version,
+                    ledger_version,
+                    false,
+                )?
+                .transactions;
+            history.extend(transactions.into_iter().rev().filter_map(|txn| match txn {
+                Transaction::BlockMetadata(metadata) => Some(metadata),
+                _ => None,
+            }));
+            end_version = start_version;
+        }
+        history.truncate(num_blocks);
+        history.reverse();
+        Ok(history)
+    }
+
     fn save_highest_timeout_cert(&self, highest_timeout_cert: TimeoutCertificate) -> Result<()> {
         self.db
             .save_highest_timeout_certificate(to_vec_named(&highest_timeout_cert)?)
@@ -9,6 +9,7 @@ use consensus_types::{
 };
 use failure::Result;
 use libra_crypto::HashValue;
+use libra_types::block_metadata::BlockMetadata;
 use libra_types::crypto_proxies::ValidatorVerifier;
 use libra_types::ledger_info::LedgerInfo;
 use std::collections::BTreeMap;
@@ -150,6 +151,10 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
         self.try_start().unwrap()
     }
 
+    fn get_committed_history(&self, _num_blocks: usize) -> Result<Vec<BlockMetadata>> {
+        Ok(vec![])
+    }
+
     fn save_highest_timeout_cert(
         &self,
         highest_timeout_certificate: TimeoutCertificate,
@@ -197,6 +202,11 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
         )
         .unwrap()
     }
+
+    fn get_committed_history(&self, _num_blocks: usize) -> Result<Vec<BlockMetadata>> {
+        Ok(vec![])
+    }
+
     fn save_highest_timeout_cert(&self, _: TimeoutCertificate) -> Result<()> {
         Ok(())
     }
@@ -5,7 +5,9 @@ use failure::prelude::*;
 use libra_config::config::NodeConfig;
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 
-use crate::chained_bft::chained_bft_consensus_provider::ChainedBftProvider;
+use crate::chained_bft::{
+    chained_bft_consensus_provider::ChainedBftProvider, observer_provider::ObserverProvider,
+};
 use executor::Executor;
 use grpcio::{ChannelBuilder, EnvBuilder};
 use libra_mempool::proto::mempool::MempoolClient;
@@ -27,11 +29,13 @@ pub trait ConsensusProvider {
     fn stop(&mut self);
 }
 
-/// Helper function to create a ConsensusProvider based on configuration
+/// Helper function to create a ConsensusProvider based on configuration. The proposals are
+/// published to the observers of `observer_network`, if any.
 pub fn make_consensus_provider(
     node_config: &mut NodeConfig,
     network_sender: ConsensusNetworkSender,
     network_receiver: ConsensusNetworkEvents,
+    observer_network: Option<(ConsensusNetworkSender, ConsensusNetworkEvents)>,
     executor: Arc<Executor<MoveVM>>,
     state_sync_client: Arc<StateSyncClient>,
 ) -> Box<dyn ConsensusProvider> {
@@ -39,12 +43,29 @@ pub fn make_consensus_provider(
         node_config,
         network_sender,
         network_receiver,
+        observer_network,
         create_mempool_client(node_config),
         executor,
         state_sync_client,
     ))
 }
 
+/// Helper function to create a ConsensusProvider following the committed blocks as an observer of
+/// the proposals received from `network_receiver`, for full nodes.
+pub fn make_observer_provider(
+    node_config: &NodeConfig,
+    network_receiver: ConsensusNetworkEvents,
+    executor: Arc<Executor<MoveVM>>,
+    state_sync_client: Arc<StateSyncClient>,
+) -> Box<dyn ConsensusProvider> {
+    Box::new(ObserverProvider::new(
+        node_config,
+        network_receiver,
+        executor,
+        state_sync_client,
+    ))
+}
+
 /// Create a mempool client assuming the mempool is running on localhost
 fn create_mempool_client(config: &NodeConfig) -> Arc<MempoolClient> {
     let port = config.mempool.mempool_service_port;
@@ -85,6 +85,9 @@ pub static ref TIMEOUT_COUNT: IntCounter = register_int_counter!("libra_consensu
 /// The timeout of the current round.
 pub static ref ROUND_TIMEOUT_MS: IntGauge = register_int_gauge!("libra_consensus_round_timeout_s", "The timeout of the current round.").unwrap();
 
+/// The timeout of the rounds right after a commit, as derived from the recent round latencies.
+pub static ref PACEMAKER_BASE_TIMEOUT_MS: IntGauge = register_int_gauge!("libra_consensus_pacemaker_base_timeout_ms", "The timeout of the rounds right after a commit, derived from the recent round latencies by the adaptive pacemaker.").unwrap();
+
 ////////////////////////
 // SYNCMANAGER COUNTERS
 ////////////////////////
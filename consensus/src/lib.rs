@@ -21,6 +21,8 @@ extern crate prometheus;
 
 mod chained_bft;
 
+pub use chained_bft::consensusdb_inspector;
+
 mod util;
 
 #[cfg(feature = "fuzzing")]
@@ -25,21 +25,60 @@
 //! assert!(signature.verify(&hashed_message, &public_key).is_ok());
 //! ```
 //! **Note**: The above example generates a private key using a private function intended only for
-//! testing purposes. Production code should generate the key according to the spec [draft-irtf-cfrg-bls-signature-00](https://tools.ietf.org/id/draft-irtf-cfrg-bls-signature-00.html#keygen).
+//! testing purposes. Production code should derive the key from a secret with
+//! [`BLS12381PrivateKey::derive`], which follows KeyGen of the spec [draft-irtf-cfrg-bls-signature-00](https://tools.ietf.org/id/draft-irtf-cfrg-bls-signature-00.html#keygen).
 //!
-//! This module is not currently used, but could be included in the future for improved
-//! performance in consensus.
-
-use crate::{traits::*, HashValue};
+//! Signatures of the same message can be aggregated into one, verified against the aggregate of
+//! the public keys of the signers. Each public key must come with a proof of possession of its
+//! private key, checked before the key takes part in any aggregate:
+//!
+//! ```
+//! # use libra_crypto::hash::{CryptoHasher, TestOnlyHasher};
+//! # use libra_crypto::{
+//! #     bls12381::*,
+//! #     traits::{Signature, SigningKey, Uniform},
+//! # };
+//! # use rand::{rngs::StdRng, SeedableRng};
+//! # let mut hasher = TestOnlyHasher::default();
+//! # hasher.write("Test message".as_bytes());
+//! # let hashed_message = hasher.finish();
+//! let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+//! let private_keys: Vec<_> = (0..3)
+//!     .map(|_| BLS12381PrivateKey::generate_for_testing(&mut rng))
+//!     .collect();
+//! let public_keys: Vec<BLS12381PublicKey> = private_keys.iter().map(Into::into).collect();
+//! for (private_key, public_key) in private_keys.iter().zip(&public_keys) {
+//!     let proof = private_key.create_proof_of_possession();
+//!     assert!(public_key.verify_proof_of_possession(&proof).is_ok());
+//! }
+//! let signatures: Vec<_> = private_keys
+//!     .iter()
+//!     .map(|private_key| private_key.sign_message(&hashed_message))
+//!     .collect();
+//! let signature = aggregate_signatures(&signatures).unwrap();
+//! let public_key = aggregate_public_keys(&public_keys).unwrap();
+//! assert!(signature.verify(&hashed_message, &public_key).is_ok());
+//! ```
+//!
+//! Consensus signs with BLS12-381 when configured to, so that quorum and timeout certificates
+//! carry a single aggregate signature.
+
+use crate::{
+    hash::{CryptoHasher, ProofOfPossessionHasher},
+    hkdf::Hkdf,
+    traits::*,
+    HashValue,
+};
 use core::convert::TryFrom;
 use failure::prelude::*;
 use libra_crypto_derive::{Deref, SilentDebug, SilentDisplay};
 use pairing::{
-    bls12_381::{Fr, FrRepr},
-    PrimeField,
+    bls12_381::{Fr, FrRepr, G1Compressed, G2Compressed, G1, G2},
+    CurveAffine, CurveProjective, EncodedPoint, Field, PrimeField,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 
 /// The length of the BLS12381PrivateKey.
 pub const BLS12381_PRIVATE_KEY_LENGTH: usize = 32;
@@ -47,6 +86,13 @@ pub const BLS12381_PRIVATE_KEY_LENGTH: usize = 32;
 pub const BLS12381_PUBLIC_KEY_LENGTH: usize = threshold_crypto::PK_SIZE;
 /// The length of the BLS12381Signature.
 pub const BLS12381_SIGNATURE_LENGTH: usize = threshold_crypto::SIG_SIZE;
+/// The minimum length of the secret a BLS12381PrivateKey is derived from.
+pub const BLS12381_KEYGEN_SECRET_MIN_LENGTH: usize = 32;
+
+/// The salt of HKDF-Extract in KeyGen.
+const KEYGEN_SALT: &[u8] = b"BLS-SIG-KEYGEN-SALT-";
+/// The length of the output of HKDF-Expand in KeyGen, reduced modulo r into the private key.
+const KEYGEN_OUTPUT_LENGTH: usize = 48;
 
 // type alias for this unwieldy type.
 type ThresholdBLSPrivateKey =
@@ -67,11 +113,70 @@ pub struct BLS12381PublicKey(threshold_crypto::PublicKey);
 #[derive(Clone, Hash, Serialize, Deserialize, Deref, Debug, PartialEq, Eq)]
 pub struct BLS12381Signature(threshold_crypto::Signature);
 
+impl BLS12381PrivateKey {
+    /// Derives a private key from `secret` following KeyGen of the spec
+    /// [draft-irtf-cfrg-bls-signature-00](https://tools.ietf.org/id/draft-irtf-cfrg-bls-signature-00.html#keygen):
+    /// the HKDF-SHA256 expansion of `secret` to 48 bytes, reduced modulo r. `secret` must be
+    /// uniformly random and at least 32 bytes long.
+    pub fn derive(secret: &[u8]) -> Result<Self> {
+        ensure!(
+            secret.len() >= BLS12381_KEYGEN_SECRET_MIN_LENGTH,
+            "The secret is {} bytes long, at least {} are needed",
+            secret.len(),
+            BLS12381_KEYGEN_SECRET_MIN_LENGTH
+        );
+        let output = Hkdf::<Sha256>::extract_then_expand(
+            Some(KEYGEN_SALT),
+            secret,
+            None,
+            KEYGEN_OUTPUT_LENGTH,
+        )?;
+        // Reduces the big-endian output modulo r, one byte at a time.
+        let base = Fr::from_repr(FrRepr::from(256)).expect("256 is less than r.");
+        let mut fr = Fr::zero();
+        for byte in output {
+            fr.mul_assign(&base);
+            fr.add_assign(
+                &Fr::from_repr(FrRepr::from(u64::from(byte))).expect("A byte is less than r."),
+            );
+        }
+        ensure!(!fr.is_zero(), "The secret derives the zero private key");
+        Ok(BLS12381PrivateKey(
+            threshold_crypto::serde_impl::SerdeSecret(threshold_crypto::SecretKey::from_mut(
+                &mut fr,
+            )),
+        ))
+    }
+
+    /// Proves the possession of this private key, by signing the public key. The proof is
+    /// checked with [`BLS12381PublicKey::verify_proof_of_possession`].
+    pub fn create_proof_of_possession(&self) -> BLS12381Signature {
+        self.sign_message(&BLS12381PublicKey::from(self).proof_of_possession_message())
+    }
+}
+
 impl BLS12381PublicKey {
     /// Serializes a BLS12381PublicKey.
     pub fn to_bytes(&self) -> [u8; BLS12381_PUBLIC_KEY_LENGTH] {
         self.0.to_bytes()
     }
+
+    /// Checks that `proof` proves the possession of the private key of this public key, see
+    /// [`BLS12381PrivateKey::create_proof_of_possession`]. A public key must pass this check
+    /// before it is aggregated with others.
+    pub fn verify_proof_of_possession(&self, proof: &BLS12381Signature) -> Result<()> {
+        proof
+            .verify(&self.proof_of_possession_message(), self)
+            .map_err(|_| format_err!("Invalid proof of possession for public key {}", self))
+    }
+
+    /// The message signed by a proof of possession. It is hashed with its own salt, so that no
+    /// other message signed with the key can pass as a proof of possession.
+    fn proof_of_possession_message(&self) -> HashValue {
+        let mut hasher = ProofOfPossessionHasher::default();
+        hasher.write(&self.to_bytes());
+        hasher.finish()
+    }
 }
 
 impl BLS12381Signature {
@@ -269,3 +374,57 @@ impl TryFrom<&[u8]> for BLS12381Signature {
         Ok(BLS12381Signature(sig))
     }
 }
+
+/////////////////
+// Aggregation //
+/////////////////
+
+/// Aggregates signatures of the same message into one, which verifies against the aggregate of
+/// the public keys of the signers, see [`aggregate_public_keys`].
+///
+/// Aggregation is only secure if each public key comes with a proof of possession of its private
+/// key, checked with [`BLS12381PublicKey::verify_proof_of_possession`]. Otherwise, a signer can
+/// choose a public key canceling out the ones of others, and forge an aggregate signature on their
+/// behalf.
+pub fn aggregate_signatures<'a>(
+    signatures: impl IntoIterator<Item = &'a BLS12381Signature>,
+) -> Result<BLS12381Signature> {
+    let mut aggregate = G2::zero();
+    let mut num_signatures = 0;
+    for signature in signatures {
+        let mut compressed = G2Compressed::empty();
+        compressed.as_mut().copy_from_slice(&signature.to_bytes());
+        let point = compressed
+            .into_affine()
+            .map_err(|_| CryptoMaterialError::DeserializationError)?;
+        aggregate.add_assign(&point.into_projective());
+        num_signatures += 1;
+    }
+    ensure!(num_signatures > 0, "No signature to aggregate");
+    Ok(BLS12381Signature::try_from(
+        aggregate.into_affine().into_compressed().as_ref(),
+    )?)
+}
+
+/// Aggregates public keys into one, against which the aggregate of their signatures of the same
+/// message verifies, see [`aggregate_signatures`]. Each public key must have passed
+/// [`BLS12381PublicKey::verify_proof_of_possession`].
+pub fn aggregate_public_keys<'a>(
+    public_keys: impl IntoIterator<Item = &'a BLS12381PublicKey>,
+) -> Result<BLS12381PublicKey> {
+    let mut aggregate = G1::zero();
+    let mut num_public_keys = 0;
+    for public_key in public_keys {
+        let mut compressed = G1Compressed::empty();
+        compressed.as_mut().copy_from_slice(&public_key.to_bytes());
+        let point = compressed
+            .into_affine()
+            .map_err(|_| CryptoMaterialError::DeserializationError)?;
+        aggregate.add_assign(&point.into_projective());
+        num_public_keys += 1;
+    }
+    ensure!(num_public_keys > 0, "No public key to aggregate");
+    Ok(BLS12381PublicKey::try_from(
+        aggregate.into_affine().into_compressed().as_ref(),
+    )?)
+}
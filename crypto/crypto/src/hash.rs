@@ -550,6 +550,15 @@ define_hasher! {
     (DiscoveryMsgHasher, DISCOVERY_MSG_HASHER, b"DiscoveryMsg")
 }
 
+define_hasher! {
+    /// The hasher used to compute the message signed by a proof of possession of a private key.
+    (
+        ProofOfPossessionHasher,
+        PROOF_OF_POSSESSION_HASHER,
+        b"ProofOfPossession"
+    )
+}
+
 fn create_literal_hash(word: &str) -> HashValue {
     let mut s = word.as_bytes().to_vec();
     assert!(s.len() <= HashValue::LENGTH);
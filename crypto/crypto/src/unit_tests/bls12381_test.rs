@@ -3,13 +3,14 @@
 
 use crate::{
     bls12381::{
-        BLS12381PrivateKey, BLS12381PublicKey, BLS12381_PRIVATE_KEY_LENGTH,
-        BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
+        aggregate_public_keys, aggregate_signatures, BLS12381PrivateKey, BLS12381PublicKey,
+        BLS12381_PRIVATE_KEY_LENGTH, BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
     },
     hash::HashValue,
     traits::*,
     unit_tests::uniform_keypair_strategy,
 };
+use pairing::{bls12_381::G1Compressed, CurveAffine, CurveProjective, EncodedPoint};
 use proptest::prelude::*;
 use std::convert::TryFrom;
 
@@ -88,4 +89,95 @@ proptest! {
         let deserialized = lcs::from_bytes(&serialized).unwrap();
         prop_assert!(keypair.public_key.verify_signature(&hash, &deserialized).is_ok());
     }
+
+    #[test]
+    fn test_aggregate_and_verify(
+        hash in any::<HashValue>(),
+        other_hash in any::<HashValue>(),
+        keypairs in proptest::collection::vec(
+            uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+            2..5,
+        )
+    ) {
+        prop_assume!(hash != other_hash);
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.private_key.sign_message(&hash))
+            .collect();
+        let public_keys: Vec<_> = keypairs.iter().map(|keypair| keypair.public_key.clone()).collect();
+        let signature = aggregate_signatures(&signatures).unwrap();
+        let public_key = aggregate_public_keys(&public_keys).unwrap();
+        prop_assert!(public_key.verify_signature(&hash, &signature).is_ok());
+        prop_assert!(public_key.verify_signature(&other_hash, &signature).is_err());
+        // The aggregate doesn't verify against a subset of the signers.
+        let public_key = aggregate_public_keys(&public_keys[1..]).unwrap();
+        prop_assert!(public_key.verify_signature(&hash, &signature).is_err());
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(20))]
+
+    #[test]
+    fn test_proof_of_possession(
+        hash in any::<HashValue>(),
+        keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+        other_keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>()
+    ) {
+        prop_assume!(keypair.public_key != other_keypair.public_key);
+        let proof = keypair.private_key.create_proof_of_possession();
+        prop_assert!(keypair.public_key.verify_proof_of_possession(&proof).is_ok());
+        // A proof doesn't carry over to another key.
+        prop_assert!(other_keypair.public_key.verify_proof_of_possession(&proof).is_err());
+        // Nor does a signature of another message pass as a proof.
+        let signature = keypair.private_key.sign_message(&hash);
+        prop_assert!(keypair.public_key.verify_proof_of_possession(&signature).is_err());
+    }
+
+    #[test]
+    fn test_derive(secret in proptest::collection::vec(any::<u8>(), 32..64)) {
+        let private_key = BLS12381PrivateKey::derive(&secret).unwrap();
+        prop_assert_eq!(&private_key, &BLS12381PrivateKey::derive(&secret).unwrap());
+        let mut other_secret = secret.clone();
+        other_secret[0] ^= 1;
+        prop_assert_ne!(private_key, BLS12381PrivateKey::derive(&other_secret).unwrap());
+    }
+}
+
+#[test]
+fn test_derive_short_secret() {
+    assert!(BLS12381PrivateKey::derive(&[0; 31]).is_err());
+}
+
+#[test]
+fn test_rogue_key_fails_proof_of_possession() {
+    // A rogue key cancels out the key of another signer in the aggregate, so that its owner alone
+    // can sign for both. Without the rogue private key, there's no proof of possession for it.
+    let private_key = BLS12381PrivateKey::derive(&[1; 32]).unwrap();
+    let honest_key = BLS12381PublicKey::from(&BLS12381PrivateKey::derive(&[2; 32]).unwrap());
+    let mut rogue_point = G1Compressed::empty();
+    rogue_point
+        .as_mut()
+        .copy_from_slice(&BLS12381PublicKey::from(&private_key).to_bytes());
+    let mut rogue_point = rogue_point.into_affine().unwrap().into_projective();
+    let mut honest_point = G1Compressed::empty();
+    honest_point
+        .as_mut()
+        .copy_from_slice(&honest_key.to_bytes());
+    rogue_point.sub_assign(&honest_point.into_affine().unwrap().into_projective());
+    let rogue_key =
+        BLS12381PublicKey::try_from(rogue_point.into_affine().into_compressed().as_ref()).unwrap();
+
+    let hash = HashValue::random();
+    let signature = private_key.sign_message(&hash);
+    let aggregate = aggregate_public_keys(vec![&honest_key, &rogue_key]).unwrap();
+    assert!(aggregate.verify_signature(&hash, &signature).is_ok());
+    let proof = private_key.create_proof_of_possession();
+    assert!(rogue_key.verify_proof_of_possession(&proof).is_err());
+}
+
+#[test]
+fn test_aggregate_nothing() {
+    assert!(aggregate_signatures(std::iter::empty()).is_err());
+    assert!(aggregate_public_keys(std::iter::empty()).is_err());
 }
@@ -263,6 +263,10 @@ impl TryFrom<&[u8]> for Proof {
     type Error = CryptoMaterialError;
 
     fn try_from(bytes: &[u8]) -> std::result::Result<Proof, CryptoMaterialError> {
+        if bytes.len() != PROOF_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+
         let mut c_buf = [0u8; 32];
         c_buf[..16].copy_from_slice(&bytes[32..48]);
         let mut s_buf = [0u8; 32];
@@ -270,7 +274,7 @@ impl TryFrom<&[u8]> for Proof {
         Ok(Proof {
             gamma: CompressedEdwardsY::from_slice(&bytes[..32])
                 .decompress()
-                .unwrap(),
+                .ok_or(CryptoMaterialError::PointNotOnCurveError)?,
             c: ed25519_Scalar::from_bits(c_buf),
             s: ed25519_Scalar::from_bits(s_buf),
         })
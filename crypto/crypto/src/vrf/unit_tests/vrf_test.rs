@@ -165,6 +165,15 @@ fn test_verify() {
     }
 }
 
+#[test]
+fn test_proof_wrong_length() {
+    let tv = &TESTVECTORS[0];
+    let pi = ::hex::decode(tv.pi).unwrap();
+    assert!(Proof::try_from(&pi[..PROOF_LENGTH - 1]).is_err());
+    assert!(Proof::try_from(&[&pi[..], &[0u8]].concat()[..]).is_err());
+    assert!(Proof::try_from(&pi[..]).is_ok());
+}
+
 #[test]
 fn test_output_from_proof() {
     for tv in TESTVECTORS.iter() {
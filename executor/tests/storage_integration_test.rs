@@ -49,7 +49,14 @@ fn gen_ledger_info_with_sigs(
 }
 
 fn gen_block_metadata(index: u8, proposer: AccountAddress) -> BlockMetadata {
-    BlockMetadata::new(gen_block_id(index), index as u64, BTreeMap::new(), proposer)
+    BlockMetadata::new(
+        gen_block_id(index),
+        index as u64,
+        index as u64,
+        BTreeMap::new(),
+        vec![],
+        proposer,
+    )
 }
 
 fn create_storage_service_and_executor(config: &NodeConfig) -> (ServerHandle, Executor<MoveVM>) {
@@ -336,9 +343,18 @@ fn test_execution_with_storage() {
             sequence_number: 1,
             fetch_events: false,
         },
-        RequestItem::GetAccountState { address: account1 },
-        RequestItem::GetAccountState { address: account2 },
-        RequestItem::GetAccountState { address: account3 },
+        RequestItem::GetAccountState {
+            address: account1,
+            version: None,
+        },
+        RequestItem::GetAccountState {
+            address: account2,
+            version: None,
+        },
+        RequestItem::GetAccountState {
+            address: account3,
+            version: None,
+        },
         RequestItem::GetTransactions {
             start_version: 3,
             limit: 10,
@@ -550,8 +566,14 @@ fn test_execution_with_storage() {
             sequence_number: 15,
             fetch_events: false,
         },
-        RequestItem::GetAccountState { address: account1 },
-        RequestItem::GetAccountState { address: account3 },
+        RequestItem::GetAccountState {
+            address: account1,
+            version: None,
+        },
+        RequestItem::GetAccountState {
+            address: account3,
+            version: None,
+        },
         RequestItem::GetTransactions {
             start_version: 7,
             limit: 14,
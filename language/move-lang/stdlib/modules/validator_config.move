@@ -7,6 +7,10 @@ module ValidatorConfig {
         consensus_pubkey: bytearray,
         network_identity_pubkey: bytearray,
         network_signing_pubkey: bytearray,
+        // The BLS12-381 key signing consensus certificates, empty unless registered
+        consensus_bls_pubkey: bytearray,
+        // The proof of possession of the private key of consensus_bls_pubkey
+        consensus_bls_pop: bytearray,
     }
 
     // A current or prospective validator should publish one of these under their address
@@ -34,6 +38,16 @@ module ValidatorConfig {
         config.network_signing_pubkey
     }
 
+    // Public accessor for consensus_bls_pubkey
+    public consensus_bls_pubkey(config: &Config): bytearray {
+        config.consensus_bls_pubkey
+    }
+
+    // Public accessor for consensus_bls_pop
+    public consensus_bls_pop(config: &Config): bytearray {
+        config.consensus_bls_pop
+    }
+
     // The following are self methods for initializing and maintaining a Validator's config
 
     // Register the transaction sender as a candidate validator by creating a ValidatorConfig
@@ -49,6 +63,8 @@ module ValidatorConfig {
                     consensus_pubkey,
                     network_identity_pubkey,
                     network_signing_pubkey,
+                    consensus_bls_pubkey: h"",
+                    consensus_bls_pop: h"",
                 }
             }
         )
@@ -60,4 +76,16 @@ module ValidatorConfig {
         borrow_global_mut<T>(Transaction.sender()).config.consensus_pubkey = consensus_pubkey;
     }
 
+    // Rotate a validator candidate's BLS12-381 consensus public key, along with the proof of
+    // possession of its private key. The proof is checked off-chain, a key without a valid proof
+    // isn't used by consensus. The change will not take effect until the next reconfiguration.
+    public rotate_consensus_bls_pubkey(
+        consensus_bls_pubkey: bytearray,
+        consensus_bls_pop: bytearray,
+    ) acquires T {
+        let config = &mut borrow_global_mut<T>(Transaction.sender()).config;
+        config.consensus_bls_pubkey = consensus_bls_pubkey;
+        config.consensus_bls_pop = consensus_bls_pop;
+    }
+
 }
@@ -13,6 +13,8 @@ module ValidatorSet {
         consensus_voting_power: u64,
         network_signing_pubkey: bytearray,
         network_identity_pubkey: bytearray,
+        consensus_bls_pubkey: bytearray,
+        consensus_bls_pop: bytearray,
     }
 
     struct ChangeEvent {
@@ -68,6 +70,8 @@ module ValidatorSet {
             consensus_voting_power: 1,
             network_signing_pubkey: config.network_signing_pubkey(),
             network_identity_pubkey: config.network_identity_pubkey(),
+            consensus_bls_pubkey: config.consensus_bls_pubkey(),
+            consensus_bls_pop: config.consensus_bls_pop(),
         };
         borrow_global_mut<T>(0x1D8).validators.push_back(info);
     }
@@ -24,6 +24,11 @@ pub fn rotate_consensus_pubkey() -> &'static str {
     include_str!("../transaction_scripts/rotate_consensus_pubkey.mvir")
 }
 
+/// Returns the source code for the rotate-consensus-bls-pubkey script.
+pub fn rotate_consensus_bls_pubkey() -> &'static str {
+    include_str!("../transaction_scripts/rotate_consensus_bls_pubkey.mvir")
+}
+
 /// Returns the source code for the rotate-key transaction script.
 pub fn rotate_key() -> &'static str {
     include_str!("../transaction_scripts/rotate_authentication_key.mvir")
@@ -58,6 +63,11 @@ lazy_static! {
         { parse_program(rotate_consensus_pubkey()).unwrap() };
 }
 
+lazy_static! {
+    pub static ref ROTATE_CONSENSUS_BLS_PUBKEY_TXN_BODY: Program =
+        { parse_program(rotate_consensus_bls_pubkey()).unwrap() };
+}
+
 lazy_static! {
     pub static ref ROTATE_AUTHENTICATION_KEY_TXN_BODY: Program =
         { parse_program(rotate_key()).unwrap() };
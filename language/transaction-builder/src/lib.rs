@@ -16,7 +16,8 @@ use stdlib::{
     transaction_scripts::{
         BLOCK_PROLOGUE_TXN_BODY, CREATE_ACCOUNT_TXN_BODY, MINT_TXN_BODY,
         PEER_TO_PEER_TRANSFER_TXN_BODY, PEER_TO_PEER_TRANSFER_WITH_METADATA_TXN_BODY,
-        ROTATE_AUTHENTICATION_KEY_TXN_BODY, ROTATE_CONSENSUS_PUBKEY_TXN_BODY,
+        ROTATE_AUTHENTICATION_KEY_TXN_BODY, ROTATE_CONSENSUS_BLS_PUBKEY_TXN_BODY,
+        ROTATE_CONSENSUS_PUBKEY_TXN_BODY,
     },
 };
 #[cfg(any(test, feature = "fuzzing"))]
@@ -31,6 +32,8 @@ lazy_static! {
         { compile_script(&ROTATE_AUTHENTICATION_KEY_TXN_BODY) };
     static ref ROTATE_CONSENSUS_PUBKEY_TXN: Vec<u8> =
         { compile_script(&ROTATE_CONSENSUS_PUBKEY_TXN_BODY) };
+    static ref ROTATE_CONSENSUS_BLS_PUBKEY_TXN: Vec<u8> =
+        { compile_script(&ROTATE_CONSENSUS_BLS_PUBKEY_TXN_BODY) };
     static ref MINT_TXN: Vec<u8> = { compile_script(&MINT_TXN_BODY) };
     static ref BLOCK_PROLOGUE_TXN: Vec<u8> = { compile_script(&BLOCK_PROLOGUE_TXN_BODY) };
 }
@@ -140,6 +143,21 @@ pub fn encode_rotate_consensus_pubkey_script(new_key: Vec<u8>) -> Script {
     )
 }
 
+/// Encode a program that rotates the sender's BLS12-381 consensus public key to `new_key`, along
+/// with the proof of possession of its private key.
+pub fn encode_rotate_consensus_bls_pubkey_script(
+    new_key: Vec<u8>,
+    proof_of_possession: Vec<u8>,
+) -> Script {
+    Script::new(
+        ROTATE_CONSENSUS_BLS_PUBKEY_TXN.clone(),
+        vec![
+            TransactionArgument::ByteArray(ByteArray::new(new_key)),
+            TransactionArgument::ByteArray(ByteArray::new(proof_of_possession)),
+        ],
+    )
+}
+
 /// Encode a program that rotates the sender's authentication key to `new_key`. `new_key` should be
 /// a 256 bit sha3 hash of an ed25519 public key.
 pub fn rotate_authentication_key_script(new_hashed_key: Vec<u8>) -> Script {
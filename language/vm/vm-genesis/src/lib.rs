@@ -69,6 +69,8 @@ lazy_static! {
         Identifier::new("register_candidate_validator").unwrap();
     static ref ROTATE_AUTHENTICATION_KEY: Identifier =
         { Identifier::new("rotate_authentication_key").unwrap() };
+    static ref ROTATE_CONSENSUS_BLS_PUBKEY: Identifier =
+        Identifier::new("rotate_consensus_bls_pubkey").unwrap();
     static ref EPILOGUE: Identifier = Identifier::new("epilogue").unwrap();
 }
 
@@ -318,6 +320,23 @@ pub fn encode_genesis_transaction_with_validator(
                         ],
                     )
                     .unwrap();
+                if let Some((public_key, proof_of_possession)) =
+                    validator_keys.consensus_bls_public_key()
+                {
+                    txn_executor
+                        .execute_function_with_sender_FOR_GENESIS_ONLY(
+                            validator_address,
+                            &VALIDATOR_CONFIG_MODULE,
+                            &ROTATE_CONSENSUS_BLS_PUBKEY,
+                            vec![
+                                Value::byte_array(ByteArray::new(public_key.to_bytes().to_vec())),
+                                Value::byte_array(ByteArray::new(
+                                    proof_of_possession.to_bytes().to_vec(),
+                                )),
+                            ],
+                        )
+                        .unwrap();
+                }
                 // Then, add the account to the validator set
                 txn_executor
                     .execute_function(
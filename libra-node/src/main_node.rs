@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use admission_control_service::runtime::AdmissionControlRuntime;
-use consensus::consensus_provider::{make_consensus_provider, ConsensusProvider};
+use consensus::consensus_provider::{
+    make_consensus_provider, make_observer_provider, ConsensusProvider,
+};
 use debug_interface::{node_debug_service::NodeDebugService, proto::create_node_debug_interface};
 use executor::Executor;
 use grpc_helpers::ServerHandle;
@@ -27,6 +29,7 @@ use network::{
     },
     NetworkPublicKeys, ProtocolId,
 };
+use parity_multiaddr::Protocol;
 use state_synchronizer::StateSynchronizer;
 use std::{
     convert::{TryFrom, TryInto},
@@ -111,6 +114,9 @@ pub fn setup_network(
     network_builder
         .permissioned(config.is_permissioned)
         .advertised_address(config.advertised_address.clone())
+        .rate_limit(config.rate_limit.clone())
+        .compression(config.compression.clone())
+        .reputation(config.reputation.clone())
         .direct_send_protocols(vec![
             ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL),
             ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL),
@@ -151,10 +157,19 @@ pub fn setup_network(
         let network_signing_private = config.network_keypairs.take_network_signing_private()
             .expect("Failed to move network signing private key out of NodeConfig, key not set or moved already");
         let network_signing_public: Ed25519PublicKey = (&network_signing_private).into();
+        let identity_keypair = Some(config.network_keypairs.get_network_identity_keypair());
+        // Listening on a QUIC address, e.g. `/ip4/0.0.0.0/udp/6180/quic`, selects QUIC.
+        let transport = if config
+            .listen_address
+            .iter()
+            .any(|protocol| protocol == Protocol::Quic)
+        {
+            TransportType::QuicNoise(identity_keypair)
+        } else {
+            TransportType::TcpNoise(identity_keypair)
+        };
         network_builder
-            .transport(TransportType::TcpNoise(Some(
-                config.network_keypairs.get_network_identity_keypair(),
-            )))
+            .transport(transport)
             .connectivity_check_interval_ms(config.connectivity_check_interval_ms)
             .seed_peers(seed_peers)
             .trusted_peers(trusted_peers)
@@ -198,6 +213,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
     let mut ac_network_sender = None;
     let mut ac_network_events = vec![];
     let mut validator_network_provider = None;
+    let mut observer_network = None;
 
     for i in 0..node_config.networks.len() {
         let peer_id =
@@ -233,6 +249,18 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
                 )
                 .unwrap()
             );
+            // Validators publish their proposals to the observers of their first full node
+            // network, while full nodes observe those of their upstream network.
+            if node_config.consensus.observer
+                && observer_network.is_none()
+                && (RoleType::Validator == node_config.get_role()
+                    || node_config.is_upstream_network(network))
+            {
+                observer_network =
+                    Some(network_provider.add_consensus(vec![ProtocolId::from_static(
+                        CONSENSUS_DIRECT_SEND_PROTOCOL,
+                    )]));
+            }
             // Start the network provider.
             runtime.executor().spawn(network_provider.start());
             network_runtimes.push(runtime);
@@ -300,6 +328,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
             node_config,
             consensus_network_sender,
             consensus_network_events,
+            observer_network,
             executor,
             state_synchronizer.create_client(),
         );
@@ -308,6 +337,20 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
             .expect("Failed to start consensus. Can't proceed.");
         consensus = Some(consensus_provider);
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+    } else if let Some((_, observer_network_events)) = observer_network {
+        // Initialize and start the observer.
+        instant = Instant::now();
+        let mut observer_provider = make_observer_provider(
+            node_config,
+            observer_network_events,
+            executor,
+            state_synchronizer.create_client(),
+        );
+        observer_provider
+            .start()
+            .expect("Failed to start the consensus observer. Can't proceed.");
+        consensus = Some(observer_provider);
+        debug!("Observer started in {} ms", instant.elapsed().as_millis());
     }
 
     LibraHandle {
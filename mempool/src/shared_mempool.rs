@@ -13,7 +13,7 @@ use libra_logger::prelude::*;
 use libra_types::{transaction::SignedTransaction, PeerId};
 use network::{
     proto::MempoolSyncMsg,
-    validator_network::{Event, MempoolNetworkEvents, MempoolNetworkSender},
+    validator_network::{Event, MempoolNetworkEvents, MempoolNetworkSender, PeerBehavior},
 };
 use std::{
     collections::HashMap,
@@ -290,6 +290,7 @@ async fn inbound_network_task<V>(
 {
     let peer_info = smp.peer_info.clone();
     let subscribers = smp.subscribers.clone();
+    let mut network_sender = smp.network_sender.clone();
 
     // Use a BoundedExecutor to restrict only `workers_available` concurrent
     // worker tasks that can process incoming transactions.
@@ -312,6 +313,7 @@ async fn inbound_network_task<V>(
                 }
                 Event::Message((peer_id, msg)) => {
                     OP_COUNTERS.inc("smp.event.message");
+                    let mut malformed = false;
                     let transactions: Vec<_> = msg
                         .transactions
                         .clone()
@@ -323,10 +325,19 @@ async fn inbound_network_task<V>(
                                     .error(&e)
                                     .data(&msg)
                                     .log();
+                                malformed = true;
                                 None
                             }
                         })
                         .collect();
+                    if malformed {
+                        if let Err(e) = network_sender
+                            .report_peer(peer_id, PeerBehavior::MalformedMessage)
+                            .await
+                        {
+                            warn!("Failed to report peer {}: {:?}", peer_id, e);
+                        }
+                    }
                     OP_COUNTERS.inc_by(
                         &format!("smp.transactions.received.{:?}", peer_id),
                         transactions.len(),
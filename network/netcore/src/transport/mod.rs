@@ -18,6 +18,7 @@ use std::time::Duration;
 pub mod and_then;
 pub mod boxed;
 pub mod memory;
+pub mod quic;
 pub mod tcp;
 pub mod timeout;
 
@@ -0,0 +1,492 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! QUIC connections come with their own encryption and stream multiplexing, so the connections
+//! established by the [`QuicTransport`] are [`StreamMultiplexer`]s on their own: every substream
+//! is a bidirectional QUIC stream. Unlike substreams multiplexed over a single TCP stream, a lost
+//! packet only holds back the substream it belongs to.
+//!
+//! The TLS handshake of QUIC relies on a self-signed certificate generated when the transport is
+//! created, so it doesn't authenticate peers on its own: upper layers are responsible for it, e.g.
+//! by running a Noise handshake over the first substream of every connection. To make sure that no
+//! one is relaying the connection, they must then check that both sides see the same
+//! [server certificate](QuicConnection::server_certificate).
+//!
+//! Addresses look like `/ip4/127.0.0.1/udp/6180/quic`.
+use crate::{multiplexing::StreamMultiplexer, transport::Transport};
+use futures::{
+    compat::Compat01As03,
+    future::{self, BoxFuture, FutureExt, TryFutureExt},
+    io::{AsyncRead, AsyncWrite},
+    ready,
+    stream::Stream,
+};
+use futures_01::Future as Future01;
+use parity_multiaddr::{Multiaddr, Protocol};
+use std::{
+    fmt::{self, Debug, Display},
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio_01::runtime::{Builder as RuntimeBuilder, Runtime};
+
+/// The name in the self-signed certificates, which isn't checked.
+const SERVER_NAME: &str = "libra";
+const ALPN_PROTOCOL: &[u8] = b"/libra/quic/0.1.0";
+/// The maximum number of concurrent substreams each side may open on a connection.
+const MAX_CONCURRENT_SUBSTREAMS: u64 = 1024;
+
+/// Transport to build QUIC connections
+#[derive(Clone)]
+pub struct QuicTransport {
+    inner: Arc<QuicTransportInner>,
+}
+
+struct QuicTransportInner {
+    // The drivers of the endpoints and connections rely on the timers of a tokio 0.1 runtime.
+    runtime: Runtime,
+    // The DER self-signed certificate of the transport.
+    certificate: Vec<u8>,
+    server_config: quinn::ServerConfig,
+    client_config: quinn::ClientConfig,
+    // The endpoint to dial from: the one bound by `listen_on` if any, or one bound to an ephemeral
+    // port otherwise.
+    endpoint: Mutex<Option<quinn::Endpoint>>,
+}
+
+impl QuicTransport {
+    pub fn new() -> io::Result<Self> {
+        let certificate =
+            rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()]).map_err(other)?;
+        let certificate_der = certificate.serialize_der().map_err(other)?;
+        let private_key_der = certificate.serialize_private_key_der();
+
+        let mut server_config = quinn::ServerConfigBuilder::default();
+        server_config.protocols(&[ALPN_PROTOCOL]);
+        server_config
+            .certificate(
+                quinn::CertificateChain::from_certs(vec![quinn::Certificate::from_der(
+                    &certificate_der,
+                )
+                .map_err(other)?]),
+                quinn::PrivateKey::from_der(&private_key_der).map_err(other)?,
+            )
+            .map_err(other)?;
+        let mut server_config = server_config.build();
+        Arc::make_mut(&mut server_config.transport).stream_window_bidi = MAX_CONCURRENT_SUBSTREAMS;
+
+        let mut client_config = quinn::ClientConfigBuilder::default();
+        client_config.protocols(&[ALPN_PROTOCOL]);
+        let mut client_config = client_config.build();
+        Arc::make_mut(&mut client_config.transport).stream_window_bidi = MAX_CONCURRENT_SUBSTREAMS;
+
+        let runtime = RuntimeBuilder::new()
+            .name_prefix("quic-")
+            .core_threads(1)
+            .build()?;
+
+        Ok(Self {
+            inner: Arc::new(QuicTransportInner {
+                runtime,
+                certificate: certificate_der,
+                server_config,
+                client_config,
+                endpoint: Mutex::new(None),
+            }),
+        })
+    }
+}
+
+impl QuicTransportInner {
+    fn bind(
+        &self,
+        socket_addr: SocketAddr,
+        listen: bool,
+    ) -> io::Result<(quinn::Endpoint, quinn::Incoming)> {
+        let mut builder = quinn::Endpoint::builder();
+        builder.default_client_config(self.client_config.clone());
+        if listen {
+            builder.listen(self.server_config.clone());
+        }
+        let socket = UdpSocket::bind(socket_addr)?;
+        let (driver, endpoint, incoming) = builder.with_socket(socket).map_err(other)?;
+        self.runtime.executor().spawn(driver.map_err(|_| ()));
+        Ok((endpoint, incoming))
+    }
+
+    fn dialing_endpoint(&self, remote_addr: &SocketAddr) -> io::Result<quinn::Endpoint> {
+        let mut endpoint = self.endpoint.lock().unwrap();
+        if let Some(ref endpoint) = *endpoint {
+            return Ok(endpoint.clone());
+        }
+        let socket_addr = if remote_addr.is_ipv4() {
+            SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)
+        } else {
+            SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0)
+        };
+        let (new_endpoint, _incoming) = self.bind(socket_addr, false)?;
+        *endpoint = Some(new_endpoint.clone());
+        Ok(new_endpoint)
+    }
+
+    /// Returns a client config which accepts any server certificate, and records it in
+    /// `server_certificate`.
+    fn recording_client_config(
+        &self,
+        server_certificate: Arc<Mutex<Option<Vec<u8>>>>,
+    ) -> quinn::ClientConfig {
+        let mut client_config = self.client_config.clone();
+        let mut crypto = rustls::ClientConfig::clone(&client_config.crypto);
+        crypto
+            .dangerous()
+            .set_certificate_verifier(Arc::new(RecordingCertVerifier { server_certificate }));
+        client_config.crypto = Arc::new(crypto);
+        client_config
+    }
+
+    fn new_connection(
+        &self,
+        new_connection: quinn::NewConnection,
+        server_certificate: Vec<u8>,
+    ) -> QuicConnection {
+        let quinn::NewConnection {
+            driver,
+            connection,
+            bi_streams,
+            ..
+        } = new_connection;
+        self.runtime.executor().spawn(driver.map_err(|_| ()));
+        QuicConnection {
+            connection,
+            bi_streams: Arc::new(Mutex::new(Compat01As03::new(bi_streams))),
+            server_certificate,
+        }
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicConnection;
+    type Error = io::Error;
+    type Listener = QuicListenerStream;
+    type Inbound = BoxFuture<'static, io::Result<QuicConnection>>;
+    type Outbound = BoxFuture<'static, io::Result<QuicConnection>>;
+
+    fn listen_on(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let (endpoint, incoming) = self.inner.bind(socket_addr, true)?;
+        let local_addr = socketaddr_to_multiaddr(endpoint.local_addr()?);
+        *self.inner.endpoint.lock().unwrap() = Some(endpoint);
+
+        Ok((
+            QuicListenerStream {
+                incoming: Compat01As03::new(incoming),
+                transport: Arc::clone(&self.inner),
+            },
+            local_addr,
+        ))
+    }
+
+    fn dial(&self, addr: Multiaddr) -> Result<Self::Outbound, Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let endpoint = self.inner.dialing_endpoint(&socket_addr)?;
+        let server_certificate = Arc::new(Mutex::new(None));
+        let client_config = self
+            .inner
+            .recording_client_config(Arc::clone(&server_certificate));
+        let connecting = endpoint
+            .connect_with(client_config, &socket_addr, SERVER_NAME)
+            .map_err(other)?;
+        let transport = Arc::clone(&self.inner);
+
+        let outbound = async move {
+            let new_connection = Compat01As03::new(connecting).await.map_err(other)?;
+            let server_certificate =
+                server_certificate.lock().unwrap().take().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::Other, "No server certificate recorded")
+                })?;
+            Ok(transport.new_connection(new_connection, server_certificate))
+        };
+        Ok(outbound.boxed())
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct QuicListenerStream {
+    incoming: Compat01As03<quinn::Incoming>,
+    transport: Arc<QuicTransportInner>,
+}
+
+impl Stream for QuicListenerStream {
+    type Item = io::Result<(BoxFuture<'static, io::Result<QuicConnection>>, Multiaddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        match ready!(Pin::new(&mut self.incoming).poll_next(context)) {
+            Some(Ok(connecting)) => {
+                let dialer_addr = socketaddr_to_multiaddr(connecting.remote_address());
+                let transport = Arc::clone(&self.transport);
+                let inbound = async move {
+                    let new_connection = Compat01As03::new(connecting).await.map_err(other)?;
+                    let server_certificate = transport.certificate.clone();
+                    Ok(transport.new_connection(new_connection, server_certificate))
+                };
+                Poll::Ready(Some(Ok((inbound.boxed(), dialer_addr))))
+            }
+            Some(Err(())) => Poll::Ready(Some(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "QUIC endpoint closed",
+            )))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A QUIC connection, whose substreams are bidirectional QUIC streams
+pub struct QuicConnection {
+    connection: quinn::Connection,
+    bi_streams: Arc<Mutex<Compat01As03<quinn::IncomingBiStreams>>>,
+    server_certificate: Vec<u8>,
+}
+
+impl QuicConnection {
+    /// Returns the DER certificate presented by the listener during the TLS handshake: the
+    /// certificate of the local transport for inbound connections, and the one received from the
+    /// remote peer for outbound connections.
+    pub fn server_certificate(&self) -> &[u8] {
+        &self.server_certificate
+    }
+}
+
+impl Debug for QuicConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicConnection")
+            .field("remote_address", &self.connection.remote_address())
+            .finish()
+    }
+}
+
+impl StreamMultiplexer for QuicConnection {
+    type Substream = QuicSubstream;
+    type Listener = QuicSubstreamListener;
+    type Outbound = BoxFuture<'static, io::Result<QuicSubstream>>;
+    type Close = future::Ready<io::Result<()>>;
+
+    fn listen_for_inbound(&self) -> Self::Listener {
+        QuicSubstreamListener {
+            bi_streams: Arc::clone(&self.bi_streams),
+        }
+    }
+
+    fn open_outbound(&self) -> Self::Outbound {
+        Compat01As03::new(self.connection.open_bi())
+            .map_ok(|(send, recv)| QuicSubstream::new(send, recv))
+            .map_err(other)
+            .boxed()
+    }
+
+    fn close(&self) -> Self::Close {
+        self.connection.close(0u32.into(), b"");
+        future::ready(Ok(()))
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct QuicSubstreamListener {
+    bi_streams: Arc<Mutex<Compat01As03<quinn::IncomingBiStreams>>>,
+}
+
+impl Stream for QuicSubstreamListener {
+    type Item = io::Result<QuicSubstream>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut bi_streams = self.bi_streams.lock().unwrap();
+        match ready!(Pin::new(&mut *bi_streams).poll_next(context)) {
+            Some(Ok((send, recv))) => Poll::Ready(Some(Ok(QuicSubstream::new(send, recv)))),
+            Some(Err(e)) => Poll::Ready(Some(Err(other(e)))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// A bidirectional QUIC stream
+///
+/// Closing the substream finishes its send side.
+pub struct QuicSubstream {
+    send: Compat01As03<quinn::SendStream>,
+    recv: Compat01As03<quinn::RecvStream>,
+}
+
+impl QuicSubstream {
+    fn new(send: quinn::SendStream, recv: quinn::RecvStream) -> Self {
+        Self {
+            send: Compat01As03::new(send),
+            recv: Compat01As03::new(recv),
+        }
+    }
+}
+
+impl Debug for QuicSubstream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicSubstream").finish()
+    }
+}
+
+impl AsyncRead for QuicSubstream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSubstream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_close(context)
+    }
+}
+
+/// Accepts any server certificate, and records it so that upper layers can authenticate it.
+struct RecordingCertVerifier {
+    server_certificate: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+impl rustls::ServerCertVerifier for RecordingCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        let certificate = presented_certs
+            .first()
+            .ok_or(rustls::TLSError::NoCertificatesPresented)?;
+        *self.server_certificate.lock().unwrap() = Some(certificate.0.clone());
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+fn other<E: Display>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+fn socketaddr_to_multiaddr(socketaddr: SocketAddr) -> Multiaddr {
+    let ipaddr: Multiaddr = socketaddr.ip().into();
+    ipaddr
+        .with(Protocol::Udp(socketaddr.port()))
+        .with(Protocol::Quic)
+}
+
+fn multiaddr_to_socketaddr(addr: &Multiaddr) -> io::Result<SocketAddr> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid Multiaddr '{:?}'", addr),
+        )
+    };
+    let mut iter = addr.iter();
+    let proto1 = iter.next().ok_or_else(invalid)?;
+    let proto2 = iter.next().ok_or_else(invalid)?;
+    let proto3 = iter.next().ok_or_else(invalid)?;
+
+    if iter.next().is_some() {
+        return Err(invalid());
+    }
+
+    match (proto1, proto2, proto3) {
+        (Protocol::Ip4(ip), Protocol::Udp(port), Protocol::Quic) => {
+            Ok(SocketAddr::new(ip.into(), port))
+        }
+        (Protocol::Ip6(ip), Protocol::Udp(port), Protocol::Quic) => {
+            Ok(SocketAddr::new(ip.into(), port))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        multiplexing::StreamMultiplexer,
+        transport::{quic::QuicTransport, Transport},
+    };
+    use futures::{
+        future::{join, FutureExt},
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::StreamExt,
+    };
+    use std::io;
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), io::Error> {
+        let listener_transport = QuicTransport::new()?;
+        let dialer_transport = QuicTransport::new()?;
+
+        let (listener, addr) =
+            listener_transport.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+        let dial = dialer_transport.dial(addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+        let (outgoing, incoming) = join(dial, listener).await;
+        let outgoing = outgoing?;
+
+        // Both sides see the certificate of the listener.
+        assert_eq!(outgoing.server_certificate(), incoming.server_certificate());
+
+        let dialer = async move {
+            let mut substream = outgoing.open_outbound().await?;
+            substream.write_all(b"Earth").await?;
+            substream.close().await?;
+            let mut buf = Vec::new();
+            substream.read_to_end(&mut buf).await?;
+            assert_eq!(buf, b"Air");
+            let result: io::Result<()> = Ok(());
+            result
+        };
+        let listener = async move {
+            let (maybe_substream, _) = incoming.listen_for_inbound().into_future().await;
+            let mut substream = maybe_substream.unwrap()?;
+            let mut buf = Vec::new();
+            substream.read_to_end(&mut buf).await?;
+            assert_eq!(buf, b"Earth");
+            substream.write_all(b"Air").await?;
+            substream.close().await?;
+            let result: io::Result<()> = Ok(());
+            result
+        };
+        let (dialer_result, listener_result) = join(dialer, listener).await;
+        dialer_result?;
+        listener_result
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::new().unwrap();
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.dial("/memory/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compression of the DirectSend messages and rpcs, negotiated per protocol
+//!
+//! Every protocol for which compression is enabled gets a compressed variant, whose id is the id
+//! of the protocol followed by `+zstd`, e.g. `/libra/consensus/rpc/0.1.0+zstd`. The variants are
+//! handled by the same actors as the protocols themselves, and advertised along with them through
+//! the [`Identity`](crate::protocols::identity::Identity) protocol. To send messages of a
+//! protocol to a peer, the [`DirectSend`](crate::protocols::direct_send::DirectSend) and
+//! [`Rpc`](crate::protocols::rpc::Rpc) actors open a substream for its compressed variant if both
+//! sides enabled compression for it, and for the protocol itself otherwise. Upstream actors only
+//! ever see the protocol itself.
+//!
+//! ## Wire Protocol
+//!
+//! The messages of a compressed substream are length-prefixed as usual, and start with a flag
+//! byte:
+//!
+//! * `0`: the rest of the message is sent as is. This is the case of the messages smaller than
+//!   `min_size_bytes`, and of those which compression doesn't make smaller.
+//! * `1`: the rest of the message is compressed with zstd.
+use crate::{
+    counters,
+    peer_manager::{PeerManagerError, PeerManagerRequestSender},
+    ProtocolId,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use libra_config::config::CompressionConfig;
+use libra_types::PeerId;
+use std::{
+    collections::HashSet,
+    io::{self, Read},
+};
+
+#[cfg(test)]
+mod test;
+
+const COMPRESSED_PROTOCOL_SUFFIX: &[u8] = b"+zstd";
+const UNCOMPRESSED_FLAG: u8 = 0;
+const ZSTD_FLAG: u8 = 1;
+/// Compressed messages can't expand beyond the maximum frame length of the `LengthDelimitedCodec`
+/// used for uncompressed messages.
+const MAX_DECOMPRESSED_SIZE: usize = 8 * 1024 * 1024;
+
+/// Returns whether `protocol` is the compressed variant of a protocol.
+pub fn is_compressed_protocol(protocol: &ProtocolId) -> bool {
+    protocol.ends_with(COMPRESSED_PROTOCOL_SUFFIX)
+}
+
+/// Returns the compressed variant of `protocol`.
+pub fn compressed_protocol(protocol: &ProtocolId) -> ProtocolId {
+    let mut compressed = BytesMut::with_capacity(protocol.len() + COMPRESSED_PROTOCOL_SUFFIX.len());
+    compressed.put_slice(protocol);
+    compressed.put_slice(COMPRESSED_PROTOCOL_SUFFIX);
+    compressed.freeze()
+}
+
+/// Splits the protocol of a negotiated substream into the protocol its messages belong to, and
+/// whether they are compressed.
+pub fn split_protocol(protocol: ProtocolId) -> (ProtocolId, bool) {
+    if is_compressed_protocol(&protocol) {
+        let len = protocol.len() - COMPRESSED_PROTOCOL_SUFFIX.len();
+        (protocol.slice_to(len), true)
+    } else {
+        (protocol, false)
+    }
+}
+
+/// The protocols whose messages are compressed, and how.
+pub struct Compression {
+    protocols: HashSet<ProtocolId>,
+    min_size_bytes: usize,
+    level: i32,
+}
+
+impl Compression {
+    pub fn new(config: &CompressionConfig) -> Self {
+        Self {
+            protocols: config
+                .protocols
+                .iter()
+                .map(|protocol| ProtocolId::from(protocol.as_bytes()))
+                .collect(),
+            min_size_bytes: config.min_size_bytes,
+            level: config.level,
+        }
+    }
+
+    /// Returns whether compression is enabled for `protocol`.
+    pub fn is_enabled(&self, protocol: &ProtocolId) -> bool {
+        self.protocols.contains(protocol)
+    }
+
+    /// Opens a substream with `peer_id` for the compressed variant of `protocol` if compression
+    /// is enabled for it on both sides, and for `protocol` itself otherwise.
+    ///
+    /// Returns the substream, and whether its messages are compressed.
+    pub async fn open_substream<TSubstream>(
+        &self,
+        peer_mgr_reqs_tx: &mut PeerManagerRequestSender<TSubstream>,
+        peer_id: PeerId,
+        protocol: ProtocolId,
+    ) -> Result<(TSubstream, bool), PeerManagerError> {
+        if self.is_enabled(&protocol) {
+            match peer_mgr_reqs_tx
+                .open_substream(peer_id, compressed_protocol(&protocol))
+                .await
+            {
+                Ok(substream) => return Ok((substream, true)),
+                // The peer didn't enable compression for this protocol.
+                Err(PeerManagerError::ProtocolNotSupported(_)) => (),
+                Err(e) => return Err(e),
+            }
+        }
+        let substream = peer_mgr_reqs_tx.open_substream(peer_id, protocol).await?;
+        Ok((substream, false))
+    }
+
+    /// Encodes a message to send on a compressed substream.
+    pub fn encode(&self, data: &[u8], message_type: &str) -> Bytes {
+        let compressed = if data.len() >= self.min_size_bytes {
+            zstd::stream::encode_all(data, self.level)
+                .ok()
+                .filter(|compressed| compressed.len() < data.len())
+        } else {
+            None
+        };
+        let (flag, payload) = match compressed {
+            Some(ref compressed) => (ZSTD_FLAG, &compressed[..]),
+            None => (UNCOMPRESSED_FLAG, data),
+        };
+        let mut encoded = BytesMut::with_capacity(1 + payload.len());
+        encoded.put_u8(flag);
+        encoded.put_slice(payload);
+        observe(message_type, "sent", data.len(), encoded.len());
+        encoded.freeze()
+    }
+}
+
+/// Decodes a message received on a compressed substream.
+pub fn decode(encoded: Bytes, message_type: &str) -> io::Result<Bytes> {
+    let data = match encoded.first() {
+        Some(&UNCOMPRESSED_FLAG) => encoded.slice_from(1),
+        Some(&ZSTD_FLAG) => {
+            let mut data = vec![];
+            zstd::stream::Decoder::new(&encoded[1..])?
+                .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+                .read_to_end(&mut data)?;
+            if data.len() > MAX_DECOMPRESSED_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Decompressed message too large",
+                ));
+            }
+            Bytes::from(data)
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid compression flag",
+            ))
+        }
+    };
+    observe(message_type, "received", data.len(), encoded.len());
+    Ok(data)
+}
+
+fn observe(message_type: &str, direction: &str, uncompressed_len: usize, compressed_len: usize) {
+    counters::LIBRA_NETWORK_COMPRESSION_BYTES
+        .with_label_values(&[message_type, direction, "uncompressed"])
+        .inc_by(uncompressed_len as i64);
+    counters::LIBRA_NETWORK_COMPRESSION_BYTES
+        .with_label_values(&[message_type, direction, "compressed"])
+        .inc_by(compressed_len as i64);
+}
@@ -0,0 +1,136 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::peer_manager::PeerManagerRequest;
+use futures::{future::join, stream::StreamExt};
+use memsocket::MemorySocket;
+use std::collections::BTreeSet;
+use tokio::runtime::Runtime;
+
+const PROTOCOL: &[u8] = b"/libra/consensus/direct-send/0.1.0";
+const OTHER_PROTOCOL: &[u8] = b"/libra/mempool/direct-send/0.1.0";
+
+fn compression(min_size_bytes: usize) -> Compression {
+    let mut protocols = BTreeSet::new();
+    protocols.insert(String::from_utf8(PROTOCOL.to_vec()).unwrap());
+    Compression::new(&CompressionConfig {
+        protocols,
+        min_size_bytes,
+        level: 3,
+    })
+}
+
+#[test]
+fn test_protocols() {
+    let compression = compression(0);
+    let protocol = ProtocolId::from_static(PROTOCOL);
+    assert!(compression.is_enabled(&protocol));
+    assert!(!compression.is_enabled(&ProtocolId::from_static(OTHER_PROTOCOL)));
+
+    let compressed = compressed_protocol(&protocol);
+    assert_eq!(
+        compressed.as_ref(),
+        b"/libra/consensus/direct-send/0.1.0+zstd"
+    );
+    assert!(is_compressed_protocol(&compressed));
+    assert!(!is_compressed_protocol(&protocol));
+    assert_eq!(split_protocol(compressed), (protocol.clone(), true));
+    assert_eq!(split_protocol(protocol.clone()), (protocol, false));
+}
+
+#[test]
+fn test_encode_decode() {
+    let compression = compression(100);
+
+    // Small messages aren't compressed.
+    let small = vec![7u8; 50];
+    let encoded = compression.encode(&small, "direct_send");
+    assert_eq!(encoded[0], UNCOMPRESSED_FLAG);
+    assert_eq!(&encoded[1..], &small[..]);
+    assert_eq!(decode(encoded, "direct_send").unwrap().as_ref(), &small[..]);
+
+    // Large messages are.
+    let large = vec![7u8; 10_000];
+    let encoded = compression.encode(&large, "direct_send");
+    assert_eq!(encoded[0], ZSTD_FLAG);
+    assert!(encoded.len() < large.len() / 10);
+    assert_eq!(decode(encoded, "direct_send").unwrap().as_ref(), &large[..]);
+
+    // Unless compression doesn't make them smaller.
+    let random: Vec<u8> = (0..10_000).map(|_| rand::random()).collect();
+    let encoded = compression.encode(&random, "direct_send");
+    assert_eq!(encoded[0], UNCOMPRESSED_FLAG);
+    assert_eq!(
+        decode(encoded, "direct_send").unwrap().as_ref(),
+        &random[..]
+    );
+}
+
+#[test]
+fn test_decode_invalid() {
+    assert!(decode(Bytes::new(), "rpc").is_err());
+    assert!(decode(Bytes::from_static(&[2, 0, 0]), "rpc").is_err());
+    assert!(decode(Bytes::from_static(&[ZSTD_FLAG, 0, 0]), "rpc").is_err());
+
+    // Messages can't be decompressed beyond the maximum frame length.
+    let compression = compression(0);
+    let too_large = vec![0u8; MAX_DECOMPRESSED_SIZE + 1];
+    let encoded = compression.encode(&too_large, "rpc");
+    assert_eq!(encoded[0], ZSTD_FLAG);
+    assert!(decode(encoded, "rpc").is_err());
+}
+
+#[test]
+fn test_open_substream_fallback() {
+    let compression = compression(0);
+    let peer_id = PeerId::random();
+    let (peer_mgr_reqs_tx, mut peer_mgr_reqs_rx) = channel::new_test(8);
+    let mut peer_mgr_reqs_tx = PeerManagerRequestSender::new(peer_mgr_reqs_tx);
+
+    // The peer doesn't support the compressed variant of the protocol.
+    let f_peer_mgr = async move {
+        for (expected, result) in vec![
+            (
+                compressed_protocol(&ProtocolId::from_static(PROTOCOL)),
+                false,
+            ),
+            (ProtocolId::from_static(PROTOCOL), true),
+            (ProtocolId::from_static(OTHER_PROTOCOL), true),
+        ] {
+            match peer_mgr_reqs_rx.next().await.unwrap() {
+                PeerManagerRequest::OpenSubstream(_, protocol, substream_tx) => {
+                    assert_eq!(protocol, expected);
+                    let response = if result {
+                        Ok(MemorySocket::new_pair().0)
+                    } else {
+                        Err(PeerManagerError::ProtocolNotSupported(protocol))
+                    };
+                    substream_tx.send(response).unwrap();
+                }
+                req => panic!("Unexpected PeerManagerRequest: {:?}", req),
+            }
+        }
+    };
+    let f_open = async move {
+        let (_, compressed) = compression
+            .open_substream(
+                &mut peer_mgr_reqs_tx,
+                peer_id,
+                ProtocolId::from_static(PROTOCOL),
+            )
+            .await
+            .unwrap();
+        assert!(!compressed);
+        let (_, compressed) = compression
+            .open_substream(
+                &mut peer_mgr_reqs_tx,
+                peer_id,
+                ProtocolId::from_static(OTHER_PROTOCOL),
+            )
+            .await
+            .unwrap();
+        assert!(!compressed);
+    };
+    Runtime::new().unwrap().block_on(join(f_peer_mgr, f_open));
+}
@@ -12,10 +12,11 @@
 //!
 //! When dialing a peer with a given list of addresses, we attempt each address
 //! in order with a capped exponential backoff delay until we eventually connect
-//! to the peer.
+//! to the peer. Peers banned for their bad reputation are not dialed until their ban expires.
 use crate::{
     common::NetworkPublicKeys,
     peer_manager::{PeerManagerError, PeerManagerNotification, PeerManagerRequestSender},
+    reputation::Reputation,
 };
 use channel;
 use futures::{
@@ -64,6 +65,8 @@ pub struct ConnectivityManager<TTicker, TSubstream, TBackoff> {
     backoff_strategy: TBackoff,
     /// Maximum delay b/w 2 consecutive attempts to connect with a disconnected peer.
     max_delay_ms: u64,
+    /// Reputation of the peers, whose banned ones aren't dialed.
+    reputation: Arc<Reputation>,
     /// A local counter incremented on receiving an incoming message. Printing this in debugging
     /// allows for easy debugging.
     event_id: u32,
@@ -113,6 +116,7 @@ where
         requests_rx: channel::Receiver<ConnectivityRequest>,
         backoff_strategy: TBackoff,
         max_delay_ms: u64,
+        reputation: Arc<Reputation>,
     ) -> Self {
         Self {
             eligible,
@@ -126,6 +130,7 @@ where
             dial_states: HashMap::new(),
             backoff_strategy,
             max_delay_ms,
+            reputation,
             event_id: 0,
         }
     }
@@ -192,17 +197,18 @@ where
         }
     }
 
-    /// Cancel all pending dials to peers that are no longer eligible.
+    /// Cancel all pending dials to peers that are no longer eligible, or banned.
     ///
     /// For instance, a validator might leave the validator set after a
     /// reconfiguration. If there is a pending dial to this validator, calling
     /// this function will remove it from the dial queue.
     async fn cancel_stale_dials(&mut self) {
         let eligible = self.eligible.read().unwrap().clone();
+        let reputation = &self.reputation;
         let stale_dials: Vec<_> = self
             .dial_queue
             .keys()
-            .filter(|peer_id| !eligible.contains_key(peer_id))
+            .filter(|peer_id| !eligible.contains_key(peer_id) || reputation.is_banned(peer_id))
             .cloned()
             .collect();
         for p in stale_dials.into_iter() {
@@ -220,6 +226,7 @@ where
             .iter()
             .filter(|(peer_id, addrs)| {
                 eligible.contains_key(peer_id)  // The node is eligible to be dialed.
+                    && !self.reputation.is_banned(peer_id) // The node is not banned.
                     && self.connected.get(peer_id).is_none() // The node is not already connected.
                     && self.dial_queue.get(peer_id).is_none() // There is no pending dial to this node.
                     && !addrs.is_empty() // There is an address to dial.
@@ -310,7 +317,7 @@ where
         &'a mut self,
         pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, PeerId>>,
     ) {
-        // Cancel dials to peers that are no longer eligible.
+        // Cancel dials to peers that are no longer eligible, or banned.
         self.cancel_stale_dials().await;
         // Disconnect from connected peers that are no longer eligible.
         self.close_stale_connections().await;
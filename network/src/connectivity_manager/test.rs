@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::peer_manager::PeerManagerRequest;
+use crate::{peer_manager::PeerManagerRequest, reputation::PeerBehavior};
 use core::str::FromStr;
 use futures::SinkExt;
+use libra_config::config::ReputationConfig;
 use libra_crypto::{ed25519::compat, test_utils::TEST_SEED, x25519};
 use memsocket::MemorySocket;
 use rand::{rngs::StdRng, SeedableRng};
@@ -20,6 +21,23 @@ fn setup_conn_mgr(
     channel::Sender<PeerManagerNotification<MemorySocket>>,
     channel::Sender<ConnectivityRequest>,
     channel::Sender<()>,
+) {
+    setup_conn_mgr_with_reputation(
+        rt,
+        seed_peer_id,
+        Arc::new(Reputation::new(&ReputationConfig::default())),
+    )
+}
+
+fn setup_conn_mgr_with_reputation(
+    rt: &mut Runtime,
+    seed_peer_id: PeerId,
+    reputation: Arc<Reputation>,
+) -> (
+    channel::Receiver<PeerManagerRequest<MemorySocket>>,
+    channel::Sender<PeerManagerNotification<MemorySocket>>,
+    channel::Sender<ConnectivityRequest>,
+    channel::Sender<()>,
 ) {
     let (peer_mgr_reqs_tx, peer_mgr_reqs_rx): (
         channel::Sender<PeerManagerRequest<MemorySocket>>,
@@ -50,6 +68,7 @@ fn setup_conn_mgr(
             conn_mgr_reqs_rx,
             FixedInterval::from_millis(100),
             300, /* ms */
+            reputation,
         )
     };
     rt.spawn(conn_mgr.start());
@@ -894,3 +913,42 @@ fn multiple_addrs_shrinking() {
     };
     rt.block_on(f_peer_mgr);
 }
+
+#[test]
+fn banned_peer_not_dialed() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    let seed_peer_id = PeerId::random();
+    info!("Seed peer_id is {}", seed_peer_id.short_str());
+    let reputation = Arc::new(Reputation::new(&ReputationConfig::default()));
+    let (_peer_mgr_reqs_rx, _peer_mgr_notifs_tx, mut conn_mgr_reqs_tx, mut ticker_tx) =
+        setup_conn_mgr_with_reputation(&mut rt, seed_peer_id, Arc::clone(&reputation));
+
+    // Ban the seed peer.
+    reputation.report(seed_peer_id, PeerBehavior::InvalidSignature);
+    assert!(reputation.report(seed_peer_id, PeerBehavior::InvalidSignature));
+
+    let events_f = async move {
+        let seed_address = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+
+        // Send address of seed peer.
+        info!("Sending address of seed peer");
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateAddresses(
+                seed_peer_id,
+                vec![seed_address],
+            ))
+            .await
+            .unwrap();
+
+        // Trigger connectivity check twice, so that the first one is complete once the second
+        // tick is sent.
+        info!("Sending ticks to trigger connectivity check");
+        ticker_tx.send(()).await.unwrap();
+        ticker_tx.send(()).await.unwrap();
+
+        // No dial to the banned seed peer is queued.
+        assert_eq!(get_dial_queue_size(&mut conn_mgr_reqs_tx).await, 0);
+    };
+    rt.block_on(events_f);
+}
@@ -3,7 +3,7 @@
 
 use lazy_static;
 use libra_metrics::{Histogram, IntGauge, OpMetrics};
-use prometheus::{HistogramVec, IntCounterVec, IntGaugeVec};
+use prometheus::{HistogramVec, IntCounter, IntCounterVec, IntGaugeVec};
 
 lazy_static::lazy_static! {
     pub static ref LIBRA_NETWORK_PEERS: IntGaugeVec = register_int_gauge_vec!(
@@ -43,6 +43,58 @@ lazy_static::lazy_static! {
         "Libra network direct send bytes histogram",
         &["state"]
     ).unwrap();
+
+    pub static ref LIBRA_NETWORK_RATE_LIMITED_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "libra_network_rate_limited_messages",
+        "Libra network inbound messages throttled or dropped by rate limiting",
+        &["type", "state"]
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_RATE_LIMITED_PEERS: IntCounter = register_int_counter!(
+        "libra_network_rate_limited_peers",
+        "Libra network peers disconnected for staying over their rate limits"
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_COMPRESSION_BYTES: IntCounterVec = register_int_counter_vec!(
+        "libra_network_compression_bytes",
+        "Libra network bytes of the messages on compressed substreams, before and after compression",
+        &["type", "direction", "state"]
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_OUTBOUND_QUEUE_DEPTH: IntGaugeVec = register_int_gauge_vec!(
+        "libra_network_outbound_queue_depth",
+        "Libra network outbound messages waiting for their turn to be written, by priority class",
+        &["class"]
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_OUTBOUND_QUEUEING_DELAY: HistogramVec = register_histogram_vec!(
+        "libra_network_outbound_queueing_delay_seconds",
+        "Libra network time outbound messages wait for their turn to be written, by priority class",
+        &["class"]
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_PEER_SCORE: IntGaugeVec = register_int_gauge_vec!(
+        "libra_network_peer_score",
+        "Libra network reputation score of the peers reported by the upper layers",
+        &["peer_id"]
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_PEER_REPORTS: IntCounterVec = register_int_counter_vec!(
+        "libra_network_peer_reports",
+        "Libra network reports of the behavior of peers, by behavior",
+        &["behavior"]
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_BANNED_PEERS: IntCounter = register_int_counter!(
+        "libra_network_banned_peers",
+        "Libra network peers banned for their score dropping below the threshold"
+    ).unwrap();
+
+    pub static ref LIBRA_NETWORK_BANNED_CONNECTIONS: IntCounterVec = register_int_counter_vec!(
+        "libra_network_banned_connections",
+        "Libra network connections to or from banned peers refused, by origin",
+        &["origin"]
+    ).unwrap();
 }
 
 lazy_static::lazy_static! {
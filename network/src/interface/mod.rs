@@ -15,11 +15,12 @@ use crate::{
     common::NetworkPublicKeys,
     connectivity_manager::ConnectivityRequest,
     counters,
-    peer_manager::{PeerManagerNotification, PeerManagerRequest},
+    peer_manager::{PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender},
     protocols::{
         direct_send::{DirectSendNotification, DirectSendRequest, Message},
         rpc::{InboundRpcRequest, OutboundRpcRequest, RpcNotification, RpcRequest},
     },
+    reputation::{PeerBehavior, PeerReputation, Reputation},
     validator_network::{
         AdmissionControlNetworkEvents, AdmissionControlNetworkSender, ConsensusNetworkEvents,
         ConsensusNetworkSender, DiscoveryNetworkEvents, DiscoveryNetworkSender,
@@ -33,7 +34,7 @@ use futures::{channel::oneshot, future::BoxFuture, FutureExt, SinkExt, StreamExt
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use parity_multiaddr::Multiaddr;
-use std::{collections::HashMap, fmt::Debug, time::Duration};
+use std::{collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
 
 pub use crate::peer_manager::PeerManagerError;
 
@@ -65,6 +66,11 @@ pub enum NetworkRequest {
     /// if, for example, we are not currently connected with the peer. Results
     /// are sent back to the caller via the oneshot channel.
     DisconnectPeer(PeerId, oneshot::Sender<Result<(), PeerManagerError>>),
+    /// Report the behavior of a peer. Peers whose reputation gets too bad are disconnected and
+    /// temporarily banned.
+    ReportPeer(PeerId, PeerBehavior),
+    /// Get the reputation of the recently reported peers.
+    GetPeerReputations(oneshot::Sender<HashMap<PeerId, PeerReputation>>),
 }
 
 /// Notifications that [`NetworkProvider`] sends to consumers of its API. The
@@ -128,6 +134,8 @@ pub struct NetworkProvider<TSubstream> {
     ds_notifs_rx: channel::Receiver<DirectSendNotification>,
     /// Channel over which we send requests to the ConnectivityManager actor.
     conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
+    /// Reputation of the peers, updated by the reports of the upper layers.
+    reputation: Arc<Reputation>,
     /// Channel to receive requests from other actors.
     requests_rx: channel::Receiver<NetworkRequest>,
     /// Channel over which other actors send requests to network.
@@ -263,6 +271,7 @@ where
             let rpc_reqs_tx = self.rpc_reqs_tx.clone();
             let ds_reqs_tx = self.ds_reqs_tx.clone();
             let conn_mgr_reqs_tx = self.conn_mgr_reqs_tx.clone();
+            let reputation = self.reputation.clone();
             let mut reqs = self
                 .requests_rx
                 .map(move |req| {
@@ -272,6 +281,7 @@ where
                         rpc_reqs_tx.clone(),
                         ds_reqs_tx.clone(),
                         conn_mgr_reqs_tx.clone(),
+                        reputation.clone(),
                     )
                     .boxed()
                 })
@@ -328,6 +338,7 @@ where
         ds_reqs_tx: channel::Sender<DirectSendRequest>,
         ds_notifs_rx: channel::Receiver<DirectSendNotification>,
         conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
+        reputation: Arc<Reputation>,
         requests_rx: channel::Receiver<NetworkRequest>,
         requests_tx: channel::Sender<NetworkRequest>,
         max_concurrent_reqs: u32,
@@ -343,6 +354,7 @@ where
             ds_reqs_tx,
             ds_notifs_rx,
             conn_mgr_reqs_tx,
+            reputation,
             requests_rx,
             requests_tx,
             max_concurrent_reqs,
@@ -357,6 +369,7 @@ where
         mut rpc_reqs_tx: channel::Sender<RpcRequest>,
         mut ds_reqs_tx: channel::Sender<DirectSendRequest>,
         conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
+        reputation: Arc<Reputation>,
     ) {
         trace!("NetworkRequest::{:?}", req);
         match req {
@@ -398,6 +411,30 @@ where
                     .await
                     .unwrap();
             }
+            NetworkRequest::ReportPeer(peer_id, behavior) => {
+                if !reputation.report(peer_id, behavior) {
+                    return;
+                }
+                warn!(
+                    "Banning peer {} for its bad reputation, last reported: {:?}",
+                    peer_id.short_str(),
+                    behavior
+                );
+                let mut peer_mgr_reqs_tx = PeerManagerRequestSender::new(peer_mgr_reqs_tx);
+                match peer_mgr_reqs_tx.disconnect_peer(peer_id).await {
+                    Ok(()) | Err(PeerManagerError::NotConnected(_)) => {}
+                    Err(e) => warn!(
+                        "Failed to disconnect banned peer {}: {:?}",
+                        peer_id.short_str(),
+                        e
+                    ),
+                }
+            }
+            NetworkRequest::GetPeerReputations(sender) => {
+                if sender.send(reputation.reputations()).is_err() {
+                    warn!("Receiver for GetPeerReputations dropped");
+                }
+            }
         }
     }
 
@@ -19,10 +19,14 @@ pub mod protocols;
 pub mod validator_network;
 
 mod common;
+mod compression;
 mod connectivity_manager;
 mod counters;
 mod error;
 mod peer_manager;
+mod rate_limiter;
+mod reputation;
+mod scheduler;
 mod sink;
 mod transport;
 mod utils;
@@ -3,6 +3,7 @@
 
 //! Errors that originate from the PeerManager module
 
+use crate::ProtocolId;
 use failure::Fail;
 use futures::channel::oneshot;
 use libra_types::PeerId;
@@ -25,6 +26,12 @@ pub enum PeerManagerError {
     #[fail(display = "Already connected at {}", _0)]
     AlreadyConnected(Multiaddr),
 
+    #[fail(display = "Peer {} is banned", _0)]
+    Banned(PeerId),
+
+    #[fail(display = "Protocol {:?} not supported by Peer", _0)]
+    ProtocolNotSupported(ProtocolId),
+
     #[fail(display = "Sending end of oneshot dropped")]
     OneshotSenderDropped,
 }
@@ -12,11 +12,14 @@
 //!  * An actor responsible for dialing and listening for new connections.
 //!  * An actor per Peer which owns the underlying connection and is responsible for listening for
 //!  and opening substreams as well as negotiating particular protocols on those substreams.
-use crate::{common::NegotiatedSubstream, counters, protocols::identity::Identity, ProtocolId};
+use crate::{
+    common::NegotiatedSubstream, compression, counters, protocols::identity::Identity,
+    reputation::Reputation, ProtocolId,
+};
 use channel;
 use futures::{
     channel::oneshot,
-    future::{BoxFuture, FutureExt},
+    future::{self, BoxFuture, FutureExt},
     sink::SinkExt,
     stream::{Fuse, FuturesUnordered, StreamExt},
 };
@@ -29,7 +32,7 @@ use netcore::{
     transport::{ConnectionOrigin, Transport},
 };
 use parity_multiaddr::Multiaddr;
-use std::{collections::HashMap, marker::PhantomData};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 use tokio::runtime::TaskExecutor;
 
 mod error;
@@ -168,6 +171,8 @@ where
     internal_event_tx: channel::Sender<InternalEvent<TMuxer>>,
     /// A map of outstanding disconnect requests
     outstanding_disconnect_requests: HashMap<PeerId, oneshot::Sender<Result<(), PeerManagerError>>>,
+    /// Reputation of the peers, whose banned ones are neither dialed nor accepted.
+    reputation: Arc<Reputation>,
     /// Pin the transport type corresponding to this PeerManager instance
     phantom_transport: PhantomData<TTransport>,
 }
@@ -189,6 +194,7 @@ where
             channel::Sender<PeerManagerNotification<TMuxer::Substream>>,
         >,
         peer_event_handlers: Vec<channel::Sender<PeerManagerNotification<TMuxer::Substream>>>,
+        reputation: Arc<Reputation>,
     ) -> Self {
         let (internal_event_tx, internal_event_rx) =
             channel::new(1024, &counters::PENDING_PEER_MANAGER_INTERNAL_EVENTS);
@@ -214,6 +220,7 @@ where
             internal_event_tx,
             internal_event_rx,
             outstanding_disconnect_requests: HashMap::new(),
+            reputation,
             phantom_transport: PhantomData,
         }
     }
@@ -251,6 +258,31 @@ where
         trace!("InternalEvent::{:?}", event);
         match event {
             InternalEvent::NewConnection(identity, addr, origin, conn) => {
+                let peer_id = identity.peer_id();
+                if self.reputation.is_banned(&peer_id) {
+                    // The remote peer is only known once the connection is upgraded, so that's
+                    // when the connections of banned peers are refused.
+                    let origin_label = match origin {
+                        ConnectionOrigin::Inbound => "inbound",
+                        ConnectionOrigin::Outbound => "outbound",
+                    };
+                    counters::LIBRA_NETWORK_BANNED_CONNECTIONS
+                        .with_label_values(&[origin_label])
+                        .inc();
+                    info!(
+                        "Closing {:?} connection with banned Peer {}",
+                        origin,
+                        peer_id.short_str()
+                    );
+                    conn.close().await.unwrap_or_else(|e| {
+                        error!(
+                            "Closing connection with Peer {} failed with error: {}",
+                            peer_id.short_str(),
+                            e
+                        )
+                    });
+                    return;
+                }
                 self.add_peer(identity, addr, origin, conn).await;
             }
             InternalEvent::NewSubstream(peer_id, substream) => {
@@ -322,6 +354,24 @@ where
                             requested_peer_id.short_str()
                         );
                     }
+                } else if self.reputation.is_banned(&requested_peer_id) {
+                    counters::LIBRA_NETWORK_BANNED_CONNECTIONS
+                        .with_label_values(&["outbound"])
+                        .inc();
+                    debug!(
+                        "Not dialing banned Peer {} at address {}",
+                        requested_peer_id.short_str(),
+                        addr
+                    );
+                    if response_tx
+                        .send(Err(PeerManagerError::Banned(requested_peer_id)))
+                        .is_err()
+                    {
+                        warn!(
+                            "Receiver for DialPeer {} dropped",
+                            requested_peer_id.short_str()
+                        );
+                    }
                 } else {
                     self.dial_peer(requested_peer_id, addr, response_tx).await;
                 };
@@ -931,8 +981,22 @@ where
         protocol: ProtocolId,
         channel: oneshot::Sender<Result<TMuxer::Substream, PeerManagerError>>,
     ) -> BoxFuture<'static, ()> {
-        let outbound = self.connection.open_outbound();
         let optimistic_negotiation = self.identity.is_protocol_supported(&protocol);
+        // Compressed variants of the protocols are only negotiated with the peers advertising
+        // them, so that the requester can fall back to the protocol itself right away.
+        if !optimistic_negotiation && compression::is_compressed_protocol(&protocol) {
+            if channel
+                .send(Err(PeerManagerError::ProtocolNotSupported(protocol)))
+                .is_err()
+            {
+                warn!(
+                    "oneshot channel receiver dropped for new substream with peer {}",
+                    self.identity.peer_id().short_str()
+                );
+            }
+            return future::ready(()).boxed();
+        }
+        let outbound = self.connection.open_outbound();
         let negotiate = Self::negotiate_outbound_substream(
             self.identity.peer_id(),
             outbound,
@@ -3,10 +3,11 @@
 
 use crate::{
     peer_manager::{
-        DisconnectReason, InternalEvent, Peer, PeerHandle, PeerManager, PeerManagerNotification,
-        PeerManagerRequest,
+        DisconnectReason, InternalEvent, Peer, PeerHandle, PeerManager, PeerManagerError,
+        PeerManagerNotification, PeerManagerRequest,
     },
     protocols::identity::{exchange_identity, Identity},
+    reputation::{PeerBehavior, Reputation},
     ProtocolId,
 };
 use channel;
@@ -17,7 +18,7 @@ use futures::{
     io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     stream::StreamExt,
 };
-use libra_config::config::RoleType;
+use libra_config::config::{ReputationConfig, RoleType};
 use libra_types::PeerId;
 use memsocket::MemorySocket;
 use netcore::{
@@ -29,7 +30,7 @@ use netcore::{
     transport::{boxed::BoxedTransport, memory::MemoryTransport, ConnectionOrigin, TransportExt},
 };
 use parity_multiaddr::Multiaddr;
-use std::{collections::HashMap, io, time::Duration};
+use std::{collections::HashMap, io, sync::Arc, time::Duration};
 use tokio::{runtime::TaskExecutor, timer::Timeout};
 
 const HELLO_PROTOCOL: &[u8] = b"/hello-world/1.0.0";
@@ -306,6 +307,7 @@ fn build_test_peer_manager(
         peer_manager_request_rx,
         protocol_handlers,
         Vec::new(),
+        Arc::new(Reputation::new(&ReputationConfig::default())),
     );
 
     (peer_manager, peer_manager_request_tx, hello_rx)
@@ -726,3 +728,43 @@ fn peer_manager_simultaneous_dial_disconnect_event() {
 
     runtime.block_on(test);
 }
+
+#[test]
+fn peer_manager_refuses_banned_peer() {
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let (mut peer_manager, _request_tx, _hello_rx) =
+        build_test_peer_manager(runtime.executor(), ids[1]);
+    peer_manager
+        .reputation
+        .report(ids[0], PeerBehavior::InvalidSignature);
+    assert!(peer_manager
+        .reputation
+        .report(ids[0], PeerBehavior::InvalidSignature));
+
+    let test = async move {
+        // The connections of the banned peer are closed.
+        let (outbound, inbound) = build_test_connection();
+        let event = InternalEvent::NewConnection(
+            build_test_identity(ids[0]),
+            Multiaddr::empty(),
+            ConnectionOrigin::Inbound,
+            inbound,
+        );
+        peer_manager.handle_internal_event(event).await;
+        assert!(!peer_manager.active_peers.contains_key(&ids[0]));
+        assert!(open_hello_substream(&outbound).await.is_err());
+
+        // The banned peer isn't dialed.
+        let (response_tx, response_rx) = oneshot::channel();
+        let request = PeerManagerRequest::DialPeer(ids[0], Multiaddr::empty(), response_tx);
+        peer_manager.handle_request(request).await;
+        match response_rx.await.unwrap() {
+            Err(PeerManagerError::Banned(peer_id)) => assert_eq!(peer_id, ids[0]),
+            result => panic!("Expected a Banned error, received: {:?}", result),
+        }
+    };
+
+    runtime.block_on(test);
+}
@@ -30,6 +30,10 @@
 //! 3. Sends the serialized message on the newly negotiated substream.
 //! 4. Drops the substream.
 //!
+//! If compression is enabled for the protocol, the substream is negotiated to its compressed
+//! variant when the remote peer supports it, see [`compression`](crate::compression). The messages
+//! to each peer are written by priority of their protocol, see [`scheduler`](crate::scheduler).
+//!
 //! ## Wire Protocol (listener):
 //!
 //! To receive a message from remote peers, the listener
@@ -44,9 +48,12 @@
 //! [substream negotiation]: ../../../netcore/negotiate/index.html
 //! [`protocol-select`]: ../../../netcore/negotiate/index.html
 use crate::{
+    compression::{self, Compression},
     counters,
     error::NetworkError,
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    rate_limiter::{self, RateLimiter},
+    scheduler::OutboundScheduler,
     ProtocolId,
 };
 use bytes::Bytes;
@@ -62,6 +69,7 @@ use netcore::compat::IoCompat;
 use std::{
     collections::{hash_map::Entry, HashMap},
     fmt::Debug,
+    sync::Arc,
 };
 use tokio::{
     codec::{Framed, LengthDelimitedCodec},
@@ -120,6 +128,12 @@ pub struct DirectSend<TSubstream> {
     peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
     /// Outbound message queues for each (PeerId, ProtocolId) pair.
     message_queues: HashMap<(PeerId, ProtocolId), channel::Sender<Bytes>>,
+    /// Rate limits of the inbound messages.
+    rate_limiter: Arc<RateLimiter>,
+    /// Compression of the outbound messages.
+    compression: Arc<Compression>,
+    /// Priority scheduling of the outbound messages.
+    scheduler: OutboundScheduler,
 }
 
 impl<TSubstream> DirectSend<TSubstream>
@@ -132,6 +146,9 @@ where
         ds_notifs_tx: channel::Sender<DirectSendNotification>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
+        rate_limiter: Arc<RateLimiter>,
+        compression: Arc<Compression>,
+        scheduler: OutboundScheduler,
     ) -> Self {
         Self {
             executor,
@@ -140,6 +157,9 @@ where
             peer_mgr_notifs_rx,
             peer_mgr_reqs_tx,
             message_queues: HashMap::new(),
+            rate_limiter,
+            compression,
+            scheduler,
         }
     }
 
@@ -170,28 +190,61 @@ where
                     substream.protocol,
                     substream.substream,
                     self.ds_notifs_tx.clone(),
+                    self.peer_mgr_reqs_tx.clone(),
+                    Arc::clone(&self.rate_limiter),
                 ));
             }
             _ => unreachable!("Unexpected PeerManagerNotification"),
         }
     }
 
-    // Handle a new inbound substream. Keep forwarding the messages to the NetworkProvider.
+    // Handle a new inbound substream. Keep forwarding the messages to the NetworkProvider, within
+    // the rate limits of the peer, decompressed if the substream is compressed.
     async fn handle_inbound_substream(
         peer_id: PeerId,
         protocol: ProtocolId,
         substream: TSubstream,
         mut ds_notifs_tx: channel::Sender<DirectSendNotification>,
+        mut peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
+        rate_limiter: Arc<RateLimiter>,
     ) {
+        let (protocol, compressed) = compression::split_protocol(protocol);
         let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
         while let Some(item) = substream.next().await {
             match item {
                 Ok(data) => {
+                    if !rate_limiter::admit(
+                        &rate_limiter,
+                        &mut peer_mgr_reqs_tx,
+                        peer_id,
+                        &protocol,
+                        data.len(),
+                        "direct_send",
+                    )
+                    .await
+                    {
+                        continue;
+                    }
+                    let mdata = if compressed {
+                        match compression::decode(data.freeze(), "direct_send") {
+                            Ok(mdata) => mdata,
+                            Err(e) => {
+                                warn!(
+                                    "DirectSend substream with peer {} receives invalid message {}",
+                                    peer_id.short_str(),
+                                    e
+                                );
+                                break;
+                            }
+                        }
+                    } else {
+                        data.freeze()
+                    };
                     let notif = DirectSendNotification::RecvMessage(
                         peer_id,
                         Message {
                             protocol: protocol.clone(),
-                            mdata: data.freeze(),
+                            mdata,
                         },
                     );
                     ds_notifs_tx
@@ -222,9 +275,11 @@ where
         mut peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_id: PeerId,
         protocol: ProtocolId,
+        compression: Arc<Compression>,
+        scheduler: OutboundScheduler,
     ) -> Result<channel::Sender<Bytes>, NetworkError> {
         // Create a channel for the (PeerId, ProtocolId) pair.
-        let (msg_tx, msg_rx) = channel::new::<Bytes>(
+        let (msg_tx, mut msg_rx) = channel::new::<Bytes>(
             1024,
             &counters::OP_COUNTERS.peer_gauge(
                 &counters::PENDING_DIRECT_SEND_OUTBOUND_MESSAGES,
@@ -233,17 +288,29 @@ where
         );
 
         // Open a new substream for the (PeerId, ProtocolId) pair
-        let raw_substream = peer_mgr_reqs_tx.open_substream(peer_id, protocol).await?;
-        let substream = Framed::new(IoCompat::new(raw_substream), LengthDelimitedCodec::new());
+        let (raw_substream, compressed) = compression
+            .open_substream(&mut peer_mgr_reqs_tx, peer_id, protocol.clone())
+            .await?;
+        let mut substream = Framed::new(IoCompat::new(raw_substream), LengthDelimitedCodec::new());
 
         // Spawn a task to forward the messages from the queue to the substream.
         let f_substream = async move {
-            if let Err(e) = msg_rx.map(Ok).forward(substream).await {
-                warn!(
-                    "Forward messages to peer {} error {:?}",
-                    peer_id.short_str(),
-                    e
-                );
+            while let Some(mdata) = msg_rx.next().await {
+                let mdata = if compressed {
+                    compression.encode(&mdata, "direct_send")
+                } else {
+                    mdata
+                };
+                // Hold the turn of the message until it is written out.
+                let _permit = scheduler.acquire(peer_id, &protocol).await;
+                if let Err(e) = substream.send(mdata).await {
+                    warn!(
+                        "Forward messages to peer {} error {:?}",
+                        peer_id.short_str(),
+                        e
+                    );
+                    break;
+                }
             }
             // The messages in queue will be dropped
             counters::LIBRA_NETWORK_DIRECT_SEND_MESSAGES
@@ -279,6 +346,8 @@ where
                     peer_mgr_reqs_tx,
                     peer_id,
                     protocol.clone(),
+                    Arc::clone(&self.compression),
+                    self.scheduler.clone(),
                 )
                 .await?;
                 entry.insert(msg_tx)
@@ -3,18 +3,23 @@
 
 use crate::{
     common::NegotiatedSubstream,
+    compression::Compression,
     peer_manager::{
         PeerManagerError, PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
     },
     protocols::direct_send::{DirectSend, DirectSendNotification, DirectSendRequest, Message},
+    rate_limiter::RateLimiter,
+    scheduler::OutboundScheduler,
     ProtocolId,
 };
 use bytes::Bytes;
 use channel;
 use futures::{sink::SinkExt, stream::StreamExt};
+use libra_config::config::{CompressionConfig, RateLimitConfig};
 use libra_types::PeerId;
 use memsocket::MemorySocket;
 use netcore::compat::IoCompat;
+use std::sync::Arc;
 use tokio::{
     codec::{Framed, LengthDelimitedCodec},
     runtime::{Runtime, TaskExecutor},
@@ -44,6 +49,9 @@ fn start_direct_send_actor(
         ds_notifs_tx,
         peer_mgr_notifs_rx,
         PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+        Arc::new(RateLimiter::new(&RateLimitConfig::default())),
+        Arc::new(Compression::new(&CompressionConfig::default())),
+        OutboundScheduler::new(),
     );
     executor.spawn(direct_send.start());
 
@@ -44,6 +44,9 @@ pub enum RpcError {
 
     #[fail(display = "Rpc timed out")]
     TimedOut,
+
+    #[fail(display = "Rpc request dropped, peer {} is over its rate limits", _0)]
+    RateLimited(PeerId),
 }
 
 impl From<io::Error> for RpcError {
@@ -3,8 +3,11 @@
 
 use crate::{
     common::NegotiatedSubstream,
-    peer_manager::PeerManagerNotification,
+    compression::Compression,
+    peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
     protocols::rpc::{self, RpcNotification},
+    rate_limiter::RateLimiter,
+    scheduler::OutboundScheduler,
     ProtocolId,
 };
 use bytes::{Bytes, BytesMut};
@@ -14,11 +17,12 @@ use futures::{
     io::{AsyncReadExt, AsyncWriteExt},
     stream::StreamExt,
 };
+use libra_config::config::{CompressionConfig, RateLimitConfig};
 use libra_proptest_helpers::ValueGenerator;
 use libra_types::{account_address::ADDRESS_LENGTH, PeerId};
 use memsocket::MemorySocket;
 use proptest::{arbitrary::any, collection::vec, prop_oneof, strategy::Strategy};
-use std::{io, time::Duration};
+use std::{io, sync::Arc, time::Duration};
 use tokio::{
     codec::{Encoder, LengthDelimitedCodec},
     runtime::current_thread,
@@ -75,10 +79,19 @@ pub fn fuzzer(data: &[u8]) {
     let peer_mgr_notif =
         PeerManagerNotification::NewInboundSubstream(MOCK_PEER_ID, listener_substream);
 
+    let (peer_mgr_reqs_tx, _peer_mgr_reqs_rx) = channel::new_test(8);
+
     // run the rpc inbound protocol using the in-memory substream
-    let f_handle_inbound =
-        rpc::handle_inbound_substream(notification_tx, peer_mgr_notif, INBOUND_RPC_TIMEOUT)
-            .map(|_| io::Result::Ok(()));
+    let f_handle_inbound = rpc::handle_inbound_substream(
+        notification_tx,
+        peer_mgr_notif,
+        INBOUND_RPC_TIMEOUT,
+        PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+        Arc::new(RateLimiter::new(&RateLimitConfig::default())),
+        Arc::new(Compression::new(&CompressionConfig::default())),
+        OutboundScheduler::new(),
+    )
+    .map(|_| io::Result::Ok(()));
 
     // mock the notification channel to echo the fuzzer data back to the dialer
     // as an rpc response
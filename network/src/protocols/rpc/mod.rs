@@ -35,6 +35,11 @@
 //! 5. Awaits the serialized response message from remote.
 //! 6. Awaits the listener's half-close to complete the substream close.
 //!
+//! If compression is enabled for the rpc method, the substream is negotiated to its compressed
+//! variant when the remote peer supports it, see [`compression`](crate::compression). Both the
+//! request and the response are then compressed. Requests and responses are written by priority of
+//! their rpc method, see [`scheduler`](crate::scheduler).
+//!
 //! ## Wire Protocol (listener):
 //!
 //! To handle new rpc requests from remote peers, the listener
@@ -56,8 +61,11 @@
 //! [`protocol-select`]: ../../../netcore/negotiate/index.html
 
 use crate::{
+    compression::{self, Compression},
     counters,
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    rate_limiter::{self, RateLimiter},
+    scheduler::OutboundScheduler,
     sink::NetworkSinkExt,
     ProtocolId,
 };
@@ -76,7 +84,7 @@ use futures::{
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::compat::IoCompat;
-use std::{fmt::Debug, io, time::Duration};
+use std::{fmt::Debug, io, sync::Arc, time::Duration};
 use tokio::{
     codec::{Framed, LengthDelimitedCodec},
     future::FutureExt as _,
@@ -177,6 +185,12 @@ pub struct Rpc<TSubstream> {
     // TODO(philiphayes): partition inbound queue by peer to prevent one peer
     // from starving other peers' rpcs?
     max_concurrent_inbound_rpcs: u32,
+    /// Rate limits of the inbound rpc requests.
+    rate_limiter: Arc<RateLimiter>,
+    /// Compression of the requests and responses.
+    compression: Arc<Compression>,
+    /// Priority scheduling of the requests and responses.
+    scheduler: OutboundScheduler,
 }
 
 impl<TSubstream> Rpc<TSubstream>
@@ -193,6 +207,9 @@ where
         inbound_rpc_timeout: Duration,
         max_concurrent_outbound_rpcs: u32,
         max_concurrent_inbound_rpcs: u32,
+        rate_limiter: Arc<RateLimiter>,
+        compression: Arc<Compression>,
+        scheduler: OutboundScheduler,
     ) -> Self {
         Self {
             executor,
@@ -203,6 +220,9 @@ where
             inbound_rpc_timeout,
             max_concurrent_outbound_rpcs,
             max_concurrent_inbound_rpcs,
+            rate_limiter,
+            compression,
+            scheduler,
         }
     }
 
@@ -217,6 +237,9 @@ where
         let inbound_rpc_timeout = self.inbound_rpc_timeout;
         let max_concurrent_outbound_rpcs = self.max_concurrent_outbound_rpcs;
         let max_concurrent_inbound_rpcs = self.max_concurrent_inbound_rpcs;
+        let rate_limiter = self.rate_limiter;
+        let compression = self.compression;
+        let scheduler = self.scheduler;
 
         // inbound and outbound requests use separate bounded executors to ensure
         // backpressure propagates independently and doesn't starve the other
@@ -225,14 +248,20 @@ where
         let outbound_handler = handle_outbounds(
             BoundedExecutor::new(max_concurrent_outbound_rpcs as usize, executor.clone()),
             requests_rx,
-            peer_mgr_reqs_tx,
+            peer_mgr_reqs_tx.clone(),
+            Arc::clone(&compression),
+            scheduler.clone(),
         );
 
         let inbound_handler = handle_inbounds(
             BoundedExecutor::new(max_concurrent_inbound_rpcs as usize, executor),
             peer_mgr_notifs_rx,
+            peer_mgr_reqs_tx,
             rpc_handler_tx,
             inbound_rpc_timeout,
+            rate_limiter,
+            compression,
+            scheduler,
         );
 
         // drive inbound and outbound handlers to completion
@@ -247,12 +276,19 @@ async fn handle_outbounds<TSubstream>(
     executor: BoundedExecutor,
     mut requests_rx: channel::Receiver<RpcRequest>,
     peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    compression: Arc<Compression>,
+    scheduler: OutboundScheduler,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
     while let Some(req) = requests_rx.next().await {
         executor
-            .spawn(handle_outbound_rpc(peer_mgr_tx.clone(), req))
+            .spawn(handle_outbound_rpc(
+                peer_mgr_tx.clone(),
+                req,
+                Arc::clone(&compression),
+                scheduler.clone(),
+            ))
             .await;
     }
 }
@@ -261,8 +297,12 @@ async fn handle_outbounds<TSubstream>(
 async fn handle_inbounds<TSubstream>(
     executor: BoundedExecutor,
     mut peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
+    peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
     rpc_handler_tx: channel::Sender<RpcNotification>,
     inbound_rpc_timeout: Duration,
+    rate_limiter: Arc<RateLimiter>,
+    compression: Arc<Compression>,
+    scheduler: OutboundScheduler,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static,
 {
@@ -272,6 +312,10 @@ async fn handle_inbounds<TSubstream>(
                 rpc_handler_tx.clone(),
                 notif,
                 inbound_rpc_timeout,
+                peer_mgr_tx.clone(),
+                Arc::clone(&rate_limiter),
+                Arc::clone(&compression),
+                scheduler.clone(),
             ))
             .await;
     }
@@ -290,6 +334,8 @@ async fn handle_inbounds<TSubstream>(
 async fn handle_outbound_rpc<TSubstream>(
     peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
     req: RpcRequest,
+    compression: Arc<Compression>,
+    scheduler: OutboundScheduler,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
 {
@@ -301,12 +347,19 @@ async fn handle_outbound_rpc<TSubstream>(
             let timeout = req.timeout;
 
             // Future to run the actual outbound rpc protocol and get the results.
-            let mut f_rpc_res = handle_outbound_rpc_inner(peer_mgr_tx, peer_id, protocol, req_data)
-                .timeout(timeout)
-                .map_err(Into::<RpcError>::into)
-                .map(|r| r.and_then(|x| x))
-                .boxed()
-                .fuse();
+            let mut f_rpc_res = handle_outbound_rpc_inner(
+                peer_mgr_tx,
+                peer_id,
+                protocol,
+                req_data,
+                &compression,
+                &scheduler,
+            )
+            .timeout(timeout)
+            .map_err(Into::<RpcError>::into)
+            .map(|r| r.and_then(|x| x))
+            .boxed()
+            .fuse();
 
             // If the rpc client drops their oneshot receiver, this future should
             // cancel the request.
@@ -351,21 +404,33 @@ async fn handle_outbound_rpc_inner<TSubstream>(
     peer_id: PeerId,
     protocol: ProtocolId,
     req_data: Bytes,
+    compression: &Compression,
+    scheduler: &OutboundScheduler,
 ) -> Result<Bytes, RpcError>
 where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
 {
     let _timer = counters::LIBRA_NETWORK_RPC_LATENCY.start_timer();
     // Request a new substream with the peer.
-    let substream = peer_mgr_tx.open_substream(peer_id, protocol).await?;
+    let (substream, compressed) = compression
+        .open_substream(&mut peer_mgr_tx, peer_id, protocol.clone())
+        .await?;
     // Rpc messages are length-prefixed.
     let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
-    // Send the rpc request data.
+    // Send the rpc request data, in its turn.
     let req_len = req_data.len();
-    substream.buffered_send(req_data).await?;
+    let permit = scheduler.acquire(peer_id, &protocol).await;
+    if compressed {
+        substream
+            .buffered_send(compression.encode(&req_data, "rpc"))
+            .await?;
+    } else {
+        substream.buffered_send(req_data).await?;
+    }
     // We won't send anything else on this substream, so we can half-close our
     // output side.
     substream.close().await?;
+    drop(permit);
     counters::LIBRA_NETWORK_RPC_MESSAGES
         .with_label_values(&["request", "sent"])
         .inc();
@@ -374,10 +439,13 @@ where
         .observe(req_len as f64);
 
     // Wait for listener's response.
-    let res_data = match substream.next().await {
+    let mut res_data = match substream.next().await {
         Some(res_data) => res_data?.freeze(),
         None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
     };
+    if compressed {
+        res_data = compression::decode(res_data, "rpc")?;
+    }
 
     // Wait for listener to half-close their side.
     match substream.next().await {
@@ -389,11 +457,15 @@ where
 }
 
 /// Handle an new inbound substream. Run the inbound rpc protocol over the
-/// substream.
+/// substream, within the rate limits of the peer.
 async fn handle_inbound_substream<TSubstream>(
     notification_tx: channel::Sender<RpcNotification>,
     notif: PeerManagerNotification<TSubstream>,
     timeout: Duration,
+    peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    rate_limiter: Arc<RateLimiter>,
+    compression: Arc<Compression>,
+    scheduler: OutboundScheduler,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Debug + Send + Unpin,
 {
@@ -405,6 +477,10 @@ async fn handle_inbound_substream<TSubstream>(
                 peer_id,
                 substream.protocol,
                 substream.substream,
+                peer_mgr_tx,
+                &rate_limiter,
+                &compression,
+                &scheduler,
             )
             .timeout(timeout)
             .map_err(Into::<RpcError>::into)
@@ -436,10 +512,15 @@ async fn handle_inbound_substream_inner<TSubstream>(
     peer_id: PeerId,
     protocol: ProtocolId,
     substream: TSubstream,
+    mut peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    rate_limiter: &RateLimiter,
+    compression: &Compression,
+    scheduler: &OutboundScheduler,
 ) -> Result<(), RpcError>
 where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
 {
+    let (protocol, compressed) = compression::split_protocol(protocol);
     // Rpc messages are length-prefixed.
     let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
     // Read the rpc request data.
@@ -458,12 +539,30 @@ where
         return Err(RpcError::UnexpectedRpcRequest);
     };
 
+    if !rate_limiter::admit(
+        rate_limiter,
+        &mut peer_mgr_tx,
+        peer_id,
+        &protocol,
+        req_data.len(),
+        "rpc",
+    )
+    .await
+    {
+        return Err(RpcError::RateLimited(peer_id));
+    }
+    let req_data = if compressed {
+        compression::decode(req_data, "rpc")?
+    } else {
+        req_data
+    };
+
     // Build the event and context we push up to upper layers for handling.
     let (res_tx, res_rx) = oneshot::channel();
     let notification = RpcNotification::RecvRpc(
         peer_id,
         InboundRpcRequest {
-            protocol,
+            protocol: protocol.clone(),
             data: req_data,
             res_tx,
         },
@@ -476,13 +575,21 @@ where
     let res_data = res_rx.await??;
     let res_len = res_data.len();
 
-    // Send the response to remote
-    substream.buffered_send(res_data).await?;
+    // Send the response to remote, in its turn.
+    let permit = scheduler.acquire(peer_id, &protocol).await;
+    if compressed {
+        substream
+            .buffered_send(compression.encode(&res_data, "rpc"))
+            .await?;
+    } else {
+        substream.buffered_send(res_data).await?;
+    }
 
     // We won't send anything else on this substream, so we can half-close
     // our output. The initiator will have also half-closed their side before
     // this, so this should gracefully shutdown the socket.
     substream.close().await?;
+    drop(permit);
     counters::LIBRA_NETWORK_RPC_MESSAGES
         .with_label_values(&["response", "sent"])
         .inc();
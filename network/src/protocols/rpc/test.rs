@@ -7,6 +7,7 @@ use crate::{
     peer_manager::{PeerManagerNotification, PeerManagerRequest},
 };
 use futures::future::{join, join3, join4};
+use libra_config::config::{CompressionConfig, RateLimitConfig};
 use memsocket::MemorySocket;
 use tokio::runtime::Runtime;
 
@@ -28,10 +29,24 @@ where
         timeout,
     };
     let rpc_req = RpcRequest::SendRpc(recipient, outbound_req);
-    handle_outbound_rpc(peer_mgr_tx, rpc_req).await;
+    handle_outbound_rpc(
+        peer_mgr_tx,
+        rpc_req,
+        no_compression(),
+        OutboundScheduler::new(),
+    )
+    .await;
     res_rx.await.unwrap()
 }
 
+fn unlimited_rate_limiter() -> Arc<RateLimiter> {
+    Arc::new(RateLimiter::new(&RateLimitConfig::default()))
+}
+
+fn no_compression() -> Arc<Compression> {
+    Arc::new(Compression::new(&CompressionConfig::default()))
+}
+
 // On the next OpenSubstream event, return the given substream.
 async fn mock_peer_manager<TSubstream: Debug>(
     mut peer_mgr_rx: channel::Receiver<PeerManagerRequest<TSubstream>>,
@@ -88,10 +103,15 @@ fn upgrades() {
     let inbound_notif = PeerManagerNotification::NewInboundSubstream(dialer_peer_id, substream);
 
     // Handle the inbound substream
+    let (listener_peer_mgr_reqs_tx, _) = channel::new_test(8);
     let f_listener_upgrade = handle_inbound_substream(
         listener_rpc_notifs_tx,
         inbound_notif,
         Duration::from_millis(500),
+        PeerManagerRequestSender::new(listener_peer_mgr_reqs_tx),
+        unlimited_rate_limiter(),
+        no_compression(),
+        OutboundScheduler::new(),
     );
 
     // Make an outbound substream request
@@ -244,6 +264,10 @@ fn dialer_close_before_listener_recv() {
             dialer_peer_id,
             ProtocolId::from_static(protocol_id),
             listener_substream,
+            PeerManagerRequestSender::new(channel::new_test(8).0),
+            &unlimited_rate_limiter(),
+            &no_compression(),
+            &OutboundScheduler::new(),
         )
         .await;
 
@@ -293,6 +317,10 @@ fn dialer_close_before_listener_send() {
             dialer_peer_id,
             ProtocolId::from_static(protocol_id),
             listener_substream,
+            PeerManagerRequestSender::new(channel::new_test(8).0),
+            &unlimited_rate_limiter(),
+            &no_compression(),
+            &OutboundScheduler::new(),
         )
         .await;
 
@@ -341,6 +369,10 @@ fn dialer_sends_two_requests_err() {
             dialer_peer_id,
             ProtocolId::from_static(protocol_id),
             listener_substream,
+            PeerManagerRequestSender::new(channel::new_test(8).0),
+            &unlimited_rate_limiter(),
+            &no_compression(),
+            &OutboundScheduler::new(),
         )
         .await;
 
@@ -437,10 +469,15 @@ fn inbound_rpc_timeout() {
         substream: listener_substream,
     };
     let inbound_notif = PeerManagerNotification::NewInboundSubstream(dialer_peer_id, substream);
+    let (listener_peer_mgr_reqs_tx, _) = channel::new_test(8);
     let f_listener_upgrade = handle_inbound_substream(
         listener_rpc_notifs_tx,
         inbound_notif,
         Duration::from_millis(100),
+        PeerManagerRequestSender::new(listener_peer_mgr_reqs_tx),
+        unlimited_rate_limiter(),
+        no_compression(),
+        OutboundScheduler::new(),
     );
 
     // The listener future should complete (with a timeout) despite the dialer
@@ -471,7 +508,12 @@ fn outbound_cancellation_before_send() {
         timeout: Duration::from_secs(1),
     };
     let rpc_req = RpcRequest::SendRpc(listener_peer_id, outbound_req);
-    let f_rpc = handle_outbound_rpc(dialer_peer_mgr_reqs_tx, rpc_req);
+    let f_rpc = handle_outbound_rpc(
+        dialer_peer_mgr_reqs_tx,
+        rpc_req,
+        no_compression(),
+        OutboundScheduler::new(),
+    );
 
     // drop res_rx to cancel the rpc request
     drop(res_rx);
@@ -519,8 +561,13 @@ fn outbound_cancellation_recv() {
             timeout: Duration::from_secs(1),
         };
         let rpc_req = RpcRequest::SendRpc(listener_peer_id, outbound_req);
-        let (f_rpc, f_rpc_done) =
-            handle_outbound_rpc(dialer_peer_mgr_reqs_tx, rpc_req).remote_handle();
+        let (f_rpc, f_rpc_done) = handle_outbound_rpc(
+            dialer_peer_mgr_reqs_tx,
+            rpc_req,
+            no_compression(),
+            OutboundScheduler::new(),
+        )
+        .remote_handle();
         executor.spawn(f_rpc);
 
         futures::select! {
@@ -604,6 +651,9 @@ fn rpc_protocol() {
         Duration::from_millis(500),
         10,
         10,
+        unlimited_rate_limiter(),
+        no_compression(),
+        OutboundScheduler::new(),
     );
 
     // Fake the dialer NetworkProvider
@@ -655,6 +705,9 @@ fn rpc_protocol() {
         Duration::from_millis(500),
         10,
         10,
+        unlimited_rate_limiter(),
+        no_compression(),
+        OutboundScheduler::new(),
     );
 
     // Fake the listener NetworkProvider
@@ -0,0 +1,244 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rate limits of the inbound messages and rpcs of each peer
+//!
+//! Every peer gets two token buckets for each limited protocol: one counting messages and one
+//! counting bytes, both refilled continuously at the configured rate, up to one second worth of
+//! tokens. An inbound message is delivered right away if both buckets hold enough tokens for it.
+//! Otherwise the buckets go into debt, and the message is held back until they are refilled,
+//! as long as that takes less than `max_delay_ms`: the message is dropped beyond that. A peer
+//! which keeps going over its limits for `disconnect_after_ms` after its first dropped message,
+//! i.e. which doesn't get a message through without delay meanwhile, is disconnected.
+//!
+//! The limits are applied by the [`DirectSend`](crate::protocols::direct_send::DirectSend) and
+//! [`Rpc`](crate::protocols::rpc::Rpc) actors, which share a single [`RateLimiter`].
+use crate::{counters, peer_manager::PeerManagerRequestSender, ProtocolId};
+use libra_config::config::{ProtocolRateLimit, RateLimitConfig};
+use libra_logger::prelude::*;
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::timer::delay_for;
+
+#[cfg(test)]
+mod test;
+
+/// Buckets which weren't used for this long are full, and forgotten.
+const IDLE_BUCKETS_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What to do with an inbound message.
+#[derive(Debug, PartialEq)]
+pub enum RateLimitDecision {
+    /// Deliver the message.
+    Accept,
+    /// Deliver the message after the delay.
+    Delay(Duration),
+    /// Drop the message.
+    Drop,
+    /// Drop the message and disconnect the peer.
+    Disconnect,
+}
+
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Option<Self> {
+        if rate == 0 {
+            return None;
+        }
+        Some(Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+        })
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.tokens = (self.tokens + self.rate * elapsed.as_secs_f64()).min(self.rate);
+    }
+
+    /// Returns how long it takes for the bucket to hold `amount` tokens.
+    fn delay(&self, amount: f64) -> Duration {
+        if self.tokens >= amount {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64((amount - self.tokens) / self.rate)
+        }
+    }
+
+    fn take(&mut self, amount: f64) {
+        self.tokens -= amount;
+    }
+}
+
+struct PeerBuckets {
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+    last_update: Instant,
+    // When the peer started going over its limits so far that its messages get dropped
+    dropping_since: Option<Instant>,
+}
+
+impl PeerBuckets {
+    fn new(limit: &ProtocolRateLimit, now: Instant) -> Self {
+        Self {
+            messages: TokenBucket::new(limit.messages_per_sec),
+            bytes: TokenBucket::new(limit.bytes_per_sec),
+            last_update: now,
+            dropping_since: None,
+        }
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut TokenBucket> {
+        self.messages.iter_mut().chain(self.bytes.iter_mut())
+    }
+}
+
+struct RateLimiterState {
+    buckets: HashMap<(PeerId, ProtocolId), PeerBuckets>,
+    last_sweep: Instant,
+}
+
+/// Token bucket limits of the inbound messages of each peer, by protocol.
+pub struct RateLimiter {
+    limits: HashMap<ProtocolId, ProtocolRateLimit>,
+    max_delay: Duration,
+    disconnect_after: Duration,
+    state: Mutex<RateLimiterState>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            limits: config
+                .protocols
+                .iter()
+                .map(|(protocol, limit)| (ProtocolId::from(protocol.as_bytes()), *limit))
+                .collect(),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+            disconnect_after: Duration::from_millis(config.disconnect_after_ms),
+            state: Mutex::new(RateLimiterState {
+                buckets: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    /// Decides what to do with a message of `num_bytes` bytes received from `peer_id`.
+    pub fn check(
+        &self,
+        peer_id: PeerId,
+        protocol: &ProtocolId,
+        num_bytes: usize,
+    ) -> RateLimitDecision {
+        self.check_at(peer_id, protocol, num_bytes, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        peer_id: PeerId,
+        protocol: &ProtocolId,
+        num_bytes: usize,
+        now: Instant,
+    ) -> RateLimitDecision {
+        let limit = match self.limits.get(protocol) {
+            Some(limit) => limit,
+            None => return RateLimitDecision::Accept,
+        };
+        let mut state = self.state.lock().unwrap();
+        if now.duration_since(state.last_sweep) >= IDLE_BUCKETS_TIMEOUT {
+            state.buckets.retain(|_, buckets| {
+                now.duration_since(buckets.last_update) < IDLE_BUCKETS_TIMEOUT
+            });
+            state.last_sweep = now;
+        }
+        let key = (peer_id, protocol.clone());
+        let buckets = state
+            .buckets
+            .entry(key.clone())
+            .or_insert_with(|| PeerBuckets::new(limit, now));
+        let elapsed = now.duration_since(buckets.last_update);
+        buckets.last_update = now;
+        buckets.iter_mut().for_each(|bucket| bucket.refill(elapsed));
+
+        let delay = buckets
+            .messages
+            .as_ref()
+            .map_or_else(|| Duration::from_secs(0), |bucket| bucket.delay(1.0))
+            .max(buckets.bytes.as_ref().map_or_else(
+                || Duration::from_secs(0),
+                |bucket| bucket.delay(num_bytes as f64),
+            ));
+        if delay > self.max_delay {
+            let dropping_since = *buckets.dropping_since.get_or_insert(now);
+            if now.duration_since(dropping_since) >= self.disconnect_after {
+                state.buckets.remove(&key);
+                return RateLimitDecision::Disconnect;
+            }
+            return RateLimitDecision::Drop;
+        }
+        if let Some(bucket) = buckets.messages.as_mut() {
+            bucket.take(1.0);
+        }
+        if let Some(bucket) = buckets.bytes.as_mut() {
+            bucket.take(num_bytes as f64);
+        }
+        if delay == Duration::from_secs(0) {
+            // The peer is back within its limits.
+            buckets.dropping_since = None;
+            RateLimitDecision::Accept
+        } else {
+            RateLimitDecision::Delay(delay)
+        }
+    }
+}
+
+/// Applies the limits of `peer_id` to an inbound message of `num_bytes` bytes: waits for the
+/// delay, if any, and disconnects the peer if it stayed over its limits for too long.
+///
+/// Returns whether the message should be delivered.
+pub async fn admit<TSubstream>(
+    rate_limiter: &RateLimiter,
+    peer_mgr_reqs_tx: &mut PeerManagerRequestSender<TSubstream>,
+    peer_id: PeerId,
+    protocol: &ProtocolId,
+    num_bytes: usize,
+    message_type: &str,
+) -> bool {
+    match rate_limiter.check(peer_id, protocol, num_bytes) {
+        RateLimitDecision::Accept => true,
+        RateLimitDecision::Delay(delay) => {
+            counters::LIBRA_NETWORK_RATE_LIMITED_MESSAGES
+                .with_label_values(&[message_type, "throttled"])
+                .inc();
+            delay_for(delay).await;
+            true
+        }
+        RateLimitDecision::Drop => {
+            counters::LIBRA_NETWORK_RATE_LIMITED_MESSAGES
+                .with_label_values(&[message_type, "dropped"])
+                .inc();
+            false
+        }
+        RateLimitDecision::Disconnect => {
+            counters::LIBRA_NETWORK_RATE_LIMITED_MESSAGES
+                .with_label_values(&[message_type, "dropped"])
+                .inc();
+            counters::LIBRA_NETWORK_RATE_LIMITED_PEERS.inc();
+            warn!(
+                "Disconnecting peer {} staying over its rate limits",
+                peer_id.short_str()
+            );
+            if let Err(e) = peer_mgr_reqs_tx.disconnect_peer(peer_id).await {
+                warn!("Failed to disconnect peer {}: {:?}", peer_id.short_str(), e);
+            }
+            false
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use std::collections::BTreeMap;
+
+const PROTOCOL: &[u8] = b"/libra/consensus/rpc/0.1.0";
+const OTHER_PROTOCOL: &[u8] = b"/libra/mempool/direct-send/0.1.0";
+
+fn rate_limiter(messages_per_sec: u64, bytes_per_sec: u64) -> RateLimiter {
+    let mut protocols = BTreeMap::new();
+    protocols.insert(
+        String::from_utf8(PROTOCOL.to_vec()).unwrap(),
+        ProtocolRateLimit {
+            messages_per_sec,
+            bytes_per_sec,
+        },
+    );
+    RateLimiter::new(&RateLimitConfig {
+        protocols,
+        max_delay_ms: 1000,
+        disconnect_after_ms: 5000,
+    })
+}
+
+#[test]
+fn test_message_limit() {
+    let rate_limiter = rate_limiter(10, 0);
+    let peer_id = PeerId::random();
+    let protocol = ProtocolId::from_static(PROTOCOL);
+    let now = Instant::now();
+
+    // A second worth of messages goes through right away.
+    for _ in 0..10 {
+        assert_eq!(
+            rate_limiter.check_at(peer_id, &protocol, 1000, now),
+            RateLimitDecision::Accept
+        );
+    }
+    // The next second worth is delayed.
+    for i in 1..=10 {
+        assert_eq!(
+            rate_limiter.check_at(peer_id, &protocol, 1000, now),
+            RateLimitDecision::Delay(Duration::from_millis(100 * i))
+        );
+    }
+    assert_eq!(
+        rate_limiter.check_at(peer_id, &protocol, 1000, now),
+        RateLimitDecision::Drop
+    );
+    // The other peers and protocols aren't affected.
+    assert_eq!(
+        rate_limiter.check_at(PeerId::random(), &protocol, 1000, now),
+        RateLimitDecision::Accept
+    );
+    assert_eq!(
+        rate_limiter.check_at(peer_id, &ProtocolId::from_static(OTHER_PROTOCOL), 1000, now),
+        RateLimitDecision::Accept
+    );
+    // The buckets are refilled over time.
+    assert_eq!(
+        rate_limiter.check_at(peer_id, &protocol, 1000, now + Duration::from_secs(3)),
+        RateLimitDecision::Accept
+    );
+}
+
+#[test]
+fn test_byte_limit() {
+    let rate_limiter = rate_limiter(0, 1000);
+    let peer_id = PeerId::random();
+    let protocol = ProtocolId::from_static(PROTOCOL);
+    let now = Instant::now();
+
+    assert_eq!(
+        rate_limiter.check_at(peer_id, &protocol, 1000, now),
+        RateLimitDecision::Accept
+    );
+    assert_eq!(
+        rate_limiter.check_at(peer_id, &protocol, 500, now),
+        RateLimitDecision::Delay(Duration::from_millis(500))
+    );
+    // Messages which can't get through within the max delay are dropped.
+    assert_eq!(
+        rate_limiter.check_at(peer_id, &protocol, 1000, now),
+        RateLimitDecision::Drop
+    );
+    assert_eq!(
+        rate_limiter.check_at(PeerId::random(), &protocol, 3000, now),
+        RateLimitDecision::Drop
+    );
+}
+
+#[test]
+fn test_disconnect() {
+    let rate_limiter = rate_limiter(1, 0);
+    let peer_id = PeerId::random();
+    let protocol = ProtocolId::from_static(PROTOCOL);
+    let now = Instant::now();
+
+    // The peer keeps sending 4 messages per second: one message per second gets through after a
+    // delay, the others are dropped, until the peer gets disconnected 5 seconds after the first
+    // drop.
+    let decisions: Vec<_> = (0..=22)
+        .map(|i| {
+            let at = now + Duration::from_millis(250 * i);
+            rate_limiter.check_at(peer_id, &protocol, 0, at)
+        })
+        .collect();
+    assert_eq!(decisions[0], RateLimitDecision::Accept);
+    assert_eq!(
+        decisions[1],
+        RateLimitDecision::Delay(Duration::from_millis(750))
+    );
+    assert_eq!(decisions[2], RateLimitDecision::Drop);
+    assert_eq!(decisions[3], RateLimitDecision::Drop);
+    assert_eq!(
+        decisions[4],
+        RateLimitDecision::Delay(Duration::from_secs(1))
+    );
+    assert_eq!(
+        decisions[20],
+        RateLimitDecision::Delay(Duration::from_secs(1))
+    );
+    assert_eq!(decisions[21], RateLimitDecision::Drop);
+    assert_eq!(decisions[22], RateLimitDecision::Disconnect);
+}
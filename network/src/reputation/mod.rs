@@ -0,0 +1,229 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reputation scores of the peers, and temporary bans of the misbehaving ones
+//!
+//! The upper layers report the behavior of the peers they talk to: a valid message raises the
+//! score of its sender a little, while an invalid signature, a malformed message or an rpc timeout
+//! lowers it, by an amount depending on the severity of the offense. Scores start at 0 and decay
+//! exponentially towards it, so that past behavior, good or bad, is eventually forgotten. A peer
+//! whose score drops to the ban threshold is banned for the configured duration: its connection
+//! is closed, and no connection to or from it is established until the ban expires.
+//!
+//! A single [`Reputation`] is shared by the [`PeerManager`](crate::peer_manager::PeerManager),
+//! which refuses the connections of banned peers, the
+//! [`ConnectivityManager`](crate::connectivity_manager::ConnectivityManager), which doesn't dial
+//! them, and the [`NetworkProvider`](crate::interface::NetworkProvider), which records the
+//! reports and answers the queries of the upper layers.
+use crate::counters;
+use libra_config::config::ReputationConfig;
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Scores can't grow above this, so that a peer can't bank good behavior to misbehave later.
+const MAX_SCORE: f64 = 100.0;
+
+/// Peers which aren't banned and whose score decayed this close to 0 are forgotten.
+const FORGOTTEN_SCORE: f64 = 1.0;
+
+/// How often the forgotten peers are swept.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Behavior of a peer, as reported by the upper layers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PeerBehavior {
+    /// The peer sent a valid message, or answered an rpc in time.
+    Good,
+    /// The peer sent a message whose signature doesn't verify.
+    InvalidSignature,
+    /// The peer sent a message which can't be deserialized, or is otherwise invalid.
+    MalformedMessage,
+    /// The peer didn't answer an rpc in time.
+    RpcTimeout,
+}
+
+impl PeerBehavior {
+    fn score_delta(self) -> f64 {
+        match self {
+            PeerBehavior::Good => 1.0,
+            PeerBehavior::InvalidSignature => -50.0,
+            PeerBehavior::MalformedMessage => -20.0,
+            PeerBehavior::RpcTimeout => -5.0,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PeerBehavior::Good => "good",
+            PeerBehavior::InvalidSignature => "invalid_signature",
+            PeerBehavior::MalformedMessage => "malformed_message",
+            PeerBehavior::RpcTimeout => "rpc_timeout",
+        }
+    }
+}
+
+/// The reputation of a peer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeerReputation {
+    pub score: f64,
+    /// How long the peer stays banned, if it is.
+    pub banned_for: Option<Duration>,
+}
+
+struct PeerState {
+    score: f64,
+    last_update: Instant,
+    banned_until: Option<Instant>,
+}
+
+impl PeerState {
+    fn new(now: Instant) -> Self {
+        Self {
+            score: 0.0,
+            last_update: now,
+            banned_until: None,
+        }
+    }
+
+    /// Decays the score towards 0 for the time elapsed since the last update, and lifts an
+    /// expired ban.
+    fn decay(&mut self, decay_half_life: Duration, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_update);
+        self.score *= 0.5f64.powf(elapsed.as_secs_f64() / decay_half_life.as_secs_f64());
+        self.last_update = now;
+        if self.banned_until.map_or(false, |until| until <= now) {
+            self.banned_until = None;
+        }
+    }
+
+    fn reputation(&self, now: Instant) -> PeerReputation {
+        PeerReputation {
+            score: self.score,
+            banned_for: self.banned_until.map(|until| until.duration_since(now)),
+        }
+    }
+}
+
+struct ReputationState {
+    peers: HashMap<PeerId, PeerState>,
+    last_sweep: Instant,
+}
+
+/// Reputation scores of the peers, and their bans.
+pub struct Reputation {
+    ban_threshold: f64,
+    ban_duration: Duration,
+    decay_half_life: Duration,
+    state: Mutex<ReputationState>,
+}
+
+impl Reputation {
+    pub fn new(config: &ReputationConfig) -> Self {
+        Self {
+            ban_threshold: config.ban_threshold as f64,
+            ban_duration: Duration::from_millis(config.ban_duration_ms),
+            // A zero half-life would make the decay a division by zero.
+            decay_half_life: Duration::from_millis(config.decay_half_life_ms.max(1)),
+            state: Mutex::new(ReputationState {
+                peers: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    /// Records the `behavior` of `peer_id`.
+    ///
+    /// Returns whether the peer just got banned, in which case its connection should be closed.
+    pub fn report(&self, peer_id: PeerId, behavior: PeerBehavior) -> bool {
+        self.report_at(peer_id, behavior, Instant::now())
+    }
+
+    fn report_at(&self, peer_id: PeerId, behavior: PeerBehavior, now: Instant) -> bool {
+        counters::LIBRA_NETWORK_PEER_REPORTS
+            .with_label_values(&[behavior.as_str()])
+            .inc();
+        let mut state = self.state.lock().unwrap();
+        self.sweep(&mut state, now);
+        let peer = state
+            .peers
+            .entry(peer_id)
+            .or_insert_with(|| PeerState::new(now));
+        peer.decay(self.decay_half_life, now);
+        if peer.banned_until.is_some() {
+            // Late reports about a banned peer don't extend its ban.
+            return false;
+        }
+        peer.score = (peer.score + behavior.score_delta()).min(MAX_SCORE);
+        counters::LIBRA_NETWORK_PEER_SCORE
+            .with_label_values(&[&peer_id.short_str()])
+            .set(peer.score as i64);
+        if peer.score > self.ban_threshold {
+            return false;
+        }
+        peer.banned_until = Some(now + self.ban_duration);
+        counters::LIBRA_NETWORK_BANNED_PEERS.inc();
+        true
+    }
+
+    /// Returns whether `peer_id` is currently banned.
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.is_banned_at(peer_id, Instant::now())
+    }
+
+    fn is_banned_at(&self, peer_id: &PeerId, now: Instant) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .peers
+            .get(peer_id)
+            .and_then(|peer| peer.banned_until)
+            .map_or(false, |until| now < until)
+    }
+
+    /// Returns the reputation of every peer reported recently enough not to be forgotten yet.
+    pub fn reputations(&self) -> HashMap<PeerId, PeerReputation> {
+        self.reputations_at(Instant::now())
+    }
+
+    fn reputations_at(&self, now: Instant) -> HashMap<PeerId, PeerReputation> {
+        let mut state = self.state.lock().unwrap();
+        self.sweep(&mut state, now);
+        state
+            .peers
+            .iter_mut()
+            .map(|(peer_id, peer)| {
+                peer.decay(self.decay_half_life, now);
+                counters::LIBRA_NETWORK_PEER_SCORE
+                    .with_label_values(&[&peer_id.short_str()])
+                    .set(peer.score as i64);
+                (*peer_id, peer.reputation(now))
+            })
+            .collect()
+    }
+
+    /// Forgets the peers which aren't banned and whose score decayed close enough to 0, along
+    /// with their score metric.
+    fn sweep(&self, state: &mut ReputationState, now: Instant) {
+        if now.saturating_duration_since(state.last_sweep) < SWEEP_INTERVAL {
+            return;
+        }
+        let decay_half_life = self.decay_half_life;
+        state.peers.retain(|peer_id, peer| {
+            peer.decay(decay_half_life, now);
+            let keep = peer.banned_until.is_some() || peer.score.abs() >= FORGOTTEN_SCORE;
+            if !keep {
+                // Peers whose short ids collide share the metric, which may be gone already.
+                let _ =
+                    counters::LIBRA_NETWORK_PEER_SCORE.remove_label_values(&[&peer_id.short_str()]);
+            }
+            keep
+        });
+        state.last_sweep = now;
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+fn reputation() -> Reputation {
+    Reputation::new(&ReputationConfig {
+        ban_threshold: -100,
+        ban_duration_ms: 120_000,
+        decay_half_life_ms: 10_000,
+    })
+}
+
+#[test]
+fn test_ban_on_threshold() {
+    let reputation = reputation();
+    let peer_id = PeerId::random();
+    let other_peer_id = PeerId::random();
+    let now = Instant::now();
+
+    assert!(!reputation.report_at(peer_id, PeerBehavior::InvalidSignature, now));
+    assert!(!reputation.is_banned_at(&peer_id, now));
+    // The second invalid signature brings the score down to the threshold.
+    assert!(reputation.report_at(peer_id, PeerBehavior::InvalidSignature, now));
+    assert!(reputation.is_banned_at(&peer_id, now));
+    // Further reports don't ban the peer again.
+    assert!(!reputation.report_at(peer_id, PeerBehavior::MalformedMessage, now));
+    // Other peers aren't affected.
+    assert!(!reputation.is_banned_at(&other_peer_id, now));
+
+    let reputations = reputation.reputations_at(now);
+    assert_eq!(
+        reputations.get(&peer_id),
+        Some(&PeerReputation {
+            score: -100.0,
+            banned_for: Some(Duration::from_secs(120)),
+        })
+    );
+    assert_eq!(reputations.get(&other_peer_id), None);
+}
+
+#[test]
+fn test_ban_expires() {
+    let reputation = reputation();
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    reputation.report_at(peer_id, PeerBehavior::InvalidSignature, now);
+    assert!(reputation.report_at(peer_id, PeerBehavior::InvalidSignature, now));
+    assert!(reputation.is_banned_at(&peer_id, now + Duration::from_secs(119)));
+    assert!(!reputation.is_banned_at(&peer_id, now + Duration::from_secs(120)));
+
+    // The score decayed back close to 0 meanwhile, so one more offense doesn't get the peer
+    // banned again.
+    let later = now + Duration::from_secs(120);
+    assert!(!reputation.report_at(peer_id, PeerBehavior::InvalidSignature, later));
+    let reputations = reputation.reputations_at(later);
+    let peer_reputation = reputations.get(&peer_id).unwrap();
+    assert!((peer_reputation.score + 50.0).abs() < 0.1);
+    assert_eq!(peer_reputation.banned_for, None);
+}
+
+#[test]
+fn test_score_decay() {
+    let reputation = reputation();
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    reputation.report_at(peer_id, PeerBehavior::MalformedMessage, now);
+    let score = |at| reputation.reputations_at(at).get(&peer_id).unwrap().score;
+    assert!((score(now + Duration::from_secs(10)) + 10.0).abs() < 1e-6);
+    assert!((score(now + Duration::from_secs(20)) + 5.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_max_score() {
+    let reputation = reputation();
+    let peer_id = PeerId::random();
+    let now = Instant::now();
+
+    // Good behavior can't offset more than a couple of invalid signatures.
+    for _ in 0..1000 {
+        reputation.report_at(peer_id, PeerBehavior::Good, now);
+    }
+    for _ in 0..3 {
+        reputation.report_at(peer_id, PeerBehavior::InvalidSignature, now);
+    }
+    assert!(!reputation.is_banned_at(&peer_id, now));
+    assert!(reputation.report_at(peer_id, PeerBehavior::InvalidSignature, now));
+}
+
+#[test]
+fn test_forget_idle_peers() {
+    let reputation = reputation();
+    let peer_id = PeerId::random();
+    let banned_peer_id = PeerId::random();
+    let now = Instant::now();
+
+    reputation.report_at(peer_id, PeerBehavior::RpcTimeout, now);
+    reputation.report_at(banned_peer_id, PeerBehavior::InvalidSignature, now);
+    reputation.report_at(banned_peer_id, PeerBehavior::InvalidSignature, now);
+
+    // After a sweep interval, the score of the first peer decayed close to 0, and it's forgotten,
+    // while the banned peer is kept.
+    let reputations = reputation.reputations_at(now + SWEEP_INTERVAL);
+    assert_eq!(reputations.get(&peer_id), None);
+    assert!(reputations.contains_key(&banned_peer_id));
+}
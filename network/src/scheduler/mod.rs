@@ -0,0 +1,237 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Priority scheduling of the outbound messages and rpcs of each peer
+//!
+//! All the substreams with a peer share a single connection, so a burst of messages of one
+//! protocol, e.g. mempool broadcasts or state sync chunks, could hold back the messages of the
+//! others, e.g. consensus votes and proposals. To prevent it, outbound messages wait for a
+//! [`Permit`] before being written to their substream. Only `MAX_IN_FLIGHT` messages are written
+//! to a peer at once: the others wait in one queue per [`PriorityClass`] of their protocol, and
+//! the queues of the higher classes are drained first. To protect the lower classes from
+//! starvation, a class passed over `MAX_SKIPS` times while it had waiting messages gets the next
+//! permit.
+//!
+//! The permits are requested by the [`DirectSend`](crate::protocols::direct_send::DirectSend) and
+//! [`Rpc`](crate::protocols::rpc::Rpc) actors, which share a single [`OutboundScheduler`].
+use crate::{
+    counters,
+    validator_network::{
+        ADMISSION_CONTROL_RPC_PROTOCOL, CONSENSUS_DIRECT_SEND_PROTOCOL, CONSENSUS_RPC_PROTOCOL,
+        HEALTH_CHECKER_RPC_PROTOCOL, MEMPOOL_DIRECT_SEND_PROTOCOL,
+        STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL,
+    },
+    ProtocolId,
+};
+use futures::channel::oneshot;
+use libra_types::PeerId;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+#[cfg(test)]
+mod test;
+
+/// The number of messages written to a peer at once. More than one, so that a substream stalled
+/// by flow control doesn't hold back the others.
+const MAX_IN_FLIGHT: usize = 4;
+/// A class with waiting messages gets the next permit after being passed over this many times.
+const MAX_SKIPS: usize = 8;
+const NUM_CLASSES: usize = 4;
+
+/// Priority classes of the protocols, from the highest to the lowest.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum PriorityClass {
+    Consensus,
+    StateSync,
+    Mempool,
+    Discovery,
+}
+
+impl PriorityClass {
+    const ALL: [PriorityClass; NUM_CLASSES] = [
+        PriorityClass::Consensus,
+        PriorityClass::StateSync,
+        PriorityClass::Mempool,
+        PriorityClass::Discovery,
+    ];
+
+    /// Returns the class of `protocol`. Unknown protocols get the lowest class.
+    pub fn of(protocol: &ProtocolId) -> Self {
+        match protocol.as_ref() {
+            // Health checks are latency sensitive too: late pings get peers disconnected.
+            CONSENSUS_RPC_PROTOCOL
+            | CONSENSUS_DIRECT_SEND_PROTOCOL
+            | HEALTH_CHECKER_RPC_PROTOCOL => PriorityClass::Consensus,
+            STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL => PriorityClass::StateSync,
+            MEMPOOL_DIRECT_SEND_PROTOCOL | ADMISSION_CONTROL_RPC_PROTOCOL => PriorityClass::Mempool,
+            // Discovery, and unknown protocols.
+            _ => PriorityClass::Discovery,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            PriorityClass::Consensus => "consensus",
+            PriorityClass::StateSync => "state_sync",
+            PriorityClass::Mempool => "mempool",
+            PriorityClass::Discovery => "discovery",
+        }
+    }
+}
+
+struct Waiter {
+    permit_tx: oneshot::Sender<Permit>,
+    enqueued_at: Instant,
+}
+
+#[derive(Default)]
+struct PeerQueues {
+    in_flight: usize,
+    queues: [VecDeque<Waiter>; NUM_CLASSES],
+    // How many times each class was passed over since its last permit
+    skips: [usize; NUM_CLASSES],
+}
+
+impl PeerQueues {
+    fn is_waiting(&self) -> bool {
+        self.queues.iter().any(|queue| !queue.is_empty())
+    }
+
+    /// Pops the next waiter: the first of the highest starved class if any, the first of the
+    /// highest class with waiters otherwise.
+    fn pop_next(&mut self) -> Option<(PriorityClass, Waiter)> {
+        let waiting: Vec<_> = PriorityClass::ALL
+            .iter()
+            .copied()
+            .filter(|class| !self.queues[class.index()].is_empty())
+            .collect();
+        let class = waiting
+            .iter()
+            .copied()
+            .find(|class| self.skips[class.index()] >= MAX_SKIPS)
+            .or_else(|| waiting.first().copied())?;
+        for other in waiting {
+            if other > class {
+                self.skips[other.index()] += 1;
+            }
+        }
+        self.skips[class.index()] = 0;
+        let waiter = self.queues[class.index()].pop_front()?;
+        Some((class, waiter))
+    }
+}
+
+/// The turn of an outbound message, which lasts until the permit is dropped.
+pub struct Permit {
+    scheduler: Option<OutboundScheduler>,
+    peer_id: PeerId,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(scheduler) = self.scheduler.take() {
+            scheduler.release(self.peer_id);
+        }
+    }
+}
+
+/// Priority queues of the outbound messages of each peer.
+#[derive(Clone, Default)]
+pub struct OutboundScheduler {
+    peers: Arc<Mutex<HashMap<PeerId, PeerQueues>>>,
+}
+
+impl OutboundScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for the turn of a message of `protocol` to `peer_id`. The message should be written
+    /// while holding the returned permit.
+    pub async fn acquire(&self, peer_id: PeerId, protocol: &ProtocolId) -> Permit {
+        let class = PriorityClass::of(protocol);
+        let permit_rx = {
+            let mut peers = self.peers.lock().unwrap();
+            let peer = peers.entry(peer_id).or_default();
+            if peer.in_flight < MAX_IN_FLIGHT && !peer.is_waiting() {
+                peer.in_flight += 1;
+                None
+            } else {
+                let (permit_tx, permit_rx) = oneshot::channel();
+                peer.queues[class.index()].push_back(Waiter {
+                    permit_tx,
+                    enqueued_at: Instant::now(),
+                });
+                counters::LIBRA_NETWORK_OUTBOUND_QUEUE_DEPTH
+                    .with_label_values(&[class.as_str()])
+                    .inc();
+                Some(permit_rx)
+            }
+        };
+        match permit_rx {
+            Some(permit_rx) => {
+                // The senders are only dropped after sending a permit.
+                permit_rx.await.expect("Permit sender dropped")
+            }
+            None => {
+                counters::LIBRA_NETWORK_OUTBOUND_QUEUEING_DELAY
+                    .with_label_values(&[class.as_str()])
+                    .observe(0.0);
+                Permit {
+                    scheduler: Some(self.clone()),
+                    peer_id,
+                }
+            }
+        }
+    }
+
+    /// Hands the permit of a message which was written to `peer_id` over to the next waiting
+    /// message, if any.
+    fn release(&self, peer_id: PeerId) {
+        loop {
+            let (class, waiter) = {
+                let mut peers = self.peers.lock().unwrap();
+                let peer = match peers.get_mut(&peer_id) {
+                    Some(peer) => peer,
+                    None => return,
+                };
+                match peer.pop_next() {
+                    Some((class, waiter)) => {
+                        counters::LIBRA_NETWORK_OUTBOUND_QUEUE_DEPTH
+                            .with_label_values(&[class.as_str()])
+                            .dec();
+                        (class, waiter)
+                    }
+                    None => {
+                        peer.in_flight -= 1;
+                        if peer.in_flight == 0 {
+                            peers.remove(&peer_id);
+                        }
+                        return;
+                    }
+                }
+            };
+            let permit = Permit {
+                scheduler: Some(self.clone()),
+                peer_id,
+            };
+            match waiter.permit_tx.send(permit) {
+                Ok(()) => {
+                    counters::LIBRA_NETWORK_OUTBOUND_QUEUEING_DELAY
+                        .with_label_values(&[class.as_str()])
+                        .observe(waiter.enqueued_at.elapsed().as_secs_f64());
+                    return;
+                }
+                // The message was given up on while waiting: try the next one.
+                Err(mut permit) => permit.scheduler = None,
+            }
+        }
+    }
+}
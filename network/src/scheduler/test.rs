@@ -0,0 +1,127 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::validator_network::DISCOVERY_DIRECT_SEND_PROTOCOL;
+use futures::{executor::block_on, pin_mut, poll};
+
+fn waiter() -> Waiter {
+    let (permit_tx, _) = oneshot::channel();
+    Waiter {
+        permit_tx,
+        enqueued_at: Instant::now(),
+    }
+}
+
+#[test]
+fn test_priority_class() {
+    assert_eq!(
+        PriorityClass::of(&ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)),
+        PriorityClass::Consensus
+    );
+    assert_eq!(
+        PriorityClass::of(&ProtocolId::from_static(HEALTH_CHECKER_RPC_PROTOCOL)),
+        PriorityClass::Consensus
+    );
+    assert_eq!(
+        PriorityClass::of(&ProtocolId::from_static(
+            STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL
+        )),
+        PriorityClass::StateSync
+    );
+    assert_eq!(
+        PriorityClass::of(&ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL)),
+        PriorityClass::Mempool
+    );
+    assert_eq!(
+        PriorityClass::of(&ProtocolId::from_static(DISCOVERY_DIRECT_SEND_PROTOCOL)),
+        PriorityClass::Discovery
+    );
+    assert_eq!(
+        PriorityClass::of(&ProtocolId::from_static(b"/libra/unknown/0.1.0")),
+        PriorityClass::Discovery
+    );
+}
+
+#[test]
+fn test_priority_order() {
+    let mut peer = PeerQueues::default();
+    for class in &[
+        PriorityClass::Discovery,
+        PriorityClass::Mempool,
+        PriorityClass::StateSync,
+        PriorityClass::Consensus,
+    ] {
+        peer.queues[class.index()].push_back(waiter());
+    }
+    let order: Vec<_> = std::iter::from_fn(|| peer.pop_next().map(|(class, _)| class)).collect();
+    assert_eq!(order, PriorityClass::ALL.to_vec());
+}
+
+#[test]
+fn test_starvation() {
+    let mut peer = PeerQueues::default();
+    for _ in 0..2 * MAX_SKIPS {
+        peer.queues[PriorityClass::Consensus.index()].push_back(waiter());
+    }
+    peer.queues[PriorityClass::Mempool.index()].push_back(waiter());
+    peer.queues[PriorityClass::Discovery.index()].push_back(waiter());
+
+    // The lower classes get a permit once they were passed over MAX_SKIPS times, the higher of
+    // them first.
+    let order: Vec<_> = std::iter::from_fn(|| peer.pop_next().map(|(class, _)| class)).collect();
+    assert_eq!(order.len(), 2 * MAX_SKIPS + 2);
+    assert!(order[..MAX_SKIPS]
+        .iter()
+        .all(|class| *class == PriorityClass::Consensus));
+    assert_eq!(order[MAX_SKIPS], PriorityClass::Mempool);
+    assert_eq!(order[MAX_SKIPS + 1], PriorityClass::Discovery);
+    assert!(order[MAX_SKIPS + 2..]
+        .iter()
+        .all(|class| *class == PriorityClass::Consensus));
+}
+
+#[test]
+fn test_acquire() {
+    let scheduler = OutboundScheduler::new();
+    let peer_id = PeerId::random();
+    let consensus = ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL);
+    let mempool = ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL);
+
+    block_on(async {
+        // Messages are written right away up to the limit.
+        let mut permits = vec![];
+        for _ in 0..MAX_IN_FLIGHT {
+            permits.push(scheduler.acquire(peer_id, &mempool).await);
+        }
+        // Other peers aren't affected.
+        scheduler.acquire(PeerId::random(), &mempool).await;
+
+        // Beyond the limit, messages wait for their turn, by priority.
+        let mempool_permit = scheduler.acquire(peer_id, &mempool);
+        let consensus_permit = scheduler.acquire(peer_id, &consensus);
+        pin_mut!(mempool_permit, consensus_permit);
+        assert!(poll!(mempool_permit.as_mut()).is_pending());
+        assert!(poll!(consensus_permit.as_mut()).is_pending());
+        permits.pop();
+        assert!(poll!(mempool_permit.as_mut()).is_pending());
+        let consensus_permit = consensus_permit.await;
+        permits.pop();
+        let _mempool_permit = mempool_permit.await;
+
+        // Messages given up on while waiting don't hold back the others.
+        let cancelled = scheduler.acquire(peer_id, &consensus);
+        let next_permit = scheduler.acquire(peer_id, &mempool);
+        {
+            pin_mut!(cancelled);
+            assert!(poll!(cancelled.as_mut()).is_pending());
+        }
+        pin_mut!(next_permit);
+        assert!(poll!(next_permit.as_mut()).is_pending());
+        drop(consensus_permit);
+        next_permit.await;
+    });
+
+    // Idle peers are forgotten.
+    assert!(scheduler.peers.lock().unwrap().is_empty());
+}
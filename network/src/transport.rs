@@ -5,6 +5,10 @@ use crate::{
     common::NetworkPublicKeys,
     protocols::identity::{exchange_identity, Identity},
 };
+use futures::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    stream::StreamExt,
+};
 use libra_crypto::{
     x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
     ValidKey,
@@ -13,7 +17,11 @@ use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::{
     multiplexing::{yamux::Yamux, StreamMultiplexer},
-    transport::{boxed, memory, tcp, TransportExt},
+    transport::{
+        boxed, memory,
+        quic::{QuicConnection, QuicTransport},
+        tcp, ConnectionOrigin, TransportExt,
+    },
 };
 use noise::NoiseConfig;
 use std::{
@@ -271,6 +279,96 @@ pub fn build_permissionless_tcp_noise_transport(
         .boxed()
 }
 
+/// Authenticates the peer of a QUIC connection with its network identity key, by running a Noise
+/// handshake over the first substream of the connection. Both sides then send each other, over the
+/// Noise channel, the server certificate they saw during the QUIC handshake: a mismatch means that
+/// someone is relaying the connection.
+async fn authenticate_quic_connection(
+    noise_config: &NoiseConfig,
+    connection: QuicConnection,
+    origin: ConnectionOrigin,
+) -> io::Result<(Vec<u8>, QuicConnection)> {
+    let substream = match origin {
+        ConnectionOrigin::Inbound => {
+            connection
+                .listen_for_inbound()
+                .next()
+                .await
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "Connection closed by remote",
+                    )
+                })??
+        }
+        ConnectionOrigin::Outbound => connection.open_outbound().await?,
+    };
+    let (remote_static_key, mut socket) =
+        noise_config.upgrade_connection(substream, origin).await?;
+
+    let certificate = connection.server_certificate();
+    socket
+        .write_all(&(certificate.len() as u16).to_be_bytes())
+        .await?;
+    socket.write_all(certificate).await?;
+    socket.flush().await?;
+    let mut len = [0u8; 2];
+    socket.read_exact(&mut len).await?;
+    let mut remote_certificate = vec![0u8; u16::from_be_bytes(len) as usize];
+    socket.read_exact(&mut remote_certificate).await?;
+    if remote_certificate != certificate {
+        security_log(SecurityEvent::InvalidNetworkPeer)
+            .error("QuicCertificateMismatch")
+            .data(&remote_static_key)
+            .log();
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "QUIC server certificate mismatch",
+        ));
+    }
+    socket.close().await?;
+    Ok((remote_static_key, connection))
+}
+
+// Transport based on QUIC, whose peers are authenticated with Noise as with the TCP + Noise
+// transport.
+pub fn build_quic_noise_transport(
+    own_identity: Identity,
+    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
+    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
+
+    QuicTransport::new()
+        .expect("Unable to set up the QUIC transport")
+        .and_then(move |connection, origin| {
+            async move {
+                let (remote_static_key, muxer) =
+                    authenticate_quic_connection(&noise_config, connection, origin).await?;
+                if let Some(peer_id) = identity_key_to_peer_id(&trusted_peers, &remote_static_key) {
+                    Ok((peer_id, muxer))
+                } else {
+                    security_log(SecurityEvent::InvalidNetworkPeer)
+                        .error("UntrustedPeer")
+                        .data(&trusted_peers)
+                        .data(&remote_static_key)
+                        .log();
+                    Err(io::Error::new(io::ErrorKind::Other, "Not a trusted peer"))
+                }
+            }
+        })
+        .and_then(move |(peer_id, muxer), origin| {
+            async move {
+                let (identity, muxer) = exchange_identity(&own_identity, muxer, origin).await?;
+                match_peer_id(identity, peer_id)
+                    .and_then(|identity| check_role(&own_identity, identity))
+                    .and_then(|identity| Ok((identity, muxer)))
+            }
+        })
+        .with_timeout(TRANSPORT_TIMEOUT)
+        .boxed()
+}
+
 pub fn build_tcp_transport(
     own_identity: Identity,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
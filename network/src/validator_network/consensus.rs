@@ -8,12 +8,12 @@ use crate::{
     interface::NetworkRequest,
     proto::{ConsensusMsg, ConsensusMsg_oneof, RequestBlock, RespondBlock},
     protocols::rpc::error::RpcError,
-    validator_network::{NetworkEvents, NetworkSender},
+    validator_network::{NetworkEvents, NetworkSender, PeerBehavior, PeerReputation},
     NetworkPublicKeys, ProtocolId,
 };
 use channel;
 use libra_types::{validator_public_keys::ValidatorPublicKeys, PeerId};
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 /// Protocol id for consensus RPC calls
 pub const CONSENSUS_RPC_PROTOCOL: &[u8] = b"/libra/consensus/rpc/0.1.0";
@@ -112,6 +112,20 @@ impl ConsensusNetworkSender {
             )
             .await
     }
+
+    pub async fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner.report_peer(peer, behavior).await
+    }
+
+    pub async fn peer_reputations(
+        &mut self,
+    ) -> Result<HashMap<PeerId, PeerReputation>, NetworkError> {
+        self.inner.peer_reputations().await
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,6 @@
 
 //! Network API for [`Consensus`](/consensus/index.html) and [`Mempool`](/mempool/index.html)
 
-pub use crate::protocols::rpc::error::RpcError;
 use crate::{
     common::NetworkPublicKeys,
     error::NetworkError,
@@ -12,6 +11,10 @@ use crate::{
     utils::MessageExt,
     ProtocolId,
 };
+pub use crate::{
+    protocols::rpc::error::RpcError,
+    reputation::{PeerBehavior, PeerReputation},
+};
 use bytes::Bytes;
 use futures::{
     channel::oneshot,
@@ -312,6 +315,30 @@ impl<TMessage: Message + Default> NetworkSender<TMessage> {
         Ok(res_rx.await??)
     }
 
+    /// Report the behavior of the peer with the given `PeerId`. Peers whose
+    /// reputation gets too bad are disconnected and temporarily banned.
+    pub async fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send(NetworkRequest::ReportPeer(peer, behavior))
+            .await?;
+        Ok(())
+    }
+
+    /// Get the reputation of the recently reported peers.
+    pub async fn peer_reputations(
+        &mut self,
+    ) -> Result<HashMap<PeerId, PeerReputation>, NetworkError> {
+        let (res_tx, res_rx) = oneshot::channel();
+        self.inner
+            .send(NetworkRequest::GetPeerReputations(res_tx))
+            .await?;
+        Ok(res_rx.await?)
+    }
+
     /// Unwrap the `NetworkSender` into the underlying
     /// `channel::Sender<NetworkRequest>`.
     pub fn into_inner(self) -> channel::Sender<NetworkRequest> {
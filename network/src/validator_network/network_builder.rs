@@ -11,6 +11,7 @@
 //! set.
 use crate::{
     common::NetworkPublicKeys,
+    compression::{self, Compression},
     connectivity_manager::ConnectivityManager,
     counters,
     interface::{LibraNetworkProvider, NetworkProvider},
@@ -20,13 +21,16 @@ use crate::{
         direct_send::DirectSend, discovery::Discovery, health_checker::HealthChecker,
         identity::Identity, rpc::Rpc,
     },
+    rate_limiter::RateLimiter,
+    reputation::Reputation,
+    scheduler::OutboundScheduler,
     transport::*,
     validator_network::{DISCOVERY_DIRECT_SEND_PROTOCOL, HEALTH_CHECKER_RPC_PROTOCOL},
     ProtocolId,
 };
 use channel;
 use futures::StreamExt;
-use libra_config::config::RoleType;
+use libra_config::config::{CompressionConfig, RateLimitConfig, ReputationConfig, RoleType};
 use libra_crypto::{
     ed25519::*,
     x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
@@ -58,7 +62,7 @@ pub const MAX_CONCURRENT_NETWORK_REQS: u32 = 100;
 pub const MAX_CONCURRENT_NETWORK_NOTIFS: u32 = 100;
 pub const MAX_CONNECTION_DELAY_MS: u64 = 10 * 60 * 1000 /* 10 minutes */;
 
-/// The type of the transport layer, i.e., running on memory or TCP stream,
+/// The type of the transport layer, i.e., running on memory, TCP stream or QUIC,
 /// with or without Noise encryption
 pub enum TransportType {
     Memory,
@@ -67,6 +71,7 @@ pub enum TransportType {
     Tcp,
     TcpNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
     PermissionlessTcpNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
+    QuicNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
 }
 
 /// Build Network module with custom configuration values.
@@ -101,6 +106,9 @@ pub struct NetworkBuilder {
     signing_keys: Option<(Ed25519PrivateKey, Ed25519PublicKey)>,
     is_permissioned: bool,
     health_checker_enabled: bool,
+    rate_limit: RateLimitConfig,
+    compression: CompressionConfig,
+    reputation: ReputationConfig,
 }
 
 impl NetworkBuilder {
@@ -138,6 +146,9 @@ impl NetworkBuilder {
             signing_keys: None,
             is_permissioned: true,
             health_checker_enabled: true,
+            rate_limit: RateLimitConfig::default(),
+            compression: CompressionConfig::default(),
+            reputation: ReputationConfig::default(),
         }
     }
 
@@ -293,11 +304,50 @@ impl NetworkBuilder {
         self
     }
 
-    fn supported_protocols(&self) -> Vec<ProtocolId> {
-        self.direct_send_protocols
+    /// Set the limits of the inbound DirectSend messages and rpcs of each peer.
+    pub fn rate_limit(&mut self, rate_limit: RateLimitConfig) -> &mut Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Set the protocols whose DirectSend messages and rpcs are compressed.
+    pub fn compression(&mut self, compression: CompressionConfig) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the score threshold, duration and decay of the bans of misbehaving peers.
+    pub fn reputation(&mut self, reputation: ReputationConfig) -> &mut Self {
+        self.reputation = reputation;
+        self
+    }
+
+    /// Returns the given protocols along with their compressed variants, if compression is
+    /// enabled for them.
+    fn with_compressed_protocols(
+        protocols: &[ProtocolId],
+        compression: &Compression,
+    ) -> Vec<ProtocolId> {
+        protocols
             .iter()
-            .chain(&self.rpc_protocols)
             .cloned()
+            .chain(
+                protocols
+                    .iter()
+                    .filter(|protocol| compression.is_enabled(protocol))
+                    .map(compression::compressed_protocol),
+            )
+            .collect()
+    }
+
+    fn supported_protocols(&self) -> Vec<ProtocolId> {
+        let compression = Compression::new(&self.compression);
+        Self::with_compressed_protocols(&self.direct_send_protocols, &compression)
+            .into_iter()
+            .chain(Self::with_compressed_protocols(
+                &self.rpc_protocols,
+                &compression,
+            ))
             .collect()
     }
 
@@ -351,6 +401,10 @@ impl NetworkBuilder {
                 let keys = keys.take().expect("Identity keys not set");
                 self.build_with_transport(build_permissionless_tcp_noise_transport(identity, keys))
             }
+            TransportType::QuicNoise(ref mut keys) => {
+                let keys = keys.take().expect("Identity keys not set");
+                self.build_with_transport(build_quic_noise_transport(identity, keys, trusted_peers))
+            }
         }
     }
 
@@ -370,15 +424,24 @@ impl NetworkBuilder {
         let (pm_reqs_tx, pm_reqs_rx) =
             channel::new(self.channel_size, &counters::PENDING_PEER_MANAGER_REQUESTS);
 
+        // DirectSend and RPC actors share the limits of each peer.
+        let rate_limiter = Arc::new(RateLimiter::new(&self.rate_limit));
+        let compression = Arc::new(Compression::new(&self.compression));
+        // They also share the priority queues of the outbound messages to each peer.
+        let scheduler = OutboundScheduler::new();
+        // The bans of misbehaving peers are shared by the actors establishing connections and the
+        // network provider, which records the reports of their behavior.
+        let reputation = Arc::new(Reputation::new(&self.reputation));
+
         // Initialize and start DirectSend actor.
         let (pm_ds_notifs_tx, pm_ds_notifs_rx) = channel::new(
             self.channel_size,
             &counters::PENDING_PEER_MANAGER_DIRECT_SEND_NOTIFICATIONS,
         );
-        let direct_send_handlers = self
-            .direct_send_protocols
-            .iter()
-            .map(|p| (p.clone(), pm_ds_notifs_tx.clone()));
+        let direct_send_handlers =
+            Self::with_compressed_protocols(&self.direct_send_protocols, &compression)
+                .into_iter()
+                .map(|p| (p, pm_ds_notifs_tx.clone()));
         protocol_handlers.extend(direct_send_handlers);
         let (ds_reqs_tx, ds_reqs_rx) =
             channel::new(self.channel_size, &counters::PENDING_DIRECT_SEND_REQUESTS);
@@ -392,6 +455,9 @@ impl NetworkBuilder {
             ds_net_notifs_tx,
             pm_ds_notifs_rx,
             PeerManagerRequestSender::new(pm_reqs_tx.clone()),
+            Arc::clone(&rate_limiter),
+            Arc::clone(&compression),
+            scheduler.clone(),
         );
         self.executor.spawn(ds.start());
         debug!("Started direct send actor");
@@ -401,10 +467,9 @@ impl NetworkBuilder {
             self.channel_size,
             &counters::PENDING_PEER_MANAGER_RPC_NOTIFICATIONS,
         );
-        let rpc_handlers = self
-            .rpc_protocols
-            .iter()
-            .map(|p| (p.clone(), pm_rpc_notifs_tx.clone()));
+        let rpc_handlers = Self::with_compressed_protocols(&self.rpc_protocols, &compression)
+            .into_iter()
+            .map(|p| (p, pm_rpc_notifs_tx.clone()));
         protocol_handlers.extend(rpc_handlers);
         let (rpc_net_notifs_tx, rpc_net_notifs_rx) =
             channel::new(self.channel_size, &counters::PENDING_RPC_NOTIFICATIONS);
@@ -419,6 +484,9 @@ impl NetworkBuilder {
             Duration::from_millis(self.inbound_rpc_timeout_ms),
             self.max_concurrent_outbound_rpcs,
             self.max_concurrent_inbound_rpcs,
+            rate_limiter,
+            compression,
+            scheduler,
         );
         self.executor.spawn(rpc.start());
         debug!("Started RPC actor");
@@ -447,6 +515,7 @@ impl NetworkBuilder {
                 conn_mgr_reqs_rx,
                 ExponentialBackoff::from_millis(2).factor(1000 /* seconds */),
                 self.max_connection_delay_ms,
+                Arc::clone(&reputation),
             );
             self.executor.spawn(conn_mgr.start());
             debug!("Started connection manager");
@@ -466,6 +535,7 @@ impl NetworkBuilder {
             pm_reqs_rx,
             protocol_handlers,
             peer_event_handlers,
+            Arc::clone(&reputation),
         );
         let listen_addr = peer_mgr.listen_addr().clone();
         self.executor.spawn(peer_mgr.start());
@@ -482,6 +552,7 @@ impl NetworkBuilder {
             ds_reqs_tx,
             ds_net_notifs_rx,
             net_conn_mgr_reqs_tx.clone(),
+            reputation,
             network_reqs_rx,
             network_reqs_tx,
             self.max_concurrent_network_reqs,
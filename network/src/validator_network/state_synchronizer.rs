@@ -7,11 +7,12 @@ use crate::{
     error::NetworkError,
     interface::NetworkRequest,
     proto::StateSynchronizerMsg,
-    validator_network::{NetworkEvents, NetworkSender},
+    validator_network::{NetworkEvents, NetworkSender, PeerBehavior, PeerReputation},
     ProtocolId,
 };
 use channel;
 use libra_types::PeerId;
+use std::collections::HashMap;
 
 /// Protocol id for state-synchronizer direct-send calls
 pub const STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL: &[u8] =
@@ -54,6 +55,20 @@ impl StateSynchronizerSender {
         let protocol = ProtocolId::from_static(STATE_SYNCHRONIZER_DIRECT_SEND_PROTOCOL);
         self.inner.send_to(recipient, protocol, message).await
     }
+
+    pub async fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner.report_peer(peer, behavior).await
+    }
+
+    pub async fn peer_reputations(
+        &mut self,
+    ) -> Result<HashMap<PeerId, PeerReputation>, NetworkError> {
+        self.inner.peer_reputations().await
+    }
 }
 
 #[cfg(test)]
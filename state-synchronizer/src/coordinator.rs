@@ -22,7 +22,7 @@ use libra_types::{
 };
 use network::{
     proto::{GetChunkRequest, GetChunkResponse, StateSynchronizerMsg, StateSynchronizerMsg_oneof},
-    validator_network::{Event, StateSynchronizerEvents, StateSynchronizerSender},
+    validator_network::{Event, PeerBehavior, StateSynchronizerEvents, StateSynchronizerSender},
 };
 use std::{
     collections::HashMap,
@@ -60,7 +60,9 @@ pub(crate) enum CoordinatorMessage {
 /// The coordinator can work in two modes:
 /// * FullNode: infinite stream of ChunkRequests is sent to the predefined static peers
 /// (the parent is going to reply with a ChunkResponse if its committed version becomes
-/// higher within the timeout interval).
+/// higher within the timeout interval). A full node following consensus as an observer only
+/// falls back to this stream when the observer stops committing blocks, e.g. because no
+/// proposals arrive.
 /// * Validator: the ChunkRequests are generated on demand for a specific target LedgerInfo to
 /// synchronize to.
 pub(crate) struct SyncCoordinator<T> {
@@ -80,6 +82,9 @@ pub(crate) struct SyncCoordinator<T> {
     peer_manager: PeerManager,
     // Optional sync request to be called when the target sync is reached
     sync_request: Option<SyncRequest>,
+    // Time of the last commit notified by consensus, which only happens on full nodes following
+    // consensus as observers
+    last_consensus_commit: Option<SystemTime>,
     // queue of incoming long polling requests
     // peer will be notified about new chunk of transactions if it's available before expiry time
     // value format is (expiration_time, known_version, limit)
@@ -121,6 +126,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             peer_manager: PeerManager::new(upstream_peers),
             subscriptions: HashMap::new(),
             sync_request: None,
+            last_consensus_commit: None,
             executor_proxy,
         }
     }
@@ -153,6 +159,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                             }
                         }
                         CoordinatorMessage::Commit => {
+                            self.last_consensus_commit = Some(SystemTime::now());
                             if let Err(e) = self.process_commit().await {
                                 error!("[state sync] process commit fail: {}", e);
                             }
@@ -243,8 +250,11 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             );
         }
 
-        self.peer_manager
-            .set_peers(request.target.signatures().keys().copied().collect());
+        // Full nodes keep syncing from their upstream peers rather than from the validators.
+        if self.role == RoleType::Validator {
+            self.peer_manager
+                .set_peers(self.executor_proxy.get_signers(&request.target));
+        }
         self.sync_request = Some(request);
         self.send_chunk_request(self.local_state.highest_version_in_local_storage())
             .await
@@ -396,10 +406,13 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         counters::RESPONSES_RECEIVED
             .with_label_values(&[&*peer_id.to_string()])
             .inc();
-        let txn_list_with_proof: TransactionListWithProof = response
+        let txn_list_with_proof: Result<TransactionListWithProof> = response
             .txn_list_with_proof
-            .ok_or_else(|| format_err!("Missing txn_list_with_proof"))?
-            .try_into()?;
+            .ok_or_else(|| format_err!("Missing txn_list_with_proof"))
+            .and_then(|txn_list_with_proof| txn_list_with_proof.try_into());
+        let txn_list_with_proof = self
+            .report_on_error(peer_id, txn_list_with_proof, PeerBehavior::MalformedMessage)
+            .await?;
 
         let known_version = self.local_state.highest_version_in_local_storage();
         let chunk_start_version =
@@ -422,10 +435,13 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                 chunk_start_version
             );
         }
-        let response_li: LedgerInfoWithSignatures = response
+        let response_li: Result<LedgerInfoWithSignatures> = response
             .ledger_info_with_sigs
-            .ok_or_else(|| format_err!("Missing ledger_info_with_sigs"))?
-            .try_into()?;
+            .ok_or_else(|| format_err!("Missing ledger_info_with_sigs"))
+            .and_then(|response_li| response_li.try_into());
+        let response_li = self
+            .report_on_error(peer_id, response_li, PeerBehavior::MalformedMessage)
+            .await?;
 
         if let Some(sync_req) = self.sync_request.as_ref() {
             // Valid responses should not exceed the LI version of the request.
@@ -446,7 +462,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         let new_version = known_version + chunk_size;
         self.send_chunk_request(new_version).await?;
 
-        self.validate_and_store_chunk(txn_list_with_proof, response_li)
+        self.validate_and_store_chunk(peer_id, txn_list_with_proof, response_li)
             .await
             .map_err(|e| {
                 self.peer_manager
@@ -472,14 +488,41 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
 
     async fn validate_and_store_chunk(
         &mut self,
+        peer_id: &PeerId,
         txn_list_with_proof: TransactionListWithProof,
         target: LedgerInfoWithSignatures,
     ) -> Result<()> {
-        self.executor_proxy.validate_ledger_info(&target)?;
+        let validation = self.executor_proxy.validate_ledger_info(&target);
+        // A ledger info of another epoch than the local validators' can't be told apart from a
+        // forged one, so the peer isn't blamed for it.
+        if target.ledger_info().epoch() == self.executor_proxy.verifier_epoch() {
+            self.report_on_error(peer_id, validation, PeerBehavior::InvalidSignature)
+                .await?;
+        } else {
+            validation?;
+        }
+        // Execution failures may well be local ones (e.g. of the storage).
         self.executor_proxy
             .execute_chunk(txn_list_with_proof, target)
-            .await?;
-        Ok(())
+            .await
+    }
+
+    /// Reports `behavior` of `peer_id` to the network if `result` is an error, which is passed
+    /// through.
+    async fn report_on_error<R>(
+        &self,
+        peer_id: &PeerId,
+        result: Result<R>,
+        behavior: PeerBehavior,
+    ) -> Result<R> {
+        if result.is_err() {
+            if let Some(mut network_sender) = self.peer_manager.get_network_sender(peer_id) {
+                if let Err(e) = network_sender.report_peer(*peer_id, behavior).await {
+                    warn!("[state sync] Failed to report peer {}: {:?}", peer_id, e);
+                }
+            }
+        }
+        result
     }
 
     /// Ensures that StateSynchronizer is making progress:
@@ -488,7 +531,9 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         if self.peer_manager.is_empty() {
             return;
         }
-        if self.role == RoleType::Validator && self.sync_request.is_none() {
+        if self.sync_request.is_none()
+            && (self.role == RoleType::Validator || self.is_observer_committing())
+        {
             return;
         }
 
@@ -511,8 +556,21 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         }
     }
 
+    /// Returns whether the observer of a full node committed a block recently enough for the full
+    /// node not to poll its upstream peers.
+    fn is_observer_committing(&self) -> bool {
+        let observer_timeout = Duration::from_millis(self.config.observer_timeout_ms);
+        self.last_consensus_commit.map_or(false, |commit_time| {
+            commit_time
+                .elapsed()
+                .map(|elapsed| elapsed < observer_timeout)
+                .unwrap_or(true)
+        })
+    }
+
     /// Sends a chunk request with a given `known_version` (might be chosen optimistically).
-    /// The request includes a target for Validator and a non-zero timeout for a FullNode.
+    /// The request includes the target of the sync request if any, which Validators always have,
+    /// and a non-zero timeout for a FullNode otherwise.
     async fn send_chunk_request(&mut self, known_version: u64) -> Result<()> {
         let (peer_id, mut sender) = self
             .peer_manager
@@ -522,15 +580,8 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         let mut req = GetChunkRequest::default();
         req.known_version = known_version;
         req.limit = self.config.chunk_limit;
-        if self.role == RoleType::Validator {
-            let target = self
-                .sync_request
-                .as_ref()
-                .ok_or_else(|| {
-                    format_err!("[state sync] Validator chunk request without a sync request.")
-                })?
-                .target
-                .clone();
+        if let Some(sync_request) = self.sync_request.as_ref() {
+            let target = sync_request.target.clone();
             if target.ledger_info().version() <= known_version {
                 debug!(
                     "[state sync] Reached version {}, no need to send more requests",
@@ -539,6 +590,8 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                 return Ok(());
             }
             req.ledger_info_with_sigs = Some(target.into());
+        } else if self.role == RoleType::Validator {
+            bail!("[state sync] Validator chunk request without a sync request.");
         } else {
             req.timeout = self.config.long_poll_timeout_ms;
         }
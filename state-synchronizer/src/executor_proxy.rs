@@ -8,6 +8,7 @@ use futures::{channel::oneshot, Future, FutureExt};
 use grpcio::EnvBuilder;
 use libra_config::config::NodeConfig;
 use libra_types::{
+    account_address::AccountAddress,
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeEventWithProof, ValidatorVerifier},
     transaction::TransactionListWithProof,
 };
@@ -39,6 +40,12 @@ pub trait ExecutorProxyTrait: Sync + Send {
 
     fn validate_ledger_info(&self, target: &LedgerInfoWithSignatures) -> Result<()>;
 
+    /// Epoch of the validators `validate_ledger_info` checks the signatures against
+    fn verifier_epoch(&self) -> u64;
+
+    /// Returns the validators who signed the target ledger info
+    fn get_signers(&self, target: &LedgerInfoWithSignatures) -> Vec<AccountAddress>;
+
     fn get_epoch_proof(&self, start_epoch: u64) -> Result<ValidatorChangeEventWithProof>;
 }
 
@@ -46,6 +53,7 @@ pub(crate) struct ExecutorProxy {
     storage_read_client: Arc<StorageReadServiceClient>,
     executor: Arc<Executor<MoveVM>>,
     validator_verifier: ValidatorVerifier,
+    verifier_epoch: u64,
 }
 
 impl ExecutorProxy {
@@ -57,10 +65,23 @@ impl ExecutorProxy {
             config.storage.port,
         ));
         let validator_verifier = config.consensus.consensus_peers.get_validator_verifier();
+        // The configured validators are the ones of the epoch following the latest reconfiguration
+        let verifier_epoch = storage_read_client
+            .get_startup_info()
+            .ok()
+            .and_then(|startup_info| startup_info)
+            .map_or(0, |startup_info| {
+                startup_info
+                    .ledger_info_with_validators
+                    .ledger_info()
+                    .epoch()
+                    + 1
+            });
         Self {
             storage_read_client,
             executor,
             validator_verifier,
+            verifier_epoch,
         }
     }
 }
@@ -77,7 +98,7 @@ fn convert_to_future<T: Send + 'static>(
             }
         }
     }
-        .boxed()
+    .boxed()
 }
 
 impl ExecutorProxyTrait for ExecutorProxy {
@@ -99,7 +120,7 @@ impl ExecutorProxyTrait for ExecutorProxy {
                 highest_synced_version,
             })
         }
-            .boxed()
+        .boxed()
     }
 
     fn execute_chunk(
@@ -125,7 +146,7 @@ impl ExecutorProxyTrait for ExecutorProxy {
                 .get_transactions_async(known_version + 1, limit, target_version, false)
                 .await
         }
-            .boxed()
+        .boxed()
     }
 
     fn validate_ledger_info(&self, target: &LedgerInfoWithSignatures) -> Result<()> {
@@ -133,6 +154,14 @@ impl ExecutorProxyTrait for ExecutorProxy {
         Ok(())
     }
 
+    fn verifier_epoch(&self) -> u64 {
+        self.verifier_epoch
+    }
+
+    fn get_signers(&self, target: &LedgerInfoWithSignatures) -> Vec<AccountAddress> {
+        target.signers(&self.validator_verifier)
+    }
+
     fn get_epoch_proof(&self, start_epoch: u64) -> Result<ValidatorChangeEventWithProof> {
         let ledger_info_per_epoch = self
             .storage_read_client
@@ -119,7 +119,7 @@ impl ExecutorProxyTrait for MockExecutorProxy {
                 highest_synced_version,
             })
         }
-            .boxed()
+        .boxed()
     }
 
     fn execute_chunk(
@@ -146,6 +146,14 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         Ok(())
     }
 
+    fn verifier_epoch(&self) -> u64 {
+        0
+    }
+
+    fn get_signers(&self, target: &LedgerInfoWithSignatures) -> Vec<AccountAddress> {
+        target.signatures().keys().copied().collect()
+    }
+
     fn get_epoch_proof(&self, _start_epoch: u64) -> Result<ValidatorChangeEventWithProof> {
         unimplemented!("get epoch proof not supported for mock executor proxy");
     }
@@ -340,3 +348,12 @@ fn test_full_node() {
     // after receiving first chunk immediately
     assert!(env.wait_for_version(0, 20));
 }
+
+#[test]
+fn test_full_node_sync_request() {
+    // Full nodes following consensus as observers hand over to state sync for specific targets
+    // when they miss blocks, without giving up their upstream peers.
+    let env = SynchronizerEnv::new(SynchronizerEnv::default_handler(), RoleType::FullNode);
+    env.sync_to(0, 5);
+    assert!(env.wait_for_version(0, 5));
+}
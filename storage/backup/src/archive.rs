@@ -0,0 +1,192 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the on-disk format of a backup.
+//!
+//! A backup is a directory holding a [`BackupManifest`] and a number of chunk files. Every file
+//! starts with a magic number and the format version, followed by an LCS-encoded payload. The
+//! manifest records the hash of every chunk file, so a corrupted or misplaced chunk is detected
+//! before it is decoded.
+//!
+//! ```text
+//! |<- 8 bytes ->|<-- 4 bytes -->|<------ variable ------>|
+//! |    magic    | format version |      LCS payload       |
+//! ```
+
+use failure::prelude::*;
+use libra_crypto::HashValue;
+use libra_types::{
+    account_state_blob::AccountStateBlob,
+    contract_event::ContractEvent,
+    crypto_proxies::LedgerInfoWithSignatures,
+    proof::{SparseMerkleRangeProof, TransactionAccumulatorRangeProof, TransactionListProof},
+    transaction::{Transaction, TransactionInfo, TransactionListWithProof, Version},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{convert::TryFrom, fs, path::Path};
+
+/// The format version written by this crate. Bumped on every incompatible change to any of the
+/// structures below.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Name of the manifest file in a backup directory.
+pub const MANIFEST_FILE_NAME: &str = "MANIFEST";
+
+/// Name of the file holding a standalone copy of the ledger info the backup is taken at, so that
+/// operators can keep it apart from the backup and pass it back as the trusted ledger info on
+/// restore.
+pub const LEDGER_INFO_FILE_NAME: &str = "LEDGER_INFO";
+
+const MAGIC: &[u8; 8] = b"LIBRABAK";
+const HEADER_LEN: usize = 12;
+
+/// Describes a backup and lists the chunk files it consists of.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BackupManifest {
+    /// The ledger info the backup is taken at. Everything in the backup is proven against it.
+    pub ledger_info: LedgerInfoWithSignatures,
+    /// The ledger infos persisted from `first_version` up to the one above, in ascending order of
+    /// epoch. They are only restored if they chain up to it.
+    pub prior_ledger_infos: Vec<LedgerInfoWithSignatures>,
+    /// The version of the first backed up transaction.
+    pub first_version: Version,
+    /// Frozen subtree root hashes of the transaction accumulator right before `first_version`.
+    pub frozen_subtree_hashes: Vec<HashValue>,
+    /// Chunks of transactions, in ascending order of version.
+    pub transaction_chunks: Vec<ChunkEntry>,
+    /// Chunks of account states at the version of `ledger_info`, in ascending order of key.
+    pub account_state_chunks: Vec<ChunkEntry>,
+}
+
+/// A chunk file and the hash of its content.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChunkEntry {
+    pub file_name: String,
+    pub hash: HashValue,
+}
+
+/// A chunk of consecutive transactions together with their infos, events and the accumulator
+/// range proof.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransactionChunk {
+    pub first_version: Version,
+    pub transactions: Vec<Transaction>,
+    pub events: Vec<Vec<ContractEvent>>,
+    pub transaction_infos: Vec<TransactionInfo>,
+    pub proof_left_siblings: Vec<HashValue>,
+    pub proof_right_siblings: Vec<HashValue>,
+}
+
+impl TryFrom<TransactionListWithProof> for TransactionChunk {
+    type Error = Error;
+
+    fn try_from(txn_list_with_proof: TransactionListWithProof) -> Result<Self> {
+        let first_version = txn_list_with_proof
+            .first_transaction_version
+            .ok_or_else(|| format_err!("Transaction list is empty."))?;
+        let events = txn_list_with_proof
+            .events
+            .ok_or_else(|| format_err!("Transaction list doesn't carry events."))?;
+        let range_proof = txn_list_with_proof
+            .proof
+            .ledger_info_to_transaction_infos_proof();
+
+        Ok(Self {
+            first_version,
+            transactions: txn_list_with_proof.transactions,
+            events,
+            proof_left_siblings: range_proof.left_siblings().to_vec(),
+            proof_right_siblings: range_proof.right_siblings().to_vec(),
+            transaction_infos: txn_list_with_proof.proof.transaction_infos().to_vec(),
+        })
+    }
+}
+
+impl From<TransactionChunk> for TransactionListWithProof {
+    fn from(chunk: TransactionChunk) -> Self {
+        TransactionListWithProof::new(
+            chunk.transactions,
+            Some(chunk.events),
+            Some(chunk.first_version),
+            TransactionListProof::new(
+                TransactionAccumulatorRangeProof::new(
+                    chunk.proof_left_siblings,
+                    chunk.proof_right_siblings,
+                ),
+                chunk.transaction_infos,
+            ),
+        )
+    }
+}
+
+/// A chunk of account states in ascending order of key, together with the proof that all accounts
+/// up to and including the last one exist in the state tree.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AccountStateChunk {
+    pub accounts: Vec<(HashValue, AccountStateBlob)>,
+    pub proof_siblings: Vec<HashValue>,
+}
+
+impl AccountStateChunk {
+    pub fn new(
+        accounts: Vec<(HashValue, AccountStateBlob)>,
+        proof: &SparseMerkleRangeProof,
+    ) -> Self {
+        Self {
+            accounts,
+            proof_siblings: proof.siblings().to_vec(),
+        }
+    }
+
+    pub fn into_inner(self) -> (Vec<(HashValue, AccountStateBlob)>, SparseMerkleRangeProof) {
+        (
+            self.accounts,
+            SparseMerkleRangeProof::new(self.proof_siblings),
+        )
+    }
+}
+
+/// Writes `payload` to `path` and returns the hash of the file content.
+pub fn write_file<T: Serialize>(path: &Path, payload: &T) -> Result<HashValue> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+    bytes.extend(lcs::to_bytes(payload)?);
+
+    fs::write(path, &bytes)?;
+    Ok(HashValue::from_sha3_256(&bytes))
+}
+
+/// Reads and decodes the file at `path`. If `expected_hash` is given, the file content is checked
+/// against it before being decoded.
+pub fn read_file<T: DeserializeOwned>(path: &Path, expected_hash: Option<HashValue>) -> Result<T> {
+    let bytes = fs::read(path)?;
+    if let Some(expected_hash) = expected_hash {
+        let hash = HashValue::from_sha3_256(&bytes);
+        ensure!(
+            hash == expected_hash,
+            "Hash of {:?} is {:x}, expected {:x}.",
+            path,
+            hash,
+            expected_hash,
+        );
+    }
+
+    ensure!(
+        bytes.len() >= HEADER_LEN && bytes[..MAGIC.len()] == MAGIC[..],
+        "{:?} is not a backup file.",
+        path,
+    );
+    let mut format_version = [0u8; 4];
+    format_version.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+    let format_version = u32::from_be_bytes(format_version);
+    ensure!(
+        format_version == FORMAT_VERSION,
+        "{:?} has format version {}, only version {} is supported.",
+        path,
+        format_version,
+        FORMAT_VERSION,
+    );
+
+    Ok(lcs::from_bytes(&bytes[HEADER_LEN..])?)
+}
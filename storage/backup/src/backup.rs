@@ -0,0 +1,121 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module writes a backup of a [`LibraDB`] to a directory.
+
+use crate::archive::{
+    self, AccountStateChunk, BackupManifest, ChunkEntry, TransactionChunk, LEDGER_INFO_FILE_NAME,
+    MANIFEST_FILE_NAME,
+};
+use failure::prelude::*;
+use libra_logger::prelude::*;
+use libra_types::transaction::Version;
+use libradb::LibraDB;
+use std::{convert::TryFrom, fs, path::Path};
+
+/// Knobs of a backup.
+#[derive(Clone, Debug)]
+pub struct BackupOptions {
+    /// The version to back up at. It must be the version of a persisted ledger info, i.e. the
+    /// last version of an epoch or the latest version. Defaults to the latest version.
+    pub version: Option<Version>,
+    /// The version of the first transaction to back up.
+    pub first_version: Version,
+    /// Maximum number of transactions in a chunk file.
+    pub transaction_chunk_size: u64,
+    /// Maximum number of accounts in a chunk file.
+    pub account_chunk_size: usize,
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        Self {
+            version: None,
+            first_version: 0,
+            transaction_chunk_size: 1000,
+            account_chunk_size: 10000,
+        }
+    }
+}
+
+/// Backs up `db` into `dir`, which is created if it doesn't exist. The manifest is written last,
+/// so a backup that didn't run to completion can't be restored from.
+pub fn backup(db: &LibraDB, dir: &Path, opts: &BackupOptions) -> Result<BackupManifest> {
+    ensure!(
+        opts.transaction_chunk_size > 0 && opts.account_chunk_size > 0,
+        "Chunk sizes must be positive.",
+    );
+    fs::create_dir_all(dir)?;
+
+    let ledger_info = match opts.version {
+        Some(version) => db.get_ledger_info_by_version(version)?,
+        None => {
+            db.get_startup_info()?
+                .ok_or_else(|| format_err!("DB is not bootstrapped."))?
+                .ledger_info
+        }
+    };
+    let ledger_version = ledger_info.ledger_info().version();
+    ensure!(
+        opts.first_version <= ledger_version,
+        "First version {} is beyond the version to back up at: {}.",
+        opts.first_version,
+        ledger_version,
+    );
+    archive::write_file(&dir.join(LEDGER_INFO_FILE_NAME), &ledger_info)?;
+
+    let mut transaction_chunks = Vec::new();
+    let mut version = opts.first_version;
+    while version <= ledger_version {
+        let chunk = TransactionChunk::try_from(db.get_transactions(
+            version,
+            opts.transaction_chunk_size,
+            ledger_version,
+            true, /* fetch_events */
+        )?)?;
+        let num_txns = chunk.transactions.len() as u64;
+        let file_name = format!("transaction_{}.chunk", version);
+        let hash = archive::write_file(&dir.join(&file_name), &chunk)?;
+        transaction_chunks.push(ChunkEntry { file_name, hash });
+        version += num_txns;
+    }
+    info!(
+        "Backed up transactions [{}, {}] in {} chunks.",
+        opts.first_version,
+        ledger_version,
+        transaction_chunks.len()
+    );
+
+    let mut account_state_chunks = Vec::new();
+    let mut accounts = Vec::with_capacity(opts.account_chunk_size);
+    let mut account_iter = db.get_account_iter(ledger_version)?.peekable();
+    while let Some(res) = account_iter.next() {
+        accounts.push(res?);
+        if accounts.len() == opts.account_chunk_size || account_iter.peek().is_none() {
+            let (rightmost_key, _) = accounts.last().expect("Chunk should not be empty.");
+            let proof = db.get_account_state_range_proof(*rightmost_key, ledger_version)?;
+            let chunk =
+                AccountStateChunk::new(std::mem::replace(&mut accounts, Vec::new()), &proof);
+            let file_name = format!("account_state_{}.chunk", account_state_chunks.len());
+            let hash = archive::write_file(&dir.join(&file_name), &chunk)?;
+            account_state_chunks.push(ChunkEntry { file_name, hash });
+        }
+    }
+    info!(
+        "Backed up account states at version {} in {} chunks.",
+        ledger_version,
+        account_state_chunks.len()
+    );
+
+    let manifest = BackupManifest {
+        prior_ledger_infos: db.get_ledger_infos_between(opts.first_version, ledger_version)?,
+        first_version: opts.first_version,
+        frozen_subtree_hashes: db.get_frozen_subtree_hashes_before(opts.first_version)?,
+        ledger_info,
+        transaction_chunks,
+        account_state_chunks,
+    };
+    archive::write_file(&dir.join(MANIFEST_FILE_NAME), &manifest)?;
+
+    Ok(manifest)
+}
@@ -0,0 +1,56 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    archive::{self, BackupManifest, LEDGER_INFO_FILE_NAME, MANIFEST_FILE_NAME},
+    backup::{backup, BackupOptions},
+    restore::restore,
+};
+use libra_tools::tempdir::TempPath;
+use libra_types::crypto_proxies::LedgerInfoWithSignatures;
+use libradb::{mock_genesis::db_with_mock_genesis, LibraDB};
+use std::fs;
+
+#[test]
+fn test_backup_and_restore() {
+    let src_tmp_dir = TempPath::new();
+    let src_db = db_with_mock_genesis(&src_tmp_dir).unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let manifest = backup(&src_db, backup_dir.path(), &BackupOptions::default()).unwrap();
+    assert_eq!(manifest.transaction_chunks.len(), 1);
+
+    let trusted_ledger_info: LedgerInfoWithSignatures =
+        archive::read_file(&backup_dir.path().join(LEDGER_INFO_FILE_NAME), None).unwrap();
+    let tgt_tmp_dir = TempPath::new();
+    let tgt_db = LibraDB::new(&tgt_tmp_dir);
+    restore(&tgt_db, backup_dir.path(), &trusted_ledger_info).unwrap();
+
+    assert_eq!(
+        tgt_db.get_startup_info().unwrap(),
+        src_db.get_startup_info().unwrap()
+    );
+}
+
+#[test]
+fn test_restore_rejects_corrupted_chunk() {
+    let src_tmp_dir = TempPath::new();
+    let src_db = db_with_mock_genesis(&src_tmp_dir).unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    backup(&src_db, backup_dir.path(), &BackupOptions::default()).unwrap();
+
+    let manifest: BackupManifest =
+        archive::read_file(&backup_dir.path().join(MANIFEST_FILE_NAME), None).unwrap();
+    let chunk_path = backup_dir
+        .path()
+        .join(&manifest.account_state_chunks[0].file_name);
+    let mut bytes = fs::read(&chunk_path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    fs::write(&chunk_path, bytes).unwrap();
+
+    let tgt_tmp_dir = TempPath::new();
+    let tgt_db = LibraDB::new(&tgt_tmp_dir);
+    assert!(restore(&tgt_db, backup_dir.path(), &manifest.ledger_info).is_err());
+}
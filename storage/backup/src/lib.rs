@@ -0,0 +1,18 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This crate implements backing up a [`LibraDB`](libradb::LibraDB) to a directory of portable
+//! archive files, and rebuilding a `LibraDB` from such a directory.
+//!
+//! A backup is taken at a version for which a `LedgerInfoWithSignatures` exists. It holds the
+//! transactions (with their infos and events) from a chosen first version up to that version, the
+//! account states at that version, and the ledger infos of earlier epochs. Everything is streamed
+//! chunk by chunk in both directions, so neither side needs to hold the whole ledger in memory.
+//! See [`archive`] for the file format.
+
+pub mod archive;
+pub mod backup;
+pub mod restore;
+
+#[cfg(test)]
+mod backup_test;
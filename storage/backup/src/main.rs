@@ -0,0 +1,132 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Command line tool to back up a LibraDB to archive files and to restore a LibraDB from them.
+//!
+//! A backup is never taken from a live DB. It is read from a checkpoint, which is either given
+//! directly or created on the fly by the storage service of a running node.
+
+use grpcio::EnvBuilder;
+use libra_types::crypto_proxies::LedgerInfoWithSignatures;
+use libradb::LibraDB;
+use libradb_backup::{
+    archive,
+    backup::{backup, BackupOptions},
+    restore::restore,
+};
+use std::{path::PathBuf, sync::Arc};
+use storage_client::StorageWriteServiceClient;
+use storage_service::StorageService;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Tool to back up and restore a LibraDB")]
+enum Command {
+    /// Back up a LibraDB to a directory of archive files
+    #[structopt(name = "backup")]
+    Backup {
+        /// Directory of the checkpoint to back up, or with --storage-host, the storage directory
+        /// of the running node
+        #[structopt(long = "db-dir", parse(from_os_str))]
+        db_dir: PathBuf,
+        /// Host of the storage service of a running node, which is asked to create the checkpoint
+        /// to back up
+        #[structopt(long = "storage-host")]
+        storage_host: Option<String>,
+        /// Port of the storage service of a running node
+        #[structopt(long = "storage-port", default_value = "6184")]
+        storage_port: u16,
+        /// Name of the checkpoint the storage service creates
+        #[structopt(long = "checkpoint-name", default_value = "backup")]
+        checkpoint_name: String,
+        /// Directory to write the backup to
+        #[structopt(long = "backup-dir", parse(from_os_str))]
+        backup_dir: PathBuf,
+        /// Version to back up at, which must end an epoch or be the latest (default: latest)
+        #[structopt(long = "version")]
+        version: Option<u64>,
+        /// Version of the first transaction to back up
+        #[structopt(long = "first-version", default_value = "0")]
+        first_version: u64,
+        /// Maximum number of transactions in a chunk file
+        #[structopt(long = "transaction-chunk-size", default_value = "1000")]
+        transaction_chunk_size: u64,
+        /// Maximum number of accounts in a chunk file
+        #[structopt(long = "account-chunk-size", default_value = "10000")]
+        account_chunk_size: usize,
+    },
+    /// Restore an empty LibraDB from a backup directory
+    #[structopt(name = "restore")]
+    Restore {
+        /// Directory of the backup to restore from
+        #[structopt(long = "backup-dir", parse(from_os_str))]
+        backup_dir: PathBuf,
+        /// Directory of the DB to restore into
+        #[structopt(long = "db-dir", parse(from_os_str))]
+        db_dir: PathBuf,
+        /// File holding the trusted ledger info, obtained independently of the backup
+        #[structopt(long = "trusted-ledger-info", parse(from_os_str))]
+        trusted_ledger_info: PathBuf,
+    },
+}
+
+fn main() {
+    ::libra_logger::init_for_e2e_testing();
+
+    match Command::from_args() {
+        Command::Backup {
+            db_dir,
+            storage_host,
+            storage_port,
+            checkpoint_name,
+            backup_dir,
+            version,
+            first_version,
+            transaction_chunk_size,
+            account_chunk_size,
+        } => {
+            let checkpoint_dir = match storage_host {
+                Some(host) => {
+                    let client = StorageWriteServiceClient::new(
+                        Arc::new(EnvBuilder::new().name_prefix("grpc-backup-").build()),
+                        &host,
+                        storage_port,
+                        None,
+                    );
+                    let ledger_info = client
+                        .create_checkpoint(checkpoint_name.clone())
+                        .expect("Failed to create checkpoint.");
+                    println!("Created checkpoint {} at {}.", checkpoint_name, ledger_info);
+                    db_dir
+                        .join(StorageService::CHECKPOINT_DIR_NAME)
+                        .join(&checkpoint_name)
+                }
+                None => db_dir,
+            };
+            let db = LibraDB::open_readonly(checkpoint_dir).expect("Failed to open checkpoint.");
+            let opts = BackupOptions {
+                version,
+                first_version,
+                transaction_chunk_size,
+                account_chunk_size,
+            };
+            let manifest = backup(&db, &backup_dir, &opts).expect("Backup failed.");
+            println!(
+                "Backed up at {} into {:?}.",
+                manifest.ledger_info, backup_dir
+            );
+        }
+        Command::Restore {
+            backup_dir,
+            db_dir,
+            trusted_ledger_info,
+        } => {
+            let trusted_ledger_info: LedgerInfoWithSignatures =
+                archive::read_file(&trusted_ledger_info, None)
+                    .expect("Failed to read trusted ledger info.");
+            let db = LibraDB::new(db_dir);
+            restore(&db, &backup_dir, &trusted_ledger_info).expect("Restore failed.");
+            println!("Restored {:?} at {}.", backup_dir, trusted_ledger_info);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module rebuilds a [`LibraDB`] from a backup directory.
+
+use crate::archive::{
+    self, AccountStateChunk, BackupManifest, TransactionChunk, MANIFEST_FILE_NAME,
+};
+use failure::prelude::*;
+use libra_logger::prelude::*;
+use libra_types::{
+    crypto_proxies::LedgerInfoWithSignatures, transaction::TransactionListWithProof,
+};
+use libradb::LibraDB;
+use std::path::Path;
+
+/// Restores the backup in `dir` into `db`, which must be empty.
+///
+/// Every chunk is checked against its hash in the manifest, then verified against
+/// `trusted_ledger_info` before it is written. `trusted_ledger_info` must come from a source
+/// other than the backup itself, and the backup must have been taken at exactly it.
+pub fn restore(
+    db: &LibraDB,
+    dir: &Path,
+    trusted_ledger_info: &LedgerInfoWithSignatures,
+) -> Result<()> {
+    ensure!(
+        db.get_startup_info()?.is_none(),
+        "Can only restore into an empty DB."
+    );
+
+    let manifest: BackupManifest = archive::read_file(&dir.join(MANIFEST_FILE_NAME), None)?;
+    ensure!(
+        manifest.ledger_info == *trusted_ledger_info,
+        "The backup is taken at {}, not at the trusted ledger info {}.",
+        manifest.ledger_info,
+        trusted_ledger_info,
+    );
+    let ledger_version = trusted_ledger_info.ledger_info().version();
+
+    if manifest.first_version > 0 {
+        db.restore_frozen_subtrees(manifest.first_version, &manifest.frozen_subtree_hashes)?;
+    }
+
+    let mut state_root_hash = None;
+    for entry in &manifest.transaction_chunks {
+        let chunk: TransactionChunk =
+            archive::read_file(&dir.join(&entry.file_name), Some(entry.hash))?;
+        let txn_list_with_proof = TransactionListWithProof::from(chunk);
+        db.restore_transactions(trusted_ledger_info, &txn_list_with_proof)?;
+        state_root_hash = txn_list_with_proof
+            .proof
+            .transaction_infos()
+            .last()
+            .map(|txn_info| txn_info.state_root_hash());
+    }
+    let state_root_hash =
+        state_root_hash.ok_or_else(|| format_err!("The backup carries no transactions."))?;
+    info!(
+        "Restored transactions [{}, {}].",
+        manifest.first_version, ledger_version
+    );
+
+    db.restore_ledger_infos(trusted_ledger_info, &manifest.prior_ledger_infos)?;
+
+    let mut state_restore = db.get_state_snapshot_restore(ledger_version, state_root_hash)?;
+    for entry in &manifest.account_state_chunks {
+        let chunk: AccountStateChunk =
+            archive::read_file(&dir.join(&entry.file_name), Some(entry.hash))?;
+        let (accounts, proof) = chunk.into_inner();
+        state_restore.add_chunk(accounts, proof)?;
+    }
+    state_restore.finish()?;
+    info!("Restored account states at version {}.", ledger_version);
+
+    db.finish_restore(trusted_ledger_info)
+}
@@ -0,0 +1,348 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides the APIs used by the backup tool to read a consistent snapshot of the
+//! ledger out of a [`LibraDB`](crate::LibraDB), and to rebuild a `LibraDB` from such a snapshot.
+//!
+//! All reads are made at a fixed version. The backup tool makes them from a checkpoint opened via
+//! [`LibraDB::open_readonly`](crate::LibraDB::open_readonly), so nothing else touches the DB while
+//! the snapshot is read. On the restore side, nothing is written to the DB before being verified
+//! against a trusted [`LedgerInfoWithSignatures`].
+
+#[cfg(test)]
+mod test;
+
+use crate::{
+    change_set::ChangeSet,
+    errors::LibraDbError,
+    schema::{
+        ledger_info::LedgerInfoSchema, transaction_accumulator::TransactionAccumulatorSchema,
+        transaction_info::TransactionInfoSchema,
+    },
+    state_store::StateStore,
+    LibraDB,
+};
+use failure::prelude::*;
+use itertools::{izip, zip_eq};
+use jellyfish_merkle::restore::JellyfishMerkleRestore;
+use libra_crypto::{hash::TransactionAccumulatorHasher, HashValue};
+use libra_types::{
+    account_state_blob::AccountStateBlob,
+    crypto_proxies::LedgerInfoWithSignatures,
+    proof::{
+        accumulator::InMemoryAccumulator, position::FrozenSubTreeIterator, SparseMerkleRangeProof,
+    },
+    transaction::{TransactionListWithProof, Version},
+};
+use schemadb::{ReadOptions, SchemaBatch};
+
+impl LibraDB {
+    // ================================== Backup APIs ==================================
+    /// Returns the ledger info that certifies the ledger at exactly `version`. Ledger infos are
+    /// only persisted for the last version of each epoch and for the latest version, so only those
+    /// versions can be backed up.
+    pub fn get_ledger_info_by_version(&self, version: Version) -> Result<LedgerInfoWithSignatures> {
+        let mut iter = self.db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        for res in iter {
+            let (_epoch, ledger_info_with_sigs) = res?;
+            if ledger_info_with_sigs.ledger_info().version() == version {
+                return Ok(ledger_info_with_sigs);
+            }
+        }
+        Err(LibraDbError::NotFound(format!("LedgerInfo at version {}", version)).into())
+    }
+
+    /// Returns all persisted ledger infos with version in `[first_version, ledger_version)`, in
+    /// ascending order of epoch.
+    pub fn get_ledger_infos_between(
+        &self,
+        first_version: Version,
+        ledger_version: Version,
+    ) -> Result<Vec<LedgerInfoWithSignatures>> {
+        let mut iter = self.db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        let mut result = Vec::new();
+        for res in iter {
+            let (_epoch, ledger_info_with_sigs) = res?;
+            let version = ledger_info_with_sigs.ledger_info().version();
+            if version >= ledger_version {
+                break;
+            }
+            if version >= first_version {
+                result.push(ledger_info_with_sigs);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns an iterator that yields all account state blobs in the state tree at `version`, in
+    /// ascending order of hashed account address.
+    pub fn get_account_iter(
+        &self,
+        version: Version,
+    ) -> Result<Box<dyn Iterator<Item = Result<(HashValue, AccountStateBlob)>> + '_>> {
+        Ok(Box::new(
+            self.state_store.iter_accounts(version, HashValue::zero())?,
+        ))
+    }
+
+    /// Returns the proof that all accounts up to and including `rightmost_key` exist in the state
+    /// tree at `version`.
+    pub fn get_account_state_range_proof(
+        &self,
+        rightmost_key: HashValue,
+        version: Version,
+    ) -> Result<SparseMerkleRangeProof> {
+        self.state_store
+            .get_account_state_range_proof(rightmost_key, version)
+    }
+
+    /// Returns the frozen subtree root hashes of the transaction accumulator right before
+    /// `version` is appended, from left to right. These are what's needed to restore the
+    /// transactions starting from `version` without restoring the ones before it.
+    pub fn get_frozen_subtree_hashes_before(&self, version: Version) -> Result<Vec<HashValue>> {
+        if version == 0 {
+            Ok(Vec::new())
+        } else {
+            self.ledger_store
+                .get_ledger_frozen_subtree_hashes(version - 1)
+        }
+    }
+
+    // ================================== Restore APIs ==================================
+    /// Starts restoring the state tree at `version`, whose root hash is expected to be
+    /// `expected_root_hash`. If a previous restoration of the same version was interrupted, it
+    /// picks up from where it stopped.
+    pub fn get_state_snapshot_restore(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<StateSnapshotRestore> {
+        Ok(StateSnapshotRestore {
+            inner: JellyfishMerkleRestore::new(&self.state_store, version, expected_root_hash)?,
+        })
+    }
+
+    /// Writes the frozen subtree root hashes of a transaction accumulator with `num_leaves`
+    /// leaves, so that transactions can be restored starting from version `num_leaves`.
+    ///
+    /// The hashes can't be verified on their own. They are covered by the root hash check in
+    /// [`finish_restore`](LibraDB::finish_restore).
+    pub fn restore_frozen_subtrees(
+        &self,
+        num_leaves: u64,
+        frozen_subtree_hashes: &[HashValue],
+    ) -> Result<()> {
+        let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
+        ensure!(
+            positions.len() == frozen_subtree_hashes.len(),
+            "Wrong number of frozen subtrees for {} leaves. Expected: {}, got: {}.",
+            num_leaves,
+            positions.len(),
+            frozen_subtree_hashes.len(),
+        );
+
+        let mut batch = SchemaBatch::new();
+        zip_eq(positions, frozen_subtree_hashes)
+            .map(|(position, hash)| batch.put::<TransactionAccumulatorSchema>(&position, hash))
+            .collect::<Result<Vec<()>>>()?;
+        self.db.write_schemas(batch)
+    }
+
+    /// Verifies a chunk of transactions against `ledger_info_with_sigs` and writes the
+    /// transactions, their infos and events. Chunks must be restored in ascending order of
+    /// version without gaps.
+    pub fn restore_transactions(
+        &self,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+        txn_list_with_proof: &TransactionListWithProof,
+    ) -> Result<()> {
+        let first_version = txn_list_with_proof
+            .first_transaction_version
+            .ok_or_else(|| format_err!("Can't restore an empty transaction chunk."))?;
+        let events = txn_list_with_proof
+            .events
+            .as_ref()
+            .ok_or_else(|| format_err!("Transaction chunk doesn't carry events."))?;
+        txn_list_with_proof.verify(ledger_info_with_sigs.ledger_info(), Some(first_version))?;
+
+        if let Some((latest_version, _)) = self.ledger_store.get_latest_transaction_info_option()? {
+            ensure!(
+                first_version == latest_version + 1,
+                "Transaction chunk not applicable: first_version {}, latest restored version {}",
+                first_version,
+                latest_version,
+            );
+        }
+
+        let mut cs = ChangeSet::new();
+        izip!(first_version.., &txn_list_with_proof.transactions, events)
+            .map(|(version, txn, events)| {
+                self.transaction_store
+                    .put_transaction(version, txn, &mut cs)?;
+                self.event_store.put_events(version, events, &mut cs)?;
+                Ok(())
+            })
+            .collect::<Result<()>>()?;
+        self.ledger_store.put_transaction_infos(
+            first_version,
+            txn_list_with_proof.proof.transaction_infos(),
+            &mut cs,
+        )?;
+        self.db.write_schemas(cs.batch)
+    }
+
+    /// Writes the ledger infos ending the epochs before the one of the trusted
+    /// `ledger_info_with_sigs`, in ascending order of epoch.
+    ///
+    /// Nothing is written unless the whole chain checks out. Every ledger info must be covered by
+    /// the restored transaction accumulator, which the trusted ledger info vouches for, so the
+    /// first one is verified too. The chain must start at genesis if the restored transactions do,
+    /// and each ledger info, up to and including the trusted one, must follow the previous epoch
+    /// and be signed by the validator set the previous one hands over to.
+    pub fn restore_ledger_infos(
+        &self,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        let first = match ledger_infos.first() {
+            Some(first) => first.ledger_info(),
+            None => return Ok(()),
+        };
+        let first_restored_version = self.get_first_restored_version()?;
+        ensure!(
+            first.version() >= first_restored_version,
+            "LedgerInfo at epoch {} precedes the restored transactions, so it can't be verified.",
+            first.epoch(),
+        );
+        ensure!(
+            first_restored_version > 0 || first.epoch() == 0,
+            "The restored transactions start at genesis while the LedgerInfos start at epoch {}.",
+            first.epoch(),
+        );
+        for prior in ledger_infos {
+            self.ensure_accumulator_root_matches(prior)?;
+        }
+
+        let successors = ledger_infos[1..]
+            .iter()
+            .chain(std::iter::once(ledger_info_with_sigs));
+        for (prev, next) in ledger_infos.iter().zip(successors) {
+            let (prev, next_li) = (prev.ledger_info(), next.ledger_info());
+            let validator_set = prev.next_validator_set().ok_or_else(|| {
+                format_err!(
+                    "LedgerInfo at epoch {} doesn't end the epoch.",
+                    prev.epoch()
+                )
+            })?;
+            ensure!(
+                next_li.epoch() == prev.epoch() + 1,
+                "LedgerInfo at epoch {} doesn't follow epoch {}.",
+                next_li.epoch(),
+                prev.epoch(),
+            );
+            next.verify(&validator_set.into())?;
+        }
+
+        let mut cs = ChangeSet::new();
+        ledger_infos
+            .iter()
+            .map(|prior| self.ledger_store.put_ledger_info(prior, &mut cs))
+            .collect::<Result<()>>()?;
+        self.db.write_schemas(cs.batch)
+    }
+
+    /// Finishes a restoration by checking that the restored ledger ends exactly at the version of
+    /// the trusted `ledger_info_with_sigs` and has the same transaction accumulator root hash, and
+    /// that the state tree at that version has been fully restored. `ledger_info_with_sigs` then
+    /// becomes the latest ledger info.
+    pub fn finish_restore(&self, ledger_info_with_sigs: &LedgerInfoWithSignatures) -> Result<()> {
+        let ledger_version = ledger_info_with_sigs.ledger_info().version();
+        let (latest_version, txn_info) = self.ledger_store.get_latest_transaction_info()?;
+        ensure!(
+            latest_version == ledger_version,
+            "Restored transactions end at version {} while the ledger info is at version {}.",
+            latest_version,
+            ledger_version,
+        );
+        self.ensure_accumulator_root_matches(ledger_info_with_sigs)?;
+
+        let state_root_hash = self.state_store.get_root_hash(ledger_version)?;
+        ensure!(
+            state_root_hash == txn_info.state_root_hash(),
+            "Restored state root hash {:x} doesn't match {:x} in TransactionInfo.",
+            state_root_hash,
+            txn_info.state_root_hash(),
+        );
+
+        let mut cs = ChangeSet::new();
+        self.ledger_store
+            .put_ledger_info(ledger_info_with_sigs, &mut cs)?;
+        self.db.write_schemas(cs.batch)?;
+        self.ledger_store
+            .set_latest_ledger_info(ledger_info_with_sigs.clone());
+
+        Ok(())
+    }
+
+    /// Returns the version of the first restored `TransactionInfo`, or `Version::max_value()` if
+    /// no transaction has been restored yet.
+    fn get_first_restored_version(&self) -> Result<Version> {
+        let mut iter = self
+            .db
+            .iter::<TransactionInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        Ok(iter
+            .next()
+            .transpose()?
+            .map_or(Version::max_value(), |(version, _)| version))
+    }
+
+    fn ensure_accumulator_root_matches(
+        &self,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        let num_leaves = ledger_info.version() + 1;
+        let root_hash = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            self.ledger_store
+                .get_ledger_frozen_subtree_hashes(ledger_info.version())?,
+            num_leaves,
+        )?
+        .root_hash();
+        ensure!(
+            root_hash == ledger_info.transaction_accumulator_hash(),
+            "Restored transaction accumulator root hash {:x} doesn't match {:x} in LedgerInfo \
+             at version {}.",
+            root_hash,
+            ledger_info.transaction_accumulator_hash(),
+            ledger_info.version(),
+        );
+        Ok(())
+    }
+}
+
+/// Restores the state tree at a certain version chunk by chunk. See
+/// [`JellyfishMerkleRestore`](jellyfish_merkle::restore::JellyfishMerkleRestore).
+pub struct StateSnapshotRestore<'a> {
+    inner: JellyfishMerkleRestore<'a, StateStore>,
+}
+
+impl<'a> StateSnapshotRestore<'a> {
+    /// Verifies a chunk of accounts with `proof` and writes it. Chunks must come in ascending
+    /// order of account key.
+    pub fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        self.inner.add_chunk(chunk, proof)
+    }
+
+    /// Writes the remaining nodes including the root, after all the chunks are added.
+    pub fn finish(self) -> Result<()> {
+        self.inner.finish()
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{mock_genesis::db_with_mock_genesis, test_helper::arb_blocks_to_commit};
+use libra_crypto::hash::CryptoHash;
+use libra_tools::tempdir::TempPath;
+use libra_types::{
+    block_info::BlockInfo,
+    crypto_proxies::{random_validator_verifier, ValidatorSigner},
+    ledger_info::LedgerInfo,
+    transaction::TransactionToCommit,
+};
+use proptest::prelude::*;
+
+const TXN_CHUNK_SIZE: u64 = 3;
+const ACCOUNT_CHUNK_SIZE: usize = 2;
+
+/// Makes the ledger info of the `i`-th non-empty block end epoch `i + 1` and be signed by the
+/// validator set handed over by the previous one, so that they chain up from the mock genesis.
+fn to_epoch_changes(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)> {
+    let mut signers: Vec<ValidatorSigner> = Vec::new();
+    input
+        .into_iter()
+        .filter(|(txns_to_commit, _)| !txns_to_commit.is_empty())
+        .enumerate()
+        .map(|(i, (txns_to_commit, ledger_info_with_sigs))| {
+            let partial_ledger_info = ledger_info_with_sigs.ledger_info();
+            let (next_signers, next_verifier) = random_validator_verifier(1, None, false);
+            let ledger_info = LedgerInfo::new(
+                BlockInfo::new(
+                    i as u64 + 1,
+                    partial_ledger_info.round(),
+                    partial_ledger_info.consensus_block_id(),
+                    partial_ledger_info.transaction_accumulator_hash(),
+                    partial_ledger_info.version(),
+                    partial_ledger_info.timestamp_usecs(),
+                    Some((&next_verifier).into()),
+                ),
+                partial_ledger_info.consensus_data_hash(),
+            );
+            let signatures = signers
+                .iter()
+                .map(|s| (s.author(), s.sign_message(ledger_info.hash()).unwrap()))
+                .collect();
+            signers = next_signers;
+            (
+                txns_to_commit,
+                LedgerInfoWithSignatures::new(ledger_info, signatures),
+            )
+        })
+        .collect()
+}
+
+fn db_with_blocks(
+    dir: &TempPath,
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<LibraDB> {
+    let db = db_with_mock_genesis(dir)?;
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in to_epoch_changes(input) {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    Ok(db)
+}
+
+fn restore_transactions_from(
+    src_db: &LibraDB,
+    tgt_db: &LibraDB,
+) -> Result<LedgerInfoWithSignatures> {
+    let ledger_info_with_sigs = src_db.ledger_store.get_latest_ledger_info()?;
+    let ledger_version = ledger_info_with_sigs.ledger_info().version();
+
+    let mut version = 0;
+    while version <= ledger_version {
+        let txn_list_with_proof = src_db.get_transactions(
+            version,
+            TXN_CHUNK_SIZE,
+            ledger_version,
+            true, /* fetch_events */
+        )?;
+        tgt_db.restore_transactions(&ledger_info_with_sigs, &txn_list_with_proof)?;
+        version += txn_list_with_proof.transactions.len() as u64;
+    }
+    Ok(ledger_info_with_sigs)
+}
+
+fn restore_from(src_db: &LibraDB, tgt_db: &LibraDB) -> Result<LedgerInfoWithSignatures> {
+    let ledger_info_with_sigs = restore_transactions_from(src_db, tgt_db)?;
+    let ledger_version = ledger_info_with_sigs.ledger_info().version();
+
+    tgt_db.restore_ledger_infos(
+        &ledger_info_with_sigs,
+        &src_db.get_ledger_infos_between(0, ledger_version)?,
+    )?;
+
+    let (_, txn_info) = src_db.ledger_store.get_latest_transaction_info()?;
+    let mut restore =
+        tgt_db.get_state_snapshot_restore(ledger_version, txn_info.state_root_hash())?;
+    let accounts = src_db
+        .get_account_iter(ledger_version)?
+        .collect::<Result<Vec<_>>>()?;
+    for chunk in accounts.chunks(ACCOUNT_CHUNK_SIZE) {
+        let (rightmost_key, _) = chunk.last().expect("Chunk should not be empty.");
+        let proof = src_db.get_account_state_range_proof(*rightmost_key, ledger_version)?;
+        restore.add_chunk(chunk.to_vec(), proof)?;
+    }
+    restore.finish()?;
+
+    tgt_db.finish_restore(&ledger_info_with_sigs)?;
+    Ok(ledger_info_with_sigs)
+}
+
+fn test_backup_and_restore_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let src_tmp_dir = TempPath::new();
+    let src_db = db_with_blocks(&src_tmp_dir, input)?;
+    let (cur_ver, _) = src_db.ledger_store.get_latest_transaction_info()?;
+
+    let tgt_tmp_dir = TempPath::new();
+    let tgt_db = LibraDB::new(&tgt_tmp_dir);
+    let ledger_info_with_sigs = restore_from(&src_db, &tgt_db)?;
+    let ledger_version = ledger_info_with_sigs.ledger_info().version();
+
+    assert_eq!(
+        tgt_db.ledger_store.get_latest_ledger_info()?,
+        ledger_info_with_sigs
+    );
+    assert_eq!(
+        tgt_db.get_transactions(0, cur_ver + 1, ledger_version, true)?,
+        src_db.get_transactions(0, cur_ver + 1, ledger_version, true)?,
+    );
+    assert_eq!(
+        tgt_db
+            .get_account_iter(ledger_version)?
+            .collect::<Result<Vec<_>>>()?,
+        src_db
+            .get_account_iter(ledger_version)?
+            .collect::<Result<Vec<_>>>()?,
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_rejects_tampered_transactions() {
+    let src_tmp_dir = TempPath::new();
+    let src_db = db_with_mock_genesis(&src_tmp_dir).unwrap();
+    let ledger_info_with_sigs = src_db.ledger_store.get_latest_ledger_info().unwrap();
+
+    let mut txn_list_with_proof = src_db
+        .get_transactions(0, 1, 0 /* ledger_version */, true)
+        .unwrap();
+    txn_list_with_proof.events = Some(vec![vec![]; 2]);
+
+    let tgt_tmp_dir = TempPath::new();
+    let tgt_db = LibraDB::new(&tgt_tmp_dir);
+    assert!(tgt_db
+        .restore_transactions(&ledger_info_with_sigs, &txn_list_with_proof)
+        .is_err());
+    assert!(tgt_db
+        .ledger_store
+        .get_latest_transaction_info_option()
+        .unwrap()
+        .is_none());
+}
+
+fn test_restore_rejects_broken_epoch_chain_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let src_tmp_dir = TempPath::new();
+    let src_db = db_with_blocks(&src_tmp_dir, input)?;
+
+    let tgt_tmp_dir = TempPath::new();
+    let tgt_db = LibraDB::new(&tgt_tmp_dir);
+    let ledger_info_with_sigs = restore_transactions_from(&src_db, &tgt_db)?;
+    let ledger_version = ledger_info_with_sigs.ledger_info().version();
+    let prior_ledger_infos = src_db.get_ledger_infos_between(0, ledger_version)?;
+
+    // Without the genesis one, the chain has no trusted start.
+    if !prior_ledger_infos.is_empty() {
+        assert!(tgt_db
+            .restore_ledger_infos(&ledger_info_with_sigs, &prior_ledger_infos[1..])
+            .is_err());
+    }
+    // Out of order, the epochs don't follow one another.
+    if prior_ledger_infos.len() > 1 {
+        let mut reversed = prior_ledger_infos.clone();
+        reversed.reverse();
+        assert!(tgt_db
+            .restore_ledger_infos(&ledger_info_with_sigs, &reversed)
+            .is_err());
+    }
+    assert!(tgt_db
+        .get_ledger_infos_between(0, ledger_version)?
+        .is_empty());
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_restore_rejects_broken_epoch_chain(input in arb_blocks_to_commit()) {
+        test_restore_rejects_broken_epoch_chain_impl(input).unwrap();
+    }
+
+    #[test]
+    fn test_backup_and_restore(input in arb_blocks_to_commit()) {
+        test_backup_and_restore_impl(input).unwrap();
+    }
+}
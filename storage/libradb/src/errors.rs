@@ -17,4 +17,10 @@ pub enum LibraDbError {
         _0, _1
     )]
     TooManyRequested(u64, u64),
+    /// Requested data of a version that has been pruned.
+    #[fail(
+        display = "Version {} has been pruned, the oldest readable version is {}.",
+        _0, _1
+    )]
+    Pruned(u64, u64),
 }
@@ -13,8 +13,10 @@ pub mod mock_genesis;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod test_helper;
 
+pub mod backup;
 pub mod errors;
 pub mod schema;
+pub mod verify;
 
 mod change_set;
 mod event_store;
@@ -34,8 +36,8 @@ use crate::{
     event_store::EventStore,
     ledger_counters::LedgerCounters,
     ledger_store::LedgerStore,
-    pruner::Pruner,
-    schema::*,
+    pruner::{Pruner, PruningDimension},
+    schema::{pruner_progress::PrunerProgressSchema, *},
     state_store::StateStore,
     system_store::SystemStore,
     transaction_store::TransactionStore,
@@ -43,6 +45,7 @@ use crate::{
 use failure::prelude::*;
 use itertools::{izip, zip_eq};
 use lazy_static::lazy_static;
+use libra_config::config::{SecondaryIndexConfig, StorageConfig, StorageEngine};
 use libra_crypto::hash::{CryptoHash, HashValue};
 use libra_logger::prelude::*;
 use libra_metrics::OpMetrics;
@@ -50,21 +53,21 @@ use libra_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_config::AccountResource,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{AccountStateBlob, AccountStateRangeWithProof, AccountStateWithProof},
     contract_event::EventWithProof,
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeEventWithProof},
     get_with_proof::{RequestItem, ResponseItem},
     proof::{
-        AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-        TransactionListProof, TransactionProof,
+        AccountStateProof, AccountStateRangeProof, AccumulatorConsistencyProof, EventProof,
+        SparseMerkleProof, SparseMerkleRangeProof, TransactionListProof, TransactionProof,
     },
     transaction::{
         TransactionInfo, TransactionListWithProof, TransactionToCommit, TransactionWithProof,
         Version,
     },
 };
-use schemadb::{ColumnFamilyOptions, ColumnFamilyOptionsMap, DB, DEFAULT_CF_NAME};
-use std::{convert::TryInto, iter::Iterator, path::Path, sync::Arc, time::Instant};
+use schemadb::{ColumnFamilyName, DB, DEFAULT_CF_NAME};
+use std::{convert::TryInto, fs, iter::Iterator, path::Path, sync::Arc, time::Instant};
 use storage_proto::StartupInfo;
 use storage_proto::TreeState;
 
@@ -92,71 +95,144 @@ pub struct LibraDB {
     state_store: StateStore,
     event_store: EventStore,
     system_store: SystemStore,
-    pruner: Pruner,
+    /// `None` if opened in read-only mode.
+    pruner: Option<Pruner>,
 }
 
 impl LibraDB {
-    /// Config parameter for the pruner.
-    const NUM_HISTORICAL_VERSIONS_TO_KEEP: u64 = 1_000_000;
+    /// Name of the directory under the db root path holding the RocksDB instance.
+    const DB_NAME: &'static str = "libradb";
 
-    /// This creates an empty LibraDB instance on disk or opens one if it already exists.
+    /// This creates an empty LibraDB instance on disk or opens one if it already exists, with the
+    /// default storage config.
     pub fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
-        let cf_opts_map: ColumnFamilyOptionsMap = [
-            (
-                /* LedgerInfo CF = */ DEFAULT_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (EPOCH_BY_VERSION_CF_NAME, ColumnFamilyOptions::default()),
-            (EVENT_ACCUMULATOR_CF_NAME, ColumnFamilyOptions::default()),
-            (EVENT_BY_KEY_CF_NAME, ColumnFamilyOptions::default()),
-            (EVENT_CF_NAME, ColumnFamilyOptions::default()),
-            (
-                JELLYFISH_MERKLE_NODE_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (LEDGER_COUNTERS_CF_NAME, ColumnFamilyOptions::default()),
-            (STALE_NODE_INDEX_CF_NAME, ColumnFamilyOptions::default()),
-            (TRANSACTION_CF_NAME, ColumnFamilyOptions::default()),
-            (
-                TRANSACTION_ACCUMULATOR_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (
-                TRANSACTION_BY_ACCOUNT_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (TRANSACTION_INFO_CF_NAME, ColumnFamilyOptions::default()),
-            (VALIDATOR_CF_NAME, ColumnFamilyOptions::default()),
-        ]
-        .iter()
-        .cloned()
-        .collect();
+        Self::new_with_config(db_root_path, &StorageConfig::default())
+    }
+
+    /// Same as [`LibraDB::new`], but configured by `config`. With the in-memory engine,
+    /// `db_root_path` is ignored and the instance always starts empty.
+    pub fn new_with_config<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        config: &StorageConfig,
+    ) -> Self {
+        let db = match config.engine {
+            StorageEngine::Rocksdb => {
+                let path = db_root_path.as_ref().join(Self::DB_NAME);
+                let instant = Instant::now();
+                let db =
+                    DB::open_with_config(path.clone(), &Self::column_families(), &config.rocksdb)
+                        .unwrap_or_else(|e| unrecoverable!("LibraDB open failed: {:?}", e));
+
+                info!(
+                    "Opened LibraDB at {:?} in {} ms",
+                    path,
+                    instant.elapsed().as_millis()
+                );
+                db
+            }
+            StorageEngine::InMemory => {
+                info!("Opened LibraDB in memory");
+                DB::open_in_memory(&Self::column_families())
+            }
+        };
+        let db = Arc::new(db);
+
+        Self::new_with_db(
+            Arc::clone(&db),
+            Some(Pruner::new(Arc::clone(&db), &config.pruning)),
+            config.secondary_indexes.clone(),
+        )
+    }
 
-        let path = db_root_path.as_ref().join("libradb");
+    /// Opens the LibraDB instance at `db_root_path` in read-only mode, typically a checkpoint
+    /// created by [`LibraDB::create_checkpoint`]. All read APIs work as usual, while all writes are
+    /// refused.
+    pub fn open_readonly<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        let path = db_root_path.as_ref().join(Self::DB_NAME);
         let instant = Instant::now();
-        let db = Arc::new(
-            DB::open(path.clone(), cf_opts_map)
-                .unwrap_or_else(|e| unrecoverable!("LibraDB open failed: {:?}", e)),
-        );
+        let db = Arc::new(DB::open_readonly(path.clone(), &Self::column_families())?);
 
         info!(
-            "Opened LibraDB at {:?} in {} ms",
+            "Opened LibraDB at {:?} in read-only mode in {} ms",
             path,
             instant.elapsed().as_millis()
         );
 
+        // Read-only instances never index anything, so just serve whatever has been indexed.
+        let secondary_indexes = SecondaryIndexConfig {
+            transaction_by_script_hash: true,
+            transaction_by_receiver: true,
+        };
+        Ok(Self::new_with_db(
+            db,
+            None, /* pruner */
+            secondary_indexes,
+        ))
+    }
+
+    fn new_with_db(
+        db: Arc<DB>,
+        pruner: Option<Pruner>,
+        secondary_indexes: SecondaryIndexConfig,
+    ) -> Self {
         LibraDB {
             db: Arc::clone(&db),
             event_store: EventStore::new(Arc::clone(&db)),
             ledger_store: LedgerStore::new(Arc::clone(&db)),
             state_store: StateStore::new(Arc::clone(&db)),
-            transaction_store: TransactionStore::new(Arc::clone(&db)),
+            transaction_store: TransactionStore::new(Arc::clone(&db), secondary_indexes),
             system_store: SystemStore::new(Arc::clone(&db)),
-            pruner: Pruner::new(Arc::clone(&db), Self::NUM_HISTORICAL_VERSIONS_TO_KEEP),
+            pruner,
         }
     }
 
+    fn column_families() -> Vec<ColumnFamilyName> {
+        vec![
+            /* LedgerInfo CF = */ DEFAULT_CF_NAME,
+            EPOCH_BY_VERSION_CF_NAME,
+            EVENT_ACCUMULATOR_CF_NAME,
+            EVENT_BY_KEY_CF_NAME,
+            EVENT_CF_NAME,
+            JELLYFISH_MERKLE_NODE_CF_NAME,
+            LEDGER_COUNTERS_CF_NAME,
+            PRUNER_PROGRESS_CF_NAME,
+            STALE_NODE_INDEX_CF_NAME,
+            TRANSACTION_CF_NAME,
+            TRANSACTION_ACCUMULATOR_CF_NAME,
+            TRANSACTION_BY_ACCOUNT_CF_NAME,
+            TRANSACTION_BY_RECEIVER_CF_NAME,
+            TRANSACTION_BY_SCRIPT_HASH_CF_NAME,
+            TRANSACTION_INFO_CF_NAME,
+            VALIDATOR_CF_NAME,
+        ]
+    }
+
     // ================================== Public API ==================================
+    /// Creates a point-in-time copy of the DB under `checkpoint_root_path`, which can be opened
+    /// via [`LibraDB::open_readonly`] without affecting this instance. Returns the latest ledger
+    /// info in the checkpoint.
+    ///
+    /// Transactions committed after the returned ledger info but before the checkpoint was taken,
+    /// if any, are included as well, just like in the live DB.
+    pub fn create_checkpoint<P: AsRef<Path>>(
+        &self,
+        checkpoint_root_path: P,
+    ) -> Result<LedgerInfoWithSignatures> {
+        fs::create_dir_all(checkpoint_root_path.as_ref())?;
+        self.db
+            .create_checkpoint(checkpoint_root_path.as_ref().join(Self::DB_NAME))?;
+
+        let ledger_info_with_sigs = Self::open_readonly(checkpoint_root_path.as_ref())?
+            .ledger_store
+            .get_latest_ledger_info()?;
+        info!(
+            "Created LibraDB checkpoint at {:?}, version {}.",
+            checkpoint_root_path.as_ref(),
+            ledger_info_with_sigs.ledger_info().version(),
+        );
+        Ok(ledger_info_with_sigs)
+    }
+
     /// Returns the account state corresponding to the given version and account address with proof
     /// based on `ledger_version`
     fn get_account_state_with_proof(
@@ -178,6 +254,8 @@ impl LibraDB {
             ledger_version,
             latest_version
         );
+        self.error_if_pruned(PruningDimension::Ledger, version)?;
+        self.error_if_pruned(PruningDimension::State, version)?;
 
         let (txn_info, txn_info_accumulator_proof) = self
             .ledger_store
@@ -192,6 +270,53 @@ impl LibraDB {
         ))
     }
 
+    /// Returns up to `limit` accounts whose keys (hashed addresses) are not less than `cursor`, in
+    /// ascending order of key, as of `version`, with the proof that none of them is left out.
+    fn get_account_state_range_with_proof(
+        &self,
+        cursor: HashValue,
+        limit: u64,
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<AccountStateRangeWithProof> {
+        error_if_too_many_requested(limit, MAX_LIMIT)?;
+        ensure!(
+            version <= ledger_version,
+            "The queried version {} should be equal to or older than ledger version {}.",
+            version,
+            ledger_version
+        );
+
+        let account_blobs = self
+            .state_store
+            .iter_accounts(version, cursor)?
+            .take(limit as usize)
+            .collect::<Result<Vec<_>>>()?;
+        let (txn_info, txn_info_accumulator_proof) = self
+            .ledger_store
+            .get_transaction_info_with_proof(version, ledger_version)?;
+        let (_, cursor_proof) = self
+            .state_store
+            .get_account_state_with_proof_by_key(cursor, version)?;
+        let range_proof = match account_blobs.last() {
+            Some((last_key, _)) => self
+                .state_store
+                .get_account_state_range_proof(*last_key, version)?,
+            None => SparseMerkleRangeProof::new(vec![]),
+        };
+
+        Ok(AccountStateRangeWithProof::new(
+            version,
+            account_blobs,
+            AccountStateRangeProof::new(
+                txn_info_accumulator_proof,
+                txn_info,
+                cursor_proof,
+                range_proof,
+            ),
+        ))
+    }
+
     /// Returns events specified by `query_path` with sequence number in range designated by
     /// `start_seq_num`, `ascending` and `limit`. If ascending is true this query will return up to
     /// `limit` events that were emitted after `start_event_seq_num`. Otherwise, it will return up
@@ -295,6 +420,72 @@ impl LibraDB {
             .transpose()
     }
 
+    /// Returns up to `limit` transactions running the script with `script_hash`, starting from
+    /// `start_version`.
+    fn get_txns_by_script_hash(
+        &self,
+        script_hash: HashValue,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Vec<TransactionListWithProof>> {
+        error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+        let versions = self.transaction_store.lookup_transactions_by_script_hash(
+            script_hash,
+            start_version,
+            limit,
+            ledger_version,
+        )?;
+        self.get_transaction_lists(&versions, ledger_version, fetch_events)
+    }
+
+    /// Returns up to `limit` transactions whose scripts take `receiver` as an argument, starting
+    /// from `start_version`.
+    fn get_txns_by_receiver(
+        &self,
+        receiver: AccountAddress,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Vec<TransactionListWithProof>> {
+        error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+        let versions = self.transaction_store.lookup_transactions_by_receiver(
+            receiver,
+            start_version,
+            limit,
+            ledger_version,
+        )?;
+        self.get_transaction_lists(&versions, ledger_version, fetch_events)
+    }
+
+    /// Gets the transactions at ascending `versions`, with each run of consecutive versions
+    /// proven together as one list.
+    fn get_transaction_lists(
+        &self,
+        versions: &[Version],
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Vec<TransactionListWithProof>> {
+        let mut txn_lists_with_proof = vec![];
+        let mut run_start = 0;
+        for i in 1..=versions.len() {
+            if i == versions.len() || versions[i] != versions[i - 1] + 1 {
+                txn_lists_with_proof.push(self.get_transactions(
+                    versions[run_start],
+                    (i - run_start) as u64,
+                    ledger_version,
+                    fetch_events,
+                )?);
+                run_start = i;
+            }
+        }
+        Ok(txn_lists_with_proof)
+    }
+
     /// Gets the latest version number available in the ledger.
     fn get_latest_version(&self) -> Result<Version> {
         Ok(self
@@ -304,6 +495,24 @@ impl LibraDB {
             .version())
     }
 
+    /// Gets the oldest version of which data of `dimension` has not been pruned.
+    pub(crate) fn least_readable_version(&self, dimension: PruningDimension) -> Result<Version> {
+        Ok(self
+            .db
+            .get::<PrunerProgressSchema>(&dimension)?
+            .unwrap_or(0))
+    }
+
+    /// Returns an error if data of `dimension` at `version` has been pruned.
+    fn error_if_pruned(&self, dimension: PruningDimension, version: Version) -> Result<()> {
+        let least_readable_version = self.least_readable_version(dimension)?;
+        if version < least_readable_version {
+            Err(LibraDbError::Pruned(version, least_readable_version).into())
+        } else {
+            Ok(())
+        }
+    }
+
     /// Returns ledger infos reflecting epoch bumps starting with the given epoch.
     pub fn get_epoch_change_ledger_infos(
         &self,
@@ -382,7 +591,9 @@ impl LibraDB {
                 .expect("Counters should be bumped with transactions being saved.")
                 .bump_op_counters();
 
-            self.pruner.wake(last_version);
+            if let Some(pruner) = &self.pruner {
+                pruner.wake(last_version);
+            }
         }
 
         Ok(())
@@ -466,13 +677,15 @@ impl LibraDB {
         let response_items = request_items
             .into_iter()
             .map(|request_item| match request_item {
-                RequestItem::GetAccountState { address } => Ok(ResponseItem::GetAccountState {
-                    account_state_with_proof: self.get_account_state_with_proof(
-                        address,
-                        ledger_version,
-                        ledger_version,
-                    )?,
-                }),
+                RequestItem::GetAccountState { address, version } => {
+                    Ok(ResponseItem::GetAccountState {
+                        account_state_with_proof: self.get_account_state_with_proof(
+                            address,
+                            version.unwrap_or(ledger_version),
+                            ledger_version,
+                        )?,
+                    })
+                }
                 RequestItem::GetAccountTransactionBySequenceNumber {
                     account,
                     sequence_number,
@@ -531,6 +744,44 @@ impl LibraDB {
                         txn_list_with_proof,
                     })
                 }
+                RequestItem::GetAccountStatesRange { cursor, limit } => {
+                    Ok(ResponseItem::GetAccountStatesRange {
+                        account_state_range_with_proof: self.get_account_state_range_with_proof(
+                            cursor,
+                            limit,
+                            ledger_version,
+                            ledger_version,
+                        )?,
+                    })
+                }
+                RequestItem::GetTransactionsByScriptHash {
+                    script_hash,
+                    start_version,
+                    limit,
+                    fetch_events,
+                } => Ok(ResponseItem::GetTransactionsByScriptHash {
+                    txn_lists_with_proof: self.get_txns_by_script_hash(
+                        script_hash,
+                        start_version,
+                        limit,
+                        ledger_version,
+                        fetch_events,
+                    )?,
+                }),
+                RequestItem::GetTransactionsByReceiver {
+                    receiver,
+                    start_version,
+                    limit,
+                    fetch_events,
+                } => Ok(ResponseItem::GetTransactionsByReceiver {
+                    txn_lists_with_proof: self.get_txns_by_receiver(
+                        receiver,
+                        start_version,
+                        limit,
+                        ledger_version,
+                        fetch_events,
+                    )?,
+                }),
             })
             .collect::<Result<Vec<_>>>()?;
 
@@ -575,6 +826,24 @@ impl LibraDB {
             .get_account_state_with_proof_by_version(address, version)
     }
 
+    /// Gets the tree state right after the latest transaction saved, or `None` if the DB is empty.
+    /// Unlike the ones in [`StartupInfo`], it doesn't depend on any ledger info being saved.
+    pub fn get_latest_tree_state(&self) -> Result<Option<TreeState>> {
+        let (latest_version, txn_info) =
+            match self.ledger_store.get_latest_transaction_info_option()? {
+                Some(x) => x,
+                None => return Ok(None),
+            };
+        let ledger_frozen_subtree_hashes = self
+            .ledger_store
+            .get_ledger_frozen_subtree_hashes(latest_version)?;
+        Ok(Some(TreeState::new(
+            latest_version,
+            ledger_frozen_subtree_hashes,
+            txn_info.state_root_hash(),
+        )))
+    }
+
     /// Gets information needed from storage during the startup of the executor or state
     /// synchronizer module.
     ///
@@ -586,18 +855,11 @@ impl LibraDB {
             None => return Ok(None),
         };
 
-        let latest_tree_state = {
-            let (latest_version, txn_info) = self.ledger_store.get_latest_transaction_info()?;
-            let account_state_root_hash = txn_info.state_root_hash();
-            let ledger_frozen_subtree_hashes = self
-                .ledger_store
-                .get_ledger_frozen_subtree_hashes(latest_version)?;
-            TreeState::new(
-                latest_version,
-                ledger_frozen_subtree_hashes,
-                account_state_root_hash,
-            )
-        };
+        let latest_tree_state = self.get_latest_tree_state()?.ok_or_else(|| {
+            Error::from(LibraDbError::NotFound(String::from(
+                "Genesis TransactionInfo.",
+            )))
+        })?;
         let li_version = ledger_info_with_sigs.ledger_info().version();
         assert!(latest_tree_state.version >= li_version);
         let current_epoch = if ledger_info_with_sigs
@@ -739,6 +1001,9 @@ impl LibraDB {
                 err
             ),
         }
+        for (ticker, count) in self.db.get_statistics_tickers() {
+            OP_COUNTER.set(&format!("rocksdb_{}", ticker), count as usize);
+        }
 
         Ok(())
     }
@@ -6,6 +6,7 @@ use crate::{
     mock_genesis::{db_with_mock_genesis, GENESIS_INFO},
     test_helper::arb_blocks_to_commit,
 };
+use libra_config::config::{PruningConfig, SecondaryIndexConfig};
 use libra_crypto::hash::CryptoHash;
 use libra_tools::tempdir::TempPath;
 use libra_types::{
@@ -35,7 +36,28 @@ fn test_save_blocks_impl(
 ) -> Result<()> {
     let tmp_dir = TempPath::new();
     let db = db_with_mock_genesis(&tmp_dir)?;
+    save_and_verify_blocks(&db, input)
+}
 
+fn test_save_blocks_in_memory_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let mut config = StorageConfig::default();
+    config.engine = StorageEngine::InMemory;
+    // The path is not used by the in-memory engine.
+    let db = LibraDB::new_with_config("/nonexistent", &config);
+    db.save_transactions(
+        &[GENESIS_INFO.2.clone()],
+        0, /* first_version */
+        &Some(GENESIS_INFO.1.clone()),
+    )?;
+    save_and_verify_blocks(&db, input)
+}
+
+fn save_and_verify_blocks(
+    db: &LibraDB,
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
     let num_batches = input.len();
     let mut cur_ver = 0;
     for (batch_idx, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
@@ -50,7 +72,7 @@ fn test_save_blocks_impl(
             *ledger_info_with_sigs
         );
         verify_committed_transactions(
-            &db,
+            db,
             &txns_to_commit,
             cur_ver,
             ledger_info_with_sigs,
@@ -65,7 +87,7 @@ fn test_save_blocks_impl(
     let latest_ledger_info = input.last().unwrap().1.clone();
     // Verify an old batch with the latest LedgerInfo.
     verify_committed_transactions(
-        &db,
+        db,
         &first_batch,
         0,
         &latest_ledger_info,
@@ -73,14 +95,14 @@ fn test_save_blocks_impl(
     )?;
     // Verify an old batch with an old LedgerInfo.
     verify_committed_transactions(
-        &db,
+        db,
         &first_batch,
         0,
         &first_batch_ledger_info,
         true, /* is_latest */
     )?;
     let (_, ledger_infos_with_sigs): (Vec<_>, Vec<_>) = input.iter().cloned().unzip();
-    verify_epochs(&db, &ledger_infos_with_sigs)?;
+    verify_epochs(db, &ledger_infos_with_sigs)?;
 
     Ok(())
 }
@@ -371,6 +393,292 @@ fn verify_committed_transactions(
     Ok(())
 }
 
+fn test_checkpoint_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir)?;
+    let checkpoint_dir = TempPath::new();
+
+    let num_blocks_before_checkpoint = input.len() / 2;
+    let mut cur_ver = 0;
+    let mut checkpoint_startup_info = None;
+    for (i, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
+        if i == num_blocks_before_checkpoint {
+            let checkpoint_ledger_info = db.create_checkpoint(&checkpoint_dir)?;
+            assert_eq!(
+                checkpoint_ledger_info,
+                db.ledger_store.get_latest_ledger_info()?
+            );
+            checkpoint_startup_info = db.get_startup_info()?;
+        }
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    // The checkpoint is frozen at the time it was created.
+    let checkpoint = LibraDB::open_readonly(&checkpoint_dir)?;
+    assert_eq!(checkpoint.get_startup_info()?, checkpoint_startup_info);
+    let ledger_version = checkpoint.get_latest_version()?;
+    assert_eq!(
+        checkpoint.get_transactions(0, ledger_version + 1, ledger_version, true)?,
+        db.get_transactions(0, ledger_version + 1, ledger_version, true)?,
+    );
+
+    // And refuses writes.
+    let (txns_to_commit, ledger_info_with_sigs) = input
+        .last()
+        .expect("arb_blocks_to_commit yields at least one block.");
+    assert!(checkpoint
+        .save_transactions(
+            txns_to_commit,
+            ledger_version + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )
+        .is_err());
+
+    Ok(())
+}
+
+fn test_get_account_states_range_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    const PAGE_SIZE: u64 = 3;
+
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir)?;
+    let mut cur_ver = 0;
+    let mut latest_blobs = HashMap::new();
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+        for txn_to_commit in txns_to_commit {
+            for (addr, blob) in txn_to_commit.account_states() {
+                latest_blobs.insert(addr.hash(), blob.clone());
+            }
+        }
+    }
+    let ledger_info = input
+        .last()
+        .expect("arb_blocks_to_commit yields at least one block.")
+        .1
+        .ledger_info();
+
+    // Walk through all accounts page by page.
+    let mut cursor = HashValue::zero();
+    let mut accounts = vec![];
+    loop {
+        let (mut response_items, _, _, _) = db.update_to_latest_ledger(
+            0,
+            vec![RequestItem::GetAccountStatesRange {
+                cursor,
+                limit: PAGE_SIZE,
+            }],
+        )?;
+        let range = response_items
+            .pop()
+            .expect("One response item per request item.")
+            .into_get_account_states_range_response()?;
+        range.verify(ledger_info, cur_ver, cursor, PAGE_SIZE)?;
+
+        let is_last_page = (range.account_blobs.len() as u64) < PAGE_SIZE;
+        let next_cursor = range.next_cursor();
+        accounts.extend(range.account_blobs);
+        match next_cursor {
+            Some(next_cursor) if !is_last_page => cursor = next_cursor,
+            _ => break,
+        }
+    }
+
+    assert!(accounts.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    let accounts: HashMap<_, _> = accounts.into_iter().collect();
+    for (key, blob) in latest_blobs {
+        assert_eq!(accounts.get(&key), Some(&blob));
+    }
+
+    Ok(())
+}
+
+fn test_get_historical_account_state_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    const WINDOW: u64 = 3;
+
+    let tmp_dir = TempPath::new();
+    db_with_mock_genesis(&tmp_dir)?;
+    let mut config = StorageConfig::default();
+    config.pruning = PruningConfig {
+        enabled: true,
+        state_window: Some(WINDOW),
+        ledger_window: None,
+        event_window: None,
+    };
+    let db = LibraDB::new_with_config(&tmp_dir, &config);
+
+    let mut cur_ver = 0;
+    let mut account_states = vec![];
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )?;
+        for txn_to_commit in txns_to_commit {
+            cur_ver += 1;
+            for (addr, blob) in txn_to_commit.account_states() {
+                account_states.push((cur_ver, *addr, blob.clone()));
+            }
+        }
+    }
+    db.pruner.as_ref().unwrap().wake_and_wait(cur_ver)?;
+    let ledger_info = input
+        .last()
+        .expect("arb_blocks_to_commit yields at least one block.")
+        .1
+        .ledger_info();
+    let least_readable_version = cur_ver.saturating_sub(WINDOW);
+
+    for (version, address, blob) in account_states {
+        let result = db.update_to_latest_ledger(
+            0,
+            vec![RequestItem::GetAccountState {
+                address,
+                version: Some(version),
+            }],
+        );
+        if version < least_readable_version {
+            let error = result.expect_err("State should have been pruned.");
+            match error.downcast::<LibraDbError>()? {
+                LibraDbError::Pruned(pruned_version, oldest_version) => {
+                    assert_eq!(pruned_version, version);
+                    assert_eq!(oldest_version, least_readable_version);
+                }
+                e => panic!("Unexpected error: {}", e),
+            }
+        } else {
+            let account_state_with_proof = result?
+                .0
+                .pop()
+                .expect("One response item per request item.")
+                .into_get_account_state_response()?;
+            assert_eq!(account_state_with_proof.blob, Some(blob));
+            account_state_with_proof.verify(ledger_info, version, address)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn test_get_indexed_transactions_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    const PAGE_SIZE: u64 = 2;
+
+    let tmp_dir = TempPath::new();
+    db_with_mock_genesis(&tmp_dir)?;
+    let mut config = StorageConfig::default();
+    config.secondary_indexes = SecondaryIndexConfig {
+        transaction_by_script_hash: true,
+        transaction_by_receiver: true,
+    };
+    let db = LibraDB::new_with_config(&tmp_dir, &config);
+
+    let mut cur_ver = 0;
+    let mut by_script_hash: HashMap<_, Vec<_>> = HashMap::new();
+    let mut by_receiver: HashMap<_, Vec<_>> = HashMap::new();
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )?;
+        for txn_to_commit in txns_to_commit {
+            cur_ver += 1;
+            if let Some(script) = txn_to_commit.transaction().as_script() {
+                by_script_hash
+                    .entry(script.code_hash())
+                    .or_default()
+                    .push(cur_ver);
+                for receiver in script.address_args() {
+                    by_receiver.entry(receiver).or_default().push(cur_ver);
+                }
+            }
+        }
+    }
+    let ledger_info = input
+        .last()
+        .expect("arb_blocks_to_commit yields at least one block.")
+        .1
+        .ledger_info();
+
+    // Pages through the query built by `request`, returning the versions of all transactions.
+    let query_all = |request: &dyn Fn(Version) -> RequestItem| -> Result<Vec<Version>> {
+        let mut start_version = 0;
+        let mut versions = vec![];
+        loop {
+            let (mut response_items, _, _, _) =
+                db.update_to_latest_ledger(0, vec![request(start_version)])?;
+            let txn_lists_with_proof = match response_items
+                .pop()
+                .expect("One response item per request item.")
+            {
+                ResponseItem::GetTransactionsByScriptHash {
+                    txn_lists_with_proof,
+                }
+                | ResponseItem::GetTransactionsByReceiver {
+                    txn_lists_with_proof,
+                } => txn_lists_with_proof,
+                _ => bail!("Unexpected response item."),
+            };
+            let num_versions = versions.len();
+            for txn_list_with_proof in txn_lists_with_proof {
+                let first_version = txn_list_with_proof.first_transaction_version;
+                txn_list_with_proof.verify(ledger_info, first_version)?;
+                let first_version = first_version.expect("Lists should not be empty.");
+                versions.extend(
+                    first_version..first_version + txn_list_with_proof.transactions.len() as u64,
+                );
+            }
+            if ((versions.len() - num_versions) as u64) < PAGE_SIZE {
+                break;
+            }
+            start_version = versions.last().expect("Page is full.") + 1;
+        }
+        Ok(versions)
+    };
+
+    for (script_hash, expected_versions) in by_script_hash {
+        let versions = query_all(&|start_version| RequestItem::GetTransactionsByScriptHash {
+            script_hash,
+            start_version,
+            limit: PAGE_SIZE,
+            fetch_events: true,
+        })?;
+        assert_eq!(versions, expected_versions);
+    }
+    for (receiver, mut expected_versions) in by_receiver {
+        // A transaction taking the same address twice is indexed once.
+        expected_versions.dedup();
+        let versions = query_all(&|start_version| RequestItem::GetTransactionsByReceiver {
+            receiver,
+            start_version,
+            limit: PAGE_SIZE,
+            fetch_events: false,
+        })?;
+        assert_eq!(versions, expected_versions);
+    }
+
+    Ok(())
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
 
@@ -379,10 +687,35 @@ proptest! {
         test_save_blocks_impl(input).unwrap();
     }
 
+    #[test]
+    fn test_save_blocks_in_memory(input in arb_blocks_to_commit()) {
+        test_save_blocks_in_memory_impl(input).unwrap();
+    }
+
     #[test]
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input).unwrap();
     }
+
+    #[test]
+    fn test_checkpoint(input in arb_blocks_to_commit()) {
+        test_checkpoint_impl(input).unwrap();
+    }
+
+    #[test]
+    fn test_get_account_states_range(input in arb_blocks_to_commit()) {
+        test_get_account_states_range_impl(input).unwrap();
+    }
+
+    #[test]
+    fn test_get_historical_account_state(input in arb_blocks_to_commit()) {
+        test_get_historical_account_state_impl(input).unwrap();
+    }
+
+    #[test]
+    fn test_get_indexed_transactions(input in arb_blocks_to_commit()) {
+        test_get_indexed_transactions_impl(input).unwrap();
+    }
 }
 
 #[test]
@@ -480,6 +813,9 @@ fn test_too_many_requested() {
         )
         .is_err());
     assert!(db.get_transactions(0, 1001 /* limit */, 0, true).is_err());
+    assert!(db
+        .get_account_state_range_with_proof(HashValue::zero(), 1001 /* limit */, 0, 0)
+        .is_err());
     assert!(db
         .get_events_by_query_path(
             &AccessPath::new_for_sent_event(AccountAddress::random()),
@@ -3,34 +3,95 @@
 
 //! This module provides `Pruner` which manages a thread pruning old data in the background and is
 //! meant to be triggered by other threads as they commit new data to the DB.
+//!
+//! Data is pruned along a few independent dimensions (see [`PruningDimension`]), each with its own
+//! retention window. The progress of a dimension is persisted in the same batch as the deletions
+//! it accounts for, so the pruner never leaves the DB half pruned and resumes where it was after a
+//! restart.
 
 use crate::{
     schema::{
-        jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        event::EventSchema, event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema, jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+        ledger_counters::LedgerCountersSchema, pruner_progress::PrunerProgressSchema,
+        stale_node_index::StaleNodeIndexSchema, transaction::TransactionSchema,
+        transaction_accumulator::TransactionAccumulatorSchema,
+        transaction_by_account::TransactionByAccountSchema,
+        transaction_by_receiver::TransactionByReceiverSchema,
+        transaction_by_script_hash::TransactionByScriptHashSchema,
+        transaction_info::TransactionInfoSchema,
     },
     OP_COUNTER,
 };
 use failure::prelude::*;
+use jellyfish_merkle::StaleNodeIndex;
+use libra_config::config::PruningConfig;
 use libra_logger::prelude::*;
-use libra_types::transaction::Version;
+use libra_types::{
+    proof::position::Position,
+    transaction::{Transaction, Version},
+};
+use num_derive::{FromPrimitive, ToPrimitive};
 use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
+#[cfg(test)]
+use std::thread::sleep;
 use std::{
+    cmp::min,
+    collections::HashMap,
+    iter::Peekable,
     sync::{
+        atomic::{AtomicU64, Ordering},
         mpsc::{channel, Receiver, Sender},
         Arc, Mutex,
     },
     thread::JoinHandle,
-};
-
-use failure::_core::sync::atomic::Ordering;
-use jellyfish_merkle::StaleNodeIndex;
-#[cfg(test)]
-use std::thread::sleep;
-use std::{
-    iter::Peekable,
-    sync::atomic::AtomicU64,
     time::{Duration, Instant},
 };
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter};
+
+/// The kinds of data that are pruned independently of each other.
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, PartialEq, FromPrimitive, ToPrimitive, EnumIter, AsRefStr,
+)]
+#[strum(serialize_all = "snake_case")]
+pub(crate) enum PruningDimension {
+    /// Stale nodes of the account state tree.
+    State = 0,
+    /// Transactions, together with their infos, the transaction indices, the ledger counters and
+    /// the transaction accumulator.
+    Ledger = 1,
+    /// Events, together with the event-by-key index and the event accumulators.
+    Event = 2,
+}
+
+const NUM_DIMENSIONS: usize = 3;
+
+/// The least readable version of each dimension. All versions before it can no longer be
+/// accessed.
+#[derive(Default)]
+struct Progress([AtomicU64; NUM_DIMENSIONS]);
+
+impl Progress {
+    /// Loads the progress persisted by a previous run of the pruner.
+    fn load(db: &DB) -> Result<Self> {
+        let progress = Self::default();
+        for dimension in PruningDimension::iter() {
+            if let Some(version) = db.get::<PrunerProgressSchema>(&dimension)? {
+                progress.set(dimension, version);
+            }
+        }
+        Ok(progress)
+    }
+
+    fn get(&self, dimension: PruningDimension) -> Version {
+        self.0[dimension as usize].load(Ordering::Relaxed)
+    }
+
+    fn set(&self, dimension: PruningDimension, version: Version) {
+        self.0[dimension as usize].store(version, Ordering::Relaxed)
+    }
+}
 
 /// The `Pruner` is meant to be part of a `LibraDB` instance and runs in the background to prune old
 /// data.
@@ -38,48 +99,86 @@ use std::{
 /// It creates a worker thread on construction and joins it on destruction. When destructed, it
 /// quits the worker thread eagerly without waiting for all pending work to be done.
 pub(crate) struct Pruner {
-    /// Other than the latest version, how many historical versions to keep being readable. For
-    /// example, this being 0 means keep only the latest version.
-    num_historical_versions_to_keep: u64,
+    /// Other than the latest version, how many historical versions to keep being readable, for
+    /// each dimension being pruned. For example, a window being 0 means keep only the latest
+    /// version.
+    windows: Vec<(PruningDimension, u64)>,
     /// The worker thread handle, created upon Pruner instance construction and joined upon its
     /// destruction. It only becomes `None` after joined in `drop()`.
     worker_thread: Option<JoinHandle<()>>,
     /// The sender side of the channel talking to the worker thread.
     command_sender: Mutex<Sender<Command>>,
-    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress. If it
-    /// sets this atomic value to `V`, all versions before `V` can no longer be accessed.
+    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress.
     #[allow(dead_code)]
-    worker_progress: Arc<AtomicU64>,
+    worker_progress: Arc<Progress>,
 }
 
 impl Pruner {
     /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(db: Arc<DB>, num_historical_versions_to_keep: u64) -> Self {
+    pub fn new(db: Arc<DB>, config: &PruningConfig) -> Self {
         let (command_sender, command_receiver) = channel();
-        let worker_progress = Arc::new(AtomicU64::new(0));
-        let worker_progress_clone = Arc::clone(&worker_progress);
+        let worker_progress = Arc::new(
+            Progress::load(&db)
+                .unwrap_or_else(|e| unrecoverable!("Loading pruner progress failed: {:?}", e)),
+        );
+        let worker = Worker::new(db, command_receiver, Arc::clone(&worker_progress));
 
         let worker_thread = std::thread::Builder::new()
             .name("libradb_pruner".into())
-            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work_loop())
+            .spawn(move || worker.work_loop())
             .expect("Creating pruner thread should succeed.");
 
         Self {
-            num_historical_versions_to_keep,
+            windows: Self::windows(config),
             worker_thread: Some(worker_thread),
             command_sender: Mutex::new(command_sender),
             worker_progress,
         }
     }
 
+    fn windows(config: &PruningConfig) -> Vec<(PruningDimension, u64)> {
+        if !config.enabled {
+            return Vec::new();
+        }
+
+        // Events of pruned transactions can't be proven anymore, so they go together.
+        let event_window = match (config.event_window, config.ledger_window) {
+            (Some(event_window), Some(ledger_window)) => Some(min(event_window, ledger_window)),
+            (event_window, ledger_window) => event_window.or(ledger_window),
+        };
+
+        vec![
+            (PruningDimension::State, config.state_window),
+            (PruningDimension::Ledger, config.ledger_window),
+            (PruningDimension::Event, event_window),
+        ]
+        .into_iter()
+        .filter_map(|(dimension, window)| window.map(|window| (dimension, window)))
+        .collect()
+    }
+
+    /// Returns the least readable version of each dimension being pruned, given the latest
+    /// version.
+    fn targets(
+        &self,
+        latest_version: Version,
+    ) -> impl Iterator<Item = (PruningDimension, Version)> + '_ {
+        self.windows
+            .iter()
+            .filter(move |(_, window)| latest_version > *window)
+            .map(move |(dimension, window)| (*dimension, latest_version - window))
+    }
+
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
-        if latest_version > self.num_historical_versions_to_keep {
-            let least_readable_version = latest_version - self.num_historical_versions_to_keep;
-            self.command_sender
-                .lock()
-                .expect("command_sender to pruner thread should lock.")
+        let command_sender = self
+            .command_sender
+            .lock()
+            .expect("command_sender to pruner thread should lock.");
+        for (dimension, least_readable_version) in self.targets(latest_version) {
+            command_sender
                 .send(Command::Prune {
+                    dimension,
                     least_readable_version,
                 })
                 .expect("Receiver should not destruct prematurely.");
@@ -92,19 +191,15 @@ impl Pruner {
     pub fn wake_and_wait(&self, latest_version: Version) -> Result<()> {
         self.wake(latest_version);
 
-        if latest_version > self.num_historical_versions_to_keep {
-            let least_readable_version = latest_version - self.num_historical_versions_to_keep;
-            // Assuming no big pruning chunks will be issued by a test.
-            const TIMEOUT: Duration = Duration::from_secs(10);
-            let end = Instant::now() + TIMEOUT;
+        // Assuming no big pruning chunks will be issued by a test.
+        const TIMEOUT: Duration = Duration::from_secs(10);
+        let end = Instant::now() + TIMEOUT;
 
-            while Instant::now() < end {
-                if self.worker_progress.load(Ordering::Relaxed) >= least_readable_version {
-                    return Ok(());
-                }
+        for (dimension, least_readable_version) in self.targets(latest_version) {
+            while self.worker_progress.get(dimension) < least_readable_version {
+                ensure!(Instant::now() < end, "Timeout waiting for pruner worker.");
                 sleep(Duration::from_millis(1));
             }
-            bail!("Timeout waiting for pruner worker.");
         }
         Ok(())
     }
@@ -127,17 +222,20 @@ impl Drop for Pruner {
 
 enum Command {
     Quit,
-    Prune { least_readable_version: Version },
+    Prune {
+        dimension: PruningDimension,
+        least_readable_version: Version,
+    },
 }
 
 struct Worker {
     db: Arc<DB>,
     command_receiver: Receiver<Command>,
-    target_least_readable_version: Version,
-    /// Keeps a record of the pruning progress. If this equals to version `V`, we know versions
-    /// smaller than `V` are no longer readable.
-    /// This being an atomic value is to communicate the info with the Pruner thread (for tests).
-    least_readable_version: Arc<AtomicU64>,
+    /// The least readable version each dimension is being pruned towards.
+    target_least_readable_versions: HashMap<PruningDimension, Version>,
+    /// Keeps a record of the pruning progress of each dimension.
+    /// This being atomic values is to communicate the info with the Pruner thread (for tests).
+    least_readable_versions: Arc<Progress>,
     /// Indicates if there's NOT any pending work to do currently, to hint
     /// `Self::receive_commands()` to `recv()` blocking-ly.
     blocking_recv: bool,
@@ -151,13 +249,13 @@ impl Worker {
     fn new(
         db: Arc<DB>,
         command_receiver: Receiver<Command>,
-        least_readable_version: Arc<AtomicU64>,
+        least_readable_versions: Arc<Progress>,
     ) -> Self {
         Self {
             db,
             command_receiver,
-            least_readable_version,
-            target_least_readable_version: 0,
+            target_least_readable_versions: HashMap::new(),
+            least_readable_versions,
             blocking_recv: true,
             index_min_nonpurged_version: 0,
             index_purged_at: Instant::now(),
@@ -166,38 +264,82 @@ impl Worker {
 
     fn work_loop(mut self) {
         while self.receive_commands() {
-            // Process a reasonably small batch of work before trying to receive commands again,
-            // in case `Command::Quit` is received (that's when we should quit.)
-            match prune_state(
-                Arc::clone(&self.db),
-                self.least_readable_version.load(Ordering::Relaxed),
-                self.target_least_readable_version,
-                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
-            ) {
-                Ok(least_readable_version) => {
-                    // Make next recv() blocking if all done.
-                    self.blocking_recv =
-                        least_readable_version == self.target_least_readable_version;
-
-                    // Log the progress.
-                    self.least_readable_version
-                        .store(least_readable_version, Ordering::Relaxed);
-                    OP_COUNTER.set(
-                        "pruner.least_readable_state_version",
-                        least_readable_version as usize,
-                    );
-
-                    // Try to purge the log.
-                    if let Err(e) = self.maybe_purge_index() {
-                        crit!("Failed purging state state node index, ignored. Err: {}", e);
-                    }
+            // Process a reasonably small batch of work on each dimension before trying to receive
+            // commands again, in case `Command::Quit` is received (that's when we should quit.)
+            let mut all_done = true;
+            for (dimension, target_least_readable_version) in
+                self.target_least_readable_versions.clone()
+            {
+                let least_readable_version = self.least_readable_versions.get(dimension);
+                if least_readable_version >= target_least_readable_version {
+                    continue;
                 }
-                Err(e) => {
-                    crit!("Error pruning stale state nodes. {:?}", e);
-                    // On error, stop retrying vigorously by making next recv() blocking.
-                    self.blocking_recv = true;
+
+                match self.prune(
+                    dimension,
+                    least_readable_version,
+                    target_least_readable_version,
+                ) {
+                    Ok(new_least_readable_version) => {
+                        all_done &= new_least_readable_version >= target_least_readable_version;
+                        self.record_progress(
+                            dimension,
+                            least_readable_version,
+                            new_least_readable_version,
+                        );
+                    }
+                    Err(e) => {
+                        // On error, stop retrying vigorously by not counting it as pending work.
+                        crit!("Error pruning {} data. {:?}", dimension.as_ref(), e);
+                    }
                 }
             }
+            // Make next recv() blocking if all done.
+            self.blocking_recv = all_done;
+        }
+    }
+
+    fn prune(
+        &self,
+        dimension: PruningDimension,
+        least_readable_version: Version,
+        target_least_readable_version: Version,
+    ) -> Result<Version> {
+        let prune_fn = match dimension {
+            PruningDimension::State => prune_state,
+            PruningDimension::Ledger => prune_ledger,
+            PruningDimension::Event => prune_events,
+        };
+        prune_fn(
+            Arc::clone(&self.db),
+            least_readable_version,
+            target_least_readable_version,
+            Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+        )
+    }
+
+    fn record_progress(
+        &mut self,
+        dimension: PruningDimension,
+        old_least_readable_version: Version,
+        new_least_readable_version: Version,
+    ) {
+        self.least_readable_versions
+            .set(dimension, new_least_readable_version);
+        OP_COUNTER.set(
+            &format!("pruner.least_readable_{}_version", dimension.as_ref()),
+            new_least_readable_version as usize,
+        );
+        OP_COUNTER.inc_by(
+            &format!("pruner.pruned_{}_versions", dimension.as_ref()),
+            (new_least_readable_version - old_least_readable_version) as usize,
+        );
+
+        if dimension == PruningDimension::State {
+            // Try to purge the log.
+            if let Err(e) = self.maybe_purge_index() {
+                crit!("Failed purging state state node index, ignored. Err: {}", e);
+            }
         }
     }
 
@@ -227,10 +369,15 @@ impl Worker {
                 // On `Command::Quit` inform the outer loop to quit by returning `false`.
                 Command::Quit => return false,
                 Command::Prune {
+                    dimension,
                     least_readable_version,
                 } => {
-                    if least_readable_version > self.target_least_readable_version {
-                        self.target_least_readable_version = least_readable_version;
+                    let target = self
+                        .target_least_readable_versions
+                        .entry(dimension)
+                        .or_insert(0);
+                    if least_readable_version > *target {
+                        *target = least_readable_version;
                         // Switch to non-blocking to allow some work to be done after the
                         // channel has drained.
                         self.blocking_recv = false;
@@ -254,7 +401,7 @@ impl Worker {
         // this imposes at most one minute of work in vain after restarting.)
         let now = Instant::now();
         if now - self.index_purged_at > MIN_INTERVAL {
-            let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
+            let least_readable_version = self.least_readable_versions.get(PruningDimension::State);
 
             if least_readable_version - self.index_min_nonpurged_version + 1 > MIN_VERSIONS {
                 let new_min_non_purged_version = least_readable_version + 1;
@@ -332,29 +479,137 @@ pub fn prune_state(
     target_least_readable_version: Version,
     max_versions: usize,
 ) -> Result<Version> {
-    let indices = StaleNodeIndicesByVersionIterator::new(
+    let indices_by_version = StaleNodeIndicesByVersionIterator::new(
         &db,
         least_readable_version,
         target_least_readable_version,
     )?
     .take(max_versions) // Iterator<Item = Result<Vec<StaleNodeIndex>>>
-    .collect::<Result<Vec<_>>>()? // now Vec<Vec<StaleNodeIndex>>
-    .into_iter()
-    .flatten()
-    .collect::<Vec<_>>();
+    .collect::<Result<Vec<_>>>()?; // now Vec<Vec<StaleNodeIndex>>
 
-    if indices.is_empty() {
-        Ok(least_readable_version)
+    // If the iterator is drained, all nodes that became stale up to the target are gone.
+    let new_least_readable_version = if indices_by_version.len() < max_versions {
+        target_least_readable_version
     } else {
-        let new_least_readable_version = indices.last().expect("Should exist.").stale_since_version;
-        let mut batch = SchemaBatch::new();
-        indices
-            .into_iter()
-            .map(|index| batch.delete::<JellyfishMerkleNodeSchema>(&index.node_key))
-            .collect::<Result<_>>()?;
-        db.write_schemas(batch)?;
-        Ok(new_least_readable_version)
+        indices_by_version
+            .last()
+            .and_then(|indices| indices.last())
+            .expect("Should exist.")
+            .stale_since_version
+    };
+
+    let mut batch = SchemaBatch::new();
+    indices_by_version
+        .into_iter()
+        .flatten()
+        .map(|index| batch.delete::<JellyfishMerkleNodeSchema>(&index.node_key))
+        .collect::<Result<_>>()?;
+    batch.put::<PrunerProgressSchema>(&PruningDimension::State, &new_least_readable_version)?;
+    db.write_schemas(batch)?;
+
+    Ok(new_least_readable_version)
+}
+
+/// Prunes transactions before `target_least_readable_version`, by at most `max_versions` at a
+/// time, together with everything that is only needed to prove them.
+///
+/// The transaction accumulator keeps the roots of the frozen subtrees that cover the pruned
+/// transactions, since they are siblings in the proofs of all later transactions.
+fn prune_ledger(
+    db: Arc<DB>,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let new_least_readable_version = min(
+        target_least_readable_version,
+        least_readable_version + max_versions as u64,
+    );
+    let mut batch = SchemaBatch::new();
+
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+    for res in iter {
+        let (version, txn) = res?;
+        if version >= new_least_readable_version {
+            break;
+        }
+        // Entries of the secondary indices are deleted whether or not they are enabled, in case
+        // they were at some point.
+        if let Some(script) = txn.as_script() {
+            batch.delete::<TransactionByScriptHashSchema>(&(script.code_hash(), version))?;
+            for receiver in script.address_args() {
+                batch.delete::<TransactionByReceiverSchema>(&(receiver, version))?;
+            }
+        }
+        if let Transaction::UserTransaction(txn) = txn {
+            batch.delete::<TransactionByAccountSchema>(&(txn.sender(), txn.sequence_number()))?;
+        }
+        batch.delete::<TransactionSchema>(&version)?;
     }
+
+    for version in least_readable_version..new_least_readable_version {
+        batch.delete::<TransactionInfoSchema>(&version)?;
+        batch.delete::<LedgerCountersSchema>(&version)?;
+
+        // Walk up from the leaf, deleting every node whose subtree is fully pruned, until reaching
+        // the root of a frozen subtree. A left sibling is fully pruned once its right sibling is.
+        let last_pruned_leaf_index = new_least_readable_version - 1;
+        let mut position = Position::from_leaf_index(version);
+        while position.parent().is_freezable(last_pruned_leaf_index) {
+            batch.delete::<TransactionAccumulatorSchema>(&position)?;
+            if position.is_right_child() {
+                batch.delete::<TransactionAccumulatorSchema>(&position.sibling())?;
+            }
+            position = position.parent();
+        }
+    }
+
+    batch.put::<PrunerProgressSchema>(&PruningDimension::Ledger, &new_least_readable_version)?;
+    db.write_schemas(batch)?;
+
+    Ok(new_least_readable_version)
+}
+
+/// Prunes events emitted before `target_least_readable_version`, by at most `max_versions` at a
+/// time, together with the event-by-key index and the event accumulators.
+fn prune_events(
+    db: Arc<DB>,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let new_least_readable_version = min(
+        target_least_readable_version,
+        least_readable_version + max_versions as u64,
+    );
+    let mut batch = SchemaBatch::new();
+
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+    for res in iter {
+        let ((version, index), event) = res?;
+        if version >= new_least_readable_version {
+            break;
+        }
+        batch.delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
+        batch.delete::<EventSchema>(&(version, index))?;
+    }
+
+    let mut iter = db.iter::<EventAccumulatorSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+    for res in iter {
+        let ((version, position), _) = res?;
+        if version >= new_least_readable_version {
+            break;
+        }
+        batch.delete::<EventAccumulatorSchema>(&(version, position))?;
+    }
+
+    batch.put::<PrunerProgressSchema>(&PruningDimension::Event, &new_least_readable_version)?;
+    db.write_schemas(batch)?;
+
+    Ok(new_least_readable_version)
 }
 
 #[cfg(test)]
@@ -2,13 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::{change_set::ChangeSet, state_store::StateStore, LibraDB};
+use crate::{
+    change_set::ChangeSet, mock_genesis::db_with_mock_genesis, state_store::StateStore,
+    test_helper::arb_blocks_to_commit, LibraDB,
+};
 use libra_crypto::HashValue;
 use libra_tools::tempdir::TempPath;
 use libra_types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     account_state_blob::AccountStateBlob,
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::TransactionToCommit,
 };
+use proptest::prelude::*;
 use std::collections::HashMap;
 
 fn put_account_state_set(
@@ -54,7 +60,10 @@ fn test_pruner() {
     let state_store = &StateStore::new(Arc::clone(&db));
     let pruner = Pruner::new(
         Arc::clone(&db),
-        0, /* num_historical_versions_to_keep */
+        &PruningConfig {
+            state_window: Some(0),
+            ..PruningConfig::default()
+        },
     );
 
     let _root0 = put_account_state_set(
@@ -141,15 +150,17 @@ fn test_worker_quit_eagerly() {
         let worker = Worker::new(
             Arc::clone(&db),
             command_receiver,
-            Arc::new(AtomicU64::new(0)), /* progress */
+            Arc::new(Progress::default()),
         );
         command_sender
             .send(Command::Prune {
+                dimension: PruningDimension::State,
                 least_readable_version: 1,
             })
             .unwrap();
         command_sender
             .send(Command::Prune {
+                dimension: PruningDimension::State,
                 least_readable_version: 2,
             })
             .unwrap();
@@ -161,3 +172,111 @@ fn test_worker_quit_eagerly() {
         verify_state_in_store(state_store, address, Some(&value2), 2);
     }
 }
+
+#[test]
+fn test_pruning_windows() {
+    let config = PruningConfig {
+        enabled: true,
+        state_window: None,
+        ledger_window: Some(10),
+        event_window: Some(100),
+    };
+    // Events are never kept longer than the transactions emitting them.
+    assert_eq!(
+        Pruner::windows(&config),
+        vec![
+            (PruningDimension::Ledger, 10),
+            (PruningDimension::Event, 10)
+        ],
+    );
+
+    let config = PruningConfig {
+        ledger_window: None,
+        ..config
+    };
+    assert_eq!(
+        Pruner::windows(&config),
+        vec![(PruningDimension::Event, 100)],
+    );
+
+    let config = PruningConfig {
+        enabled: false,
+        ..config
+    };
+    assert!(Pruner::windows(&config).is_empty());
+}
+
+fn test_ledger_and_event_pruner_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    const WINDOW: u64 = 3;
+
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir)?;
+    let pruner = Pruner::new(
+        Arc::clone(&db.db),
+        &PruningConfig {
+            enabled: true,
+            state_window: None,
+            ledger_window: Some(WINDOW),
+            event_window: Some(WINDOW),
+        },
+    );
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+        pruner.wake_and_wait(cur_ver /* latest_version */)?;
+    }
+    let least_readable_version = cur_ver.saturating_sub(WINDOW);
+
+    // Everything since the least readable version can still be read and proven.
+    let ledger_info_with_sigs = db.ledger_store.get_latest_ledger_info()?;
+    let txn_list_with_proof = db.get_transactions(
+        least_readable_version,
+        cur_ver - least_readable_version + 1,
+        cur_ver,
+        true, /* fetch_events */
+    )?;
+    txn_list_with_proof.verify(
+        ledger_info_with_sigs.ledger_info(),
+        Some(least_readable_version),
+    )?;
+    db.get_startup_info()?;
+
+    // Everything before it is gone.
+    for version in 0..least_readable_version {
+        assert!(db.transaction_store.get_transaction(version).is_err());
+        assert!(db.ledger_store.get_transaction_info(version).is_err());
+        assert!(db.event_store.get_events_by_version(version)?.is_empty());
+        assert!(db.db.get::<LedgerCountersSchema>(&version)?.is_none());
+    }
+
+    // The progress survives a restart.
+    drop(pruner);
+    let progress = Progress::load(&db.db)?;
+    assert_eq!(
+        progress.get(PruningDimension::Ledger),
+        least_readable_version
+    );
+    assert_eq!(
+        progress.get(PruningDimension::Event),
+        least_readable_version
+    );
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_ledger_and_event_pruner(input in arb_blocks_to_commit()) {
+        test_ledger_and_event_pruner_impl(input).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the progress of the pruner.
+//!
+//! For each dimension the pruner works on, the least version that is still readable is recorded.
+//! It is updated in the same batch as the deletions, so the pruner resumes from where it was after
+//! a restart.
+//! ```text
+//! |<--key---->|<---------value-------->|
+//! | dimension | least readable version |
+//! ```
+
+use crate::{
+    pruner::PruningDimension,
+    schema::{ensure_slice_len_eq, PRUNER_PROGRESS_CF_NAME},
+};
+use byteorder::{BigEndian, ReadBytesExt};
+use failure::prelude::*;
+use libra_types::transaction::Version;
+use num_traits::{FromPrimitive, ToPrimitive};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    PrunerProgressSchema,
+    PruningDimension,
+    Version,
+    PRUNER_PROGRESS_CF_NAME
+);
+
+impl KeyCodec<PrunerProgressSchema> for PruningDimension {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(vec![self.to_u8().ok_or_else(|| {
+            format_err!("Failed to convert {:?} to u8.", self)
+        })?])
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<u8>())?;
+        PruningDimension::from_u8(data[0])
+            .ok_or_else(|| format_err!("Unknown pruning dimension {}.", data[0]))
+    }
+}
+
+impl ValueCodec<PrunerProgressSchema> for Version {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Version>())?;
+        Ok((&data[..]).read_u64::<BigEndian>()?)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,13 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use schemadb::schema::assert_encode_decode;
+use strum::IntoEnumIterator;
+
+#[test]
+fn test_encode_decode() {
+    for dimension in PruningDimension::iter() {
+        assert_encode_decode::<PrunerProgressSchema>(&dimension, &100);
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for an opt-in transaction index via which the
+//! versions of all user transactions whose scripts take `receiver` as an argument, e.g. payments
+//! to it, can be found, in ascending order. With the version one can resort to
+//! `TransactionSchema` for the transaction content.
+//!
+//! ```text
+//! |<-------key------->|
+//! | receiver | txn_ver |
+//! ```
+
+use crate::schema::{ensure_slice_len_eq, TRANSACTION_BY_RECEIVER_CF_NAME};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use libra_types::{
+    account_address::{AccountAddress, ADDRESS_LENGTH},
+    transaction::Version,
+};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::{convert::TryFrom, mem::size_of};
+
+define_schema!(
+    TransactionByReceiverSchema,
+    Key,
+    (),
+    TRANSACTION_BY_RECEIVER_CF_NAME
+);
+
+type Key = (AccountAddress, Version);
+
+impl KeyCodec<TransactionByReceiverSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (ref receiver, version) = *self;
+
+        let mut encoded = receiver.to_vec();
+        encoded.write_u64::<BigEndian>(version)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+
+        let receiver = AccountAddress::try_from(&data[..ADDRESS_LENGTH])?;
+        let version = (&data[ADDRESS_LENGTH..]).read_u64::<BigEndian>()?;
+
+        Ok((receiver, version))
+    }
+}
+
+impl ValueCodec<TransactionByReceiverSchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
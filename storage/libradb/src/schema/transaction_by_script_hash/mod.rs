@@ -0,0 +1,66 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for an opt-in transaction index via which the
+//! versions of all user transactions running the script with `script_hash` can be found, in
+//! ascending order. With the version one can resort to `TransactionSchema` for the transaction
+//! content.
+//!
+//! ```text
+//! |<--------key-------->|
+//! | script_hash | txn_ver |
+//! ```
+
+use crate::schema::{ensure_slice_len_eq, TRANSACTION_BY_SCRIPT_HASH_CF_NAME};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use libra_crypto::HashValue;
+use libra_types::transaction::Version;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    TransactionByScriptHashSchema,
+    Key,
+    (),
+    TRANSACTION_BY_SCRIPT_HASH_CF_NAME
+);
+
+type Key = (HashValue, Version);
+
+impl KeyCodec<TransactionByScriptHashSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (ref script_hash, version) = *self;
+
+        let mut encoded = script_hash.to_vec();
+        encoded.write_u64::<BigEndian>(version)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, HashValue::LENGTH + size_of::<Version>())?;
+
+        let script_hash = HashValue::from_slice(&data[..HashValue::LENGTH])?;
+        let version = (&data[HashValue::LENGTH..]).read_u64::<BigEndian>()?;
+
+        Ok((script_hash, version))
+    }
+}
+
+impl ValueCodec<TransactionByScriptHashSchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -17,14 +17,16 @@ use failure::prelude::*;
 use jellyfish_merkle::{
     iterator::JellyfishMerkleIterator,
     node_type::{LeafNode, Node, NodeKey},
-    JellyfishMerkleTree, TreeReader,
+    JellyfishMerkleTree, NodeBatch, TreeReader, TreeWriter,
 };
 use libra_crypto::{hash::CryptoHash, HashValue};
 use libra_types::{
-    account_address::AccountAddress, account_state_blob::AccountStateBlob,
-    proof::SparseMerkleProof, transaction::Version,
+    account_address::AccountAddress,
+    account_state_blob::AccountStateBlob,
+    proof::{SparseMerkleProof, SparseMerkleRangeProof},
+    transaction::Version,
 };
-use schemadb::DB;
+use schemadb::{ReadOptions, SchemaBatch, DB};
 use std::{collections::HashMap, sync::Arc};
 
 pub(crate) struct StateStore {
@@ -42,9 +44,17 @@ impl StateStore {
         address: AccountAddress,
         version: Version,
     ) -> Result<(Option<AccountStateBlob>, SparseMerkleProof)> {
-        let (blob, proof) =
-            JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)?;
-        Ok((blob, proof))
+        self.get_account_state_with_proof_by_key(address.hash(), version)
+    }
+
+    /// Get the account state blob given the key (hashed address) of the account and the proof of
+    /// it, which is a non-inclusion proof if the key doesn't exist.
+    pub fn get_account_state_with_proof_by_key(
+        &self,
+        key: HashValue,
+        version: Version,
+    ) -> Result<(Option<AccountStateBlob>, SparseMerkleProof)> {
+        JellyfishMerkleTree::new(self).get_with_proof(key, version)
     }
 
     /// Put the results generated by `account_state_sets` to `batch` and return the result root
@@ -101,7 +111,6 @@ impl StateStore {
 
     /// Returns an iterator that yields all accounts from left to right that is not less than
     /// `starting_key`, one entry at a time, at given version.
-    #[allow(dead_code)]
     pub fn iter_accounts<'a>(
         &'a self,
         version: Version,
@@ -109,6 +118,21 @@ impl StateStore {
     ) -> Result<JellyfishMerkleIterator<'a, Self>> {
         JellyfishMerkleIterator::new(self, version, starting_key)
     }
+
+    /// Get the proof that all accounts up to and including `rightmost_key` exist in the state
+    /// Merkle tree at `version`.
+    pub fn get_account_state_range_proof(
+        &self,
+        rightmost_key: HashValue,
+        version: Version,
+    ) -> Result<SparseMerkleRangeProof> {
+        JellyfishMerkleTree::new(self).get_range_proof(rightmost_key, version)
+    }
+
+    /// Get the root hash of the state Merkle tree at `version`.
+    pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
+        Ok(self.get_node(&NodeKey::new_empty_path(version))?.hash())
+    }
 }
 
 impl TreeReader for StateStore {
@@ -117,6 +141,37 @@ impl TreeReader for StateStore {
     }
 
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
-        unimplemented!();
+        // This is only called when resuming an interrupted restoration, in which case the DB holds
+        // nothing but the nodes restored so far, so a full scan is affordable.
+        let mut iter = self
+            .db
+            .iter::<JellyfishMerkleNodeSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        let mut rightmost: Option<(NodeKey, LeafNode)> = None;
+        for res in iter {
+            let (node_key, node) = res?;
+            if let Node::Leaf(leaf_node) = node {
+                let is_rightmost = match &rightmost {
+                    Some((_, prev_leaf)) => leaf_node.account_key() > prev_leaf.account_key(),
+                    None => true,
+                };
+                if is_rightmost {
+                    rightmost = Some((node_key, leaf_node));
+                }
+            }
+        }
+        Ok(rightmost)
+    }
+}
+
+impl TreeWriter for StateStore {
+    fn write_node_batch(&self, node_batch: &NodeBatch) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        node_batch
+            .iter()
+            .map(|(node_key, node)| batch.put::<JellyfishMerkleNodeSchema>(node_key, node))
+            .collect::<Result<Vec<()>>>()?;
+        self.db.write_schemas(batch)
     }
 }
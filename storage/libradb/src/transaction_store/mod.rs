@@ -5,24 +5,35 @@
 
 use crate::schema::transaction::TransactionSchema;
 use crate::{
-    change_set::ChangeSet, errors::LibraDbError,
-    schema::transaction_by_account::TransactionByAccountSchema,
+    change_set::ChangeSet,
+    errors::LibraDbError,
+    schema::{
+        transaction_by_account::TransactionByAccountSchema,
+        transaction_by_receiver::TransactionByReceiverSchema,
+        transaction_by_script_hash::TransactionByScriptHashSchema,
+    },
 };
 use failure::prelude::*;
+use libra_config::config::SecondaryIndexConfig;
+use libra_crypto::HashValue;
 use libra_types::{
     account_address::AccountAddress,
     transaction::{Transaction, Version},
 };
-use schemadb::DB;
+use schemadb::{ReadOptions, DB};
 use std::sync::Arc;
 
 pub(crate) struct TransactionStore {
     db: Arc<DB>,
+    secondary_indexes: SecondaryIndexConfig,
 }
 
 impl TransactionStore {
-    pub fn new(db: Arc<DB>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<DB>, secondary_indexes: SecondaryIndexConfig) -> Self {
+        Self {
+            db,
+            secondary_indexes,
+        }
     }
 
     /// Gets the version of a transaction by the sender `address` and `sequence_number`.
@@ -44,6 +55,64 @@ impl TransactionStore {
         Ok(None)
     }
 
+    /// Gets the versions of up to `limit` transactions running the script with `script_hash`,
+    /// starting from `start_version`.
+    pub fn lookup_transactions_by_script_hash(
+        &self,
+        script_hash: HashValue,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        ensure!(
+            self.secondary_indexes.transaction_by_script_hash,
+            "The transaction_by_script_hash index is not enabled."
+        );
+        let mut iter = self
+            .db
+            .iter::<TransactionByScriptHashSchema>(ReadOptions::default())?;
+        iter.seek(&(script_hash, start_version))?;
+
+        let mut versions = vec![];
+        for res in iter.take(limit as usize) {
+            let ((hash, version), ()) = res?;
+            if hash != script_hash || version > ledger_version {
+                break;
+            }
+            versions.push(version);
+        }
+        Ok(versions)
+    }
+
+    /// Gets the versions of up to `limit` transactions whose scripts take `receiver` as an
+    /// argument, starting from `start_version`.
+    pub fn lookup_transactions_by_receiver(
+        &self,
+        receiver: AccountAddress,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        ensure!(
+            self.secondary_indexes.transaction_by_receiver,
+            "The transaction_by_receiver index is not enabled."
+        );
+        let mut iter = self
+            .db
+            .iter::<TransactionByReceiverSchema>(ReadOptions::default())?;
+        iter.seek(&(receiver, start_version))?;
+
+        let mut versions = vec![];
+        for res in iter.take(limit as usize) {
+            let ((address, version), ()) = res?;
+            if address != receiver || version > ledger_version {
+                break;
+            }
+            versions.push(version);
+        }
+        Ok(versions)
+    }
+
     /// Get signed transaction given `version`
     pub fn get_transaction(&self, version: Version) -> Result<Transaction> {
         self.db
@@ -64,6 +133,18 @@ impl TransactionStore {
                 &version,
             )?;
         }
+        if let Some(script) = transaction.as_script() {
+            if self.secondary_indexes.transaction_by_script_hash {
+                cs.batch
+                    .put::<TransactionByScriptHashSchema>(&(script.code_hash(), version), &())?;
+            }
+            if self.secondary_indexes.transaction_by_receiver {
+                for receiver in script.address_args() {
+                    cs.batch
+                        .put::<TransactionByReceiverSchema>(&(receiver, version), &())?;
+                }
+            }
+        }
         cs.batch.put::<TransactionSchema>(&version, &transaction)?;
 
         Ok(())
@@ -3,6 +3,7 @@
 
 use super::*;
 use crate::LibraDB;
+use libra_config::config::StorageConfig;
 use libra_proptest_helpers::Index;
 use libra_tools::tempdir::TempPath;
 use libra_types::proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen};
@@ -58,4 +59,86 @@ proptest! {
 
         prop_assert!(store.get_transaction(ledger_version + 1).is_err());
     }
+    #[test]
+    fn test_secondary_indexes(
+        mut universe in any_with::<AccountInfoUniverse>(3),
+        gens in vec(
+            (any::<Index>(), any::<SignatureCheckedTransactionGen>()),
+            1..10
+        ),
+    ) {
+        let txns = gens
+            .into_iter()
+            .map(|(index, gen)| Transaction::UserTransaction(
+                gen.materialize(index, &mut universe).into_inner()
+            ))
+            .collect::<Vec<_>>();
+
+        let tmp_dir = TempPath::new();
+        let mut config = StorageConfig::default();
+        config.secondary_indexes = SecondaryIndexConfig {
+            transaction_by_script_hash: true,
+            transaction_by_receiver: true,
+        };
+        let db = LibraDB::new_with_config(&tmp_dir, &config);
+        let store = &db.transaction_store;
+
+        let mut cs = ChangeSet::new();
+        for (ver, txn) in txns.iter().enumerate() {
+            store
+                .put_transaction(ver as Version, &txn, &mut cs)
+                .unwrap();
+        }
+        store.db.write_schemas(cs.batch).unwrap();
+
+        let ledger_version = txns.len() as Version - 1;
+        for (ver, txn) in txns.iter().enumerate() {
+            let ver = ver as Version;
+            let script = match txn.as_script() {
+                Some(script) => script,
+                None => continue,
+            };
+
+            let versions = store
+                .lookup_transactions_by_script_hash(script.code_hash(), 0, 100, ledger_version)
+                .unwrap();
+            prop_assert!(versions.contains(&ver));
+            prop_assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+            prop_assert_eq!(
+                store
+                    .lookup_transactions_by_script_hash(script.code_hash(), ver, 1, ledger_version)
+                    .unwrap(),
+                vec![ver]
+            );
+            if ver > 0 {
+                prop_assert!(store
+                    .lookup_transactions_by_script_hash(script.code_hash(), ver, 1, ver - 1)
+                    .unwrap()
+                    .is_empty());
+            }
+
+            for receiver in script.address_args() {
+                prop_assert_eq!(
+                    store
+                        .lookup_transactions_by_receiver(receiver, ver, 1, ledger_version)
+                        .unwrap(),
+                    vec![ver]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_secondary_indexes_disabled() {
+    let tmp_dir = TempPath::new();
+    let db = LibraDB::new(&tmp_dir);
+    let store = &db.transaction_store;
+
+    assert!(store
+        .lookup_transactions_by_script_hash(HashValue::zero(), 0, 1, 0)
+        .is_err());
+    assert!(store
+        .lookup_transactions_by_receiver(AccountAddress::random(), 0, 1, 0)
+        .is_err());
 }
@@ -0,0 +1,613 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides [`LibraDB::verify`](crate::LibraDB::verify), which checks that the data in
+//! a `LibraDB` is internally consistent. It is meant to be run offline against a DB opened in
+//! read-only mode, e.g. after a disk incident, and reports every inconsistency it finds instead of
+//! stopping at the first one.
+//!
+//! The following is checked:
+//!   - The transaction accumulator root at the version of each stored ledger info matches the one
+//!     the ledger info commits to.
+//!   - Each transaction info matches the hash of the stored transaction, the root of the event
+//!     accumulator built from the stored events and the root of the state tree at its version.
+//!   - The state tree at a version hashes up to the state root hash of that version.
+//!   - The `TransactionByAccount` and `EventByKey` indices agree with the primary data, both ways.
+//!
+//! Data removed by the pruner is skipped.
+
+#[cfg(test)]
+mod test;
+
+use crate::{
+    pruner::PruningDimension,
+    schema::{
+        event::EventSchema, event_by_key::EventByKeySchema, ledger_info::LedgerInfoSchema,
+        transaction_by_account::TransactionByAccountSchema,
+    },
+    LibraDB,
+};
+use accumulator::HashReader;
+use failure::prelude::*;
+use jellyfish_merkle::{
+    node_type::{Node, NodeKey},
+    JellyfishMerkleTree, TreeReader,
+};
+use libra_crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
+    HashValue,
+};
+use libra_nibble::Nibble;
+use libra_types::{
+    proof::{accumulator::InMemoryAccumulator, position::Position},
+    transaction::{Transaction, Version},
+};
+use schemadb::ReadOptions;
+use std::{cmp::max, collections::BTreeMap, fmt};
+
+/// Number of accounts whose proofs are checked in sampling mode, in place of a full walk of the
+/// state tree.
+const NUM_SAMPLED_ACCOUNTS: u64 = 1000;
+
+/// Controls how thoroughly [`LibraDB::verify`] checks the DB.
+#[derive(Clone, Debug)]
+pub struct VerifyOptions {
+    /// Checks only one out of every `sample_rate` transactions and index entries, and checks the
+    /// state tree by verifying the proofs of a sample of accounts instead of walking all of it. 1
+    /// means checking everything.
+    pub sample_rate: u64,
+    /// Version of the state tree to check. Defaults to the latest version.
+    pub state_version: Option<Version>,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 1,
+            state_version: None,
+        }
+    }
+}
+
+/// The kinds of inconsistencies [`LibraDB::verify`] detects.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InconsistencyKind {
+    /// Data that is expected to exist is missing or can't be decoded.
+    UnreadableData,
+    /// The transaction accumulator doesn't match the root hash committed to by a ledger info.
+    TransactionAccumulatorRoot,
+    /// A leaf of the transaction accumulator doesn't match the transaction info.
+    TransactionAccumulatorLeaf,
+    /// The transaction doesn't match the hash in its transaction info.
+    TransactionHash,
+    /// The events of a transaction don't match the event root hash in its transaction info.
+    EventRootHash,
+    /// The state tree root doesn't match the state root hash in the transaction info.
+    StateRootHash,
+    /// A node of the state tree doesn't match the hash its parent has of it.
+    StateTreeNode,
+    /// The `TransactionByAccount` index disagrees with the transactions.
+    TransactionByAccount,
+    /// The `EventByKey` index disagrees with the events.
+    EventByKey,
+}
+
+/// An inconsistency found by [`LibraDB::verify`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Inconsistency {
+    pub kind: InconsistencyKind,
+    /// The version the inconsistent data belongs to, if known.
+    pub version: Option<Version>,
+    pub details: String,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{:?}]", self.kind)?;
+        if let Some(version) = self.version {
+            write!(f, " version {}:", version)?;
+        }
+        write!(f, " {}", self.details)
+    }
+}
+
+/// The result of [`LibraDB::verify`].
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub num_ledger_infos: usize,
+    pub num_transactions: usize,
+    pub num_state_tree_nodes: usize,
+    pub num_index_entries: usize,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl VerifyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Checked {} ledger infos, {} transactions, {} state tree nodes or accounts and {} \
+             index entries.",
+            self.num_ledger_infos,
+            self.num_transactions,
+            self.num_state_tree_nodes,
+            self.num_index_entries,
+        )?;
+        if self.is_consistent() {
+            return writeln!(f, "No inconsistency found.");
+        }
+        writeln!(f, "Found {} inconsistencies:", self.inconsistencies.len())?;
+        for inconsistency in &self.inconsistencies {
+            writeln!(f, "  {}", inconsistency)?;
+        }
+        Ok(())
+    }
+}
+
+impl LibraDB {
+    // ================================ Verification APIs ================================
+    /// Checks the consistency of all data in the DB as described in the [module
+    /// doc](crate::verify). Inconsistencies are collected into the returned report; an error is
+    /// only returned if the check can't be carried out at all.
+    pub fn verify(&self, opts: &VerifyOptions) -> Result<VerifyReport> {
+        ensure!(opts.sample_rate > 0, "Sample rate must be positive.");
+
+        let mut verifier = Verifier {
+            db: self,
+            opts,
+            report: VerifyReport::default(),
+        };
+        verifier.run()?;
+        Ok(verifier.report)
+    }
+}
+
+struct Verifier<'a> {
+    db: &'a LibraDB,
+    opts: &'a VerifyOptions,
+    report: VerifyReport,
+}
+
+impl<'a> Verifier<'a> {
+    fn run(&mut self) -> Result<()> {
+        let latest_version = match self.db.ledger_store.get_latest_transaction_info_option()? {
+            Some((version, _)) => version,
+            None => return Ok(()),
+        };
+        let first_version = self.db.least_readable_version(PruningDimension::Ledger)?;
+        let first_event_version = max(
+            first_version,
+            self.db.least_readable_version(PruningDimension::Event)?,
+        );
+        let first_state_version = max(
+            first_version,
+            self.db.least_readable_version(PruningDimension::State)?,
+        );
+
+        let expected_root_hashes = self.verify_ledger_infos(first_version)?;
+        self.verify_transactions(
+            first_version,
+            first_event_version,
+            first_state_version,
+            latest_version,
+            &expected_root_hashes,
+        );
+        self.verify_state_tree(self.opts.state_version.unwrap_or(latest_version));
+        self.verify_transaction_by_account(first_version, latest_version)?;
+        self.verify_event_by_key(first_event_version, latest_version)?;
+
+        Ok(())
+    }
+
+    fn is_sampled(&self, index: u64) -> bool {
+        index % self.opts.sample_rate == 0
+    }
+
+    /// Runs `check`, which returns the details of the inconsistency it finds, if any. Errors are
+    /// recorded as unreadable data.
+    fn check<F>(&mut self, kind: InconsistencyKind, version: Option<Version>, check: F)
+    where
+        F: FnOnce() -> Result<Option<String>>,
+    {
+        let (kind, details) = match check() {
+            Ok(None) => return,
+            Ok(Some(details)) => (kind, details),
+            Err(err) => (InconsistencyKind::UnreadableData, err.to_string()),
+        };
+        self.report.inconsistencies.push(Inconsistency {
+            kind,
+            version,
+            details,
+        });
+    }
+
+    /// Checks the transaction accumulator root of every ledger info that isn't pruned against the
+    /// frozen subtrees in the DB, and returns the root hashes by version.
+    fn verify_ledger_infos(
+        &mut self,
+        first_version: Version,
+    ) -> Result<BTreeMap<Version, HashValue>> {
+        let db = self.db;
+        let mut expected_root_hashes = BTreeMap::new();
+
+        let mut iter = db.db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        for res in iter {
+            let (epoch, ledger_info_with_sigs) = match res {
+                Ok(kv) => kv,
+                Err(err) => {
+                    self.check(InconsistencyKind::UnreadableData, None, || Err(err));
+                    continue;
+                }
+            };
+            let ledger_info = ledger_info_with_sigs.ledger_info();
+            let version = ledger_info.version();
+            if version < first_version {
+                continue;
+            }
+            self.report.num_ledger_infos += 1;
+
+            let expected_root_hash = ledger_info.transaction_accumulator_hash();
+            expected_root_hashes.insert(version, expected_root_hash);
+            self.check(
+                InconsistencyKind::TransactionAccumulatorRoot,
+                Some(version),
+                || {
+                    let root_hash = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+                        db.ledger_store.get_ledger_frozen_subtree_hashes(version)?,
+                        version + 1,
+                    )?
+                    .root_hash();
+                    Ok(if root_hash == expected_root_hash {
+                        None
+                    } else {
+                        Some(format!(
+                            "Ledger info of epoch {} has root hash {:x}, frozen subtrees have {:x}.",
+                            epoch, expected_root_hash, root_hash,
+                        ))
+                    })
+                },
+            );
+        }
+
+        Ok(expected_root_hashes)
+    }
+
+    fn verify_transactions(
+        &mut self,
+        first_version: Version,
+        first_event_version: Version,
+        first_state_version: Version,
+        latest_version: Version,
+        expected_root_hashes: &BTreeMap<Version, HashValue>,
+    ) {
+        let db = self.db;
+
+        // Without sampling, the accumulator is also rebuilt from the transaction infos, which
+        // catches internal nodes of the accumulator that don't match the leaves.
+        let mut accumulator = if self.opts.sample_rate == 1 {
+            let frozen_subtree_hashes = if first_version == 0 {
+                Ok(Vec::new())
+            } else {
+                db.ledger_store
+                    .get_ledger_frozen_subtree_hashes(first_version - 1)
+            };
+            frozen_subtree_hashes
+                .and_then(|hashes| {
+                    InMemoryAccumulator::<TransactionAccumulatorHasher>::new(hashes, first_version)
+                })
+                .map_err(|err| {
+                    self.check(InconsistencyKind::UnreadableData, None, || Err(err));
+                })
+                .ok()
+        } else {
+            None
+        };
+
+        for version in first_version..=latest_version {
+            let txn_info = match db.ledger_store.get_transaction_info(version) {
+                Ok(txn_info) => txn_info,
+                Err(err) => {
+                    self.check(InconsistencyKind::UnreadableData, Some(version), || {
+                        Err(err)
+                    });
+                    // The rebuilt accumulator is meaningless from now on.
+                    accumulator = None;
+                    continue;
+                }
+            };
+            let txn_info_hash = txn_info.hash();
+
+            if let Some(acc) = accumulator.take() {
+                let acc = acc.append(&[txn_info_hash]);
+                if let Some(expected_root_hash) = expected_root_hashes.get(&version) {
+                    let root_hash = acc.root_hash();
+                    self.check(
+                        InconsistencyKind::TransactionAccumulatorRoot,
+                        Some(version),
+                        || {
+                            Ok(if root_hash == *expected_root_hash {
+                                None
+                            } else {
+                                Some(format!(
+                                    "Ledger info has root hash {:x}, transaction infos add up to \
+                                     {:x}.",
+                                    expected_root_hash, root_hash,
+                                ))
+                            })
+                        },
+                    );
+                }
+                accumulator = Some(acc);
+            }
+
+            if !self.is_sampled(version - first_version) {
+                continue;
+            }
+            self.report.num_transactions += 1;
+
+            self.check(
+                InconsistencyKind::TransactionAccumulatorLeaf,
+                Some(version),
+                || {
+                    let leaf_hash = db.ledger_store.get(Position::from_leaf_index(version))?;
+                    Ok(if leaf_hash == txn_info_hash {
+                        None
+                    } else {
+                        Some(format!(
+                            "Accumulator leaf is {:x}, transaction info hashes to {:x}.",
+                            leaf_hash, txn_info_hash,
+                        ))
+                    })
+                },
+            );
+
+            let mut txn = None;
+            self.check(InconsistencyKind::TransactionHash, Some(version), || {
+                let txn_hash = txn
+                    .get_or_insert(db.transaction_store.get_transaction(version)?)
+                    .hash();
+                Ok(if txn_hash == txn_info.transaction_hash() {
+                    None
+                } else {
+                    Some(format!(
+                        "Transaction hashes to {:x}, transaction info has {:x}.",
+                        txn_hash,
+                        txn_info.transaction_hash(),
+                    ))
+                })
+            });
+            if let Some(Transaction::UserTransaction(signed_txn)) = txn {
+                let key = (signed_txn.sender(), signed_txn.sequence_number());
+                self.check(
+                    InconsistencyKind::TransactionByAccount,
+                    Some(version),
+                    || {
+                        let indexed_version = db.db.get::<TransactionByAccountSchema>(&key)?;
+                        Ok(if indexed_version == Some(version) {
+                            None
+                        } else {
+                            Some(format!(
+                                "Entry ({}, {}) is {:?}.",
+                                key.0, key.1, indexed_version,
+                            ))
+                        })
+                    },
+                );
+            }
+
+            if version >= first_event_version {
+                let mut events = None;
+                self.check(InconsistencyKind::EventRootHash, Some(version), || {
+                    let event_hashes: Vec<HashValue> = events
+                        .get_or_insert(db.event_store.get_events_by_version(version)?)
+                        .iter()
+                        .map(CryptoHash::hash)
+                        .collect();
+                    let event_root_hash =
+                        InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                            .root_hash();
+                    Ok(if event_root_hash == txn_info.event_root_hash() {
+                        None
+                    } else {
+                        Some(format!(
+                            "Events add up to {:x}, transaction info has {:x}.",
+                            event_root_hash,
+                            txn_info.event_root_hash(),
+                        ))
+                    })
+                });
+                for (index, event) in events.into_iter().flatten().enumerate() {
+                    let key = (*event.key(), event.sequence_number());
+                    self.check(InconsistencyKind::EventByKey, Some(version), || {
+                        let indexed = db.db.get::<EventByKeySchema>(&key)?;
+                        Ok(if indexed == Some((version, index as u64)) {
+                            None
+                        } else {
+                            Some(format!("Entry ({}, {}) is {:?}.", key.0, key.1, indexed))
+                        })
+                    });
+                }
+            }
+
+            if version >= first_state_version {
+                self.check(InconsistencyKind::StateRootHash, Some(version), || {
+                    let state_root_hash = db.state_store.get_root_hash(version)?;
+                    Ok(if state_root_hash == txn_info.state_root_hash() {
+                        None
+                    } else {
+                        Some(format!(
+                            "State tree root is {:x}, transaction info has {:x}.",
+                            state_root_hash,
+                            txn_info.state_root_hash(),
+                        ))
+                    })
+                });
+            }
+        }
+    }
+
+    /// Without sampling, walks the whole state tree at `version` and checks every node against the
+    /// hash its parent has of it. Otherwise checks the proofs of a sample of accounts.
+    fn verify_state_tree(&mut self, version: Version) {
+        let db = self.db;
+        let expected_root_hash = match db.ledger_store.get_transaction_info(version) {
+            Ok(txn_info) => txn_info.state_root_hash(),
+            Err(err) => {
+                self.check(InconsistencyKind::UnreadableData, Some(version), || {
+                    Err(err)
+                });
+                return;
+            }
+        };
+
+        if self.opts.sample_rate > 1 {
+            let tree = JellyfishMerkleTree::new(&db.state_store);
+            for i in 0..NUM_SAMPLED_ACCOUNTS {
+                self.report.num_state_tree_nodes += 1;
+                let key = HashValue::from_sha3_256(&i.to_be_bytes());
+                self.check(InconsistencyKind::StateTreeNode, Some(version), || {
+                    let (blob, proof) = tree.get_with_proof(key, version)?;
+                    Ok(proof
+                        .verify(expected_root_hash, key, blob.as_ref())
+                        .err()
+                        .map(|err| format!("Proof of account {:x} is invalid: {}", key, err)))
+                });
+            }
+            return;
+        }
+
+        // Each entry is a node to check, the hash its parent has of it and whether its parent
+        // has it as a leaf.
+        let mut stack = vec![(NodeKey::new_empty_path(version), expected_root_hash, None)];
+        while let Some((node_key, expected_hash, expected_is_leaf)) = stack.pop() {
+            self.report.num_state_tree_nodes += 1;
+            let node = match db.state_store.get_node(&node_key) {
+                Ok(node) => node,
+                Err(err) => {
+                    self.check(InconsistencyKind::UnreadableData, Some(version), || {
+                        Err(err)
+                    });
+                    continue;
+                }
+            };
+
+            self.check(InconsistencyKind::StateTreeNode, Some(version), || {
+                let hash = node.hash();
+                Ok(if hash != expected_hash {
+                    Some(format!(
+                        "Node {:?} hashes to {:x}, expected {:x}.",
+                        node_key, hash, expected_hash,
+                    ))
+                } else if expected_is_leaf.map_or(false, |is_leaf| is_leaf != node.is_leaf()) {
+                    Some(format!("Node {:?} is of the wrong type.", node_key))
+                } else {
+                    None
+                })
+            });
+
+            if let Node::Internal(internal_node) = &node {
+                for nibble in (0..16u8).map(Nibble::from) {
+                    if let Some(child) = internal_node.child(nibble) {
+                        stack.push((
+                            node_key.gen_child_node_key(child.version, nibble),
+                            child.hash,
+                            Some(child.is_leaf),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that each entry of the `TransactionByAccount` index points to the transaction it
+    /// stands for.
+    fn verify_transaction_by_account(
+        &mut self,
+        first_version: Version,
+        latest_version: Version,
+    ) -> Result<()> {
+        let db = self.db;
+        let mut iter = db
+            .db
+            .iter::<TransactionByAccountSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        for (index, res) in iter.enumerate() {
+            if !self.is_sampled(index as u64) {
+                continue;
+            }
+            self.report.num_index_entries += 1;
+
+            self.check(InconsistencyKind::TransactionByAccount, None, || {
+                let ((address, sequence_number), version) = res?;
+                if version < first_version || version > latest_version {
+                    return Ok(Some(format!(
+                        "Entry ({}, {}) points to version {}, out of range [{}, {}].",
+                        address, sequence_number, version, first_version, latest_version,
+                    )));
+                }
+                Ok(match db.transaction_store.get_transaction(version)? {
+                    Transaction::UserTransaction(txn)
+                        if txn.sender() == address && txn.sequence_number() == sequence_number =>
+                    {
+                        None
+                    }
+                    _ => Some(format!(
+                        "Entry ({}, {}) points to version {} holding another transaction.",
+                        address, sequence_number, version,
+                    )),
+                })
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks that each entry of the `EventByKey` index points to the event it stands for.
+    fn verify_event_by_key(
+        &mut self,
+        first_version: Version,
+        latest_version: Version,
+    ) -> Result<()> {
+        let db = self.db;
+        let mut iter = db.db.iter::<EventByKeySchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        for (index, res) in iter.enumerate() {
+            if !self.is_sampled(index as u64) {
+                continue;
+            }
+            self.report.num_index_entries += 1;
+
+            self.check(InconsistencyKind::EventByKey, None, || {
+                let ((key, sequence_number), (version, event_index)) = res?;
+                if version < first_version || version > latest_version {
+                    return Ok(Some(format!(
+                        "Entry ({}, {}) points to version {}, out of range [{}, {}].",
+                        key, sequence_number, version, first_version, latest_version,
+                    )));
+                }
+                Ok(match db.db.get::<EventSchema>(&(version, event_index))? {
+                    Some(event)
+                        if *event.key() == key && event.sequence_number() == sequence_number =>
+                    {
+                        None
+                    }
+                    _ => Some(format!(
+                        "Entry ({}, {}) points to event {} of version {}, which is missing or \
+                         another event.",
+                        key, sequence_number, event_index, version,
+                    )),
+                })
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    mock_genesis::db_with_mock_genesis,
+    schema::{transaction::TransactionSchema, transaction_info::TransactionInfoSchema},
+    test_helper::arb_blocks_to_commit,
+};
+use libra_config::config::{PruningConfig, StorageConfig};
+use libra_tools::tempdir::TempPath;
+use libra_types::{crypto_proxies::LedgerInfoWithSignatures, transaction::TransactionToCommit};
+use proptest::prelude::*;
+
+fn kinds(report: &VerifyReport) -> Vec<InconsistencyKind> {
+    report.inconsistencies.iter().map(|i| i.kind).collect()
+}
+
+fn save_blocks(
+    db: &LibraDB,
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<Version> {
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    Ok(cur_ver)
+}
+
+fn test_verify_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir)?;
+    let cur_ver = save_blocks(&db, input)?;
+
+    let report = db.verify(&VerifyOptions::default())?;
+    assert!(report.is_consistent(), "{}", report);
+    assert_eq!(report.num_transactions as u64, cur_ver + 1);
+    let sampled_report = db.verify(&VerifyOptions {
+        sample_rate: 2,
+        state_version: None,
+    })?;
+    assert!(sampled_report.is_consistent(), "{}", sampled_report);
+    assert_eq!(sampled_report.num_transactions as u64, cur_ver / 2 + 1);
+
+    // The tampering below needs a transaction on top of the genesis one.
+    if cur_ver == 0 {
+        return Ok(());
+    }
+
+    // Swap in another transaction.
+    let txn = db.db.get::<TransactionSchema>(&cur_ver)?.unwrap();
+    db.db.put::<TransactionSchema>(&(cur_ver - 1), &txn)?;
+    let report = db.verify(&VerifyOptions::default())?;
+    assert!(kinds(&report).contains(&InconsistencyKind::TransactionHash));
+    assert_eq!(report.inconsistencies[0].version, Some(cur_ver - 1));
+
+    // Tamper with the latest transaction info, which is caught by the accumulator.
+    let txn_info = db.db.get::<TransactionInfoSchema>(&0)?.unwrap();
+    db.db.put::<TransactionInfoSchema>(&cur_ver, &txn_info)?;
+    let report = db.verify(&VerifyOptions::default())?;
+    let kinds = kinds(&report);
+    assert!(kinds.contains(&InconsistencyKind::TransactionAccumulatorRoot));
+    assert!(kinds.contains(&InconsistencyKind::TransactionAccumulatorLeaf));
+
+    Ok(())
+}
+
+fn test_verify_pruned_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    const WINDOW: u64 = 3;
+
+    let tmp_dir = TempPath::new();
+    db_with_mock_genesis(&tmp_dir)?;
+    let mut config = StorageConfig::default();
+    config.pruning = PruningConfig {
+        enabled: true,
+        state_window: Some(WINDOW),
+        ledger_window: Some(WINDOW),
+        event_window: Some(WINDOW),
+    };
+    let db = LibraDB::new_with_config(&tmp_dir, &config);
+    let cur_ver = save_blocks(&db, input)?;
+    db.pruner.as_ref().unwrap().wake_and_wait(cur_ver)?;
+
+    let report = db.verify(&VerifyOptions::default())?;
+    assert!(report.is_consistent(), "{}", report);
+    assert_eq!(
+        report.num_transactions as u64,
+        cur_ver - cur_ver.saturating_sub(WINDOW) + 1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_verify_rejects_zero_sample_rate() {
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir).unwrap();
+    assert!(db
+        .verify(&VerifyOptions {
+            sample_rate: 0,
+            state_version: None,
+        })
+        .is_err());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_verify(input in arb_blocks_to_commit()) {
+        test_verify_impl(input).unwrap();
+    }
+
+    #[test]
+    fn test_verify_pruned(input in arb_blocks_to_commit()) {
+        test_verify_pruned_impl(input).unwrap();
+    }
+}
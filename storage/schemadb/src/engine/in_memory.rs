@@ -0,0 +1,195 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{KvEngine, RawBatch, RawIterator, WriteOp};
+use crate::{ColumnFamilyName, ReadOptions};
+use failure::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound::{Excluded, Included, Unbounded},
+    path::Path,
+    sync::RwLock,
+};
+
+type ColumnFamily = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// The [`KvEngine`] keeping each column family in a `BTreeMap` in memory. All data is lost once the
+/// engine is dropped.
+///
+/// Unlike RocksDB iterators, iterators of this engine don't read from a snapshot: moving one
+/// forward picks up the next key present at that moment, which may have been written after the
+/// iterator was created.
+#[derive(Debug)]
+pub struct InMemoryEngine {
+    cfs: RwLock<HashMap<ColumnFamilyName, ColumnFamily>>,
+}
+
+impl InMemoryEngine {
+    /// Creates an empty engine with all the column families in `cf_names`.
+    pub fn new(cf_names: &[ColumnFamilyName]) -> Self {
+        Self {
+            cfs: RwLock::new(
+                cf_names
+                    .iter()
+                    .map(|cf_name| (*cf_name, ColumnFamily::new()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn read_cf<T>(&self, cf_name: &str, f: impl FnOnce(&ColumnFamily) -> T) -> Result<T> {
+        let cfs = self.cfs.read().unwrap();
+        let cf = cfs.get(cf_name).ok_or_else(|| unknown_cf(cf_name))?;
+        Ok(f(cf))
+    }
+
+    fn write_cf<T>(&self, cf_name: &str, f: impl FnOnce(&mut ColumnFamily) -> T) -> Result<T> {
+        let mut cfs = self.cfs.write().unwrap();
+        let cf = cfs.get_mut(cf_name).ok_or_else(|| unknown_cf(cf_name))?;
+        Ok(f(cf))
+    }
+}
+
+fn unknown_cf(cf_name: &str) -> Error {
+    format_err!("Column family not found: {}", cf_name)
+}
+
+fn to_owned_kv((key, value): (&Vec<u8>, &Vec<u8>)) -> (Vec<u8>, Vec<u8>) {
+    (key.clone(), value.clone())
+}
+
+impl KvEngine for InMemoryEngine {
+    fn get(&self, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.read_cf(cf_name, |cf| cf.get(key).cloned())
+    }
+
+    fn put(&self, cf_name: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.write_cf(cf_name, |cf| {
+            cf.insert(key.to_vec(), value.to_vec());
+        })
+    }
+
+    fn range_delete(&self, cf_name: &str, begin: &[u8], end: &[u8]) -> Result<()> {
+        self.write_cf(cf_name, |cf| {
+            let mut deleted = cf.split_off(begin);
+            let mut kept = deleted.split_off(end);
+            cf.append(&mut kept);
+        })
+    }
+
+    fn write_batch(&self, batch: &RawBatch) -> Result<()> {
+        let mut cfs = self.cfs.write().unwrap();
+        // Check all column families before touching any, so that the batch is applied atomically.
+        if let Some(cf_name) = batch.keys().find(|cf_name| !cfs.contains_key(*cf_name)) {
+            return Err(unknown_cf(cf_name));
+        }
+        for (cf_name, rows) in batch {
+            let cf = cfs.get_mut(cf_name).expect("Checked above.");
+            for (key, write_op) in rows {
+                match write_op {
+                    WriteOp::Value(value) => cf.insert(key.clone(), value.clone()),
+                    WriteOp::Deletion => cf.remove(key),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn iter<'a>(&'a self, cf_name: &str, _opts: ReadOptions) -> Result<Box<dyn RawIterator + 'a>> {
+        self.read_cf(cf_name, |_| ())?;
+        Ok(Box::new(InMemoryIterator {
+            engine: self,
+            cf_name: cf_name.to_string(),
+            current: None,
+        }))
+    }
+
+    fn create_checkpoint(&self, _path: &Path) -> Result<()> {
+        bail!("Checkpoints are not supported by the in-memory engine.")
+    }
+
+    fn get_approximate_sizes_cf(&self) -> Result<BTreeMap<String, u64>> {
+        Ok(self
+            .cfs
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(cf_name, cf)| {
+                let size = cf
+                    .iter()
+                    .map(|(key, value)| (key.len() + value.len()) as u64)
+                    .sum();
+                (cf_name.to_string(), size)
+            })
+            .collect())
+    }
+
+    fn get_statistics_tickers(&self) -> BTreeMap<&'static str, u64> {
+        BTreeMap::new()
+    }
+
+    fn flush_all(&self, _sync: bool) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct InMemoryIterator<'a> {
+    engine: &'a InMemoryEngine,
+    cf_name: String,
+    /// The key-value pair the iterator is on.
+    current: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl<'a> InMemoryIterator<'a> {
+    /// Moves the iterator to the key-value pair found by `locate`.
+    fn reposition(
+        &mut self,
+        locate: impl FnOnce(&ColumnFamily) -> Option<(Vec<u8>, Vec<u8>)>,
+    ) -> bool {
+        self.current = self
+            .engine
+            .read_cf(&self.cf_name, locate)
+            .expect("Column families are never dropped.");
+        self.current.is_some()
+    }
+}
+
+impl<'a> RawIterator for InMemoryIterator<'a> {
+    fn seek_to_first(&mut self) -> bool {
+        self.reposition(|cf| cf.iter().next().map(to_owned_kv))
+    }
+
+    fn seek_to_last(&mut self) -> bool {
+        self.reposition(|cf| cf.iter().next_back().map(to_owned_kv))
+    }
+
+    fn seek(&mut self, key: &[u8]) -> bool {
+        self.reposition(|cf| {
+            cf.range::<[u8], _>((Included(key), Unbounded))
+                .next()
+                .map(to_owned_kv)
+        })
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> bool {
+        self.reposition(|cf| {
+            cf.range::<[u8], _>((Unbounded, Included(key)))
+                .next_back()
+                .map(to_owned_kv)
+        })
+    }
+
+    fn kv(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        self.current.clone()
+    }
+
+    fn next(&mut self) {
+        if let Some((key, _)) = self.current.take() {
+            self.reposition(|cf| {
+                cf.range::<[u8], _>((Excluded(key.as_slice()), Unbounded))
+                    .next()
+                    .map(to_owned_kv)
+            });
+        }
+    }
+}
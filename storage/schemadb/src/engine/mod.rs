@@ -0,0 +1,86 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the interface of the key-value engines a [`DB`](crate::DB) can be backed
+//! by, along with the two engines provided: [`RocksdbEngine`], which persists data on disk, and
+//! [`InMemoryEngine`], which keeps everything in memory and is meant for tests and experiments.
+
+mod in_memory;
+mod rocksdb_engine;
+
+pub use in_memory::InMemoryEngine;
+pub use rocksdb_engine::RocksdbEngine;
+
+use crate::{ColumnFamilyName, ReadOptions};
+use failure::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    path::Path,
+};
+
+/// A single update to a key in a write batch.
+#[derive(Debug)]
+pub enum WriteOp {
+    Value(Vec<u8>),
+    Deletion,
+}
+
+/// Updates to be applied atomically, grouped by column family.
+pub type RawBatch = HashMap<ColumnFamilyName, BTreeMap<Vec<u8>, WriteOp>>;
+
+/// An ordered key-value store with column families, underlying a [`DB`](crate::DB). Keys and
+/// values are raw bytes and keys are ordered lexicographically within each column family.
+///
+/// Reads and writes referring to a column family the engine was not opened with fail.
+pub trait KvEngine: Debug + Send + Sync {
+    /// Reads the value of `key`, if any.
+    fn get(&self, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Writes a single key-value pair.
+    fn put(&self, cf_name: &str, key: &[u8], value: &[u8]) -> Result<()>;
+
+    /// Deletes all keys in range [`begin`, `end`).
+    fn range_delete(&self, cf_name: &str, begin: &[u8], end: &[u8]) -> Result<()>;
+
+    /// Applies all updates in `batch` atomically.
+    fn write_batch(&self, batch: &RawBatch) -> Result<()>;
+
+    /// Returns an unpositioned iterator over column family `cf_name`.
+    fn iter<'a>(&'a self, cf_name: &str, opts: ReadOptions) -> Result<Box<dyn RawIterator + 'a>>;
+
+    /// Creates a consistent point-in-time copy of the db at `path`, which must not exist yet.
+    fn create_checkpoint(&self, path: &Path) -> Result<()>;
+
+    /// Returns the approximate size of each column family in bytes.
+    fn get_approximate_sizes_cf(&self) -> Result<BTreeMap<String, u64>>;
+
+    /// Returns the values of the statistics tickers maintained by the engine, if any.
+    fn get_statistics_tickers(&self) -> BTreeMap<&'static str, u64>;
+
+    /// Flushes any data buffered in memory to persistent storage. If `sync` is true, waits until
+    /// it's done.
+    fn flush_all(&self, sync: bool) -> Result<()>;
+}
+
+/// A cursor over the key-value pairs of a column family in key order. All seeks return whether the
+/// iterator ends up on a valid key-value pair.
+pub trait RawIterator {
+    /// Seeks to the first key.
+    fn seek_to_first(&mut self) -> bool;
+
+    /// Seeks to the last key.
+    fn seek_to_last(&mut self) -> bool;
+
+    /// Seeks to the first key equal to or greater than `key`.
+    fn seek(&mut self, key: &[u8]) -> bool;
+
+    /// Seeks to the last key equal to or less than `key`.
+    fn seek_for_prev(&mut self, key: &[u8]) -> bool;
+
+    /// Returns the key-value pair the iterator is on, or `None` if it's not on a valid one.
+    fn kv(&self) -> Option<(Vec<u8>, Vec<u8>)>;
+
+    /// Moves on to the next key.
+    fn next(&mut self);
+}
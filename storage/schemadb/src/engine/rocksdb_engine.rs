@@ -0,0 +1,431 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{KvEngine, RawBatch, RawIterator, WriteOp};
+use crate::{
+    ColumnFamilyName, ColumnFamilyOptions, ColumnFamilyOptionsMap, ReadOptions, DEFAULT_CF_NAME,
+    OP_COUNTER,
+};
+use failure::prelude::*;
+use libra_config::config::{
+    ColumnFamilyConfig, RocksdbCompactionStyle, RocksdbCompression, RocksdbConfig,
+};
+use rocksdb::{
+    rocksdb_options::ColumnFamilyDescriptor, BlockBasedOptions, CFHandle, Cache, DBCompactionStyle,
+    DBCompressionType, DBOptions, LRUCacheOptions, TickerType, Writable, WriteOptions,
+};
+use std::{collections::BTreeMap, path::Path};
+
+/// Checks underlying Rocksdb instance existence by checking `CURRENT` file existence, the same way
+/// Rocksdb adopts to detect db existence.
+fn db_exists(path: &Path) -> bool {
+    let rocksdb_current_file = path.join("CURRENT");
+    rocksdb_current_file.is_file()
+}
+
+/// All the RocksDB methods return `std::result::Result<T, String>`. Since our methods return
+/// `failure::Result<T>`, manual conversion is needed.
+fn convert_rocksdb_err(msg: String) -> failure::Error {
+    format_err!("RocksDB internal error: {}.", msg)
+}
+
+/// The [`KvEngine`] persisting data on disk in a [RocksDB](https://rocksdb.org/) instance.
+#[derive(Debug)]
+pub struct RocksdbEngine {
+    inner: rocksdb::DB,
+    /// Set if opened via [`RocksdbEngine::open_with_config`] with statistics enabled.
+    statistics_enabled: bool,
+}
+
+impl RocksdbEngine {
+    /// Create db with all the column families provided if it doesn't exist at `path`; Otherwise,
+    /// try to open it with all the column families.
+    pub fn open<P: AsRef<Path>>(path: P, cf_opts_map: ColumnFamilyOptionsMap) -> Result<Self> {
+        let mut db_opts = DBOptions::new();
+
+        // For now we set the max total WAL size to be 1G. This config can be useful when column
+        // families are updated at non-uniform frequencies.
+        db_opts.set_max_total_wal_size(1 << 30);
+
+        Self::open_with_opts(path, db_opts, cf_opts_map)
+    }
+
+    /// Same as [`RocksdbEngine::open`], but with the db and each of the column families in
+    /// `cf_names` tuned according to `config`. The effective options are exported as metrics.
+    pub fn open_with_config<P: AsRef<Path>>(
+        path: P,
+        cf_names: &[ColumnFamilyName],
+        config: &RocksdbConfig,
+    ) -> Result<Self> {
+        for cf_name in config.column_family_overrides.keys() {
+            ensure!(
+                cf_names.contains(&cf_name.as_str()),
+                "Options given for unknown column family {}.",
+                cf_name,
+            );
+        }
+
+        let shared_cache = config.column_family.block_cache_size.map(new_lru_cache);
+        let cf_opts_map = cf_names
+            .iter()
+            .map(|cf_name| {
+                let cf_config = config.column_family_config(cf_name);
+                let dedicated_cache = config
+                    .column_family_overrides
+                    .get(*cf_name)
+                    .and_then(|overrides| overrides.block_cache_size)
+                    .map(new_lru_cache);
+                report_cf_options(cf_name, &cf_config);
+                (
+                    *cf_name,
+                    cf_options(
+                        &cf_config,
+                        dedicated_cache.as_ref().or(shared_cache.as_ref()),
+                    ),
+                )
+            })
+            .collect();
+
+        let mut db_opts = DBOptions::new();
+        db_opts.set_max_total_wal_size(config.max_total_wal_size);
+        db_opts.enable_statistics(config.enable_statistics);
+        OP_COUNTER.set("db_max_total_wal_size", config.max_total_wal_size as usize);
+
+        let mut engine = Self::open_with_opts(path, db_opts, cf_opts_map)?;
+        engine.statistics_enabled = config.enable_statistics;
+        Ok(engine)
+    }
+
+    fn open_with_opts<P: AsRef<Path>>(
+        path: P,
+        mut db_opts: DBOptions,
+        mut cf_opts_map: ColumnFamilyOptionsMap,
+    ) -> Result<Self> {
+        // If db exists, just open it with all cfs.
+        if db_exists(path.as_ref()) {
+            return Self::open_cf(db_opts, &path, cf_opts_map.into_iter().collect());
+        }
+
+        // If db doesn't exist, create a db first with all column families.
+        db_opts.create_if_missing(true);
+
+        let mut engine = Self::open_cf(
+            db_opts,
+            path,
+            vec![cf_opts_map
+                .remove_entry(&DEFAULT_CF_NAME)
+                .ok_or_else(|| format_err!("No \"default\" column family name found"))?],
+        )?;
+        cf_opts_map
+            .into_iter()
+            .map(|(cf_name, cf_opts)| engine.create_cf((cf_name, cf_opts)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(engine)
+    }
+
+    fn open_cf<'a, P, T>(opts: DBOptions, path: P, cfds: Vec<T>) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        T: Into<ColumnFamilyDescriptor<'a>>,
+    {
+        let inner = rocksdb::DB::open_cf(
+            opts,
+            path.as_ref().to_str().ok_or_else(|| {
+                format_err!("Path {:?} can not be converted to string.", path.as_ref())
+            })?,
+            cfds,
+        )
+        .map_err(convert_rocksdb_err)?;
+
+        Ok(Self {
+            inner,
+            statistics_enabled: false,
+        })
+    }
+
+    /// Opens the db at `path` in read-only mode with all the column families listed in
+    /// `cf_names`. Data written to the db after it's opened is not visible to this instance.
+    pub fn open_readonly<P: AsRef<Path>>(path: P, cf_names: &[ColumnFamilyName]) -> Result<Self> {
+        ensure!(
+            db_exists(path.as_ref()),
+            "No DB found at {:?}.",
+            path.as_ref()
+        );
+
+        let inner = rocksdb::DB::open_cf_for_read_only(
+            DBOptions::new(),
+            path.as_ref().to_str().ok_or_else(|| {
+                format_err!("Path {:?} can not be converted to string.", path.as_ref())
+            })?,
+            cf_names
+                .iter()
+                .map(|cf_name| (*cf_name, ColumnFamilyOptions::default()))
+                .collect(),
+            false, /* error_if_log_file_exist */
+        )
+        .map_err(convert_rocksdb_err)?;
+
+        Ok(Self {
+            inner,
+            statistics_enabled: false,
+        })
+    }
+
+    fn create_cf<'a, T>(&mut self, cfd: T) -> Result<()>
+    where
+        T: Into<ColumnFamilyDescriptor<'a>>,
+    {
+        let _cf_handle = self.inner.create_cf(cfd).map_err(convert_rocksdb_err)?;
+        Ok(())
+    }
+
+    fn get_cf_handle(&self, cf_name: &str) -> Result<&CFHandle> {
+        self.inner.cf_handle(cf_name).ok_or_else(|| {
+            format_err!(
+                "DB::cf_handle not found for column family name: {}",
+                cf_name
+            )
+        })
+    }
+}
+
+impl KvEngine for RocksdbEngine {
+    fn get(&self, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(self
+            .inner
+            .get_cf(cf_handle, key)
+            .map_err(convert_rocksdb_err)?
+            .map(|raw_value| raw_value.to_vec()))
+    }
+
+    fn put(&self, cf_name: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        self.inner
+            .put_cf_opt(cf_handle, key, value, &default_write_options())
+            .map_err(convert_rocksdb_err)
+    }
+
+    fn range_delete(&self, cf_name: &str, begin: &[u8], end: &[u8]) -> Result<()> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        self.inner
+            .delete_range_cf(&cf_handle, begin, end)
+            .map_err(convert_rocksdb_err)
+    }
+
+    fn write_batch(&self, batch: &RawBatch) -> Result<()> {
+        let db_batch = rocksdb::WriteBatch::new();
+        for (cf_name, rows) in batch {
+            let cf_handle = self.get_cf_handle(cf_name)?;
+            for (key, write_op) in rows {
+                match write_op {
+                    WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
+                    WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
+                }
+                .map_err(convert_rocksdb_err)?;
+            }
+        }
+
+        self.inner
+            .write_opt(&db_batch, &default_write_options())
+            .map_err(convert_rocksdb_err)
+    }
+
+    fn iter<'a>(&'a self, cf_name: &str, opts: ReadOptions) -> Result<Box<dyn RawIterator + 'a>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(Box::new(RocksdbIterator(
+            self.inner.iter_cf_opt(cf_handle, opts),
+        )))
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        self.inner
+            .new_checkpointer()
+            .map_err(convert_rocksdb_err)?
+            // Always flush the memtables so the checkpoint doesn't depend on replaying the WAL.
+            .create_at(path, None, 0 /* log_size_for_flush */)
+            .map_err(convert_rocksdb_err)
+    }
+
+    fn get_approximate_sizes_cf(&self) -> Result<BTreeMap<String, u64>> {
+        let mut cf_sizes = BTreeMap::new();
+
+        for cf_name in self.inner.cf_names().into_iter().map(ToString::to_string) {
+            let cf_handle = self.get_cf_handle(&cf_name)?;
+            let size = self
+                .inner
+                .get_property_int_cf(cf_handle, "rocksdb.estimate-live-data-size")
+                .ok_or_else(|| {
+                    format_err!(
+                        "Unable to get approximate size of {} column family.",
+                        cf_name,
+                    )
+                })?;
+            cf_sizes.insert(cf_name, size);
+        }
+
+        Ok(cf_sizes)
+    }
+
+    fn get_statistics_tickers(&self) -> BTreeMap<&'static str, u64> {
+        if !self.statistics_enabled {
+            return BTreeMap::new();
+        }
+
+        [
+            ("block_cache_hit", TickerType::BlockCacheHit),
+            ("block_cache_miss", TickerType::BlockCacheMiss),
+            ("bloom_filter_useful", TickerType::BloomFilterUseful),
+            ("memtable_hit", TickerType::MemtableHit),
+            ("memtable_miss", TickerType::MemtableMiss),
+            ("bytes_read", TickerType::BytesRead),
+            ("bytes_written", TickerType::BytesWritten),
+            ("compact_read_bytes", TickerType::CompactReadBytes),
+            ("compact_write_bytes", TickerType::CompactWriteBytes),
+            ("flush_write_bytes", TickerType::FlushWriteBytes),
+            ("stall_micros", TickerType::StallMicros),
+        ]
+        .iter()
+        .map(|(name, ticker)| (*name, self.inner.get_statistics_ticker_count(*ticker)))
+        .collect()
+    }
+
+    fn flush_all(&self, sync: bool) -> Result<()> {
+        for cf_name in self.inner.cf_names() {
+            let cf_handle = self.get_cf_handle(cf_name)?;
+            self.inner
+                .flush_cf(cf_handle, sync)
+                .map_err(convert_rocksdb_err)?;
+        }
+        Ok(())
+    }
+}
+
+struct RocksdbIterator<'a>(rocksdb::DBIterator<&'a rocksdb::DB>);
+
+impl<'a> RawIterator for RocksdbIterator<'a> {
+    fn seek_to_first(&mut self) -> bool {
+        self.0.seek(rocksdb::SeekKey::Start)
+    }
+
+    fn seek_to_last(&mut self) -> bool {
+        self.0.seek(rocksdb::SeekKey::End)
+    }
+
+    fn seek(&mut self, key: &[u8]) -> bool {
+        self.0.seek(rocksdb::SeekKey::Key(key))
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> bool {
+        self.0.seek_for_prev(rocksdb::SeekKey::Key(key))
+    }
+
+    fn kv(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        self.0.kv()
+    }
+
+    fn next(&mut self) {
+        self.0.next();
+    }
+}
+
+/// For now we always use synchronous writes. This makes sure that once the operation returns
+/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
+/// selectively turning this off for some non-critical writes to improve performance.
+fn default_write_options() -> WriteOptions {
+    let mut opts = WriteOptions::new();
+    opts.set_sync(true);
+    opts
+}
+
+fn new_lru_cache(capacity: u64) -> Cache {
+    let mut cache_opts = LRUCacheOptions::new();
+    cache_opts.set_capacity(capacity as usize);
+    Cache::new_lru_cache(cache_opts)
+}
+
+fn cf_options(cf_config: &ColumnFamilyConfig, block_cache: Option<&Cache>) -> ColumnFamilyOptions {
+    let mut cf_opts = ColumnFamilyOptions::new();
+    if let Some(write_buffer_size) = cf_config.write_buffer_size {
+        cf_opts.set_write_buffer_size(write_buffer_size);
+    }
+    if let Some(max_write_buffer_number) = cf_config.max_write_buffer_number {
+        cf_opts.set_max_write_buffer_number(max_write_buffer_number as i32);
+    }
+    if let Some(compression) = cf_config.compression {
+        cf_opts.compression(match compression {
+            RocksdbCompression::None => DBCompressionType::No,
+            RocksdbCompression::Snappy => DBCompressionType::Snappy,
+            RocksdbCompression::Zlib => DBCompressionType::Zlib,
+            RocksdbCompression::Bz2 => DBCompressionType::Bz2,
+            RocksdbCompression::Lz4 => DBCompressionType::Lz4,
+            RocksdbCompression::Lz4hc => DBCompressionType::Lz4hc,
+            RocksdbCompression::Zstd => DBCompressionType::Zstd,
+        });
+    }
+    if let Some(compaction_style) = cf_config.compaction_style {
+        cf_opts.set_compaction_style(match compaction_style {
+            RocksdbCompactionStyle::Level => DBCompactionStyle::Level,
+            RocksdbCompactionStyle::Universal => DBCompactionStyle::Universal,
+            RocksdbCompactionStyle::Fifo => DBCompactionStyle::Fifo,
+        });
+    }
+
+    // Only install a table factory if any of its options is set, so that an empty config keeps
+    // all RocksDB defaults.
+    if block_cache.is_some()
+        || cf_config.block_size.is_some()
+        || cf_config.bloom_filter_bits_per_key.is_some()
+    {
+        let mut block_opts = BlockBasedOptions::new();
+        if let Some(block_cache) = block_cache {
+            block_opts.set_block_cache(block_cache);
+        }
+        if let Some(block_size) = cf_config.block_size {
+            block_opts.set_block_size(block_size as usize);
+        }
+        match cf_config.bloom_filter_bits_per_key {
+            None | Some(0) => (),
+            Some(bits_per_key) => {
+                block_opts.set_bloom_filter(bits_per_key as i32, false /* block_based */)
+            }
+        }
+        cf_opts.set_block_based_table_factory(&block_opts);
+    }
+
+    cf_opts
+}
+
+/// Exports the options of column family `cf_name` that differ from the RocksDB defaults.
+fn report_cf_options(cf_name: &str, cf_config: &ColumnFamilyConfig) {
+    let numeric_options = [
+        ("block_cache_size", cf_config.block_cache_size),
+        ("block_size", cf_config.block_size),
+        (
+            "bloom_filter_bits_per_key",
+            cf_config.bloom_filter_bits_per_key.map(u64::from),
+        ),
+        ("write_buffer_size", cf_config.write_buffer_size),
+        (
+            "max_write_buffer_number",
+            cf_config.max_write_buffer_number.map(u64::from),
+        ),
+    ];
+    for (option, value) in numeric_options.iter() {
+        if let Some(value) = value {
+            OP_COUNTER.set(&format!("cf_{}_{}", option, cf_name), *value as usize);
+        }
+    }
+    if let Some(compression) = cf_config.compression {
+        OP_COUNTER.set(
+            &format!("cf_compression_{:?}_{}", compression, cf_name).to_lowercase(),
+            1,
+        );
+    }
+    if let Some(compaction_style) = cf_config.compaction_style {
+        OP_COUNTER.set(
+            &format!("cf_compaction_style_{:?}_{}", compaction_style, cf_name).to_lowercase(),
+            1,
+        );
+    }
+}
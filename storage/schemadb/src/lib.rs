@@ -1,26 +1,29 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! This library implements a schematized DB on top of [RocksDB](https://rocksdb.org/). It makes
-//! sure all data passed in and out are structured according to predefined schemas and prevents
-//! access to raw keys and values. This library also enforces a set of Libra specific DB options,
-//! like custom comparators and schema-to-column-family mapping.
+//! This library implements a schematized DB on top of [RocksDB](https://rocksdb.org/), or any other
+//! [`KvEngine`](engine::KvEngine), e.g. the in-memory one used in tests. It makes sure all data
+//! passed in and out are structured according to predefined schemas and prevents access to raw
+//! keys and values. This library also enforces a set of Libra specific DB options, like custom
+//! comparators and schema-to-column-family mapping.
 //!
 //! It requires that different kinds of key-value pairs be stored in separate column
 //! families.  To use this library to store a kind of key-value pairs, the user needs to use the
 //! [`define_schema!`] macro to define the schema name, the types of key and value, and name of the
 //! column family.
 
+pub mod engine;
 #[macro_use]
 pub mod schema;
 
-use crate::schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec};
+use crate::{
+    engine::{InMemoryEngine, KvEngine, RawBatch, RawIterator, RocksdbEngine, WriteOp},
+    schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
+};
 use failure::prelude::*;
 use lazy_static::lazy_static;
+use libra_config::config::RocksdbConfig;
 use libra_metrics::OpMetrics;
-use rocksdb::{
-    rocksdb_options::ColumnFamilyDescriptor, CFHandle, DBOptions, Writable, WriteOptions,
-};
 use std::{
     collections::{BTreeMap, HashMap},
     iter::Iterator,
@@ -46,17 +49,11 @@ pub type ColumnFamilyOptionsMap = HashMap<ColumnFamilyName, ColumnFamilyOptions>
 /// [`LedgerInfo`](../types/ledger_info/struct.LedgerInfo.html).
 pub const DEFAULT_CF_NAME: ColumnFamilyName = "default";
 
-#[derive(Debug)]
-enum WriteOp {
-    Value(Vec<u8>),
-    Deletion,
-}
-
 /// `SchemaBatch` holds a collection of updates that can be applied to a DB atomically. The updates
 /// will be applied in the order in which they are added to the `SchemaBatch`.
 #[derive(Debug, Default)]
 pub struct SchemaBatch {
-    rows: HashMap<ColumnFamilyName, BTreeMap<Vec<u8>, WriteOp>>,
+    rows: RawBatch,
 }
 
 impl SchemaBatch {
@@ -92,7 +89,7 @@ impl SchemaBatch {
 /// DB Iterator parameterized on [`Schema`] that seeks with [`Schema::Key`] and yields
 /// [`Schema::Key`] and [`Schema::Value`]
 pub struct SchemaIterator<'a, S> {
-    db_iter: rocksdb::DBIterator<&'a rocksdb::DB>,
+    db_iter: Box<dyn RawIterator + 'a>,
     phantom: PhantomData<S>,
 }
 
@@ -100,7 +97,7 @@ impl<'a, S> SchemaIterator<'a, S>
 where
     S: Schema,
 {
-    fn new(db_iter: rocksdb::DBIterator<&'a rocksdb::DB>) -> Self {
+    fn new(db_iter: Box<dyn RawIterator + 'a>) -> Self {
         SchemaIterator {
             db_iter,
             phantom: PhantomData,
@@ -109,12 +106,12 @@ where
 
     /// Seeks to the first key.
     pub fn seek_to_first(&mut self) -> bool {
-        self.db_iter.seek(rocksdb::SeekKey::Start)
+        self.db_iter.seek_to_first()
     }
 
     /// Seeks to the last key.
     pub fn seek_to_last(&mut self) -> bool {
-        self.db_iter.seek(rocksdb::SeekKey::End)
+        self.db_iter.seek_to_last()
     }
 
     /// Seeks to the first key whose binary representation is equal to or greater than that of the
@@ -124,7 +121,7 @@ where
         SK: SeekKeyCodec<S>,
     {
         let key = <SK as SeekKeyCodec<S>>::encode_seek_key(seek_key)?;
-        Ok(self.db_iter.seek(rocksdb::SeekKey::Key(&key)))
+        Ok(self.db_iter.seek(&key))
     }
 
     /// Seeks to the last key whose binary representation is less than or equal to that of the
@@ -136,7 +133,7 @@ where
         SK: SeekKeyCodec<S>,
     {
         let key = <SK as SeekKeyCodec<S>>::encode_seek_key(seek_key)?;
-        Ok(self.db_iter.seek_for_prev(rocksdb::SeekKey::Key(&key)))
+        Ok(self.db_iter.seek_for_prev(&key))
     }
 }
 
@@ -157,93 +154,62 @@ where
     }
 }
 
-/// Checks underlying Rocksdb instance existence by checking `CURRENT` file existence, the same way
-/// Rocksdb adopts to detect db existence.
-fn db_exists(path: &Path) -> bool {
-    let rocksdb_current_file = path.join("CURRENT");
-    rocksdb_current_file.is_file()
-}
-
-/// All the RocksDB methods return `std::result::Result<T, String>`. Since our methods return
-/// `failure::Result<T>`, manual conversion is needed.
-fn convert_rocksdb_err(msg: String) -> failure::Error {
-    format_err!("RocksDB internal error: {}.", msg)
-}
-
-/// This DB is a schematized RocksDB wrapper where all data passed in and out are typed according to
-/// [`Schema`]s.
+/// This DB is a schematized key-value store wrapper where all data passed in and out are typed
+/// according to [`Schema`]s.
 #[derive(Debug)]
 pub struct DB {
-    inner: rocksdb::DB,
+    engine: Box<dyn KvEngine>,
+    /// Set if opened via [`DB::open_readonly`], in which case all writes are refused.
+    readonly: bool,
 }
 
 impl DB {
+    /// Wraps `engine`, which must have been opened with all the column families used.
+    pub fn new(engine: Box<dyn KvEngine>) -> Self {
+        DB {
+            engine,
+            readonly: false,
+        }
+    }
+
     /// Create db with all the column families provided if it doesn't exist at `path`; Otherwise,
     /// try to open it with all the column families.
-    pub fn open<P: AsRef<Path>>(path: P, mut cf_opts_map: ColumnFamilyOptionsMap) -> Result<Self> {
-        let mut db_opts = DBOptions::new();
-
-        // For now we set the max total WAL size to be 1G. This config can be useful when column
-        // families are updated at non-uniform frequencies.
-        db_opts.set_max_total_wal_size(1 << 30);
-
-        // If db exists, just open it with all cfs.
-        if db_exists(path.as_ref()) {
-            return DB::open_cf(db_opts, &path, cf_opts_map.into_iter().collect());
-        }
+    pub fn open<P: AsRef<Path>>(path: P, cf_opts_map: ColumnFamilyOptionsMap) -> Result<Self> {
+        Ok(DB::new(Box::new(RocksdbEngine::open(path, cf_opts_map)?)))
+    }
 
-        // If db doesn't exist, create a db first with all column families.
-        db_opts.create_if_missing(true);
-
-        let mut db = DB::open_cf(
-            db_opts,
-            path,
-            vec![cf_opts_map
-                .remove_entry(&DEFAULT_CF_NAME)
-                .ok_or_else(|| format_err!("No \"default\" column family name found"))?],
-        )?;
-        cf_opts_map
-            .into_iter()
-            .map(|(cf_name, cf_opts)| db.create_cf((cf_name, cf_opts)))
-            .collect::<Result<Vec<_>>>()?;
-        Ok(db)
+    /// Same as [`DB::open`], but with the db and each of the column families in `cf_names` tuned
+    /// according to `config`. The effective options are exported as metrics.
+    pub fn open_with_config<P: AsRef<Path>>(
+        path: P,
+        cf_names: &[ColumnFamilyName],
+        config: &RocksdbConfig,
+    ) -> Result<Self> {
+        Ok(DB::new(Box::new(RocksdbEngine::open_with_config(
+            path, cf_names, config,
+        )?)))
     }
 
-    fn open_cf<'a, P, T>(opts: DBOptions, path: P, cfds: Vec<T>) -> Result<DB>
-    where
-        P: AsRef<Path>,
-        T: Into<ColumnFamilyDescriptor<'a>>,
-    {
-        let inner = rocksdb::DB::open_cf(
-            opts,
-            path.as_ref().to_str().ok_or_else(|| {
-                format_err!("Path {:?} can not be converted to string.", path.as_ref())
-            })?,
-            cfds,
-        )
-        .map_err(convert_rocksdb_err)?;
-
-        Ok(DB { inner })
+    /// Opens the db at `path` in read-only mode with all the column families listed in
+    /// `cf_names`. Data written to the db after it's opened is not visible to this instance.
+    pub fn open_readonly<P: AsRef<Path>>(path: P, cf_names: &[ColumnFamilyName]) -> Result<Self> {
+        Ok(DB {
+            engine: Box::new(RocksdbEngine::open_readonly(path, cf_names)?),
+            readonly: true,
+        })
     }
 
-    fn create_cf<'a, T>(&mut self, cfd: T) -> Result<()>
-    where
-        T: Into<ColumnFamilyDescriptor<'a>>,
-    {
-        let _cf_handle = self.inner.create_cf(cfd).map_err(convert_rocksdb_err)?;
-        Ok(())
+    /// Creates an empty db with all the column families in `cf_names`, kept in memory only.
+    pub fn open_in_memory(cf_names: &[ColumnFamilyName]) -> Self {
+        DB::new(Box::new(InMemoryEngine::new(cf_names)))
     }
 
     /// Reads single record by key.
     pub fn get<S: Schema>(&self, schema_key: &S::Key) -> Result<Option<S::Value>> {
         let k = <S::Key as KeyCodec<S>>::encode_key(&schema_key)?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
         let time = std::time::Instant::now();
 
-        let result = self
-            .inner
-            .get_cf(cf_handle, &k)
-            .map_err(convert_rocksdb_err)?;
+        let result = self.engine.get(S::COLUMN_FAMILY_NAME, &k)?;
         OP_COUNTER.observe_duration(&format!("db_get_{}", S::COLUMN_FAMILY_NAME), time.elapsed());
         result
             .map(|raw_value| <S::Value as ValueCodec<S>>::decode_value(&raw_value))
@@ -252,13 +218,11 @@ impl DB {
 
     /// Writes single record.
     pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> Result<()> {
+        self.ensure_writable()?;
         let k = <S::Key as KeyCodec<S>>::encode_key(&key)?;
         let v = <S::Value as ValueCodec<S>>::encode_value(&value)?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
-        self.inner
-            .put_cf_opt(cf_handle, &k, &v, &default_write_options())
-            .map_err(convert_rocksdb_err)
+        self.engine.put(S::COLUMN_FAMILY_NAME, &k, &v)
     }
 
     /// Delete all keys in range [begin, end).
@@ -270,38 +234,25 @@ impl DB {
         S: Schema,
         SK: SeekKeyCodec<S>,
     {
+        self.ensure_writable()?;
         let raw_begin = begin.encode_seek_key()?;
         let raw_end = end.encode_seek_key()?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
-        self.inner
-            .delete_range_cf(&cf_handle, &raw_begin, &raw_end)
-            .map_err(convert_rocksdb_err)
+        self.engine
+            .range_delete(S::COLUMN_FAMILY_NAME, &raw_begin, &raw_end)
     }
 
     /// Returns a [`SchemaIterator`] on a certain schema.
     pub fn iter<S: Schema>(&self, opts: ReadOptions) -> Result<SchemaIterator<S>> {
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
-        Ok(SchemaIterator::new(self.inner.iter_cf_opt(cf_handle, opts)))
+        Ok(SchemaIterator::new(
+            self.engine.iter(S::COLUMN_FAMILY_NAME, opts)?,
+        ))
     }
 
     /// Writes a group of records wrapped in a [`SchemaBatch`].
     pub fn write_schemas(&self, batch: SchemaBatch) -> Result<()> {
-        let db_batch = rocksdb::WriteBatch::new();
-        for (cf_name, rows) in &batch.rows {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            for (key, write_op) in rows {
-                match write_op {
-                    WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
-                    WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
-                }
-                .map_err(convert_rocksdb_err)?;
-            }
-        }
-
-        self.inner
-            .write_opt(&db_batch, &default_write_options())
-            .map_err(convert_rocksdb_err)?;
+        self.ensure_writable()?;
+        self.engine.write_batch(&batch.rows)?;
 
         // Bump counters only after DB write succeeds.
         for (cf_name, rows) in &batch.rows {
@@ -319,54 +270,45 @@ impl DB {
         Ok(())
     }
 
-    fn get_cf_handle(&self, cf_name: &str) -> Result<&CFHandle> {
-        self.inner.cf_handle(cf_name).ok_or_else(|| {
-            format_err!(
-                "DB::cf_handle not found for column family name: {}",
-                cf_name
-            )
-        })
+    /// Creates a [checkpoint](https://github.com/facebook/rocksdb/wiki/Checkpoints) of the db at
+    /// `path`, which must not exist yet. The checkpoint is a consistent point-in-time copy of all
+    /// column families that can be opened as a separate db. Files are hard linked where possible,
+    /// so it is cheap to create when `path` is on the same file system as the db.
+    ///
+    /// Only supported by the RocksDB engine.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        ensure!(
+            !path.as_ref().exists(),
+            "Checkpoint path {:?} already exists.",
+            path.as_ref()
+        );
+
+        let time = std::time::Instant::now();
+        self.engine.create_checkpoint(path.as_ref())?;
+        OP_COUNTER.observe_duration("db_create_checkpoint", time.elapsed());
+
+        Ok(())
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        ensure!(!self.readonly, "Writing to a DB opened in read-only mode.");
+        Ok(())
     }
 
     /// Returns the approximate size of each non-empty column family in bytes.
     pub fn get_approximate_sizes_cf(&self) -> Result<BTreeMap<String, u64>> {
-        let mut cf_sizes = BTreeMap::new();
-
-        for cf_name in self.inner.cf_names().into_iter().map(ToString::to_string) {
-            let cf_handle = self.get_cf_handle(&cf_name)?;
-            let size = self
-                .inner
-                .get_property_int_cf(cf_handle, "rocksdb.estimate-live-data-size")
-                .ok_or_else(|| {
-                    format_err!(
-                        "Unable to get approximate size of {} column family.",
-                        cf_name,
-                    )
-                })?;
-            cf_sizes.insert(cf_name, size);
-        }
+        self.engine.get_approximate_sizes_cf()
+    }
 
-        Ok(cf_sizes)
+    /// Returns the values of a selection of RocksDB statistics tickers, or nothing if statistics
+    /// are not enabled or the db is not backed by RocksDB.
+    pub fn get_statistics_tickers(&self) -> BTreeMap<&'static str, u64> {
+        self.engine.get_statistics_tickers()
     }
 
     /// Flushes all memtable data. If `sync` is true, the flush will wait until it's done. This is
     /// only used for testing `get_approximate_sizes_cf` in unit tests.
     pub fn flush_all(&self, sync: bool) -> Result<()> {
-        for cf_name in self.inner.cf_names() {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            self.inner
-                .flush_cf(cf_handle, sync)
-                .map_err(convert_rocksdb_err)?;
-        }
-        Ok(())
+        self.engine.flush_all(sync)
     }
 }
-
-/// For now we always use synchronous writes. This makes sure that once the operation returns
-/// `Ok(())` the data is persisted even if the machine crashes. In the future we might consider
-/// selectively turning this off for some non-critical writes to improve performance.
-fn default_write_options() -> WriteOptions {
-    let mut opts = WriteOptions::new();
-    opts.set_sync(true);
-    opts
-}
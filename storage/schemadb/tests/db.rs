@@ -3,6 +3,7 @@
 
 use byteorder::{LittleEndian, ReadBytesExt};
 use failure::Result;
+use libra_config::config::{ColumnFamilyConfig, RocksdbCompression, RocksdbConfig};
 use proptest::{collection::vec, prelude::*};
 use schemadb::{
     define_schema,
@@ -90,19 +91,31 @@ fn open_db(dir: &libra_tools::tempdir::TempPath) -> DB {
 }
 
 struct TestDB {
-    _tmpdir: libra_tools::tempdir::TempPath,
+    _tmpdir: Option<libra_tools::tempdir::TempPath>,
     db: DB,
 }
 
 impl TestDB {
-    fn new() -> Self {
+    /// Returns empty test DBs, one on each engine.
+    fn all() -> Vec<Self> {
         let tmpdir = libra_tools::tempdir::TempPath::new();
         let db = open_db(&tmpdir);
-
-        TestDB {
-            _tmpdir: tmpdir,
-            db,
-        }
+        let in_memory = DB::open_in_memory(&[
+            DEFAULT_CF_NAME,
+            TestSchema1::COLUMN_FAMILY_NAME,
+            TestSchema2::COLUMN_FAMILY_NAME,
+        ]);
+
+        vec![
+            TestDB {
+                _tmpdir: Some(tmpdir),
+                db,
+            },
+            TestDB {
+                _tmpdir: None,
+                db: in_memory,
+            },
+        ]
     }
 }
 
@@ -116,42 +129,42 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_schema_put_get() {
-    let db = TestDB::new();
-
-    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
-    db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
-    db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
-    db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
-    db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
-    db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
+    for db in TestDB::all() {
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+        db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
+        db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
+        db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
+        db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
 
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(0)).unwrap(),
-        Some(TestField(0)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(1)).unwrap(),
-        Some(TestField(1)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(2)).unwrap(),
-        Some(TestField(2)),
-    );
-    assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(1)).unwrap(),
+            Some(TestField(1)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(2)).unwrap(),
+            Some(TestField(2)),
+        );
+        assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
 
-    assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(2)).unwrap(),
-        Some(TestField(3)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(3)).unwrap(),
-        Some(TestField(4)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(4)).unwrap(),
-        Some(TestField(5)),
-    );
+        assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(2)).unwrap(),
+            Some(TestField(3)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(3)).unwrap(),
+            Some(TestField(4)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(4)).unwrap(),
+            Some(TestField(5)),
+        );
+    }
 }
 
 proptest! {
@@ -162,23 +175,25 @@ proptest! {
         ranges_to_delete in vec(
             (0..100u32).prop_flat_map(|begin| (Just(begin), (begin..100u32))), 0..10)
     ) {
-        let db = TestDB::new();
-        for i in 0..100u32 {
-            db.put::<TestSchema1>(&TestField(i), &TestField(i)).unwrap();
-        }
-        let mut should_exist = [true; 100];
-        for (begin, end) in ranges_to_delete {
-            db.range_delete::<TestSchema1, TestField>(&TestField(begin), &TestField(end)).unwrap();
-            for i in begin..end {
-                should_exist[i as usize] = false;
+        for db in TestDB::all() {
+            for i in 0..100u32 {
+                db.put::<TestSchema1>(&TestField(i), &TestField(i)).unwrap();
+            }
+            let mut should_exist = [true; 100];
+            for (begin, end) in &ranges_to_delete {
+                db.range_delete::<TestSchema1, TestField>(&TestField(*begin), &TestField(*end))
+                    .unwrap();
+                for i in *begin..*end {
+                    should_exist[i as usize] = false;
+                }
             }
-        }
 
-        for (i, should_exist) in should_exist.iter().enumerate() {
-            assert_eq!(
-                db.get::<TestSchema1>(&TestField(i as u32)).unwrap().is_some(),
-                *should_exist,
-            )
+            for (i, should_exist) in should_exist.iter().enumerate() {
+                assert_eq!(
+                    db.get::<TestSchema1>(&TestField(i as u32)).unwrap().is_some(),
+                    *should_exist,
+                )
+            }
         }
     }
 }
@@ -201,84 +216,84 @@ fn gen_expected_values(values: &[(u32, u32)]) -> Vec<(TestField, TestField)> {
 
 #[test]
 fn test_single_schema_batch() {
-    let db = TestDB::new();
-
-    let mut db_batch = SchemaBatch::new();
-    db_batch
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-    db.write_schemas(db_batch).unwrap();
+    for db in TestDB::all() {
+        let mut db_batch = SchemaBatch::new();
+        db_batch
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
+        db.write_schemas(db_batch).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(4, 4), (5, 5)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
 fn test_two_schema_batches() {
-    let db = TestDB::new();
-
-    let mut db_batch1 = SchemaBatch::new();
-    db_batch1
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
-    db.write_schemas(db_batch1).unwrap();
+    for db in TestDB::all() {
+        let mut db_batch1 = SchemaBatch::new();
+        db_batch1
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
+        db.write_schemas(db_batch1).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
 
-    let mut db_batch2 = SchemaBatch::new();
-    db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-    db.write_schemas(db_batch2).unwrap();
+        let mut db_batch2 = SchemaBatch::new();
+        db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
+        db.write_schemas(db_batch2).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
@@ -303,23 +318,133 @@ fn test_reopen() {
 
 #[test]
 fn test_report_size() {
-    let db = TestDB::new();
+    for db in TestDB::all() {
+        for i in 0..1000 {
+            let mut db_batch = SchemaBatch::new();
+            db_batch
+                .put::<TestSchema1>(&TestField(i), &TestField(i))
+                .unwrap();
+            db_batch
+                .put::<TestSchema2>(&TestField(i), &TestField(i))
+                .unwrap();
+            db.write_schemas(db_batch).unwrap();
+        }
 
-    for i in 0..1000 {
-        let mut db_batch = SchemaBatch::new();
-        db_batch
-            .put::<TestSchema1>(&TestField(i), &TestField(i))
-            .unwrap();
-        db_batch
-            .put::<TestSchema2>(&TestField(i), &TestField(i))
-            .unwrap();
-        db.write_schemas(db_batch).unwrap();
+        db.flush_all(/* sync = */ true).unwrap();
+
+        let cf_sizes = db.get_approximate_sizes_cf().unwrap();
+        assert!(*cf_sizes.get("TestCF1").unwrap() > 0);
+        assert!(*cf_sizes.get("TestCF2").unwrap() > 0);
+        assert_eq!(*cf_sizes.get("default").unwrap(), 0);
+    }
+}
+
+#[test]
+fn test_checkpoint() {
+    let tmpdir = libra_tools::tempdir::TempPath::new();
+    let checkpoint_dir = libra_tools::tempdir::TempPath::new();
+    {
+        let db = open_db(&tmpdir);
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.create_checkpoint(&checkpoint_dir).unwrap();
+        // Refuses to overwrite an existing checkpoint.
+        assert!(db.create_checkpoint(&checkpoint_dir).is_err());
+        // Writes after the checkpoint is created don't show up in it.
+        db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+    }
+    {
+        let db = DB::open_readonly(
+            &checkpoint_dir,
+            &[
+                DEFAULT_CF_NAME,
+                TestSchema1::COLUMN_FAMILY_NAME,
+                TestSchema2::COLUMN_FAMILY_NAME,
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
+        assert!(db.put::<TestSchema1>(&TestField(1), &TestField(1)).is_err());
+        assert!(db.write_schemas(SchemaBatch::new()).is_err());
+    }
+}
+
+#[test]
+fn test_open_with_config() {
+    let tmpdir = libra_tools::tempdir::TempPath::new();
+    let cf_names = [
+        DEFAULT_CF_NAME,
+        TestSchema1::COLUMN_FAMILY_NAME,
+        TestSchema2::COLUMN_FAMILY_NAME,
+    ];
+    let mut config = RocksdbConfig::default();
+    config.enable_statistics = true;
+    config.column_family = ColumnFamilyConfig {
+        block_cache_size: Some(1 << 20),
+        compression: Some(RocksdbCompression::Lz4),
+        ..ColumnFamilyConfig::default()
+    };
+    config.column_family_overrides.insert(
+        TestSchema1::COLUMN_FAMILY_NAME.to_string(),
+        ColumnFamilyConfig {
+            block_cache_size: Some(1 << 16),
+            bloom_filter_bits_per_key: Some(10),
+            compression: Some(RocksdbCompression::None),
+            ..ColumnFamilyConfig::default()
+        },
+    );
+
+    let cf_config = config.column_family_config(TestSchema1::COLUMN_FAMILY_NAME);
+    assert_eq!(cf_config.block_cache_size, Some(1 << 16));
+    assert_eq!(cf_config.compression, Some(RocksdbCompression::None));
+    assert_eq!(
+        config.column_family_config(TestSchema2::COLUMN_FAMILY_NAME),
+        config.column_family,
+    );
+
+    {
+        let db = DB::open_with_config(&tmpdir, &cf_names, &config).unwrap();
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.put::<TestSchema2>(&TestField(0), &TestField(0)).unwrap();
+        assert!(db.get_statistics_tickers()["bytes_written"] > 0);
+    }
+    {
+        // Options can be changed between restarts.
+        config.column_family_overrides.clear();
+        config.enable_statistics = false;
+        let db = DB::open_with_config(&tmpdir, &cf_names, &config).unwrap();
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert!(db.get_statistics_tickers().is_empty());
     }
 
-    db.flush_all(/* sync = */ true).unwrap();
+    config
+        .column_family_overrides
+        .insert("UnknownCF".to_string(), ColumnFamilyConfig::default());
+    assert!(DB::open_with_config(&tmpdir, &cf_names, &config).is_err());
+}
+
+#[test]
+fn test_in_memory_unsupported() {
+    let db = DB::open_in_memory(&[DEFAULT_CF_NAME, TestSchema1::COLUMN_FAMILY_NAME]);
+    // Column families not given on creation don't exist.
+    assert!(db.put::<TestSchema2>(&TestField(0), &TestField(0)).is_err());
+    let mut db_batch = SchemaBatch::new();
+    db_batch
+        .put::<TestSchema1>(&TestField(0), &TestField(0))
+        .unwrap();
+    db_batch
+        .put::<TestSchema2>(&TestField(0), &TestField(0))
+        .unwrap();
+    assert!(db.write_schemas(db_batch).is_err());
+    // The batch is applied atomically.
+    assert_eq!(db.get::<TestSchema1>(&TestField(0)).unwrap(), None);
 
-    let cf_sizes = db.get_approximate_sizes_cf().unwrap();
-    assert!(*cf_sizes.get("TestCF1").unwrap() > 0);
-    assert!(*cf_sizes.get("TestCF2").unwrap() > 0);
-    assert_eq!(*cf_sizes.get("default").unwrap(), 0);
+    let checkpoint_dir = libra_tools::tempdir::TempPath::new();
+    assert!(db.create_checkpoint(&checkpoint_dir).is_err());
 }
@@ -71,12 +71,13 @@ fn collect_values(iter: SchemaIterator<TestSchema>) -> Vec<u32> {
 }
 
 struct TestDB {
-    _tmpdir: libra_tools::tempdir::TempPath,
+    _tmpdir: Option<libra_tools::tempdir::TempPath>,
     db: DB,
 }
 
 impl TestDB {
-    fn new() -> Self {
+    /// Returns identical test DBs, one on each engine.
+    fn all() -> Vec<Self> {
         let tmpdir = libra_tools::tempdir::TempPath::new();
         let cf_opts_map: ColumnFamilyOptionsMap = [
             (DEFAULT_CF_NAME, ColumnFamilyOptions::default()),
@@ -88,8 +89,13 @@ impl TestDB {
         .iter()
         .cloned()
         .collect();
-        let db = DB::open(&tmpdir.path(), cf_opts_map).unwrap();
+        let rocksdb = DB::open(&tmpdir.path(), cf_opts_map).unwrap();
+        let in_memory = DB::open_in_memory(&[DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME]);
 
+        vec![Self::new(Some(tmpdir), rocksdb), Self::new(None, in_memory)]
+    }
+
+    fn new(tmpdir: Option<libra_tools::tempdir::TempPath>, db: DB) -> Self {
         db.put::<TestSchema>(&TestKey(1, 0, 0), &TestValue(100))
             .unwrap();
         db.put::<TestSchema>(&TestKey(1, 0, 2), &TestValue(102))
@@ -132,83 +138,93 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_seek_to_first() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_to_first();
-    assert_eq!(
-        collect_values(iter),
-        [100, 102, 104, 110, 112, 114, 200, 202]
-    );
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_to_first();
+        assert_eq!(
+            collect_values(iter),
+            [100, 102, 104, 110, 112, 114, 200, 202]
+        );
+    }
 }
 
 #[test]
 fn test_seek_to_last() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_to_last();
-    assert_eq!(collect_values(iter), [202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_to_last();
+        assert_eq!(collect_values(iter), [202]);
+    }
 }
 
 #[test]
 fn test_seek_by_existing_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_by_nonexistent_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [112, 114, 200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_existing_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_nonexistent_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_by_1prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_1prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_by_2prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_2prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
+    for db in TestDB::all() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+    }
 }
@@ -11,7 +11,7 @@ mod state_view;
 
 use failure::prelude::*;
 use futures::{compat::Future01CompatExt, executor::block_on, prelude::*};
-use futures_01::future::Future as Future01;
+use futures_01::{future::Future as Future01, stream::Stream as Stream01};
 use grpcio::{ChannelBuilder, Environment};
 use libra_types::{
     account_address::AccountAddress,
@@ -28,10 +28,12 @@ use rand::Rng;
 use std::convert::TryFrom;
 use std::{pin::Pin, sync::Arc};
 use storage_proto::{
-    proto::storage::{GetStartupInfoRequest, StorageClient},
-    GetAccountStateWithProofByVersionRequest, GetAccountStateWithProofByVersionResponse,
-    GetEpochChangeLedgerInfosRequest, GetEpochChangeLedgerInfosResponse, GetStartupInfoResponse,
-    GetTransactionsRequest, GetTransactionsResponse, SaveTransactionsRequest, StartupInfo,
+    proto::storage::{GetStartupInfoRequest, PromoteToPrimaryRequest, StorageClient},
+    CreateCheckpointRequest, CreateCheckpointResponse, GetAccountStateWithProofByVersionRequest,
+    GetAccountStateWithProofByVersionResponse, GetEpochChangeLedgerInfosRequest,
+    GetEpochChangeLedgerInfosResponse, GetStartupInfoResponse, GetTransactionsRequest,
+    GetTransactionsResponse, PromoteToPrimaryResponse, ReplicatedTransactions,
+    SaveTransactionsRequest, StartupInfo, SubscribeToTransactionsRequest, TreeState,
 };
 
 pub use crate::state_view::VerifiedStateView;
@@ -255,6 +257,55 @@ impl StorageWriteServiceClient {
     fn client(&self) -> &StorageClient {
         pick(&self.clients)
     }
+
+    /// Asks the storage service to create a checkpoint named `name`. Returns the latest ledger
+    /// info in the checkpoint.
+    ///
+    /// See [`LibraDB::create_checkpoint`].
+    ///
+    /// [`LibraDB::create_checkpoint`]:
+    /// ../libradb/struct.LibraDB.html#method.create_checkpoint
+    pub fn create_checkpoint(&self, name: String) -> Result<LedgerInfoWithSignatures> {
+        let req = CreateCheckpointRequest::new(name);
+        block_on(
+            convert_grpc_response(self.client().create_checkpoint_async(&req.into())).map(|resp| {
+                let resp = CreateCheckpointResponse::try_from(resp?)?;
+                Ok(resp.ledger_info_with_sigs)
+            }),
+        )
+    }
+
+    /// Subscribes to the batches of transactions saved on the storage service from
+    /// `start_version` on, each with the tree state right after it. The returned iterator blocks
+    /// until the next batch is saved and ends when the connection does.
+    pub fn subscribe_to_transactions(
+        &self,
+        start_version: Version,
+    ) -> Result<impl Iterator<Item = Result<ReplicatedTransactions>>> {
+        let req = SubscribeToTransactionsRequest::new(start_version);
+        let stream = self
+            .client()
+            .subscribe_to_transactions(&req.into())
+            .map_err(convert_grpc_err)?;
+        Ok(stream
+            .wait()
+            .map(|item| ReplicatedTransactions::try_from(item.map_err(convert_grpc_err)?)))
+    }
+
+    /// Asks a storage service following a primary to stop replicating and start accepting
+    /// writes. Returns the tree state it starts from, `None` if its DB is empty.
+    pub fn promote_to_primary(&self) -> Result<Option<TreeState>> {
+        block_on(
+            convert_grpc_response(
+                self.client()
+                    .promote_to_primary_async(&PromoteToPrimaryRequest::default()),
+            )
+            .map(|resp| {
+                let resp = PromoteToPrimaryResponse::try_from(resp?)?;
+                Ok(resp.tree_state)
+            }),
+        )
+    }
 }
 
 impl StorageWrite for StorageWriteServiceClient {
@@ -523,6 +523,180 @@ impl Into<Vec<LedgerInfoWithSignatures>> for GetEpochChangeLedgerInfosResponse {
     }
 }
 
+/// Helper to construct and parse [`proto::storage::CreateCheckpointRequest`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct CreateCheckpointRequest {
+    pub name: String,
+}
+
+impl CreateCheckpointRequest {
+    /// Constructor.
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
+
+impl TryFrom<crate::proto::storage::CreateCheckpointRequest> for CreateCheckpointRequest {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::storage::CreateCheckpointRequest) -> Result<Self> {
+        Ok(Self { name: proto.name })
+    }
+}
+
+impl From<CreateCheckpointRequest> for crate::proto::storage::CreateCheckpointRequest {
+    fn from(request: CreateCheckpointRequest) -> Self {
+        Self { name: request.name }
+    }
+}
+
+/// Helper to construct and parse [`proto::storage::CreateCheckpointResponse`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct CreateCheckpointResponse {
+    pub ledger_info_with_sigs: LedgerInfoWithSignatures,
+}
+
+impl CreateCheckpointResponse {
+    /// Constructor.
+    pub fn new(ledger_info_with_sigs: LedgerInfoWithSignatures) -> Self {
+        Self {
+            ledger_info_with_sigs,
+        }
+    }
+}
+
+impl TryFrom<crate::proto::storage::CreateCheckpointResponse> for CreateCheckpointResponse {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::storage::CreateCheckpointResponse) -> Result<Self> {
+        Ok(Self {
+            ledger_info_with_sigs: proto
+                .ledger_info_with_sigs
+                .ok_or_else(|| format_err!("Missing ledger_info_with_sigs"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<CreateCheckpointResponse> for crate::proto::storage::CreateCheckpointResponse {
+    fn from(response: CreateCheckpointResponse) -> Self {
+        Self {
+            ledger_info_with_sigs: Some(response.ledger_info_with_sigs.into()),
+        }
+    }
+}
+
+/// Helper to construct and parse [`proto::storage::SubscribeToTransactionsRequest`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct SubscribeToTransactionsRequest {
+    pub start_version: Version,
+}
+
+impl SubscribeToTransactionsRequest {
+    /// Constructor.
+    pub fn new(start_version: Version) -> Self {
+        Self { start_version }
+    }
+}
+
+impl TryFrom<crate::proto::storage::SubscribeToTransactionsRequest>
+    for SubscribeToTransactionsRequest
+{
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::storage::SubscribeToTransactionsRequest) -> Result<Self> {
+        Ok(Self {
+            start_version: proto.start_version,
+        })
+    }
+}
+
+impl From<SubscribeToTransactionsRequest>
+    for crate::proto::storage::SubscribeToTransactionsRequest
+{
+    fn from(request: SubscribeToTransactionsRequest) -> Self {
+        Self {
+            start_version: request.start_version,
+        }
+    }
+}
+
+/// Helper to construct and parse [`proto::storage::ReplicatedTransactions`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct ReplicatedTransactions {
+    pub batch: SaveTransactionsRequest,
+    pub tree_state: TreeState,
+}
+
+impl ReplicatedTransactions {
+    /// Constructor.
+    pub fn new(batch: SaveTransactionsRequest, tree_state: TreeState) -> Self {
+        Self { batch, tree_state }
+    }
+}
+
+impl TryFrom<crate::proto::storage::ReplicatedTransactions> for ReplicatedTransactions {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::storage::ReplicatedTransactions) -> Result<Self> {
+        let batch = proto
+            .batch
+            .ok_or_else(|| format_err!("Missing batch"))?
+            .try_into()?;
+        let tree_state = proto
+            .tree_state
+            .ok_or_else(|| format_err!("Missing tree_state"))?
+            .try_into()?;
+
+        Ok(Self { batch, tree_state })
+    }
+}
+
+impl From<ReplicatedTransactions> for crate::proto::storage::ReplicatedTransactions {
+    fn from(replicated: ReplicatedTransactions) -> Self {
+        Self {
+            batch: Some(replicated.batch.into()),
+            tree_state: Some(replicated.tree_state.into()),
+        }
+    }
+}
+
+/// Helper to construct and parse [`proto::storage::PromoteToPrimaryResponse`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct PromoteToPrimaryResponse {
+    pub tree_state: Option<TreeState>,
+}
+
+impl PromoteToPrimaryResponse {
+    /// Constructor.
+    pub fn new(tree_state: Option<TreeState>) -> Self {
+        Self { tree_state }
+    }
+}
+
+impl TryFrom<crate::proto::storage::PromoteToPrimaryResponse> for PromoteToPrimaryResponse {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::storage::PromoteToPrimaryResponse) -> Result<Self> {
+        let tree_state = proto.tree_state.map(TreeState::try_from).transpose()?;
+
+        Ok(Self { tree_state })
+    }
+}
+
+impl From<PromoteToPrimaryResponse> for crate::proto::storage::PromoteToPrimaryResponse {
+    fn from(response: PromoteToPrimaryResponse) -> Self {
+        Self {
+            tree_state: response.tree_state.map(Into::into),
+        }
+    }
+}
+
 pub mod prelude {
     pub use super::*;
 }
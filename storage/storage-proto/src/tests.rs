@@ -32,4 +32,29 @@ proptest! {
     fn test_get_startup_info_response(res in any::<GetStartupInfoResponse>()) {
         assert_protobuf_encode_decode::<crate::proto::storage::GetStartupInfoResponse, GetStartupInfoResponse>(&res);
     }
+
+    #[test]
+    fn test_create_checkpoint_request(req in any::<CreateCheckpointRequest>()) {
+        assert_protobuf_encode_decode::<crate::proto::storage::CreateCheckpointRequest, CreateCheckpointRequest>(&req);
+    }
+
+    #[test]
+    fn test_create_checkpoint_response(resp in any::<CreateCheckpointResponse>()) {
+        assert_protobuf_encode_decode::<crate::proto::storage::CreateCheckpointResponse, CreateCheckpointResponse>(&resp);
+    }
+
+    #[test]
+    fn test_subscribe_to_transactions_request(req in any::<SubscribeToTransactionsRequest>()) {
+        assert_protobuf_encode_decode::<crate::proto::storage::SubscribeToTransactionsRequest, SubscribeToTransactionsRequest>(&req);
+    }
+
+    #[test]
+    fn test_replicated_transactions(replicated in any::<ReplicatedTransactions>()) {
+        assert_protobuf_encode_decode::<crate::proto::storage::ReplicatedTransactions, ReplicatedTransactions>(&replicated);
+    }
+
+    #[test]
+    fn test_promote_to_primary_response(resp in any::<PromoteToPrimaryResponse>()) {
+        assert_protobuf_encode_decode::<crate::proto::storage::PromoteToPrimaryResponse, PromoteToPrimaryResponse>(&resp);
+    }
 }
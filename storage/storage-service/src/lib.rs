@@ -9,31 +9,68 @@
 
 #[cfg(feature = "fuzzing")]
 pub mod mocks;
+mod replication;
 
 use failure::prelude::*;
-use grpc_helpers::{provide_grpc_response, spawn_service_thread_with_drop_closure, ServerHandle};
-use libra_config::config::NodeConfig;
+use grpc_helpers::{
+    provide_grpc_response, provide_grpc_streaming_response, spawn_service_thread_with_drop_closure,
+    ServerHandle,
+};
+use libra_config::config::{NodeConfig, StorageConfig};
 use libra_logger::prelude::*;
 use libra_metrics::counters::SVC_COUNTERS;
 use libra_types::proto::types::{UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse};
 use libradb::LibraDB;
+use replication::{spawn_follower, Replication};
 use std::{
     convert::TryFrom,
     ops::Deref,
-    path::Path,
+    path::{Component, Path, PathBuf},
     sync::{mpsc, Arc, Mutex},
 };
 use storage_proto::proto::storage::{
-    create_storage, GetAccountStateWithProofByVersionRequest,
-    GetAccountStateWithProofByVersionResponse, GetEpochChangeLedgerInfosRequest,
-    GetEpochChangeLedgerInfosResponse, GetStartupInfoRequest, GetStartupInfoResponse,
-    GetTransactionsRequest, GetTransactionsResponse, SaveTransactionsRequest,
-    SaveTransactionsResponse, Storage,
+    create_storage, CreateCheckpointRequest, CreateCheckpointResponse,
+    GetAccountStateWithProofByVersionRequest, GetAccountStateWithProofByVersionResponse,
+    GetEpochChangeLedgerInfosRequest, GetEpochChangeLedgerInfosResponse, GetStartupInfoRequest,
+    GetStartupInfoResponse, GetTransactionsRequest, GetTransactionsResponse,
+    PromoteToPrimaryRequest, PromoteToPrimaryResponse, ReplicatedTransactions,
+    SaveTransactionsRequest, SaveTransactionsResponse, Storage, SubscribeToTransactionsRequest,
 };
 
-/// Starts storage service according to config.
+/// Starts storage service according to config. If a primary is configured, the service follows
+/// it until promoted.
 pub fn start_storage_service(config: &NodeConfig) -> ServerHandle {
-    let (storage_service, shutdown_receiver) = StorageService::new(&config.get_storage_dir());
+    let (storage_service, shutdown_receiver) =
+        StorageService::new_with_config(&config.get_storage_dir(), &config.storage);
+    if config.storage.replication.primary_address.is_some() {
+        spawn_follower(
+            Arc::downgrade(&storage_service.db),
+            Arc::clone(&storage_service.replication),
+            &config.storage,
+        );
+    }
+    spawn_storage_service(config, storage_service, shutdown_receiver)
+}
+
+/// Starts storage service according to config, serving the checkpoint at `checkpoint_path` in
+/// read-only mode instead of the DB in the storage directory.
+pub fn start_readonly_storage_service(
+    config: &NodeConfig,
+    checkpoint_path: &Path,
+) -> Result<ServerHandle> {
+    let (storage_service, shutdown_receiver) = StorageService::new_readonly(&checkpoint_path)?;
+    Ok(spawn_storage_service(
+        config,
+        storage_service,
+        shutdown_receiver,
+    ))
+}
+
+fn spawn_storage_service(
+    config: &NodeConfig,
+    storage_service: StorageService,
+    shutdown_receiver: mpsc::Receiver<()>,
+) -> ServerHandle {
     spawn_service_thread_with_drop_closure(
         create_storage(storage_service),
         config.storage.address.clone(),
@@ -55,6 +92,9 @@ pub fn start_storage_service(config: &NodeConfig) -> ServerHandle {
 #[derive(Clone)]
 pub struct StorageService {
     db: Arc<LibraDBWrapper>,
+    /// The directory checkpoints are created in.
+    checkpoint_root_path: PathBuf,
+    replication: Arc<Replication>,
 }
 
 /// When dropping GRPC server we want to wait until LibraDB is dropped first, so the RocksDB
@@ -73,8 +113,7 @@ struct LibraDBWrapper {
 }
 
 impl LibraDBWrapper {
-    pub fn new<P: AsRef<Path>>(path: &P) -> (Self, mpsc::Receiver<()>) {
-        let db = LibraDB::new(path);
+    pub fn new(db: LibraDB) -> (Self, mpsc::Receiver<()>) {
         let (shutdown_sender, shutdown_receiver) = mpsc::channel();
         (
             Self {
@@ -108,6 +147,9 @@ impl Deref for LibraDBWrapper {
 }
 
 impl StorageService {
+    /// Name of the directory under the storage directory that checkpoints are created in.
+    pub const CHECKPOINT_DIR_NAME: &'static str = "checkpoints";
+
     /// This opens a [`LibraDB`] at `path` and returns a [`StorageService`] instance serving it.
     ///
     /// A receiver side of a channel is also returned through which one can receive a notice after
@@ -126,10 +168,38 @@ impl StorageService {
     ///    // LibraDB instance is guaranteed to be properly dropped at this point.
     /// ```
     pub fn new<P: AsRef<Path>>(path: &P) -> (Self, mpsc::Receiver<()>) {
-        let (db_wrapper, shutdown_receiver) = LibraDBWrapper::new(path);
+        Self::new_with_config(path, &StorageConfig::default())
+    }
+
+    /// Same as [`StorageService::new`], but the underlying [`LibraDB`] is configured by `config`.
+    pub fn new_with_config<P: AsRef<Path>>(
+        path: &P,
+        config: &StorageConfig,
+    ) -> (Self, mpsc::Receiver<()>) {
+        Self::new_with_db(path, LibraDB::new_with_config(path, config), config)
+    }
+
+    /// Same as [`StorageService::new`], but opens the [`LibraDB`] at `path` in read-only mode,
+    /// typically a checkpoint. All read APIs are served as usual, while writes are refused.
+    pub fn new_readonly<P: AsRef<Path>>(path: &P) -> Result<(Self, mpsc::Receiver<()>)> {
+        Ok(Self::new_with_db(
+            path,
+            LibraDB::open_readonly(path)?,
+            &StorageConfig::default(),
+        ))
+    }
+
+    fn new_with_db<P: AsRef<Path>>(
+        path: &P,
+        db: LibraDB,
+        config: &StorageConfig,
+    ) -> (Self, mpsc::Receiver<()>) {
+        let (db_wrapper, shutdown_receiver) = LibraDBWrapper::new(db);
         (
             Self {
                 db: Arc::new(db_wrapper),
+                checkpoint_root_path: path.as_ref().join(Self::CHECKPOINT_DIR_NAME),
+                replication: Arc::new(Replication::new(config)),
             },
             shutdown_receiver,
         )
@@ -203,11 +273,7 @@ impl StorageService {
         req: SaveTransactionsRequest,
     ) -> Result<SaveTransactionsResponse> {
         let rust_req = storage_proto::SaveTransactionsRequest::try_from(req)?;
-        self.db.save_transactions(
-            &rust_req.txns_to_commit,
-            rust_req.first_version,
-            &rust_req.ledger_info_with_signatures,
-        )?;
+        self.replication.save_transactions(&self.db, rust_req)?;
         Ok(SaveTransactionsResponse::default())
     }
 
@@ -228,6 +294,52 @@ impl StorageService {
         let rust_resp = storage_proto::GetEpochChangeLedgerInfosResponse::new(ledger_infos);
         Ok(rust_resp.into())
     }
+
+    fn create_checkpoint_inner(
+        &self,
+        req: CreateCheckpointRequest,
+    ) -> Result<CreateCheckpointResponse> {
+        let rust_req = storage_proto::CreateCheckpointRequest::try_from(req)?;
+
+        // Only allow a plain directory name so that checkpoints can't be created elsewhere.
+        let mut components = Path::new(&rust_req.name).components();
+        let is_plain_name = match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => true,
+            _ => false,
+        };
+        ensure!(
+            is_plain_name,
+            "Invalid checkpoint name {:?}.",
+            rust_req.name
+        );
+
+        let ledger_info_with_sigs = self
+            .db
+            .create_checkpoint(self.checkpoint_root_path.join(&rust_req.name))?;
+        let rust_resp = storage_proto::CreateCheckpointResponse::new(ledger_info_with_sigs);
+        Ok(rust_resp.into())
+    }
+
+    fn subscribe_to_transactions_inner(
+        &self,
+        req: SubscribeToTransactionsRequest,
+    ) -> Result<impl Iterator<Item = ReplicatedTransactions>> {
+        let rust_req = storage_proto::SubscribeToTransactionsRequest::try_from(req)?;
+        let receiver = self
+            .replication
+            .subscribe(&self.db, rust_req.start_version)?;
+        Ok(receiver.into_iter().map(Into::into))
+    }
+
+    fn promote_to_primary_inner(
+        &self,
+        _req: PromoteToPrimaryRequest,
+    ) -> Result<PromoteToPrimaryResponse> {
+        self.replication.promote()?;
+        let rust_resp =
+            storage_proto::PromoteToPrimaryResponse::new(self.db.get_latest_tree_state()?);
+        Ok(rust_resp.into())
+    }
 }
 
 impl Storage for StorageService {
@@ -302,6 +414,42 @@ impl Storage for StorageService {
         let resp = self.get_epoch_change_ledger_infos_inner(req);
         provide_grpc_response(resp, ctx, sink);
     }
+
+    fn create_checkpoint(
+        &mut self,
+        ctx: grpcio::RpcContext,
+        req: CreateCheckpointRequest,
+        sink: grpcio::UnarySink<CreateCheckpointResponse>,
+    ) {
+        debug!("[GRPC] Storage::create_checkpoint");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = self.create_checkpoint_inner(req);
+        provide_grpc_response(resp, ctx, sink);
+    }
+
+    fn subscribe_to_transactions(
+        &mut self,
+        ctx: grpcio::RpcContext,
+        req: SubscribeToTransactionsRequest,
+        sink: grpcio::ServerStreamingSink<ReplicatedTransactions>,
+    ) {
+        debug!("[GRPC] Storage::subscribe_to_transactions");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = self.subscribe_to_transactions_inner(req);
+        provide_grpc_streaming_response(resp, ctx, sink);
+    }
+
+    fn promote_to_primary(
+        &mut self,
+        ctx: grpcio::RpcContext,
+        req: PromoteToPrimaryRequest,
+        sink: grpcio::UnarySink<PromoteToPrimaryResponse>,
+    ) {
+        debug!("[GRPC] Storage::promote_to_primary");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = self.promote_to_primary_inner(req);
+        provide_grpc_response(resp, ctx, sink);
+    }
 }
 
 #[cfg(test)]
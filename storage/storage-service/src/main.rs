@@ -5,13 +5,18 @@ use debug_interface::{node_debug_service::NodeDebugService, proto::create_node_d
 use executable_helpers::helpers::setup_executable;
 use failure::prelude::*;
 use grpc_helpers::spawn_service_thread;
+use grpcio::EnvBuilder;
 use libra_config::config::NodeConfig;
 use libra_logger::prelude::*;
-use std::{path::PathBuf, thread};
+use std::{path::PathBuf, sync::Arc, thread};
+use storage_client::StorageWriteServiceClient;
 use structopt::StructOpt;
 
 pub struct StorageNode {
     node_config: NodeConfig,
+    /// If set, this checkpoint is served in read-only mode instead of the DB in the storage
+    /// directory.
+    checkpoint: Option<PathBuf>,
 }
 
 impl Drop for StorageNode {
@@ -21,14 +26,22 @@ impl Drop for StorageNode {
 }
 
 impl StorageNode {
-    pub fn new(node_config: NodeConfig) -> Self {
-        StorageNode { node_config }
+    pub fn new(node_config: NodeConfig, checkpoint: Option<PathBuf>) -> Self {
+        StorageNode {
+            node_config,
+            checkpoint,
+        }
     }
 
     pub fn run(&self) -> Result<()> {
         info!("Starting storage node");
 
-        let _handle = storage_service::start_storage_service(&self.node_config);
+        let _handle = match &self.checkpoint {
+            Some(checkpoint) => {
+                storage_service::start_readonly_storage_service(&self.node_config, checkpoint)?
+            }
+            None => storage_service::start_storage_service(&self.node_config),
+        };
 
         // Start Debug interface
         let debug_service = create_node_debug_interface(NodeDebugService::new());
@@ -55,6 +68,28 @@ struct Args {
     #[structopt(short = "d", long)]
     /// Disable logging
     no_logging: bool,
+    #[structopt(long, parse(from_os_str))]
+    /// Serve the checkpoint at this path in read-only mode, instead of the DB in the storage
+    /// directory
+    checkpoint: Option<PathBuf>,
+    #[structopt(long)]
+    /// Instead of starting a storage service, promote the one running at the address in the
+    /// config from follower to primary, e.g. after the primary failed
+    promote: bool,
+}
+
+fn promote(config: &NodeConfig) -> Result<()> {
+    let client = StorageWriteServiceClient::new(
+        Arc::new(EnvBuilder::new().build()),
+        &config.storage.address,
+        config.storage.port,
+        config.storage.grpc_max_receive_len,
+    );
+    match client.promote_to_primary()? {
+        Some(tree_state) => info!("Promoted to primary at version {}.", tree_state.version),
+        None => info!("Promoted to primary with an empty DB."),
+    }
+    Ok(())
 }
 
 fn main() {
@@ -63,7 +98,12 @@ fn main() {
     let (config, _logger) =
         setup_executable(args.config.as_ref().map(PathBuf::as_path), args.no_logging);
 
-    let storage_node = StorageNode::new(config);
+    if args.promote {
+        promote(&config).expect("Unable to promote storage service");
+        return;
+    }
+
+    let storage_node = StorageNode::new(config, args.checkpoint);
 
     storage_node.run().expect("Unable to run storage node");
 }
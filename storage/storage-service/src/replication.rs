@@ -0,0 +1,250 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Write-ahead replication of a storage service to hot-standby followers.
+//!
+//! Every batch of transactions saved by a primary is published, together with the tree state
+//! right after it, to the followers subscribed to it. A follower applies each batch through the
+//! same [`LibraDB::save_transactions`] path and checks that it ends up in the same tree state.
+//! The most recent batches are buffered so that followers can catch up after reconnecting.
+
+use crate::LibraDBWrapper;
+use failure::prelude::*;
+use grpcio::EnvBuilder;
+use libra_config::config::StorageConfig;
+use libra_logger::prelude::*;
+use libra_types::transaction::Version;
+use libradb::LibraDB;
+use std::{
+    collections::VecDeque,
+    sync::{mpsc, Arc, Mutex, Weak},
+    thread,
+    time::Duration,
+};
+use storage_client::StorageWriteServiceClient;
+use storage_proto::{ReplicatedTransactions, SaveTransactionsRequest, TreeState};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Role {
+    /// Accepts writes and publishes them to followers.
+    Primary,
+    /// Replicates a primary and refuses writes of its own.
+    Follower,
+    /// A follower that ended up in a different tree state than its primary. It stops replicating
+    /// and can't be promoted.
+    Diverged,
+}
+
+/// The replication role of a storage service and the log of batches it publishes.
+pub(crate) struct Replication {
+    role: Mutex<Role>,
+    log: Mutex<ReplicationLog>,
+}
+
+struct ReplicationLog {
+    buffer_size: usize,
+    /// The most recent batches saved, oldest first.
+    recent_batches: VecDeque<ReplicatedTransactions>,
+    subscribers: Vec<mpsc::Sender<ReplicatedTransactions>>,
+}
+
+impl ReplicationLog {
+    fn publish(&mut self, replicated: ReplicatedTransactions) {
+        // Subscribers that went away are dropped here.
+        self.subscribers
+            .retain(|subscriber| subscriber.send(replicated.clone()).is_ok());
+        if self.buffer_size > 0 {
+            if self.recent_batches.len() == self.buffer_size {
+                self.recent_batches.pop_front();
+            }
+            self.recent_batches.push_back(replicated);
+        }
+    }
+}
+
+impl Replication {
+    pub fn new(config: &StorageConfig) -> Self {
+        let role = if config.replication.primary_address.is_some() {
+            Role::Follower
+        } else {
+            Role::Primary
+        };
+        Self {
+            role: Mutex::new(role),
+            log: Mutex::new(ReplicationLog {
+                buffer_size: config.replication.buffer_size,
+                recent_batches: VecDeque::new(),
+                subscribers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Saves a batch written by a client of this service.
+    pub fn save_transactions(&self, db: &LibraDB, batch: SaveTransactionsRequest) -> Result<()> {
+        let role = *self.role.lock().unwrap();
+        ensure!(
+            role == Role::Primary,
+            "Refusing writes while following a primary (role: {:?}).",
+            role
+        );
+        self.save_and_publish(db, batch)?;
+        Ok(())
+    }
+
+    /// Saves `batch` and publishes it to subscribers. The log stays locked throughout, so that
+    /// batches are published in the order they are saved.
+    fn save_and_publish(&self, db: &LibraDB, batch: SaveTransactionsRequest) -> Result<TreeState> {
+        let mut log = self.log.lock().unwrap();
+        db.save_transactions(
+            &batch.txns_to_commit,
+            batch.first_version,
+            &batch.ledger_info_with_signatures,
+        )?;
+        let tree_state = db
+            .get_latest_tree_state()?
+            .ok_or_else(|| format_err!("No tree state after saving transactions."))?;
+        log.publish(ReplicatedTransactions::new(batch, tree_state.clone()));
+        Ok(tree_state)
+    }
+
+    /// Applies a batch replicated from the primary. Returns false if this service no longer
+    /// follows the primary.
+    fn apply_replicated(&self, db: &LibraDB, replicated: ReplicatedTransactions) -> Result<bool> {
+        let mut role = self.role.lock().unwrap();
+        if *role != Role::Follower {
+            return Ok(false);
+        }
+        let tree_state = self.save_and_publish(db, replicated.batch)?;
+        if tree_state != replicated.tree_state {
+            *role = Role::Diverged;
+            bail!(
+                "Diverged from the primary at version {}: expected {:?}, got {:?}.",
+                tree_state.version,
+                replicated.tree_state,
+                tree_state,
+            );
+        }
+        Ok(true)
+    }
+
+    /// Returns the batches saved from `start_version` on, first the ones still buffered, then the
+    /// ones saved from now on as they are saved.
+    pub fn subscribe(
+        &self,
+        db: &LibraDB,
+        start_version: Version,
+    ) -> Result<mpsc::Receiver<ReplicatedTransactions>> {
+        let mut log = self.log.lock().unwrap();
+        let next_version = db
+            .get_latest_tree_state()?
+            .map_or(0, |tree_state| tree_state.version + 1);
+        let backlog = if start_version == next_version {
+            Vec::new()
+        } else {
+            let first_buffered = log
+                .recent_batches
+                .iter()
+                .position(|replicated| replicated.batch.first_version == start_version)
+                .ok_or_else(|| {
+                    format_err!(
+                        "Version {} is neither the next version {} nor the start of a buffered \
+                         batch. Restore the follower from a checkpoint of the primary instead.",
+                        start_version,
+                        next_version,
+                    )
+                })?;
+            log.recent_batches
+                .iter()
+                .skip(first_buffered)
+                .cloned()
+                .collect()
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        for replicated in backlog {
+            sender.send(replicated).expect("Receiver is alive.");
+        }
+        log.subscribers.push(sender);
+        Ok(receiver)
+    }
+
+    /// Stops following the primary and starts accepting writes. Promoting a primary is a no-op.
+    pub fn promote(&self) -> Result<()> {
+        let mut role = self.role.lock().unwrap();
+        match *role {
+            Role::Primary => info!("Already the primary, nothing to promote."),
+            Role::Follower => {
+                *role = Role::Primary;
+                info!("Promoted to primary.");
+            }
+            Role::Diverged => bail!("Refusing to promote a follower diverged from its primary."),
+        }
+        Ok(())
+    }
+}
+
+/// Spawns the thread following the primary configured in `config`. It runs until the service is
+/// promoted, diverges from the primary, or is dropped, reconnecting to the primary whenever the
+/// connection is lost.
+pub(crate) fn spawn_follower(
+    db: Weak<LibraDBWrapper>,
+    replication: Arc<Replication>,
+    config: &StorageConfig,
+) -> thread::JoinHandle<()> {
+    let primary_address = config
+        .replication
+        .primary_address
+        .clone()
+        .expect("Followers have a primary.");
+    let client = StorageWriteServiceClient::new(
+        Arc::new(EnvBuilder::new().name_prefix("grpc-sto-follower-").build()),
+        &primary_address,
+        config.replication.primary_port,
+        config.grpc_max_receive_len,
+    );
+    let retry_interval = Duration::from_millis(config.replication.retry_interval_ms);
+
+    thread::spawn(move || loop {
+        match follow(&db, &replication, &client) {
+            Ok(()) => return,
+            Err(e) => error!(
+                "Failed to replicate from primary {}: {}",
+                primary_address, e
+            ),
+        }
+        if *replication.role.lock().unwrap() != Role::Follower {
+            return;
+        }
+        thread::sleep(retry_interval);
+    })
+}
+
+/// Replicates the primary from the next version missing locally on. Returns `Ok` once there's
+/// nothing left to follow.
+fn follow(
+    db: &Weak<LibraDBWrapper>,
+    replication: &Replication,
+    client: &StorageWriteServiceClient,
+) -> Result<()> {
+    let start_version = match db.upgrade() {
+        Some(db) => db
+            .get_latest_tree_state()?
+            .map_or(0, |tree_state| tree_state.version + 1),
+        None => return Ok(()),
+    };
+    info!("Following primary from version {}.", start_version);
+
+    for replicated in client.subscribe_to_transactions(start_version)? {
+        let replicated = replicated?;
+        // Only hold on to the DB while applying a batch, so that the service can shut down while
+        // waiting for the next one.
+        let db = match db.upgrade() {
+            Some(db) => db,
+            None => return Ok(()),
+        };
+        if !replication.apply_replicated(&db, replicated)? {
+            return Ok(());
+        }
+    }
+    bail!("Stream from primary ended.")
+}
@@ -4,13 +4,17 @@
 use super::*;
 use grpcio::EnvBuilder;
 use itertools::zip_eq;
-use libra_config::config::NodeConfigHelpers;
-use libra_types::get_with_proof::{RequestItem, ResponseItem};
+use libra_config::config::{NodeConfig, NodeConfigHelpers};
+use libra_types::{
+    crypto_proxies::LedgerInfoWithSignatures,
+    get_with_proof::{RequestItem, ResponseItem},
+    transaction::TransactionToCommit,
+};
 use libradb::mock_genesis::db_with_mock_genesis;
 #[cfg(test)]
 use libradb::test_helper::arb_blocks_to_commit;
 use proptest::prelude::*;
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Debug, thread, time::Duration};
 use storage_client::{
     StorageRead, StorageReadServiceClient, StorageWrite, StorageWriteServiceClient,
 };
@@ -24,6 +28,18 @@ fn start_test_storage_with_read_write_client(
     StorageWriteServiceClient,
 ) {
     let mut config = NodeConfigHelpers::get_single_node_test_config(/* random_ports = */ true);
+    start_test_storage(&mut config, need_to_use_genesis)
+}
+
+fn start_test_storage(
+    config: &mut NodeConfig,
+    need_to_use_genesis: bool,
+) -> (
+    libra_tools::tempdir::TempPath,
+    ServerHandle,
+    StorageReadServiceClient,
+    StorageWriteServiceClient,
+) {
     let tmp_dir = libra_tools::tempdir::TempPath::new();
     config.storage.dir = tmp_dir.path().to_path_buf();
 
@@ -33,7 +49,7 @@ fn start_test_storage_with_read_write_client(
     } else {
         LibraDB::new(&tmp_dir);
     }
-    let storage_server_handle = start_storage_service(&config);
+    let storage_server_handle = start_storage_service(config);
 
     let read_client = StorageReadServiceClient::new(
         Arc::new(EnvBuilder::new().build()),
@@ -49,9 +65,81 @@ fn start_test_storage_with_read_write_client(
     (tmp_dir, storage_server_handle, read_client, write_client)
 }
 
+/// Polls `actual` until it returns `expected`, for a few seconds at most.
+fn wait_until_eq<T: Debug + PartialEq>(expected: T, actual: impl Fn() -> T) {
+    for _ in 0..500 {
+        if actual() == expected {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(actual(), expected);
+}
+
+fn test_replication_and_promotion_impl(
+    mut blocks: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let mut primary_config =
+        NodeConfigHelpers::get_single_node_test_config(/* random_ports = */ true);
+    let (_primary_dir, primary_handle, primary_read, primary_write) =
+        start_test_storage(&mut primary_config, /* need_to_use_genesis = */ true);
+    let mut follower_config =
+        NodeConfigHelpers::get_single_node_test_config(/* random_ports = */ true);
+    follower_config.storage.replication.primary_address =
+        Some(primary_config.storage.address.clone());
+    follower_config.storage.replication.primary_port = primary_config.storage.port;
+    follower_config.storage.replication.retry_interval_ms = 10;
+    let (_follower_dir, _follower_handle, follower_read, follower_write) =
+        start_test_storage(&mut follower_config, /* need_to_use_genesis = */ true);
+
+    // Everything saved on the primary shows up on the follower.
+    let (last_txns, last_ledger_info_with_sigs) = blocks.pop().unwrap();
+    let mut version = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in blocks {
+        let num_txns = txns_to_commit.len() as u64;
+        primary_write
+            .save_transactions(txns_to_commit, version + 1, Some(ledger_info_with_sigs))
+            .unwrap();
+        version += num_txns;
+    }
+    let primary_startup_info = primary_read.get_startup_info().unwrap();
+    wait_until_eq(primary_startup_info, || {
+        follower_read.get_startup_info().unwrap()
+    });
+
+    // Followers refuse writes of their own.
+    assert!(follower_write
+        .save_transactions(
+            last_txns.clone(),
+            version + 1,
+            Some(last_ledger_info_with_sigs.clone())
+        )
+        .is_err());
+    // Subscribers can't skip versions.
+    assert!(primary_write
+        .subscribe_to_transactions(version + 2)
+        .unwrap()
+        .next()
+        .unwrap()
+        .is_err());
+
+    // Once the primary is gone, the follower takes over.
+    drop(primary_handle);
+    let tree_state = follower_write.promote_to_primary().unwrap().unwrap();
+    assert_eq!(tree_state.version, version);
+    follower_write
+        .save_transactions(last_txns, version + 1, Some(last_ledger_info_with_sigs))
+        .unwrap();
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
 
+    #[test]
+    fn test_replication_and_promotion(blocks in arb_blocks_to_commit().no_shrink()) {
+        test_replication_and_promotion_impl(blocks);
+    }
+
     #[test]
     fn test_storage_service_basic(blocks in arb_blocks_to_commit().no_shrink()) {
         let(_tmp_dir, _server_handler, read_client, write_client) =
@@ -79,6 +167,7 @@ proptest! {
                 .keys()
                 .map(|address| RequestItem::GetAccountState{
                     address: *address,
+                    version: None,
                 }).collect::<Vec<_>>();
             let (
                 response_items,
@@ -108,3 +197,39 @@ proptest! {
          }
     }
 }
+
+#[test]
+fn test_create_checkpoint() {
+    let (tmp_dir, _server_handler, read_client, write_client) =
+        start_test_storage_with_read_write_client(/* need_to_use_genesis = */ true);
+
+    let ledger_info_with_sigs = write_client
+        .create_checkpoint("checkpoint".to_string())
+        .unwrap();
+    let (_, latest_ledger_info_with_sigs, _, _) =
+        read_client.update_to_latest_ledger(0, vec![]).unwrap();
+    assert_eq!(ledger_info_with_sigs, latest_ledger_info_with_sigs);
+
+    // The checkpoint can be served separately.
+    let (checkpoint, _shutdown_receiver) = StorageService::new_readonly(
+        &tmp_dir
+            .path()
+            .join(StorageService::CHECKPOINT_DIR_NAME)
+            .join("checkpoint"),
+    )
+    .unwrap();
+    assert_eq!(
+        checkpoint.db.get_startup_info().unwrap(),
+        read_client.get_startup_info().unwrap()
+    );
+
+    // An existing checkpoint is not overwritten.
+    assert!(write_client
+        .create_checkpoint("checkpoint".to_string())
+        .is_err());
+    // Checkpoints can only be created in the checkpoint directory.
+    assert!(write_client
+        .create_checkpoint("../checkpoint".to_string())
+        .is_err());
+    assert!(write_client.create_checkpoint("".to_string()).is_err());
+}
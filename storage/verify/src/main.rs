@@ -0,0 +1,44 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Command line tool to check that a LibraDB is internally consistent, e.g. after a disk incident.
+//!
+//! The DB is opened in read-only mode, so it can be a checkpoint of the DB of a running node. The
+//! tool exits with a non-zero code if any inconsistency is found.
+
+use libradb::{verify::VerifyOptions, LibraDB};
+use std::{path::PathBuf, process};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Tool to check the integrity of a LibraDB")]
+struct Opt {
+    /// Directory of the DB to check
+    #[structopt(long = "db-dir", parse(from_os_str))]
+    db_dir: PathBuf,
+    /// Check only one out of every N transactions and index entries, and sample accounts instead
+    /// of walking the whole state tree
+    #[structopt(long = "sample-rate", default_value = "1")]
+    sample_rate: u64,
+    /// Version of the state tree to check (default: latest)
+    #[structopt(long = "state-version")]
+    state_version: Option<u64>,
+}
+
+fn main() {
+    ::libra_logger::init_for_e2e_testing();
+    let opt = Opt::from_args();
+
+    let db = LibraDB::open_readonly(&opt.db_dir).expect("Failed to open DB.");
+    let report = db
+        .verify(&VerifyOptions {
+            sample_rate: opt.sample_rate,
+            state_version: opt.state_version,
+        })
+        .expect("Verification failed.");
+
+    print!("{}", report);
+    if !report.is_consistent() {
+        process::exit(1);
+    }
+}
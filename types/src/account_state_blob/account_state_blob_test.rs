@@ -26,6 +26,29 @@ proptest! {
     fn account_state_with_proof(account_state_with_proof in any::<AccountStateWithProof>()) {
         assert_protobuf_encode_decode::<crate::proto::types::AccountStateWithProof, AccountStateWithProof>(&account_state_with_proof);
     }
+
+    #[test]
+    fn account_state_range_with_proof(account_state_range_with_proof in any::<AccountStateRangeWithProof>()) {
+        assert_protobuf_encode_decode::<crate::proto::types::AccountStateRangeWithProof, AccountStateRangeWithProof>(&account_state_range_with_proof);
+    }
+
+    #[test]
+    fn account_state_range_next_cursor(mut range in any::<AccountStateRangeWithProof>()) {
+        let blob = AccountStateBlob::from(vec![1u8]);
+        range.account_blobs.clear();
+        prop_assert_eq!(range.next_cursor(), None);
+
+        let mut key = [0u8; HashValue::LENGTH];
+        key[HashValue::LENGTH - 2] = 0x12;
+        key[HashValue::LENGTH - 1] = 0xff;
+        range.account_blobs.push((HashValue::new(key), blob.clone()));
+        let mut expected = [0u8; HashValue::LENGTH];
+        expected[HashValue::LENGTH - 2] = 0x13;
+        prop_assert_eq!(range.next_cursor(), Some(HashValue::new(expected)));
+
+        range.account_blobs.push((HashValue::new([0xff; HashValue::LENGTH]), blob));
+        prop_assert_eq!(range.next_cursor(), None);
+    }
 }
 
 #[test]
@@ -4,8 +4,11 @@
 #[cfg(any(test, feature = "fuzzing"))]
 use crate::account_config::{account_resource_path, AccountResource};
 use crate::{
-    account_address::AccountAddress, account_config::get_account_resource_or_default,
-    ledger_info::LedgerInfo, proof::AccountStateProof, transaction::Version,
+    account_address::AccountAddress,
+    account_config::get_account_resource_or_default,
+    ledger_info::LedgerInfo,
+    proof::{AccountStateProof, AccountStateRangeProof},
+    transaction::Version,
 };
 use failure::prelude::*;
 use libra_crypto::{
@@ -212,5 +215,124 @@ impl From<AccountStateWithProof> for crate::proto::types::AccountStateWithProof
     }
 }
 
+/// A page of account states in ascending order of key (hashed address), together with the proof
+/// the client can use to authenticate that it's complete.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct AccountStateRangeWithProof {
+    /// The transaction version at which these account states are seen.
+    pub version: Version,
+    /// Hashed addresses and blobs of the accounts, in ascending order of the hashed address.
+    pub account_blobs: Vec<(HashValue, AccountStateBlob)>,
+    /// The proof the client can use to authenticate the range.
+    pub proof: AccountStateRangeProof,
+}
+
+impl AccountStateRangeWithProof {
+    /// Constructor.
+    pub fn new(
+        version: Version,
+        account_blobs: Vec<(HashValue, AccountStateBlob)>,
+        proof: AccountStateRangeProof,
+    ) -> Self {
+        Self {
+            version,
+            account_blobs,
+            proof,
+        }
+    }
+
+    /// Verifies the account state blobs with the proof, both carried by `self`.
+    ///
+    /// Two things are ensured if no error is raised:
+    ///   1. These account states exist in the ledger represented by `ledger_info` and are seen at
+    /// the time the transaction at version `version` is just committed.
+    ///   2. They are the first (at most `limit`) accounts whose hashed addresses are not less than
+    /// `cursor`, with none left out.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        version: Version,
+        cursor: HashValue,
+        limit: u64,
+    ) -> Result<()> {
+        ensure!(
+            self.version == version,
+            "State version ({}) is not expected ({}).",
+            self.version,
+            version,
+        );
+
+        self.proof
+            .verify(ledger_info, version, cursor, limit, &self.account_blobs)
+    }
+
+    /// Returns the cursor to get the page following this one, or `None` if this page is empty or
+    /// ends with the largest possible key.
+    pub fn next_cursor(&self) -> Option<HashValue> {
+        let (last_key, _) = self.account_blobs.last()?;
+        let mut bytes = *last_key.as_ref();
+        // Adds one to the big-endian key.
+        for byte in bytes.iter_mut().rev() {
+            if *byte == std::u8::MAX {
+                *byte = 0;
+            } else {
+                *byte += 1;
+                return Some(HashValue::new(bytes));
+            }
+        }
+        None
+    }
+}
+
+impl TryFrom<crate::proto::types::AccountStateRangeWithProof> for AccountStateRangeWithProof {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::types::AccountStateRangeWithProof) -> Result<Self> {
+        ensure!(
+            proto.keys.len() == proto.blobs.len(),
+            "Number of keys ({}) does not match number of blobs ({}).",
+            proto.keys.len(),
+            proto.blobs.len(),
+        );
+        let account_blobs = proto
+            .keys
+            .iter()
+            .zip(proto.blobs.into_iter())
+            .map(|(key, blob)| {
+                Ok((
+                    HashValue::from_slice(key)?,
+                    AccountStateBlob::try_from(blob)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self::new(
+            proto.version,
+            account_blobs,
+            proto
+                .proof
+                .ok_or_else(|| format_err!("Missing proof"))?
+                .try_into()?,
+        ))
+    }
+}
+
+impl From<AccountStateRangeWithProof> for crate::proto::types::AccountStateRangeWithProof {
+    fn from(range: AccountStateRangeWithProof) -> Self {
+        let (keys, blobs) = range
+            .account_blobs
+            .into_iter()
+            .map(|(key, blob)| (key.to_vec(), blob.into()))
+            .unzip();
+        Self {
+            version: range.version,
+            keys,
+            blobs,
+            proof: Some(range.proof.into()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod account_state_blob_test;
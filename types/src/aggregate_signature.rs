@@ -0,0 +1,99 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::account_address::AccountAddress;
+use failure::prelude::*;
+use libra_crypto::bls12381::BLS12381Signature;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// The BLS12-381 signatures of several validators over the same message, aggregated into one.
+/// The signers are recorded in a bitmap over the validators ordered by account address, so that
+/// the size of an aggregate signature barely grows with the number of validators.
+///
+/// Aggregate signatures are built and verified by a
+/// [`ValidatorVerifier`](crate::validator_verifier::ValidatorVerifier) of BLS12-381 public keys.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AggregateSignature {
+    /// Bit `i % 8` of byte `i / 8` is set if the `i`-th validator signed.
+    signers: Vec<u8>,
+    signature: BLS12381Signature,
+}
+
+impl AggregateSignature {
+    pub fn new(signers: Vec<u8>, signature: BLS12381Signature) -> Self {
+        Self { signers, signature }
+    }
+
+    /// Returns a bitmap with the given indices of validators set, out of `num_validators`.
+    pub fn signers_bitmap(
+        num_validators: usize,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Vec<u8> {
+        let mut signers = vec![0u8; (num_validators + 7) / 8];
+        for index in indices {
+            signers[index / 8] |= 1 << (index % 8);
+        }
+        signers
+    }
+
+    /// Returns the signers out of `validators`, which must be ordered by account address. Returns
+    /// None if the bitmap doesn't match the number of validators.
+    pub fn signers<'a>(&self, validators: &'a [AccountAddress]) -> Option<Vec<&'a AccountAddress>> {
+        Self::decode_signers_bitmap(&self.signers, validators)
+    }
+
+    /// Returns the validators set in `bitmap`, as built by `signers_bitmap`, out of `validators`
+    /// ordered by account address. Returns None if the bitmap doesn't match the number of
+    /// validators.
+    pub fn decode_signers_bitmap<'a>(
+        bitmap: &[u8],
+        validators: &'a [AccountAddress],
+    ) -> Option<Vec<&'a AccountAddress>> {
+        if bitmap.len() != (validators.len() + 7) / 8 {
+            return None;
+        }
+        let is_signer = |index: usize| bitmap[index / 8] & (1 << (index % 8)) != 0;
+        // Bits past the last validator must be left unset.
+        if (validators.len()..bitmap.len() * 8).any(is_signer) {
+            return None;
+        }
+        Some(
+            validators
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| is_signer(*index))
+                .map(|(_, validator)| validator)
+                .collect(),
+        )
+    }
+
+    /// The bitmap of the signers, see `signers_bitmap`.
+    pub fn bitmap(&self) -> &[u8] {
+        &self.signers
+    }
+
+    pub fn signature(&self) -> &BLS12381Signature {
+        &self.signature
+    }
+}
+
+impl TryFrom<crate::proto::types::AggregateSignature> for AggregateSignature {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::types::AggregateSignature) -> Result<Self> {
+        Ok(Self::new(
+            proto.signers,
+            BLS12381Signature::try_from(&proto.signature[..])?,
+        ))
+    }
+}
+
+impl From<AggregateSignature> for crate::proto::types::AggregateSignature {
+    fn from(aggregate_signature: AggregateSignature) -> Self {
+        Self {
+            signers: aggregate_signature.signers,
+            signature: aggregate_signature.signature.to_bytes().to_vec(),
+        }
+    }
+}
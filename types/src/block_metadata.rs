@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::account_address::AccountAddress;
+use crate::aggregate_signature::AggregateSignature;
 use crate::byte_array::ByteArray;
 use failure::prelude::*;
 use libra_crypto::{ed25519::Ed25519Signature, HashValue};
@@ -22,29 +23,60 @@ use std::collections::BTreeMap;
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BlockMetadata {
     id: HashValue,
+    round: u64,
     timestamp_usec: u64,
     // Since Move doesn't support hashmaps, this vote map would be stored as a vector of key value
     // pairs in the Move module. Thus we need a BTreeMap here to define how the values are being
     // ordered.
     previous_block_votes: BTreeMap<AccountAddress, Ed25519Signature>,
+    // When the votes for the previous block were aggregated into one signature instead, the
+    // bitmap of their signers over the validators ordered by account address (see
+    // `AggregateSignature`). Empty otherwise.
+    previous_block_signers: Vec<u8>,
     proposer: AccountAddress,
 }
 
 impl BlockMetadata {
     pub fn new(
         id: HashValue,
+        round: u64,
         timestamp_usec: u64,
         previous_block_votes: BTreeMap<AccountAddress, Ed25519Signature>,
+        previous_block_signers: Vec<u8>,
         proposer: AccountAddress,
     ) -> Self {
         Self {
             id,
+            round,
             timestamp_usec,
             previous_block_votes,
+            previous_block_signers,
             proposer,
         }
     }
 
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    pub fn previous_block_votes(&self) -> &BTreeMap<AccountAddress, Ed25519Signature> {
+        &self.previous_block_votes
+    }
+
+    /// Returns the validators which voted for the previous block, out of `validators` ordered by
+    /// account address, whether their votes were aggregated or not.
+    pub fn previous_block_voters(&self, validators: &[AccountAddress]) -> Vec<AccountAddress> {
+        if self.previous_block_signers.is_empty() {
+            return self.previous_block_votes.keys().copied().collect();
+        }
+        AggregateSignature::decode_signers_bitmap(&self.previous_block_signers, validators)
+            .map_or_else(Vec::new, |signers| signers.into_iter().copied().collect())
+    }
+
+    pub fn proposer(&self) -> AccountAddress {
+        self.proposer
+    }
+
     pub fn into_inner(self) -> Result<(ByteArray, u64, ByteArray, AccountAddress)> {
         let id = ByteArray::new(self.id.to_vec());
         let vote_maps = ByteArray::new(lcs::to_bytes(&self.previous_block_votes)?);
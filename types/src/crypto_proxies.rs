@@ -78,7 +78,7 @@ impl<Sig: RawSignature> From<Sig> for SignatureWrapper<Sig> {
 // types that do not go through the instantiated polymorphic structures
 // below is banned.
 
-use libra_crypto::ed25519::*;
+use libra_crypto::{bls12381::BLS12381Signature, ed25519::*};
 use std::collections::BTreeMap;
 
 // used in chained_bft::consensus_types::block_test
@@ -91,6 +91,8 @@ pub type ValidatorInfo = RawValidatorInfo<Ed25519PublicKey>;
 pub type ValidatorVerifier = RawValidatorVerifier<Ed25519PublicKey>;
 pub type ValidatorSigner = RawValidatorSigner<Ed25519PrivateKey>;
 pub type ValidatorChangeEventWithProof = RawValidatorChangeEventWithProof<Ed25519Signature>;
+// The signatures aggregated into one in the certificates of consensus, when configured to.
+pub type AggregatableSignature = BLS12381Signature;
 
 /// Helper function to get random validator signers and a corresponding validator verifier for
 /// testing.  If custom_voting_power_quorum is not None, set a custom voting power quorum amount.
@@ -99,6 +101,35 @@ pub fn random_validator_verifier(
     count: usize,
     custom_voting_power_quorum: Option<u64>,
     pseudo_random_account_address: bool,
+) -> (Vec<ValidatorSigner>, ValidatorVerifier) {
+    random_validator_verifier_impl(
+        count,
+        custom_voting_power_quorum,
+        pseudo_random_account_address,
+        false,
+    )
+}
+
+/// Same as [`random_validator_verifier`], with the BLS12-381 public keys of the signers in the
+/// validator verifier too, for testing aggregate signatures.
+pub fn random_bls_validator_verifier(
+    count: usize,
+    custom_voting_power_quorum: Option<u64>,
+    pseudo_random_account_address: bool,
+) -> (Vec<ValidatorSigner>, ValidatorVerifier) {
+    random_validator_verifier_impl(
+        count,
+        custom_voting_power_quorum,
+        pseudo_random_account_address,
+        true,
+    )
+}
+
+fn random_validator_verifier_impl(
+    count: usize,
+    custom_voting_power_quorum: Option<u64>,
+    pseudo_random_account_address: bool,
+    with_bls_public_keys: bool,
 ) -> (Vec<ValidatorSigner>, ValidatorVerifier) {
     let mut signers = Vec::new();
     let mut account_address_to_validator_info = BTreeMap::new();
@@ -108,10 +139,16 @@ pub fn random_validator_verifier(
         } else {
             ValidatorSigner::random([i as u8; 32])
         };
-        account_address_to_validator_info.insert(
-            random_signer.author(),
-            ValidatorInfo::new(random_signer.public_key(), 1),
-        );
+        let mut validator_info = ValidatorInfo::new(random_signer.public_key(), 1);
+        if with_bls_public_keys {
+            validator_info = validator_info
+                .with_bls_public_key(
+                    random_signer.bls_public_key(),
+                    &random_signer.bls_proof_of_possession(),
+                )
+                .expect("Signers prove possession of their keys");
+        }
+        account_address_to_validator_info.insert(random_signer.author(), validator_info);
         signers.push(random_signer);
     }
     (
@@ -5,22 +5,24 @@ use crate::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_config::get_account_resource_or_default,
-    account_state_blob::AccountStateWithProof,
+    account_state_blob::{AccountStateRangeWithProof, AccountStateWithProof},
     contract_event::EventWithProof,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     proof::AccumulatorConsistencyProof,
     proto::types::{
-        GetAccountStateRequest, GetAccountStateResponse,
-        GetAccountTransactionBySequenceNumberRequest,
+        GetAccountStateRequest, GetAccountStateResponse, GetAccountStatesRangeRequest,
+        GetAccountStatesRangeResponse, GetAccountTransactionBySequenceNumberRequest,
         GetAccountTransactionBySequenceNumberResponse, GetEventsByEventAccessPathRequest,
-        GetEventsByEventAccessPathResponse, GetTransactionsRequest, GetTransactionsResponse,
+        GetEventsByEventAccessPathResponse, GetTransactionsByReceiverRequest,
+        GetTransactionsByReceiverResponse, GetTransactionsByScriptHashRequest,
+        GetTransactionsByScriptHashResponse, GetTransactionsRequest, GetTransactionsResponse,
     },
-    transaction::{TransactionListWithProof, TransactionWithProof, Version},
+    transaction::{Script, TransactionListWithProof, TransactionWithProof, Version},
     validator_change::ValidatorChangeEventWithProof,
     validator_verifier::ValidatorVerifier,
 };
 use failure::prelude::*;
-use libra_crypto::{hash::CryptoHash, *};
+use libra_crypto::*;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use std::{
@@ -182,10 +184,7 @@ pub fn verify_update_to_latest_ledger_response<Sig: Signature>(
     response_items: &[ResponseItem],
     ledger_info_with_sigs: &LedgerInfoWithSignatures<Sig>,
 ) -> Result<()> {
-    let (ledger_info, signatures) = (
-        ledger_info_with_sigs.ledger_info(),
-        ledger_info_with_sigs.signatures(),
-    );
+    let ledger_info = ledger_info_with_sigs.ledger_info();
 
     // Verify that the same or a newer ledger info is returned.
     ensure!(
@@ -196,8 +195,8 @@ pub fn verify_update_to_latest_ledger_response<Sig: Signature>(
     );
 
     // Verify ledger info signatures.
-    if !(ledger_info.version() == 0 && signatures.is_empty()) {
-        validator_verifier.batch_verify_aggregated_signature(ledger_info.hash(), signatures)?;
+    if !(ledger_info.version() == 0 && !ledger_info_with_sigs.is_signed()) {
+        ledger_info_with_sigs.verify(&validator_verifier)?;
     }
 
     // Verify each sub response.
@@ -222,11 +221,15 @@ fn verify_response_item(
     match (req, res) {
         // GetAccountState
         (
-            RequestItem::GetAccountState { address },
+            RequestItem::GetAccountState { address, version },
             ResponseItem::GetAccountState {
                 account_state_with_proof,
             },
-        ) => account_state_with_proof.verify(ledger_info, ledger_info.version(), *address),
+        ) => account_state_with_proof.verify(
+            ledger_info,
+            version.unwrap_or_else(|| ledger_info.version()),
+            *address,
+        ),
         // GetAccountTransactionBySequenceNumber
         (
             RequestItem::GetAccountTransactionBySequenceNumber {
@@ -284,6 +287,56 @@ fn verify_response_item(
             *fetch_events,
             txn_list_with_proof,
         ),
+        // GetAccountStatesRange
+        (
+            RequestItem::GetAccountStatesRange { cursor, limit },
+            ResponseItem::GetAccountStatesRange {
+                account_state_range_with_proof,
+            },
+        ) => account_state_range_with_proof.verify(
+            ledger_info,
+            ledger_info.version(),
+            *cursor,
+            *limit,
+        ),
+        // GetTransactionsByScriptHash
+        (
+            RequestItem::GetTransactionsByScriptHash {
+                script_hash,
+                start_version,
+                limit,
+                fetch_events,
+            },
+            ResponseItem::GetTransactionsByScriptHash {
+                txn_lists_with_proof,
+            },
+        ) => verify_get_indexed_txns_resp(
+            ledger_info,
+            *start_version,
+            *limit,
+            *fetch_events,
+            txn_lists_with_proof,
+            |script| script.code_hash() == *script_hash,
+        ),
+        // GetTransactionsByReceiver
+        (
+            RequestItem::GetTransactionsByReceiver {
+                receiver,
+                start_version,
+                limit,
+                fetch_events,
+            },
+            ResponseItem::GetTransactionsByReceiver {
+                txn_lists_with_proof,
+            },
+        ) => verify_get_indexed_txns_resp(
+            ledger_info,
+            *start_version,
+            *limit,
+            *fetch_events,
+            txn_lists_with_proof,
+            |script| script.address_args().contains(receiver),
+        ),
         // Request-response item types mismatch.
         _ => bail!(
             "RequestItem/ResponseItem types mismatch. request: {:?}, response: {:?}",
@@ -428,6 +481,58 @@ fn verify_get_txns_resp(
     }
 }
 
+/// Verifies the response to a query served by one of the transaction indices. Every transaction
+/// returned must be on the ledger and run a script satisfying `matches`.
+///
+/// Note that this does not prove that no matching transaction is left out.
+fn verify_get_indexed_txns_resp(
+    ledger_info: &LedgerInfo,
+    req_start_version: Version,
+    req_limit: u64,
+    req_fetch_events: bool,
+    txn_lists_with_proof: &[TransactionListWithProof],
+    matches: impl Fn(&Script) -> bool,
+) -> Result<()> {
+    let mut next_version = req_start_version;
+    let mut num_txns = 0;
+    for txn_list_with_proof in txn_lists_with_proof {
+        let first_version = txn_list_with_proof
+            .first_transaction_version
+            .ok_or_else(|| format_err!("Empty transaction list returned."))?;
+        ensure!(
+            first_version >= next_version,
+            "Transaction lists out of order. First version: {}, expected at least: {}.",
+            first_version,
+            next_version,
+        );
+        ensure!(
+            req_fetch_events == txn_list_with_proof.events.is_some(),
+            "Bad indexed transactions response. Events requested: {}, events returned: {}.",
+            req_fetch_events,
+            txn_list_with_proof.events.is_some(),
+        );
+        txn_list_with_proof.verify(ledger_info, Some(first_version))?;
+        for (txn, version) in txn_list_with_proof.transactions.iter().zip(first_version..) {
+            ensure!(
+                txn.as_script().map_or(false, &matches),
+                "Transaction at version {} doesn't match the query.",
+                version,
+            );
+        }
+
+        num_txns += txn_list_with_proof.transactions.len() as u64;
+        next_version = first_version + txn_list_with_proof.transactions.len() as u64;
+    }
+    ensure!(
+        num_txns <= req_limit,
+        "More transactions returned than requested. num_txns: {}, limit: {}.",
+        num_txns,
+        req_limit,
+    );
+
+    Ok(())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub enum RequestItem {
@@ -439,6 +544,8 @@ pub enum RequestItem {
     // this can't be the first variant, tracked here https://github.com/AltSysrq/proptest/issues/141
     GetAccountState {
         address: AccountAddress,
+        /// The version at which to get the account state. `None` means the latest version.
+        version: Option<Version>,
     },
     GetEventsByEventAccessPath {
         access_path: AccessPath,
@@ -451,6 +558,22 @@ pub enum RequestItem {
         limit: u64,
         fetch_events: bool,
     },
+    GetAccountStatesRange {
+        cursor: HashValue,
+        limit: u64,
+    },
+    GetTransactionsByScriptHash {
+        script_hash: HashValue,
+        start_version: Version,
+        limit: u64,
+        fetch_events: bool,
+    },
+    GetTransactionsByReceiver {
+        receiver: AccountAddress,
+        start_version: Version,
+        limit: u64,
+        fetch_events: bool,
+    },
 }
 
 impl TryFrom<crate::proto::types::RequestItem> for RequestItem {
@@ -466,7 +589,8 @@ impl TryFrom<crate::proto::types::RequestItem> for RequestItem {
         let request = match item {
             GetAccountStateRequest(request) => {
                 let address = AccountAddress::try_from(request.address)?;
-                RequestItem::GetAccountState { address }
+                let version = request.version;
+                RequestItem::GetAccountState { address, version }
             }
             GetAccountTransactionBySequenceNumberRequest(request) => {
                 let account = AccountAddress::try_from(request.account)?;
@@ -506,6 +630,38 @@ impl TryFrom<crate::proto::types::RequestItem> for RequestItem {
                     fetch_events,
                 }
             }
+            GetAccountStatesRangeRequest(request) => {
+                let cursor = HashValue::from_slice(&request.cursor)?;
+                let limit = request.limit;
+
+                RequestItem::GetAccountStatesRange { cursor, limit }
+            }
+            GetTransactionsByScriptHashRequest(request) => {
+                let script_hash = HashValue::from_slice(&request.script_hash)?;
+                let start_version = request.start_version;
+                let limit = request.limit;
+                let fetch_events = request.fetch_events;
+
+                RequestItem::GetTransactionsByScriptHash {
+                    script_hash,
+                    start_version,
+                    limit,
+                    fetch_events,
+                }
+            }
+            GetTransactionsByReceiverRequest(request) => {
+                let receiver = AccountAddress::try_from(request.receiver)?;
+                let start_version = request.start_version;
+                let limit = request.limit;
+                let fetch_events = request.fetch_events;
+
+                RequestItem::GetTransactionsByReceiver {
+                    receiver,
+                    start_version,
+                    limit,
+                    fetch_events,
+                }
+            }
         };
 
         Ok(request)
@@ -517,9 +673,10 @@ impl From<RequestItem> for crate::proto::types::RequestItem {
         use crate::proto::types::request_item::RequestedItems;
 
         let req = match request {
-            RequestItem::GetAccountState { address } => {
+            RequestItem::GetAccountState { address, version } => {
                 RequestedItems::GetAccountStateRequest(GetAccountStateRequest {
                     address: address.into(),
+                    version,
                 })
             }
             RequestItem::GetAccountTransactionBySequenceNumber {
@@ -555,6 +712,38 @@ impl From<RequestItem> for crate::proto::types::RequestItem {
                 limit,
                 fetch_events,
             }),
+            RequestItem::GetAccountStatesRange { cursor, limit } => {
+                RequestedItems::GetAccountStatesRangeRequest(GetAccountStatesRangeRequest {
+                    cursor: cursor.to_vec(),
+                    limit,
+                })
+            }
+            RequestItem::GetTransactionsByScriptHash {
+                script_hash,
+                start_version,
+                limit,
+                fetch_events,
+            } => RequestedItems::GetTransactionsByScriptHashRequest(
+                GetTransactionsByScriptHashRequest {
+                    script_hash: script_hash.to_vec(),
+                    start_version,
+                    limit,
+                    fetch_events,
+                },
+            ),
+            RequestItem::GetTransactionsByReceiver {
+                receiver,
+                start_version,
+                limit,
+                fetch_events,
+            } => {
+                RequestedItems::GetTransactionsByReceiverRequest(GetTransactionsByReceiverRequest {
+                    receiver: receiver.into(),
+                    start_version,
+                    limit,
+                    fetch_events,
+                })
+            }
         };
 
         Self {
@@ -583,6 +772,15 @@ pub enum ResponseItem {
     GetTransactions {
         txn_list_with_proof: TransactionListWithProof,
     },
+    GetAccountStatesRange {
+        account_state_range_with_proof: AccountStateRangeWithProof,
+    },
+    GetTransactionsByScriptHash {
+        txn_lists_with_proof: Vec<TransactionListWithProof>,
+    },
+    GetTransactionsByReceiver {
+        txn_lists_with_proof: Vec<TransactionListWithProof>,
+    },
 }
 
 impl ResponseItem {
@@ -627,6 +825,37 @@ impl ResponseItem {
             _ => bail!("Not ResponseItem::GetTransactions."),
         }
     }
+
+    pub fn into_get_account_states_range_response(self) -> Result<AccountStateRangeWithProof> {
+        match self {
+            ResponseItem::GetAccountStatesRange {
+                account_state_range_with_proof,
+            } => Ok(account_state_range_with_proof),
+            _ => bail!("Not ResponseItem::GetAccountStatesRange."),
+        }
+    }
+
+    pub fn into_get_transactions_by_script_hash_response(
+        self,
+    ) -> Result<Vec<TransactionListWithProof>> {
+        match self {
+            ResponseItem::GetTransactionsByScriptHash {
+                txn_lists_with_proof,
+            } => Ok(txn_lists_with_proof),
+            _ => bail!("Not ResponseItem::GetTransactionsByScriptHash."),
+        }
+    }
+
+    pub fn into_get_transactions_by_receiver_response(
+        self,
+    ) -> Result<Vec<TransactionListWithProof>> {
+        match self {
+            ResponseItem::GetTransactionsByReceiver {
+                txn_lists_with_proof,
+            } => Ok(txn_lists_with_proof),
+            _ => bail!("Not ResponseItem::GetTransactionsByReceiver."),
+        }
+    }
 }
 
 impl TryFrom<crate::proto::types::ResponseItem> for ResponseItem {
@@ -690,6 +919,38 @@ impl TryFrom<crate::proto::types::ResponseItem> for ResponseItem {
                     txn_list_with_proof,
                 }
             }
+            GetAccountStatesRangeResponse(response) => {
+                let account_state_range_with_proof = response
+                    .account_state_range_with_proof
+                    .ok_or_else(|| format_err!("Missing account_state_range_with_proof"))?
+                    .try_into()?;
+
+                ResponseItem::GetAccountStatesRange {
+                    account_state_range_with_proof,
+                }
+            }
+            GetTransactionsByScriptHashResponse(response) => {
+                let txn_lists_with_proof = response
+                    .txn_lists_with_proof
+                    .into_iter()
+                    .map(TryFrom::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+
+                ResponseItem::GetTransactionsByScriptHash {
+                    txn_lists_with_proof,
+                }
+            }
+            GetTransactionsByReceiverResponse(response) => {
+                let txn_lists_with_proof = response
+                    .txn_lists_with_proof
+                    .into_iter()
+                    .map(TryFrom::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+
+                ResponseItem::GetTransactionsByReceiver {
+                    txn_lists_with_proof,
+                }
+            }
         };
 
         Ok(response)
@@ -730,6 +991,31 @@ impl From<ResponseItem> for crate::proto::types::ResponseItem {
             } => ResponseItems::GetTransactionsResponse(GetTransactionsResponse {
                 txn_list_with_proof: Some(txn_list_with_proof.into()),
             }),
+            ResponseItem::GetAccountStatesRange {
+                account_state_range_with_proof,
+            } => ResponseItems::GetAccountStatesRangeResponse(GetAccountStatesRangeResponse {
+                account_state_range_with_proof: Some(account_state_range_with_proof.into()),
+            }),
+            ResponseItem::GetTransactionsByScriptHash {
+                txn_lists_with_proof,
+            } => ResponseItems::GetTransactionsByScriptHashResponse(
+                GetTransactionsByScriptHashResponse {
+                    txn_lists_with_proof: txn_lists_with_proof
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                },
+            ),
+            ResponseItem::GetTransactionsByReceiver {
+                txn_lists_with_proof,
+            } => ResponseItems::GetTransactionsByReceiverResponse(
+                GetTransactionsByReceiverResponse {
+                    txn_lists_with_proof: txn_lists_with_proof
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                },
+            ),
         };
 
         Self {
@@ -4,6 +4,7 @@
 use crate::block_info::{BlockInfo, Round};
 use crate::{
     account_address::AccountAddress,
+    aggregate_signature::AggregateSignature,
     transaction::Version,
     validator_set::ValidatorSet,
     validator_verifier::{ValidatorVerifier, VerifyError},
@@ -185,6 +186,8 @@ pub struct LedgerInfoWithSignatures<Sig> {
     /// The validator is identified by its account address: in order to verify a signature
     /// one needs to retrieve the public key of the validator for the given epoch.
     signatures: BTreeMap<AccountAddress, Sig>,
+    /// The BLS12-381 signatures of the validators aggregated into one, in place of `signatures`.
+    aggregated_signature: Option<AggregateSignature>,
 }
 
 impl<Sig> Display for LedgerInfoWithSignatures<Sig> {
@@ -198,6 +201,19 @@ impl<Sig: Signature> LedgerInfoWithSignatures<Sig> {
         LedgerInfoWithSignatures {
             ledger_info,
             signatures,
+            aggregated_signature: None,
+        }
+    }
+
+    /// Creates a ledger info signed by the validators with a single aggregate signature.
+    pub fn new_with_aggregated_signature(
+        ledger_info: LedgerInfo,
+        aggregated_signature: AggregateSignature,
+    ) -> Self {
+        LedgerInfoWithSignatures {
+            ledger_info,
+            signatures: BTreeMap::new(),
+            aggregated_signature: Some(aggregated_signature),
         }
     }
 
@@ -217,12 +233,40 @@ impl<Sig: Signature> LedgerInfoWithSignatures<Sig> {
         &self.signatures
     }
 
+    pub fn aggregated_signature(&self) -> Option<&AggregateSignature> {
+        self.aggregated_signature.as_ref()
+    }
+
+    /// Returns false for a ledger info without any signature, like the one of genesis.
+    pub fn is_signed(&self) -> bool {
+        !self.signatures.is_empty() || self.aggregated_signature.is_some()
+    }
+
+    /// Returns the validators who signed, out of the ones of `validator`.
+    pub fn signers(
+        &self,
+        validator: &ValidatorVerifier<Sig::VerifyingKeyMaterial>,
+    ) -> Vec<AccountAddress> {
+        match &self.aggregated_signature {
+            Some(aggregated_signature) => aggregated_signature
+                .signers(&validator.get_ordered_account_addresses())
+                .map_or_else(Vec::new, |signers| signers.into_iter().copied().collect()),
+            None => self.signatures.keys().copied().collect(),
+        }
+    }
+
     pub fn verify(
         &self,
         validator: &ValidatorVerifier<Sig::VerifyingKeyMaterial>,
     ) -> ::std::result::Result<(), VerifyError> {
         let ledger_hash = self.ledger_info().hash();
-        validator.batch_verify_aggregated_signature(ledger_hash, self.signatures())
+        match &self.aggregated_signature {
+            Some(_) if !self.signatures.is_empty() => Err(VerifyError::InvalidSigners),
+            Some(aggregated_signature) => {
+                validator.verify_aggregate_signature(ledger_hash, aggregated_signature)
+            }
+            None => validator.batch_verify_aggregated_signature(ledger_hash, self.signatures()),
+        }
     }
 }
 
@@ -252,10 +296,15 @@ impl<Sig: Signature> TryFrom<crate::proto::types::LedgerInfoWithSignatures>
             signatures.len() == num_signatures,
             "Signatures should be from different validators."
         );
+        let aggregated_signature = proto
+            .aggregated_signature
+            .map(AggregateSignature::try_from)
+            .transpose()?;
 
         Ok(LedgerInfoWithSignatures {
             ledger_info,
             signatures,
+            aggregated_signature,
         })
     }
 }
@@ -279,6 +328,7 @@ impl<Sig: Signature> From<LedgerInfoWithSignatures<Sig>>
         Self {
             signatures,
             ledger_info,
+            aggregated_signature: ledger_info_with_sigs.aggregated_signature.map(Into::into),
         }
     }
 }
@@ -286,10 +336,13 @@ impl<Sig: Signature> From<LedgerInfoWithSignatures<Sig>>
 #[cfg(test)]
 mod tests {
     use crate::block_info::BlockInfo;
+    use crate::crypto_proxies::random_bls_validator_verifier;
     use crate::ledger_info::{LedgerInfo, LedgerInfoWithSignatures};
     use crate::validator_signer::ValidatorSigner;
-    use libra_crypto::{ed25519::*, HashValue};
+    use crate::validator_verifier::VerifyError;
+    use libra_crypto::{ed25519::*, hash::CryptoHash, HashValue};
     use std::collections::BTreeMap;
+    use std::convert::TryFrom;
 
     #[test]
     fn test_signatures_hash() {
@@ -335,4 +388,53 @@ mod tests {
             ledger_info_with_signatures_reversed_bytes
         );
     }
+
+    #[test]
+    fn test_aggregated_signature() {
+        let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+        let (signers, validator_verifier) = random_bls_validator_verifier(4, None, false);
+        let bls_signatures = signers[1..]
+            .iter()
+            .map(|signer| (signer.author(), signer.bls_sign_message(ledger_info.hash())))
+            .collect();
+        let aggregated_signature = validator_verifier
+            .aggregate_signatures(&bls_signatures)
+            .unwrap();
+        let ledger_info_with_signatures =
+            LedgerInfoWithSignatures::<Ed25519Signature>::new_with_aggregated_signature(
+                ledger_info.clone(),
+                aggregated_signature,
+            );
+        assert!(ledger_info_with_signatures.is_signed());
+        assert_eq!(
+            ledger_info_with_signatures.verify(&validator_verifier),
+            Ok(())
+        );
+        let mut expected_signers: Vec<_> =
+            signers[1..].iter().map(|signer| signer.author()).collect();
+        expected_signers.sort();
+        assert_eq!(
+            ledger_info_with_signatures.signers(&validator_verifier),
+            expected_signers
+        );
+
+        let proto = crate::proto::types::LedgerInfoWithSignatures::from(
+            ledger_info_with_signatures.clone(),
+        );
+        assert_eq!(
+            LedgerInfoWithSignatures::try_from(proto).unwrap(),
+            ledger_info_with_signatures
+        );
+
+        // Individual signatures can't be slipped in next to the aggregate one.
+        let mut ledger_info_with_signatures = ledger_info_with_signatures;
+        ledger_info_with_signatures.add_signature(
+            signers[0].author(),
+            signers[0].sign_message(ledger_info.hash()).unwrap(),
+        );
+        assert_eq!(
+            ledger_info_with_signatures.verify(&validator_verifier),
+            Err(VerifyError::InvalidSigners)
+        );
+    }
 }
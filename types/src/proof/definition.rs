@@ -461,6 +461,16 @@ where
         Self::new(vec![], vec![])
     }
 
+    /// Returns the left siblings.
+    pub fn left_siblings(&self) -> &[HashValue] {
+        &self.left_siblings
+    }
+
+    /// Returns the right siblings.
+    pub fn right_siblings(&self) -> &[HashValue] {
+        &self.right_siblings
+    }
+
     /// Verifies the proof is correct. The verifier needs to have `expected_root_hash`, the index
     /// of the first leaf and all of the leaves in possession.
     pub fn verify(
@@ -629,7 +639,7 @@ pub type TestAccumulatorRangeProof = AccumulatorRangeProof<TestOnlyHasher>;
 ///
 /// if the proof wants show that `[a, b, c, d, e]` exists in the tree, it would need the siblings
 /// `X` and `h` on the right.
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SparseMerkleRangeProof {
     /// The vector of siblings. The ones near the bottom are at the beginning of the vector. In the
     /// above example, it's `[X, h]`.
@@ -648,6 +658,25 @@ impl SparseMerkleRangeProof {
     }
 }
 
+impl TryFrom<crate::proto::types::SparseMerkleRangeProof> for SparseMerkleRangeProof {
+    type Error = Error;
+
+    fn try_from(proto_proof: crate::proto::types::SparseMerkleRangeProof) -> Result<Self> {
+        Ok(Self::new(from_proto_siblings(
+            proto_proof.right_siblings,
+            *SPARSE_MERKLE_PLACEHOLDER_HASH,
+        )?))
+    }
+}
+
+impl From<SparseMerkleRangeProof> for crate::proto::types::SparseMerkleRangeProof {
+    fn from(proof: SparseMerkleRangeProof) -> Self {
+        Self {
+            right_siblings: into_proto_siblings(proof.siblings, *SPARSE_MERKLE_PLACEHOLDER_HASH),
+        }
+    }
+}
+
 /// The complete proof used to authenticate a `Transaction` object.  This structure consists of an
 /// `AccumulatorProof` from `LedgerInfo` to `TransactionInfo` the verifier needs to verify the
 /// correctness of the `TransactionInfo` object, and the `TransactionInfo` object that is supposed
@@ -864,6 +893,307 @@ impl From<AccountStateProof> for crate::proto::types::AccountStateProof {
     }
 }
 
+/// The complete proof used to authenticate a range of accounts in key order, namely the first
+/// accounts whose keys (hashed addresses) are not less than a cursor. This structure consists of
+/// the `AccumulatorProof` from `LedgerInfo` to `TransactionInfo`, the `TransactionInfo` object, the
+/// `SparseMerkleProof` from state root to the cursor and the `SparseMerkleRangeProof` from state
+/// root to the last account in the range.
+///
+/// The proof of the cursor authenticates everything on the left of the range, which shows no
+/// account between the cursor and the first one in the range is left out, while the range proof
+/// authenticates everything on the right of the last account.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct AccountStateRangeProof {
+    /// The accumulator proof from ledger info root to leaf that authenticates the hash of the
+    /// `TransactionInfo` object.
+    ledger_info_to_transaction_info_proof: TransactionAccumulatorProof,
+
+    /// The `TransactionInfo` object at the leaf of the accumulator.
+    transaction_info: TransactionInfo,
+
+    /// The sparse merkle proof from state root to the cursor, which may or may not exist.
+    transaction_info_to_cursor_proof: SparseMerkleProof,
+
+    /// The sparse merkle range proof from state root to the last account in the range. Empty if
+    /// the range is empty.
+    transaction_info_to_last_account_proof: SparseMerkleRangeProof,
+}
+
+impl AccountStateRangeProof {
+    /// Constructs a new `AccountStateRangeProof` using given
+    /// `ledger_info_to_transaction_info_proof`, `transaction_info`,
+    /// `transaction_info_to_cursor_proof` and `transaction_info_to_last_account_proof`.
+    pub fn new(
+        ledger_info_to_transaction_info_proof: TransactionAccumulatorProof,
+        transaction_info: TransactionInfo,
+        transaction_info_to_cursor_proof: SparseMerkleProof,
+        transaction_info_to_last_account_proof: SparseMerkleRangeProof,
+    ) -> Self {
+        AccountStateRangeProof {
+            ledger_info_to_transaction_info_proof,
+            transaction_info,
+            transaction_info_to_cursor_proof,
+            transaction_info_to_last_account_proof,
+        }
+    }
+
+    /// Returns the `ledger_info_to_transaction_info_proof` object in this proof.
+    pub fn ledger_info_to_transaction_info_proof(&self) -> &TransactionAccumulatorProof {
+        &self.ledger_info_to_transaction_info_proof
+    }
+
+    /// Returns the `transaction_info` object in this proof.
+    pub fn transaction_info(&self) -> &TransactionInfo {
+        &self.transaction_info
+    }
+
+    /// Returns the `transaction_info_to_cursor_proof` object in this proof.
+    pub fn transaction_info_to_cursor_proof(&self) -> &SparseMerkleProof {
+        &self.transaction_info_to_cursor_proof
+    }
+
+    /// Returns the `transaction_info_to_last_account_proof` object in this proof.
+    pub fn transaction_info_to_last_account_proof(&self) -> &SparseMerkleRangeProof {
+        &self.transaction_info_to_last_account_proof
+    }
+
+    /// Verifies that `accounts` are the first accounts, in ascending order of key, whose keys are
+    /// not less than `cursor` in the state at version `state_version`. If fewer than `limit`
+    /// accounts are given, also verifies that there are no more such accounts.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        state_version: Version,
+        cursor: HashValue,
+        limit: u64,
+        accounts: &[(HashValue, AccountStateBlob)],
+    ) -> Result<()> {
+        ensure!(
+            accounts.len() as u64 <= limit,
+            "More accounts ({}) than the limit ({}).",
+            accounts.len(),
+            limit,
+        );
+        ensure!(
+            accounts.first().map_or(true, |(key, _)| *key >= cursor),
+            "Accounts before the cursor."
+        );
+        ensure!(
+            accounts.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Accounts are not in strictly ascending order of key."
+        );
+
+        let state_root_hash = self.transaction_info.state_root_hash();
+        let cursor_proof = &self.transaction_info_to_cursor_proof;
+        let first_blob = accounts
+            .first()
+            .filter(|(key, _)| *key == cursor)
+            .map(|(_, blob)| blob);
+        cursor_proof.verify(state_root_hash, cursor, first_blob)?;
+
+        let leaves: Vec<(HashValue, HashValue)> = accounts
+            .iter()
+            .map(|(key, blob)| (*key, SparseMerkleLeafNode::new(*key, blob.hash()).hash()))
+            .collect();
+        let is_complete = (accounts.len() as u64) < limit;
+        let mut num_verified = 0;
+        // The leaf at the bottom of the path to the cursor is the first account if it's not before
+        // the cursor.
+        if let Some((key, value_hash)) = cursor_proof.leaf() {
+            if key >= cursor && (!accounts.is_empty() || is_complete) {
+                ensure!(
+                    accounts.first().map(|(key, blob)| (*key, blob.hash()))
+                        == Some((key, value_hash)),
+                    "Account {:x} is left out after the cursor.",
+                    key,
+                );
+                num_verified = 1;
+            }
+        }
+
+        // All the other accounts after the cursor are in the subtrees on the right of the path to
+        // the cursor. From the bottom up, the range must fully cover each of them until it ends.
+        let num_siblings = cursor_proof.siblings().len();
+        for (i, sibling) in cursor_proof.siblings().iter().enumerate() {
+            // Siblings are ordered from the bottom level to the root level.
+            let sibling_depth = num_siblings - 1 - i;
+            if get_bit(cursor, sibling_depth) {
+                // The sibling is on the left of the cursor, so no account in it is in the range.
+                continue;
+            }
+            let subtree_depth = sibling_depth + 1;
+            let num_in_subtree = leaves[num_verified..]
+                .iter()
+                .take_while(|(key, _)| key.common_prefix_bits_len(cursor) == sibling_depth)
+                .count();
+            let subtree_leaves = &leaves[num_verified..num_verified + num_in_subtree];
+            num_verified += num_in_subtree;
+
+            let subtree_hash = if num_verified < leaves.len() || is_complete {
+                sparse_merkle_subtree_hash(subtree_depth, subtree_leaves)
+            } else if num_in_subtree == 0 {
+                // The range ended before this subtree.
+                continue;
+            } else {
+                // The range ends in this subtree, whose part on the right of the last account is
+                // proven by the range proof.
+                let (last_key, _) = leaves.last().expect("Range must not be empty.");
+                let right_siblings = self.transaction_info_to_last_account_proof.siblings();
+                let num_right_siblings_above = last_key
+                    .iter_bits()
+                    .take(subtree_depth)
+                    .filter(|bit| !*bit)
+                    .count();
+                ensure!(
+                    right_siblings.len() >= num_right_siblings_above,
+                    "Too few siblings in the range proof.",
+                );
+                let mut right_siblings =
+                    right_siblings[..right_siblings.len() - num_right_siblings_above].to_vec();
+                let subtree_hash = sparse_merkle_partial_subtree_hash(
+                    subtree_depth,
+                    subtree_leaves,
+                    &mut right_siblings,
+                )?;
+                ensure!(
+                    right_siblings.is_empty(),
+                    "Too many siblings in the range proof."
+                );
+                subtree_hash
+            };
+            ensure!(
+                subtree_hash == *sibling,
+                "Accounts in subtree at depth {} on the right of the cursor hash to {:x}, \
+                 expected {:x}.",
+                subtree_depth,
+                subtree_hash,
+                sibling,
+            );
+        }
+        ensure!(
+            num_verified == leaves.len(),
+            "Accounts not in the state tree.",
+        );
+
+        verify_transaction_info(
+            ledger_info,
+            state_version,
+            &self.transaction_info,
+            &self.ledger_info_to_transaction_info_proof,
+        )?;
+        Ok(())
+    }
+}
+
+impl TryFrom<crate::proto::types::AccountStateRangeProof> for AccountStateRangeProof {
+    type Error = Error;
+
+    fn try_from(proto_proof: crate::proto::types::AccountStateRangeProof) -> Result<Self> {
+        let ledger_info_to_transaction_info_proof = proto_proof
+            .ledger_info_to_transaction_info_proof
+            .ok_or_else(|| format_err!("Missing ledger_info_to_transaction_info_proof"))?
+            .try_into()?;
+        let transaction_info = proto_proof
+            .transaction_info
+            .ok_or_else(|| format_err!("Missing transaction_info"))?
+            .try_into()?;
+        let transaction_info_to_cursor_proof = proto_proof
+            .transaction_info_to_cursor_proof
+            .ok_or_else(|| format_err!("Missing transaction_info_to_cursor_proof"))?
+            .try_into()?;
+        let transaction_info_to_last_account_proof = proto_proof
+            .transaction_info_to_last_account_proof
+            .ok_or_else(|| format_err!("Missing transaction_info_to_last_account_proof"))?
+            .try_into()?;
+
+        Ok(AccountStateRangeProof::new(
+            ledger_info_to_transaction_info_proof,
+            transaction_info,
+            transaction_info_to_cursor_proof,
+            transaction_info_to_last_account_proof,
+        ))
+    }
+}
+
+impl From<AccountStateRangeProof> for crate::proto::types::AccountStateRangeProof {
+    fn from(proof: AccountStateRangeProof) -> Self {
+        Self {
+            ledger_info_to_transaction_info_proof: Some(
+                proof.ledger_info_to_transaction_info_proof.into(),
+            ),
+            transaction_info: Some(proof.transaction_info.into()),
+            transaction_info_to_cursor_proof: Some(proof.transaction_info_to_cursor_proof.into()),
+            transaction_info_to_last_account_proof: Some(
+                proof.transaction_info_to_last_account_proof.into(),
+            ),
+        }
+    }
+}
+
+/// Returns the `index`-th bit of `key`, counting from the most significant one.
+fn get_bit(key: HashValue, index: usize) -> bool {
+    key[index / 8] & (1 << (7 - index % 8)) != 0
+}
+
+/// Computes the root hash of the subtree at `depth` that holds exactly `leaves`, given as pairs of
+/// key and leaf hash in ascending order of key.
+fn sparse_merkle_subtree_hash(depth: usize, leaves: &[(HashValue, HashValue)]) -> HashValue {
+    match leaves {
+        [] => *SPARSE_MERKLE_PLACEHOLDER_HASH,
+        [(_, leaf_hash)] => *leaf_hash,
+        _ => {
+            let num_left = leaves
+                .iter()
+                .take_while(|(key, _)| !get_bit(*key, depth))
+                .count();
+            SparseMerkleInternalNode::new(
+                sparse_merkle_subtree_hash(depth + 1, &leaves[..num_left]),
+                sparse_merkle_subtree_hash(depth + 1, &leaves[num_left..]),
+            )
+            .hash()
+        }
+    }
+}
+
+/// Computes the root hash of the subtree at `depth` that holds `leaves` on the left of, and
+/// including, the last one, and whatever `right_siblings` stand for on its right. The siblings
+/// are consumed from the end of `right_siblings`, i.e. from the top down.
+fn sparse_merkle_partial_subtree_hash(
+    depth: usize,
+    leaves: &[(HashValue, HashValue)],
+    right_siblings: &mut Vec<HashValue>,
+) -> Result<HashValue> {
+    let (last_key, last_leaf_hash) = leaves.last().expect("Leaves must not be empty.");
+    if leaves.len() == 1 && right_siblings.is_empty() {
+        return Ok(*last_leaf_hash);
+    }
+    ensure!(
+        depth < HashValue::LENGTH_IN_BITS,
+        "Too many siblings in the range proof."
+    );
+
+    let (left_hash, right_hash) = if get_bit(*last_key, depth) {
+        let num_left = leaves
+            .iter()
+            .take_while(|(key, _)| !get_bit(*key, depth))
+            .count();
+        (
+            sparse_merkle_subtree_hash(depth + 1, &leaves[..num_left]),
+            sparse_merkle_partial_subtree_hash(depth + 1, &leaves[num_left..], right_siblings)?,
+        )
+    } else {
+        let right_sibling = right_siblings
+            .pop()
+            .ok_or_else(|| format_err!("Too few siblings in the range proof."))?;
+        (
+            sparse_merkle_partial_subtree_hash(depth + 1, leaves, right_siblings)?,
+            right_sibling,
+        )
+    };
+    Ok(SparseMerkleInternalNode::new(left_hash, right_hash).hash())
+}
+
 /// The complete proof used to authenticate a contract event. This structure consists of the
 /// `AccumulatorProof` from `LedgerInfo` to `TransactionInfo`, the `TransactionInfo` object and the
 /// `AccumulatorProof` from event accumulator root to the event.
@@ -1003,6 +1333,11 @@ impl TransactionListProof {
         Self::new(AccumulatorRangeProof::new_empty(), vec![])
     }
 
+    /// Returns the proof from the ledger info root to the list of `TransactionInfo` objects.
+    pub fn ledger_info_to_transaction_infos_proof(&self) -> &TransactionAccumulatorRangeProof {
+        &self.ledger_info_to_transaction_infos_proof
+    }
+
     /// Returns the list of `TransactionInfo` objects.
     pub fn transaction_infos(&self) -> &[TransactionInfo] {
         &self.transaction_infos
@@ -27,10 +27,10 @@ use libra_crypto_derive::CryptoHasher;
 use std::marker::PhantomData;
 
 pub use self::definition::{
-    AccountStateProof, AccumulatorConsistencyProof, AccumulatorProof, AccumulatorRangeProof,
-    EventAccumulatorProof, EventProof, SparseMerkleProof, SparseMerkleRangeProof,
-    TransactionAccumulatorProof, TransactionAccumulatorRangeProof, TransactionListProof,
-    TransactionProof,
+    AccountStateProof, AccountStateRangeProof, AccumulatorConsistencyProof, AccumulatorProof,
+    AccumulatorRangeProof, EventAccumulatorProof, EventProof, SparseMerkleProof,
+    SparseMerkleRangeProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+    TransactionListProof, TransactionProof,
 };
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -6,7 +6,7 @@
 
 use crate::proof::{
     definition::MAX_ACCUMULATOR_PROOF_DEPTH, AccumulatorConsistencyProof, AccumulatorProof,
-    AccumulatorRangeProof, SparseMerkleProof,
+    AccumulatorRangeProof, SparseMerkleProof, SparseMerkleRangeProof,
 };
 use libra_crypto::{
     hash::{CryptoHasher, ACCUMULATOR_PLACEHOLDER_HASH, SPARSE_MERKLE_PLACEHOLDER_HASH},
@@ -132,3 +132,14 @@ where
             .boxed()
     }
 }
+
+impl Arbitrary for SparseMerkleRangeProof {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        vec(arb_sparse_merkle_sibling(), 0..=256)
+            .prop_map(SparseMerkleRangeProof::new)
+            .boxed()
+    }
+}
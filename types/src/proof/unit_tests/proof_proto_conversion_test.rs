@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::proof::{
-    AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-    TestAccumulatorProof, TestAccumulatorRangeProof, TransactionListProof, TransactionProof,
+    AccountStateProof, AccountStateRangeProof, AccumulatorConsistencyProof, EventProof,
+    SparseMerkleProof, SparseMerkleRangeProof, TestAccumulatorProof, TestAccumulatorRangeProof,
+    TransactionListProof, TransactionProof,
 };
 use libra_prost_ext::test_helpers::assert_protobuf_encode_decode;
 use proptest::prelude::*;
@@ -19,6 +20,13 @@ proptest! {
         assert_protobuf_encode_decode::<crate::proto::types::SparseMerkleProof, SparseMerkleProof>(&proof);
     }
 
+    #[test]
+    fn test_sparse_merkle_range_protobuf_conversion_roundtrip(
+        proof in any::<SparseMerkleRangeProof>(),
+    ) {
+        assert_protobuf_encode_decode::<crate::proto::types::SparseMerkleRangeProof, SparseMerkleRangeProof>(&proof);
+    }
+
     #[test]
     fn test_accumulator_consistency_protobuf_conversion_roundtrip(
         proof in any::<AccumulatorConsistencyProof>(),
@@ -43,6 +51,13 @@ proptest! {
         assert_protobuf_encode_decode::<crate::proto::types::AccountStateProof, AccountStateProof>(&proof);
     }
 
+    #[test]
+    fn test_account_state_range_proof_protobuf_conversion_roundtrip(
+        proof in any::<AccountStateRangeProof>(),
+    ) {
+        assert_protobuf_encode_decode::<crate::proto::types::AccountStateRangeProof, AccountStateRangeProof>(&proof);
+    }
+
     #[test]
     fn test_event_proof_protobuf_conversion_roundtrip(proof in any::<EventProof>()) {
         assert_protobuf_encode_decode::<crate::proto::types::EventProof, EventProof>(&proof);
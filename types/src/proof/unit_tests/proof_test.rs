@@ -7,10 +7,11 @@ use crate::{
     account_state_blob::AccountStateBlob,
     ledger_info::LedgerInfo,
     proof::{
-        definition::MAX_ACCUMULATOR_PROOF_DEPTH, AccountStateProof, EventAccumulatorInternalNode,
-        EventAccumulatorProof, EventProof, SparseMerkleInternalNode, SparseMerkleLeafNode,
-        SparseMerkleProof, TestAccumulatorInternalNode, TestAccumulatorProof,
-        TransactionAccumulatorInternalNode, TransactionAccumulatorProof, TransactionProof,
+        definition::MAX_ACCUMULATOR_PROOF_DEPTH, AccountStateProof, AccountStateRangeProof,
+        EventAccumulatorInternalNode, EventAccumulatorProof, EventProof, SparseMerkleInternalNode,
+        SparseMerkleLeafNode, SparseMerkleProof, SparseMerkleRangeProof,
+        TestAccumulatorInternalNode, TestAccumulatorProof, TransactionAccumulatorInternalNode,
+        TransactionAccumulatorProof, TransactionProof,
     },
     transaction::{RawTransaction, Script, Transaction, TransactionInfo},
     vm_error::StatusCode,
@@ -447,3 +448,146 @@ fn test_verify_account_state_and_event() {
         )
         .is_err());
 }
+
+#[test]
+fn test_verify_account_state_range() {
+    //                  root
+    //                 /     \
+    //               /         \
+    //       txn_info0      default
+    //           |
+    //       state_root
+    //         /    \
+    //        c      default
+    //       / \
+    //   key1   d
+    //         / \
+    //     key2   key3
+    let key1 = b"hello".test_only_hash();
+    let key2 = b"world".test_only_hash();
+    let key3 = b"!".test_only_hash();
+    let non_existing_key = b"#".test_only_hash();
+    assert_eq!(key1[0], 0b0011_0011);
+    assert_eq!(key2[0], 0b0100_0010);
+    assert_eq!(key3[0], 0b0110_1001);
+    assert_eq!(non_existing_key[0], 0b0100_0001);
+
+    let blob1 = AccountStateBlob::from(b"value1".to_vec());
+    let blob2 = AccountStateBlob::from(b"value2".to_vec());
+    let blob3 = AccountStateBlob::from(b"value3".to_vec());
+    let account1 = (key1, blob1.clone());
+    let account2 = (key2, blob2.clone());
+    let account3 = (key3, blob3.clone());
+
+    let leaf1_hash = SparseMerkleLeafNode::new(key1, blob1.hash()).hash();
+    let leaf2_hash = SparseMerkleLeafNode::new(key2, blob2.hash()).hash();
+    let leaf3_hash = SparseMerkleLeafNode::new(key3, blob3.hash()).hash();
+    let internal_d_hash = SparseMerkleInternalNode::new(leaf2_hash, leaf3_hash).hash();
+    let internal_c_hash = SparseMerkleInternalNode::new(leaf1_hash, internal_d_hash).hash();
+    let state_root_hash =
+        SparseMerkleInternalNode::new(internal_c_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH).hash();
+
+    let txn_info0 = TransactionInfo::new(
+        b"txn0".test_only_hash(),
+        state_root_hash,
+        *ACCUMULATOR_PLACEHOLDER_HASH,
+        /* gas_used = */ 0,
+        /* major_status = */ StatusCode::EXECUTED,
+    );
+    let root_hash =
+        TransactionAccumulatorInternalNode::new(txn_info0.hash(), *ACCUMULATOR_PLACEHOLDER_HASH)
+            .hash();
+    let consensus_data_hash = b"consensus_data".test_only_hash();
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(0, 0, *GENESIS_BLOCK_ID, root_hash, 1, 10000, None),
+        consensus_data_hash,
+    );
+    let ledger_info_to_transaction_info_proof =
+        TransactionAccumulatorProof::new(vec![*ACCUMULATOR_PLACEHOLDER_HASH]);
+
+    let zero_key_proof = SparseMerkleProof::new(
+        Some((key1, blob1.hash())),
+        vec![internal_d_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+    );
+    let non_existing_key_proof = SparseMerkleProof::new(
+        Some((key2, blob2.hash())),
+        vec![leaf3_hash, leaf1_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+    );
+    let range_proof = |cursor_proof: &SparseMerkleProof, right_siblings: Vec<HashValue>| {
+        AccountStateRangeProof::new(
+            ledger_info_to_transaction_info_proof.clone(),
+            txn_info0.clone(),
+            cursor_proof.clone(),
+            SparseMerkleRangeProof::new(right_siblings),
+        )
+    };
+
+    // All accounts from the very beginning.
+    let proof = range_proof(&zero_key_proof, vec![]);
+    let accounts = vec![account1.clone(), account2.clone(), account3.clone()];
+    for limit in 4..10 {
+        assert!(proof
+            .verify(&ledger_info, 0, HashValue::zero(), limit, &accounts)
+            .is_ok());
+    }
+    // Leaving out one account or adding one more is caught.
+    let left_out = vec![account1.clone(), account3.clone()];
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 10, &left_out)
+        .is_err());
+    let extra = vec![
+        account1.clone(),
+        (non_existing_key, blob3.clone()),
+        account2.clone(),
+        account3.clone(),
+    ];
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 10, &extra)
+        .is_err());
+    // Too many accounts for the limit.
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 2, &accounts)
+        .is_err());
+
+    // The first two accounts, with the range proof covering `key3` on the right.
+    let accounts = vec![account1.clone(), account2.clone()];
+    let proof = range_proof(
+        &zero_key_proof,
+        vec![leaf3_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+    );
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 2, &accounts)
+        .is_ok());
+    // The page is full, so it can't claim there are no more accounts.
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 3, &accounts)
+        .is_err());
+    // Wrong range proof.
+    let proof = range_proof(&zero_key_proof, vec![*SPARSE_MERKLE_PLACEHOLDER_HASH]);
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 2, &accounts)
+        .is_err());
+
+    // Starting from a cursor that doesn't exist.
+    let proof = range_proof(&non_existing_key_proof, vec![]);
+    let accounts = vec![account2.clone(), account3.clone()];
+    assert!(proof
+        .verify(&ledger_info, 0, non_existing_key, 3, &accounts)
+        .is_ok());
+    assert!(proof
+        .verify(&ledger_info, 0, non_existing_key, 3, &accounts[1..])
+        .is_err());
+    let accounts = vec![account1, account2, account3];
+    assert!(proof
+        .verify(&ledger_info, 0, non_existing_key, 3, &accounts)
+        .is_err());
+
+    // An empty page.
+    let proof = range_proof(&zero_key_proof, vec![]);
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 0, &[])
+        .is_ok());
+    assert!(proof
+        .verify(&ledger_info, 0, HashValue::zero(), 1, &[])
+        .is_err());
+}
@@ -937,11 +937,20 @@ impl Arbitrary for BlockMetadata {
         (
             any::<HashValue>(),
             any::<u64>(),
+            any::<u64>(),
             signature_strategy,
+            prop::collection::vec(any::<u8>(), 0..4),
             any::<AccountAddress>(),
         )
-            .prop_map(|(id, timestamp, signatures, proposer)| {
-                BlockMetadata::new(id, timestamp, signatures.into_iter().collect(), proposer)
+            .prop_map(|(id, round, timestamp, signatures, signers, proposer)| {
+                BlockMetadata::new(
+                    id,
+                    round,
+                    timestamp,
+                    signatures.into_iter().collect(),
+                    signers,
+                    proposer,
+                )
             })
             .boxed()
     }
@@ -1082,6 +1082,17 @@ impl Transaction {
         }
     }
 
+    /// Returns the script run by this transaction, if it's a user transaction running one.
+    pub fn as_script(&self) -> Option<&Script> {
+        match self {
+            Transaction::UserTransaction(txn) => match txn.payload() {
+                TransactionPayload::Script(script) => Some(script),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn format_for_client(&self, get_transaction_name: impl Fn(&[u8]) -> String) -> String {
         match self {
             Transaction::UserTransaction(user_txn) => {
@@ -1,7 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::transaction::transaction_argument::TransactionArgument;
+use crate::{
+    account_address::AccountAddress, transaction::transaction_argument::TransactionArgument,
+};
+use libra_crypto::HashValue;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -27,6 +30,23 @@ impl Script {
         &self.args
     }
 
+    /// Returns the hash of the code, which identifies the script, e.g. in the VM whitelist.
+    pub fn code_hash(&self) -> HashValue {
+        HashValue::from_sha3_256(&self.code)
+    }
+
+    /// Returns the addresses passed to the script as arguments, e.g. the payee of a peer-to-peer
+    /// payment.
+    pub fn address_args(&self) -> Vec<AccountAddress> {
+        self.args
+            .iter()
+            .filter_map(|arg| match arg {
+                TransactionArgument::Address(address) => Some(*address),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn into_inner(self) -> (Vec<u8>, Vec<TransactionArgument>) {
         (self.code, self.args)
     }
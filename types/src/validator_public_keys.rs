@@ -7,7 +7,12 @@ use failure::Result;
 use libra_crypto::ed25519::compat::generate_keypair as generate_ed25519_keypair;
 #[cfg(any(test, feature = "fuzzing"))]
 use libra_crypto::x25519::compat::generate_keypair as generate_x25519_keypair;
-use libra_crypto::{ed25519::*, traits::ValidKey, x25519::X25519StaticPublicKey};
+use libra_crypto::{
+    bls12381::{BLS12381PublicKey, BLS12381Signature},
+    ed25519::*,
+    traits::ValidKey,
+    x25519::X25519StaticPublicKey,
+};
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
@@ -32,6 +37,11 @@ pub struct ValidatorPublicKeys {
     // This key establishes the corresponding PrivateKey holder's eligibility to join the p2p
     // network
     network_identity_public_key: X25519StaticPublicKey,
+    // This BLS12-381 key can validate the signatures aggregated into consensus certificates. Empty
+    // unless the validator registered one
+    consensus_bls_public_key: Vec<u8>,
+    // The proof of possession of the private key of consensus_bls_public_key
+    consensus_bls_proof_of_possession: Vec<u8>,
 }
 
 impl fmt::Display for ValidatorPublicKeys {
@@ -54,9 +64,23 @@ impl ValidatorPublicKeys {
             consensus_voting_power,
             network_signing_public_key,
             network_identity_public_key,
+            consensus_bls_public_key: vec![],
+            consensus_bls_proof_of_possession: vec![],
         }
     }
 
+    /// Sets the BLS12-381 key of this validator, along with the proof of possession of its private
+    /// key.
+    pub fn with_consensus_bls_public_key(
+        mut self,
+        public_key: &BLS12381PublicKey,
+        proof_of_possession: &BLS12381Signature,
+    ) -> Self {
+        self.consensus_bls_public_key = public_key.to_bytes().to_vec();
+        self.consensus_bls_proof_of_possession = proof_of_possession.to_bytes().to_vec();
+        self
+    }
+
     #[cfg(any(test, feature = "fuzzing"))]
     pub fn new_with_random_network_keys(
         account_address: AccountAddress,
@@ -71,6 +95,8 @@ impl ValidatorPublicKeys {
             consensus_voting_power,
             network_signing_public_key,
             network_identity_public_key,
+            consensus_bls_public_key: vec![],
+            consensus_bls_proof_of_possession: vec![],
         }
     }
 
@@ -99,6 +125,16 @@ impl ValidatorPublicKeys {
     pub fn network_identity_public_key(&self) -> &X25519StaticPublicKey {
         &self.network_identity_public_key
     }
+
+    /// Returns the BLS12-381 key for validating aggregate signatures from this validator, along
+    /// with the proof of possession of its private key, if the validator registered well-formed
+    /// ones. The proof isn't checked here.
+    pub fn consensus_bls_public_key(&self) -> Option<(BLS12381PublicKey, BLS12381Signature)> {
+        let public_key = BLS12381PublicKey::try_from(&self.consensus_bls_public_key[..]).ok()?;
+        let proof_of_possession =
+            BLS12381Signature::try_from(&self.consensus_bls_proof_of_possession[..]).ok()?;
+        Some((public_key, proof_of_possession))
+    }
 }
 
 impl TryFrom<crate::proto::types::ValidatorPublicKeys> for ValidatorPublicKeys {
@@ -112,13 +148,15 @@ impl TryFrom<crate::proto::types::ValidatorPublicKeys> for ValidatorPublicKeys {
             Ed25519PublicKey::try_from(&proto.network_signing_public_key[..])?;
         let network_identity_public_key =
             X25519StaticPublicKey::try_from(&proto.network_identity_public_key[..])?;
-        Ok(Self::new(
+        Ok(Self {
             account_address,
             consensus_public_key,
             consensus_voting_power,
             network_signing_public_key,
             network_identity_public_key,
-        ))
+            consensus_bls_public_key: proto.consensus_bls_public_key,
+            consensus_bls_proof_of_possession: proto.consensus_bls_proof_of_possession,
+        })
     }
 }
 
@@ -130,6 +168,8 @@ impl From<ValidatorPublicKeys> for crate::proto::types::ValidatorPublicKeys {
             consensus_voting_power: keys.consensus_voting_power,
             network_signing_public_key: keys.network_signing_public_key.to_bytes().to_vec(),
             network_identity_public_key: keys.network_identity_public_key.to_bytes().to_vec(),
+            consensus_bls_public_key: keys.consensus_bls_public_key,
+            consensus_bls_proof_of_possession: keys.consensus_bls_proof_of_possession,
         }
     }
 }
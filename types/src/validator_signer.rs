@@ -3,7 +3,12 @@
 
 use crate::account_address::{AccountAddress, ADDRESS_LENGTH};
 use failure::Error;
-use libra_crypto::{test_utils::TEST_SEED, HashValue, *};
+use libra_crypto::{
+    bls12381::{BLS12381PrivateKey, BLS12381PublicKey, BLS12381Signature},
+    ed25519::Ed25519PrivateKey,
+    test_utils::TEST_SEED,
+    HashValue, *,
+};
 use rand::{rngs::StdRng, SeedableRng};
 use std::convert::TryFrom;
 
@@ -49,6 +54,38 @@ impl<PrivateKey: SigningKey> ValidatorSigner<PrivateKey> {
     pub fn public_key(&self) -> PrivateKey::VerifyingKeyMaterial {
         self.public_key.clone()
     }
+
+    /// Returns the private key associated with this signer. Only meant for tests that need to
+    /// hand the key over to something else signing on behalf of the same author.
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.private_key
+    }
+}
+
+/// Derives the BLS12-381 private key of a validator from its Ed25519 consensus private key, so that
+/// no other secret needs to be kept to sign the messages aggregated into consensus certificates.
+pub fn derive_bls_private_key(consensus_private_key: &Ed25519PrivateKey) -> BLS12381PrivateKey {
+    BLS12381PrivateKey::derive(&consensus_private_key.to_bytes())
+        .expect("Consensus private keys are long enough to derive from.")
+}
+
+impl ValidatorSigner<Ed25519PrivateKey> {
+    /// Constructs a BLS12-381 signature for `message` using the key derived from `private_key`,
+    /// see [`derive_bls_private_key`].
+    pub fn bls_sign_message(&self, message: HashValue) -> BLS12381Signature {
+        derive_bls_private_key(&self.private_key).sign_message(&message)
+    }
+
+    /// Returns the BLS12-381 public key derived from `private_key`.
+    pub fn bls_public_key(&self) -> BLS12381PublicKey {
+        (&derive_bls_private_key(&self.private_key)).into()
+    }
+
+    /// Returns the proof of possession of the BLS12-381 private key derived from `private_key`.
+    pub fn bls_proof_of_possession(&self) -> BLS12381Signature {
+        derive_bls_private_key(&self.private_key).create_proof_of_possession()
+    }
 }
 
 impl<PrivateKey: SigningKey + Genesis> ValidatorSigner<PrivateKey> {
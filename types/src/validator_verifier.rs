@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::account_address::AccountAddress;
+use crate::aggregate_signature::AggregateSignature;
 use crate::validator_set::ValidatorSet;
 use failure::prelude::*;
+use libra_crypto::bls12381::{
+    aggregate_public_keys, aggregate_signatures, BLS12381PublicKey, BLS12381Signature,
+};
 use libra_crypto::ed25519::Ed25519PublicKey;
 use libra_crypto::*;
 use std::collections::BTreeMap;
@@ -34,6 +38,9 @@ pub enum VerifyError {
     #[fail(display = "Signature is invalid")]
     /// The signature does not match the hash.
     InvalidSignature,
+    #[fail(display = "Signers of the aggregate signature don't match the validators")]
+    /// The bitmap of signers of an aggregate signature is malformed.
+    InvalidSigners,
 }
 
 /// Helper struct to manage validator information for validation
@@ -41,6 +48,9 @@ pub enum VerifyError {
 pub struct ValidatorInfo<PublicKey> {
     public_key: PublicKey,
     voting_power: u64,
+    /// The key validating the signatures of the validator aggregated into one, only set once the
+    /// validator proved possession of its private key.
+    bls_public_key: Option<BLS12381PublicKey>,
 }
 
 impl<PublicKey: VerifyingKey> ValidatorInfo<PublicKey> {
@@ -48,13 +58,31 @@ impl<PublicKey: VerifyingKey> ValidatorInfo<PublicKey> {
         ValidatorInfo {
             public_key,
             voting_power,
+            bls_public_key: None,
         }
     }
 
+    /// Sets the BLS12-381 key of the validator, if `proof_of_possession` proves the possession of
+    /// its private key. Without this check, the validator could choose a key forging aggregate
+    /// signatures on behalf of others.
+    pub fn with_bls_public_key(
+        mut self,
+        bls_public_key: BLS12381PublicKey,
+        proof_of_possession: &BLS12381Signature,
+    ) -> Result<Self> {
+        bls_public_key.verify_proof_of_possession(proof_of_possession)?;
+        self.bls_public_key = Some(bls_public_key);
+        Ok(self)
+    }
+
     pub fn public_key(&self) -> &PublicKey {
         &self.public_key
     }
 
+    pub fn bls_public_key(&self) -> Option<&BLS12381PublicKey> {
+        self.bls_public_key.as_ref()
+    }
+
     pub fn voting_power(&self) -> u64 {
         self.voting_power
     }
@@ -142,6 +170,22 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         }
     }
 
+    /// Verify the correctness of a BLS12-381 signature of a hash by a known author, which is to be
+    /// aggregated with others.
+    pub fn verify_bls_signature(
+        &self,
+        author: AccountAddress,
+        hash: HashValue,
+        signature: &BLS12381Signature,
+    ) -> std::result::Result<(), VerifyError> {
+        match self.get_bls_public_key(&author) {
+            Some(public_key) => public_key
+                .verify_signature(&hash, signature)
+                .map_err(|_| VerifyError::InvalidSignature),
+            None => Err(VerifyError::UnknownAuthor),
+        }
+    }
+
     /// This function will successfully return when at least quorum_size signatures of known authors
     /// are successfully verified. Also, an aggregated signature is considered invalid if any of the
     /// attached signatures is invalid or it does not correspond to a known author. The latter is to
@@ -240,6 +284,13 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
             .map(|validator_info| validator_info.public_key.clone())
     }
 
+    /// Returns the BLS12-381 public key for this address, if the validator has one.
+    pub fn get_bls_public_key(&self, author: &AccountAddress) -> Option<BLS12381PublicKey> {
+        self.address_to_validator_info
+            .get(&author)
+            .and_then(|validator_info| validator_info.bls_public_key.clone())
+    }
+
     /// Returns the voting power for this address.
     pub fn get_voting_power(&self, author: &AccountAddress) -> Option<u64> {
         self.address_to_validator_info
@@ -274,6 +325,56 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
     pub fn quorum_voting_power(&self) -> u64 {
         self.quorum_voting_power
     }
+
+    /// Aggregates the BLS12-381 signatures of validators over the same message into one. Each
+    /// signer must have a BLS12-381 public key.
+    pub fn aggregate_signatures(
+        &self,
+        signatures: &BTreeMap<AccountAddress, BLS12381Signature>,
+    ) -> std::result::Result<AggregateSignature, VerifyError> {
+        let validators = self.get_ordered_account_addresses();
+        let indices = signatures
+            .keys()
+            .map(|author| match self.get_bls_public_key(author) {
+                Some(_) => validators
+                    .binary_search(author)
+                    .map_err(|_| VerifyError::UnknownAuthor),
+                None => Err(VerifyError::UnknownAuthor),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let signature =
+            aggregate_signatures(signatures.values()).map_err(|_| VerifyError::InvalidSignature)?;
+        Ok(AggregateSignature::new(
+            AggregateSignature::signers_bitmap(validators.len(), indices),
+            signature,
+        ))
+    }
+
+    /// Verifies an aggregate signature over `hash`, checking that its signers have at least
+    /// quorum voting power. A single pairing check covers all the signers, against the aggregate
+    /// of their BLS12-381 public keys, whose proofs of possession were checked when added to the
+    /// validators (see [`ValidatorInfo::with_bls_public_key`]).
+    pub fn verify_aggregate_signature(
+        &self,
+        hash: HashValue,
+        aggregate_signature: &AggregateSignature,
+    ) -> std::result::Result<(), VerifyError> {
+        let validators = self.get_ordered_account_addresses();
+        let signers = aggregate_signature
+            .signers(&validators)
+            .ok_or(VerifyError::InvalidSigners)?;
+        self.check_voting_power(signers.iter().cloned())?;
+        let public_keys = signers
+            .iter()
+            .map(|author| self.get_bls_public_key(author))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(VerifyError::UnknownAuthor)?;
+        let public_key =
+            aggregate_public_keys(&public_keys).map_err(|_| VerifyError::InvalidSignature)?;
+        public_key
+            .verify_signature(&hash, aggregate_signature.signature())
+            .map_err(|_| VerifyError::InvalidSignature)
+    }
 }
 
 impl<PublicKey> fmt::Display for ValidatorVerifier<PublicKey> {
@@ -286,18 +387,25 @@ impl<PublicKey> fmt::Display for ValidatorVerifier<PublicKey> {
     }
 }
 
+/// The BLS12-381 key of a validator is only kept if its proof of possession is valid, so that the
+/// validator can't take part in aggregate signatures otherwise.
 impl From<&ValidatorSet> for ValidatorVerifier<Ed25519PublicKey> {
     fn from(validator_set: &ValidatorSet) -> Self {
         ValidatorVerifier::new(validator_set.payload().iter().fold(
             BTreeMap::new(),
             |mut map, key| {
-                map.insert(
-                    key.account_address().clone(),
-                    ValidatorInfo::new(
-                        key.consensus_public_key().clone(),
-                        key.consensus_voting_power(),
-                    ),
+                let info = ValidatorInfo::new(
+                    key.consensus_public_key().clone(),
+                    key.consensus_voting_power(),
                 );
+                let info = match key.consensus_bls_public_key() {
+                    Some((bls_public_key, proof_of_possession)) => info
+                        .clone()
+                        .with_bls_public_key(bls_public_key, &proof_of_possession)
+                        .unwrap_or(info),
+                    None => info,
+                };
+                map.insert(key.account_address().clone(), info);
                 map
             },
         ))
@@ -326,9 +434,12 @@ impl From<&ValidatorVerifier<Ed25519PublicKey>> for ValidatorSet {
 
 #[cfg(test)]
 mod tests {
-    use crate::crypto_proxies::random_validator_verifier;
+    use crate::crypto_proxies::{random_bls_validator_verifier, random_validator_verifier};
     use crate::validator_verifier::VerifyError::TooLittleVotingPower;
     use crate::{
+        aggregate_signature::AggregateSignature,
+        validator_public_keys::ValidatorPublicKeys,
+        validator_set::ValidatorSet,
         validator_signer::ValidatorSigner,
         validator_verifier::{ValidatorInfo, ValidatorVerifier, VerifyError},
     };
@@ -602,4 +713,133 @@ mod tests {
             Err(VerifyError::UnknownAuthor)
         );
     }
+
+    #[test]
+    fn test_aggregate_signature() {
+        let (validator_signers, validator_verifier) =
+            random_bls_validator_verifier(10, None, false);
+        let random_hash = HashValue::random();
+        let sign = |signers: &[ValidatorSigner<Ed25519PrivateKey>]| {
+            signers
+                .iter()
+                .map(|signer| (signer.author(), signer.bls_sign_message(random_hash)))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        // 7 signers out of 10 make a quorum.
+        let aggregate_signature = validator_verifier
+            .aggregate_signatures(&sign(&validator_signers[..7]))
+            .unwrap();
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &aggregate_signature),
+            Ok(())
+        );
+        assert_eq!(
+            validator_verifier
+                .verify_aggregate_signature(HashValue::random(), &aggregate_signature),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // Claiming another set of signers fails.
+        let indices = (0..8).filter(|i| *i != 3);
+        let wrong_signers = AggregateSignature::new(
+            AggregateSignature::signers_bitmap(10, indices),
+            aggregate_signature.signature().clone(),
+        );
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &wrong_signers),
+            Err(VerifyError::InvalidSignature)
+        );
+        let padding_set = AggregateSignature::new(
+            AggregateSignature::signers_bitmap(16, 0..11),
+            aggregate_signature.signature().clone(),
+        );
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &padding_set),
+            Err(VerifyError::InvalidSigners)
+        );
+        let too_long =
+            AggregateSignature::new(vec![0xff; 3], aggregate_signature.signature().clone());
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &too_long),
+            Err(VerifyError::InvalidSigners)
+        );
+
+        // 6 signers out of 10 don't.
+        let aggregate_signature = validator_verifier
+            .aggregate_signatures(&sign(&validator_signers[..6]))
+            .unwrap();
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &aggregate_signature),
+            Err(VerifyError::TooLittleVotingPower {
+                voting_power: 6,
+                quorum_voting_power: 7
+            })
+        );
+
+        // Unknown signers can't be aggregated.
+        let unknown_signer = ValidatorSigner::<Ed25519PrivateKey>::random([42; 32]);
+        assert_eq!(
+            validator_verifier.aggregate_signatures(&sign(&[unknown_signer])),
+            Err(VerifyError::UnknownAuthor)
+        );
+
+        // Nor can validators without BLS12-381 public keys.
+        let (validator_signers, validator_verifier) = random_validator_verifier(4, None, false);
+        assert_eq!(
+            validator_verifier.aggregate_signatures(&sign(&validator_signers)),
+            Err(VerifyError::UnknownAuthor)
+        );
+    }
+
+    #[test]
+    fn test_bls_public_key_needs_proof_of_possession() {
+        let signer = ValidatorSigner::<Ed25519PrivateKey>::random(TEST_SEED);
+        let other_signer = ValidatorSigner::<Ed25519PrivateKey>::random([1; 32]);
+        let info = ValidatorInfo::new(signer.public_key(), 1);
+        assert!(info
+            .clone()
+            .with_bls_public_key(
+                signer.bls_public_key(),
+                &other_signer.bls_proof_of_possession()
+            )
+            .is_err());
+        assert!(info
+            .with_bls_public_key(signer.bls_public_key(), &signer.bls_proof_of_possession())
+            .is_ok());
+
+        // A BLS12-381 public key of the validator set without a valid proof of possession is
+        // left out.
+        let keys = |proof_signer: &ValidatorSigner<Ed25519PrivateKey>| {
+            ValidatorPublicKeys::new_with_random_network_keys(
+                signer.author(),
+                signer.public_key(),
+                1,
+            )
+            .with_consensus_bls_public_key(
+                &signer.bls_public_key(),
+                &proof_signer.bls_proof_of_possession(),
+            )
+        };
+        let validator_verifier = ValidatorVerifier::from(&ValidatorSet::new(vec![keys(&signer)]));
+        assert_eq!(
+            validator_verifier.get_bls_public_key(&signer.author()),
+            Some(signer.bls_public_key())
+        );
+        let validator_verifier =
+            ValidatorVerifier::from(&ValidatorSet::new(vec![keys(&other_signer)]));
+        assert_eq!(
+            validator_verifier.get_bls_public_key(&signer.author()),
+            None
+        );
+        let random_hash = HashValue::random();
+        assert_eq!(
+            validator_verifier.verify_bls_signature(
+                signer.author(),
+                random_hash,
+                &signer.bls_sign_message(random_hash)
+            ),
+            Err(VerifyError::UnknownAuthor)
+        );
+    }
 }
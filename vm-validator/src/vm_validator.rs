@@ -65,7 +65,10 @@ impl TransactionValidation for VMValidator {
         // Just ask something from storage. It doesn't matter what it is -- we just need the
         // transaction info object in account state proof which contains the state root hash.
         let address = AccountAddress::new([0xff; ADDRESS_LENGTH]);
-        let item = RequestItem::GetAccountState { address };
+        let item = RequestItem::GetAccountState {
+            address,
+            version: None,
+        };
 
         match self
             .storage_read_client
@@ -108,7 +111,10 @@ pub async fn get_account_state(
     storage_read_client: Arc<dyn StorageRead>,
     address: AccountAddress,
 ) -> Result<(u64, u64)> {
-    let req_item = RequestItem::GetAccountState { address };
+    let req_item = RequestItem::GetAccountState {
+        address,
+        version: None,
+    };
     let (response_items, _, _, _) = storage_read_client
         .update_to_latest_ledger_async(0 /* client_known_version */, vec![req_item])
         .await?;